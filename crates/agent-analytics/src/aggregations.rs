@@ -4,8 +4,9 @@
 //! tool usage frequency, conversation metrics, and deep work detection.
 
 use agent_core::session::Session;
-use agent_core::types::Role;
-use chrono::NaiveDate;
+use agent_core::tool_quota::QUOTA_EXCEEDED;
+use agent_core::types::{Rating, Role};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -27,6 +28,18 @@ pub struct DailySummary {
     pub tool_call_count: u32,
     /// Total tool errors (tool results flagged as errors).
     pub tool_error_count: u32,
+    /// Tool calls refused for being over a `[tools.quotas]` limit.
+    #[serde(default)]
+    pub quota_denied_count: u32,
+    /// Approximate token volume of all message content (see [`estimate_tokens`]).
+    #[serde(default)]
+    pub estimated_tokens: u64,
+    /// Assistant messages the user rated thumbs up.
+    #[serde(default)]
+    pub thumbs_up_count: u32,
+    /// Assistant messages the user rated thumbs down.
+    #[serde(default)]
+    pub thumbs_down_count: u32,
     /// Top tools used, sorted by frequency.
     pub top_tools: Vec<(String, u32)>,
     /// Tags seen across sessions.
@@ -44,6 +57,10 @@ impl DailySummary {
             assistant_message_count: 0,
             tool_call_count: 0,
             tool_error_count: 0,
+            quota_denied_count: 0,
+            estimated_tokens: 0,
+            thumbs_up_count: 0,
+            thumbs_down_count: 0,
             top_tools: Vec::new(),
             tags: Vec::new(),
         }
@@ -57,6 +74,11 @@ impl DailySummary {
             self.tool_error_count as f64 / self.tool_call_count as f64
         }
     }
+
+    /// Fraction of rated messages rated thumbs up, if any were rated.
+    pub fn approval_rate(&self) -> Option<f64> {
+        approval_rate(self.thumbs_up_count, self.thumbs_down_count)
+    }
 }
 
 /// Statistics computed for a single session.
@@ -74,6 +96,92 @@ pub struct SessionStats {
     pub tool_error_count: u32,
     /// Distinct tools used.
     pub tools_used: Vec<String>,
+    /// Tool calls refused for being over a quota.
+    #[serde(default)]
+    pub quota_denied_count: u32,
+    /// Calls to a tool whose previous call in the same turn failed.
+    #[serde(default)]
+    pub tool_retry_count: u32,
+    /// The session ended on a turn whose last tool result was an error.
+    #[serde(default)]
+    pub abandoned: bool,
+    /// User messages that got an assistant reply.
+    #[serde(default)]
+    pub response_count: u32,
+    /// Sum over those replies of the seconds from the user message to the
+    /// last assistant message of its turn.
+    #[serde(default)]
+    pub total_response_latency_secs: u64,
+    #[serde(default)]
+    pub thumbs_up_count: u32,
+    #[serde(default)]
+    pub thumbs_down_count: u32,
+}
+
+impl SessionStats {
+    /// Assistant messages (model round trips) per user message.
+    pub fn iterations_per_user_message(&self) -> f64 {
+        if self.user_message_count == 0 {
+            0.0
+        } else {
+            self.assistant_message_count as f64 / self.user_message_count as f64
+        }
+    }
+
+    /// Mean seconds from a user message to the end of the agent's reply.
+    pub fn average_response_latency_secs(&self) -> Option<f64> {
+        (self.response_count > 0)
+            .then(|| self.total_response_latency_secs as f64 / self.response_count as f64)
+    }
+}
+
+/// Conversation quality across the sessions in a date range, for tuning
+/// models and prompts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct QualityMetrics {
+    pub sessions: usize,
+    pub user_messages: u32,
+    /// Assistant messages; each is one model round trip.
+    pub agent_iterations: u32,
+    pub tool_calls: u32,
+    pub tool_retries: u32,
+    /// Tool calls refused for being over a quota.
+    pub quota_denials: u32,
+    pub abandoned_sessions: usize,
+    pub average_response_latency_secs: Option<f64>,
+    /// Assistant messages rated thumbs up.
+    pub thumbs_up: u32,
+    /// Assistant messages rated thumbs down.
+    pub thumbs_down: u32,
+}
+
+impl QualityMetrics {
+    /// Agent iterations per user message.
+    pub fn iterations_per_user_message(&self) -> f64 {
+        if self.user_messages == 0 {
+            0.0
+        } else {
+            self.agent_iterations as f64 / self.user_messages as f64
+        }
+    }
+
+    /// Fraction of tool calls that were retries (0.0..1.0).
+    pub fn retry_rate(&self) -> f64 {
+        if self.tool_calls == 0 {
+            0.0
+        } else {
+            self.tool_retries as f64 / self.tool_calls as f64
+        }
+    }
+
+    /// Fraction of rated messages rated thumbs up, if any were rated.
+    pub fn approval_rate(&self) -> Option<f64> {
+        approval_rate(self.thumbs_up, self.thumbs_down)
+    }
+}
+
+fn approval_rate(up: u32, down: u32) -> Option<f64> {
+    (up + down > 0).then(|| up as f64 / (up + down) as f64)
 }
 
 /// Analytics engine for computing metrics across sessions.
@@ -136,9 +244,18 @@ impl Analytics {
             tool_call_count: 0,
             tool_error_count: 0,
             tools_used: Vec::new(),
+            quota_denied_count: 0,
+            tool_retry_count: 0,
+            abandoned: false,
+            response_count: 0,
+            total_response_latency_secs: 0,
+            thumbs_up_count: 0,
+            thumbs_down_count: 0,
         };
+        record_turns(session, &mut stats);
 
         for msg in &session.messages {
+            summary.estimated_tokens += estimate_tokens(&msg.content);
             match msg.role {
                 Role::User => {
                     summary.user_message_count += 1;
@@ -147,6 +264,17 @@ impl Analytics {
                 Role::Assistant => {
                     summary.assistant_message_count += 1;
                     stats.assistant_message_count += 1;
+                    match msg.feedback.as_ref().map(|f| f.rating) {
+                        Some(Rating::Up) => {
+                            summary.thumbs_up_count += 1;
+                            stats.thumbs_up_count += 1;
+                        }
+                        Some(Rating::Down) => {
+                            summary.thumbs_down_count += 1;
+                            stats.thumbs_down_count += 1;
+                        }
+                        None => {}
+                    }
 
                     // Count tool calls from assistant messages.
                     if let Some(calls) = &msg.tool_calls {
@@ -166,10 +294,14 @@ impl Analytics {
                 Role::Tool => {
                     // Check for tool errors by looking for error indicators.
                     // Tool results that start with "Error" or contain is_error pattern.
-                    if msg.content.starts_with("Error") || msg.content.starts_with("error:") {
+                    if is_tool_error(&msg.content) {
                         summary.tool_error_count += 1;
                         stats.tool_error_count += 1;
                     }
+                    if msg.content.starts_with(QUOTA_EXCEEDED) {
+                        summary.quota_denied_count += 1;
+                        stats.quota_denied_count += 1;
+                    }
                 }
                 Role::System => {}
             }
@@ -199,7 +331,7 @@ impl Analytics {
         if let Some(day_tools) = self.daily_tool_counts.get(&date) {
             let mut sorted: Vec<(String, u32)> =
                 day_tools.iter().map(|(k, v)| (k.clone(), *v)).collect();
-            sorted.sort_by(|a, b| b.1.cmp(&a.1));
+            sorted.sort_by_key(|e| std::cmp::Reverse(e.1));
             sorted.truncate(10);
 
             if let Some(summary) = self.daily_summaries.get_mut(&date) {
@@ -225,6 +357,21 @@ impl Analytics {
         summaries
     }
 
+    /// One summary per day in `start..=end`, with empty days zero-filled so
+    /// the series can be charted directly.
+    pub fn daily_series(&self, start: NaiveDate, end: NaiveDate) -> Vec<DailySummary> {
+        start
+            .iter_days()
+            .take_while(|date| *date <= end)
+            .map(|date| {
+                self.daily_summaries
+                    .get(&date)
+                    .cloned()
+                    .unwrap_or_else(|| DailySummary::new(date))
+            })
+            .collect()
+    }
+
     /// Get top tools across all processed sessions.
     pub fn top_tools(&self, limit: usize) -> Vec<(String, u32)> {
         let mut counts: Vec<_> = self
@@ -232,7 +379,7 @@ impl Analytics {
             .iter()
             .map(|(k, v)| (k.clone(), *v))
             .collect();
-        counts.sort_by(|a, b| b.1.cmp(&a.1));
+        counts.sort_by_key(|e| std::cmp::Reverse(e.1));
         counts.truncate(limit);
         counts
     }
@@ -255,6 +402,14 @@ impl Analytics {
             .collect()
     }
 
+    /// Deep work sessions started within a date range.
+    pub fn deep_work_sessions_between(&self, start: NaiveDate, end: NaiveDate) -> usize {
+        self.deep_work_sessions()
+            .into_iter()
+            .filter(|s| s.date >= start && s.date <= end)
+            .count()
+    }
+
     /// Calculate average session duration across all processed sessions.
     pub fn average_session_duration(&self) -> Option<u64> {
         if self.session_stats.is_empty() {
@@ -277,6 +432,33 @@ impl Analytics {
         }
     }
 
+    /// Conversation quality of the sessions started within a date range.
+    pub fn quality_metrics(&self, start: NaiveDate, end: NaiveDate) -> QualityMetrics {
+        let mut metrics = QualityMetrics::default();
+        let mut responses = 0;
+        let mut latency_secs = 0;
+        for stats in self
+            .session_stats
+            .iter()
+            .filter(|s| s.date >= start && s.date <= end)
+        {
+            metrics.sessions += 1;
+            metrics.user_messages += stats.user_message_count;
+            metrics.agent_iterations += stats.assistant_message_count;
+            metrics.tool_calls += stats.tool_call_count;
+            metrics.tool_retries += stats.tool_retry_count;
+            metrics.quota_denials += stats.quota_denied_count;
+            metrics.abandoned_sessions += stats.abandoned as usize;
+            metrics.thumbs_up += stats.thumbs_up_count;
+            metrics.thumbs_down += stats.thumbs_down_count;
+            responses += stats.response_count;
+            latency_secs += stats.total_response_latency_secs;
+        }
+        metrics.average_response_latency_secs =
+            (responses > 0).then(|| latency_secs as f64 / responses as f64);
+        metrics
+    }
+
     /// Get all processed session stats.
     pub fn session_stats(&self) -> &[SessionStats] {
         &self.session_stats
@@ -317,6 +499,78 @@ fn session_duration(session: &Session) -> u64 {
     }
 }
 
+/// Whether a tool result reports a failure.
+fn is_tool_error(content: &str) -> bool {
+    content.starts_with("Error") || content.starts_with("error:")
+}
+
+/// Fill in the per-turn metrics of `stats`: tool retries, response latency
+/// and whether the session was abandoned. A turn runs from one user message
+/// to the next.
+fn record_turns(session: &Session, stats: &mut SessionStats) {
+    let mut call_names: HashMap<&str, &str> = HashMap::new();
+    // Tools whose latest call this turn failed.
+    let mut failed: Vec<&str> = Vec::new();
+    let mut turn_start = None;
+    let mut reply_end = None;
+    let mut last_result_failed = false;
+
+    fn close_turn(
+        stats: &mut SessionStats,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) {
+        if let (Some(start), Some(end)) = (start, end) {
+            stats.response_count += 1;
+            stats.total_response_latency_secs += (end - start).num_seconds().max(0) as u64;
+        }
+    }
+
+    for msg in &session.messages {
+        match msg.role {
+            Role::User => {
+                close_turn(stats, turn_start, reply_end);
+                turn_start = Some(msg.timestamp);
+                reply_end = None;
+                failed.clear();
+                last_result_failed = false;
+            }
+            Role::Assistant => {
+                reply_end = Some(msg.timestamp);
+                for call in msg.tool_calls.iter().flatten() {
+                    if failed.contains(&call.name.as_str()) {
+                        stats.tool_retry_count += 1;
+                    }
+                    call_names.insert(&call.id, &call.name);
+                }
+            }
+            Role::Tool => {
+                let name = msg
+                    .tool_call_id
+                    .as_deref()
+                    .and_then(|id| call_names.get(id).copied());
+                last_result_failed = is_tool_error(&msg.content);
+                if let Some(name) = name {
+                    failed.retain(|n| *n != name);
+                    if last_result_failed {
+                        failed.push(name);
+                    }
+                }
+            }
+            Role::System => {}
+        }
+    }
+    close_turn(stats, turn_start, reply_end);
+    stats.abandoned = last_result_failed;
+}
+
+/// Rough token count for a piece of text (about four characters per token).
+///
+/// Sessions do not record provider usage, so this is only meant for trends.
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
 /// Format seconds as a human-readable duration string.
 pub fn format_duration(seconds: u64) -> String {
     let hours = seconds / 3600;
@@ -440,6 +694,121 @@ mod tests {
         assert_eq!(top[1], ("file_read".to_string(), 1));
     }
 
+    #[test]
+    fn test_quota_denials_counted() {
+        let mut analytics = Analytics::default();
+        let denial = format!("{}: `web_fetch` may be called 1 times per day", QUOTA_EXCEEDED);
+        analytics.process_session(&make_session(
+            "quota",
+            vec![
+                user_msg("fetch it", 0),
+                assistant_with_tool("web_fetch", 5),
+                tool_result(&denial, 10),
+                assistant_msg("I could not fetch it", 15),
+            ],
+        ));
+        analytics.finalize_all();
+
+        let today = chrono::Utc::now().date_naive();
+        assert_eq!(analytics.get_daily_summary(today).unwrap().quota_denied_count, 1);
+        assert_eq!(analytics.session_stats()[0].quota_denied_count, 1);
+        assert_eq!(analytics.quality_metrics(today, today).quota_denials, 1);
+    }
+
+    #[test]
+    fn test_quality_metrics() {
+        let mut analytics = Analytics::default();
+        let session = make_session(
+            "quality",
+            vec![
+                user_msg("fix the build", 0),
+                assistant_with_tool("shell_exec", 5),
+                tool_result("Error: exit 1", 10),
+                assistant_with_tool("shell_exec", 15),
+                tool_result("ok", 20),
+                assistant_msg("fixed", 30),
+                user_msg("deploy it", 40),
+                assistant_with_tool("shell_exec", 45),
+                tool_result("Error: permission denied", 50),
+            ],
+        );
+        analytics.process_session(&session);
+        analytics.process_session(&make_session(
+            "fine",
+            vec![user_msg("hi", 0), assistant_msg("hello", 3)],
+        ));
+
+        let stats = &analytics.session_stats()[0];
+        // Only the call straight after the failure is a retry.
+        assert_eq!(stats.tool_retry_count, 1);
+        assert!(stats.abandoned);
+        assert_eq!(stats.iterations_per_user_message(), 2.0);
+        let latency = stats.average_response_latency_secs().unwrap();
+        assert!((17.0..=18.0).contains(&latency));
+        assert!(!analytics.session_stats()[1].abandoned);
+
+        let today = chrono::Utc::now().date_naive();
+        let quality = analytics.quality_metrics(today, today);
+        assert_eq!(quality.sessions, 2);
+        assert_eq!(quality.abandoned_sessions, 1);
+        assert_eq!(quality.tool_retries, 1);
+        assert_eq!(quality.iterations_per_user_message(), 5.0 / 3.0);
+        assert!((quality.retry_rate() - 1.0 / 3.0).abs() < 1e-9);
+        let latency = quality.average_response_latency_secs.unwrap();
+        assert!((12.0..=13.0).contains(&latency));
+    }
+
+    #[test]
+    fn test_feedback_rates() {
+        let rated = |content: &str, rating: Rating| {
+            let mut msg = assistant_msg(content, 5);
+            msg.feedback = Some(agent_core::types::Feedback {
+                rating,
+                comment: None,
+                timestamp: chrono::Utc::now(),
+            });
+            msg
+        };
+        let mut analytics = Analytics::default();
+        analytics.process_session(&make_session(
+            "rated",
+            vec![
+                user_msg("a", 0),
+                rated("good", Rating::Up),
+                user_msg("b", 10),
+                rated("also good", Rating::Up),
+                user_msg("c", 20),
+                rated("wrong", Rating::Down),
+                user_msg("d", 30),
+                assistant_msg("unrated", 35),
+            ],
+        ));
+
+        let today = chrono::Utc::now().date_naive();
+        let summary = analytics.get_daily_summary(today).unwrap();
+        assert_eq!((summary.thumbs_up_count, summary.thumbs_down_count), (2, 1));
+        let quality = analytics.quality_metrics(today, today);
+        assert_eq!((quality.thumbs_up, quality.thumbs_down), (2, 1));
+        assert!((quality.approval_rate().unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert!(QualityMetrics::default().approval_rate().is_none());
+    }
+
+    #[test]
+    fn test_daily_series_zero_fills_gaps() {
+        let mut analytics = Analytics::default();
+        analytics.process_session(&make_session(
+            "series",
+            vec![user_msg("abcdefgh", 0), assistant_msg("ok", 5)],
+        ));
+
+        let today = chrono::Utc::now().date_naive();
+        let series = analytics.daily_series(today - Duration::days(2), today);
+        assert_eq!(series.len(), 3);
+        assert_eq!(series[0].session_count, 0);
+        assert_eq!(series[2].session_count, 1);
+        assert_eq!(series[2].estimated_tokens, 3);
+    }
+
     #[test]
     fn test_session_duration() {
         let session = make_session("dur", vec![user_msg("a", 0), assistant_msg("b", 120)]);
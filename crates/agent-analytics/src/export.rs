@@ -0,0 +1,547 @@
+//! HTML and PDF rendering of markdown reports.
+//!
+//! [`ReportGenerator`](crate::ReportGenerator) writes reports as markdown;
+//! this module turns that markdown into a self-contained styled HTML page
+//! or a plain PDF. Only the markdown the generator emits is understood:
+//! `#`/`##` headings, `-` and `1.` lists, pipe tables, `**bold**` and
+//! `` `code` ``.
+
+use std::fmt::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Output format for a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+    Pdf,
+}
+
+impl ReportFormat {
+    /// Format implied by a file's extension (`.html`/`.htm`, `.pdf`,
+    /// anything else markdown).
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("html" | "htm") => Self::Html,
+            Some("pdf") => Self::Pdf,
+            _ => Self::Markdown,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Html => "text/html; charset=utf-8",
+            Self::Pdf => "application/pdf",
+        }
+    }
+
+    /// Render a markdown report in this format.
+    pub fn render(self, markdown: &str) -> Vec<u8> {
+        match self {
+            Self::Markdown => markdown.as_bytes().to_vec(),
+            Self::Html => to_html(markdown).into_bytes(),
+            Self::Pdf => to_pdf(markdown),
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            "pdf" => Ok(Self::Pdf),
+            other => Err(format!(
+                "Unknown report format: '{}'. Use 'markdown', 'html' or 'pdf'.",
+                other
+            )),
+        }
+    }
+}
+
+/// One block of a parsed report.
+enum Block<'a> {
+    Heading(usize, &'a str),
+    Paragraph(&'a str),
+    List { ordered: bool, items: Vec<&'a str> },
+    Table(Vec<Vec<&'a str>>),
+}
+
+fn parse(markdown: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    for line in markdown.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        let hashes = line.bytes().take_while(|b| *b == b'#').count();
+        if hashes > 0 && line[hashes..].starts_with(' ') {
+            blocks.push(Block::Heading(hashes, line[hashes..].trim()));
+            continue;
+        }
+        if line.starts_with('|') {
+            let cells: Vec<&str> = line.trim_matches('|').split('|').map(str::trim).collect();
+            // Skip the `|---|---|` separator row.
+            if cells
+                .iter()
+                .all(|c| !c.is_empty() && c.chars().all(|ch| ch == '-' || ch == ':'))
+            {
+                continue;
+            }
+            match blocks.last_mut() {
+                Some(Block::Table(rows)) => rows.push(cells),
+                _ => blocks.push(Block::Table(vec![cells])),
+            }
+            continue;
+        }
+        let (ordered, item) = if let Some(item) = line.strip_prefix("- ") {
+            (false, Some(item))
+        } else {
+            let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+            (
+                true,
+                (digits > 0)
+                    .then(|| line[digits..].strip_prefix(". "))
+                    .flatten(),
+            )
+        };
+        match (item, blocks.last_mut()) {
+            (Some(item), Some(Block::List { ordered: o, items })) if *o == ordered => {
+                items.push(item)
+            }
+            (Some(item), _) => blocks.push(Block::List {
+                ordered,
+                items: vec![item],
+            }),
+            (None, _) => blocks.push(Block::Paragraph(line)),
+        }
+    }
+    blocks
+}
+
+// ── HTML ───────────────────────────────────────────────────────────────
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escape `text` and render `**bold**` and `` `code` `` spans.
+fn inline_html(text: &str) -> String {
+    let mut out = String::new();
+    let mut bold = false;
+    let mut code = false;
+    let mut rest = text;
+    while let Some(i) = rest.find(['*', '`']) {
+        out.push_str(&escape_html(&rest[..i]));
+        if rest[i..].starts_with('`') {
+            out.push_str(if code { "</code>" } else { "<code>" });
+            code = !code;
+            rest = &rest[i + 1..];
+        } else if rest[i..].starts_with("**") && !code {
+            out.push_str(if bold { "</strong>" } else { "<strong>" });
+            bold = !bold;
+            rest = &rest[i + 2..];
+        } else {
+            out.push('*');
+            rest = &rest[i + 1..];
+        }
+    }
+    out.push_str(&escape_html(rest));
+    if code {
+        out.push_str("</code>");
+    }
+    if bold {
+        out.push_str("</strong>");
+    }
+    out
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,-apple-system,sans-serif;max-width:760px;\
+margin:2.5em auto;padding:0 1em;color:#1f2328;line-height:1.5}h1{font-size:1.6em;\
+border-bottom:1px solid #d0d7de;padding-bottom:.3em}h2{font-size:1.2em;margin-top:1.6em}\
+table{border-collapse:collapse;width:100%;font-size:.9em}th,td{border:1px solid #d0d7de;\
+padding:.35em .7em;text-align:left}th{background:#f6f8fa}tr:nth-child(even) td{background:#fbfcfd}\
+code{background:#f6f8fa;padding:.1em .35em;border-radius:4px;font-size:.9em}\
+@media print{body{margin:0}}";
+
+/// Render a markdown report as a standalone HTML page with inline styles.
+pub fn to_html(markdown: &str) -> String {
+    let blocks = parse(markdown);
+    let title = blocks
+        .iter()
+        .find_map(|b| match b {
+            Block::Heading(_, text) => Some(text.replace('*', "")),
+            _ => None,
+        })
+        .unwrap_or_else(|| "Report".to_string());
+
+    let mut body = String::new();
+    for block in &blocks {
+        match block {
+            Block::Heading(level, text) => {
+                let level = (*level).min(6);
+                let _ = writeln!(body, "<h{0}>{1}</h{0}>", level, inline_html(text));
+            }
+            Block::Paragraph(text) => {
+                let _ = writeln!(body, "<p>{}</p>", inline_html(text));
+            }
+            Block::List { ordered, items } => {
+                let tag = if *ordered { "ol" } else { "ul" };
+                let _ = writeln!(body, "<{}>", tag);
+                for item in items {
+                    let _ = writeln!(body, "<li>{}</li>", inline_html(item));
+                }
+                let _ = writeln!(body, "</{}>", tag);
+            }
+            Block::Table(rows) => {
+                body.push_str("<table>\n");
+                for (i, row) in rows.iter().enumerate() {
+                    let cell = if i == 0 { "th" } else { "td" };
+                    body.push_str("<tr>");
+                    for text in row {
+                        let _ = write!(body, "<{0}>{1}</{0}>", cell, inline_html(text));
+                    }
+                    body.push_str("</tr>\n");
+                }
+                body.push_str("</table>\n");
+            }
+        }
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\"><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <title>{}</title><style>{}</style></head><body>\n{}</body></html>\n",
+        escape_html(&title),
+        HTML_STYLE,
+        body
+    )
+}
+
+// ── PDF ────────────────────────────────────────────────────────────────
+
+const PAGE_WIDTH: f32 = 595.0; // A4, in points.
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+
+/// Standard PDF fonts; no font data needs embedding.
+#[derive(Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+    Mono,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Self::Regular => "F1",
+            Self::Bold => "F2",
+            Self::Mono => "F3",
+        }
+    }
+}
+
+/// Text lines laid out onto pages of PDF content stream operators.
+struct Layout {
+    pages: Vec<String>,
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self {
+            pages: vec![String::new()],
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn space(&mut self, points: f32) {
+        self.y -= points;
+    }
+
+    fn line(&mut self, text: &str, font: Font, size: f32, indent: f32) {
+        let height = size * 1.4;
+        if self.y - height < MARGIN {
+            self.pages.push(String::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+        self.y -= height;
+        let page = self.pages.last_mut().expect("at least one page");
+        let _ = writeln!(
+            page,
+            "BT /{} {} Tf {} {} Td ({}) Tj ET",
+            font.resource(),
+            size,
+            MARGIN + indent,
+            self.y,
+            escape_pdf(text)
+        );
+    }
+
+    /// Wrap `text` to the page width, estimating glyph widths.
+    fn paragraph(&mut self, text: &str, font: Font, size: f32, indent: f32) {
+        let avg_glyph = match font {
+            Font::Mono => 0.6,
+            _ => 0.5,
+        } * size;
+        let max_chars = ((PAGE_WIDTH - 2.0 * MARGIN - indent) / avg_glyph) as usize;
+        let mut current = String::new();
+        for word in text.split_whitespace() {
+            if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > max_chars
+            {
+                self.line(&current, font, size, indent);
+                current.clear();
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        if !current.is_empty() {
+            self.line(&current, font, size, indent);
+        }
+    }
+}
+
+/// Escape a string for a PDF literal, mapping it to WinAnsi (Latin-1 for
+/// the characters reports use) and replacing anything else with `?`.
+fn escape_pdf(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '(' | ')' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(out, "\\{:03o}", c as u32);
+            }
+            // WinAnsi has dashes and bullets outside Latin-1, but no
+            // comparison signs.
+            '–' => out.push_str("\\226"),
+            '—' => out.push_str("\\227"),
+            '•' => out.push_str("\\225"),
+            '≥' => out.push_str(">="),
+            '≤' => out.push_str("<="),
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+/// Markdown spans reduced to plain text.
+fn plain(text: &str) -> String {
+    text.replace("**", "").replace('`', "")
+}
+
+/// Render a markdown report as a PDF document.
+pub fn to_pdf(markdown: &str) -> Vec<u8> {
+    let mut layout = Layout::new();
+    for block in parse(markdown) {
+        match block {
+            Block::Heading(1, text) => {
+                layout.paragraph(&plain(text), Font::Bold, 18.0, 0.0);
+                layout.space(6.0);
+            }
+            Block::Heading(_, text) => {
+                layout.space(8.0);
+                layout.paragraph(&plain(text), Font::Bold, 13.0, 0.0);
+                layout.space(2.0);
+            }
+            Block::Paragraph(text) => {
+                layout.paragraph(&plain(text), Font::Regular, 10.5, 0.0);
+                layout.space(4.0);
+            }
+            Block::List { ordered, items } => {
+                for (i, item) in items.iter().enumerate() {
+                    let marker = if ordered {
+                        format!("{}.", i + 1)
+                    } else {
+                        "-".to_string()
+                    };
+                    layout.paragraph(
+                        &format!("{} {}", marker, plain(item)),
+                        Font::Regular,
+                        10.5,
+                        12.0,
+                    );
+                }
+                layout.space(4.0);
+            }
+            Block::Table(rows) => {
+                let rows: Vec<Vec<String>> = rows
+                    .iter()
+                    .map(|r| r.iter().map(|c| plain(c)).collect())
+                    .collect();
+                let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+                let widths: Vec<usize> = (0..columns)
+                    .map(|i| {
+                        rows.iter()
+                            .filter_map(|r| r.get(i))
+                            .map(|c| c.chars().count())
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    .collect();
+                for (n, row) in rows.iter().enumerate() {
+                    let line: Vec<String> = widths
+                        .iter()
+                        .enumerate()
+                        .map(|(i, w)| format!("{:<w$}", row.get(i).map_or("", |c| c), w = *w))
+                        .collect();
+                    // Monospaced throughout, header included, to keep columns aligned.
+                    layout.line(line.join("  ").trim_end(), Font::Mono, 9.0, 0.0);
+                    if n == 0 {
+                        let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+                        layout.line(&rule.join("  "), Font::Mono, 9.0, 0.0);
+                    }
+                }
+                layout.space(6.0);
+            }
+        }
+    }
+    write_pdf(&layout.pages)
+}
+
+/// Assemble a PDF from page content streams: catalog, page tree, three
+/// standard fonts, then a page and content object per page.
+fn write_pdf(pages: &[String]) -> Vec<u8> {
+    let first_page = 6;
+    let mut objects: Vec<String> = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len())
+                .map(|i| format!("{} 0 R", first_page + 2 * i))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        ),
+    ];
+    for base in ["Helvetica", "Helvetica-Bold", "Courier"] {
+        objects.push(format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+            base
+        ));
+    }
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R /F3 5 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            first_page + 2 * i + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        let _ = write!(out, "{} 0 obj\n{}\nendobj\n", i + 1, object);
+    }
+    let xref = out.len();
+    let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(out, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        out,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    out.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = "# Weekly Report\n\n**May 01 - May 07**\n\n## Overview\n\n\
+        - **Sessions:** 3\n- **Messages:** 12\n\n## Daily Breakdown\n\n\
+        | Day | Sessions |\n|-----|----------|\n| Monday | 2 |\n| Tuesday | 1 |\n\n\
+        ## Top Tools\n\n1. `shell_exec` - 4 calls\n2. `file_read` - 2 calls\n";
+
+    #[test]
+    fn test_format_parsing() {
+        assert_eq!("HTML".parse::<ReportFormat>(), Ok(ReportFormat::Html));
+        assert_eq!("md".parse::<ReportFormat>(), Ok(ReportFormat::Markdown));
+        assert!("docx".parse::<ReportFormat>().is_err());
+        assert_eq!(
+            ReportFormat::from_path(Path::new("r.PDF")),
+            ReportFormat::Pdf
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("r.txt")),
+            ReportFormat::Markdown
+        );
+    }
+
+    #[test]
+    fn test_html() {
+        let html = to_html(REPORT);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Weekly Report</title>"));
+        assert!(html.contains("<li><strong>Sessions:</strong> 3</li>"));
+        assert!(html.contains("<tr><th>Day</th><th>Sessions</th></tr>"));
+        assert!(html.contains("<tr><td>Monday</td><td>2</td></tr>"));
+        assert!(html.contains("<ol>\n<li><code>shell_exec</code> - 4 calls</li>"));
+        assert!(!html.contains("|---"));
+        assert_eq!(to_html("a <b> & c").matches("&lt;b&gt; &amp;").count(), 1);
+    }
+
+    #[test]
+    fn test_pdf_structure() {
+        let pdf = to_pdf(REPORT);
+        let text = String::from_utf8(pdf).unwrap();
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("(Weekly Report) Tj"));
+        assert!(text.contains("(Monday   2) Tj"));
+
+        // Every xref offset points at its object.
+        let xref = text.rfind("\nxref\n").unwrap() + 1;
+        let start: usize = text.lines().rev().nth(1).unwrap().parse().unwrap();
+        assert_eq!(start, xref);
+        for (i, line) in text[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|l| l.ends_with(" n "))
+            .enumerate()
+        {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+
+    #[test]
+    fn test_pdf_paginates_and_escapes() {
+        let long: String = (0..200).map(|i| format!("- item ({i}) é\n")).collect();
+        let text = String::from_utf8(to_pdf(&long)).unwrap();
+        let pages = text.matches("/Type /Page ").count();
+        assert!(pages > 1);
+        assert!(text.contains(&format!("/Count {}", pages)));
+        assert!(text.contains("(- item \\(0\\) \\351) Tj"));
+        assert_eq!(escape_pdf("≥ 3 – ≤ 5 ✓"), ">= 3 \\226 <= 5 ?");
+    }
+}
@@ -0,0 +1,295 @@
+//! Progress toward the usage goals configured under `[[analytics.goals]]`.
+//!
+//! A goal is evaluated over the day, week (Monday to Sunday) or month
+//! containing a date. While that period is still running an unmet goal is
+//! [`GoalState::Behind`]; once it has ended, [`GoalState::Missed`].
+
+use crate::aggregations::Analytics;
+use agent_core::config::{GoalConfig, GoalMetric, GoalPeriod};
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalState {
+    Met,
+    /// Not met yet, but the period has not ended.
+    Behind,
+    /// Not met, and the period has ended.
+    Missed,
+}
+
+/// A goal evaluated over one period.
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalStatus {
+    pub name: String,
+    pub metric: GoalMetric,
+    pub period: GoalPeriod,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub value: f64,
+    /// The target in words, e.g. "≤ 5%".
+    pub target: String,
+    pub state: GoalState,
+    /// How close the value is to the target, from 0 to 1 (1 when met).
+    pub progress: f64,
+}
+
+/// First and last day of the `period` containing `date`.
+pub fn period_bounds(period: GoalPeriod, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    match period {
+        GoalPeriod::Day => (date, date),
+        GoalPeriod::Week => {
+            let start = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+            (start, start + Duration::days(6))
+        }
+        GoalPeriod::Month => {
+            let start = date.with_day(1).expect("day 1 exists");
+            let next = if date.month() == 12 {
+                NaiveDate::from_ymd_opt(date.year() + 1, 1, 1)
+            } else {
+                NaiveDate::from_ymd_opt(date.year(), date.month() + 1, 1)
+            };
+            (start, next.expect("valid month") - Duration::days(1))
+        }
+    }
+}
+
+/// Value of `metric` over a date range.
+pub fn metric_value(
+    analytics: &Analytics,
+    metric: GoalMetric,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> f64 {
+    let summaries = analytics.get_range_summaries(start, end);
+    match metric {
+        GoalMetric::ToolErrorRate => analytics.error_rate(start, end) * 100.0,
+        GoalMetric::DeepWorkSessions => analytics.deep_work_sessions_between(start, end) as f64,
+        GoalMetric::Sessions => summaries.iter().map(|s| s.session_count as f64).sum(),
+        GoalMetric::Messages => summaries.iter().map(|s| s.message_count as f64).sum(),
+        GoalMetric::ToolCalls => summaries.iter().map(|s| s.tool_call_count as f64).sum(),
+        GoalMetric::ActiveHours => analytics.total_active_time(start, end) as f64 / 3600.0,
+        GoalMetric::ActiveDays => summaries.iter().filter(|s| s.session_count > 0).count() as f64,
+    }
+}
+
+/// Evaluate `goal` over the period containing `date`, as of `today`.
+pub fn evaluate(
+    analytics: &Analytics,
+    goal: &GoalConfig,
+    date: NaiveDate,
+    today: NaiveDate,
+) -> GoalStatus {
+    let (start, end) = period_bounds(goal.period, date);
+    let value = metric_value(analytics, goal.metric, start, end);
+
+    let above_min = goal.min.is_none_or(|min| value >= min);
+    let below_max = goal.max.is_none_or(|max| value <= max);
+    let state = match (above_min && below_max, end < today) {
+        (true, _) => GoalState::Met,
+        (false, false) => GoalState::Behind,
+        (false, true) => GoalState::Missed,
+    };
+    let progress = match (goal.min, goal.max) {
+        _ if state == GoalState::Met => 1.0,
+        (Some(min), _) if !above_min && min > 0.0 => value / min,
+        (_, Some(max)) if !below_max && value > 0.0 => max / value,
+        _ => 0.0,
+    };
+
+    GoalStatus {
+        name: goal.name.clone(),
+        metric: goal.metric,
+        period: goal.period,
+        period_start: start,
+        period_end: end,
+        value,
+        target: describe_target(goal),
+        state,
+        progress: progress.clamp(0.0, 1.0),
+    }
+}
+
+/// Every goal evaluated over its current period.
+pub fn current_statuses(
+    analytics: &Analytics,
+    goals: &[GoalConfig],
+    today: NaiveDate,
+) -> Vec<GoalStatus> {
+    goals
+        .iter()
+        .map(|g| evaluate(analytics, g, today, today))
+        .collect()
+}
+
+/// Goals missed in their most recently finished period.
+pub fn missed_goals(
+    analytics: &Analytics,
+    goals: &[GoalConfig],
+    today: NaiveDate,
+) -> Vec<GoalStatus> {
+    goals
+        .iter()
+        .map(|g| {
+            let (start, _) = period_bounds(g.period, today);
+            evaluate(analytics, g, start - Duration::days(1), today)
+        })
+        .filter(|s| s.state == GoalState::Missed)
+        .collect()
+}
+
+/// `value` formatted with the metric's unit.
+pub fn format_value(metric: GoalMetric, value: f64) -> String {
+    let number = if value.fract() == 0.0 {
+        format!("{}", value)
+    } else {
+        format!("{:.1}", value)
+    };
+    match metric {
+        GoalMetric::ToolErrorRate => format!("{}%", number),
+        GoalMetric::ActiveHours => format!("{}h", number),
+        _ => number,
+    }
+}
+
+fn describe_target(goal: &GoalConfig) -> String {
+    let fmt = |v| format_value(goal.metric, v);
+    match (goal.min, goal.max) {
+        (Some(min), Some(max)) => format!("{} – {}", fmt(min), fmt(max)),
+        (Some(min), None) => format!("≥ {}", fmt(min)),
+        (None, Some(max)) => format!("≤ {}", fmt(max)),
+        (None, None) => "any".to_string(),
+    }
+}
+
+/// JSON body posted to `[analytics] webhook_url` for a missed goal. `text`
+/// makes it readable as a Slack-style incoming webhook message.
+pub fn webhook_payload(status: &GoalStatus) -> serde_json::Value {
+    serde_json::json!({
+        "event": "goal_missed",
+        "text": format!(
+            "Goal missed: {} — {} for {} to {} (target {})",
+            status.name,
+            format_value(status.metric, status.value),
+            status.period_start,
+            status.period_end,
+            status.target
+        ),
+        "goal": status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::session::Session;
+    use agent_core::types::{Message, ToolCall};
+
+    fn goal(metric: GoalMetric, min: Option<f64>, max: Option<f64>) -> GoalConfig {
+        GoalConfig {
+            name: format!("{:?}", metric),
+            metric,
+            period: GoalPeriod::Week,
+            min,
+            max,
+        }
+    }
+
+    /// A session on `date` lasting `minutes`, with one failed and one
+    /// successful tool call.
+    fn analytics_with_session(date: NaiveDate, minutes: i64) -> Analytics {
+        let start = date.and_hms_opt(9, 0, 0).unwrap().and_utc();
+        let mut session = Session::new("work");
+        session.created_at = start;
+        let call = |id: &str| ToolCall {
+            id: id.into(),
+            name: "shell_exec".into(),
+            arguments: "{}".into(),
+        };
+        let mut first = Message::assistant_with_tool_calls("", vec![call("a"), call("b")]);
+        first.timestamp = start;
+        let mut error = Message::tool_result("a", "Error: failed");
+        error.timestamp = start;
+        let mut last = Message::assistant("done");
+        last.timestamp = start + Duration::minutes(minutes);
+        session.messages = vec![first, error, Message::tool_result("b", "ok"), last];
+        session.messages[2].timestamp = start;
+
+        let mut analytics = Analytics::default();
+        analytics.process_session(&session);
+        analytics.finalize_all();
+        analytics
+    }
+
+    #[test]
+    fn test_period_bounds() {
+        let date = NaiveDate::from_ymd_opt(2026, 12, 17).unwrap(); // Thursday
+        let d = |m, day| NaiveDate::from_ymd_opt(2026, m, day).unwrap();
+        assert_eq!(period_bounds(GoalPeriod::Day, date), (date, date));
+        assert_eq!(
+            period_bounds(GoalPeriod::Week, date),
+            (d(12, 14), d(12, 20))
+        );
+        assert_eq!(
+            period_bounds(GoalPeriod::Month, date),
+            (d(12, 1), d(12, 31))
+        );
+    }
+
+    #[test]
+    fn test_evaluate_states() {
+        let monday = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        let analytics = analytics_with_session(monday, 45);
+
+        // 1 of 2 tool calls failed: 50%.
+        let errors = goal(GoalMetric::ToolErrorRate, None, Some(5.0));
+        let status = evaluate(&analytics, &errors, monday, monday);
+        assert_eq!(status.value, 50.0);
+        assert_eq!(status.state, GoalState::Behind);
+        assert_eq!(status.target, "≤ 5%");
+        assert_eq!(status.progress, 0.1);
+
+        let next_week = monday + Duration::days(7);
+        assert_eq!(
+            evaluate(&analytics, &errors, monday, next_week).state,
+            GoalState::Missed
+        );
+
+        let deep = goal(GoalMetric::DeepWorkSessions, Some(3.0), None);
+        let status = evaluate(&analytics, &deep, monday, monday);
+        assert_eq!(status.value, 1.0);
+        assert!((status.progress - 1.0 / 3.0).abs() < 1e-9);
+
+        let sessions = goal(GoalMetric::Sessions, Some(1.0), None);
+        assert_eq!(
+            evaluate(&analytics, &sessions, monday, next_week).state,
+            GoalState::Met
+        );
+    }
+
+    #[test]
+    fn test_missed_goals_checks_previous_period() {
+        let monday = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        let analytics = analytics_with_session(monday, 10);
+        let goals = vec![
+            goal(GoalMetric::ToolErrorRate, None, Some(5.0)),
+            goal(GoalMetric::Sessions, Some(1.0), None),
+        ];
+
+        // Mid-week, the finished period is the week before, with no sessions.
+        let missed = missed_goals(&analytics, &goals, monday + Duration::days(3));
+        assert_eq!(missed.len(), 1);
+        assert_eq!(missed[0].metric, GoalMetric::Sessions);
+
+        let missed = missed_goals(&analytics, &goals, monday + Duration::days(8));
+        assert_eq!(missed.len(), 1);
+        assert_eq!(missed[0].metric, GoalMetric::ToolErrorRate);
+        assert_eq!(missed[0].period_start, monday);
+
+        let payload = webhook_payload(&missed[0]);
+        assert_eq!(payload["event"], "goal_missed");
+        assert_eq!(payload["goal"]["state"], "missed");
+        assert!(payload["text"].as_str().unwrap().contains("50%"));
+    }
+}
@@ -1,10 +1,14 @@
 //! Analytics, aggregation, and reporting for agent-shell sessions.
 //!
 //! Computes metrics from session data: daily summaries, tool usage frequency,
-//! conversation patterns, and markdown report generation.
+//! conversation patterns, and report generation as markdown, HTML or PDF.
 
 pub mod aggregations;
+pub mod export;
+pub mod goals;
 pub mod reports;
 
-pub use aggregations::{Analytics, DailySummary, SessionStats};
+pub use aggregations::{Analytics, DailySummary, QualityMetrics, SessionStats};
+pub use export::ReportFormat;
+pub use goals::{GoalState, GoalStatus};
 pub use reports::ReportGenerator;
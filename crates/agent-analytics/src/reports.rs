@@ -1,14 +1,135 @@
 //! Markdown report generation from analytics data.
 //!
-//! Generates weekly, monthly, and summary reports in markdown format.
+//! Generates weekly, monthly, and summary reports in markdown format, which
+//! [`ReportFormat`] can render as HTML or PDF.
 
 use crate::aggregations::{format_duration, Analytics};
+use crate::export::{self, ReportFormat};
+use crate::goals::{self, GoalState, GoalStatus};
+use agent_core::config::GoalConfig;
 use chrono::{Datelike, Duration, NaiveDate};
 
 /// Report generator for creating markdown summaries.
 pub struct ReportGenerator;
 
 impl ReportGenerator {
+    /// Generate the report for the `week` or `month` containing `today`,
+    /// ending with the current status of `goals`.
+    pub fn period_report(
+        analytics: &Analytics,
+        period: &str,
+        today: NaiveDate,
+        goals: &[GoalConfig],
+    ) -> Result<String, String> {
+        let mut report = match period {
+            "week" => {
+                let weekday = today.weekday().num_days_from_monday();
+                let monday = today - Duration::days(weekday as i64);
+                Self::weekly_report(analytics, monday)
+            }
+            "month" => Self::monthly_report(analytics, today.year(), today.month()),
+            other => {
+                return Err(format!(
+                    "Unknown period: '{}'. Use 'week' or 'month'.",
+                    other
+                ))
+            }
+        };
+        report.push_str(&Self::goals_section(&goals::current_statuses(
+            analytics, goals, today,
+        )));
+        Ok(report)
+    }
+
+    /// A `## Goals` table, or nothing when no goals are configured.
+    pub fn goals_section(statuses: &[GoalStatus]) -> String {
+        if statuses.is_empty() {
+            return String::new();
+        }
+        let mut section = String::from("## Goals\n\n");
+        section.push_str("| Goal | Period | Current | Target | Status |\n");
+        section.push_str("|------|--------|---------|--------|--------|\n");
+        for status in statuses {
+            let state = match status.state {
+                GoalState::Met => "Met",
+                GoalState::Behind => "Behind",
+                GoalState::Missed => "Missed",
+            };
+            section.push_str(&format!(
+                "| {} | {} – {} | {} | {} | {} |\n",
+                status.name.replace('|', "/"),
+                status.period_start.format("%b %d"),
+                status.period_end.format("%b %d"),
+                goals::format_value(status.metric, status.value),
+                status.target,
+                state
+            ));
+        }
+        section.push('\n');
+        section
+    }
+
+    /// A `## Conversation Quality` list for sessions started in a date
+    /// range, or nothing when there were none.
+    pub fn quality_section(analytics: &Analytics, start: NaiveDate, end: NaiveDate) -> String {
+        let quality = analytics.quality_metrics(start, end);
+        if quality.sessions == 0 {
+            return String::new();
+        }
+        let mut section = String::from("## Conversation Quality\n\n");
+        section.push_str(&format!(
+            "- **Agent Iterations per Message:** {:.1}\n",
+            quality.iterations_per_user_message()
+        ));
+        section.push_str(&format!(
+            "- **Tool Retries:** {} ({:.1}% of calls)\n",
+            quality.tool_retries,
+            quality.retry_rate() * 100.0
+        ));
+        if quality.quota_denials > 0 {
+            section.push_str(&format!(
+                "- **Quota Denials:** {} (tool calls over a `[tools.quotas]` limit)\n",
+                quality.quota_denials
+            ));
+        }
+        section.push_str(&format!(
+            "- **Abandoned Sessions:** {} / {} (ended on a tool error)\n",
+            quality.abandoned_sessions, quality.sessions
+        ));
+        if let Some(rate) = quality.approval_rate() {
+            section.push_str(&format!(
+                "- **User Feedback:** {} up / {} down ({:.0}% positive)\n",
+                quality.thumbs_up,
+                quality.thumbs_down,
+                rate * 100.0
+            ));
+        }
+        section.push_str(&format!(
+            "- **Avg Response Time:** {}\n\n",
+            match quality.average_response_latency_secs {
+                Some(secs) if secs < 60.0 => format!("{:.0}s", secs),
+                Some(secs) => format_duration(secs as u64),
+                None => "-".to_string(),
+            }
+        ));
+        section
+    }
+
+    /// Render a markdown report as a self-contained, styled HTML page.
+    pub fn to_html(report: &str) -> String {
+        export::to_html(report)
+    }
+
+    /// Render a markdown report as a PDF document.
+    pub fn to_pdf(report: &str) -> Vec<u8> {
+        export::to_pdf(report)
+    }
+
+    /// Render a markdown report in `format`.
+    pub fn render(report: &str, format: ReportFormat) -> Vec<u8> {
+        format.render(report)
+    }
+
     /// Generate a weekly report.
     pub fn weekly_report(analytics: &Analytics, week_start: NaiveDate) -> String {
         let week_end = week_start + Duration::days(6);
@@ -71,6 +192,8 @@ impl ReportGenerator {
         }
         report.push('\n');
 
+        report.push_str(&Self::quality_section(analytics, week_start, week_end));
+
         // Top tools.
         let top_tools = analytics.top_tools(10);
         if !top_tools.is_empty() {
@@ -176,6 +299,8 @@ impl ReportGenerator {
         }
         report.push('\n');
 
+        report.push_str(&Self::quality_section(analytics, first_day, last_day));
+
         // Top tools.
         let top_tools = analytics.top_tools(15);
         if !top_tools.is_empty() {
@@ -231,6 +356,24 @@ impl ReportGenerator {
             format_duration(week_time)
         ));
 
+        let quality = analytics.quality_metrics(week_ago, today);
+        if quality.user_messages > 0 {
+            output.push_str(&format!(
+                "  Quality: {:.1} iterations/msg, {} tool retries, {} abandoned\n",
+                quality.iterations_per_user_message(),
+                quality.tool_retries,
+                quality.abandoned_sessions
+            ));
+        }
+        if let Some(rate) = quality.approval_rate() {
+            output.push_str(&format!(
+                "  Feedback: {} up, {} down ({:.0}% positive)\n",
+                quality.thumbs_up,
+                quality.thumbs_down,
+                rate * 100.0
+            ));
+        }
+
         // All time.
         output.push_str(&format!(
             "  All time: {} sessions across {} days\n",
@@ -313,6 +456,9 @@ mod tests {
         assert!(report.contains("## Overview"));
         assert!(report.contains("## Daily Breakdown"));
         assert!(report.contains("Sessions"));
+        assert!(report.contains("## Conversation Quality"));
+        assert!(report.contains("- **Agent Iterations per Message:** 1.0"));
+        assert!(report.contains("- **Avg Response Time:** 1h 0m"));
     }
 
     #[test]
@@ -337,6 +483,31 @@ mod tests {
         assert!(report.contains("## Weekly Breakdown"));
     }
 
+    #[test]
+    fn test_period_report() {
+        let analytics = Analytics::default();
+        let today = NaiveDate::from_ymd_opt(2026, 3, 12).unwrap();
+        let week = ReportGenerator::period_report(&analytics, "week", today, &[]).unwrap();
+        assert!(week.contains("March 09, 2026 - March 15, 2026"));
+        assert!(!week.contains("## Goals"));
+        let month = ReportGenerator::period_report(&analytics, "month", today, &[]).unwrap();
+        assert!(month.contains("**March 2026**"));
+        assert!(ReportGenerator::period_report(&analytics, "year", today, &[]).is_err());
+
+        let goals = [GoalConfig {
+            name: "Daily habit".into(),
+            metric: agent_core::config::GoalMetric::ActiveDays,
+            period: agent_core::config::GoalPeriod::Week,
+            min: Some(5.0),
+            max: None,
+        }];
+        let week = ReportGenerator::period_report(&analytics, "week", today, &goals).unwrap();
+        assert!(week.contains("| Daily habit | Mar 09 – Mar 15 | 0 | ≥ 5 | Behind |"));
+
+        let html = ReportGenerator::render(&week, ReportFormat::Html);
+        assert!(String::from_utf8(html).unwrap().contains("<h1>Weekly Report</h1>"));
+    }
+
     #[test]
     fn test_text_summary() {
         let mut analytics = Analytics::default();
@@ -366,6 +537,7 @@ mod tests {
         let report = ReportGenerator::weekly_report(&analytics, today);
         assert!(report.contains("# Weekly Report"));
         assert!(report.contains("0 |")); // Table rows should show zeros.
+        assert!(!report.contains("## Conversation Quality"));
     }
 
     #[test]
@@ -0,0 +1,236 @@
+//! Packing and unpacking backup archives.
+//!
+//! An archive is a tar.gz with up to three top-level entries: `sessions/`,
+//! `skills/` and `config.toml`. Unpacking only writes entries under those
+//! names, so a crafted archive cannot reach outside the target directories.
+
+use crate::{BackupError, Result};
+use agent_core::config::AppConfig;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+const SESSIONS: &str = "sessions";
+const SKILLS: &str = "skills";
+const CONFIG: &str = "config.toml";
+
+/// What goes into a backup.
+#[derive(Debug, Clone, Default)]
+pub struct Sources {
+    pub sessions_dir: Option<PathBuf>,
+    pub skills_dir: Option<PathBuf>,
+    /// Config file contents.
+    pub config: Option<String>,
+}
+
+impl Sources {
+    /// The parts `[backup]` selects: the sessions directory, the skills
+    /// directory and `config` itself.
+    pub fn for_config(config: &AppConfig) -> Result<Self> {
+        let backup = &config.backup;
+        Ok(Self {
+            sessions_dir: backup.sessions.then(|| sessions_dir(config)),
+            skills_dir: backup.skills.then(skills_dir),
+            config: match backup.config {
+                true => Some(
+                    toml::to_string_pretty(config)
+                        .map_err(|e| BackupError::Invalid(format!("config: {e}")))?,
+                ),
+                false => None,
+            },
+        })
+    }
+}
+
+/// Where a restore writes to. Parts with no target are left out.
+#[derive(Debug, Clone, Default)]
+pub struct Targets {
+    pub sessions_dir: Option<PathBuf>,
+    pub skills_dir: Option<PathBuf>,
+    pub config_path: Option<PathBuf>,
+}
+
+impl Targets {
+    /// The directories `config` uses, with the config file at `config_path`.
+    pub fn for_config(config: &AppConfig, config_path: PathBuf) -> Self {
+        Self {
+            sessions_dir: Some(sessions_dir(config)),
+            skills_dir: Some(skills_dir()),
+            config_path: Some(config_path),
+        }
+    }
+}
+
+fn sessions_dir(config: &AppConfig) -> PathBuf {
+    config
+        .session
+        .history_dir
+        .clone()
+        .unwrap_or_else(|| AppConfig::data_dir().join("sessions"))
+}
+
+fn skills_dir() -> PathBuf {
+    AppConfig::data_dir().join("skills")
+}
+
+/// Outcome of unpacking an archive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Unpacked {
+    /// Files written.
+    pub restored: usize,
+    /// Files left alone because they already existed.
+    pub skipped: usize,
+}
+
+/// Pack `sources` into a tar.gz.
+pub fn pack(sources: &Sources) -> Result<Vec<u8>> {
+    let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    tar.follow_symlinks(false);
+    for (name, dir) in [(SESSIONS, &sources.sessions_dir), (SKILLS, &sources.skills_dir)] {
+        if let Some(dir) = dir.as_ref().filter(|dir| dir.is_dir()) {
+            tar.append_dir_all(name, dir)?;
+        }
+    }
+    if let Some(config) = &sources.config {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(config.len() as u64);
+        header.set_mode(0o600);
+        header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+        header.set_cksum();
+        tar.append_data(&mut header, CONFIG, config.as_bytes())?;
+    }
+    Ok(tar.into_inner()?.finish()?)
+}
+
+/// Unpack a tar.gz made by [`pack`] into `targets`. Existing files are
+/// kept unless `overwrite` is set.
+pub fn unpack(data: &[u8], targets: &Targets, overwrite: bool) -> Result<Unpacked> {
+    let mut tar = tar::Archive::new(GzDecoder::new(data));
+    let mut unpacked = Unpacked::default();
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let Some(dest) = destination(&path, targets)? else {
+            continue;
+        };
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => {
+                std::fs::create_dir_all(&dest)?;
+                continue;
+            }
+            tar::EntryType::Regular => {}
+            // Links and devices never come from `pack`.
+            _ => continue,
+        }
+        if dest.exists() && !overwrite {
+            unpacked.skipped += 1;
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents)?;
+        std::fs::write(&dest, contents)?;
+        unpacked.restored += 1;
+    }
+    Ok(unpacked)
+}
+
+/// Where an archive entry goes, or `None` for parts without a target.
+fn destination(path: &Path, targets: &Targets) -> Result<Option<PathBuf>> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part),
+            Component::CurDir => {}
+            _ => {
+                return Err(BackupError::Invalid(format!(
+                    "unsafe path in archive: {}",
+                    path.display()
+                )))
+            }
+        }
+    }
+    let Some((first, rest)) = parts.split_first() else {
+        return Ok(None);
+    };
+    let base = match first.to_str() {
+        Some(SESSIONS) => &targets.sessions_dir,
+        Some(SKILLS) => &targets.skills_dir,
+        Some(CONFIG) if rest.is_empty() => return Ok(targets.config_path.clone()),
+        _ => {
+            return Err(BackupError::Invalid(format!(
+                "unexpected entry in archive: {}",
+                path.display()
+            )))
+        }
+    };
+    Ok(base
+        .as_ref()
+        .map(|base| rest.iter().fold(base.clone(), |dest, part| dest.join(part))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack_round_trip() {
+        let src = tempfile::tempdir().unwrap();
+        let sessions = src.path().join("sessions");
+        std::fs::create_dir_all(sessions.join("archive")).unwrap();
+        std::fs::write(sessions.join("a.json"), "{\"id\":\"a\"}").unwrap();
+        std::fs::write(sessions.join("archive/b.json"), "{\"id\":\"b\"}").unwrap();
+        let data = pack(&Sources {
+            sessions_dir: Some(sessions),
+            skills_dir: Some(src.path().join("missing")),
+            config: Some("[backup]\nkeep = 3\n".into()),
+        })
+        .unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let targets = Targets {
+            sessions_dir: Some(dest.path().join("s")),
+            skills_dir: Some(dest.path().join("k")),
+            config_path: Some(dest.path().join("config.toml")),
+        };
+        std::fs::create_dir_all(dest.path().join("s")).unwrap();
+        std::fs::write(dest.path().join("s/a.json"), "local").unwrap();
+
+        let unpacked = unpack(&data, &targets, false).unwrap();
+        assert_eq!(unpacked, Unpacked { restored: 2, skipped: 1 });
+        assert_eq!(std::fs::read_to_string(dest.path().join("s/a.json")).unwrap(), "local");
+        assert_eq!(
+            std::fs::read_to_string(dest.path().join("s/archive/b.json")).unwrap(),
+            "{\"id\":\"b\"}"
+        );
+        assert!(std::fs::read_to_string(dest.path().join("config.toml"))
+            .unwrap()
+            .contains("keep = 3"));
+
+        unpack(&data, &targets, true).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dest.path().join("s/a.json")).unwrap(),
+            "{\"id\":\"a\"}"
+        );
+    }
+
+    #[test]
+    fn test_destination_rejects_unsafe_paths() {
+        let targets = Targets {
+            sessions_dir: Some(PathBuf::from("/data/sessions")),
+            ..Default::default()
+        };
+        assert_eq!(
+            destination(Path::new("sessions/x.json"), &targets).unwrap(),
+            Some(PathBuf::from("/data/sessions/x.json"))
+        );
+        assert_eq!(destination(Path::new("skills/s.md"), &targets).unwrap(), None);
+        assert!(destination(Path::new("sessions/../../etc/passwd"), &targets).is_err());
+        assert!(destination(Path::new("/etc/passwd"), &targets).is_err());
+        assert!(destination(Path::new("other/file"), &targets).is_err());
+    }
+}
@@ -0,0 +1,65 @@
+//! Passphrase encryption of backup archives.
+//!
+//! Layout: an 8-byte magic, a 16-byte Argon2id salt, a 24-byte
+//! XChaCha20-Poly1305 nonce, then the ciphertext. The key is derived from
+//! the passphrase and salt, so every backup has its own key.
+
+use crate::{BackupError, Result};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+const MAGIC: &[u8; 8] = b"AGSHBK01";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<XChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| BackupError::Invalid(format!("key derivation failed: {e}")))?;
+    Ok(XChaCha20Poly1305::new(&key.into()))
+}
+
+pub fn encrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher(passphrase, &salt)?
+        .encrypt(&nonce, data)
+        .map_err(|_| BackupError::Invalid("encryption failed".into()))?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let header = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if data.len() < header || &data[..MAGIC.len()] != MAGIC {
+        return Err(BackupError::Invalid("not an agent-shell backup".into()));
+    }
+    let salt = &data[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = XNonce::from_slice(&data[MAGIC.len() + SALT_LEN..header]);
+    cipher(passphrase, salt)?
+        .decrypt(nonce, &data[header..])
+        .map_err(|_| BackupError::Decrypt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_wrong_passphrase() {
+        let sealed = encrypt(b"session data", "correct horse").unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert_ne!(sealed, encrypt(b"session data", "correct horse").unwrap());
+        assert_eq!(decrypt(&sealed, "correct horse").unwrap(), b"session data");
+        assert!(matches!(decrypt(&sealed, "wrong"), Err(BackupError::Decrypt)));
+        assert!(matches!(decrypt(b"plain tar", "x"), Err(BackupError::Invalid(_))));
+    }
+}
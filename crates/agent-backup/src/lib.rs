@@ -0,0 +1,325 @@
+//! Encrypted backups of agent-shell sessions, skills and config.
+//!
+//! A backup is a tar.gz of the sessions directory, the skills directory and
+//! the config file ([`archive`]), encrypted with a passphrase-derived key
+//! (Argon2id + XChaCha20-Poly1305), and stored as
+//! `agent-shell-<timestamp>.backup` on an S3-compatible bucket or a WebDAV
+//! collection ([`remote`]). After each backup only the newest `keep` are
+//! kept on the remote.
+
+pub mod archive;
+mod crypto;
+pub mod remote;
+
+pub use archive::{Sources, Targets, Unpacked};
+pub use remote::Remote;
+
+use agent_core::config::BackupConfig;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+
+const NAME_PREFIX: &str = "agent-shell-";
+const NAME_SUFFIX: &str = ".backup";
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Remote returned {status}: {message}")]
+    Remote { status: u16, message: String },
+
+    #[error("Could not decrypt backup (wrong passphrase or corrupted file)")]
+    Decrypt,
+
+    #[error("Invalid backup: {0}")]
+    Invalid(String),
+
+    #[error("Backup not configured: {0}")]
+    Config(String),
+
+    #[error("Backup not found: {0}")]
+    NotFound(String),
+}
+
+pub type Result<T> = std::result::Result<T, BackupError>;
+
+/// A backup stored on the remote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BackupInfo {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BackupInfo {
+    fn parse(name: &str) -> Option<Self> {
+        let stamp = name.strip_prefix(NAME_PREFIX)?.strip_suffix(NAME_SUFFIX)?;
+        let created_at = NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT)
+            .ok()?
+            .and_utc();
+        Some(Self {
+            name: name.to_string(),
+            created_at,
+        })
+    }
+}
+
+/// A written backup.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupReport {
+    pub name: String,
+    /// Encrypted size in bytes.
+    pub size: usize,
+    /// Older backups deleted to stay within `keep`.
+    pub removed: Vec<String>,
+}
+
+/// A restored backup.
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreReport {
+    pub name: String,
+    pub restored: usize,
+    pub skipped: usize,
+}
+
+/// Backups on one remote under one passphrase.
+pub struct Backups {
+    remote: Box<dyn Remote>,
+    passphrase: String,
+    keep: usize,
+}
+
+impl Backups {
+    pub fn new(remote: Box<dyn Remote>, passphrase: impl Into<String>, keep: usize) -> Self {
+        Self {
+            remote,
+            passphrase: passphrase.into(),
+            keep,
+        }
+    }
+
+    /// Backups as configured by `[backup]`; fails without a remote or
+    /// passphrase.
+    pub fn from_config(config: &BackupConfig) -> Result<Self> {
+        let remote = config
+            .remote
+            .as_ref()
+            .ok_or_else(|| BackupError::Config("set [backup.remote]".into()))?;
+        let passphrase = config.resolve_passphrase().ok_or_else(|| {
+            BackupError::Config("set backup.passphrase or backup.passphrase_env".into())
+        })?;
+        Ok(Self::new(remote::from_config(remote)?, passphrase, config.keep))
+    }
+
+    /// Pack, encrypt and upload `sources` as a backup taken at `now`, then
+    /// delete all but the newest `keep` backups.
+    pub async fn backup(&self, sources: &Sources, now: DateTime<Utc>) -> Result<BackupReport> {
+        let name = format!(
+            "{}{}{}",
+            NAME_PREFIX,
+            now.format(TIMESTAMP_FORMAT),
+            NAME_SUFFIX
+        );
+        let sealed = crypto::encrypt(&archive::pack(sources)?, &self.passphrase)?;
+        let size = sealed.len();
+        self.remote.put(&name, sealed).await?;
+
+        let mut removed = Vec::new();
+        for old in self.list().await?.into_iter().skip(self.keep.max(1)) {
+            match self.remote.delete(&old.name).await {
+                Ok(()) => removed.push(old.name),
+                Err(e) => tracing::warn!("Failed to delete old backup {}: {}", old.name, e),
+            }
+        }
+        Ok(BackupReport {
+            name,
+            size,
+            removed,
+        })
+    }
+
+    /// Backups on the remote, newest first. Other objects are ignored.
+    pub async fn list(&self) -> Result<Vec<BackupInfo>> {
+        let mut backups: Vec<BackupInfo> = self
+            .remote
+            .list()
+            .await?
+            .iter()
+            .filter_map(|name| BackupInfo::parse(name))
+            .collect();
+        backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+        Ok(backups)
+    }
+
+    /// Download, decrypt and unpack backup `name` (the newest when `None`)
+    /// into `targets`. Existing files are kept unless `overwrite` is set.
+    pub async fn restore(
+        &self,
+        name: Option<&str>,
+        targets: &Targets,
+        overwrite: bool,
+    ) -> Result<RestoreReport> {
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => self
+                .list()
+                .await?
+                .into_iter()
+                .next()
+                .map(|b| b.name)
+                .ok_or_else(|| BackupError::NotFound("no backups on the remote".into()))?,
+        };
+        let sealed = match self.remote.get(&name).await {
+            Err(BackupError::Remote { status: 404, .. }) => {
+                return Err(BackupError::NotFound(name))
+            }
+            other => other?,
+        };
+        let data = crypto::decrypt(&sealed, &self.passphrase)?;
+        let unpacked = archive::unpack(&data, targets, overwrite)?;
+        Ok(RestoreReport {
+            name,
+            restored: unpacked.restored,
+            skipped: unpacked.skipped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::config::BackupRemote;
+    use axum::body::Bytes;
+    use axum::extract::{Path, State};
+    use axum::http::{Method, StatusCode};
+    use axum::response::IntoResponse;
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    type Store = Arc<Mutex<BTreeMap<String, Vec<u8>>>>;
+
+    /// Just enough WebDAV for the client: MKCOL, PUT, GET, DELETE and a
+    /// depth-1 PROPFIND on `/dav/`.
+    async fn dav(
+        State(store): State<Store>,
+        method: Method,
+        path: Option<Path<String>>,
+        body: Bytes,
+    ) -> axum::response::Response {
+        let mut store = store.lock().unwrap();
+        match (method.as_str(), path) {
+            ("MKCOL", None) => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+            ("PROPFIND", None) => {
+                let mut xml = String::from(
+                    "<?xml version=\"1.0\"?><d:multistatus xmlns:d=\"DAV:\">\
+                     <d:response><d:href>/dav/</d:href></d:response>",
+                );
+                for name in store.keys() {
+                    xml.push_str(&format!(
+                        "<d:response><d:href>/dav/{name}</d:href></d:response>"
+                    ));
+                }
+                xml.push_str("</d:multistatus>");
+                (StatusCode::MULTI_STATUS, xml).into_response()
+            }
+            ("PUT", Some(Path(name))) => {
+                store.insert(name, body.to_vec());
+                StatusCode::CREATED.into_response()
+            }
+            ("GET", Some(Path(name))) => match store.get(&name) {
+                Some(data) => data.clone().into_response(),
+                None => StatusCode::NOT_FOUND.into_response(),
+            },
+            ("DELETE", Some(Path(name))) => match store.remove(&name) {
+                Some(_) => StatusCode::NO_CONTENT.into_response(),
+                None => StatusCode::NOT_FOUND.into_response(),
+            },
+            _ => StatusCode::BAD_REQUEST.into_response(),
+        }
+    }
+
+    async fn webdav(store: Store) -> Box<dyn Remote> {
+        let app = axum::Router::new()
+            .route("/dav/", axum::routing::any(dav))
+            .route("/dav/{name}", axum::routing::any(dav))
+            .with_state(store);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        remote::from_config(&BackupRemote::WebDav {
+            url: format!("http://{addr}/dav"),
+            username: Some("me".into()),
+            password: Some("pw".into()),
+            password_env: None,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_backup_rotate_and_restore_over_webdav() {
+        let store = Store::default();
+        store
+            .lock()
+            .unwrap()
+            .insert("notes.txt".into(), b"not a backup".to_vec());
+        let backups = Backups::new(webdav(store.clone()).await, "hunter2", 2);
+
+        let src = tempfile::tempdir().unwrap();
+        let sessions = src.path().join("sessions");
+        std::fs::create_dir_all(&sessions).unwrap();
+        let sources = Sources {
+            sessions_dir: Some(sessions.clone()),
+            skills_dir: None,
+            config: Some("[session]\nmax_history = 10\n".into()),
+        };
+        for hour in 1..=3 {
+            std::fs::write(sessions.join("s.json"), format!("version {hour}")).unwrap();
+            let at = Utc.with_ymd_and_hms(2026, 10, 1, hour, 0, 0).unwrap();
+            let report = backups.backup(&sources, at).await.unwrap();
+            assert_eq!(report.name, format!("agent-shell-20261001T0{hour}0000Z.backup"));
+        }
+
+        let listed = backups.list().await.unwrap();
+        let names: Vec<&str> = listed.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["agent-shell-20261001T030000Z.backup", "agent-shell-20261001T020000Z.backup"]
+        );
+        assert!(store.lock().unwrap().contains_key("notes.txt"));
+        let stored = store.lock().unwrap()[names[0]].clone();
+        assert!(!String::from_utf8_lossy(&stored).contains("version 3"));
+
+        let dest = tempfile::tempdir().unwrap();
+        let targets = Targets {
+            sessions_dir: Some(dest.path().join("sessions")),
+            skills_dir: None,
+            config_path: Some(dest.path().join("config.toml")),
+        };
+        let report = backups.restore(None, &targets, false).await.unwrap();
+        assert_eq!((report.restored, report.skipped), (2, 0));
+        assert_eq!(
+            std::fs::read_to_string(dest.path().join("sessions/s.json")).unwrap(),
+            "version 3"
+        );
+        let report = backups
+            .restore(Some(names[1]), &targets, false)
+            .await
+            .unwrap();
+        assert_eq!((report.restored, report.skipped), (0, 2));
+
+        let wrong = Backups::new(webdav(store.clone()).await, "wrong", 2);
+        assert!(matches!(
+            wrong.restore(None, &targets, true).await,
+            Err(BackupError::Decrypt)
+        ));
+        assert!(matches!(
+            backups.restore(Some("agent-shell-gone.backup"), &targets, true).await,
+            Err(BackupError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,170 @@
+//! Generate Rust types from the server's OpenAPI description.
+//!
+//! Every schema in `openapi.json` becomes a serde type in `types.rs` under
+//! `OUT_DIR`: string enums become enums, objects (and `allOf` compositions,
+//! which the server emits for flattened fields) become structs. Optional or
+//! nullable properties become `Option`s; free-form values become
+//! `serde_json::Value`.
+
+use serde_json::{Map, Value};
+use std::fmt::Write as _;
+
+const SPEC: &str = "openapi.json";
+
+fn main() {
+    println!("cargo:rerun-if-changed={SPEC}");
+    let spec: Value = serde_json::from_str(
+        &std::fs::read_to_string(SPEC).expect("openapi.json is readable"),
+    )
+    .expect("openapi.json is valid JSON");
+    let schemas = spec["components"]["schemas"]
+        .as_object()
+        .expect("openapi.json has component schemas");
+
+    let mut out = String::from("// Generated by build.rs from openapi.json. Do not edit.\n");
+    for (name, schema) in schemas {
+        out.push('\n');
+        if let Some(variants) = schema["enum"].as_array() {
+            write_enum(&mut out, name, schema, variants);
+        } else {
+            let mut properties = Vec::new();
+            collect_properties(schema, schemas, &mut properties);
+            write_struct(&mut out, name, schema, &properties);
+        }
+    }
+
+    let dest = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("types.rs");
+    std::fs::write(dest, out).expect("types.rs is writable");
+}
+
+/// A struct field: name, schema, and whether the schema requires it.
+type Property<'a> = (&'a str, &'a Value, bool);
+
+/// Properties of an object schema, following `allOf` references.
+fn collect_properties<'a>(
+    schema: &'a Value,
+    schemas: &'a Map<String, Value>,
+    out: &mut Vec<Property<'a>>,
+) {
+    if let Some(parts) = schema["allOf"].as_array() {
+        for part in parts {
+            match ref_name(part) {
+                Some(name) => collect_properties(&schemas[name], schemas, out),
+                None => collect_properties(part, schemas, out),
+            }
+        }
+        return;
+    }
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    if let Some(properties) = schema["properties"].as_object() {
+        for (name, property) in properties {
+            out.push((name, property, required.contains(&name.as_str())));
+        }
+    }
+}
+
+fn write_enum(out: &mut String, name: &str, schema: &Value, variants: &[Value]) {
+    write_doc(out, "", schema);
+    out.push_str(
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]\n",
+    );
+    let _ = writeln!(out, "pub enum {name} {{");
+    for variant in variants.iter().filter_map(Value::as_str) {
+        let _ = writeln!(out, "    #[serde(rename = \"{variant}\")]");
+        let _ = writeln!(out, "    {},", pascal_case(variant));
+    }
+    out.push_str("}\n");
+}
+
+fn write_struct(out: &mut String, name: &str, schema: &Value, properties: &[Property]) {
+    write_doc(out, "", schema);
+    out.push_str("#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]\n");
+    let _ = writeln!(out, "pub struct {name} {{");
+    for (field, property, required) in properties {
+        write_doc(out, "    ", property);
+        let (ty, nullable) = rust_type(property);
+        if *required && !nullable {
+            let _ = writeln!(out, "    pub {}: {ty},", field_name(field));
+        } else {
+            out.push_str("    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n");
+            let _ = writeln!(out, "    pub {}: Option<{ty}>,", field_name(field));
+        }
+    }
+    out.push_str("}\n");
+}
+
+/// The Rust type for a schema, and whether it admits `null`.
+fn rust_type(schema: &Value) -> (String, bool) {
+    if let Some(name) = ref_name(schema) {
+        return (name.to_string(), false);
+    }
+    if let Some(options) = schema["oneOf"].as_array() {
+        let nullable = options.iter().any(|o| o["type"] == "null");
+        let mut types = options.iter().filter(|o| o["type"] != "null").map(rust_type);
+        return match (types.next(), types.next()) {
+            (Some((ty, inner)), None) => (ty, nullable || inner),
+            _ => ("serde_json::Value".into(), nullable),
+        };
+    }
+    let (kind, nullable) = match &schema["type"] {
+        Value::String(kind) => (kind.as_str(), false),
+        Value::Array(kinds) => {
+            let nullable = kinds.iter().any(|k| k == "null");
+            let kind = kinds.iter().filter_map(Value::as_str).find(|k| *k != "null");
+            (kind.unwrap_or(""), nullable)
+        }
+        _ => ("", false),
+    };
+    let unsigned = schema["minimum"].as_f64().is_some_and(|m| m >= 0.0);
+    let ty = match (kind, schema["format"].as_str()) {
+        ("string", _) => "String".to_string(),
+        ("boolean", _) => "bool".to_string(),
+        ("integer", Some("int32")) if unsigned => "u32".to_string(),
+        ("integer", Some("int32")) => "i32".to_string(),
+        ("integer", _) if unsigned => "u64".to_string(),
+        ("integer", _) => "i64".to_string(),
+        ("number", _) => "f64".to_string(),
+        ("array", _) => format!("Vec<{}>", rust_type(&schema["items"]).0),
+        _ => "serde_json::Value".to_string(),
+    };
+    (ty, nullable)
+}
+
+fn ref_name(schema: &Value) -> Option<&str> {
+    schema["$ref"].as_str()?.strip_prefix("#/components/schemas/")
+}
+
+fn write_doc(out: &mut String, indent: &str, schema: &Value) {
+    let Some(description) = schema["description"].as_str() else {
+        return;
+    };
+    for line in description.lines() {
+        // Square brackets would be read as intra-doc links.
+        let line = line.replace('[', "\\[").replace(']', "\\]");
+        let _ = writeln!(out, "{indent}/// {line}");
+    }
+}
+
+fn field_name(name: &str) -> String {
+    match name {
+        "type" | "struct" | "enum" | "fn" | "mod" | "ref" | "use" | "where" => {
+            format!("r#{name}")
+        }
+        _ => name.to_string(),
+    }
+}
+
+fn pascal_case(value: &str) -> String {
+    value
+        .split(['_', '-', ' '])
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
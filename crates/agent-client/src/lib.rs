@@ -0,0 +1,354 @@
+//! Client for the agent-shell HTTP API (`agent-shell serve`).
+//!
+//! The request and response types in [`types`] are generated at build time
+//! from `openapi.json`, the server's OpenAPI description (also served at
+//! `/openapi.json`), so they follow the API without hand-written structs.
+//! [`Client`] wraps the common calls; [`Client::get`] and [`Client::post`]
+//! reach the rest.
+//!
+//! ```no_run
+//! use agent_client::{types, Client};
+//!
+//! # async fn demo() -> Result<(), agent_client::Error> {
+//! let client = Client::new("http://localhost:8080").with_token("secret");
+//! let run = client
+//!     .run(&types::RunRequest {
+//!         task: "run the tests and summarize failures".into(),
+//!         agent: None,
+//!         name: None,
+//!         stream: None,
+//!     })
+//!     .await?;
+//! println!("{}", run.reply.choices[0].message.content);
+//! let usage = client.session_usage(&run.session_id).await?;
+//! println!("{} tokens", usage.total_tokens);
+//! # Ok(())
+//! # }
+//! ```
+
+mod sse;
+
+pub use sse::{Event, EventStream};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Types generated from `openapi.json`.
+pub mod types {
+    #![allow(clippy::all)]
+    include!(concat!(env!("OUT_DIR"), "/types.rs"));
+}
+
+/// Header carrying the ID of the session a run was recorded in.
+const SESSION_ID_HEADER: &str = "x-session-id";
+
+/// Header carrying the ID a streamed turn can be resumed under.
+const RUN_ID_HEADER: &str = "x-run-id";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The server answered with problem details; branch on `code`.
+    #[error("{} ({:?}): {}", .0.status, .0.code, .0.detail)]
+    Api(Box<types::Problem>),
+
+    /// An error status without a problem-details body (e.g. from a proxy).
+    #[error("HTTP {status}: {body}")]
+    Status { status: u16, body: String },
+
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+}
+
+impl Error {
+    /// The server's error code, when it sent one.
+    pub fn code(&self) -> Option<types::ErrorCode> {
+        match self {
+            Self::Api(problem) => Some(problem.code),
+            _ => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A completed run.
+#[derive(Debug, Clone)]
+pub struct RunReply {
+    /// The session the run was recorded in.
+    pub session_id: String,
+    pub reply: types::ChatResponse,
+}
+
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl Client {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    /// Send `token` as the bearer token (`server.auth_token`).
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Use a preconfigured HTTP client, e.g. with a timeout or proxy.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.http.request(method, format!("{}{}", self.base_url, path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// GET `path` and decode the JSON response.
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = send(self.request(reqwest::Method::GET, path)).await?;
+        Ok(response.json().await?)
+    }
+
+    /// POST `body` as JSON to `path` and decode the JSON response.
+    pub async fn post<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        let response = send(self.request(reqwest::Method::POST, path).json(body)).await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn health(&self) -> Result<serde_json::Value> {
+        self.get("/health").await
+    }
+
+    /// Send a chat turn and wait for the whole reply. `stream` is ignored;
+    /// use [`Client::chat_stream`] for a streamed reply.
+    pub async fn chat(&self, request: &types::ChatRequest) -> Result<types::ChatResponse> {
+        let request = types::ChatRequest {
+            stream: Some(false),
+            ..request.clone()
+        };
+        self.post("/v1/chat/completions", &request).await
+    }
+
+    /// Send a chat turn and stream the reply as server-sent events.
+    pub async fn chat_stream(&self, request: &types::ChatRequest) -> Result<EventStream> {
+        let request = types::ChatRequest {
+            stream: Some(true),
+            ..request.clone()
+        };
+        let response =
+            send(self.request(reqwest::Method::POST, "/v1/chat/completions").json(&request))
+                .await?;
+        Ok(EventStream::new(response, header(RUN_ID_HEADER)))
+    }
+
+    /// Run a task in a new session and wait for the reply.
+    pub async fn run(&self, request: &types::RunRequest) -> Result<RunReply> {
+        let request = types::RunRequest {
+            stream: Some(false),
+            ..request.clone()
+        };
+        let response =
+            send(self.request(reqwest::Method::POST, "/v1/runs").json(&request)).await?;
+        let session_id = header(SESSION_ID_HEADER)(&response)
+            .ok_or_else(|| Error::InvalidResponse(format!("missing {SESSION_ID_HEADER}")))?;
+        Ok(RunReply {
+            session_id,
+            reply: response.json().await?,
+        })
+    }
+
+    /// Resume a streamed turn after event `after` (0 replays it all).
+    pub async fn resume(&self, run_id: &str, after: u64) -> Result<EventStream> {
+        let path = format!("/v1/runs/{}/events?after={}", run_id, after);
+        let response = send(self.request(reqwest::Method::GET, &path)).await?;
+        let mut events = EventStream::new(response, |_| None);
+        events.run_id = Some(run_id.to_string());
+        Ok(events)
+    }
+
+    pub async fn list_sessions(&self) -> Result<Vec<types::SessionInfo>> {
+        self.get("/v1/sessions").await
+    }
+
+    pub async fn create_session(
+        &self,
+        request: &types::CreateSessionRequest,
+    ) -> Result<types::SessionCreated> {
+        self.post("/v1/sessions", request).await
+    }
+
+    pub async fn fork_session(
+        &self,
+        id: &str,
+        request: &types::ForkSessionRequest,
+    ) -> Result<types::SessionCreated> {
+        self.post(&format!("/v1/sessions/{}/fork", id), request).await
+    }
+
+    pub async fn session_messages(&self, id: &str) -> Result<Vec<types::MessageResponse>> {
+        self.get(&format!("/v1/sessions/{}/messages", id)).await
+    }
+
+    pub async fn session_usage(&self, id: &str) -> Result<types::SessionUsageResponse> {
+        self.get(&format!("/v1/sessions/{}/usage", id)).await
+    }
+
+    pub async fn list_checkpoints(&self, id: &str) -> Result<Vec<types::CheckpointInfo>> {
+        self.get(&format!("/v1/sessions/{}/checkpoints", id)).await
+    }
+
+    pub async fn rollback(
+        &self,
+        id: &str,
+        request: &types::RollbackRequest,
+    ) -> Result<types::Rollback> {
+        self.post(&format!("/v1/sessions/{}/rollback", id), request).await
+    }
+
+    pub async fn list_agents(&self) -> Result<Vec<types::AgentInfo>> {
+        self.get("/v1/agents").await
+    }
+
+    pub async fn config(&self) -> Result<types::ConfigResponse> {
+        self.get("/v1/config").await
+    }
+}
+
+/// Send a request, turning error statuses into [`Error`]s.
+async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let response = request.send().await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(match serde_json::from_str::<types::Problem>(&body) {
+        Ok(problem) => Error::Api(Box::new(problem)),
+        Err(_) => Error::Status {
+            status: status.as_u16(),
+            body,
+        },
+    })
+}
+
+fn header(name: &'static str) -> impl Fn(&reqwest::Response) -> Option<String> {
+    move |response| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::response::IntoResponse;
+    use axum::routing::{get, post};
+    use futures::StreamExt;
+
+    async fn serve(app: axum::Router) -> Client {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        Client::new(format!("http://{}", addr)).with_token("secret")
+    }
+
+    #[tokio::test]
+    async fn test_decodes_responses_and_problems() {
+        let app = axum::Router::new()
+            .route(
+                "/v1/sessions/{id}/usage",
+                get(|headers: HeaderMap| async move {
+                    assert_eq!(headers["authorization"], "Bearer secret");
+                    axum::Json(serde_json::json!({
+                        "prompt_tokens": 1000, "completion_tokens": 500, "cost_usd": 0.01,
+                        "estimated": false, "total_tokens": 1500, "budget_usd": null,
+                    }))
+                }),
+            )
+            .route(
+                "/v1/sessions",
+                post(|| async {
+                    (
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        [("content-type", "application/problem+json")],
+                        r#"{"type":"about:blank","title":"Unprocessable Entity","status":422,
+                            "code":"validation_failed","detail":"bad body",
+                            "details":{"errors":["body.name: is required"]}}"#,
+                    )
+                        .into_response()
+                }),
+            );
+        let client = serve(app).await;
+
+        let usage = client.session_usage("abc").await.unwrap();
+        assert_eq!((usage.total_tokens, usage.prompt_tokens), (1500, 1000));
+        assert_eq!(usage.budget_usd, None);
+
+        let err = client
+            .create_session(&types::CreateSessionRequest {
+                name: String::new(),
+                agent: None,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Some(types::ErrorCode::ValidationFailed));
+        let Error::Api(problem) = err else { unreachable!() };
+        assert_eq!(problem.details.unwrap()["errors"][0], "body.name: is required");
+    }
+
+    #[tokio::test]
+    async fn test_streams_events() {
+        let app = axum::Router::new().route(
+            "/v1/chat/completions",
+            post(|| async {
+                (
+                    [("content-type", "text/event-stream"), ("x-run-id", "r1")],
+                    "id: 1\ndata: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n\
+                     id: 2\nevent: usage\ndata: {\"total_tokens\":3}\n\n\
+                     : ping\n\nid: 3\ndata: [DONE]\n\n",
+                )
+            }),
+        );
+        let client = serve(app).await;
+        let request = types::ChatRequest {
+            messages: vec![types::ChatMessage {
+                role: "user".into(),
+                content: "hello".into(),
+                images: None,
+            }],
+            session_id: None,
+            stream: None,
+        };
+        let stream = client.chat_stream(&request).await.unwrap();
+        assert_eq!(stream.run_id.as_deref(), Some("r1"));
+        let events: Vec<Event> = stream.map(|e| e.unwrap()).collect().await;
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].content().as_deref(), Some("Hi"));
+        assert_eq!(events[1].event.as_deref(), Some("usage"));
+        assert_eq!(events[2].id, Some(3));
+        assert!(events[2].is_done());
+    }
+}
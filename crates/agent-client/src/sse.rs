@@ -0,0 +1,135 @@
+//! Server-sent events from streamed turns.
+
+use crate::{Error, Result};
+use futures::stream::{BoxStream, Stream, StreamExt};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// One server-sent event.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Event {
+    /// Sequence number; pass the last one seen to [`crate::Client::resume`].
+    pub id: Option<u64>,
+    /// Event name; `None` for chat completion chunks.
+    pub event: Option<String>,
+    pub data: String,
+}
+
+impl Event {
+    /// Whether this is the `[DONE]` marker that ends a turn.
+    pub fn is_done(&self) -> bool {
+        self.data == "[DONE]"
+    }
+
+    /// Decode `data` as JSON.
+    pub fn json(&self) -> Result<serde_json::Value> {
+        serde_json::from_str(&self.data).map_err(|e| Error::InvalidResponse(e.to_string()))
+    }
+
+    /// The text delta of a chat completion chunk.
+    pub fn content(&self) -> Option<String> {
+        if self.event.is_some() || self.is_done() {
+            return None;
+        }
+        let chunk = self.json().ok()?;
+        chunk["choices"][0]["delta"]["content"]
+            .as_str()
+            .map(String::from)
+    }
+}
+
+/// Events of a streamed turn, in order.
+pub struct EventStream {
+    /// ID the turn can be resumed under, when the server sent one.
+    pub run_id: Option<String>,
+    bytes: BoxStream<'static, reqwest::Result<Vec<u8>>>,
+    buffer: String,
+    ready: VecDeque<Event>,
+    finished: bool,
+}
+
+impl EventStream {
+    pub(crate) fn new(
+        response: reqwest::Response,
+        run_id: impl Fn(&reqwest::Response) -> Option<String>,
+    ) -> Self {
+        Self {
+            run_id: run_id(&response),
+            bytes: response
+                .bytes_stream()
+                .map(|chunk| chunk.map(|bytes| bytes.to_vec()))
+                .boxed(),
+            buffer: String::new(),
+            ready: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    /// Move every complete event in the buffer to `ready`.
+    fn parse_buffer(&mut self) {
+        let normalized = self.buffer.replace("\r\n", "\n");
+        let mut rest = normalized.as_str();
+        while let Some(end) = rest.find("\n\n") {
+            if let Some(event) = parse_event(&rest[..end]) {
+                self.ready.push_back(event);
+            }
+            rest = &rest[end + 2..];
+        }
+        self.buffer = rest.to_string();
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<Event>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(event) = self.ready.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            if self.finished {
+                return Poll::Ready(None);
+            }
+            match self.bytes.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    self.buffer.push_str(&String::from_utf8_lossy(&chunk));
+                    self.parse_buffer();
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    self.finished = true;
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+                Poll::Ready(None) => {
+                    self.finished = true;
+                    let rest = std::mem::take(&mut self.buffer);
+                    if let Some(event) = parse_event(rest.trim_end()) {
+                        self.ready.push_back(event);
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Parse one event block; comments-only blocks (keep-alives) yield `None`.
+fn parse_event(block: &str) -> Option<Event> {
+    let mut event = Event::default();
+    let mut data = Vec::new();
+    for line in block.lines() {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "id" => event.id = value.parse().ok(),
+            "event" => event.event = Some(value.to_string()),
+            "data" => data.push(value),
+            _ => {}
+        }
+    }
+    if data.is_empty() {
+        return None;
+    }
+    event.data = data.join("\n");
+    Some(event)
+}
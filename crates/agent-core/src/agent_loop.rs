@@ -1,25 +1,45 @@
-use crate::config::AppConfig;
+use crate::agents::AgentPreset;
+use crate::checkpoint::FileSnapshot;
+use crate::config::{AppConfig, NetworkConfig};
+use crate::context::ContextLinker;
+use crate::environments;
 use crate::error::AgentError;
+use crate::hooks::{HookAction, HookRegistry, LlmResponse};
+use crate::middleware::{Endpoint, ProviderRequest, ProviderResponse};
+use crate::mock_provider::MockScenario;
 use crate::provider::{ProviderChain, RequestError, ResolvedProvider};
+use crate::tool_call_parser::{self, ToolCallAssembler};
 use crate::tool_loop::ToolLoopConfig;
 use crate::tool_registry::ToolRegistry;
-use crate::types::{AgentEvent, Message, Role, ToolCall, ToolOutput, ToolSchema};
+use crate::tool_validation;
+use crate::types::{
+    AgentEvent, ImagePart, Message, Role, TokenUsage, ToolCall, ToolOutput, ToolSchema,
+};
+use crate::workspace;
 
 use async_openai::config::OpenAIConfig;
 use async_openai::types::{
     ChatCompletionMessageToolCall, ChatCompletionRequestAssistantMessageArgs,
     ChatCompletionRequestMessage, ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestMessageContentPartImage, ChatCompletionRequestMessageContentPartText,
     ChatCompletionRequestToolMessageArgs, ChatCompletionRequestUserMessageArgs,
-    ChatCompletionToolArgs, ChatCompletionToolType, CreateChatCompletionRequestArgs,
-    FunctionObjectArgs,
+    ChatCompletionRequestUserMessageContent, ChatCompletionRequestUserMessageContentPart,
+    ChatCompletionStreamOptions, ChatCompletionToolArgs, ChatCompletionToolType,
+    CreateChatCompletionRequestArgs, FunctionObjectArgs,
 };
 use async_openai::Client;
 use futures::StreamExt;
+use reqwest::header::HeaderMap;
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio::task::JoinSet;
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
+
+/// Text of the user message that carries images returned by tools, since
+/// tool messages themselves can only hold text.
+const TOOL_IMAGES_NOTE: &str = "Images returned by the tool calls above:";
 
 /// Result of a single agent turn, containing the final response and all
 /// intermediate messages (assistant tool-call messages + tool result messages)
@@ -31,6 +51,24 @@ pub struct AgentTurnResult {
     /// - Tool result messages
     /// - The final assistant text response (last element)
     pub messages: Vec<Message>,
+    /// Files tools modified during the turn, as they were beforehand.
+    pub snapshots: Vec<FileSnapshot>,
+    /// Tokens used by every model call in the turn.
+    pub usage: TokenUsage,
+    /// Calls each tool ran in the turn, by registered name, to add to
+    /// [`Session::tool_calls`](crate::session::Session::tool_calls).
+    pub tool_calls: HashMap<String, u32>,
+}
+
+/// Result of a single tool call run outside a turn (see
+/// [`AgentLoop::run_tool_call`]).
+#[derive(Debug, Clone)]
+pub struct ToolCallResult {
+    pub output: ToolOutput,
+    /// Files the call modified, as they were beforehand.
+    pub snapshots: Vec<FileSnapshot>,
+    /// The call, by registered name, if it ran.
+    pub tool_calls: HashMap<String, u32>,
 }
 
 impl AgentTurnResult {
@@ -50,6 +88,7 @@ pub struct AgentLoop {
     config: AppConfig,
     tool_registry: Arc<ToolRegistry>,
     loop_config: ToolLoopConfig,
+    hooks: HookRegistry,
 }
 
 impl AgentLoop {
@@ -57,11 +96,13 @@ impl AgentLoop {
     pub fn new(config: AppConfig, tool_registry: Arc<ToolRegistry>) -> Result<Self, AgentError> {
         let provider_chain = ProviderChain::from_config(&config)?;
         let loop_config = ToolLoopConfig::default();
+        let hooks = HookRegistry::from_config(&config.hooks);
         Ok(Self {
             provider_chain,
             config,
             tool_registry,
             loop_config,
+            hooks,
         })
     }
 
@@ -73,43 +114,189 @@ impl AgentLoop {
     ) -> Result<Self, AgentError> {
         loop_config.validate()?;
         let provider_chain = ProviderChain::from_config(&config)?;
+        let hooks = HookRegistry::from_config(&config.hooks);
         Ok(Self {
             provider_chain,
             config,
             tool_registry,
             loop_config,
+            hooks,
         })
     }
 
+    /// Providers and their middleware, e.g. to [`layer`](ProviderChain::layer)
+    /// an extension's middleware.
+    pub fn provider_chain_mut(&mut self) -> &mut ProviderChain {
+        &mut self.provider_chain
+    }
+
+    /// Hooks applied to every turn; starts with the `[[hooks]]` commands.
+    pub fn hooks_mut(&mut self) -> &mut HookRegistry {
+        &mut self.hooks
+    }
+
+    /// Project context appended to the system prompt: the project's notes
+    /// file and detected environments for the project containing the
+    /// primary workspace root (or the current directory). Re-read every
+    /// turn so `memory_write` additions apply.
+    fn project_context(&self) -> Vec<String> {
+        let (memory, context) = (&self.config.memory, &self.config.context);
+        if !memory.enabled && !context.environments {
+            return Vec::new();
+        }
+        let dir = match workspace::primary_root(&self.config.sandbox.workspace_root) {
+            Some(root) => root,
+            None => match std::env::current_dir() {
+                Ok(dir) => dir,
+                Err(_) => return Vec::new(),
+            },
+        };
+        let mut linker = ContextLinker::new();
+        let mut sections = Vec::new();
+        if memory.enabled {
+            if let Some(notes) =
+                linker.load_project_notes(&dir, &memory.file_name, memory.max_bytes)
+            {
+                sections.push(notes.prompt_section());
+            }
+        }
+        if context.environments {
+            let root = linker
+                .detect_project(&dir)
+                .map(|p| p.path.clone())
+                .unwrap_or(dir);
+            sections.extend(environments::prompt_section(&environments::detect_environments(
+                &root,
+            )));
+        }
+        sections
+    }
+
     /// Run the agent for a single user turn. Takes the full message history and
     /// returns all generated messages (assistant messages with tool calls, tool
     /// result messages, and the final assistant response).
     ///
     /// Uses true SSE streaming — content chunks are emitted as they arrive from
     /// the LLM, rather than buffering the entire response.
+    ///
+    /// `session_tool_calls` are the session's earlier calls by tool
+    /// ([`Session::tool_calls`](crate::session::Session::tool_calls)),
+    /// counted against `[tools.quotas]`.
     pub async fn run(
         &self,
         messages: &[Message],
         session_tool_allowlist: Option<&[String]>,
         session_tool_denylist: &[String],
+        session_tool_calls: &HashMap<String, u32>,
+        event_tx: mpsc::UnboundedSender<AgentEvent>,
+    ) -> Result<AgentTurnResult, AgentError> {
+        self.run_as(
+            None,
+            messages,
+            session_tool_allowlist,
+            session_tool_denylist,
+            session_tool_calls,
+            event_tx,
+        )
+        .await
+    }
+
+    /// Like [`run`](Self::run), with an agent preset's system prompt, model,
+    /// temperature and tool allowlist (if any) applied on top of the session's.
+    pub async fn run_as(
+        &self,
+        preset: Option<&AgentPreset>,
+        messages: &[Message],
+        session_tool_allowlist: Option<&[String]>,
+        session_tool_denylist: &[String],
+        session_tool_calls: &HashMap<String, u32>,
         event_tx: mpsc::UnboundedSender<AgentEvent>,
     ) -> Result<AgentTurnResult, AgentError> {
+        let result = self
+            .run_turn(
+                preset,
+                messages,
+                session_tool_allowlist,
+                session_tool_denylist,
+                session_tool_calls,
+                event_tx,
+            )
+            .await;
+        if let Err(e) = &result {
+            self.hooks.on_error(e).await;
+        }
+        result
+    }
+
+    async fn run_turn(
+        &self,
+        preset: Option<&AgentPreset>,
+        messages: &[Message],
+        session_tool_allowlist: Option<&[String]>,
+        session_tool_denylist: &[String],
+        session_tool_calls: &HashMap<String, u32>,
+        event_tx: mpsc::UnboundedSender<AgentEvent>,
+    ) -> Result<AgentTurnResult, AgentError> {
+        // A preset's tool list narrows the session allowlist. Either may
+        // name a tool by ID or alias.
+        let registry = &self.tool_registry;
+        let same_tool = |a: &String, b: &String| {
+            a == b || registry.resolve(a).is_some_and(|a| registry.resolve(b) == Some(a))
+        };
+        let allowlist: Option<Vec<String>> =
+            match (preset.and_then(|p| p.tools.as_ref()), session_tool_allowlist) {
+                (Some(tools), Some(allow)) => Some(
+                    tools
+                        .iter()
+                        .filter(|t| allow.iter().any(|a| same_tool(t, a)))
+                        .cloned()
+                        .collect(),
+                ),
+                (Some(tools), None) => Some(tools.clone()),
+                (None, allow) => allow.map(<[String]>::to_vec),
+            };
         let tool_schemas = self
             .tool_registry
-            .schemas(session_tool_allowlist, session_tool_denylist);
+            .schemas(allowlist.as_deref(), session_tool_denylist);
 
         // Build OpenAI tool definitions once (they don't change between iterations).
         let openai_tools = build_chat_tools(&tool_schemas)?;
 
         // Build the set of allowed tool names for runtime policy enforcement.
         let allowed_tools: HashSet<String> = tool_schemas.iter().map(|s| s.name.clone()).collect();
+        let schemas_by_name: HashMap<&str, &serde_json::Value> = tool_schemas
+            .iter()
+            .map(|s| (s.name.as_str(), &s.parameters))
+            .collect();
+        // Schema validation failures per tool, against `tools.argument_retries`.
+        let mut argument_failures: HashMap<String, usize> = HashMap::new();
+        // Calls that ran this turn, counted against quotas with the session's.
+        let mut turn_calls: HashMap<String, u32> = HashMap::new();
 
         // Build the running message list (we'll extend it with tool results).
-        let mut running_messages = self.build_openai_messages(messages)?;
+        let system_prompt = preset
+            .and_then(|p| p.system_prompt.as_deref())
+            .or(self.config.system_prompt.as_deref());
+        let system_prompt = {
+            let sections: Vec<String> = system_prompt
+                .map(String::from)
+                .into_iter()
+                .chain(self.project_context())
+                .collect();
+            (!sections.is_empty()).then(|| sections.join("\n\n"))
+        };
+        let mut messages = messages.to_vec();
+        self.hooks.pre_prompt(&mut messages).await?;
+        let mut running_messages = build_openai_messages(&messages, system_prompt.as_deref())?;
+        let model_override = preset.and_then(|p| p.model.clone());
+        let temperature_override = preset.and_then(|p| p.temperature);
         let mut iteration = 0;
         let loop_start = std::time::Instant::now();
         // Track all messages generated during this turn for session persistence.
         let mut turn_messages: Vec<Message> = Vec::new();
+        // Files as they were before the first tool call that modified them.
+        let mut snapshots: Vec<FileSnapshot> = Vec::new();
+        let mut usage = TokenUsage::default();
 
         loop {
             iteration += 1;
@@ -142,7 +329,13 @@ impl AgentLoop {
             let event_tx_clone = event_tx.clone();
             let streamed = self
                 .provider_chain
-                .request_with_failover(None, |provider| {
+                .request_with_failover(None, |mut provider| {
+                    if let Some(model) = &model_override {
+                        provider.model = model.clone();
+                    }
+                    if let Some(temperature) = temperature_override {
+                        provider.temperature = temperature;
+                    }
                     let msgs = msgs_snapshot.clone();
                     let tools = tools_snapshot.clone();
                     let etx = event_tx_clone.clone();
@@ -151,9 +344,29 @@ impl AgentLoop {
                     }
                 })
                 .await?;
+            if let Some(call_usage) = streamed.usage {
+                usage += call_usage;
+                let _ = event_tx.send(AgentEvent::Usage(call_usage));
+            }
 
-            let content = streamed.content;
-            let mut tool_calls = streamed.tool_calls;
+            let mut response = LlmResponse {
+                content: streamed.content,
+                tool_calls: streamed.tool_calls,
+            };
+            // Models without native tool calling write the call as text.
+            if response.tool_calls.is_empty() {
+                if let Some((rest, calls)) = tool_call_parser::extract_from_content(
+                    &response.content,
+                    &|name| allowed_tools.contains(name),
+                ) {
+                    debug!("Recovered {} tool call(s) from message content", calls.len());
+                    response.content = rest;
+                    response.tool_calls = calls;
+                }
+            }
+            self.hooks.post_llm(&mut response).await;
+            let content = response.content;
+            let mut tool_calls = response.tool_calls;
 
             // Enforce per-turn tool call limit.
             if tool_calls.len() > self.loop_config.max_tool_calls_per_turn {
@@ -167,6 +380,14 @@ impl AgentLoop {
 
             // Check for tool calls.
             if !tool_calls.is_empty() {
+                // Hooks may rewrite arguments before they are recorded, or veto calls.
+                let mut vetoes: HashMap<usize, String> = HashMap::new();
+                for (idx, tc) in tool_calls.iter_mut().enumerate() {
+                    if let HookAction::Veto(reason) = self.hooks.pre_tool(tc).await {
+                        vetoes.insert(idx, reason);
+                    }
+                }
+
                 // Add assistant's message with tool calls to running history.
                 let tc_openai: Vec<ChatCompletionMessageToolCall> = tool_calls
                     .iter()
@@ -196,45 +417,116 @@ impl AgentLoop {
 
                 // Execute tool calls concurrently for reduced latency.
                 let mut join_set = JoinSet::new();
+                let mut exhausted: Option<String> = None;
                 let mut immediate_outputs: Vec<(usize, ToolOutput)> = Vec::new();
 
                 for (idx, tc) in tool_calls.iter().enumerate() {
                     let _ = event_tx.send(AgentEvent::ToolCallStart {
                         id: tc.id.clone(),
                         name: tc.name.clone(),
+                        arguments: tc.arguments.clone(),
                     });
 
+                    if let Some(reason) = vetoes.remove(&idx) {
+                        immediate_outputs.push((idx, ToolOutput {
+                            tool_call_id: tc.id.clone(),
+                            content: reason,
+                            is_error: true,
+                            duration_ms: None,
+                            images: Vec::new(),
+                        }));
+                        continue;
+                    }
+
                     // Policy enforcement: reject tools not in the allowed set.
                     if !allowed_tools.contains(&tc.name) {
                         immediate_outputs.push((idx, ToolOutput {
                             tool_call_id: tc.id.clone(),
                             content: format!("Tool not allowed: {}", tc.name),
                             is_error: true,
+                            duration_ms: None,
+                            images: Vec::new(),
                         }));
                         continue;
                     }
 
-                    // Parse arguments, returning an error to the model on failure.
-                    let args: serde_json::Value = match serde_json::from_str(&tc.arguments) {
+                    // Parse and validate arguments against the tool's schema. The
+                    // model is told exactly what is wrong and may retry a few
+                    // times before the turn ends with the error.
+                    let schema = schemas_by_name.get(tc.name.as_str()).copied();
+                    let parsed = serde_json::from_str::<serde_json::Value>(&tc.arguments)
+                        .map_err(|e| vec![format!("arguments: invalid JSON: {}", e)])
+                        .and_then(|args| {
+                            let issues = schema
+                                .map(|s| tool_validation::validate_arguments(s, &args))
+                                .unwrap_or_default();
+                            if issues.is_empty() { Ok(args) } else { Err(issues) }
+                        });
+                    let args = match parsed {
                         Ok(v) => v,
-                        Err(e) => {
+                        Err(issues) => {
+                            let failures = argument_failures.entry(tc.name.clone()).or_default();
+                            *failures += 1;
+                            let max_attempts = self.config.tools.argument_retries + 1;
+                            let content = if *failures < max_attempts {
+                                tool_validation::retry_feedback(
+                                    &tc.name,
+                                    &issues,
+                                    schema.unwrap_or(&serde_json::Value::Null),
+                                    *failures,
+                                    max_attempts,
+                                )
+                            } else {
+                                let content = format!(
+                                    "Invalid arguments for `{}`:\n- {}",
+                                    tc.name,
+                                    issues.join("\n- ")
+                                );
+                                exhausted.get_or_insert_with(|| {
+                                    format!(
+                                        "[Stopped: `{}` was called with invalid arguments {} time(s)]\n{}",
+                                        tc.name, failures, content
+                                    )
+                                });
+                                content
+                            };
                             immediate_outputs.push((idx, ToolOutput {
                                 tool_call_id: tc.id.clone(),
-                                content: format!("Invalid JSON arguments: {}", e),
+                                content,
                                 is_error: true,
+                                duration_ms: None,
+                                images: Vec::new(),
                             }));
                             continue;
                         }
                     };
 
-                    // Spawn concurrent tool execution.
+                    let admitted = self.admit(
+                        &tc.name,
+                        &args,
+                        session_tool_calls,
+                        &mut turn_calls,
+                        &mut snapshots,
+                    );
+                    if let Err(reason) = admitted {
+                        immediate_outputs.push((idx, ToolOutput {
+                            tool_call_id: tc.id.clone(),
+                            content: reason,
+                            is_error: true,
+                            duration_ms: None,
+                            images: Vec::new(),
+                        }));
+                        continue;
+                    }
+
+                    // Spawn concurrent tool execution, in this turn's workspaces.
                     let registry = self.tool_registry.clone();
                     let name = tc.name.clone();
                     let id = tc.id.clone();
-                    join_set.spawn(async move {
+                    join_set.spawn(workspace::scope(workspace::active_roots(), async move {
                         let output = registry.execute(&name, &id, args).await;
                         (idx, output)
-                    });
+                    }));
                 }
 
                 // Collect all results, maintaining original order for deterministic
@@ -250,14 +542,26 @@ impl AgentLoop {
                 }
                 indexed_outputs.sort_by_key(|(idx, _)| *idx);
 
-                for (_, output) in indexed_outputs {
+                let mut tool_images: Vec<ImagePart> = Vec::new();
+                for (idx, mut output) in indexed_outputs {
+                    self.hooks.post_tool(&tool_calls[idx], &mut output).await;
+                    // Audit trail of tool calls; a caller's span (e.g. the
+                    // server's run span) adds its correlation ID.
+                    info!(
+                        tool = %tool_calls[idx].name,
+                        call_id = %output.tool_call_id,
+                        is_error = output.is_error,
+                        duration_ms = output.duration_ms,
+                        "Tool call finished"
+                    );
                     let _ = event_tx.send(AgentEvent::ToolResult(output.clone()));
 
                     // Track tool result for session persistence.
-                    turn_messages.push(Message::tool_result(
-                        &output.tool_call_id,
-                        &output.content,
-                    ));
+                    turn_messages.push(
+                        Message::tool_result(&output.tool_call_id, &output.content)
+                            .with_images(output.images.clone()),
+                    );
+                    tool_images.extend(output.images);
 
                     let tool_msg = ChatCompletionRequestToolMessageArgs::default()
                         .tool_call_id(&output.tool_call_id)
@@ -266,6 +570,23 @@ impl AgentLoop {
                         .expect("tool message build should not fail");
                     running_messages.push(ChatCompletionRequestMessage::Tool(tool_msg));
                 }
+                if !tool_images.is_empty() {
+                    running_messages.push(user_message(TOOL_IMAGES_NOTE, &tool_images)?);
+                }
+
+                // Out of argument retries: surface the failure instead of
+                // letting the model keep guessing.
+                if let Some(reason) = exhausted {
+                    let message = Message::assistant(&reason);
+                    let _ = event_tx.send(AgentEvent::Done(message.clone()));
+                    turn_messages.push(message);
+                    return Ok(AgentTurnResult {
+                        messages: turn_messages,
+                        snapshots,
+                        usage,
+                        tool_calls: turn_calls,
+                    });
+                }
 
                 // Continue the loop — the model needs to process tool results.
                 continue;
@@ -276,86 +597,264 @@ impl AgentLoop {
             let final_message = Message::assistant(&content);
             let _ = event_tx.send(AgentEvent::Done(final_message.clone()));
             turn_messages.push(final_message);
-            return Ok(AgentTurnResult { messages: turn_messages });
+            return Ok(AgentTurnResult {
+                messages: turn_messages,
+                snapshots,
+                usage,
+                tool_calls: turn_calls,
+            });
         }
 
         // If we hit max iterations, return whatever we have.
         let fallback = Message::assistant("[Agent reached maximum tool iterations]");
         let _ = event_tx.send(AgentEvent::Done(fallback.clone()));
         turn_messages.push(fallback);
-        Ok(AgentTurnResult { messages: turn_messages })
+        Ok(AgentTurnResult {
+            messages: turn_messages,
+            snapshots,
+            usage,
+            tool_calls: turn_calls,
+        })
     }
 
-    /// Convert our Message types to async-openai request messages.
-    fn build_openai_messages(
+    /// Run one tool call outside a model turn, such as a re-run from the
+    /// UI, through the same gate as calls the model makes: the session's
+    /// tool policy, `pre_tool` hooks, argument validation, quotas and
+    /// snapshots, then `post_tool` hooks. `session_tool_calls` are the
+    /// session's earlier calls, for per-session quotas. Runs in the
+    /// caller's workspace scope; the snapshots and call belong to the
+    /// session.
+    pub async fn run_tool_call(
         &self,
-        messages: &[Message],
-    ) -> Result<Vec<ChatCompletionRequestMessage>, AgentError> {
-        let mut result = Vec::new();
-
-        // Inject system prompt if configured and not already present.
-        let has_system = messages.iter().any(|m| m.role == Role::System);
-        if !has_system {
-            if let Some(sys_prompt) = &self.config.system_prompt {
-                let sys_msg = ChatCompletionRequestSystemMessageArgs::default()
-                    .content(sys_prompt.as_str())
-                    .build()
-                    .map_err(|e| AgentError::Provider(e.to_string()))?;
-                result.push(ChatCompletionRequestMessage::System(sys_msg));
+        mut call: ToolCall,
+        session_tool_allowlist: Option<&[String]>,
+        session_tool_denylist: &[String],
+        session_tool_calls: &HashMap<String, u32>,
+    ) -> ToolCallResult {
+        let refused = |call: &ToolCall, content: String| ToolCallResult {
+            output: ToolOutput {
+                tool_call_id: call.id.clone(),
+                content,
+                is_error: true,
+                duration_ms: None,
+                images: Vec::new(),
+            },
+            snapshots: Vec::new(),
+            tool_calls: HashMap::new(),
+        };
+        if let HookAction::Veto(reason) = self.hooks.pre_tool(&mut call).await {
+            return refused(&call, reason);
+        }
+        let tool_schemas = self
+            .tool_registry
+            .schemas(session_tool_allowlist, session_tool_denylist);
+        let Some(schema) = tool_schemas.iter().find(|s| s.name == call.name) else {
+            let content = format!("Tool not allowed: {}", call.name);
+            return refused(&call, content);
+        };
+        let parsed = serde_json::from_str::<serde_json::Value>(&call.arguments)
+            .map_err(|e| vec![format!("arguments: invalid JSON: {}", e)])
+            .and_then(|args| {
+                let issues = tool_validation::validate_arguments(&schema.parameters, &args);
+                if issues.is_empty() { Ok(args) } else { Err(issues) }
+            });
+        let args = match parsed {
+            Ok(args) => args,
+            Err(issues) => {
+                let content =
+                    format!("Invalid arguments for `{}`:\n- {}", call.name, issues.join("\n- "));
+                return refused(&call, content);
             }
+        };
+        let (mut snapshots, mut tool_calls) = (Vec::new(), HashMap::new());
+        if let Err(reason) =
+            self.admit(&call.name, &args, session_tool_calls, &mut tool_calls, &mut snapshots)
+        {
+            return refused(&call, reason);
         }
+        let mut output = self.tool_registry.execute(&call.name, &call.id, args).await;
+        self.hooks.post_tool(&call, &mut output).await;
+        info!(
+            tool = %call.name,
+            call_id = %output.tool_call_id,
+            is_error = output.is_error,
+            duration_ms = output.duration_ms,
+            "Tool call finished"
+        );
+        ToolCallResult {
+            output,
+            snapshots,
+            tool_calls,
+        }
+    }
 
-        for msg in messages {
-            match msg.role {
-                Role::System => {
-                    let m = ChatCompletionRequestSystemMessageArgs::default()
-                        .content(msg.content.as_str())
-                        .build()
-                        .map_err(|e| AgentError::Provider(e.to_string()))?;
-                    result.push(ChatCompletionRequestMessage::System(m));
-                }
-                Role::User => {
-                    let m = ChatCompletionRequestUserMessageArgs::default()
-                        .content(msg.content.as_str())
-                        .build()
-                        .map_err(|e| AgentError::Provider(e.to_string()))?;
-                    result.push(ChatCompletionRequestMessage::User(m));
-                }
-                Role::Assistant => {
-                    let mut builder = ChatCompletionRequestAssistantMessageArgs::default();
-                    builder.content(msg.content.as_str());
-                    if let Some(tool_calls) = &msg.tool_calls {
-                        let tc_openai: Vec<ChatCompletionMessageToolCall> = tool_calls
-                            .iter()
-                            .map(|tc| ChatCompletionMessageToolCall {
-                                id: tc.id.clone(),
-                                r#type: ChatCompletionToolType::Function,
-                                function: async_openai::types::FunctionCall {
-                                    name: tc.name.clone(),
-                                    arguments: tc.arguments.clone(),
-                                },
-                            })
-                            .collect();
-                        builder.tool_calls(tc_openai);
-                    }
-                    let m = builder
-                        .build()
-                        .map_err(|e| AgentError::Provider(e.to_string()))?;
-                    result.push(ChatCompletionRequestMessage::Assistant(m));
-                }
-                Role::Tool => {
-                    let m = ChatCompletionRequestToolMessageArgs::default()
-                        .tool_call_id(msg.tool_call_id.as_deref().unwrap_or(""))
-                        .content(msg.content.as_str())
-                        .build()
-                        .map_err(|e| AgentError::Provider(e.to_string()))?;
-                    result.push(ChatCompletionRequestMessage::Tool(m));
+    /// Count a validated call to `name` in `turn_calls`, under its
+    /// registered name, if it is within its quotas with the session's and
+    /// turn's earlier calls, and snapshot the files it may modify; or
+    /// explain why it may not run.
+    fn admit(
+        &self,
+        name: &str,
+        args: &serde_json::Value,
+        session_calls: &HashMap<String, u32>,
+        turn_calls: &mut HashMap<String, u32>,
+        snapshots: &mut Vec<FileSnapshot>,
+    ) -> Result<(), String> {
+        let name = self.tool_registry.resolve(name).unwrap_or(name);
+        let used = session_calls.get(name).copied().unwrap_or(0);
+        let turn = turn_calls.entry(name.to_string()).or_default();
+        if let Err(reason) = self.tool_registry.quotas().admit(name, used + *turn) {
+            warn!(tool = %name, "{}", reason);
+            return Err(reason);
+        }
+        *turn += 1;
+        for path in self.tool_registry.affected_paths(name, args) {
+            if !snapshots.iter().any(|s| s.path == path) {
+                snapshots.extend(FileSnapshot::capture(&path));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Convert our Message types to async-openai request messages.
+fn build_openai_messages(
+    messages: &[Message],
+    system_prompt: Option<&str>,
+) -> Result<Vec<ChatCompletionRequestMessage>, AgentError> {
+    let mut result = Vec::new();
+
+    // Inject system prompt if configured and not already present.
+    let has_system = messages.iter().any(|m| m.role == Role::System);
+    if !has_system {
+        if let Some(sys_prompt) = system_prompt {
+            let sys_msg = ChatCompletionRequestSystemMessageArgs::default()
+                .content(sys_prompt)
+                .build()
+                .map_err(|e| AgentError::Provider(e.to_string()))?;
+            result.push(ChatCompletionRequestMessage::System(sys_msg));
+        }
+    }
+
+    // Images from a run of tool results follow it as one user message.
+    let mut tool_images: Vec<ImagePart> = Vec::new();
+    for msg in messages {
+        if msg.role != Role::Tool && !tool_images.is_empty() {
+            result.push(user_message(TOOL_IMAGES_NOTE, &tool_images)?);
+            tool_images.clear();
+        }
+        match msg.role {
+            Role::System => {
+                let m = ChatCompletionRequestSystemMessageArgs::default()
+                    .content(msg.content.as_str())
+                    .build()
+                    .map_err(|e| AgentError::Provider(e.to_string()))?;
+                result.push(ChatCompletionRequestMessage::System(m));
+            }
+            Role::User => {
+                result.push(user_message(&msg.content, &msg.images)?);
+            }
+            Role::Assistant => {
+                let mut builder = ChatCompletionRequestAssistantMessageArgs::default();
+                builder.content(msg.content.as_str());
+                if let Some(tool_calls) = &msg.tool_calls {
+                    let tc_openai: Vec<ChatCompletionMessageToolCall> = tool_calls
+                        .iter()
+                        .map(|tc| ChatCompletionMessageToolCall {
+                            id: tc.id.clone(),
+                            r#type: ChatCompletionToolType::Function,
+                            function: async_openai::types::FunctionCall {
+                                name: tc.name.clone(),
+                                arguments: tc.arguments.clone(),
+                            },
+                        })
+                        .collect();
+                    builder.tool_calls(tc_openai);
                 }
+                let m = builder
+                    .build()
+                    .map_err(|e| AgentError::Provider(e.to_string()))?;
+                result.push(ChatCompletionRequestMessage::Assistant(m));
+            }
+            Role::Tool => {
+                let m = ChatCompletionRequestToolMessageArgs::default()
+                    .tool_call_id(msg.tool_call_id.as_deref().unwrap_or(""))
+                    .content(msg.content.as_str())
+                    .build()
+                    .map_err(|e| AgentError::Provider(e.to_string()))?;
+                result.push(ChatCompletionRequestMessage::Tool(m));
+                tool_images.extend(msg.images.iter().cloned());
             }
         }
+    }
+    if !tool_images.is_empty() {
+        result.push(user_message(TOOL_IMAGES_NOTE, &tool_images)?);
+    }
 
-        Ok(result)
+    Ok(result)
+}
+
+/// A user message; with images, its content becomes text and image parts.
+fn user_message(
+    text: &str,
+    images: &[ImagePart],
+) -> Result<ChatCompletionRequestMessage, AgentError> {
+    let mut builder = ChatCompletionRequestUserMessageArgs::default();
+    if images.is_empty() {
+        builder.content(text);
+    } else {
+        let mut parts = Vec::with_capacity(images.len() + 1);
+        if !text.is_empty() {
+            parts.push(ChatCompletionRequestUserMessageContentPart::Text(
+                ChatCompletionRequestMessageContentPartText { text: text.into() },
+            ));
+        }
+        parts.extend(images.iter().map(|image| {
+            ChatCompletionRequestUserMessageContentPart::ImageUrl(
+                ChatCompletionRequestMessageContentPartImage {
+                    image_url: async_openai::types::ImageUrl {
+                        url: image.data_url(),
+                        detail: None,
+                    },
+                },
+            )
+        }));
+        builder.content(ChatCompletionRequestUserMessageContent::Array(parts));
     }
+    let m = builder
+        .build()
+        .map_err(|e| AgentError::Provider(e.to_string()))?;
+    Ok(ChatCompletionRequestMessage::User(m))
+}
+
+/// Replace image parts with a text note, for providers without vision.
+fn without_images(messages: Vec<ChatCompletionRequestMessage>) -> Vec<ChatCompletionRequestMessage> {
+    messages
+        .into_iter()
+        .map(|message| match message {
+            ChatCompletionRequestMessage::User(mut m) => {
+                if let ChatCompletionRequestUserMessageContent::Array(parts) = &m.content {
+                    let mut texts = Vec::new();
+                    let mut omitted = 0;
+                    for part in parts {
+                        match part {
+                            ChatCompletionRequestUserMessageContentPart::Text(t) => {
+                                texts.push(t.text.clone())
+                            }
+                            _ => omitted += 1,
+                        }
+                    }
+                    texts.push(format!(
+                        "[{} image(s) omitted: this model does not accept image input]",
+                        omitted
+                    ));
+                    m.content = ChatCompletionRequestUserMessageContent::Text(texts.join("\n"));
+                }
+                ChatCompletionRequestMessage::User(m)
+            }
+            other => other,
+        })
+        .collect()
 }
 
 /// Build OpenAI-format tool definitions from our tool schemas.
@@ -380,44 +879,106 @@ fn build_chat_tools(
         .collect()
 }
 
-/// Accumulated result from consuming a streaming response.
-struct StreamedResponse {
-    content: String,
-    tool_calls: Vec<ToolCall>,
-}
-
-/// Open a streaming chat completion, consume all deltas, emit ContentChunk
-/// events in real time, and return the accumulated content + tool calls.
+/// Build the chat completion request for `provider` and send it through
+/// the provider's middleware to [`StreamEndpoint`]. A response produced by
+/// middleware alone (e.g. a cache hit) is emitted as one content chunk.
 async fn consume_stream(
     provider: ResolvedProvider,
     messages: Vec<ChatCompletionRequestMessage>,
     tools: Vec<async_openai::types::ChatCompletionTool>,
     event_tx: mpsc::UnboundedSender<AgentEvent>,
-) -> Result<StreamedResponse, RequestError> {
-    let openai_config = OpenAIConfig::new()
-        .with_api_base(&provider.api_base)
-        .with_api_key(provider.api_key.as_deref().unwrap_or("not-needed"));
-    let client = Client::with_config(openai_config);
+) -> Result<ProviderResponse, RequestError> {
+    let messages = if provider.vision {
+        messages
+    } else {
+        without_images(messages)
+    };
 
     let mut request_builder = CreateChatCompletionRequestArgs::default();
     request_builder
         .model(&provider.model)
         .messages(messages)
         .temperature(provider.temperature)
-        .max_completion_tokens(provider.max_tokens);
+        .max_completion_tokens(provider.max_tokens)
+        .stream_options(ChatCompletionStreamOptions {
+            include_usage: true,
+        });
 
     if !tools.is_empty() {
         request_builder.tools(tools);
     }
 
-    let request = request_builder
+    let body = request_builder
         .build()
         .map_err(|e| RequestError::Permanent(format!("Failed to build request: {}", e)))?;
+    let request = ProviderRequest {
+        provider: provider.name.clone(),
+        body,
+        headers: HeaderMap::new(),
+    };
 
+    // Held answers stream into a closed channel and are sent whole below.
+    let live = !provider.middleware.holds_output();
+    let endpoint = StreamEndpoint {
+        provider: &provider,
+        event_tx: if live {
+            event_tx.clone()
+        } else {
+            mpsc::unbounded_channel().0
+        },
+        reached: AtomicBool::new(false),
+    };
+    let response = provider.middleware.run(request, &endpoint).await?;
+    if (!live || !endpoint.reached.load(Ordering::Relaxed)) && !response.content.is_empty() {
+        let _ = event_tx.send(AgentEvent::ContentChunk(response.content.clone()));
+    }
+    Ok(response)
+}
+
+/// Sends a request to the provider's API and streams the answer.
+struct StreamEndpoint<'a> {
+    provider: &'a ResolvedProvider,
+    event_tx: mpsc::UnboundedSender<AgentEvent>,
+    /// Whether the request got past the middleware.
+    reached: AtomicBool,
+}
+
+#[async_trait::async_trait]
+impl Endpoint for StreamEndpoint<'_> {
+    async fn call(&self, request: ProviderRequest) -> Result<ProviderResponse, RequestError> {
+        self.reached.store(true, Ordering::Relaxed);
+        match &self.provider.mock {
+            Some(scenario) => Ok(mock_completion(self.provider, scenario, request, &self.event_tx).await),
+            None => stream_completion(self.provider, request, &self.event_tx).await,
+        }
+    }
+}
+
+/// Open a streaming chat completion, consume all deltas, emit ContentChunk
+/// events in real time, and return the accumulated content + tool calls.
+async fn stream_completion(
+    provider: &ResolvedProvider,
+    request: ProviderRequest,
+    event_tx: &mpsc::UnboundedSender<AgentEvent>,
+) -> Result<ProviderResponse, RequestError> {
+    let openai_config = OpenAIConfig::new()
+        .with_api_base(&provider.api_base)
+        .with_api_key(provider.api_key.as_deref().unwrap_or("not-needed"));
+    let mut client = Client::with_config(openai_config);
+    if !request.headers.is_empty() || provider.network != NetworkConfig::default() {
+        let builder = reqwest::Client::builder().default_headers(request.headers);
+        let http = crate::network::apply(builder, &provider.network)
+            .map_err(|e| RequestError::Permanent(e.to_string()))?
+            .build()
+            .map_err(|e| RequestError::Permanent(format!("Failed to build HTTP client: {}", e)))?;
+        client = client.with_http_client(http);
+    }
+
+    let prompt_chars = prompt_chars(&request.body);
     let timeout_duration = std::time::Duration::from_secs(provider.timeout_secs);
     let mut stream = match tokio::time::timeout(
         timeout_duration,
-        client.chat().create_stream(request),
+        client.chat().create_stream(request.body),
     )
     .await
     {
@@ -433,17 +994,21 @@ async fn consume_stream(
 
     // Accumulate the full content and tool call fragments from streamed deltas.
     let mut content = String::new();
-    // Tool calls arrive as indexed chunks: first chunk has id + name, subsequent
-    // chunks append to arguments. We accumulate by index.
-    let mut tc_ids: HashMap<u32, String> = HashMap::new();
-    let mut tc_names: HashMap<u32, String> = HashMap::new();
-    let mut tc_args: HashMap<u32, String> = HashMap::new();
+    // Tool calls arrive as indexed chunks; the assembler tolerates the ways
+    // local servers get this wrong (missing ids, repeated or cumulative
+    // fragments, fenced arguments).
+    let mut assembler = ToolCallAssembler::new();
+    // Sent in the last chunk by providers that support `include_usage`.
+    let mut reported = None;
 
     while let Some(chunk_result) = stream.next().await {
         let chunk = match chunk_result {
             Ok(c) => c,
             Err(e) => return classify_provider_error(e),
         };
+        if let Some(u) = &chunk.usage {
+            reported = Some((u.prompt_tokens as u64, u.completion_tokens as u64));
+        }
 
         for choice in &chunk.choices {
             let delta = &choice.delta;
@@ -459,47 +1024,112 @@ async fn consume_stream(
             // Accumulate tool call deltas by index.
             if let Some(tc_chunks) = &delta.tool_calls {
                 for tc_chunk in tc_chunks {
-                    let idx = tc_chunk.index;
-
-                    if let Some(id) = &tc_chunk.id {
-                        tc_ids.insert(idx, id.clone());
-                    }
-
-                    if let Some(func) = &tc_chunk.function {
-                        if let Some(name) = &func.name {
-                            tc_names.insert(idx, name.clone());
-                        }
-                        if let Some(args) = &func.arguments {
-                            tc_args.entry(idx).or_default().push_str(args);
-                            let _ = event_tx.send(AgentEvent::ToolCallArgsChunk {
-                                id: tc_ids.get(&idx).cloned().unwrap_or_default(),
-                                chunk: args.clone(),
-                            });
-                        }
+                    let func = tc_chunk.function.as_ref();
+                    let args = func.and_then(|f| f.arguments.as_deref());
+                    let id = assembler.push(
+                        tc_chunk.index,
+                        tc_chunk.id.as_deref(),
+                        func.and_then(|f| f.name.as_deref()),
+                        args,
+                    );
+                    if let Some(args) = args.filter(|a| !a.is_empty()) {
+                        let _ = event_tx.send(AgentEvent::ToolCallArgsChunk {
+                            id,
+                            chunk: args.to_string(),
+                        });
                     }
                 }
             }
         }
     }
 
-    // Assemble accumulated tool calls into final ToolCall objects.
-    let mut tool_calls: Vec<ToolCall> = Vec::new();
-    let mut indices: Vec<u32> = tc_ids.keys().copied().collect();
-    indices.sort();
-    for idx in indices {
-        tool_calls.push(ToolCall {
-            id: tc_ids.remove(&idx).unwrap_or_default(),
-            name: tc_names.remove(&idx).unwrap_or_default(),
-            arguments: tc_args.remove(&idx).unwrap_or_default(),
-        });
-    }
+    let tool_calls = assembler.finish();
+
+    let (prompt_tokens, completion_tokens) = reported.unwrap_or_else(|| {
+        let completion_chars = content.chars().count()
+            + tool_calls
+                .iter()
+                .map(|tc| tc.name.len() + tc.arguments.chars().count())
+                .sum::<usize>();
+        (estimate_tokens(prompt_chars), estimate_tokens(completion_chars))
+    });
+    let usage = TokenUsage {
+        prompt_tokens,
+        completion_tokens,
+        cost_usd: provider.cost_usd(prompt_tokens, completion_tokens),
+        estimated: reported.is_none(),
+    };
 
-    Ok(StreamedResponse {
+    Ok(ProviderResponse {
         content,
         tool_calls,
+        usage: Some(usage),
     })
 }
 
+/// Answer from a mock provider's scenario, streaming the content word by
+/// word like an API would.
+async fn mock_completion(
+    provider: &ResolvedProvider,
+    scenario: &MockScenario,
+    request: ProviderRequest,
+    event_tx: &mpsc::UnboundedSender<AgentEvent>,
+) -> ProviderResponse {
+    let mut response = scenario.respond(&request.body.messages);
+    let delay = std::time::Duration::from_millis(scenario.delay_ms);
+    for word in response.content.split_inclusive(' ') {
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        let _ = event_tx.send(AgentEvent::ContentChunk(word.to_string()));
+    }
+    for call in &response.tool_calls {
+        let _ = event_tx.send(AgentEvent::ToolCallArgsChunk {
+            id: call.id.clone(),
+            chunk: call.arguments.clone(),
+        });
+    }
+    let completion_chars = response.content.chars().count()
+        + response
+            .tool_calls
+            .iter()
+            .map(|tc| tc.name.len() + tc.arguments.chars().count())
+            .sum::<usize>();
+    let (prompt_tokens, completion_tokens) = (
+        estimate_tokens(prompt_chars(&request.body)),
+        estimate_tokens(completion_chars),
+    );
+    response.usage = Some(TokenUsage {
+        prompt_tokens,
+        completion_tokens,
+        cost_usd: provider.cost_usd(prompt_tokens, completion_tokens),
+        estimated: true,
+    });
+    response
+}
+
+/// Rough token count for `chars` characters of text (about four per token).
+fn estimate_tokens(chars: usize) -> u64 {
+    (chars as u64).div_ceil(4)
+}
+
+/// Characters of text in a request's messages, for estimating prompt
+/// tokens. Inline image data is not counted.
+fn prompt_chars(body: &async_openai::types::CreateChatCompletionRequest) -> usize {
+    fn count(value: &serde_json::Value) -> usize {
+        match value {
+            serde_json::Value::String(s) if s.starts_with("data:") => 0,
+            serde_json::Value::String(s) => s.chars().count(),
+            serde_json::Value::Array(items) => items.iter().map(count).sum(),
+            serde_json::Value::Object(map) => map.values().map(count).sum(),
+            _ => 0,
+        }
+    }
+    serde_json::to_value(&body.messages)
+        .map(|v| count(&v))
+        .unwrap_or(0)
+}
+
 /// Classify an async-openai error for failover decisions.
 ///
 /// Uses structured error matching via `ApiError.code` and `ApiError.type`
@@ -539,3 +1169,221 @@ fn classify_provider_error<T>(err: async_openai::error::OpenAIError) -> Result<T
         _ => Err(RequestError::Permanent(err.to_string())),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool_quota::QUOTA_EXCEEDED;
+
+    fn user_text(message: &ChatCompletionRequestMessage) -> Option<&str> {
+        match message {
+            ChatCompletionRequestMessage::User(m) => match &m.content {
+                ChatCompletionRequestUserMessageContent::Text(t) => Some(t),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_images_become_parts_or_are_omitted() {
+        let png = ImagePart::from_bytes("image/png", b"\x89PNG");
+        let messages = vec![
+            Message::user("what is this?").with_images(vec![png.clone()]),
+            Message::assistant_with_tool_calls(
+                "",
+                vec![ToolCall {
+                    id: "call_1".into(),
+                    name: "file_read".into(),
+                    arguments: "{}".into(),
+                }],
+            ),
+            Message::tool_result("call_1", "Image file").with_images(vec![png]),
+        ];
+
+        let built = build_openai_messages(&messages, None).unwrap();
+        // user, assistant, tool, then the tool images as a user message.
+        assert_eq!(built.len(), 4);
+        let ChatCompletionRequestMessage::User(first) = &built[0] else {
+            panic!("expected a user message");
+        };
+        let ChatCompletionRequestUserMessageContent::Array(parts) = &first.content else {
+            panic!("expected content parts");
+        };
+        assert_eq!(parts.len(), 2);
+        assert!(matches!(
+            &parts[1],
+            ChatCompletionRequestUserMessageContentPart::ImageUrl(p)
+                if p.image_url.url.starts_with("data:image/png;base64,")
+        ));
+
+        let stripped = without_images(built);
+        assert_eq!(
+            user_text(&stripped[0]),
+            Some("what is this?\n[1 image(s) omitted: this model does not accept image input]")
+        );
+        assert!(user_text(&stripped[3]).unwrap().starts_with(TOOL_IMAGES_NOTE));
+    }
+
+    #[test]
+    fn test_prompt_estimate_skips_image_data() {
+        let png = ImagePart::from_bytes("image/png", &[0u8; 4096]);
+        let messages = vec![Message::user("12345678").with_images(vec![png])];
+        let body = CreateChatCompletionRequestArgs::default()
+            .model("m")
+            .messages(build_openai_messages(&messages, None).unwrap())
+            .build()
+            .unwrap();
+        // The text plus short fields like the role, not the base64 payload.
+        let chars = prompt_chars(&body);
+        assert!((8..64).contains(&chars), "{}", chars);
+        assert_eq!(estimate_tokens(8), 2);
+        assert_eq!(estimate_tokens(9), 3);
+    }
+
+    struct UpperTool;
+
+    #[async_trait::async_trait]
+    impl crate::tool_registry::Tool for UpperTool {
+        fn name(&self) -> &str {
+            "upper"
+        }
+        fn description(&self) -> &str {
+            "Uppercase text"
+        }
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object", "properties": {"text": {"type": "string"}}})
+        }
+        async fn execute(&self, args: serde_json::Value) -> Result<String, AgentError> {
+            Ok(args["text"].as_str().unwrap_or_default().to_uppercase())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_turn_against_mock_provider() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let scenario = dir.path().join("demo.yaml");
+        std::fs::write(
+            &scenario,
+            "turns:\n  - responses:\n      - tool_calls:\n          - name: upper\n            arguments: { text: shout }\n      - content: \"Done.\"\n",
+        )
+        .unwrap();
+        let mut config = AppConfig::default();
+        config.provider.kind = crate::config::ProviderKind::Mock;
+        config.provider.scenario = Some(scenario);
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(UpperTool));
+        let agent = AgentLoop::new(config, Arc::new(registry)).unwrap();
+
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let result = agent
+            .run(&[Message::user("go")], None, &[], &HashMap::new(), tx)
+            .await
+            .unwrap();
+        assert_eq!(result.messages.len(), 3);
+        assert_eq!(result.messages[1].content, "SHOUT");
+        assert_eq!(result.final_message().content, "Done.");
+        assert!(result.usage.prompt_tokens > 0);
+        assert_eq!(result.tool_calls, HashMap::from([("upper".to_string(), 1)]));
+    }
+
+    struct VetoShout;
+
+    #[async_trait::async_trait]
+    impl crate::hooks::Hook for VetoShout {
+        fn name(&self) -> &str {
+            "veto-shout"
+        }
+        async fn pre_tool(&self, call: &mut ToolCall) -> HookAction {
+            if call.arguments.contains("shout") {
+                HookAction::Veto("no shouting".into())
+            } else {
+                HookAction::Continue
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_outside_turn_is_gated() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(UpperTool));
+        registry.configure(&crate::config::ToolsConfig {
+            aliases: HashMap::from([("caps".into(), "upper".into())]),
+            quotas: HashMap::from([(
+                "caps".into(),
+                crate::config::ToolQuota { per_session: Some(1), ..Default::default() },
+            )]),
+            ..Default::default()
+        });
+        let mut agent = AgentLoop::new(AppConfig::default(), Arc::new(registry)).unwrap();
+        agent.hooks_mut().register(Arc::new(VetoShout));
+        let call = |arguments: &str| ToolCall {
+            id: "rerun".into(),
+            name: "upper".into(),
+            arguments: arguments.into(),
+        };
+
+        let none = HashMap::new();
+        let result = agent.run_tool_call(call(r#"{"text":"hi"}"#), None, &[], &none).await;
+        assert_eq!(result.output.content, "HI");
+        assert_eq!(result.tool_calls, HashMap::from([("upper".to_string(), 1)]));
+
+        // Refused calls don't use up the quota.
+        let result = agent.run_tool_call(call(r#"{"text":"shout"}"#), None, &[], &none).await;
+        let output = result.output;
+        assert!(output.is_error && output.content.ends_with("no shouting"), "{}", output.content);
+        assert!(result.tool_calls.is_empty());
+        let result = agent.run_tool_call(call(r#"{"text":1}"#), None, &[], &none).await;
+        assert!(result.output.content.starts_with("Invalid arguments"), "{}", result.output.content);
+        assert!(result.tool_calls.is_empty());
+        let denied = ["upper".to_string()];
+        let result = agent.run_tool_call(call("{}"), None, &denied, &none).await;
+        assert_eq!(result.output.content, "Tool not allowed: upper");
+        assert!(result.tool_calls.is_empty());
+
+        // The session already used its one call, counted under the tool's
+        // name although the quota was set on its alias.
+        let used = HashMap::from([("upper".to_string(), 1)]);
+        let result = agent.run_tool_call(call("{}"), None, &[], &used).await;
+        assert!(result.output.content.starts_with(QUOTA_EXCEEDED), "{}", result.output.content);
+        assert!(result.tool_calls.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_output_guardrail_holds_back_streaming() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let scenario = dir.path().join("leak.yaml");
+        std::fs::write(
+            &scenario,
+            "turns:\n  - responses:\n      - content: \"Mail ops@example.com for access\"\n",
+        )
+        .unwrap();
+        let mut config = AppConfig::default();
+        config.provider.kind = crate::config::ProviderKind::Mock;
+        config.provider.scenario = Some(scenario);
+        config.guardrails = vec![crate::config::GuardrailConfig {
+            name: "pii".into(),
+            patterns: Vec::new(),
+            keywords: Vec::new(),
+            policies: vec![crate::config::ContentPolicy::Pii],
+            applies_to: crate::config::GuardrailScope::Output,
+            action: crate::config::GuardrailAction::Redact,
+            replacement: "[email]".into(),
+        }];
+        let agent = AgentLoop::new(config, Arc::new(ToolRegistry::new())).unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let result = agent
+            .run(&[Message::user("who do I ask?")], None, &[], &HashMap::new(), tx)
+            .await
+            .unwrap();
+        assert_eq!(result.final_message().content, "Mail [email] for access");
+        let mut streamed = String::new();
+        while let Ok(event) = rx.try_recv() {
+            if let AgentEvent::ContentChunk(chunk) = event {
+                streamed.push_str(&chunk);
+            }
+        }
+        assert_eq!(streamed, "Mail [email] for access");
+    }
+}
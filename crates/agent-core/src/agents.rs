@@ -0,0 +1,239 @@
+//! Named agent presets ("coder", "reviewer", "sysadmin").
+//!
+//! A preset bundles a system prompt, model, temperature, tool allowlist and
+//! skills. Presets are defined under `[agents.<name>]` in the config file or
+//! created at runtime (`POST /v1/agents`), which stores them as JSON at
+//! `session.agents_path` (default: `<data_dir>/agents.json`). Config-defined
+//! presets take precedence and are read-only through the API.
+//!
+//! A session created with a preset records its name; every turn in that
+//! session runs with the preset applied (see [`crate::AgentLoop::run_as`]).
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::AppConfig;
+
+/// Overrides applied to every turn of a session created with this agent.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct AgentPreset {
+    /// Human-readable description.
+    pub description: Option<String>,
+    /// Replaces the configured system prompt.
+    pub system_prompt: Option<String>,
+    /// Model used for every provider in the chain.
+    pub model: Option<String>,
+    /// Sampling temperature.
+    pub temperature: Option<f32>,
+    /// Tools this agent may use; all tools when unset.
+    pub tools: Option<Vec<String>>,
+    /// Skills whose SKILL.md is appended to the system prompt.
+    pub skills: Vec<String>,
+}
+
+impl AgentPreset {
+    /// Check values that would make every request fail.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(t) = self.temperature {
+            if !(0.0..=2.0).contains(&t) {
+                return Err("temperature must be between 0 and 2".into());
+            }
+        }
+        if self.model.as_deref().is_some_and(|m| m.trim().is_empty()) {
+            return Err("model must not be empty".into());
+        }
+        Ok(())
+    }
+
+    /// This preset with `(name, content)` skill documents appended to its
+    /// system prompt (or to `base_prompt` when it has none).
+    pub fn with_skill_docs(&self, base_prompt: Option<&str>, docs: &[(String, String)]) -> Self {
+        let mut preset = self.clone();
+        if docs.is_empty() {
+            return preset;
+        }
+        let mut prompt = preset
+            .system_prompt
+            .take()
+            .or_else(|| base_prompt.map(String::from))
+            .unwrap_or_default();
+        for (name, content) in docs {
+            prompt.push_str(&format!("\n\n## Skill: {}\n\n{}", name, content.trim()));
+        }
+        preset.system_prompt = Some(prompt.trim_start().to_string());
+        preset
+    }
+}
+
+/// Agent names are used in commands and URLs: `[A-Za-z0-9_-]{1,64}`.
+pub fn validate_agent_name(name: &str) -> Result<(), String> {
+    let ok = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if ok {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid agent name '{}': use 1-64 letters, digits, '-' or '_'",
+            name
+        ))
+    }
+}
+
+/// Where a preset is defined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum AgentSource {
+    Config,
+    Api,
+}
+
+/// Presets created at runtime, persisted as one JSON document.
+#[derive(Debug, Default)]
+pub struct AgentStore {
+    path: PathBuf,
+    agents: BTreeMap<String, AgentPreset>,
+}
+
+impl AgentStore {
+    /// `session.agents_path`, defaulting to `<data_dir>/agents.json`.
+    pub fn path_for(config: &AppConfig) -> PathBuf {
+        config
+            .session
+            .agents_path
+            .clone()
+            .unwrap_or_else(|| AppConfig::data_dir().join("agents.json"))
+    }
+
+    /// Load stored presets, starting empty if the file is missing or invalid.
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let agents = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring invalid agent presets at {:?}: {}", path, e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        Self { path, agents }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Look up a preset, preferring the config file over stored presets.
+    pub fn resolve(&self, config: &AppConfig, name: &str) -> Option<(AgentPreset, AgentSource)> {
+        if let Some(preset) = config.agents.get(name) {
+            return Some((preset.clone(), AgentSource::Config));
+        }
+        self.agents
+            .get(name)
+            .map(|preset| (preset.clone(), AgentSource::Api))
+    }
+
+    /// All presets sorted by name; config entries shadow stored ones.
+    pub fn list(&self, config: &AppConfig) -> Vec<(String, AgentPreset, AgentSource)> {
+        let mut all: BTreeMap<&str, (&AgentPreset, AgentSource)> = self
+            .agents
+            .iter()
+            .map(|(name, preset)| (name.as_str(), (preset, AgentSource::Api)))
+            .collect();
+        for (name, preset) in &config.agents {
+            all.insert(name, (preset, AgentSource::Config));
+        }
+        all.into_iter()
+            .map(|(name, (preset, source))| (name.to_string(), preset.clone(), source))
+            .collect()
+    }
+
+    /// Create or replace a stored preset and save.
+    pub fn insert(&mut self, name: impl Into<String>, preset: AgentPreset) -> std::io::Result<()> {
+        self.agents.insert(name.into(), preset);
+        self.save()
+    }
+
+    /// Remove a stored preset and save. Returns `false` if it did not exist.
+    pub fn remove(&mut self, name: &str) -> std::io::Result<bool> {
+        if self.agents.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Persist atomically (temp file + rename).
+    fn save(&self) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(&self.agents)?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, json)?;
+        std::fs::rename(&tmp_path, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_agents_shadow_stored_agents() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("agents.json");
+        let config: AppConfig = toml::from_str(
+            r#"
+[agents.reviewer]
+system_prompt = "Review code."
+tools = ["file_read"]
+"#,
+        )
+        .unwrap();
+
+        let mut store = AgentStore::load(&path);
+        let stored = AgentPreset {
+            model: Some("llama3".into()),
+            ..Default::default()
+        };
+        store.insert("reviewer", stored.clone()).unwrap();
+        store.insert("sysadmin", stored).unwrap();
+
+        let (reviewer, source) = store.resolve(&config, "reviewer").unwrap();
+        assert_eq!(source, AgentSource::Config);
+        assert_eq!(reviewer.tools, Some(vec!["file_read".to_string()]));
+
+        // Reloading picks up the persisted presets.
+        let mut store = AgentStore::load(&path);
+        let names: Vec<_> = store.list(&config).into_iter().map(|(n, _, s)| (n, s)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("reviewer".to_string(), AgentSource::Config),
+                ("sysadmin".to_string(), AgentSource::Api),
+            ]
+        );
+        assert!(store.remove("sysadmin").unwrap());
+        assert!(!store.remove("sysadmin").unwrap());
+        assert!(store.resolve(&config, "sysadmin").is_none());
+    }
+
+    #[test]
+    fn test_skill_docs_extend_prompt() {
+        let preset = AgentPreset::default();
+        let docs = vec![("rust".to_string(), "Use clippy.\n".to_string())];
+        let applied = preset.with_skill_docs(Some("Base prompt."), &docs);
+        assert_eq!(
+            applied.system_prompt.as_deref(),
+            Some("Base prompt.\n\n## Skill: rust\n\nUse clippy.")
+        );
+        assert!(validate_agent_name("code-reviewer_2").is_ok());
+        assert!(validate_agent_name("../etc").is_err());
+    }
+}
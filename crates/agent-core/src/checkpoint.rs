@@ -0,0 +1,333 @@
+//! Conversation checkpoints and workspace file snapshots.
+//!
+//! A checkpoint records how many messages a session had at some point,
+//! taken automatically at every turn boundary or by hand (`/checkpoint`).
+//! Before a tool modifies or deletes files (`file_write`, destructive
+//! `shell_exec` commands) their contents are snapshotted; the snapshots are
+//! kept in the session's shadow directory and attached to the latest
+//! checkpoint, so rolling back to it can restore the workspace as well as
+//! the message list, and the session's file changes can be listed as diffs
+//! and reverted one by one.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use std::path::{Path, PathBuf};
+
+/// Largest file whose previous contents are kept in a snapshot.
+pub const MAX_SNAPSHOT_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Files snapshotted from one directory before it is removed or moved.
+const MAX_TREE_FILES: usize = 500;
+
+/// Contents of a file just before a tool modified it, held in memory until
+/// the turn's snapshots are recorded in the session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSnapshot {
+    pub path: PathBuf,
+    /// `None` if the file did not exist, so restoring removes it.
+    pub content: Option<Vec<u8>>,
+}
+
+impl FileSnapshot {
+    /// Snapshot `path` as it is now: one snapshot for a file (or a missing
+    /// file), one per file inside a directory. Files over
+    /// [`MAX_SNAPSHOT_BYTES`] and unreadable files are skipped.
+    pub fn capture(path: &Path) -> Vec<Self> {
+        let mut snapshots = Vec::new();
+        match std::fs::symlink_metadata(path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => snapshots.push(Self {
+                path: path.to_path_buf(),
+                content: None,
+            }),
+            Ok(meta) if meta.is_dir() => capture_tree(path, &mut snapshots),
+            Ok(meta) if meta.is_file() => snapshots.extend(capture_file(path, meta.len())),
+            _ => {}
+        }
+        snapshots
+    }
+}
+
+fn capture_file(path: &Path, len: u64) -> Option<FileSnapshot> {
+    if len > MAX_SNAPSHOT_BYTES {
+        tracing::debug!("Not snapshotting {} ({} bytes)", path.display(), len);
+        return None;
+    }
+    let content = std::fs::read(path).ok()?;
+    Some(FileSnapshot {
+        path: path.to_path_buf(),
+        content: Some(content),
+    })
+}
+
+fn capture_tree(dir: &Path, snapshots: &mut Vec<FileSnapshot>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if snapshots.len() >= MAX_TREE_FILES {
+            tracing::debug!("Snapshot of {} stopped at {} files", dir.display(), MAX_TREE_FILES);
+            return;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let path = entry.path();
+        if meta.is_dir() && !meta.is_symlink() {
+            capture_tree(&path, snapshots);
+        } else if meta.is_file() {
+            snapshots.extend(capture_file(&path, meta.len()));
+        }
+    }
+}
+
+/// A session's shadow directory, holding the snapshotted file contents its
+/// checkpoints refer to.
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Store `content`, returning the blob name to refer to it by.
+    pub fn put(&self, content: &[u8]) -> std::io::Result<String> {
+        std::fs::create_dir_all(&self.dir)?;
+        let blob = uuid::Uuid::new_v4().simple().to_string();
+        std::fs::write(self.dir.join(&blob), content)?;
+        Ok(blob)
+    }
+
+    pub fn get(&self, blob: &str) -> std::io::Result<Vec<u8>> {
+        std::fs::read(self.dir.join(blob))
+    }
+
+    pub fn remove(&self, blob: &str) {
+        let _ = std::fs::remove_file(self.dir.join(blob));
+    }
+
+    /// Delete the whole shadow directory.
+    pub fn clear(&self) -> std::io::Result<()> {
+        match std::fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A file modified after a checkpoint, with its contents beforehand.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileChange {
+    pub path: PathBuf,
+    /// Blob in the [`SnapshotStore`] holding the previous contents; `None`
+    /// if the file did not exist.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+}
+
+impl FileChange {
+    /// The file's contents before the change (`None`: it did not exist).
+    pub fn original(&self, store: &SnapshotStore) -> std::io::Result<Option<Vec<u8>>> {
+        self.blob.as_deref().map(|b| store.get(b)).transpose()
+    }
+
+    /// Put the file back the way it was.
+    pub fn restore(&self, store: &SnapshotStore) -> std::io::Result<()> {
+        match self.original(store)? {
+            Some(content) => {
+                if let Some(parent) = self.path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&self.path, content)
+            }
+            None => match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// How the file differs now from before the change.
+    pub fn diff(&self, store: &SnapshotStore) -> std::io::Result<FileDiff> {
+        let before = self.original(store)?;
+        let after = match std::fs::read(&self.path) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        let status = match (&before, &after) {
+            (None, None) => ChangeStatus::Unchanged,
+            (None, Some(_)) => ChangeStatus::Created,
+            (Some(_), None) => ChangeStatus::Deleted,
+            (Some(b), Some(a)) if a == b => ChangeStatus::Unchanged,
+            (Some(_), Some(_)) => ChangeStatus::Modified,
+        };
+        let text = |bytes: &Option<Vec<u8>>| match bytes {
+            Some(b) => std::str::from_utf8(b).map(str::to_string).ok(),
+            None => Some(String::new()),
+        };
+        let name = self.path.display().to_string();
+        let diff = match (text(&before), text(&after)) {
+            _ if status == ChangeStatus::Unchanged => String::new(),
+            (Some(b), Some(a)) => TextDiff::from_lines(&b, &a)
+                .unified_diff()
+                .header(&format!("a/{}", name), &format!("b/{}", name))
+                .to_string(),
+            _ => format!("Binary file {} differs\n", name),
+        };
+        Ok(FileDiff {
+            path: self.path.clone(),
+            status,
+            diff,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeStatus {
+    Created,
+    Modified,
+    Deleted,
+    /// Changed and changed back.
+    Unchanged,
+}
+
+/// A file's current contents compared with its snapshot.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FileDiff {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub path: PathBuf,
+    pub status: ChangeStatus,
+    /// Unified diff from the snapshot to the current contents.
+    pub diff: String,
+}
+
+/// A point in a session that can be rolled back to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub id: String,
+    /// Set for manual checkpoints.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Messages in the session when the checkpoint was taken.
+    pub message_count: usize,
+    /// Files modified since, as they were before the first modification.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<FileChange>,
+}
+
+impl Checkpoint {
+    pub fn new(label: Option<String>, message_count: usize) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().simple().to_string()[..8].to_string(),
+            label,
+            created_at: Utc::now(),
+            message_count,
+            files: Vec::new(),
+        }
+    }
+
+    /// Keep the earliest snapshot of each file, storing its contents.
+    pub fn add_snapshot(
+        &mut self,
+        snapshot: FileSnapshot,
+        store: &SnapshotStore,
+    ) -> std::io::Result<()> {
+        if self.files.iter().any(|f| f.path == snapshot.path) {
+            return Ok(());
+        }
+        let blob = snapshot.content.map(|c| store.put(&c)).transpose()?;
+        self.files.push(FileChange {
+            path: snapshot.path,
+            blob,
+        });
+        Ok(())
+    }
+}
+
+/// What a rollback undid.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Rollback {
+    /// The checkpoint rolled back to.
+    pub checkpoint: String,
+    pub messages_removed: usize,
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<String>))]
+    pub files_restored: Vec<PathBuf>,
+    /// Files that could not be restored, with the error.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_and_restore() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = SnapshotStore::new(dir.path().join(".shadow"));
+        let existing = dir.path().join("a.txt");
+        std::fs::write(&existing, "before").unwrap();
+        let created = dir.path().join("new/b.txt");
+
+        let mut cp = Checkpoint::new(None, 0);
+        for path in [&existing, &created] {
+            for snapshot in FileSnapshot::capture(path) {
+                cp.add_snapshot(snapshot, &store).unwrap();
+            }
+        }
+        assert_eq!(cp.files[1].blob, None);
+
+        std::fs::write(&existing, "after").unwrap();
+        std::fs::create_dir_all(created.parent().unwrap()).unwrap();
+        std::fs::write(&created, "new").unwrap();
+
+        let diff = cp.files[0].diff(&store).unwrap();
+        assert_eq!(diff.status, ChangeStatus::Modified);
+        assert!(diff.diff.contains("-before") && diff.diff.contains("+after"));
+        assert_eq!(cp.files[1].diff(&store).unwrap().status, ChangeStatus::Created);
+
+        for change in &cp.files {
+            change.restore(&store).unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "before");
+        assert!(!created.exists());
+        assert_eq!(cp.files[0].diff(&store).unwrap().status, ChangeStatus::Unchanged);
+    }
+
+    #[test]
+    fn test_capture_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        std::fs::write(dir.path().join("src/a.rs"), "a").unwrap();
+        std::fs::write(dir.path().join("src/nested/b.rs"), [0xff, 0x00]).unwrap();
+
+        let mut snapshots = FileSnapshot::capture(&dir.path().join("src"));
+        snapshots.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[1].content.as_deref(), Some(&[0xff, 0x00][..]));
+    }
+
+    #[test]
+    fn test_keeps_earliest_snapshot() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = SnapshotStore::new(dir.path());
+        let mut cp = Checkpoint::new(None, 0);
+        let snap = |content: &str| FileSnapshot {
+            path: "a.txt".into(),
+            content: Some(content.as_bytes().to_vec()),
+        };
+        cp.add_snapshot(snap("first"), &store).unwrap();
+        cp.add_snapshot(snap("second"), &store).unwrap();
+        assert_eq!(cp.files.len(), 1);
+        assert_eq!(cp.files[0].original(&store).unwrap().unwrap(), b"first");
+    }
+}
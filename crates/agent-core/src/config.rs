@@ -1,6 +1,7 @@
+use crate::agents::AgentPreset;
 use crate::profiles::ProfileConfig;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 /// Top-level application configuration, loaded from TOML.
@@ -12,13 +13,49 @@ pub struct AppConfig {
     pub providers: Vec<ProviderEntry>,
     /// Scheduled tasks (opt-in).
     pub schedules: Vec<ScheduleConfig>,
+    /// Where scheduler state and run history are kept.
+    pub scheduler: SchedulerConfig,
     /// Named profiles for workspace-specific overrides.
     #[serde(default)]
     pub profiles: HashMap<String, ProfileConfig>,
+    /// Named agent presets selectable per session.
+    pub agents: HashMap<String, AgentPreset>,
     pub sandbox: SandboxConfig,
     pub rag: RagConfig,
     pub server: ServerConfig,
     pub session: SessionConfig,
+    /// Failed-command assistance from captured terminal activity (opt-in).
+    pub error_assist: ErrorAssistConfig,
+    /// Speech-to-text and text-to-speech servers (opt-in).
+    pub audio: AudioConfig,
+    /// Image generation endpoint for `image_generate` (opt-in).
+    pub images: ImagesConfig,
+    /// Per-project notes file loaded into the system prompt.
+    pub memory: MemoryConfig,
+    /// Project context added to the system prompt.
+    pub context: ContextConfig,
+    /// Read-only kubectl access for the `k8s_inspect` tool (opt-in).
+    pub kubernetes: KubernetesConfig,
+    /// Remote command execution for the `ssh_exec` tool (opt-in).
+    pub ssh: SshConfig,
+    /// Other agent-shell instances the `remote_agent` tool can delegate to.
+    pub remote_agents: HashMap<String, RemoteAgentConfig>,
+    /// Optional built-in tools.
+    pub tools: ToolsConfig,
+    /// Plugin index and install location.
+    pub plugins: PluginsConfig,
+    /// Commands run at agent pipeline stages (`[[hooks]]`).
+    pub hooks: Vec<HookConfig>,
+    /// Content filters on prompts and completions (`[[guardrails]]`).
+    pub guardrails: Vec<GuardrailConfig>,
+    /// Usage goals and where missed goals are reported.
+    pub analytics: AnalyticsConfig,
+    /// Encrypted backups to S3 or WebDAV (opt-in).
+    pub backup: BackupConfig,
+    /// Proxy and DNS-over-HTTPS for `web_fetch` and providers.
+    pub network: NetworkConfig,
+    /// Response cache, robots.txt and per-domain limits of `web_fetch`.
+    pub web_fetch: WebFetchConfig,
     pub system_prompt: Option<String>,
 }
 
@@ -28,11 +65,29 @@ impl Default for AppConfig {
             provider: ProviderConfig::default(),
             providers: Vec::new(),
             schedules: Vec::new(),
+            scheduler: SchedulerConfig::default(),
             profiles: HashMap::new(),
+            agents: HashMap::new(),
             sandbox: SandboxConfig::default(),
             rag: RagConfig::default(),
             server: ServerConfig::default(),
             session: SessionConfig::default(),
+            error_assist: ErrorAssistConfig::default(),
+            audio: AudioConfig::default(),
+            images: ImagesConfig::default(),
+            memory: MemoryConfig::default(),
+            context: ContextConfig::default(),
+            kubernetes: KubernetesConfig::default(),
+            ssh: SshConfig::default(),
+            remote_agents: HashMap::new(),
+            tools: ToolsConfig::default(),
+            plugins: PluginsConfig::default(),
+            hooks: Vec::new(),
+            guardrails: Vec::new(),
+            analytics: AnalyticsConfig::default(),
+            backup: BackupConfig::default(),
+            network: NetworkConfig::default(),
+            web_fetch: WebFetchConfig::default(),
             system_prompt: Some(
                 "You are a helpful AI assistant with access to tools. \
                  Use tools when appropriate to help the user. \
@@ -93,6 +148,10 @@ impl AppConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct ProviderConfig {
+    /// How the provider is reached.
+    pub kind: ProviderKind,
+    /// YAML scenario a `mock` provider answers from.
+    pub scenario: Option<PathBuf>,
     /// Base URL for the OpenAI-compatible API.
     pub api_base: String,
     /// Model name (e.g. "glm-4.7-swift", "llama3", etc.).
@@ -105,6 +164,13 @@ pub struct ProviderConfig {
     pub temperature: f32,
     /// Top-p sampling.
     pub top_p: f32,
+    /// Whether the model accepts image input. Images are dropped from
+    /// requests to models without it.
+    pub vision: bool,
+    /// Price in US dollars per million prompt tokens, for cost estimates.
+    pub input_cost_per_mtok: Option<f64>,
+    /// Price in US dollars per million completion tokens.
+    pub output_cost_per_mtok: Option<f64>,
     /// Failover endpoints — tried in order if primary fails.
     pub failover: Vec<FailoverEndpoint>,
 }
@@ -112,12 +178,17 @@ pub struct ProviderConfig {
 impl Default for ProviderConfig {
     fn default() -> Self {
         Self {
+            kind: ProviderKind::default(),
+            scenario: None,
             api_base: "http://localhost:11434/v1".into(),
             model: "glm-4.7-swift".into(),
             api_key: None,
             max_tokens: 4096,
             temperature: 0.7,
             top_p: 0.9,
+            vision: false,
+            input_cost_per_mtok: None,
+            output_cost_per_mtok: None,
             failover: Vec::new(),
         }
     }
@@ -135,6 +206,12 @@ pub struct FailoverEndpoint {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderEntry {
     pub name: String,
+    #[serde(default)]
+    pub kind: ProviderKind,
+    #[serde(default)]
+    pub scenario: Option<PathBuf>,
+    /// Required unless `kind = "mock"`.
+    #[serde(default)]
     pub api_base: String,
     pub model: String,
     #[serde(default)]
@@ -155,6 +232,164 @@ pub struct ProviderEntry {
     pub temperature: Option<f32>,
     #[serde(default)]
     pub top_p: Option<f32>,
+    #[serde(default)]
+    pub vision: bool,
+    /// Price in US dollars per million prompt tokens, for cost estimates.
+    #[serde(default)]
+    pub input_cost_per_mtok: Option<f64>,
+    /// Price in US dollars per million completion tokens.
+    #[serde(default)]
+    pub output_cost_per_mtok: Option<f64>,
+    /// Request/response middleware, outermost first (`[[providers.middleware]]`).
+    #[serde(default)]
+    pub middleware: Vec<MiddlewareConfig>,
+}
+
+/// How a provider is reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    /// An OpenAI-compatible chat completions API at `api_base`.
+    #[default]
+    Openai,
+    /// Scripted responses from the YAML `scenario` file, without network
+    /// (see [`crate::mock_provider`]).
+    Mock,
+}
+
+/// A built-in provider middleware.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MiddlewareConfig {
+    /// Add HTTP headers to every request.
+    Headers { headers: HashMap<String, String> },
+    /// Append scrubbed request/response payloads to a JSONL file
+    /// (default: `provider_log.jsonl` in the data dir).
+    Log {
+        #[serde(default)]
+        path: Option<PathBuf>,
+    },
+    /// Answer identical requests from memory.
+    Cache {
+        #[serde(default = "default_cache_ttl")]
+        ttl_secs: u64,
+        #[serde(default = "default_cache_entries")]
+        max_entries: usize,
+    },
+    /// Record responses to files keyed by a hash of the request, or replay
+    /// them instead of calling the provider (default dir: `cassettes` in
+    /// the data dir).
+    Cassette {
+        #[serde(default)]
+        dir: Option<PathBuf>,
+        #[serde(default)]
+        mode: CassetteMode,
+    },
+    /// Rewrite prompts before they are sent.
+    Rewrite {
+        /// Text appended to the system prompt.
+        #[serde(default)]
+        system_append: Option<String>,
+        /// Literal replacements applied to user messages.
+        #[serde(default)]
+        replace: HashMap<String, String>,
+    },
+}
+
+/// What a cassette middleware does with requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CassetteMode {
+    /// Call the provider and save every response, replacing older ones.
+    Record,
+    /// Answer from saved responses only; a request without one fails.
+    Replay,
+    /// Replay when a response is saved, otherwise call and record it.
+    #[default]
+    Auto,
+}
+
+impl std::str::FromStr for CassetteMode {
+    type Err = crate::error::AgentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "record" => Ok(Self::Record),
+            "replay" => Ok(Self::Replay),
+            "auto" => Ok(Self::Auto),
+            other => Err(crate::error::AgentError::Config(format!(
+                "Unknown cassette mode {other:?} (expected record, replay or auto)"
+            ))),
+        }
+    }
+}
+
+/// A content filter applied to every provider (`[[guardrails]]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardrailConfig {
+    /// Shown in warnings, errors and the audit log.
+    pub name: String,
+    /// Regular expressions to look for.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Words or phrases to look for, case-insensitively and as whole words.
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Built-in content policies: `secrets` (API keys, tokens, private
+    /// keys) and `pii` (emails, IP addresses, phone numbers, home paths).
+    #[serde(default)]
+    pub policies: Vec<ContentPolicy>,
+    #[serde(default)]
+    pub applies_to: GuardrailScope,
+    #[serde(default)]
+    pub action: GuardrailAction,
+    /// What `redact` puts in place of a match.
+    #[serde(default = "default_redaction")]
+    pub replacement: String,
+}
+
+/// A built-in set of patterns for [`GuardrailConfig::policies`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentPolicy {
+    Secrets,
+    Pii,
+}
+
+/// Which side of the conversation a guardrail checks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardrailScope {
+    /// User messages and tool results sent to the provider.
+    Input,
+    /// The model's answers and tool call arguments.
+    Output,
+    #[default]
+    Both,
+}
+
+/// What happens when a guardrail matches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardrailAction {
+    /// Refuse to send the prompt, or withhold the answer.
+    #[default]
+    Block,
+    /// Let it through and notify the user.
+    Warn,
+    /// Replace each match before it is sent or shown.
+    Redact,
+}
+
+fn default_redaction() -> String {
+    "[REDACTED]".to_string()
+}
+
+fn default_cache_ttl() -> u64 {
+    300
+}
+fn default_cache_entries() -> usize {
+    100
 }
 
 fn default_priority() -> u32 {
@@ -169,25 +404,81 @@ fn default_max_retries() -> u32 {
 
 /// A scheduled task entry in the `[[schedules]]` array.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ScheduleConfig {
     pub name: String,
     /// Cron expression (5-field standard or 7-field extended).
     pub cron: String,
     #[serde(default)]
     pub workspace: Option<String>,
+    /// Session the task runs in, resumed by name and created on first run
+    /// (default: `schedule:<name>`, so runs never land in the user's session).
+    #[serde(default)]
+    pub session: Option<String>,
+    /// Project directory for the run; overrides `[sandbox] workspace_root`
+    /// for file tools and project context.
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub workspace_root: Option<PathBuf>,
     #[serde(default = "default_schedule_task")]
     pub task: ScheduleTaskType,
-    /// Skill to load for heartbeat tasks (Phase 2).
+    /// Skill whose document heartbeat runs follow.
     #[serde(default)]
     pub skill: Option<String>,
-    /// Prompt text for prompt-type tasks.
+    /// Prompt text for prompt-type tasks. For heartbeats, the kickoff
+    /// prompt, in which `{schedule}`, `{skill}` and `{now}` are filled in.
     #[serde(default)]
     pub prompt: Option<String>,
+    /// Shell command custom tasks run through the sandbox.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// URL custom tasks POST to, after the command if there is one.
+    #[serde(default)]
+    pub webhook: Option<String>,
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+    /// This schedule's own quiet hours (`"HH:MM-HH:MM"`), or `"off"` for a
+    /// schedule that must run on time (default: `[scheduler] quiet_hours`).
+    #[serde(default)]
+    pub quiet_hours: Option<String>,
+}
+
+impl ScheduleConfig {
+    /// Name of the session this schedule's runs go to.
+    pub fn session_name(&self) -> String {
+        self.session
+            .clone()
+            .unwrap_or_else(|| format!("schedule:{}", self.name))
+    }
+}
+
+/// Scheduler storage (`[scheduler]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    /// Directory for `scheduler_state.json` and `scheduler_history.jsonl`
+    /// (default: data dir).
+    pub state_dir: Option<PathBuf>,
+    /// Run records kept across all schedules; older ones are dropped.
+    pub history_limit: usize,
+    /// Local time window, e.g. `"22:00-07:00"`, in which scheduled runs,
+    /// `notify_user` notifications below error level and goal webhooks
+    /// wait until it ends.
+    pub quiet_hours: Option<String>,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            state_dir: None,
+            history_limit: 1000,
+            quiet_hours: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum ScheduleTaskType {
     Heartbeat,
@@ -206,18 +497,38 @@ fn default_enabled() -> bool {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct SandboxConfig {
-    /// Sandbox mode: "docker" for isolated containers, "unsafe" for direct execution.
+    /// Sandbox mode: "docker" for isolated containers, "native" for
+    /// platform restrictions on the host, "unsafe" for direct execution.
     pub mode: SandboxMode,
     /// Docker image to use for sandboxed execution.
     pub docker_image: String,
     /// Execution timeout in seconds.
     pub timeout_secs: u64,
-    /// Maximum memory for Docker containers (in bytes).
+    /// Maximum memory for Docker containers and native runs (in bytes).
     pub memory_limit: Option<u64>,
     /// Working directory inside the sandbox.
     pub work_dir: String,
     /// If set, file tools are restricted to paths under this directory.
     pub workspace_root: Option<PathBuf>,
+    /// Named workspace roots a session can select instead, e.g.
+    /// `repo-a = "/home/me/src/repo-a"` (see [`crate::workspace`]).
+    pub workspaces: BTreeMap<String, PathBuf>,
+    /// Access by path pattern relative to each workspace root, e.g.
+    /// `"migrations/**" = "ro"` (see [`crate::path_rules`]).
+    pub paths: BTreeMap<String, PathAccess>,
+}
+
+/// What tools may do with the files a `[sandbox.paths]` rule matches.
+/// Ordered from least to most restrictive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathAccess {
+    /// Read and write.
+    Rw,
+    /// Read only.
+    Ro,
+    /// Neither; the files are hidden from listings.
+    Deny,
 }
 
 impl Default for SandboxConfig {
@@ -229,6 +540,8 @@ impl Default for SandboxConfig {
             memory_limit: Some(512 * 1024 * 1024), // 512MB
             work_dir: "/workspace".into(),
             workspace_root: None,
+            workspaces: BTreeMap::new(),
+            paths: BTreeMap::new(),
         }
     }
 }
@@ -237,6 +550,10 @@ impl Default for SandboxConfig {
 #[serde(rename_all = "lowercase")]
 pub enum SandboxMode {
     Docker,
+    /// Run on the host under the platform's own restrictions: a
+    /// `sandbox-exec` profile on macOS, a restricted token in a Job Object
+    /// on Windows, resource limits elsewhere.
+    Native,
     Unsafe,
 }
 
@@ -284,11 +601,29 @@ pub struct ServerConfig {
     pub port: u16,
     /// Bearer token for authentication (None = no auth).
     pub auth_token: Option<String>,
+    /// Bearer token with admin scope. Enables the GraphQL admin API at
+    /// `/v1/admin/graphql`, which `auth_token` does not grant. API keys
+    /// with `admin = true` have the same scope.
+    pub admin_token: Option<String>,
     /// Enable CORS.
     pub cors: bool,
     /// Allowed CORS origins when auth is enabled.
     /// Defaults to `["http://localhost:{port}"]` if empty.
     pub cors_origins: Vec<String>,
+    /// Maximum request body size for file uploads, in bytes.
+    pub max_upload_bytes: usize,
+    /// Maximum request body size for other API requests, in bytes.
+    pub max_body_bytes: usize,
+    /// Where UI preferences are stored (default: `<data_dir>/ui-preferences.json`).
+    pub preferences_path: Option<PathBuf>,
+    /// Serve the API on this Unix domain socket (Windows: named pipe such as
+    /// `\\.\pipe\agent-shell`) instead of `host:port`. Requests over the
+    /// socket skip bearer-token auth; file permissions restrict access.
+    pub listen_socket: Option<PathBuf>,
+    /// Named API keys (`[[server.api_keys]]`). Sessions created with a key
+    /// belong to it: other keys can neither list nor open them, unless
+    /// they have admin scope.
+    pub api_keys: Vec<ApiKeyConfig>,
 }
 
 impl Default for ServerConfig {
@@ -297,12 +632,196 @@ impl Default for ServerConfig {
             host: "127.0.0.1".into(),
             port: 8080,
             auth_token: None,
+            admin_token: None,
             cors: true,
             cors_origins: Vec::new(),
+            max_upload_bytes: 25 * 1024 * 1024, // 25MB
+            max_body_bytes: 2 * 1024 * 1024,    // 2MB
+            preferences_path: None,
+            listen_socket: None,
+            api_keys: Vec::new(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Whether requests need a bearer token: `auth_token` or an API key is set.
+    pub fn auth_enabled(&self) -> bool {
+        self.auth_token.is_some() || self.api_keys.iter().any(|k| k.resolve_key().is_some())
+    }
+
+    /// Whether anything has admin scope: `admin_token` or an admin API key.
+    pub fn admin_enabled(&self) -> bool {
+        self.admin_token.is_some()
+            || self.api_keys.iter().any(|k| k.admin && k.resolve_key().is_some())
+    }
+}
+
+/// One named API key (`[[server.api_keys]]`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiKeyConfig {
+    /// Owner recorded on the sessions this key creates.
+    pub name: String,
+    /// The bearer token.
+    pub key: Option<String>,
+    /// Environment variable holding the token, used when `key` is unset.
+    pub key_env: Option<String>,
+    /// Sees and manages every session, like the legacy `auth_token`, and
+    /// may use the admin API like `admin_token`.
+    pub admin: bool,
+}
+
+impl ApiKeyConfig {
+    /// The bearer token, from `key` or `key_env`.
+    pub fn resolve_key(&self) -> Option<String> {
+        self.key
+            .clone()
+            .or_else(|| self.key_env.as_ref().and_then(|var| std::env::var(var).ok()))
+            .filter(|key| !key.is_empty())
+    }
+}
+
+/// Usage goals tracked by analytics (`[analytics]`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalyticsConfig {
+    /// Goals shown on the dashboard and in reports (`[[analytics.goals]]`).
+    pub goals: Vec<GoalConfig>,
+    /// URL that gets a JSON POST when a goal is missed for a finished period.
+    pub webhook_url: Option<String>,
+}
+
+/// Encrypted backups of sessions, skills and config (`[backup]`).
+///
+/// Backups are tar.gz archives encrypted with a key derived from
+/// `passphrase`; without a passphrase no backup is written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// Where backups are stored (`[backup.remote]`).
+    pub remote: Option<BackupRemote>,
+    /// Back up this often while `agent-shell serve` runs; only on demand
+    /// (`agent-shell backup now`) when unset.
+    pub interval_hours: Option<u32>,
+    /// Backups kept on the remote; older ones are deleted.
+    pub keep: usize,
+    pub passphrase: Option<String>,
+    /// Environment variable holding the passphrase, used when `passphrase`
+    /// is unset.
+    pub passphrase_env: Option<String>,
+    pub sessions: bool,
+    pub skills: bool,
+    pub config: bool,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            remote: None,
+            interval_hours: None,
+            keep: 7,
+            passphrase: None,
+            passphrase_env: None,
+            sessions: true,
+            skills: true,
+            config: true,
         }
     }
 }
 
+impl BackupConfig {
+    /// The encryption passphrase, from `passphrase` or `passphrase_env`.
+    pub fn resolve_passphrase(&self) -> Option<String> {
+        self.passphrase.clone().or_else(|| {
+            self.passphrase_env
+                .as_ref()
+                .and_then(|var| std::env::var(var).ok())
+        })
+    }
+}
+
+/// A backup destination.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BackupRemote {
+    /// An S3-compatible bucket (AWS, MinIO, R2, ...), addressed path-style.
+    S3 {
+        /// e.g. `https://s3.us-east-1.amazonaws.com` or `http://minio:9000`.
+        endpoint: String,
+        bucket: String,
+        #[serde(default = "default_s3_region")]
+        region: String,
+        /// Key prefix for backup objects, e.g. `agent-shell/`.
+        #[serde(default)]
+        prefix: String,
+        access_key_id: String,
+        #[serde(default)]
+        secret_access_key: Option<String>,
+        /// Environment variable holding the secret key, used when
+        /// `secret_access_key` is unset.
+        #[serde(default)]
+        secret_access_key_env: Option<String>,
+    },
+    /// A WebDAV collection (Nextcloud, ownCloud, Apache, ...).
+    WebDav {
+        /// Collection URL, e.g. `https://cloud.example.com/remote.php/dav/files/me/backups/`.
+        url: String,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(default)]
+        password: Option<String>,
+        /// Environment variable holding the password, used when `password`
+        /// is unset.
+        #[serde(default)]
+        password_env: Option<String>,
+    },
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".into()
+}
+
+/// A target for one usage metric over a day, week or month, e.g.
+/// `metric = "tool_error_rate"`, `max = 5.0` for "at most 5% tool errors".
+/// At least one of `min` and `max` should be set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoalConfig {
+    pub name: String,
+    pub metric: GoalMetric,
+    #[serde(default)]
+    pub period: GoalPeriod,
+    /// The goal is met when the metric is at least this.
+    #[serde(default)]
+    pub min: Option<f64>,
+    /// The goal is met when the metric is at most this.
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalMetric {
+    /// Failed tool calls as a percentage of all tool calls.
+    ToolErrorRate,
+    /// Sessions of 30 minutes or more.
+    DeepWorkSessions,
+    Sessions,
+    Messages,
+    ToolCalls,
+    ActiveHours,
+    ActiveDays,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalPeriod {
+    Day,
+    #[default]
+    Week,
+    Month,
+}
+
 /// Session persistence configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -313,6 +832,38 @@ pub struct SessionConfig {
     pub max_history: usize,
     /// Automatically save sessions on each message.
     pub auto_save: bool,
+    /// Where agent presets created through the API are stored.
+    pub agents_path: Option<PathBuf>,
+    /// Estimated cost per session, in US dollars, that the UI warns about
+    /// approaching.
+    pub budget_usd: Option<f64>,
+    /// Tokens per session that the UI warns about approaching.
+    pub budget_tokens: Option<u64>,
+    /// Prune sessions not updated for this many days.
+    pub retention_days: Option<u32>,
+    /// Keep at most this many sessions, pruning the least recently updated.
+    pub max_sessions: Option<usize>,
+    /// What pruning does with a session.
+    pub prune_action: PruneAction,
+}
+
+impl SessionConfig {
+    /// Whether a retention limit is configured.
+    pub fn has_retention(&self) -> bool {
+        self.retention_days.is_some() || self.max_sessions.is_some()
+    }
+}
+
+/// What happens to sessions a retention limit removes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PruneAction {
+    /// Move to `archive/` in the sessions directory. Archived sessions are
+    /// no longer listed but still count in analytics.
+    #[default]
+    Archive,
+    /// Remove for good, with their file snapshots.
+    Delete,
 }
 
 impl Default for SessionConfig {
@@ -321,6 +872,428 @@ impl Default for SessionConfig {
             history_dir: None, // resolved at runtime to data_dir/sessions
             max_history: 100,
             auto_save: true,
+            agents_path: None, // resolved at runtime to data_dir/agents.json
+            budget_usd: None,
+            budget_tokens: None,
+            retention_days: None,
+            max_sessions: None,
+            prune_action: PruneAction::Archive,
+        }
+    }
+}
+
+/// Configuration for "ask the agent about this error" on failed shell commands.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ErrorAssistConfig {
+    /// Surface failed terminal commands and allow seeding a session from them.
+    pub enabled: bool,
+    /// Number of preceding commands to include as context.
+    pub context_commands: usize,
+    /// Maximum bytes of captured terminal output to include.
+    pub max_output_bytes: usize,
+}
+
+impl Default for ErrorAssistConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            context_commands: 5,
+            max_output_bytes: 4096,
+        }
+    }
+}
+
+/// Per-project memory notes (see [`crate::context::ProjectNotes`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemoryConfig {
+    /// Load the notes file into the system prompt and offer `memory_write`.
+    pub enabled: bool,
+    /// Notes file name at the project root.
+    pub file_name: String,
+    /// Maximum bytes of notes included in the system prompt.
+    pub max_bytes: usize,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            file_name: "AGENT.md".into(),
+            max_bytes: 16 * 1024,
+        }
+    }
+}
+
+/// Project context included in the system prompt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextConfig {
+    /// List detected environments (see [`crate::environments`]).
+    pub environments: bool,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self { environments: true }
+    }
+}
+
+/// Read-only Kubernetes inspection (`k8s_inspect`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KubernetesConfig {
+    /// Register the `k8s_inspect` tool.
+    pub enabled: bool,
+    /// kubectl binary.
+    pub kubectl: String,
+    /// Kube context to use; the current context when unset.
+    pub context: Option<String>,
+    /// Namespaces the agent may inspect; any namespace when empty. The
+    /// first entry is the default when a request names none.
+    pub namespaces: Vec<String>,
+    pub timeout_secs: u64,
+    /// Upper bound for `logs` tail lines.
+    pub max_log_lines: u32,
+}
+
+impl Default for KubernetesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kubectl: "kubectl".into(),
+            context: None,
+            namespaces: Vec::new(),
+            timeout_secs: 30,
+            max_log_lines: 500,
+        }
+    }
+}
+
+/// Optional tools that are off unless enabled, and how tool calls are checked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolsConfig {
+    /// Register `clipboard_read` and `clipboard_write` (desktop sessions).
+    pub clipboard: bool,
+    /// Register `screen_capture` (screenshots attached for vision models).
+    pub screen_capture: bool,
+    /// Also show `notify_user` messages as desktop notifications (REPL and
+    /// server). The web UI gets them as toasts either way.
+    pub desktop_notifications: bool,
+    /// Start the REPL asking before every tool call, showing its arguments;
+    /// `/approval` toggles it per run.
+    pub require_approval: bool,
+    /// How many times per turn the model may retry a tool after its
+    /// arguments fail schema validation before the turn ends with the error.
+    pub argument_retries: usize,
+    /// Tools to leave out, by name or ID (`builtin.shell_exec`). Disabling
+    /// a built-in lets a plugin tool take its name.
+    pub disabled: Vec<String>,
+    /// Offer only tools that read: file writes, code execution and plugin
+    /// tools are off, and `shell_exec` refuses commands that would change
+    /// files or the repository. Also set by `--read-only`.
+    pub read_only: bool,
+    /// Extra names for tools: alias to tool name or ID.
+    pub aliases: HashMap<String, String>,
+    /// Call limits by tool name or ID, e.g. `shell_exec = { per_session = 50 }`.
+    pub quotas: HashMap<String, ToolQuota>,
+}
+
+/// Outbound HTTP for `web_fetch` and model providers (see
+/// [`crate::network`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Proxy URL: `http://`, `https://`, `socks5://` or `socks5h://` (the
+    /// proxy resolves names). Credentials may go in the URL.
+    pub proxy: Option<String>,
+    /// Hosts reached without the proxy: names, `.suffix` domains, IPs or
+    /// CIDR ranges.
+    pub no_proxy: Vec<String>,
+    /// DNS-over-HTTPS endpoint with the JSON API, e.g.
+    /// `https://cloudflare-dns.com/dns-query`.
+    pub doh_url: Option<String>,
+}
+
+/// The `web_fetch` tool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebFetchConfig {
+    /// Keep fetched pages on disk and revalidate them with their ETag or
+    /// Last-Modified date instead of downloading them again. Off unless
+    /// set; pages stay in `cache_dir` until replaced or deleted.
+    pub cache: bool,
+    /// Seconds a cached page is returned without asking the site again.
+    pub cache_ttl_secs: u64,
+    /// Directory for cached pages (default: `web_cache` in the data dir).
+    pub cache_dir: Option<PathBuf>,
+    /// Refuse pages a site's robots.txt disallows for `agent-shell`.
+    pub respect_robots_txt: bool,
+    /// Requests to one domain in any minute; 0 for no limit.
+    pub requests_per_minute: u32,
+    /// Requests to one domain in flight at once; 0 for no limit.
+    pub max_concurrent_per_domain: usize,
+}
+
+impl Default for WebFetchConfig {
+    fn default() -> Self {
+        Self {
+            cache: false,
+            cache_ttl_secs: 900,
+            cache_dir: None,
+            respect_robots_txt: true,
+            requests_per_minute: 30,
+            max_concurrent_per_domain: 2,
+        }
+    }
+}
+
+/// How often one tool may be called. Calls past a limit are refused with
+/// an error the model sees.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolQuota {
+    /// Calls per session, counting earlier turns.
+    pub per_session: Option<u32>,
+    /// Calls per local calendar day across all sessions and every process
+    /// sharing the data directory.
+    pub per_day: Option<u32>,
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            clipboard: false,
+            screen_capture: false,
+            desktop_notifications: true,
+            require_approval: false,
+            argument_retries: 2,
+            disabled: Vec::new(),
+            read_only: false,
+            aliases: HashMap::new(),
+            quotas: HashMap::new(),
+        }
+    }
+}
+
+/// Agent pipeline stage a hook runs at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStage {
+    /// Before the turn's messages are sent to the model.
+    PrePrompt,
+    /// After each model response.
+    PostLlm,
+    /// Before each tool call; may rewrite its arguments or veto it.
+    PreTool,
+    /// After each tool call.
+    PostTool,
+    /// When a turn fails.
+    OnError,
+}
+
+/// A command run at a pipeline stage (`[[hooks]]`).
+///
+/// The command gets a JSON description of the stage on stdin and may print
+/// a JSON object with replacements (see `hooks::CommandHook`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookConfig {
+    pub stage: HookStage,
+    /// Shell command (`sh -c`, `cmd /C` on Windows).
+    pub command: String,
+    /// Tools the hook applies to at `pre_tool`/`post_tool` (empty: all).
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default = "default_hook_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_hook_timeout() -> u64 {
+    10
+}
+
+/// Plugin discovery and installation (`agent-shell plugin`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginsConfig {
+    /// HTTPS URL of the JSON plugin index searched by `plugin search`.
+    pub index_url: Option<String>,
+    /// Where downloaded plugins and `installed.json` live (default: data dir).
+    pub dir: Option<PathBuf>,
+}
+
+impl PluginsConfig {
+    /// Directory holding installed plugins.
+    pub fn dir(&self) -> PathBuf {
+        self.dir
+            .clone()
+            .unwrap_or_else(|| AppConfig::data_dir().join("plugins"))
+    }
+}
+
+/// Remote command execution over SSH (`ssh_exec`).
+///
+/// Only hosts listed under `[ssh.hosts.<name>]` are reachable; the agent
+/// refers to them by name. Authentication uses the user's OpenSSH setup
+/// (agent, keys, `~/.ssh/config`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SshConfig {
+    /// Register the `ssh_exec` tool.
+    pub enabled: bool,
+    /// ssh binary.
+    pub ssh: String,
+    pub timeout_secs: u64,
+    pub hosts: HashMap<String, SshHostConfig>,
+}
+
+impl Default for SshConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ssh: "ssh".into(),
+            timeout_secs: 60,
+            hosts: HashMap::new(),
+        }
+    }
+}
+
+/// One allowlisted host and its command policy.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SshHostConfig {
+    /// Hostname, IP or `~/.ssh/config` alias.
+    pub host: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<PathBuf>,
+    /// Command patterns (`*` matches anything) the agent may run; any
+    /// command when empty. With a non-empty list, commands may not chain
+    /// or redirect (`;`, `&&`, `|`, `>`, `$(...)`).
+    pub allow: Vec<String>,
+    /// Command patterns that are always refused.
+    pub deny: Vec<String>,
+}
+
+/// A remote agent-shell instance that tasks can be delegated to through
+/// its `/v1/runs` API (`[remote_agents.<name>]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteAgentConfig {
+    /// Base URL of the instance, e.g. `http://homelab:8080`.
+    pub url: String,
+    /// The instance's `server.auth_token`.
+    pub token: Option<String>,
+    /// Environment variable holding the token, used when `token` is unset.
+    pub token_env: Option<String>,
+    /// Agent preset on the remote instance to run tasks with.
+    pub agent: Option<String>,
+    /// Shown to the model to help it choose a peer, e.g. "GPU box for builds".
+    pub description: Option<String>,
+    pub timeout_secs: u64,
+}
+
+impl Default for RemoteAgentConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            token: None,
+            token_env: None,
+            agent: None,
+            description: None,
+            timeout_secs: 1800,
+        }
+    }
+}
+
+impl RemoteAgentConfig {
+    /// The bearer token, from `token` or `token_env`.
+    pub fn resolve_token(&self) -> Option<String> {
+        self.token.clone().or_else(|| {
+            self.token_env
+                .as_ref()
+                .and_then(|var| std::env::var(var).ok())
+        })
+    }
+}
+
+/// Voice input/output via OpenAI-compatible audio endpoints.
+///
+/// Transcription and speech are each enabled by setting their URL; the
+/// server proxies requests so the browser never sees `api_key`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// Base URL of a Whisper-compatible server, e.g. `http://localhost:8000/v1`
+    /// (requests go to `<url>/audio/transcriptions`).
+    pub transcription_url: Option<String>,
+    pub transcription_model: String,
+    /// Base URL of a text-to-speech server (requests go to `<url>/audio/speech`).
+    pub speech_url: Option<String>,
+    pub speech_model: String,
+    pub voice: String,
+    /// Bearer token for the audio servers, if they require one.
+    pub api_key: Option<String>,
+    /// Largest recording accepted for transcription, in bytes.
+    pub max_upload_bytes: usize,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            transcription_url: None,
+            transcription_model: "whisper-1".into(),
+            speech_url: None,
+            speech_model: "tts-1".into(),
+            voice: "alloy".into(),
+            api_key: None,
+            max_upload_bytes: 25 * 1024 * 1024, // 25MB
+        }
+    }
+}
+
+/// API spoken by the `[images]` endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImageBackend {
+    /// OpenAI-compatible `POST <url>/images/generations`.
+    #[default]
+    Openai,
+    /// Stable Diffusion WebUI (AUTOMATIC1111) `POST <url>/sdapi/v1/txt2img`.
+    SdWebui,
+}
+
+/// Image generation for the `image_generate` tool, registered when `url`
+/// is set. Results are saved into the workspace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImagesConfig {
+    pub backend: ImageBackend,
+    /// Base URL, e.g. `https://api.openai.com/v1` or `http://127.0.0.1:7860`.
+    pub url: Option<String>,
+    /// Model name sent to OpenAI-compatible endpoints.
+    pub model: String,
+    /// Bearer token for the endpoint, if it requires one.
+    pub api_key: Option<String>,
+    /// Default image size, `WIDTHxHEIGHT`.
+    pub size: String,
+    /// Where images are saved, relative to the workspace root.
+    pub output_dir: PathBuf,
+    pub timeout_secs: u64,
+}
+
+impl Default for ImagesConfig {
+    fn default() -> Self {
+        Self {
+            backend: ImageBackend::Openai,
+            url: None,
+            model: "gpt-image-1".into(),
+            api_key: None,
+            size: "1024x1024".into(),
+            output_dir: PathBuf::from(".agent-shell/images"),
+            timeout_secs: 180,
         }
     }
 }
@@ -457,6 +1430,100 @@ working_dir = "/home/user/personal"
         assert!(config.profiles.is_empty());
     }
 
+    #[test]
+    fn test_analytics_goals_deserialize() {
+        let config: AppConfig = toml::from_str(
+            r#"
+[analytics]
+webhook_url = "https://hooks.example.com/goals"
+
+[[analytics.goals]]
+name = "Few tool errors"
+metric = "tool_error_rate"
+max = 5.0
+
+[[analytics.goals]]
+name = "Deep work"
+metric = "deep_work_sessions"
+period = "month"
+min = 12
+"#,
+        )
+        .unwrap();
+        let goals = &config.analytics.goals;
+        assert_eq!(goals.len(), 2);
+        assert_eq!(goals[0].metric, GoalMetric::ToolErrorRate);
+        assert_eq!(goals[0].period, GoalPeriod::Week);
+        assert_eq!(goals[1].min, Some(12.0));
+        assert!(AppConfig::default().analytics.goals.is_empty());
+    }
+
+    #[test]
+    fn test_backup_remote_deserializes() {
+        let config: AppConfig = toml::from_str(
+            r#"
+[backup]
+interval_hours = 24
+passphrase_env = "BACKUP_PASSPHRASE"
+skills = false
+
+[backup.remote]
+type = "s3"
+endpoint = "http://minio:9000"
+bucket = "backups"
+access_key_id = "minio"
+secret_access_key = "minio123"
+"#,
+        )
+        .unwrap();
+        let backup = &config.backup;
+        assert_eq!(backup.interval_hours, Some(24));
+        assert_eq!(backup.keep, 7);
+        assert!(backup.sessions && !backup.skills);
+        match backup.remote.as_ref().unwrap() {
+            BackupRemote::S3 { region, prefix, .. } => {
+                assert_eq!(region, "us-east-1");
+                assert!(prefix.is_empty());
+            }
+            other => panic!("unexpected remote {other:?}"),
+        }
+
+        let remote: BackupRemote =
+            toml::from_str("type = \"webdav\"\nurl = \"https://dav.example.com/b/\"").unwrap();
+        assert!(matches!(remote, BackupRemote::WebDav { .. }));
+    }
+
+    #[test]
+    fn test_server_api_keys_deserialize() {
+        let config: AppConfig = toml::from_str(
+            r#"
+[[server.api_keys]]
+name = "alice"
+key = "alice-key"
+
+[[server.api_keys]]
+name = "ops"
+key_env = "AGENT_SHELL_TEST_UNSET_KEY"
+admin = true
+"#,
+        )
+        .unwrap();
+        let keys = &config.server.api_keys;
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].resolve_key().as_deref(), Some("alice-key"));
+        assert!(!keys[0].admin);
+        assert!(keys[1].admin);
+        assert_eq!(keys[1].resolve_key(), None);
+        assert!(AppConfig::default().server.api_keys.is_empty());
+    }
+
+    #[test]
+    fn test_error_assist_disabled_by_default() {
+        let config: AppConfig = toml::from_str("").unwrap();
+        assert!(!config.error_assist.enabled);
+        assert_eq!(config.error_assist.context_commands, 5);
+    }
+
     #[test]
     fn test_empty_providers_uses_single_provider() {
         let toml_str = r#"
@@ -2,6 +2,10 @@
 //!
 //! Detects project types from file markers, extracts git metadata,
 //! and links sessions to their project context.
+//!
+//! Each project may keep a notes file (`AGENT.md` by default) at its root:
+//! durable facts such as "this repo uses pnpm" that the agent loop adds to
+//! the system prompt and the `memory_write` tool appends to.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -97,6 +101,67 @@ pub struct GitContext {
     pub untracked: usize,
 }
 
+/// Notes file kept at a project root and loaded into the system prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectNotes {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+impl ProjectNotes {
+    /// The notes as a system prompt section.
+    pub fn prompt_section(&self) -> String {
+        format!(
+            "## Project notes ({})\n\nDurable facts recorded for this project. \
+             Use the memory_write tool to add new ones.\n\n{}",
+            self.path.display(),
+            self.content.trim()
+        )
+    }
+}
+
+/// Heading written when `append_project_note` creates a notes file.
+const PROJECT_NOTES_HEADING: &str = "# Project notes\n\n";
+
+/// Append `fact` to a notes file as a `- ` bullet, creating the file if
+/// needed. Returns the contents before and after; they are equal when the
+/// fact was already recorded.
+pub fn append_project_note(path: &Path, fact: &str) -> std::io::Result<(String, String)> {
+    let before = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let line = format!("- {}", fact.split_whitespace().collect::<Vec<_>>().join(" "));
+    if before.lines().any(|l| l.trim() == line) {
+        return Ok((before.clone(), before));
+    }
+
+    let mut after = if before.trim().is_empty() {
+        PROJECT_NOTES_HEADING.to_string()
+    } else {
+        before.clone()
+    };
+    if !after.ends_with('\n') {
+        after.push('\n');
+    }
+    after.push_str(&line);
+    after.push('\n');
+    std::fs::write(path, &after)?;
+    Ok((before, after))
+}
+
+/// Which changes [`ContextLinker::get_git_diff`] returns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffTarget {
+    /// Staged and unstaged changes against `HEAD`.
+    Uncommitted,
+    /// Changes staged in the index.
+    Staged,
+    /// A revision range such as `main..HEAD`.
+    Range(String),
+}
+
 /// Project detection markers — (filename_or_extension, ProjectType).
 const PROJECT_MARKERS: &[(&str, ProjectType)] = &[
     ("Cargo.toml", ProjectType::Rust),
@@ -291,6 +356,33 @@ impl ContextLinker {
         })
     }
 
+    /// Get a unified diff for `target`. Errors carry git's message (e.g.
+    /// not a repository, unknown revision).
+    pub fn get_git_diff(dir: &Path, target: &DiffTarget) -> Result<String, String> {
+        let mut args = vec!["diff", "--no-color", "--no-ext-diff"];
+        match target {
+            DiffTarget::Uncommitted => args.push("HEAD"),
+            DiffTarget::Staged => args.push("--cached"),
+            DiffTarget::Range(range) => {
+                if range.starts_with('-') || range.trim().is_empty() {
+                    return Err(format!("Invalid revision range: {}", range));
+                }
+                args.push(range);
+            }
+        }
+        let output = std::process::Command::new("git")
+            .args(&args)
+            .args(["--"])
+            .current_dir(dir)
+            .output()
+            .map_err(|e| format!("Failed to run git: {}", e))?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+
     /// Get enriched git context using `git2` via [`GitTracker`].
     ///
     /// Falls back to the CLI-based [`get_git_context`] when git2 fails to
@@ -339,6 +431,37 @@ impl ContextLinker {
     pub fn get_project_by_path(&self, path: &Path) -> Option<&Project> {
         self.project_cache.get(path)
     }
+
+    /// Path of the notes file for the project containing `directory`,
+    /// whether or not it exists yet. `None` when no project is detected.
+    pub fn project_notes_path(&mut self, directory: &Path, file_name: &str) -> Option<PathBuf> {
+        self.detect_project(directory)
+            .map(|project| project.path.join(file_name))
+    }
+
+    /// Load the notes file for the project containing `directory`, truncated
+    /// to `max_bytes`. `None` when there is no project or no (non-empty) file.
+    pub fn load_project_notes(
+        &mut self,
+        directory: &Path,
+        file_name: &str,
+        max_bytes: usize,
+    ) -> Option<ProjectNotes> {
+        let path = self.project_notes_path(directory, file_name)?;
+        let mut content = std::fs::read_to_string(&path).ok()?;
+        if content.trim().is_empty() {
+            return None;
+        }
+        if content.len() > max_bytes {
+            let mut end = max_bytes;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            content.truncate(end);
+            content.push_str("\n[... truncated]");
+        }
+        Some(ProjectNotes { path, content })
+    }
 }
 
 impl Default for ContextLinker {
@@ -465,10 +588,39 @@ mod tests {
         assert_eq!(ProjectType::Go.display_name(), "Go");
     }
 
+    #[test]
+    fn test_project_notes_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+        let sub = dir.path().join("src");
+        std::fs::create_dir(&sub).unwrap();
+
+        let mut linker = ContextLinker::new();
+        assert!(linker.load_project_notes(&sub, "AGENT.md", 1024).is_none());
+        let path = linker.project_notes_path(&sub, "AGENT.md").unwrap();
+        assert_eq!(path, dir.path().join("AGENT.md"));
+
+        let (before, after) = append_project_note(&path, "this repo uses\npnpm").unwrap();
+        assert_eq!(before, "");
+        assert_eq!(after, "# Project notes\n\n- this repo uses pnpm\n");
+        let (before, after) = append_project_note(&path, "this repo uses pnpm").unwrap();
+        assert_eq!(before, after);
+
+        let notes = linker.load_project_notes(&sub, "AGENT.md", 1024).unwrap();
+        assert!(notes.prompt_section().contains("- this repo uses pnpm"));
+        let notes = linker.load_project_notes(&sub, "AGENT.md", 8).unwrap();
+        assert_eq!(notes.content, "# Projec\n[... truncated]");
+    }
+
     #[test]
     fn test_git_context_non_repo() {
         let dir = TempDir::new().unwrap();
         let ctx = ContextLinker::get_git_context(dir.path());
         assert!(ctx.is_none());
+        assert!(ContextLinker::get_git_diff(dir.path(), &DiffTarget::Staged).is_err());
+        let range = DiffTarget::Range("--output=/tmp/x".into());
+        assert!(ContextLinker::get_git_diff(dir.path(), &range)
+            .unwrap_err()
+            .starts_with("Invalid revision range"));
     }
 }
@@ -0,0 +1,280 @@
+//! Fine-tuning datasets from saved sessions (`agent-shell dataset export`).
+//!
+//! Each session becomes one training example, as an OpenAI chat
+//! fine-tuning line (`{"messages": [...]}` with `tool_calls` and `tool`
+//! messages) or a ShareGPT conversation (`human`, `gpt`, `function_call`
+//! and `observation` turns). Text is passed through a [`SecretScrubber`]
+//! first, and images are left out.
+
+use crate::scrubber::SecretScrubber;
+use crate::session::Session;
+use crate::tool_quota::QUOTA_EXCEEDED;
+use crate::types::{Message, Role, ToolCall};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+/// How the tool results of failed calls start.
+const FAILED_RESULT_PREFIXES: &[&str] = &[
+    "Error: ",
+    "Tool not allowed",
+    "Tool not found",
+    "Tool disabled",
+    "Invalid arguments",
+    QUOTA_EXCEEDED,
+];
+
+/// Which sessions to export.
+#[derive(Debug, Clone, Default)]
+pub struct DatasetFilter {
+    /// Keep sessions with any of these tags; empty keeps all.
+    pub tags: Vec<String>,
+    /// Keep sessions last updated at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Keep sessions last updated before this time.
+    pub until: Option<DateTime<Utc>>,
+    /// Keep only sessions that [look successful](looks_successful).
+    pub successful: bool,
+}
+
+impl DatasetFilter {
+    pub fn matches(&self, session: &Session) -> bool {
+        (self.tags.is_empty() || session.tags.iter().any(|t| self.tags.contains(t)))
+            && self.since.is_none_or(|t| session.updated_at >= t)
+            && self.until.is_none_or(|t| session.updated_at < t)
+            && (!self.successful || looks_successful(session))
+    }
+}
+
+/// Whether a session reads like a task that went well: it ends with a
+/// non-empty answer, no tool call failed and the agent was never stopped.
+pub fn looks_successful(session: &Session) -> bool {
+    let ends_with_answer = session
+        .messages
+        .last()
+        .is_some_and(|m| m.role == Role::Assistant && !m.content.trim().is_empty());
+    let failed = session.messages.iter().any(|m| match m.role {
+        Role::Tool => FAILED_RESULT_PREFIXES
+            .iter()
+            .any(|p| m.content.trim_start().starts_with(p)),
+        Role::Assistant => m.content.starts_with("[Stopped"),
+        _ => false,
+    });
+    ends_with_answer && !failed
+}
+
+/// Redacts text when a scrubber is given.
+struct Redact<'a>(Option<&'a SecretScrubber>);
+
+impl Redact<'_> {
+    fn text(&self, text: &str) -> String {
+        match self.0 {
+            Some(scrubber) => scrubber.scrub(text).into_owned(),
+            None => text.to_string(),
+        }
+    }
+
+    /// Tool arguments with their string values redacted, so the JSON stays
+    /// valid.
+    fn arguments(&self, arguments: &str) -> String {
+        fn walk(value: &mut Value, redact: &Redact) {
+            match value {
+                Value::String(s) => *s = redact.text(s),
+                Value::Array(items) => items.iter_mut().for_each(|v| walk(v, redact)),
+                Value::Object(map) => map.values_mut().for_each(|v| walk(v, redact)),
+                _ => {}
+            }
+        }
+        match serde_json::from_str::<Value>(arguments) {
+            Ok(mut value) => {
+                walk(&mut value, self);
+                value.to_string()
+            }
+            Err(_) => self.text(arguments),
+        }
+    }
+
+    fn call(&self, call: &ToolCall) -> Value {
+        json!({
+            "id": call.id,
+            "type": "function",
+            "function": {"name": call.name, "arguments": self.arguments(&call.arguments)},
+        })
+    }
+}
+
+/// Messages worth training on: a session needs at least one user message
+/// and one assistant answer.
+fn conversation(session: &Session) -> Option<&[Message]> {
+    let messages = &session.messages[..];
+    let has_user = messages.iter().any(|m| m.role == Role::User);
+    let has_answer = messages
+        .iter()
+        .any(|m| m.role == Role::Assistant && !m.content.trim().is_empty());
+    (has_user && has_answer).then_some(messages)
+}
+
+/// The session as an OpenAI chat fine-tuning example, or `None` if it has
+/// no exchange to learn from.
+pub fn openai_example(session: &Session, scrubber: Option<&SecretScrubber>) -> Option<Value> {
+    let redact = Redact(scrubber);
+    let messages: Vec<Value> = conversation(session)?
+        .iter()
+        .map(|m| match m.role {
+            Role::System => json!({"role": "system", "content": redact.text(&m.content)}),
+            Role::User => json!({"role": "user", "content": redact.text(&m.content)}),
+            Role::Assistant => {
+                let mut message = json!({"role": "assistant", "content": redact.text(&m.content)});
+                if let Some(calls) = m.tool_calls.as_ref().filter(|c| !c.is_empty()) {
+                    message["tool_calls"] = calls.iter().map(|c| redact.call(c)).collect();
+                }
+                message
+            }
+            Role::Tool => json!({
+                "role": "tool",
+                "tool_call_id": m.tool_call_id.clone().unwrap_or_default(),
+                "content": redact.text(&m.content),
+            }),
+        })
+        .collect();
+    Some(json!({ "messages": messages }))
+}
+
+/// The session as a ShareGPT conversation, or `None` if it has no exchange
+/// to learn from. ShareGPT turns alternate strictly, so text an assistant
+/// wrote alongside tool calls is dropped and the calls become one
+/// `function_call` turn.
+pub fn sharegpt_example(session: &Session, scrubber: Option<&SecretScrubber>) -> Option<Value> {
+    let redact = Redact(scrubber);
+    let mut system = Vec::new();
+    let mut turns = Vec::new();
+    for m in conversation(session)? {
+        let (from, value) = match m.role {
+            Role::System => {
+                system.push(redact.text(&m.content));
+                continue;
+            }
+            Role::User => ("human", redact.text(&m.content)),
+            Role::Assistant => match m.tool_calls.as_ref().filter(|c| !c.is_empty()) {
+                Some(calls) => {
+                    let calls: Vec<Value> = calls
+                        .iter()
+                        .map(|c| {
+                            let arguments = redact.arguments(&c.arguments);
+                            json!({
+                                "name": c.name,
+                                "arguments": serde_json::from_str::<Value>(&arguments)
+                                    .unwrap_or(Value::String(arguments)),
+                            })
+                        })
+                        .collect();
+                    let value = match calls.as_slice() {
+                        [one] => one.to_string(),
+                        _ => Value::Array(calls).to_string(),
+                    };
+                    ("function_call", value)
+                }
+                None => ("gpt", redact.text(&m.content)),
+            },
+            Role::Tool => ("observation", redact.text(&m.content)),
+        };
+        turns.push(json!({ "from": from, "value": value }));
+    }
+    let mut example = json!({ "id": session.id, "conversations": turns });
+    if !system.is_empty() {
+        example["system"] = Value::String(system.join("\n\n"));
+    }
+    Some(example)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::session::SessionManager;
+
+    fn session(messages: Vec<Message>) -> Session {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config = AppConfig::default();
+        config.session.history_dir = Some(dir.path().to_path_buf());
+        let mut sessions = SessionManager::new(&config).unwrap();
+        sessions.create_session("s").unwrap();
+        for m in messages {
+            sessions.push_message(m).unwrap();
+        }
+        sessions.active_session().unwrap().clone()
+    }
+
+    fn tool_session(result: &str) -> Session {
+        session(vec![
+            Message::user("email me at jane@example.com"),
+            Message::assistant_with_tool_calls(
+                "Reading it.",
+                vec![ToolCall {
+                    id: "call_1".into(),
+                    name: "file_read".into(),
+                    arguments: r#"{"path":"/home/jane/notes.txt"}"#.into(),
+                }],
+            ),
+            Message::tool_result("call_1", result),
+            Message::assistant("Done."),
+        ])
+    }
+
+    #[test]
+    fn test_openai_example_redacts_and_keeps_tool_calls() {
+        let scrubber = SecretScrubber::new().with_pii();
+        let example = openai_example(&tool_session("notes"), Some(&scrubber)).unwrap();
+        let messages = example["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0]["content"], "email me at [REDACTED]");
+        let call = &messages[1]["tool_calls"][0];
+        assert_eq!(call["function"]["name"], "file_read");
+        assert_eq!(
+            call["function"]["arguments"],
+            r#"{"path":"[REDACTED]/notes.txt"}"#
+        );
+        assert_eq!(messages[2]["role"], "tool");
+        assert_eq!(messages[2]["tool_call_id"], "call_1");
+
+        assert!(openai_example(&session(vec![Message::user("hi")]), None).is_none());
+    }
+
+    #[test]
+    fn test_sharegpt_example_alternates() {
+        let example = sharegpt_example(&tool_session("notes"), None).unwrap();
+        let from: Vec<&str> = example["conversations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["from"].as_str().unwrap())
+            .collect();
+        assert_eq!(from, ["human", "function_call", "observation", "gpt"]);
+        let call: Value =
+            serde_json::from_str(example["conversations"][1]["value"].as_str().unwrap()).unwrap();
+        assert_eq!(call["arguments"]["path"], "/home/jane/notes.txt");
+    }
+
+    #[test]
+    fn test_filter_and_success_heuristic() {
+        let ok = tool_session("notes");
+        let failed = tool_session("Error: file not found");
+        assert!(looks_successful(&ok));
+        assert!(!looks_successful(&failed));
+
+        let filter = DatasetFilter {
+            successful: true,
+            ..Default::default()
+        };
+        assert!(filter.matches(&ok) && !filter.matches(&failed));
+        let tagged = DatasetFilter {
+            tags: vec!["keep".into()],
+            ..Default::default()
+        };
+        assert!(!tagged.matches(&ok));
+        let recent = DatasetFilter {
+            since: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..Default::default()
+        };
+        assert!(!recent.matches(&ok));
+    }
+}
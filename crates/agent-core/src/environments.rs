@@ -0,0 +1,669 @@
+//! Runtime environment detection.
+//!
+//! Finds language toolchains (Python, Node, Rust, Go, Java, Ruby) and
+//! infrastructure (Docker Compose, Terraform, Kubernetes) in a directory,
+//! probing versions from the files that pin them. Used by the `env_detect`
+//! tool, `/context`, and the agent loop's system prompt.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Detected runtime environment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedEnvironment {
+    pub name: String,
+    pub env_type: String,
+    pub version: Option<String>,
+    pub path: PathBuf,
+}
+
+/// Detect Python virtual environments.
+fn detect_python_env(dir: &Path) -> Option<DetectedEnvironment> {
+    let venv_paths = [".venv", "venv", ".env", "env"];
+
+    for venv_name in venv_paths {
+        let venv_path = dir.join(venv_name);
+        let pyvenv_cfg = venv_path.join("pyvenv.cfg");
+
+        if pyvenv_cfg.exists() {
+            let version = parse_pyvenv_version(&pyvenv_cfg);
+
+            return Some(DetectedEnvironment {
+                name: format!("Python ({})", venv_name),
+                env_type: "python-venv".to_string(),
+                version,
+                path: venv_path,
+            });
+        }
+    }
+
+    // Check for conda-meta.
+    let conda_meta = dir.join("conda-meta");
+    if conda_meta.is_dir() {
+        return Some(DetectedEnvironment {
+            name: "Python (conda)".to_string(),
+            env_type: "python-conda".to_string(),
+            version: detect_conda_python(&conda_meta),
+            path: dir.to_path_buf(),
+        });
+    }
+
+    None
+}
+
+/// Parse Python version from pyvenv.cfg.
+fn parse_pyvenv_version(cfg_path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(cfg_path).ok()?;
+    for line in content.lines() {
+        if line.starts_with("version") {
+            return line.split('=').nth(1).map(|v| v.trim().to_string());
+        }
+    }
+    None
+}
+
+/// Get Python version from conda metadata.
+fn detect_conda_python(conda_meta: &Path) -> Option<String> {
+    if let Ok(entries) = std::fs::read_dir(conda_meta) {
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name_str = name.to_string_lossy();
+            if name_str.starts_with("python-") && name_str.ends_with(".json") {
+                return name_str
+                    .strip_prefix("python-")
+                    .and_then(|s| s.split('-').next())
+                    .map(String::from);
+            }
+        }
+    }
+    None
+}
+
+/// Detect Node.js environment.
+fn detect_node_env(dir: &Path) -> Option<DetectedEnvironment> {
+    let package_json = dir.join("package.json");
+    if !package_json.exists() {
+        return None;
+    }
+
+    let version = detect_node_version(dir);
+    let manager = detect_node_manager(dir);
+
+    Some(DetectedEnvironment {
+        name: format!("Node.js ({})", manager),
+        env_type: "node".to_string(),
+        version,
+        path: dir.to_path_buf(),
+    })
+}
+
+/// Detect Node.js version from various sources.
+fn detect_node_version(dir: &Path) -> Option<String> {
+    // Check .nvmrc.
+    let nvmrc = dir.join(".nvmrc");
+    if nvmrc.exists() {
+        if let Ok(content) = std::fs::read_to_string(&nvmrc) {
+            return Some(content.trim().to_string());
+        }
+    }
+
+    // Check .node-version.
+    let node_version = dir.join(".node-version");
+    if node_version.exists() {
+        if let Ok(content) = std::fs::read_to_string(&node_version) {
+            return Some(content.trim().to_string());
+        }
+    }
+
+    // Check package.json engines.
+    let package_json = dir.join("package.json");
+    if package_json.exists() {
+        if let Ok(content) = std::fs::read_to_string(&package_json) {
+            if let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) {
+                if let Some(node) = json.pointer("/engines/node") {
+                    return node.as_str().map(String::from);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Detect which Node manager is being used.
+fn detect_node_manager(dir: &Path) -> &'static str {
+    let package_json = dir.join("package.json");
+    if package_json.exists() {
+        if let Ok(content) = std::fs::read_to_string(&package_json) {
+            if content.contains("\"volta\"") {
+                return "volta";
+            }
+        }
+    }
+
+    if dir.join(".nvmrc").exists() {
+        return "nvm";
+    }
+
+    if dir.join(".node-version").exists() {
+        return "fnm";
+    }
+
+    "direct"
+}
+
+/// Detect Rust toolchain.
+fn detect_rust_env(dir: &Path) -> Option<DetectedEnvironment> {
+    let rust_toolchain = dir.join("rust-toolchain.toml");
+    let rust_toolchain_legacy = dir.join("rust-toolchain");
+
+    let version = if rust_toolchain.exists() {
+        std::fs::read_to_string(&rust_toolchain)
+            .ok()
+            .and_then(|content| {
+                for line in content.lines() {
+                    let trimmed = line.trim();
+                    if trimmed.starts_with("channel") {
+                        return trimmed
+                            .split('=')
+                            .nth(1)
+                            .map(|v| v.trim().trim_matches('"').to_string());
+                    }
+                }
+                None
+            })
+    } else if rust_toolchain_legacy.exists() {
+        std::fs::read_to_string(&rust_toolchain_legacy)
+            .ok()
+            .map(|s| s.trim().to_string())
+    } else if dir.join("Cargo.toml").exists() {
+        // Just note Rust is present without a specific toolchain pinned.
+        Some("default".to_string())
+    } else {
+        return None;
+    };
+
+    Some(DetectedEnvironment {
+        name: "Rust".to_string(),
+        env_type: "rust".to_string(),
+        version,
+        path: dir.to_path_buf(),
+    })
+}
+
+/// Detect Go environment.
+fn detect_go_env(dir: &Path) -> Option<DetectedEnvironment> {
+    if !dir.join("go.mod").exists() {
+        return None;
+    }
+
+    // Try to extract Go version from go.mod.
+    let version = std::fs::read_to_string(dir.join("go.mod"))
+        .ok()
+        .and_then(|content| {
+            for line in content.lines() {
+                let trimmed = line.trim();
+                if trimmed.starts_with("go ") {
+                    return Some(trimmed.strip_prefix("go ")?.trim().to_string());
+                }
+            }
+            None
+        });
+
+    Some(DetectedEnvironment {
+        name: "Go".to_string(),
+        env_type: "go".to_string(),
+        version,
+        path: dir.to_path_buf(),
+    })
+}
+
+/// Detect a Java project built with Maven or Gradle.
+fn detect_java_env(dir: &Path) -> Option<DetectedEnvironment> {
+    let gradle = ["build.gradle", "build.gradle.kts", "settings.gradle", "settings.gradle.kts"]
+        .iter()
+        .any(|f| dir.join(f).exists());
+    let (build_tool, env_type, version) = if dir.join("pom.xml").exists() {
+        let pom = std::fs::read_to_string(dir.join("pom.xml")).unwrap_or_default();
+        let version = ["maven.compiler.release", "maven.compiler.source", "java.version"]
+            .iter()
+            .find_map(|tag| xml_tag(&pom, tag));
+        ("Maven", "java-maven", version)
+    } else if gradle {
+        // The JDK isn't declared in a standard place; report the Gradle version.
+        ("Gradle", "java-gradle", detect_gradle_version(dir))
+    } else {
+        return None;
+    };
+
+    Some(DetectedEnvironment {
+        name: format!("Java ({})", build_tool),
+        env_type: env_type.to_string(),
+        version: read_version_file(dir, ".java-version").or(version),
+        path: dir.to_path_buf(),
+    })
+}
+
+/// Text of the first `<tag>...</tag>` element.
+fn xml_tag(content: &str, tag: &str) -> Option<String> {
+    let start = content.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let end = start + content[start..].find(&format!("</{}>", tag))?;
+    Some(content[start..end].trim().to_string())
+}
+
+/// Gradle version from the wrapper's `distributionUrl`
+/// (`.../gradle-8.5-bin.zip` → `8.5`).
+fn detect_gradle_version(dir: &Path) -> Option<String> {
+    let props =
+        std::fs::read_to_string(dir.join("gradle/wrapper/gradle-wrapper.properties")).ok()?;
+    let url = props
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("distributionUrl="))?;
+    let file = url.rsplit('/').next()?;
+    let version = file.strip_prefix("gradle-")?;
+    Some(version.split('-').next()?.to_string())
+}
+
+/// First line of a version file such as `.ruby-version`.
+fn read_version_file(dir: &Path, name: &str) -> Option<String> {
+    let content = std::fs::read_to_string(dir.join(name)).ok()?;
+    let version = content.lines().next()?.trim();
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+/// Detect a Ruby project managed by Bundler.
+fn detect_ruby_env(dir: &Path) -> Option<DetectedEnvironment> {
+    let gemfile = std::fs::read_to_string(dir.join("Gemfile")).ok()?;
+
+    // `ruby "3.2.2"` in the Gemfile, unless .ruby-version pins it.
+    let version = read_version_file(dir, ".ruby-version").or_else(|| {
+        gemfile.lines().find_map(|line| {
+            let rest = line.trim().strip_prefix("ruby ")?;
+            Some(rest.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+        })
+    });
+
+    let bundler = std::fs::read_to_string(dir.join("Gemfile.lock"))
+        .ok()
+        .and_then(|lock| {
+            let mut lines = lock.lines();
+            lines.find(|l| l.trim() == "BUNDLED WITH")?;
+            lines.next().map(|v| v.trim().to_string())
+        });
+
+    Some(DetectedEnvironment {
+        name: match bundler {
+            Some(v) => format!("Ruby (Bundler {})", v),
+            None => "Ruby (Bundler)".to_string(),
+        },
+        env_type: "ruby".to_string(),
+        version,
+        path: dir.to_path_buf(),
+    })
+}
+
+/// Detect a Docker Compose stack.
+fn detect_compose_env(dir: &Path) -> Option<DetectedEnvironment> {
+    let path = ["compose.yaml", "compose.yml", "docker-compose.yml", "docker-compose.yaml"]
+        .iter()
+        .map(|f| dir.join(f))
+        .find(|p| p.exists())?;
+    let content = std::fs::read_to_string(&path).unwrap_or_default();
+
+    // Count keys one level under the top-level `services:` mapping.
+    let mut in_services = false;
+    let mut indent = None;
+    let mut services = 0;
+    let mut version = None;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let depth = line.len() - trimmed.len();
+        if depth == 0 {
+            in_services = trimmed.starts_with("services:");
+            if let Some(v) = trimmed.strip_prefix("version:") {
+                version = Some(v.trim().trim_matches(|c| c == '"' || c == '\'').to_string());
+            }
+            continue;
+        }
+        if in_services && trimmed.ends_with(':') && *indent.get_or_insert(depth) == depth {
+            services += 1;
+        }
+    }
+
+    Some(DetectedEnvironment {
+        name: format!("Docker Compose ({} services)", services),
+        env_type: "docker-compose".to_string(),
+        version,
+        path,
+    })
+}
+
+/// Detect a Terraform configuration and its selected workspace.
+fn detect_terraform_env(dir: &Path) -> Option<DetectedEnvironment> {
+    let tf_files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "tf"))
+        .collect();
+    if tf_files.is_empty() {
+        return None;
+    }
+
+    // `required_version = ">= 1.5"` in a terraform block, unless pinned by tfenv.
+    let version = read_version_file(dir, ".terraform-version").or_else(|| {
+        tf_files.iter().find_map(|path| {
+            let content = std::fs::read_to_string(path).ok()?;
+            content.lines().find_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                (key.trim() == "required_version").then(|| value.trim().trim_matches('"').to_string())
+            })
+        })
+    });
+    let workspace = read_version_file(dir, ".terraform/environment")
+        .unwrap_or_else(|| "default".to_string());
+
+    Some(DetectedEnvironment {
+        name: format!("Terraform (workspace: {})", workspace),
+        env_type: "terraform".to_string(),
+        version,
+        path: dir.to_path_buf(),
+    })
+}
+
+/// Directories (besides the project root) searched for Kubernetes manifests.
+const KUBERNETES_DIRS: &[&str] = &["k8s", "kubernetes", "manifests", "deploy"];
+
+/// Detect Kubernetes manifests or a Helm chart.
+fn detect_kubernetes_env(dir: &Path) -> Option<DetectedEnvironment> {
+    if let Ok(chart) = std::fs::read_to_string(dir.join("Chart.yaml")) {
+        let version = chart.lines().find_map(|l| {
+            l.strip_prefix("version:")
+                .map(|v| v.trim().trim_matches('"').to_string())
+        });
+        return Some(DetectedEnvironment {
+            name: "Kubernetes (Helm chart)".to_string(),
+            env_type: "kubernetes".to_string(),
+            version,
+            path: dir.to_path_buf(),
+        });
+    }
+
+    let mut manifests = 0;
+    let mut found_in = None;
+    let dirs = std::iter::once(dir.to_path_buf()).chain(KUBERNETES_DIRS.iter().map(|d| dir.join(d)));
+    for search_dir in dirs {
+        let Ok(entries) = std::fs::read_dir(&search_dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            let is_yaml = path
+                .extension()
+                .is_some_and(|ext| ext == "yaml" || ext == "yml");
+            if is_yaml && is_kubernetes_manifest(&path) {
+                manifests += 1;
+                found_in.get_or_insert_with(|| search_dir.clone());
+            }
+        }
+    }
+    if manifests == 0 {
+        return None;
+    }
+
+    Some(DetectedEnvironment {
+        name: format!("Kubernetes ({} manifests)", manifests),
+        env_type: "kubernetes".to_string(),
+        version: None,
+        path: found_in.unwrap_or_else(|| dir.to_path_buf()),
+    })
+}
+
+/// A YAML file with top-level `apiVersion:` and `kind:` keys.
+fn is_kubernetes_manifest(path: &Path) -> bool {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return false;
+    };
+    content.lines().any(|l| l.starts_with("apiVersion:"))
+        && content.lines().any(|l| l.starts_with("kind:"))
+}
+
+/// Detect all environments in a directory.
+pub fn detect_environments(dir: &Path) -> Vec<DetectedEnvironment> {
+    let mut envs = Vec::new();
+
+    if let Some(env) = detect_python_env(dir) {
+        envs.push(env);
+    }
+    if let Some(env) = detect_node_env(dir) {
+        envs.push(env);
+    }
+    if let Some(env) = detect_rust_env(dir) {
+        envs.push(env);
+    }
+    if let Some(env) = detect_go_env(dir) {
+        envs.push(env);
+    }
+    if let Some(env) = detect_java_env(dir) {
+        envs.push(env);
+    }
+    if let Some(env) = detect_ruby_env(dir) {
+        envs.push(env);
+    }
+    if let Some(env) = detect_compose_env(dir) {
+        envs.push(env);
+    }
+    if let Some(env) = detect_terraform_env(dir) {
+        envs.push(env);
+    }
+    if let Some(env) = detect_kubernetes_env(dir) {
+        envs.push(env);
+    }
+
+    envs
+}
+
+/// Environments as a system prompt section, or `None` if there are none.
+pub fn prompt_section(envs: &[DetectedEnvironment]) -> Option<String> {
+    if envs.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = envs
+        .iter()
+        .map(|env| match &env.version {
+            Some(version) => format!("- {} {}", env.name, version),
+            None => format!("- {}", env.name),
+        })
+        .collect();
+    Some(format!("## Detected environments\n\n{}", lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect_python_venv() {
+        let dir = TempDir::new().unwrap();
+        let venv_dir = dir.path().join(".venv");
+        std::fs::create_dir_all(&venv_dir).unwrap();
+        std::fs::write(
+            venv_dir.join("pyvenv.cfg"),
+            "home = /usr/bin\nversion = 3.12.1\n",
+        )
+        .unwrap();
+
+        let env = detect_python_env(dir.path());
+        assert!(env.is_some());
+        let env = env.unwrap();
+        assert_eq!(env.env_type, "python-venv");
+        assert_eq!(env.version, Some("3.12.1".to_string()));
+    }
+
+    #[test]
+    fn test_detect_node_env() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+
+        let env = detect_node_env(dir.path());
+        assert!(env.is_some());
+        assert_eq!(env.unwrap().env_type, "node");
+    }
+
+    #[test]
+    fn test_detect_node_version_nvmrc() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+        std::fs::write(dir.path().join(".nvmrc"), "20.11.0\n").unwrap();
+
+        let env = detect_node_env(dir.path()).unwrap();
+        assert_eq!(env.version, Some("20.11.0".to_string()));
+    }
+
+    #[test]
+    fn test_detect_rust_env() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+
+        let env = detect_rust_env(dir.path());
+        assert!(env.is_some());
+        assert_eq!(env.unwrap().env_type, "rust");
+    }
+
+    #[test]
+    fn test_detect_rust_toolchain() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        std::fs::write(
+            dir.path().join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"1.78\"\n",
+        )
+        .unwrap();
+
+        let env = detect_rust_env(dir.path()).unwrap();
+        assert_eq!(env.version, Some("1.78".to_string()));
+    }
+
+    #[test]
+    fn test_detect_go_env() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("go.mod"),
+            "module example.com/foo\n\ngo 1.22\n",
+        )
+        .unwrap();
+
+        let env = detect_go_env(dir.path());
+        assert!(env.is_some());
+        let env = env.unwrap();
+        assert_eq!(env.env_type, "go");
+        assert_eq!(env.version, Some("1.22".to_string()));
+    }
+
+    #[test]
+    fn test_detect_no_environments() {
+        let dir = TempDir::new().unwrap();
+        let envs = detect_environments(dir.path());
+        assert!(envs.is_empty());
+    }
+
+    #[test]
+    fn test_detect_multiple_environments() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+
+        let envs = detect_environments(dir.path());
+        assert_eq!(envs.len(), 2);
+    }
+
+    #[test]
+    fn test_detect_java_envs() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("pom.xml"),
+            "<project><properties><maven.compiler.release>21</maven.compiler.release></properties></project>",
+        )
+        .unwrap();
+        let env = detect_java_env(dir.path()).unwrap();
+        assert_eq!(env.env_type, "java-maven");
+        assert_eq!(env.version, Some("21".to_string()));
+
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("build.gradle.kts"), "").unwrap();
+        std::fs::create_dir_all(dir.path().join("gradle/wrapper")).unwrap();
+        std::fs::write(
+            dir.path().join("gradle/wrapper/gradle-wrapper.properties"),
+            "distributionUrl=https\\://services.gradle.org/distributions/gradle-8.5-bin.zip\n",
+        )
+        .unwrap();
+        let env = detect_java_env(dir.path()).unwrap();
+        assert_eq!(env.name, "Java (Gradle)");
+        assert_eq!(env.version, Some("8.5".to_string()));
+    }
+
+    #[test]
+    fn test_detect_ruby_env() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("Gemfile"),
+            "source \"https://rubygems.org\"\nruby '3.2.2'\ngem \"rails\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("Gemfile.lock"),
+            "GEM\n  specs:\n\nBUNDLED WITH\n   2.4.10\n",
+        )
+        .unwrap();
+
+        let env = detect_ruby_env(dir.path()).unwrap();
+        assert_eq!(env.name, "Ruby (Bundler 2.4.10)");
+        assert_eq!(env.version, Some("3.2.2".to_string()));
+    }
+
+    #[test]
+    fn test_detect_compose_and_terraform() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("docker-compose.yml"),
+            "version: \"3.8\"\nservices:\n  web:\n    image: nginx\n    ports:\n      - 80:80\n  db:\n    image: postgres\nvolumes:\n  data:\n",
+        )
+        .unwrap();
+        let env = detect_compose_env(dir.path()).unwrap();
+        assert_eq!(env.name, "Docker Compose (2 services)");
+        assert_eq!(env.version, Some("3.8".to_string()));
+
+        std::fs::write(
+            dir.path().join("main.tf"),
+            "terraform {\n  required_version = \">= 1.5\"\n}\n",
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join(".terraform")).unwrap();
+        std::fs::write(dir.path().join(".terraform/environment"), "prod").unwrap();
+        let env = detect_terraform_env(dir.path()).unwrap();
+        assert_eq!(env.name, "Terraform (workspace: prod)");
+        assert_eq!(env.version, Some(">= 1.5".to_string()));
+    }
+
+    #[test]
+    fn test_detect_kubernetes_env() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("k8s")).unwrap();
+        std::fs::write(
+            dir.path().join("k8s/deployment.yaml"),
+            "apiVersion: apps/v1\nkind: Deployment\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("k8s/values.yaml"), "replicas: 2\n").unwrap();
+
+        let env = detect_kubernetes_env(dir.path()).unwrap();
+        assert_eq!(env.name, "Kubernetes (1 manifests)");
+        assert_eq!(env.path, dir.path().join("k8s"));
+
+        let section = prompt_section(&detect_environments(dir.path())).unwrap();
+        assert_eq!(section, "## Detected environments\n\n- Kubernetes (1 manifests)");
+    }
+}
@@ -0,0 +1,240 @@
+//! Failed-command assistance built on terminal capture.
+//!
+//! When a captured shell command exits non-zero, [`FailedCommandContext`]
+//! gathers the command, the commands leading up to it, and the tail of the
+//! session's output, then renders a prompt that seeds a new agent session.
+//! All captured text passes through [`SecretScrubber`] before it leaves the
+//! terminal layer.
+
+use crate::config::ErrorAssistConfig;
+use crate::scrubber::SecretScrubber;
+use crate::terminal_session::{TerminalCommand, TerminalSessionManager};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// A single command included as context for a failure.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextCommand {
+    pub command_text: String,
+    pub exit_code: Option<i32>,
+}
+
+/// Everything the agent needs to know about a failed command.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedCommandContext {
+    pub session_id: Uuid,
+    pub command_id: Uuid,
+    pub shell: String,
+    pub working_directory: PathBuf,
+    pub command_text: String,
+    pub exit_code: i32,
+    /// Commands run before the failure, oldest first.
+    pub preceding: Vec<ContextCommand>,
+    /// Scrubbed tail of the captured terminal output, if any was captured.
+    pub output_tail: Option<String>,
+}
+
+impl FailedCommandContext {
+    /// Collect context for a failed command.
+    ///
+    /// Returns `None` if the session or command is unknown, or if the
+    /// command did not fail.
+    pub fn collect(
+        manager: &TerminalSessionManager,
+        session_id: &Uuid,
+        command_id: &Uuid,
+        config: &ErrorAssistConfig,
+    ) -> Option<Self> {
+        let session = manager.get_session(session_id)?;
+        let command = manager.get_command(session_id, command_id)?;
+        if !command.failed() {
+            return None;
+        }
+
+        let scrubber = SecretScrubber::new();
+        let commands = manager.get_commands(session_id).unwrap_or(&[]);
+        let earlier: Vec<&TerminalCommand> = commands
+            .iter()
+            .filter(|c| c.sequence < command.sequence)
+            .collect();
+        let skip = earlier.len().saturating_sub(config.context_commands);
+        let preceding: Vec<ContextCommand> = earlier[skip..]
+            .iter()
+            .map(|c| context_command(c, &scrubber))
+            .collect();
+
+        let output_tail = manager
+            .output_tail(session_id)
+            .filter(|tail| !tail.is_empty())
+            .map(|tail| {
+                let start = tail.len().saturating_sub(config.max_output_bytes);
+                let text = String::from_utf8_lossy(&tail[start..]);
+                scrubber.scrub(&text).into_owned()
+            });
+
+        Some(Self {
+            session_id: *session_id,
+            command_id: *command_id,
+            shell: session.shell.clone(),
+            working_directory: command.working_directory.clone(),
+            command_text: scrubber.scrub_command(&command.command_text),
+            exit_code: command.exit_code.unwrap_or(-1),
+            preceding,
+            output_tail,
+        })
+    }
+
+    /// Short name for a session created from this failure.
+    pub fn session_name(&self) -> String {
+        let cmd: String = self.command_text.chars().take(40).collect();
+        format!("fix: {}", cmd)
+    }
+
+    /// Render the user prompt that asks the agent about this failure.
+    pub fn to_prompt(&self) -> String {
+        let mut prompt = String::new();
+        let _ = writeln!(
+            prompt,
+            "A command in my terminal failed with exit code {}.",
+            self.exit_code
+        );
+        let _ = writeln!(prompt);
+        let _ = writeln!(prompt, "Shell: {}", self.shell);
+        let _ = writeln!(
+            prompt,
+            "Working directory: {}",
+            self.working_directory.display()
+        );
+        let _ = writeln!(prompt, "Failed command:");
+        let _ = writeln!(prompt, "```");
+        let _ = writeln!(prompt, "{}", self.command_text);
+        let _ = writeln!(prompt, "```");
+
+        if !self.preceding.is_empty() {
+            let _ = writeln!(prompt);
+            let _ = writeln!(prompt, "Commands run before it:");
+            for c in &self.preceding {
+                let status = c
+                    .exit_code
+                    .map(|code| format!("exit {}", code))
+                    .unwrap_or_else(|| "running".into());
+                let _ = writeln!(prompt, "- `{}` ({})", c.command_text, status);
+            }
+        }
+
+        if let Some(output) = &self.output_tail {
+            let _ = writeln!(prompt);
+            let _ = writeln!(prompt, "Recent terminal output:");
+            let _ = writeln!(prompt, "```");
+            let _ = writeln!(prompt, "{}", output.trim_end());
+            let _ = writeln!(prompt, "```");
+        }
+
+        let _ = writeln!(prompt);
+        let _ = write!(prompt, "What went wrong, and how do I fix it?");
+        prompt
+    }
+}
+
+fn context_command(cmd: &TerminalCommand, scrubber: &SecretScrubber) -> ContextCommand {
+    ContextCommand {
+        command_text: scrubber.scrub_command(&cmd.command_text),
+        exit_code: cmd.exit_code,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_pty::CaptureEvent;
+    use chrono::Utc;
+
+    fn run(mgr: &mut TerminalSessionManager, sid: Uuid, cmd: &str, exit: i32) -> Uuid {
+        let cid = Uuid::new_v4();
+        mgr.process_event(&CaptureEvent::CommandStart {
+            command_id: cid,
+            session_id: sid,
+            command_text: cmd.to_string(),
+            working_directory: PathBuf::from("/work"),
+            timestamp: Utc::now(),
+        });
+        mgr.process_event(&CaptureEvent::CommandEnd {
+            command_id: cid,
+            session_id: sid,
+            exit_code: exit,
+            timestamp: Utc::now(),
+        });
+        cid
+    }
+
+    fn manager_with_session() -> (TerminalSessionManager, Uuid) {
+        let mut mgr = TerminalSessionManager::new();
+        let sid = Uuid::new_v4();
+        mgr.process_event(&CaptureEvent::session_start(sid, "bash", "/work"));
+        (mgr, sid)
+    }
+
+    #[test]
+    fn test_collect_failed_command() {
+        let (mut mgr, sid) = manager_with_session();
+        run(&mut mgr, sid, "cd project", 0);
+        run(&mut mgr, sid, "git pull", 0);
+        let failed = run(&mut mgr, sid, "cargo build", 101);
+
+        let config = ErrorAssistConfig::default();
+        let ctx = FailedCommandContext::collect(&mgr, &sid, &failed, &config).unwrap();
+        assert_eq!(ctx.command_text, "cargo build");
+        assert_eq!(ctx.exit_code, 101);
+        assert_eq!(ctx.preceding.len(), 2);
+        assert_eq!(ctx.preceding[0].command_text, "cd project");
+        assert!(ctx.output_tail.is_none());
+    }
+
+    #[test]
+    fn test_collect_ignores_successful_command() {
+        let (mut mgr, sid) = manager_with_session();
+        let ok = run(&mut mgr, sid, "ls", 0);
+
+        let config = ErrorAssistConfig::default();
+        assert!(FailedCommandContext::collect(&mgr, &sid, &ok, &config).is_none());
+    }
+
+    #[test]
+    fn test_preceding_commands_limited() {
+        let (mut mgr, sid) = manager_with_session();
+        for i in 0..10 {
+            run(&mut mgr, sid, &format!("step-{}", i), 0);
+        }
+        let failed = run(&mut mgr, sid, "make", 2);
+
+        let config = ErrorAssistConfig {
+            context_commands: 3,
+            ..Default::default()
+        };
+        let ctx = FailedCommandContext::collect(&mgr, &sid, &failed, &config).unwrap();
+        let texts: Vec<&str> = ctx.preceding.iter().map(|c| c.command_text.as_str()).collect();
+        assert_eq!(texts, vec!["step-7", "step-8", "step-9"]);
+    }
+
+    #[test]
+    fn test_prompt_includes_scrubbed_output() {
+        let (mut mgr, sid) = manager_with_session();
+        mgr.process_event(&CaptureEvent::Output {
+            session_id: sid,
+            data: b"error: API_KEY=abc123 rejected\n".to_vec(),
+            timestamp: Utc::now(),
+        });
+        let failed = run(&mut mgr, sid, "deploy", 1);
+
+        let config = ErrorAssistConfig::default();
+        let ctx = FailedCommandContext::collect(&mgr, &sid, &failed, &config).unwrap();
+        let prompt = ctx.to_prompt();
+        assert!(prompt.contains("exit code 1"));
+        assert!(prompt.contains("deploy"));
+        assert!(prompt.contains("[REDACTED]"));
+        assert!(!prompt.contains("abc123"));
+        assert_eq!(ctx.session_name(), "fix: deploy");
+    }
+}
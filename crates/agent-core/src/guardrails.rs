@@ -0,0 +1,422 @@
+//! Content filters on what goes to and comes from providers
+//! (`[[guardrails]]`).
+//!
+//! Every provider gets a [`GuardrailMiddleware`] outside its configured
+//! middleware, so logs, caches and cassettes only see filtered text. Input
+//! filters check the user messages and tool results of each request;
+//! output filters check the answer and its tool call arguments. A match is
+//! blocked, warned about or redacted as the guardrail says, and each
+//! violation is logged with the guardrail, direction and provider — never
+//! the matched text — for the audit trail.
+//!
+//! Output filters need the whole answer, so while any are configured
+//! answers are shown once complete instead of token by token.
+
+use crate::config::{ContentPolicy, GuardrailAction, GuardrailConfig, GuardrailScope};
+use crate::error::AgentError;
+use crate::middleware::{Next, ProviderMiddleware, ProviderRequest, ProviderResponse};
+use crate::notifications::{self, NotificationLevel};
+use crate::provider::RequestError;
+use crate::scrubber;
+use async_openai::types::{
+    ChatCompletionRequestMessage, ChatCompletionRequestToolMessageContent,
+    ChatCompletionRequestToolMessageContentPart, ChatCompletionRequestUserMessageContent,
+    ChatCompletionRequestUserMessageContentPart,
+};
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::Value;
+use tracing::warn;
+
+/// One compiled `[[guardrails]]` entry.
+pub struct Guardrail {
+    name: String,
+    regexes: Vec<Regex>,
+    scope: GuardrailScope,
+    action: GuardrailAction,
+    replacement: String,
+}
+
+impl Guardrail {
+    pub fn new(config: &GuardrailConfig) -> Result<Self, AgentError> {
+        let invalid = |what: String| {
+            AgentError::Config(format!("Guardrail '{}': {}", config.name, what))
+        };
+        let mut sources: Vec<String> = config.patterns.clone();
+        sources.extend(
+            config
+                .keywords
+                .iter()
+                .filter(|k| !k.trim().is_empty())
+                .map(|k| format!(r"(?i)\b{}\b", regex::escape(k.trim()))),
+        );
+        for policy in &config.policies {
+            let patterns = match policy {
+                ContentPolicy::Secrets => scrubber::DEFAULT_PATTERNS,
+                ContentPolicy::Pii => scrubber::PII_PATTERNS,
+            };
+            sources.extend(patterns.iter().map(|p| p.to_string()));
+        }
+        if sources.is_empty() {
+            return Err(invalid("needs patterns, keywords or policies".into()));
+        }
+        let regexes = sources
+            .iter()
+            .map(|p| Regex::new(p).map_err(|e| invalid(format!("invalid pattern {:?}: {}", p, e))))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            name: config.name.clone(),
+            regexes,
+            scope: config.applies_to,
+            action: config.action,
+            replacement: config.replacement.clone(),
+        })
+    }
+
+    fn checks_input(&self) -> bool {
+        self.scope != GuardrailScope::Output
+    }
+
+    fn checks_output(&self) -> bool {
+        self.scope != GuardrailScope::Input
+    }
+
+    /// Number of matches in `text`.
+    fn count(&self, text: &str) -> usize {
+        self.regexes.iter().map(|r| r.find_iter(text).count()).sum()
+    }
+
+    /// Redact `text` in place, returning the number of matches.
+    fn redact(&self, text: &mut String) -> usize {
+        let mut found = 0;
+        for regex in &self.regexes {
+            let n = regex.find_iter(text).count();
+            if n > 0 {
+                found += n;
+                *text = regex
+                    .replace_all(text, regex::NoExpand(&self.replacement))
+                    .into_owned();
+            }
+        }
+        found
+    }
+
+    /// Redact the string values of JSON tool arguments, so they stay valid
+    /// JSON; arguments that do not parse are redacted as text.
+    fn redact_arguments(&self, arguments: &mut String) -> usize {
+        fn walk(value: &mut Value, guardrail: &Guardrail) -> usize {
+            match value {
+                Value::String(s) => guardrail.redact(s),
+                Value::Array(items) => items.iter_mut().map(|v| walk(v, guardrail)).sum(),
+                Value::Object(map) => map.values_mut().map(|v| walk(v, guardrail)).sum(),
+                _ => 0,
+            }
+        }
+        match serde_json::from_str::<Value>(arguments) {
+            Ok(mut value) => {
+                let found = walk(&mut value, self);
+                if found > 0 {
+                    *arguments = value.to_string();
+                }
+                found
+            }
+            Err(_) => self.redact(arguments),
+        }
+    }
+
+    /// Record a violation in the audit log.
+    fn report(&self, direction: &str, provider: &str, matches: usize) {
+        warn!(
+            guardrail = %self.name,
+            direction,
+            action = ?self.action,
+            provider,
+            matches,
+            "Guardrail violation"
+        );
+        if self.action == GuardrailAction::Warn {
+            let what = if direction == "input" { "prompt" } else { "answer" };
+            notifications::publish(
+                NotificationLevel::Warning,
+                &format!("Guardrail: {}", self.name),
+                &format!("The {} matched this guardrail and was sent anyway.", what),
+            );
+        }
+    }
+}
+
+/// Applies the `[[guardrails]]` to every request and answer.
+pub struct GuardrailMiddleware {
+    guardrails: Vec<Guardrail>,
+}
+
+impl GuardrailMiddleware {
+    /// The middleware for these guardrails, or `None` when there are none.
+    pub fn from_config(configs: &[GuardrailConfig]) -> Result<Option<Self>, AgentError> {
+        if configs.is_empty() {
+            return Ok(None);
+        }
+        let guardrails = configs.iter().map(Guardrail::new).collect::<Result<_, _>>()?;
+        Ok(Some(Self { guardrails }))
+    }
+
+    /// Filter the user messages and tool results of a request. Blocking
+    /// looks at the whole history, so blocked text is never sent even in a
+    /// later turn; warnings only cover the messages new since the last
+    /// answer.
+    fn check_input(
+        &self,
+        provider: &str,
+        messages: &mut [ChatCompletionRequestMessage],
+    ) -> Result<(), RequestError> {
+        let new_from = messages
+            .iter()
+            .rposition(|m| matches!(m, ChatCompletionRequestMessage::Assistant(_)))
+            .map_or(0, |i| i + 1);
+        for guardrail in self.guardrails.iter().filter(|g| g.checks_input()) {
+            let found: usize = match guardrail.action {
+                GuardrailAction::Redact => messages
+                    .iter_mut()
+                    .flat_map(input_texts)
+                    .map(|text| guardrail.redact(text))
+                    .sum(),
+                GuardrailAction::Block => messages
+                    .iter_mut()
+                    .flat_map(input_texts)
+                    .map(|text| guardrail.count(text))
+                    .sum(),
+                GuardrailAction::Warn => messages[new_from..]
+                    .iter_mut()
+                    .flat_map(input_texts)
+                    .map(|text| guardrail.count(text))
+                    .sum(),
+            };
+            if found == 0 {
+                continue;
+            }
+            guardrail.report("input", provider, found);
+            if guardrail.action == GuardrailAction::Block {
+                return Err(RequestError::Permanent(format!(
+                    "Blocked by guardrail '{}': the prompt matches its filter. Edit or delete \
+                     the message and try again.",
+                    guardrail.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Filter an answer. A blocked answer is replaced by a notice and its
+    /// tool calls are dropped.
+    fn check_output(&self, provider: &str, response: &mut ProviderResponse) {
+        for guardrail in self.guardrails.iter().filter(|g| g.checks_output()) {
+            let found = match guardrail.action {
+                GuardrailAction::Redact => {
+                    guardrail.redact(&mut response.content)
+                        + response
+                            .tool_calls
+                            .iter_mut()
+                            .map(|c| guardrail.redact_arguments(&mut c.arguments))
+                            .sum::<usize>()
+                }
+                GuardrailAction::Block | GuardrailAction::Warn => {
+                    guardrail.count(&response.content)
+                        + response
+                            .tool_calls
+                            .iter()
+                            .map(|c| guardrail.count(&c.arguments))
+                            .sum::<usize>()
+                }
+            };
+            if found == 0 {
+                continue;
+            }
+            guardrail.report("output", provider, found);
+            if guardrail.action == GuardrailAction::Block {
+                response.content = format!("[Answer withheld by guardrail '{}']", guardrail.name);
+                response.tool_calls.clear();
+                return;
+            }
+        }
+    }
+}
+
+/// The text of a user message or tool result; other messages have none
+/// to check.
+fn input_texts(message: &mut ChatCompletionRequestMessage) -> Vec<&mut String> {
+    match message {
+        ChatCompletionRequestMessage::User(user) => match &mut user.content {
+            ChatCompletionRequestUserMessageContent::Text(text) => vec![text],
+            ChatCompletionRequestUserMessageContent::Array(parts) => parts
+                .iter_mut()
+                .filter_map(|part| match part {
+                    ChatCompletionRequestUserMessageContentPart::Text(t) => Some(&mut t.text),
+                    _ => None,
+                })
+                .collect(),
+        },
+        ChatCompletionRequestMessage::Tool(tool) => match &mut tool.content {
+            ChatCompletionRequestToolMessageContent::Text(text) => vec![text],
+            ChatCompletionRequestToolMessageContent::Array(parts) => parts
+                .iter_mut()
+                .map(|ChatCompletionRequestToolMessageContentPart::Text(t)| &mut t.text)
+                .collect(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+#[async_trait]
+impl ProviderMiddleware for GuardrailMiddleware {
+    fn name(&self) -> &str {
+        "guardrails"
+    }
+
+    fn holds_output(&self) -> bool {
+        self.guardrails.iter().any(Guardrail::checks_output)
+    }
+
+    async fn handle(
+        &self,
+        mut request: ProviderRequest,
+        next: Next<'_>,
+    ) -> Result<ProviderResponse, RequestError> {
+        let provider = request.provider.clone();
+        self.check_input(&provider, &mut request.body.messages)?;
+        let mut response = next.run(request).await?;
+        self.check_output(&provider, &mut response);
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ToolCall;
+    use async_openai::types::{
+        ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestToolMessageArgs,
+        ChatCompletionRequestUserMessageArgs,
+    };
+
+    fn guardrails(toml: &str) -> GuardrailMiddleware {
+        #[derive(serde::Deserialize)]
+        struct File {
+            guardrails: Vec<GuardrailConfig>,
+        }
+        let file: File = toml::from_str(toml).unwrap();
+        GuardrailMiddleware::from_config(&file.guardrails)
+            .unwrap()
+            .unwrap()
+    }
+
+    fn user(text: &str) -> ChatCompletionRequestMessage {
+        ChatCompletionRequestUserMessageArgs::default()
+            .content(text)
+            .build()
+            .unwrap()
+            .into()
+    }
+
+    fn text(message: &mut ChatCompletionRequestMessage) -> String {
+        input_texts(message).iter().map(|t| t.as_str()).collect()
+    }
+
+    #[test]
+    fn test_input_block_and_redact() {
+        let middleware = guardrails(
+            r#"
+[[guardrails]]
+name = "customer ids"
+patterns = ['CUST-\d{6}']
+action = "redact"
+applies_to = "input"
+
+[[guardrails]]
+name = "codenames"
+keywords = ["Project Falcon"]
+"#,
+        );
+        let mut messages = vec![
+            user("look up CUST-123456"),
+            ChatCompletionRequestAssistantMessageArgs::default()
+                .content("ok")
+                .build()
+                .unwrap()
+                .into(),
+            ChatCompletionRequestToolMessageArgs::default()
+                .tool_call_id("call_1")
+                .content("owner: CUST-654321")
+                .build()
+                .unwrap()
+                .into(),
+        ];
+        middleware.check_input("default", &mut messages).unwrap();
+        assert_eq!(text(&mut messages[0]), "look up [REDACTED]");
+        assert_eq!(text(&mut messages[2]), "owner: [REDACTED]");
+
+        let mut blocked = vec![user("status of project falcon?")];
+        let err = middleware.check_input("default", &mut blocked).unwrap_err();
+        assert!(matches!(err, RequestError::Permanent(m) if m.contains("'codenames'")));
+        // Whole words only.
+        middleware
+            .check_input("default", &mut [user("project falconry")])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_output_filters() {
+        let middleware = guardrails(
+            r#"
+[[guardrails]]
+name = "no secrets"
+policies = ["secrets"]
+action = "redact"
+applies_to = "output"
+replacement = "***"
+
+[[guardrails]]
+name = "profanity"
+keywords = ["darn"]
+applies_to = "output"
+"#,
+        );
+        assert!(middleware.holds_output());
+
+        let mut response = ProviderResponse {
+            content: "Use bearer abc123".into(),
+            tool_calls: vec![ToolCall {
+                id: "c1".into(),
+                name: "shell_exec".into(),
+                arguments: r#"{"command":"curl -H 'token: s3cr3t'"}"#.into(),
+            }],
+            usage: None,
+        };
+        middleware.check_output("default", &mut response);
+        assert_eq!(response.content, "Use ***");
+        let args: Value = serde_json::from_str(&response.tool_calls[0].arguments).unwrap();
+        assert_eq!(args["command"], "curl -H '***'");
+
+        let mut response = ProviderResponse {
+            content: "Darn, the build failed.".into(),
+            ..Default::default()
+        };
+        middleware.check_output("default", &mut response);
+        assert_eq!(response.content, "[Answer withheld by guardrail 'profanity']");
+    }
+
+    #[test]
+    fn test_invalid_guardrails_are_config_errors() {
+        let config = |patterns: Vec<String>| GuardrailConfig {
+            name: "bad".into(),
+            patterns,
+            keywords: Vec::new(),
+            policies: Vec::new(),
+            applies_to: GuardrailScope::Both,
+            action: GuardrailAction::Block,
+            replacement: "[REDACTED]".into(),
+        };
+        assert!(Guardrail::new(&config(vec!["(".into()])).is_err());
+        assert!(Guardrail::new(&config(Vec::new())).is_err());
+        assert!(GuardrailMiddleware::from_config(&[]).unwrap().is_none());
+    }
+}
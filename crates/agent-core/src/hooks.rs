@@ -0,0 +1,386 @@
+//! Hooks into the agent pipeline.
+//!
+//! A [`Hook`] is called at five stages of every turn: `pre_prompt` (the
+//! messages about to be sent), `post_llm` (each model response),
+//! `pre_tool` (each tool call, which it may rewrite or veto), `post_tool`
+//! (each tool result) and `on_error` (a failed turn). Plugins implement the
+//! trait and register with the [`AgentLoop`]'s [`HookRegistry`]; commands
+//! configured under `[[hooks]]` are wrapped in a [`CommandHook`].
+//!
+//! Hooks run in registration order and each sees the previous one's
+//! changes. Guardrails veto in `pre_tool`; telemetry observes every stage.
+//!
+//! [`AgentLoop`]: crate::agent_loop::AgentLoop
+
+use crate::config::{HookConfig, HookStage};
+use crate::error::AgentError;
+use crate::types::{Message, ToolCall, ToolOutput};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::warn;
+
+/// A model response as seen by `post_llm` hooks.
+#[derive(Debug, Clone)]
+pub struct LlmResponse {
+    pub content: String,
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// Whether a tool call may proceed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookAction {
+    Continue,
+    /// Skip the call; the reason is returned to the model as the result.
+    Veto(String),
+}
+
+/// Observer and editor of agent pipeline stages. Every method defaults to
+/// doing nothing.
+#[async_trait]
+pub trait Hook: Send + Sync {
+    /// Name shown in logs and veto messages.
+    fn name(&self) -> &str;
+
+    /// Inspect or rewrite the messages of a turn before the first model
+    /// call. An error aborts the turn.
+    async fn pre_prompt(&self, _messages: &mut Vec<Message>) -> Result<(), AgentError> {
+        Ok(())
+    }
+
+    /// Inspect or rewrite a model response. Its content has already been
+    /// streamed to the client; changes apply to history and tool calls.
+    async fn post_llm(&self, _response: &mut LlmResponse) {}
+
+    /// Inspect or rewrite a tool call before it runs, or veto it.
+    async fn pre_tool(&self, _call: &mut ToolCall) -> HookAction {
+        HookAction::Continue
+    }
+
+    /// Inspect or rewrite a tool's output before the model sees it.
+    async fn post_tool(&self, _call: &ToolCall, _output: &mut ToolOutput) {}
+
+    /// Observe a failed turn.
+    async fn on_error(&self, _error: &AgentError) {}
+}
+
+/// Ordered set of hooks applied by the agent loop.
+#[derive(Default, Clone)]
+pub struct HookRegistry {
+    hooks: Vec<Arc<dyn Hook>>,
+}
+
+impl HookRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with a [`CommandHook`] per `[[hooks]]` entry.
+    pub fn from_config(configs: &[HookConfig]) -> Self {
+        let mut registry = Self::new();
+        for config in configs {
+            registry.register(Arc::new(CommandHook::new(config.clone())));
+        }
+        registry
+    }
+
+    pub fn register(&mut self, hook: Arc<dyn Hook>) {
+        self.hooks.push(hook);
+    }
+
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    pub async fn pre_prompt(&self, messages: &mut Vec<Message>) -> Result<(), AgentError> {
+        for hook in &self.hooks {
+            hook.pre_prompt(messages).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn post_llm(&self, response: &mut LlmResponse) {
+        for hook in &self.hooks {
+            hook.post_llm(response).await;
+        }
+    }
+
+    /// Run `pre_tool` hooks until one vetoes the call.
+    pub async fn pre_tool(&self, call: &mut ToolCall) -> HookAction {
+        for hook in &self.hooks {
+            if let HookAction::Veto(reason) = hook.pre_tool(call).await {
+                return HookAction::Veto(format!("Tool call blocked by hook {}: {}", hook.name(), reason));
+            }
+        }
+        HookAction::Continue
+    }
+
+    pub async fn post_tool(&self, call: &ToolCall, output: &mut ToolOutput) {
+        for hook in &self.hooks {
+            hook.post_tool(call, output).await;
+        }
+    }
+
+    pub async fn on_error(&self, error: &AgentError) {
+        for hook in &self.hooks {
+            hook.on_error(error).await;
+        }
+    }
+}
+
+/// Replacements a command hook may print as a JSON object on stdout. Empty
+/// output leaves everything unchanged.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CommandReply {
+    /// `pre_prompt`: replacement messages.
+    messages: Option<Vec<Message>>,
+    /// `post_llm` and `post_tool`: replacement text.
+    content: Option<String>,
+    /// `pre_tool`: replacement arguments.
+    arguments: Option<Value>,
+    /// `pre_tool`: refuse the call with this reason.
+    veto: Option<String>,
+}
+
+/// A `[[hooks]]` command.
+///
+/// It receives `{"stage": ..., ...}` on stdin with the stage's data
+/// (`messages`, `content` and `tool_calls`, `tool` and `arguments`, `output`
+/// or `error`) and may reply with a JSON object of replacements. A non-zero
+/// exit vetoes the tool call at `pre_tool` and aborts the turn at
+/// `pre_prompt`, with stderr as the reason; at other stages it is logged.
+pub struct CommandHook {
+    config: HookConfig,
+    name: String,
+}
+
+impl CommandHook {
+    pub fn new(config: HookConfig) -> Self {
+        let name = format!("{:?}:{}", config.stage, config.command);
+        Self { config, name }
+    }
+
+    fn applies_to(&self, stage: HookStage, tool: Option<&str>) -> bool {
+        self.config.stage == stage
+            && match tool {
+                Some(tool) => self.config.tools.is_empty() || self.config.tools.iter().any(|t| t == tool),
+                None => true,
+            }
+    }
+
+    /// Run the command with `input`; `Err` carries the failure reason.
+    async fn run(&self, input: Value) -> Result<CommandReply, String> {
+        let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+        let mut child = Command::new(shell)
+            .arg(flag)
+            .arg(&self.config.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("failed to run: {e}"))?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let payload = input.to_string();
+        let output = tokio::time::timeout(Duration::from_secs(self.config.timeout_secs), async move {
+            // A hook may exit without reading its input.
+            let _ = stdin.write_all(payload.as_bytes()).await;
+            drop(stdin);
+            child.wait_with_output().await
+        })
+        .await
+        .map_err(|_| format!("timed out after {}s", self.config.timeout_secs))?
+        .map_err(|e| e.to_string())?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            return Err(if stderr.is_empty() {
+                format!("exited with {}", output.status)
+            } else {
+                stderr
+            });
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if stdout.trim().is_empty() {
+            return Ok(CommandReply::default());
+        }
+        serde_json::from_str(stdout.trim()).map_err(|e| format!("invalid JSON reply: {e}"))
+    }
+
+    /// Run at a stage that cannot be blocked; failures are only logged.
+    async fn observe(&self, input: Value) -> Option<CommandReply> {
+        match self.run(input).await {
+            Ok(reply) => Some(reply),
+            Err(e) => {
+                warn!("Hook {} failed: {}", self.name, e);
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Hook for CommandHook {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn pre_prompt(&self, messages: &mut Vec<Message>) -> Result<(), AgentError> {
+        if !self.applies_to(HookStage::PrePrompt, None) {
+            return Ok(());
+        }
+        let reply = self
+            .run(json!({ "stage": HookStage::PrePrompt, "messages": messages }))
+            .await
+            .map_err(|e| AgentError::Config(format!("Prompt blocked by hook {}: {}", self.name, e)))?;
+        if let Some(replacement) = reply.messages {
+            *messages = replacement;
+        }
+        Ok(())
+    }
+
+    async fn post_llm(&self, response: &mut LlmResponse) {
+        if !self.applies_to(HookStage::PostLlm, None) {
+            return;
+        }
+        let input = json!({
+            "stage": HookStage::PostLlm,
+            "content": response.content,
+            "tool_calls": response.tool_calls,
+        });
+        if let Some(content) = self.observe(input).await.and_then(|r| r.content) {
+            response.content = content;
+        }
+    }
+
+    async fn pre_tool(&self, call: &mut ToolCall) -> HookAction {
+        if !self.applies_to(HookStage::PreTool, Some(&call.name)) {
+            return HookAction::Continue;
+        }
+        let arguments: Value = serde_json::from_str(&call.arguments).unwrap_or(Value::String(call.arguments.clone()));
+        let input = json!({ "stage": HookStage::PreTool, "tool": call.name, "arguments": arguments });
+        match self.run(input).await {
+            Ok(CommandReply { veto: Some(reason), .. }) => HookAction::Veto(reason),
+            Ok(CommandReply { arguments: Some(arguments), .. }) => {
+                call.arguments = arguments.to_string();
+                HookAction::Continue
+            }
+            Ok(_) => HookAction::Continue,
+            Err(reason) => HookAction::Veto(reason),
+        }
+    }
+
+    async fn post_tool(&self, call: &ToolCall, output: &mut ToolOutput) {
+        if !self.applies_to(HookStage::PostTool, Some(&call.name)) {
+            return;
+        }
+        let input = json!({
+            "stage": HookStage::PostTool,
+            "tool": call.name,
+            "output": output.content,
+            "is_error": output.is_error,
+        });
+        if let Some(content) = self.observe(input).await.and_then(|r| r.content) {
+            output.content = content;
+        }
+    }
+
+    async fn on_error(&self, error: &AgentError) {
+        if self.applies_to(HookStage::OnError, None) {
+            self.observe(json!({ "stage": HookStage::OnError, "error": error.to_string() }))
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(stage: HookStage, command: &str) -> CommandHook {
+        CommandHook::new(HookConfig {
+            stage,
+            command: command.into(),
+            tools: Vec::new(),
+            timeout_secs: 10,
+        })
+    }
+
+    fn call(name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: "call-1".into(),
+            name: name.into(),
+            arguments: arguments.into(),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_hook_rewrites_and_vetoes_tool_calls() {
+        let mut registry = HookRegistry::new();
+        registry.register(Arc::new(hook(
+            HookStage::PreTool,
+            r#"grep -q 'rm -rf' && echo '{"veto":"destructive command"}' || echo '{"arguments":{"command":"ls -la"}}'"#,
+        )));
+
+        let mut safe = call("shell_exec", r#"{"command":"ls"}"#);
+        assert_eq!(registry.pre_tool(&mut safe).await, HookAction::Continue);
+        assert_eq!(safe.arguments, r#"{"command":"ls -la"}"#);
+
+        let mut unsafe_call = call("shell_exec", r#"{"command":"rm -rf /"}"#);
+        match registry.pre_tool(&mut unsafe_call).await {
+            HookAction::Veto(reason) => assert!(reason.ends_with("destructive command"), "{reason}"),
+            HookAction::Continue => panic!("expected a veto"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failing_command_blocks_only_where_it_can() {
+        let mut registry = HookRegistry::new();
+        registry.register(Arc::new(hook(HookStage::PrePrompt, "echo 'no secrets' >&2; exit 1")));
+        registry.register(Arc::new(hook(HookStage::PostTool, "exit 3")));
+
+        let mut messages = vec![Message::user("hi")];
+        let err = registry.pre_prompt(&mut messages).await.unwrap_err();
+        assert!(err.to_string().contains("no secrets"), "{err}");
+
+        let mut output = ToolOutput {
+            tool_call_id: "call-1".into(),
+            content: "done".into(),
+            is_error: false,
+            duration_ms: None,
+            images: Vec::new(),
+        };
+        registry.post_tool(&call("file_read", "{}"), &mut output).await;
+        assert_eq!(output.content, "done");
+    }
+
+    #[tokio::test]
+    async fn test_stage_and_tool_filters() {
+        let mut pre_tool = HookConfig {
+            stage: HookStage::PreTool,
+            command: "exit 1".into(),
+            tools: vec!["shell_exec".into()],
+            timeout_secs: 10,
+        };
+        let filtered = CommandHook::new(pre_tool.clone());
+        let mut read = call("file_read", "{}");
+        assert_eq!(filtered.pre_tool(&mut read).await, HookAction::Continue);
+        assert!(filtered.applies_to(HookStage::PreTool, Some("shell_exec")));
+
+        pre_tool.stage = HookStage::PostLlm;
+        let other_stage = CommandHook::new(pre_tool);
+        assert!(!other_stage.applies_to(HookStage::PreTool, Some("shell_exec")));
+    }
+}
@@ -0,0 +1,411 @@
+//! Conversation history from other tools, converted to [`Session`]s.
+//!
+//! Supported sources are the `conversations.json` of a ChatGPT or Claude
+//! data export, and aider's `.aider.chat.history.md`. Only user and
+//! assistant text is kept; system prompts, tool traffic and attachments
+//! are dropped. Each session records where it came from in
+//! `metadata["imported_from"]` (e.g. `chatgpt:<conversation id>`), so
+//! importing the same export twice adds nothing.
+
+use crate::error::AgentError;
+use crate::session::Session;
+use crate::types::Message;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Metadata key holding `<source>:<id>` of an imported conversation.
+pub const IMPORTED_FROM: &str = "imported_from";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSource {
+    ChatGpt,
+    Claude,
+    Aider,
+}
+
+impl ImportSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ChatGpt => "chatgpt",
+            Self::Claude => "claude",
+            Self::Aider => "aider",
+        }
+    }
+
+    /// Convert an export in this source's format.
+    pub fn parse(self, data: &str) -> Result<Vec<Session>, AgentError> {
+        match self {
+            Self::ChatGpt => chatgpt(data),
+            Self::Claude => claude(data),
+            Self::Aider => Ok(aider(data)),
+        }
+    }
+}
+
+impl FromStr for ImportSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "chatgpt" | "openai" => Ok(Self::ChatGpt),
+            "claude" => Ok(Self::Claude),
+            "aider" => Ok(Self::Aider),
+            other => Err(format!(
+                "Unknown import source: '{}'. Use 'chatgpt', 'claude' or 'aider'.",
+                other
+            )),
+        }
+    }
+}
+
+/// A session for an imported conversation; timestamps come from the
+/// messages when the export has none of its own.
+fn imported(
+    source: ImportSource,
+    id: &str,
+    name: &str,
+    messages: Vec<Message>,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+) -> Session {
+    let mut session = Session::new(if name.trim().is_empty() {
+        format!("Imported from {}", source.as_str())
+    } else {
+        name.trim().to_string()
+    });
+    let first = messages.first().map(|m| m.timestamp);
+    let last = messages.last().map(|m| m.timestamp);
+    session.created_at = created_at.or(first).unwrap_or(session.created_at);
+    session.updated_at = updated_at.or(last).unwrap_or(session.created_at);
+    session.messages = messages;
+    session
+        .metadata
+        .insert(IMPORTED_FROM.into(), format!("{}:{}", source.as_str(), id));
+    session.add_tag(source.as_str());
+    session
+}
+
+fn message(role: &str, content: String, at: Option<DateTime<Utc>>) -> Option<Message> {
+    let mut message = match role {
+        "user" | "human" => Message::user(content),
+        "assistant" => Message::assistant(content),
+        _ => return None,
+    };
+    if let Some(at) = at {
+        message.timestamp = at;
+    }
+    Some(message)
+}
+
+fn from_epoch(value: &Value) -> Option<DateTime<Utc>> {
+    let secs = value.as_f64()?;
+    DateTime::from_timestamp(secs.trunc() as i64, (secs.fract() * 1e9) as u32)
+}
+
+fn from_rfc3339(value: &Value) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.as_str()?)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn conversations(data: &str) -> Result<Vec<Value>, AgentError> {
+    match serde_json::from_str(data)? {
+        Value::Array(items) => Ok(items),
+        _ => Err(AgentError::Session(
+            "Expected a JSON array of conversations (conversations.json)".into(),
+        )),
+    }
+}
+
+/// ChatGPT's `conversations.json`. Each conversation is a tree of edits and
+/// regenerations; the branch ending at `current_node` is the one shown.
+pub fn chatgpt(data: &str) -> Result<Vec<Session>, AgentError> {
+    let mut sessions = Vec::new();
+    for conversation in conversations(data)? {
+        let mapping = &conversation["mapping"];
+        let Some(nodes) = mapping.as_object() else {
+            continue;
+        };
+        let mut node_id = conversation["current_node"].as_str().map(String::from);
+        if node_id.is_none() {
+            // Older exports: follow the last child from the root.
+            let mut id = nodes
+                .iter()
+                .find(|(_, node)| node["parent"].is_null())
+                .map(|(id, _)| id.clone());
+            while let Some(child) = id
+                .as_ref()
+                .and_then(|id| mapping[id]["children"].as_array()?.last()?.as_str())
+            {
+                id = Some(child.to_string());
+            }
+            node_id = id;
+        }
+
+        let created_at = from_epoch(&conversation["create_time"]);
+        let mut messages = Vec::new();
+        while let Some(id) = node_id {
+            let node = &mapping[&id];
+            let msg = &node["message"];
+            let hidden = msg["metadata"]["is_visually_hidden_from_conversation"]
+                .as_bool()
+                .unwrap_or(false);
+            let content = &msg["content"];
+            let text = match content["parts"].as_array() {
+                Some(parts) => parts
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                None => content["text"].as_str().unwrap_or_default().to_string(),
+            };
+            if !hidden && !text.trim().is_empty() {
+                let role = msg["author"]["role"].as_str().unwrap_or_default();
+                let at = from_epoch(&msg["create_time"]).or(created_at);
+                messages.extend(message(role, text, at));
+            }
+            node_id = node["parent"].as_str().map(String::from);
+        }
+        messages.reverse();
+        if messages.is_empty() {
+            continue;
+        }
+
+        let id = conversation["conversation_id"]
+            .as_str()
+            .or(conversation["id"].as_str())
+            .unwrap_or_default();
+        sessions.push(imported(
+            ImportSource::ChatGpt,
+            id,
+            conversation["title"].as_str().unwrap_or_default(),
+            messages,
+            created_at,
+            from_epoch(&conversation["update_time"]),
+        ));
+    }
+    Ok(sessions)
+}
+
+/// Claude's `conversations.json`.
+pub fn claude(data: &str) -> Result<Vec<Session>, AgentError> {
+    let mut sessions = Vec::new();
+    for conversation in conversations(data)? {
+        let Some(chat) = conversation["chat_messages"].as_array() else {
+            continue;
+        };
+        let created_at = from_rfc3339(&conversation["created_at"]);
+        let messages: Vec<Message> = chat
+            .iter()
+            .filter_map(|msg| {
+                let blocks: Vec<&str> = msg["content"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|block| block["type"] == "text")
+                    .filter_map(|block| block["text"].as_str())
+                    .collect();
+                let text = match blocks.is_empty() {
+                    true => msg["text"].as_str().unwrap_or_default().to_string(),
+                    false => blocks.join("\n\n"),
+                };
+                if text.trim().is_empty() {
+                    return None;
+                }
+                let role = msg["sender"].as_str().unwrap_or_default();
+                message(role, text, from_rfc3339(&msg["created_at"]).or(created_at))
+            })
+            .collect();
+        if messages.is_empty() {
+            continue;
+        }
+        sessions.push(imported(
+            ImportSource::Claude,
+            conversation["uuid"].as_str().unwrap_or_default(),
+            conversation["name"].as_str().unwrap_or_default(),
+            messages,
+            created_at,
+            from_rfc3339(&conversation["updated_at"]),
+        ));
+    }
+    Ok(sessions)
+}
+
+/// aider's `.aider.chat.history.md`: one session per `# aider chat started
+/// at` heading, user prompts as `#### ` lines, and aider's own output
+/// (`> ` lines) skipped. Start times are in local time.
+pub fn aider(data: &str) -> Vec<Session> {
+    const STARTED: &str = "# aider chat started at ";
+
+    struct Chat {
+        started: Option<DateTime<Utc>>,
+        stamp: String,
+        messages: Vec<Message>,
+        role: &'static str,
+        text: Vec<String>,
+    }
+
+    impl Chat {
+        fn flush(&mut self) {
+            let text = self.text.join("\n").trim().to_string();
+            self.text.clear();
+            if !text.is_empty() {
+                self.messages.extend(message(self.role, text, self.started));
+            }
+        }
+    }
+
+    let mut sessions = Vec::new();
+    let mut chat: Option<Chat> = None;
+    let finish = |chat: Option<Chat>, sessions: &mut Vec<Session>| {
+        if let Some(mut chat) = chat {
+            chat.flush();
+            if !chat.messages.is_empty() {
+                sessions.push(imported(
+                    ImportSource::Aider,
+                    &chat.stamp,
+                    &format!("aider {}", chat.stamp),
+                    chat.messages,
+                    chat.started,
+                    chat.started,
+                ));
+            }
+        }
+    };
+
+    for line in data.lines() {
+        if let Some(stamp) = line.strip_prefix(STARTED) {
+            finish(chat.take(), &mut sessions);
+            let stamp = stamp.trim().to_string();
+            let started = NaiveDateTime::parse_from_str(&stamp, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .and_then(|t| Local.from_local_datetime(&t).earliest())
+                .map(|t| t.with_timezone(&Utc));
+            chat = Some(Chat {
+                started,
+                stamp,
+                messages: Vec::new(),
+                role: "user",
+                text: Vec::new(),
+            });
+            continue;
+        }
+        let Some(chat) = chat.as_mut() else {
+            continue;
+        };
+        if line.starts_with('>') {
+            continue;
+        }
+        let (role, text) = match line.strip_prefix("#### ") {
+            Some(prompt) => ("user", prompt),
+            None => ("assistant", line),
+        };
+        if role != chat.role {
+            chat.flush();
+            chat.role = role;
+        }
+        chat.text.push(text.to_string());
+    }
+    finish(chat, &mut sessions);
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Role;
+
+    #[test]
+    fn test_chatgpt_follows_current_branch() {
+        let data = r#"[{
+            "title": "Rust lifetimes",
+            "conversation_id": "c1",
+            "create_time": 1700000000.5,
+            "update_time": 1700000100.0,
+            "current_node": "a2",
+            "mapping": {
+                "root": {"id": "root", "message": null, "parent": null, "children": ["sys"]},
+                "sys": {"id": "sys", "parent": "root", "children": ["u1"],
+                        "message": {"author": {"role": "system"}, "content": {"content_type": "text", "parts": [""]}}},
+                "u1": {"id": "u1", "parent": "sys", "children": ["a1", "a2"],
+                       "message": {"author": {"role": "user"}, "create_time": 1700000001.0,
+                                   "content": {"content_type": "text", "parts": ["What is 'a?"]}}},
+                "a1": {"id": "a1", "parent": "u1", "children": [],
+                       "message": {"author": {"role": "assistant"}, "content": {"content_type": "text", "parts": ["Old answer"]}}},
+                "a2": {"id": "a2", "parent": "u1", "children": [],
+                       "message": {"author": {"role": "assistant"}, "content": {"content_type": "text", "parts": ["A lifetime."]}}}
+            }
+        }]"#;
+        let sessions = chatgpt(data).unwrap();
+        assert_eq!(sessions.len(), 1);
+        let session = &sessions[0];
+        assert_eq!(session.name, "Rust lifetimes");
+        assert_eq!(session.metadata[IMPORTED_FROM], "chatgpt:c1");
+        assert_eq!(session.tags, ["chatgpt"]);
+        assert_eq!(session.created_at.timestamp(), 1700000000);
+        let contents: Vec<&str> = session.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["What is 'a?", "A lifetime."]);
+        assert_eq!(session.messages[0].timestamp.timestamp(), 1700000001);
+    }
+
+    #[test]
+    fn test_claude_export() {
+        let data = r#"[{
+            "uuid": "u-1", "name": "", "created_at": "2024-03-01T10:00:00Z",
+            "updated_at": "2024-03-01T10:05:00Z",
+            "chat_messages": [
+                {"sender": "human", "text": "Hi", "created_at": "2024-03-01T10:00:00Z"},
+                {"sender": "assistant", "text": "ignored",
+                 "content": [{"type": "text", "text": "Hello!"}, {"type": "tool_use", "name": "x"}]}
+            ]
+        }, {"uuid": "empty", "name": "Nothing", "chat_messages": []}]"#;
+        let sessions = claude(data).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].name, "Imported from claude");
+        assert_eq!(sessions[0].messages[1].content, "Hello!");
+        assert_eq!(sessions[0].messages[1].role, Role::Assistant);
+        assert_eq!(sessions[0].updated_at.to_rfc3339(), "2024-03-01T10:05:00+00:00");
+        assert!(claude("{}").is_err());
+    }
+
+    #[test]
+    fn test_aider_history() {
+        let data = "\
+# aider chat started at 2024-05-01 09:30:00
+
+> Aider v0.50.0
+> Model: gpt-4o
+
+#### add a --verbose flag
+#### to main.py
+
+I'll add the flag.
+
+```python
+parser.add_argument(\"--verbose\")
+```
+
+> Applied edit to main.py
+
+#### thanks
+
+You're welcome.
+
+# aider chat started at 2024-05-02 14:00:00
+
+> Aider v0.50.0
+";
+        let sessions = aider(data);
+        assert_eq!(sessions.len(), 1);
+        let messages = &sessions[0].messages;
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0].content, "add a --verbose flag\nto main.py");
+        assert!(messages[1].content.starts_with("I'll add the flag.\n\n```python"));
+        assert_eq!(messages[3].content, "You're welcome.");
+        assert_eq!(
+            sessions[0].metadata[IMPORTED_FROM],
+            "aider:2024-05-01 09:30:00"
+        );
+    }
+}
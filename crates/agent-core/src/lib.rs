@@ -1,25 +1,44 @@
 pub mod agent_loop;
+pub mod agents;
 pub mod capture;
+pub mod checkpoint;
 pub mod claude_detect;
 pub mod command_parser;
 pub mod config;
 pub mod context;
+pub mod dataset;
+pub mod environments;
 pub mod error;
+pub mod error_assist;
 pub mod event_bus;
 pub mod git_linker;
 pub mod git_tracker;
+pub mod guardrails;
+pub mod hooks;
+pub mod import;
+pub mod middleware;
+pub mod mock_provider;
+pub mod network;
+pub mod notifications;
+pub mod path_rules;
 pub mod profiles;
 pub mod provider;
 pub mod provider_registry;
+pub mod result_cache;
 pub mod scheduler;
 pub mod scrubber;
 pub mod session;
 pub mod terminal_session;
+pub mod tool_call_parser;
 pub mod tool_loop;
+pub mod tool_quota;
 pub mod tool_registry;
+pub mod tool_validation;
 pub mod types;
+pub mod workspace;
 
 pub use agent_loop::{AgentLoop, AgentTurnResult};
+pub use agents::{AgentPreset, AgentStore};
 pub use capture::HookBackend;
 pub use claude_detect::ClaudeDetector;
 pub use command_parser::CommandParser;
@@ -29,6 +48,7 @@ pub use error::AgentError;
 pub use event_bus::{EventBus, PlatformEvent};
 pub use git_linker::GitLinker;
 pub use git_tracker::GitTracker;
+pub use hooks::{Hook, HookRegistry};
 pub use profiles::ProfileConfig;
 pub use provider::ProviderChain;
 pub use provider_registry::{ProviderInfo, ProviderKind, ProviderRegistry};
@@ -0,0 +1,240 @@
+//! Scripted responses for providers with `kind = "mock"`.
+//!
+//! A [`MockScenario`] is read from a YAML file and answers chat completion
+//! requests without any network, so the UI can be demoed and skills and
+//! plugins developed offline. The answer depends only on the request: the
+//! first turn whose `match` occurs in the last user message is chosen, and
+//! the number of model calls already made since that message picks which
+//! of its responses comes next. Once a turn's responses run out the model
+//! returns nothing, which ends the turn.
+//!
+//! ```yaml
+//! delay_ms: 30                      # pause between streamed words
+//! turns:
+//!   - match: "weather"              # case-insensitive; omit to match anything
+//!     responses:
+//!       - content: "Let me check the forecast."
+//!         tool_calls:
+//!           - name: web_fetch
+//!             arguments: { url: "https://wttr.in/?format=3" }
+//!       - content: "It looks sunny today."
+//! fallback: "No scripted turn matches that message."
+//! ```
+
+use crate::error::AgentError;
+use crate::middleware::ProviderResponse;
+use crate::types::ToolCall;
+use async_openai::types::{
+    ChatCompletionRequestMessage, ChatCompletionRequestUserMessageContent,
+    ChatCompletionRequestUserMessageContentPart,
+};
+use serde::Deserialize;
+use std::path::Path;
+
+/// A scenario file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MockScenario {
+    /// Milliseconds between streamed words (0 streams everything at once).
+    pub delay_ms: u64,
+    pub turns: Vec<MockTurn>,
+    /// Answer when no turn matches.
+    pub fallback: Option<String>,
+}
+
+/// The responses to one kind of user message.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockTurn {
+    /// Text the last user message must contain; matches anything if unset.
+    #[serde(default, rename = "match")]
+    pub pattern: Option<String>,
+    /// One per model call in the turn, in order.
+    pub responses: Vec<MockResponse>,
+}
+
+/// What the model "says" on one call.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MockResponse {
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub tool_calls: Vec<MockToolCall>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockToolCall {
+    pub name: String,
+    /// A mapping, or a string sent as is.
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+const DEFAULT_FALLBACK: &str =
+    "This is the mock provider: no turn in the scenario matches that message.";
+
+impl MockScenario {
+    pub fn load(path: &Path) -> Result<Self, AgentError> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            AgentError::Config(format!(
+                "Cannot read mock scenario {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::parse(&text).map_err(|e| {
+            AgentError::Config(format!("Invalid mock scenario {}: {}", path.display(), e))
+        })
+    }
+
+    pub fn parse(text: &str) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::from_str(text)
+    }
+
+    /// The scripted answer to a request with these messages. Usage is left
+    /// for the caller to estimate.
+    pub fn respond(&self, messages: &[ChatCompletionRequestMessage]) -> ProviderResponse {
+        let last_user = messages
+            .iter()
+            .rposition(|m| matches!(m, ChatCompletionRequestMessage::User(_)));
+        let text = last_user
+            .and_then(|i| user_text(&messages[i]))
+            .unwrap_or_default()
+            .to_lowercase();
+        let calls = last_user.map_or(0, |i| {
+            messages[i + 1..]
+                .iter()
+                .filter(|m| matches!(m, ChatCompletionRequestMessage::Assistant(_)))
+                .count()
+        });
+
+        let turn = self.turns.iter().find(|turn| {
+            turn.pattern
+                .as_deref()
+                .is_none_or(|p| text.contains(&p.to_lowercase()))
+        });
+        let Some(turn) = turn else {
+            return ProviderResponse {
+                content: if calls == 0 {
+                    self.fallback
+                        .as_deref()
+                        .unwrap_or(DEFAULT_FALLBACK)
+                        .to_string()
+                } else {
+                    String::new()
+                },
+                ..Default::default()
+            };
+        };
+        let Some(response) = turn.responses.get(calls) else {
+            return ProviderResponse::default();
+        };
+        let tool_calls = response
+            .tool_calls
+            .iter()
+            .enumerate()
+            .map(|(i, call)| ToolCall {
+                id: format!("mock_{}_{}", calls, i),
+                name: call.name.clone(),
+                arguments: match &call.arguments {
+                    serde_json::Value::String(s) => s.clone(),
+                    serde_json::Value::Null => "{}".to_string(),
+                    other => other.to_string(),
+                },
+            })
+            .collect();
+        ProviderResponse {
+            content: response.content.clone(),
+            tool_calls,
+            usage: None,
+        }
+    }
+}
+
+fn user_text(message: &ChatCompletionRequestMessage) -> Option<String> {
+    let ChatCompletionRequestMessage::User(user) = message else {
+        return None;
+    };
+    Some(match &user.content {
+        ChatCompletionRequestUserMessageContent::Text(text) => text.clone(),
+        ChatCompletionRequestUserMessageContent::Array(parts) => parts
+            .iter()
+            .filter_map(|part| match part {
+                ChatCompletionRequestUserMessageContentPart::Text(t) => Some(t.text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::types::{
+        ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestUserMessageArgs,
+    };
+
+    const SCENARIO: &str = r#"
+turns:
+  - match: Weather
+    responses:
+      - content: "Checking."
+        tool_calls:
+          - name: web_fetch
+            arguments: { url: "https://wttr.in" }
+      - content: "Sunny."
+  - responses:
+      - content: "Hello!"
+fallback: "unused"
+"#;
+
+    fn user(text: &str) -> ChatCompletionRequestMessage {
+        ChatCompletionRequestUserMessageArgs::default()
+            .content(text)
+            .build()
+            .unwrap()
+            .into()
+    }
+
+    fn assistant() -> ChatCompletionRequestMessage {
+        ChatCompletionRequestAssistantMessageArgs::default()
+            .content("...")
+            .build()
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn test_turns_are_picked_by_message_and_progress() {
+        let scenario = MockScenario::parse(SCENARIO).unwrap();
+
+        let first = scenario.respond(&[user("what's the weather like?")]);
+        assert_eq!(first.content, "Checking.");
+        assert_eq!(first.tool_calls.len(), 1);
+        assert_eq!(first.tool_calls[0].name, "web_fetch");
+        assert_eq!(
+            first.tool_calls[0].arguments,
+            r#"{"url":"https://wttr.in"}"#
+        );
+
+        let second = scenario.respond(&[user("what's the weather like?"), assistant()]);
+        assert_eq!(second.content, "Sunny.");
+        assert!(second.tool_calls.is_empty());
+
+        // Out of responses: the turn ends.
+        let done = scenario.respond(&[user("weather"), assistant(), assistant()]);
+        assert!(done.content.is_empty() && done.tool_calls.is_empty());
+
+        // Only the latest user message counts.
+        let hello = scenario.respond(&[user("weather"), assistant(), user("hi")]);
+        assert_eq!(hello.content, "Hello!");
+    }
+
+    #[test]
+    fn test_fallback_when_nothing_matches() {
+        let scenario =
+            MockScenario::parse("turns:\n  - match: deploy\n    responses: []\n").unwrap();
+        assert_eq!(scenario.respond(&[user("hi")]).content, DEFAULT_FALLBACK);
+        assert!(MockScenario::parse("turns: 3").is_err());
+    }
+}
@@ -0,0 +1,200 @@
+//! Outbound HTTP settings (`[network]`) for `web_fetch` and model providers.
+//!
+//! A proxy (`http://`, `https://`, `socks5://` or `socks5h://`) routes
+//! their requests, except to hosts in `no_proxy`. A DNS-over-HTTPS
+//! resolver, queried through the same proxy with the JSON API
+//! (`application/dns-json`, as Cloudflare, Google and Quad9 serve it),
+//! replaces the system resolver; the resolver's own host name is still
+//! looked up the usual way.
+
+use crate::config::NetworkConfig;
+use crate::error::AgentError;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::Deserialize;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a DoH query may take.
+const DOH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// DNS record types asked for.
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+
+/// The configured proxy, if any.
+pub fn proxy(config: &NetworkConfig) -> Result<Option<reqwest::Proxy>, AgentError> {
+    let Some(url) = config.proxy.as_deref().filter(|u| !u.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let proxy = reqwest::Proxy::all(url)
+        .map_err(|e| AgentError::Config(format!("Invalid [network] proxy: {}", e)))?;
+    Ok(Some(proxy.no_proxy(reqwest::NoProxy::from_string(
+        &config.no_proxy.join(","),
+    ))))
+}
+
+/// The configured DoH resolver, if any.
+pub fn doh_resolver(config: &NetworkConfig) -> Result<Option<DohResolver>, AgentError> {
+    match config.doh_url.as_deref().filter(|u| !u.trim().is_empty()) {
+        Some(url) => DohResolver::new(url, proxy(config)?).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// `builder` with the proxy and DoH resolver applied.
+pub fn apply(
+    mut builder: reqwest::ClientBuilder,
+    config: &NetworkConfig,
+) -> Result<reqwest::ClientBuilder, AgentError> {
+    if let Some(proxy) = proxy(config)? {
+        builder = builder.proxy(proxy);
+    }
+    if let Some(resolver) = doh_resolver(config)? {
+        builder = builder.dns_resolver(Arc::new(resolver));
+    }
+    Ok(builder)
+}
+
+/// Resolves host names with DNS-over-HTTPS.
+#[derive(Clone)]
+pub struct DohResolver {
+    url: reqwest::Url,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+impl DohResolver {
+    pub fn new(url: &str, proxy: Option<reqwest::Proxy>) -> Result<Self, AgentError> {
+        let url = reqwest::Url::parse(url)
+            .ok()
+            .filter(|u| u.scheme() == "https")
+            .ok_or_else(|| {
+                AgentError::Config(format!(
+                    "Invalid [network] doh_url '{}': expected an https:// URL",
+                    url
+                ))
+            })?;
+        let mut builder = reqwest::Client::builder()
+            .timeout(DOH_TIMEOUT)
+            .user_agent("agent-shell/0.1");
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(proxy);
+        }
+        let client = builder
+            .build()
+            .map_err(|e| AgentError::Config(format!("Failed to build DoH client: {}", e)))?;
+        Ok(Self { url, client })
+    }
+
+    /// The IPv4 and IPv6 addresses of `host`.
+    pub async fn lookup(&self, host: &str) -> Result<Vec<IpAddr>, String> {
+        if let Ok(ip) = host.trim_matches(['[', ']']).parse::<IpAddr>() {
+            return Ok(vec![ip]);
+        }
+        let (v4, v6) = tokio::join!(self.query(host, TYPE_A), self.query(host, TYPE_AAAA));
+        let mut addrs = v4?;
+        // Hosts without IPv6 are common; only fail if both lookups do.
+        addrs.extend(v6.unwrap_or_default());
+        if addrs.is_empty() {
+            return Err(format!("DoH returned no addresses for '{}'", host));
+        }
+        Ok(addrs)
+    }
+
+    async fn query(&self, host: &str, record_type: u16) -> Result<Vec<IpAddr>, String> {
+        let response: DohResponse = self
+            .client
+            .get(self.url.clone())
+            .query(&[("name", host), ("type", &record_type.to_string())])
+            .header(reqwest::header::ACCEPT, "application/dns-json")
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("DoH query for '{}' failed: {}", host, e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid DoH response for '{}': {}", host, e))?;
+        Ok(parse_answer(&response, record_type))
+    }
+}
+
+/// Addresses of the wanted type; CNAME records in between are skipped.
+fn parse_answer(response: &DohResponse, record_type: u16) -> Vec<IpAddr> {
+    // Status 0 is NOERROR; anything else (NXDOMAIN...) has no answer.
+    if response.status != 0 {
+        return Vec::new();
+    }
+    response
+        .answer
+        .iter()
+        .filter(|a| a.record_type == record_type)
+        .filter_map(|a| a.data.parse().ok())
+        .collect()
+}
+
+impl Resolve for DohResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addrs = resolver.lookup(name.as_str()).await?;
+            let addrs: Addrs = Box::new(addrs.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_answer_skips_cnames() {
+        let response: DohResponse = serde_json::from_str(
+            r#"{"Status":0,"Answer":[
+                {"name":"docs.rs","type":5,"data":"docs.rs.cdn.example."},
+                {"name":"docs.rs.cdn.example","type":1,"data":"93.184.216.34"},
+                {"name":"docs.rs.cdn.example","type":28,"data":"2606:2800:220:1::1"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parse_answer(&response, TYPE_A),
+            ["93.184.216.34".parse::<IpAddr>().unwrap()]
+        );
+        assert_eq!(parse_answer(&response, TYPE_AAAA).len(), 1);
+        let nxdomain: DohResponse = serde_json::from_str(r#"{"Status":3}"#).unwrap();
+        assert!(parse_answer(&nxdomain, TYPE_A).is_empty());
+    }
+
+    #[test]
+    fn test_settings_are_validated() {
+        let mut config = NetworkConfig::default();
+        assert!(proxy(&config).unwrap().is_none());
+        config.proxy = Some("socks5h://127.0.0.1:1080".into());
+        config.no_proxy = vec!["localhost".into(), ".corp.example.com".into()];
+        assert!(proxy(&config).unwrap().is_some());
+        config.proxy = Some("not a url".into());
+        assert!(proxy(&config).is_err());
+
+        config.proxy = None;
+        config.doh_url = Some("http://dns.example/dns-query".into());
+        assert!(doh_resolver(&config).is_err());
+        config.doh_url = Some("https://cloudflare-dns.com/dns-query".into());
+        assert!(doh_resolver(&config).unwrap().is_some());
+    }
+}
@@ -0,0 +1,92 @@
+//! Notifications the agent raises for the user.
+//!
+//! The `notify_user` tool publishes [`Notification`]s on a process-wide
+//! channel; the server streams them to the web UI as toasts
+//! (`GET /v1/notifications/stream`). Tools are built once and have no
+//! handle on the server, hence the global rather than a field.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+/// Notifications a slow follower may fall behind by before it skips ahead.
+const CHANNEL_CAPACITY: usize = 64;
+
+/// How a notification is styled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationLevel {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// One message for the user.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Notification {
+    /// Increases by one per notification; sent as the SSE `id`.
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub level: NotificationLevel,
+    pub title: String,
+    pub message: String,
+}
+
+struct Hub {
+    next_seq: AtomicU64,
+    sender: broadcast::Sender<Notification>,
+}
+
+fn hub() -> &'static Hub {
+    static HUB: OnceLock<Hub> = OnceLock::new();
+    HUB.get_or_init(|| Hub {
+        next_seq: AtomicU64::new(1),
+        sender: broadcast::channel(CHANNEL_CAPACITY).0,
+    })
+}
+
+/// Send a notification to everyone subscribed, returning it. Nobody
+/// listening (no web UI open) is fine.
+pub fn publish(level: NotificationLevel, title: &str, message: &str) -> Notification {
+    let hub = hub();
+    let notification = Notification {
+        seq: hub.next_seq.fetch_add(1, Ordering::Relaxed),
+        timestamp: Utc::now(),
+        level,
+        title: title.to_string(),
+        message: message.to_string(),
+    };
+    let _ = hub.sender.send(notification.clone());
+    notification
+}
+
+/// Receive notifications published from now on.
+pub fn subscribe() -> broadcast::Receiver<Notification> {
+    hub().sender.subscribe()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_subscribers_receive_published() {
+        let mut rx = subscribe();
+        let sent = publish(NotificationLevel::Warning, "Disk", "Root volume is 95% full");
+        let received = loop {
+            // Other tests may publish concurrently.
+            let n = rx.recv().await.unwrap();
+            if n.seq == sent.seq {
+                break n;
+            }
+        };
+        assert_eq!(received, sent);
+        assert!(publish(NotificationLevel::Info, "a", "b").seq > sent.seq);
+    }
+}
@@ -0,0 +1,235 @@
+//! Per-path access rules (`[sandbox.paths]`).
+//!
+//! Rules refine the workspace root: each maps a glob, relative to every
+//! workspace root, to `rw`, `ro` or `deny`:
+//!
+//! ```toml
+//! [sandbox.paths]
+//! "src/**" = "rw"
+//! "migrations/**" = "ro"
+//! "secrets/**" = "deny"
+//! "*.pem" = "deny"
+//! ```
+//!
+//! `*` and `?` stay within one path component and `**` spans any number. A
+//! pattern without a `/` matches at any depth, and a rule covers everything
+//! under the paths it matches. A matching `deny` always wins; otherwise
+//! the longest matching pattern does, so `"migrations/dev/**" = "rw"` opens
+//! up part of a read-only tree. Paths no rule matches are `rw`. Rules only
+//! apply under a workspace root.
+//!
+//! The rules are compiled once when the built-in tools are registered and
+//! shared with each tool that checks paths.
+
+use crate::config::PathAccess;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// One `[sandbox.paths]` entry.
+#[derive(Debug, Clone)]
+pub struct PathRule {
+    pub pattern: String,
+    pub access: PathAccess,
+    regex: Regex,
+}
+
+/// The rules of `[sandbox.paths]`, compiled.
+#[derive(Debug, Clone, Default)]
+pub struct PathRules {
+    rules: Vec<PathRule>,
+}
+
+impl PathRules {
+    pub fn new(config: &BTreeMap<String, PathAccess>) -> Self {
+        let rules = config
+            .iter()
+            .filter_map(|(pattern, access)| {
+                let regex = glob_regex(pattern);
+                match Regex::new(&regex) {
+                    Ok(regex) => Some(PathRule {
+                        pattern: pattern.clone(),
+                        access: *access,
+                        regex,
+                    }),
+                    Err(e) => {
+                        tracing::warn!("Ignoring [sandbox.paths] rule '{}': {}", pattern, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The rule deciding access to `relative`, a path relative to a
+    /// workspace root, if any matches.
+    pub fn rule_for(&self, relative: &Path) -> Option<&PathRule> {
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.rules
+            .iter()
+            .filter(|rule| rule.regex.is_match(&relative))
+            .max_by_key(|rule| (rule.access == PathAccess::Deny, rule.pattern.len()))
+    }
+
+    /// The rule deciding access to `path` (canonical) under the first of
+    /// `roots` (canonical) that contains it.
+    pub fn rule_under(&self, path: &Path, roots: &[PathBuf]) -> Option<&PathRule> {
+        let relative = roots.iter().find_map(|root| path.strip_prefix(root).ok())?;
+        self.rule_for(relative)
+    }
+
+    /// Check that `path` (canonical) may be read, or written if `write`,
+    /// given the canonical workspace `roots`. The error says which rule
+    /// refused it.
+    pub fn check(&self, path: &Path, roots: &[PathBuf], write: bool) -> Result<(), String> {
+        let Some(rule) = self.rule_under(path, roots) else {
+            return Ok(());
+        };
+        match rule.access {
+            PathAccess::Deny => Err(format!(
+                "Path '{}' is denied by the [sandbox.paths] rule '{}'",
+                path.display(),
+                rule.pattern
+            )),
+            PathAccess::Ro if write => Err(format!(
+                "Path '{}' is read-only under the [sandbox.paths] rule '{}'",
+                path.display(),
+                rule.pattern
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether `path` (canonical) is denied under the canonical `roots`.
+    pub fn is_denied(&self, path: &Path, roots: &[PathBuf]) -> bool {
+        self.rule_under(path, roots)
+            .is_some_and(|rule| rule.access == PathAccess::Deny)
+    }
+
+    /// Paths under `root` that `ro` and `deny` rules without wildcards
+    /// name, for sandbox profiles that can only match literal paths.
+    pub fn literal_paths(&self, root: &Path) -> Vec<(PathBuf, PathAccess)> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.access != PathAccess::Rw)
+            .filter_map(|rule| {
+                let pattern = trim_pattern(&rule.pattern);
+                (anchored(&rule.pattern) && !pattern.is_empty() && !pattern.contains(['*', '?']))
+                    .then(|| (root.join(pattern), rule.access))
+            })
+            .collect()
+    }
+}
+
+/// Whether a pattern only matches from the workspace root: it starts with
+/// or contains a `/` before its end.
+fn anchored(pattern: &str) -> bool {
+    pattern.starts_with('/') || pattern.trim_end_matches('/').contains('/')
+}
+
+/// A pattern without its leading and trailing `/` and a trailing `/**`,
+/// which rules imply.
+fn trim_pattern(pattern: &str) -> &str {
+    let pattern = pattern.trim_start_matches('/').trim_end_matches('/');
+    pattern.strip_suffix("/**").unwrap_or(pattern)
+}
+
+/// Regex for a `[sandbox.paths]` glob, matched against paths relative to
+/// a workspace root with `/` separators.
+fn glob_regex(pattern: &str) -> String {
+    let mut regex = String::from(if anchored(pattern) { "^" } else { "^(?:.*/)?" });
+    let pattern = trim_pattern(pattern);
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push_str("(?:/.*)?$");
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(entries: &[(&str, PathAccess)]) -> PathRules {
+        PathRules::new(&entries.iter().map(|(p, a)| (p.to_string(), *a)).collect())
+    }
+
+    #[test]
+    fn test_longest_matching_pattern_wins() {
+        let rules = rules(&[
+            ("src/**", PathAccess::Rw),
+            ("src/generated/**", PathAccess::Ro),
+            ("migrations/", PathAccess::Ro),
+            ("secrets/**", PathAccess::Deny),
+            ("*.pem", PathAccess::Deny),
+            ("docs/*.md", PathAccess::Ro),
+        ]);
+        let access = |p: &str| rules.rule_for(Path::new(p)).map(|r| r.access);
+        assert_eq!(access("src/main.rs"), Some(PathAccess::Rw));
+        assert_eq!(access("src/generated/api.rs"), Some(PathAccess::Ro));
+        assert_eq!(access("migrations"), Some(PathAccess::Ro));
+        assert_eq!(access("migrations/001.sql"), Some(PathAccess::Ro));
+        assert_eq!(access("secrets"), Some(PathAccess::Deny));
+        assert_eq!(access("src/keys/server.pem"), Some(PathAccess::Deny));
+        assert_eq!(access("docs/guide.md"), Some(PathAccess::Ro));
+        assert_eq!(access("docs/api/index.md"), None);
+        assert_eq!(access("src2/lib.rs"), None);
+        assert_eq!(access("README.md"), None);
+    }
+
+    #[test]
+    fn test_check_under_roots() {
+        let rules = rules(&[
+            ("migrations/**", PathAccess::Ro),
+            ("secrets/**", PathAccess::Deny),
+        ]);
+        let roots = [PathBuf::from("/repo")];
+        assert!(rules
+            .check(Path::new("/repo/migrations/1.sql"), &roots, false)
+            .is_ok());
+        let err = rules
+            .check(Path::new("/repo/migrations/1.sql"), &roots, true)
+            .unwrap_err();
+        assert!(
+            err.contains("read-only") && err.contains("'migrations/**'"),
+            "{}",
+            err
+        );
+        assert!(rules.is_denied(Path::new("/repo/secrets/key"), &roots));
+        // Outside every root the rules do not apply.
+        assert!(rules
+            .check(Path::new("/other/secrets/key"), &roots, true)
+            .is_ok());
+
+        assert_eq!(
+            rules.literal_paths(Path::new("/repo")),
+            [
+                (PathBuf::from("/repo/migrations"), PathAccess::Ro),
+                (PathBuf::from("/repo/secrets"), PathAccess::Deny),
+            ]
+        );
+    }
+}
@@ -1,11 +1,14 @@
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use std::time::Instant;
 
 use tracing::{debug, warn};
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, NetworkConfig, ProviderKind};
 use crate::error::AgentError;
+use crate::guardrails::GuardrailMiddleware;
+use crate::middleware::{CassetteMiddleware, MiddlewareStack, ProviderMiddleware};
+use crate::mock_provider::MockScenario;
 
 /// A resolved provider ready for use (API key resolved from env or config).
 #[derive(Debug, Clone)]
@@ -21,6 +24,46 @@ pub struct ResolvedProvider {
     pub max_tokens: u32,
     pub temperature: f32,
     pub top_p: f32,
+    /// Whether the model accepts image input.
+    pub vision: bool,
+    /// US dollars per million prompt tokens (0 when unknown).
+    pub input_cost_per_mtok: f64,
+    /// US dollars per million completion tokens (0 when unknown).
+    pub output_cost_per_mtok: f64,
+    /// Layers every request to this provider passes through.
+    pub middleware: MiddlewareStack,
+    /// Scripted responses used instead of the API (`kind = "mock"`).
+    pub mock: Option<Arc<MockScenario>>,
+    /// Proxy and DNS settings for requests to the API.
+    pub network: NetworkConfig,
+}
+
+/// The scenario of a `mock` provider; an API provider needs `api_base`.
+fn resolve_kind(
+    name: &str,
+    kind: ProviderKind,
+    scenario: Option<&std::path::Path>,
+    api_base: &str,
+) -> Result<Option<Arc<MockScenario>>, AgentError> {
+    match (kind, scenario) {
+        (ProviderKind::Mock, Some(path)) => Ok(Some(Arc::new(MockScenario::load(path)?))),
+        (ProviderKind::Mock, None) => Err(AgentError::Config(format!(
+            "Provider {name} has kind = \"mock\" but no scenario file"
+        ))),
+        (ProviderKind::Openai, _) if api_base.trim().is_empty() => Err(AgentError::Config(
+            format!("Provider {name} has no api_base"),
+        )),
+        (ProviderKind::Openai, _) => Ok(None),
+    }
+}
+
+impl ResolvedProvider {
+    /// Cost in US dollars of a call with these token counts.
+    pub fn cost_usd(&self, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+        (prompt_tokens as f64 * self.input_cost_per_mtok
+            + completion_tokens as f64 * self.output_cost_per_mtok)
+            / 1_000_000.0
+    }
 }
 
 /// Error classification for failover decisions.
@@ -71,18 +114,18 @@ impl ProviderChain {
     /// Otherwise the single `provider` config is wrapped (including any
     /// legacy `failover` endpoints) for backward compatibility.
     pub fn from_config(config: &AppConfig) -> Result<Self, AgentError> {
-        let providers = if !config.providers.is_empty() {
+        let mut providers = if !config.providers.is_empty() {
             config
                 .providers
                 .iter()
-                .map(|entry| {
+                .map(|entry| -> Result<ResolvedProvider, AgentError> {
                     let api_key = entry.api_key.clone().or_else(|| {
                         entry
                             .api_key_env
                             .as_ref()
                             .and_then(|env_var| std::env::var(env_var).ok())
                     });
-                    ResolvedProvider {
+                    Ok(ResolvedProvider {
                         name: entry.name.clone(),
                         api_base: entry.api_base.clone(),
                         model: entry.model.clone(),
@@ -94,9 +137,20 @@ impl ProviderChain {
                         max_tokens: entry.max_tokens.unwrap_or(config.provider.max_tokens),
                         temperature: entry.temperature.unwrap_or(config.provider.temperature),
                         top_p: entry.top_p.unwrap_or(config.provider.top_p),
-                    }
+                        vision: entry.vision,
+                        input_cost_per_mtok: entry.input_cost_per_mtok.unwrap_or(0.0),
+                        output_cost_per_mtok: entry.output_cost_per_mtok.unwrap_or(0.0),
+                        middleware: MiddlewareStack::from_config(&entry.middleware)?,
+                        mock: resolve_kind(
+                            &entry.name,
+                            entry.kind,
+                            entry.scenario.as_deref(),
+                            &entry.api_base,
+                        )?,
+                        network: config.network.clone(),
+                    })
                 })
-                .collect()
+                .collect::<Result<Vec<_>, _>>()?
         } else {
             // Backward compatibility: wrap [provider] + legacy failover entries.
             let mut chain = vec![ResolvedProvider {
@@ -111,6 +165,17 @@ impl ProviderChain {
                 max_tokens: config.provider.max_tokens,
                 temperature: config.provider.temperature,
                 top_p: config.provider.top_p,
+                vision: config.provider.vision,
+                input_cost_per_mtok: config.provider.input_cost_per_mtok.unwrap_or(0.0),
+                output_cost_per_mtok: config.provider.output_cost_per_mtok.unwrap_or(0.0),
+                middleware: MiddlewareStack::default(),
+                mock: resolve_kind(
+                    "default",
+                    config.provider.kind,
+                    config.provider.scenario.as_deref(),
+                    &config.provider.api_base,
+                )?,
+                network: config.network.clone(),
             }];
 
             for (i, fo) in config.provider.failover.iter().enumerate() {
@@ -129,6 +194,20 @@ impl ProviderChain {
                     max_tokens: config.provider.max_tokens,
                     temperature: config.provider.temperature,
                     top_p: config.provider.top_p,
+                    // Same model as the primary unless overridden.
+                    vision: fo.model.is_none() && config.provider.vision,
+                    // Prices are only known for the primary's model.
+                    input_cost_per_mtok: match fo.model {
+                        None => config.provider.input_cost_per_mtok.unwrap_or(0.0),
+                        Some(_) => 0.0,
+                    },
+                    output_cost_per_mtok: match fo.model {
+                        None => config.provider.output_cost_per_mtok.unwrap_or(0.0),
+                        Some(_) => 0.0,
+                    },
+                    middleware: MiddlewareStack::default(),
+                    mock: None,
+                    network: config.network.clone(),
                 });
             }
 
@@ -139,7 +218,17 @@ impl ProviderChain {
             return Err(AgentError::Config("No providers configured".into()));
         }
 
-        Ok(Self::new(providers))
+        if let Some(guardrails) = GuardrailMiddleware::from_config(&config.guardrails)? {
+            let guardrails: Arc<dyn ProviderMiddleware> = Arc::new(guardrails);
+            for p in &mut providers {
+                p.middleware.push_front(guardrails.clone());
+            }
+        }
+        let mut chain = Self::new(providers);
+        if let Some(cassette) = CassetteMiddleware::from_env()? {
+            chain.layer(None, Arc::new(cassette));
+        }
+        Ok(chain)
     }
 
     /// Select the best available provider for the given role.
@@ -276,6 +365,16 @@ impl ProviderChain {
         )))
     }
 
+    /// Add `middleware` to the named provider, or to every provider when
+    /// `provider` is `None`. It runs inside any configured middleware.
+    pub fn layer(&mut self, provider: Option<&str>, middleware: Arc<dyn ProviderMiddleware>) {
+        for p in &mut self.providers {
+            if provider.is_none_or(|name| name == p.name) {
+                p.middleware.push(middleware.clone());
+            }
+        }
+    }
+
     /// Get the list of configured providers.
     pub fn providers(&self) -> &[ResolvedProvider] {
         &self.providers
@@ -304,6 +403,12 @@ mod tests {
             max_tokens: 4096,
             temperature: 0.7,
             top_p: 0.9,
+            vision: false,
+            input_cost_per_mtok: 0.0,
+            output_cost_per_mtok: 0.0,
+            middleware: MiddlewareStack::default(),
+            mock: None,
+            network: NetworkConfig::default(),
         }
     }
 
@@ -455,4 +560,18 @@ mod tests {
         assert!(chain.select(Some("creative")).is_ok());
         assert!(chain.select(None).is_ok());
     }
+
+    #[test]
+    fn test_mock_provider_needs_a_scenario() {
+        let mut config = AppConfig::default();
+        config.provider.kind = ProviderKind::Mock;
+        assert!(ProviderChain::from_config(&config).is_err());
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("demo.yaml");
+        std::fs::write(&path, "fallback: hi\n").unwrap();
+        config.provider.scenario = Some(path);
+        let chain = ProviderChain::from_config(&config).unwrap();
+        assert!(chain.providers()[0].mock.is_some());
+    }
 }
@@ -0,0 +1,214 @@
+//! Full copies of tool outputs too large to send to the model at once.
+//!
+//! [`ToolRegistry::execute`](crate::tool_registry::ToolRegistry::execute)
+//! sends only the first page of a large output, stores the whole output
+//! here and tells the model the reference ID; the `result_page` tool then
+//! reads later pages or greps the cached output by that ID.
+
+use regex::RegexBuilder;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+/// Size of one page of tool output, in bytes.
+pub const RESULT_PAGE_BYTES: usize = 16 * 1024;
+
+/// Outputs up to this much over a page are sent whole, so a page read
+/// back through `result_page`, with its footer, is not truncated again.
+const PAGE_SLACK: usize = 1024;
+
+/// Cached outputs kept before the oldest is dropped.
+const MAX_ENTRIES: usize = 32;
+
+/// Matching lines returned by one grep.
+const MAX_GREP_MATCHES: usize = 200;
+
+/// Reference ID and full output, oldest first.
+type Entries = VecDeque<(String, Arc<str>)>;
+
+/// Shared cache of full tool outputs, keyed by reference ID.
+#[derive(Clone, Default)]
+pub struct ResultCache {
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl ResultCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store `content` and return its reference ID. The ID is derived from
+    /// the content, so storing the same output again returns the same ID.
+    pub fn store(&self, content: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let id = format!("res_{:016x}", hasher.finish());
+
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|(existing, _)| *existing != id);
+        if entries.len() >= MAX_ENTRIES {
+            entries.pop_front();
+        }
+        entries.push_back((id.clone(), Arc::from(content)));
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<str>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .iter()
+            .find(|(existing, _)| existing == id)
+            .map(|(_, content)| content.clone())
+    }
+
+    /// If `content` is longer than one page, cache it and return the first
+    /// page with a note on how to read the rest. Otherwise `None`.
+    pub fn truncate(&self, content: &str) -> Option<String> {
+        if content.len() <= RESULT_PAGE_BYTES + PAGE_SLACK {
+            return None;
+        }
+        let id = self.store(content);
+        let (first, pages) = page(content, 1);
+        Some(format!(
+            "{}\n\n[Output truncated: {} bytes, page 1 of {}. Full result cached as `{}`; \
+             call result_page with {{\"ref\": \"{}\", \"page\": 2}} for more, or \
+             {{\"ref\": \"{}\", \"grep\": \"pattern\"}} to search it]",
+            first,
+            content.len(),
+            pages,
+            id,
+            id,
+            id
+        ))
+    }
+}
+
+/// Page `number` (1-based) of `content` and the total number of pages.
+/// Pages end on line breaks where possible and never split a character.
+pub fn page(content: &str, number: usize) -> (&str, usize) {
+    let mut bounds = vec![0];
+    let mut start = 0;
+    while content.len() - start > RESULT_PAGE_BYTES {
+        let mut end = start + RESULT_PAGE_BYTES;
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        if let Some(nl) = content[start..end].rfind('\n') {
+            if nl > RESULT_PAGE_BYTES / 2 {
+                end = start + nl + 1;
+            }
+        }
+        bounds.push(end);
+        start = end;
+    }
+    bounds.push(content.len());
+    let pages = bounds.len() - 1;
+    match number.checked_sub(1).filter(|i| *i < pages) {
+        Some(i) => (&content[bounds[i]..bounds[i + 1]], pages),
+        None => ("", pages),
+    }
+}
+
+/// Lines of `content` matching `pattern` (a case-insensitive regex, or a
+/// plain substring if it is not a valid regex), prefixed with line numbers.
+pub fn grep(content: &str, pattern: &str) -> String {
+    let regex = RegexBuilder::new(pattern).case_insensitive(true).build();
+    let needle = pattern.to_lowercase();
+    let matches = |line: &str| match &regex {
+        Ok(re) => re.is_match(line),
+        Err(_) => line.to_lowercase().contains(&needle),
+    };
+
+    let mut out = String::new();
+    let mut count = 0;
+    for (n, line) in content.lines().enumerate() {
+        if !matches(line) {
+            continue;
+        }
+        count += 1;
+        let entry = format!("{}: {}\n", n + 1, line);
+        if count <= MAX_GREP_MATCHES && out.len() + entry.len() <= RESULT_PAGE_BYTES {
+            out.push_str(&entry);
+        }
+    }
+    match count {
+        0 => format!("No lines match `{}`.", pattern),
+        _ if out.lines().count() < count => format!(
+            "{}[{} of {} matching lines shown; narrow the pattern to see the rest]",
+            out,
+            out.lines().count(),
+            count
+        ),
+        _ => out.trim_end().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big_output() -> String {
+        (1..=3000).map(|i| format!("line {i}: some tool output\n")).collect()
+    }
+
+    #[test]
+    fn test_small_output_is_not_cached() {
+        let cache = ResultCache::new();
+        assert!(cache.truncate("short").is_none());
+        assert!(cache.truncate(&"x".repeat(RESULT_PAGE_BYTES + 10)).is_none());
+    }
+
+    #[test]
+    fn test_truncate_caches_full_output() {
+        let cache = ResultCache::new();
+        let content = big_output();
+        let truncated = cache.truncate(&content).unwrap();
+        assert!(truncated.starts_with("line 1: some tool output\n"));
+        assert!(truncated.len() < RESULT_PAGE_BYTES + 400);
+
+        let id = truncated.split('`').nth(1).unwrap();
+        assert_eq!(&*cache.get(id).unwrap(), content.as_str());
+        // Same output, same reference.
+        assert_eq!(cache.store(&content), id);
+        assert!(cache.get("res_missing").is_none());
+    }
+
+    #[test]
+    fn test_pages_cover_content_on_line_breaks() {
+        let content = big_output();
+        let (_, pages) = page(&content, 1);
+        assert!(pages > 1);
+        let joined: String = (1..=pages).map(|n| page(&content, n).0).collect();
+        assert_eq!(joined, content);
+        assert!(page(&content, 2).0.starts_with("line "));
+        assert_eq!(page(&content, pages + 1).0, "");
+        assert_eq!(page(&content, 0).0, "");
+
+        let multibyte = "é".repeat(RESULT_PAGE_BYTES);
+        let (first, pages) = page(&multibyte, 1);
+        assert_eq!(pages, 2);
+        assert!(first.len() <= RESULT_PAGE_BYTES);
+    }
+
+    #[test]
+    fn test_grep() {
+        let content = big_output();
+        assert_eq!(grep(&content, r"^LINE 42:"), "42: line 42: some tool output");
+        assert_eq!(grep(&content, "nothing here"), "No lines match `nothing here`.");
+        // Invalid regex falls back to substring search.
+        assert_eq!(grep("a (b\nc", "(b"), "1: a (b");
+        assert!(grep(&content, "line").contains("matching lines shown"));
+    }
+
+    #[test]
+    fn test_evicts_oldest() {
+        let cache = ResultCache::new();
+        let first = cache.store("0");
+        for i in 1..=MAX_ENTRIES {
+            cache.store(&i.to_string());
+        }
+        assert!(cache.get(&first).is_none());
+        assert!(cache.get(&cache.store("1")).is_some());
+    }
+}
@@ -2,13 +2,13 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::str::FromStr;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, NaiveTime, TimeZone, Utc};
 use cron::Schedule;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
 
-use crate::config::{ScheduleConfig, ScheduleTaskType};
+use crate::config::{AppConfig, ScheduleConfig, ScheduleTaskType};
 use crate::error::AgentError;
 
 /// A task fired by the scheduler, sent to the main event loop for execution.
@@ -18,23 +18,34 @@ pub enum ScheduledTask {
     Heartbeat {
         schedule_name: String,
         workspace: String,
+        session: String,
+        workspace_root: Option<PathBuf>,
         skill: String,
+        /// Kickoff prompt template; the executor's default when unset.
+        prompt: Option<String>,
     },
     /// Run a fixed prompt through the agent loop.
     Prompt {
         schedule_name: String,
         workspace: String,
+        session: String,
+        workspace_root: Option<PathBuf>,
         prompt: String,
     },
-    /// Custom task type for future extensibility.
+    /// Run a shell command through the sandbox and/or call a webhook.
     Custom {
         schedule_name: String,
         workspace: String,
+        session: String,
+        workspace_root: Option<PathBuf>,
+        command: Option<String>,
+        webhook: Option<String>,
     },
 }
 
 /// Persistent state for a single schedule.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ScheduleState {
     pub last_run: Option<DateTime<Utc>>,
     pub next_run: DateTime<Utc>,
@@ -42,14 +53,88 @@ pub struct ScheduleState {
     pub last_error: Option<String>,
 }
 
+/// A daily window of local time, e.g. `22:00-07:00`, in which work that
+/// can wait does. The window may wrap past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl FromStr for QuietHours {
+    type Err = AgentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            AgentError::Config(format!(
+                "Invalid quiet hours '{}': expected HH:MM-HH:MM, e.g. 22:00-07:00",
+                s
+            ))
+        };
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        let parse = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").map_err(|_| invalid());
+        let (start, end) = (parse(start)?, parse(end)?);
+        if start == end {
+            return Err(invalid());
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl QuietHours {
+    /// Parse a `quiet_hours` setting, logging and ignoring invalid ones.
+    /// `"off"` is no window.
+    pub fn from_setting(setting: Option<&str>) -> Option<Self> {
+        match setting? {
+            "off" => None,
+            s => s.parse().map_err(|e| warn!("{}", e)).ok(),
+        }
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// When the window `at` falls in ends, or `None` if `at` is outside it.
+    pub fn deferred_until<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let time = at.time();
+        if !self.contains(time) {
+            return None;
+        }
+        let mut date = at.date_naive();
+        if time >= self.end {
+            date = date.succ_opt()?;
+        }
+        // The end may not exist locally on a DST change; an hour will do.
+        at.timezone()
+            .from_local_datetime(&date.and_time(self.end))
+            .earliest()
+            .or_else(|| Some(at.clone() + chrono::Duration::hours(1)))
+    }
+
+    /// When the window active now ends, in UTC, using local time.
+    pub fn deferred_now(&self) -> Option<DateTime<Utc>> {
+        self.deferred_until(&Local::now())
+            .map(|end| end.with_timezone(&Utc))
+    }
+}
+
 /// The cron/heartbeat scheduler.
 ///
 /// Manages a set of scheduled tasks, sleeping until each is due, then
 /// sending fired tasks through an `mpsc` channel. State is persisted
-/// to disk so missed schedules fire on restart.
+/// to disk so missed schedules fire on restart. Runs due during quiet
+/// hours are put off until the window ends, once however often they
+/// came due.
 pub struct Scheduler {
     schedules: Vec<ScheduleConfig>,
     parsed: Vec<Option<Schedule>>,
+    /// Each schedule's quiet hours: its own, or the scheduler's.
+    quiet: Vec<Option<QuietHours>>,
     state: HashMap<String, ScheduleState>,
     state_path: PathBuf,
 }
@@ -88,14 +173,45 @@ impl Scheduler {
             }
         }
 
+        let quiet = schedules
+            .iter()
+            .map(|config| QuietHours::from_setting(config.quiet_hours.as_deref()))
+            .collect();
+
         Self {
             schedules,
             parsed,
+            quiet,
             state,
             state_path,
         }
     }
 
+    /// Apply `[scheduler] quiet_hours` to schedules without their own.
+    pub fn with_quiet_hours(mut self, quiet_hours: Option<&str>) -> Self {
+        let default = QuietHours::from_setting(quiet_hours);
+        for (config, quiet) in self.schedules.iter().zip(self.quiet.iter_mut()) {
+            if config.quiet_hours.is_none() {
+                *quiet = default;
+            }
+        }
+        self
+    }
+
+    /// Where the scheduler persists its state for `config`.
+    pub fn state_path_for(config: &AppConfig) -> PathBuf {
+        state_dir(config).join("scheduler_state.json")
+    }
+
+    /// Read persisted state without a running scheduler (for observability).
+    /// A missing or unreadable file yields an empty map.
+    pub fn read_state(path: &std::path::Path) -> HashMap<String, ScheduleState> {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
     /// Load persisted state from disk, merging with current state.
     ///
     /// Restores `last_run`, `run_count`, and `last_error` for schedules that
@@ -130,11 +246,16 @@ impl Scheduler {
         Ok(())
     }
 
-    /// Run the scheduler loop, sending fired tasks through `tx`.
+    /// Run the scheduler loop, sending fired tasks through `tx` and
+    /// recording the finished runs the executor sends back on `outcomes`.
     ///
     /// Blocks until the channel is closed (receiver dropped). Persists
-    /// state after each batch of fired tasks.
-    pub async fn run(mut self, tx: mpsc::UnboundedSender<ScheduledTask>) {
+    /// state after each batch of fired tasks and each outcome.
+    pub async fn run(
+        mut self,
+        tx: mpsc::UnboundedSender<ScheduledTask>,
+        mut outcomes: mpsc::UnboundedReceiver<ScheduleRun>,
+    ) {
         let enabled_count = self
             .schedules
             .iter()
@@ -170,7 +291,28 @@ impl Scheduler {
 
             let sleep_duration = self.time_until_next_fire();
             debug!("Scheduler sleeping for {:?}", sleep_duration);
-            tokio::time::sleep(sleep_duration).await;
+            tokio::select! {
+                _ = tokio::time::sleep(sleep_duration) => {}
+                Some(run) = outcomes.recv() => {
+                    self.record_outcome(&run);
+                    if let Err(e) = self.save_state() {
+                        warn!("Failed to save scheduler state: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Record how a fired run ended: a failure's error is kept in
+    /// `last_error` until a later run succeeds.
+    pub fn record_outcome(&mut self, run: &ScheduleRun) {
+        let Some(state) = self.state.get_mut(&run.schedule) else {
+            return;
+        };
+        match run.status {
+            RunStatus::Succeeded => state.last_error = None,
+            RunStatus::Failed => state.last_error = run.error.clone(),
+            RunStatus::Fired => {}
         }
     }
 
@@ -200,6 +342,13 @@ impl Scheduler {
             };
 
             if now >= state.next_run || (state.next_run - now) <= tolerance {
+                if let Some(until) = self.quiet[i].and_then(|q| q.deferred_now()) {
+                    if state.next_run < until {
+                        info!("Quiet hours: '{}' deferred until {}", config.name, until);
+                        state.next_run = until;
+                    }
+                    continue;
+                }
                 debug!("Firing schedule: {}", config.name);
 
                 let workspace = config
@@ -211,11 +360,16 @@ impl Scheduler {
                     ScheduleTaskType::Heartbeat => ScheduledTask::Heartbeat {
                         schedule_name: config.name.clone(),
                         workspace,
+                        session: config.session_name(),
+                        workspace_root: config.workspace_root.clone(),
                         skill: config.skill.clone().unwrap_or_default(),
+                        prompt: config.prompt.clone(),
                     },
                     ScheduleTaskType::Prompt => ScheduledTask::Prompt {
                         schedule_name: config.name.clone(),
                         workspace,
+                        session: config.session_name(),
+                        workspace_root: config.workspace_root.clone(),
                         prompt: config
                             .prompt
                             .clone()
@@ -224,6 +378,10 @@ impl Scheduler {
                     ScheduleTaskType::Custom => ScheduledTask::Custom {
                         schedule_name: config.name.clone(),
                         workspace,
+                        session: config.session_name(),
+                        workspace_root: config.workspace_root.clone(),
+                        command: config.command.clone(),
+                        webhook: config.webhook.clone(),
                     },
                 };
 
@@ -277,6 +435,96 @@ impl Scheduler {
     }
 }
 
+fn state_dir(config: &AppConfig) -> PathBuf {
+    config
+        .scheduler
+        .state_dir
+        .clone()
+        .unwrap_or_else(AppConfig::data_dir)
+}
+
+/// Outcome of a scheduled run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    /// The task was handed to the executor; no outcome recorded yet.
+    Fired,
+    Succeeded,
+    Failed,
+}
+
+/// One entry in the run history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ScheduleRun {
+    pub schedule: String,
+    pub fired_at: DateTime<Utc>,
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Utc>>,
+    pub status: RunStatus,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Start of the agent's reply, for prompt tasks.
+    #[serde(default)]
+    pub summary: Option<String>,
+}
+
+/// Append-only run history (`scheduler_history.jsonl`), capped at
+/// `[scheduler] history_limit` records across all schedules.
+pub struct ScheduleHistory {
+    path: PathBuf,
+    limit: usize,
+}
+
+impl ScheduleHistory {
+    pub fn new(path: PathBuf, limit: usize) -> Self {
+        Self { path, limit }
+    }
+
+    pub fn for_config(config: &AppConfig) -> Self {
+        Self::new(
+            state_dir(config).join("scheduler_history.jsonl"),
+            config.scheduler.history_limit,
+        )
+    }
+
+    /// Append a run, dropping the oldest records once the file holds a
+    /// quarter more than the limit (so compaction is occasional).
+    pub fn record(&self, run: &ScheduleRun) -> Result<(), AgentError> {
+        use std::io::Write;
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(run)?)?;
+        drop(file);
+
+        let contents = std::fs::read_to_string(&self.path)?;
+        let lines: Vec<&str> = contents.lines().collect();
+        if lines.len() > self.limit + self.limit / 4 {
+            let kept = lines[lines.len() - self.limit..].join("\n");
+            std::fs::write(&self.path, kept + "\n")?;
+        }
+        Ok(())
+    }
+
+    /// The latest `limit` runs of `schedule`, newest first.
+    pub fn recent(&self, schedule: &str, limit: usize) -> Vec<ScheduleRun> {
+        let contents = std::fs::read_to_string(&self.path).unwrap_or_default();
+        contents
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str::<ScheduleRun>(line).ok())
+            .filter(|run| run.schedule == schedule)
+            .take(limit)
+            .collect()
+    }
+}
+
 /// Parse a cron expression, normalizing 5-field standard cron to 7-field format.
 ///
 /// The `cron` crate expects 7 fields: `sec min hour dom month dow year`.
@@ -293,6 +541,27 @@ pub fn parse_cron_expr(expr: &str) -> Result<Schedule, AgentError> {
         .map_err(|e| AgentError::Config(format!("Invalid cron expression '{}': {}", expr, e)))
 }
 
+/// A validated cron expression: its 7-field form and upcoming fire times.
+#[derive(Debug, Clone, Serialize)]
+pub struct CronPreview {
+    pub normalized: String,
+    pub next_runs: Vec<DateTime<Utc>>,
+}
+
+/// Validate `expr` the way the scheduler will and list the next `count`
+/// fire times after `after`.
+pub fn preview_cron(
+    expr: &str,
+    after: DateTime<Utc>,
+    count: usize,
+) -> Result<CronPreview, AgentError> {
+    let schedule = parse_cron_expr(expr)?;
+    Ok(CronPreview {
+        normalized: normalize_cron_fields(expr.trim()),
+        next_runs: schedule.after(&after).take(count).collect(),
+    })
+}
+
 fn normalize_cron_fields(expr: &str) -> String {
     let fields: Vec<&str> = expr.split_whitespace().collect();
     match fields.len() {
@@ -318,6 +587,8 @@ mod tests {
             name: name.to_string(),
             cron: cron.to_string(),
             workspace: Some("default".to_string()),
+            session: None,
+            workspace_root: None,
             task,
             skill: if task == ScheduleTaskType::Heartbeat {
                 Some("test-skill".to_string())
@@ -330,6 +601,9 @@ mod tests {
                 None
             },
             enabled,
+            quiet_hours: None,
+            command: None,
+            webhook: None,
         }
     }
 
@@ -447,10 +721,15 @@ mod tests {
                 name: "bad".to_string(),
                 cron: "not valid cron".to_string(),
                 workspace: None,
+                session: None,
+                workspace_root: None,
                 task: ScheduleTaskType::Prompt,
                 skill: None,
                 prompt: None,
                 enabled: true,
+                quiet_hours: None,
+                command: None,
+                webhook: None,
             },
             make_schedule_config("good", "*/10 * * * *", ScheduleTaskType::Prompt, true),
         ];
@@ -550,13 +829,143 @@ mod tests {
             ScheduledTask::Heartbeat {
                 schedule_name,
                 workspace,
+                session,
+                workspace_root,
                 skill,
+                prompt,
             } => {
                 assert_eq!(schedule_name, "hb");
                 assert_eq!(workspace, "default");
+                assert_eq!(session, "schedule:hb");
+                assert_eq!(workspace_root, &None);
                 assert_eq!(skill, "test-skill");
+                assert_eq!(prompt, &None);
             }
             _ => panic!("Expected Heartbeat task"),
         }
     }
+
+    #[test]
+    fn test_record_outcome() {
+        let configs = vec![make_schedule_config("hb", "*/30 * * * *", ScheduleTaskType::Heartbeat, true)];
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut scheduler = Scheduler::new(configs, tmp.path().to_path_buf());
+        let mut run = ScheduleRun {
+            schedule: "hb".into(),
+            fired_at: Utc::now(),
+            finished_at: Some(Utc::now()),
+            status: RunStatus::Failed,
+            error: Some("Skill not found: test-skill".into()),
+            summary: None,
+        };
+        scheduler.record_outcome(&run);
+        assert_eq!(
+            scheduler.state()["hb"].last_error.as_deref(),
+            Some("Skill not found: test-skill")
+        );
+        run.status = RunStatus::Succeeded;
+        scheduler.record_outcome(&run);
+        assert_eq!(scheduler.state()["hb"].last_error, None);
+    }
+
+    #[test]
+    fn test_quiet_hours_window() {
+        let q: QuietHours = "22:00-07:00".parse().unwrap();
+        let t = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert!(q.contains(t(23, 30)) && q.contains(t(3, 0)));
+        assert!(!q.contains(t(7, 0)) && !q.contains(t(12, 0)));
+
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        assert_eq!(
+            q.deferred_until(&at("2026-03-01T23:15:00Z")),
+            Some(at("2026-03-02T07:00:00Z"))
+        );
+        assert_eq!(
+            q.deferred_until(&at("2026-03-02T01:00:00Z")),
+            Some(at("2026-03-02T07:00:00Z"))
+        );
+        assert_eq!(q.deferred_until(&at("2026-03-02T09:00:00Z")), None);
+
+        let day: QuietHours = "12:00-13:30".parse().unwrap();
+        assert!(day.contains(t(12, 45)) && !day.contains(t(13, 30)));
+        assert!("22:00".parse::<QuietHours>().is_err());
+        assert!("25:00-07:00".parse::<QuietHours>().is_err());
+        assert_eq!(QuietHours::from_setting(Some("off")), None);
+    }
+
+    #[test]
+    fn test_quiet_hours_defer_due_schedules() {
+        // A window around the current local time.
+        let now = Local::now().time();
+        let window = format!(
+            "{}-{}",
+            (now - chrono::Duration::hours(1)).format("%H:%M"),
+            (now + chrono::Duration::hours(1)).format("%H:%M")
+        );
+        let mut on_time = make_schedule_config("on-time", "0 * * * * *", ScheduleTaskType::Prompt, true);
+        on_time.quiet_hours = Some("off".into());
+        let configs = vec![
+            make_schedule_config("quiet", "0 * * * * *", ScheduleTaskType::Prompt, true),
+            on_time,
+        ];
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut scheduler = Scheduler::new(configs, tmp.path().to_path_buf())
+            .with_quiet_hours(Some(&window));
+        for state in scheduler.state.values_mut() {
+            state.next_run = Utc::now() - chrono::Duration::seconds(1);
+        }
+
+        let tasks = scheduler.tick();
+        assert_eq!(tasks.len(), 1);
+        assert!(matches!(&tasks[0], ScheduledTask::Prompt { schedule_name, .. } if schedule_name == "on-time"));
+        let deferred = scheduler.state()["quiet"].next_run;
+        assert!(deferred > Utc::now() + chrono::Duration::minutes(55));
+        assert_eq!(scheduler.state()["quiet"].run_count, 0);
+    }
+
+    #[test]
+    fn test_preview_cron() {
+        let after = "2026-10-15T10:07:00Z".parse::<DateTime<Utc>>().unwrap();
+        let preview = preview_cron("*/30 9-17 * * MON-FRI", after, 3).unwrap();
+        assert_eq!(preview.normalized, "0 */30 9-17 * * MON-FRI *");
+        let runs: Vec<String> = preview.next_runs.iter().map(|t| t.to_rfc3339()).collect();
+        assert_eq!(
+            runs,
+            vec![
+                "2026-10-15T10:30:00+00:00",
+                "2026-10-15T11:00:00+00:00",
+                "2026-10-15T11:30:00+00:00"
+            ]
+        );
+        assert!(preview_cron("61 * * * *", after, 5).is_err());
+    }
+
+    #[test]
+    fn test_schedule_history() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let history = ScheduleHistory::new(tmp.path().join("history.jsonl"), 4);
+        let run = |schedule: &str, minute: u32, status: RunStatus| ScheduleRun {
+            schedule: schedule.into(),
+            fired_at: format!("2026-10-15T10:{:02}:00Z", minute).parse().unwrap(),
+            finished_at: None,
+            status,
+            error: None,
+            summary: None,
+        };
+        history.record(&run("backup", 0, RunStatus::Succeeded)).unwrap();
+        history.record(&run("digest", 1, RunStatus::Fired)).unwrap();
+        history.record(&run("backup", 2, RunStatus::Failed)).unwrap();
+
+        let recent = history.recent("backup", 10);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].status, RunStatus::Failed);
+        assert_eq!(history.recent("backup", 1).len(), 1);
+
+        // Past limit + 25% the oldest records are dropped.
+        for minute in 3..6 {
+            history.record(&run("digest", minute, RunStatus::Fired)).unwrap();
+        }
+        assert_eq!(history.recent("backup", 10).len(), 1);
+        assert_eq!(history.recent("digest", 10).len(), 3);
+    }
 }
@@ -7,7 +7,7 @@ use regex::Regex;
 use std::borrow::Cow;
 
 /// Default patterns for secret detection.
-const DEFAULT_PATTERNS: &[&str] = &[
+pub(crate) const DEFAULT_PATTERNS: &[&str] = &[
     // API keys and tokens
     r#"(?i)(api[_-]?key|token|secret|password|passwd|pwd)\s*[=:]\s*['"]?[^\s'""]+"#,
     // Bearer tokens
@@ -26,6 +26,19 @@ const DEFAULT_PATTERNS: &[&str] = &[
     r"(?i)(?:key|token|secret|password)\s*[=:]\s*[A-Za-z0-9+/]{32,}={0,2}",
 ];
 
+/// Personal data redacted on top of secrets with [`SecretScrubber::with_pii`].
+pub(crate) const PII_PATTERNS: &[&str] = &[
+    // Email addresses
+    r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+    // IPv4 addresses
+    r"\b(?:\d{1,3}\.){3}\d{1,3}\b",
+    // Phone numbers with a country code, or like (555) 123-4567 / 555-123-4567
+    r"\+\d{1,3}[\s.-]?(?:\(\d{1,4}\)|\d{1,4})[\s.-]?\d{3,4}[\s.-]?\d{3,4}\b",
+    r"(?:\(\d{3}\)\s?|\b\d{3}[.-])\d{3}[.-]\d{4}\b",
+    // User names in home directory paths
+    r"(?:/home/|/Users/|C:\\Users\\)[^/\\\s]+",
+];
+
 /// Scrubber for removing secrets from text.
 pub struct SecretScrubber {
     patterns: Vec<Regex>,
@@ -48,6 +61,14 @@ impl SecretScrubber {
         }
     }
 
+    /// Also redact email addresses, IP addresses, phone numbers and user
+    /// names in home directory paths.
+    pub fn with_pii(mut self) -> Self {
+        self.patterns
+            .extend(PII_PATTERNS.iter().filter_map(|p| Regex::new(p).ok()));
+        self
+    }
+
     /// Set the replacement string.
     pub fn with_replacement(mut self, replacement: impl Into<String>) -> Self {
         self.replacement = replacement.into();
@@ -190,6 +211,17 @@ mod tests {
         assert!(result.contains("[REDACTED]"));
     }
 
+    #[test]
+    fn test_pii() {
+        let input = "mail jane.doe@example.com from 10.0.0.12, call +1 555-123-4567, see /home/jane/notes";
+        assert_eq!(SecretScrubber::new().scrub(input), input);
+        let result = SecretScrubber::new().with_pii().scrub(input).into_owned();
+        for personal in ["jane", "10.0.0.12", "555-123-4567"] {
+            assert!(!result.contains(personal), "{}", result);
+        }
+        assert!(result.ends_with("[REDACTED]/notes"));
+    }
+
     #[test]
     fn test_add_custom_pattern() {
         let mut scrubber = SecretScrubber::new();
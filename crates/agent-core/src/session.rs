@@ -1,10 +1,14 @@
-use crate::config::AppConfig;
+use crate::checkpoint::{Checkpoint, FileChange, FileSnapshot, Rollback, SnapshotStore};
+use crate::config::{AppConfig, PruneAction, SessionConfig};
 use crate::error::AgentError;
-use crate::types::Message;
+use crate::types::{Feedback, Message, Role, TokenUsage};
 use chrono::{DateTime, Utc};
+use fs4::FileExt;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
 use uuid::Uuid;
 
 /// IO trait re-export for async save.
@@ -45,6 +49,81 @@ pub struct Session {
     /// Profile that was active when this session was created.
     #[serde(default)]
     pub profile: Option<String>,
+    /// Agent preset every turn in this session runs with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// Points the conversation can be rolled back to, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checkpoints: Vec<Checkpoint>,
+    /// Token for the read-only public link to this session, if shared.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub share_token: Option<String>,
+    /// Name of the API key that created the session (`server.api_keys`);
+    /// `None` for sessions created without a named key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Tokens used by every model call in the session, and their cost.
+    #[serde(default)]
+    pub usage: TokenUsage,
+    /// Calls each tool ran, by registered name, for `[tools.quotas]`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tool_calls: HashMap<String, u32>,
+    /// Names of the workspaces the session works in, primary first; empty
+    /// for `[sandbox] workspace_root` (see [`crate::workspace`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workspaces: Vec<String>,
+}
+
+/// Checkpoints kept per session; the oldest are dropped first.
+const MAX_CHECKPOINTS: usize = 50;
+
+/// Advisory lock file in a sessions directory.
+const LOCK_FILE: &str = ".lock";
+
+/// Exclusive advisory lock on a sessions directory, so processes sharing it
+/// (e.g. `serve` and the REPL) never interleave writes. Released on drop.
+struct DirLock {
+    _file: std::fs::File,
+}
+
+impl DirLock {
+    fn acquire(dir: &Path) -> Result<Self, AgentError> {
+        std::fs::create_dir_all(dir)?;
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join(LOCK_FILE))?;
+        FileExt::lock_exclusive(&file)?;
+        Ok(Self { _file: file })
+    }
+
+    /// Wait for the lock on a blocking thread.
+    async fn acquire_async(dir: &Path) -> Result<Self, AgentError> {
+        let dir = dir.to_path_buf();
+        tokio::task::spawn_blocking(move || Self::acquire(&dir))
+            .await
+            .map_err(|e| AgentError::Session(format!("Session lock task failed: {}", e)))?
+    }
+}
+
+/// Modification time and size of a session file, used to notice writes
+/// made by other processes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    /// Stamp of the file at `path`, or `None` if it does not exist.
+    fn of(path: &Path) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        Some(Self {
+            modified: meta.modified().ok(),
+            len: meta.len(),
+        })
+    }
 }
 
 impl Session {
@@ -65,6 +144,13 @@ impl Session {
             notes: None,
             hostname: None,
             profile: None,
+            agent: None,
+            checkpoints: Vec::new(),
+            share_token: None,
+            owner: None,
+            usage: TokenUsage::default(),
+            tool_calls: HashMap::new(),
+            workspaces: Vec::new(),
         }
     }
 
@@ -87,7 +173,298 @@ impl Session {
         self.messages.push(message);
     }
 
+    /// Delete a message by ID.
+    ///
+    /// Deleting an assistant message that requested tools also removes the
+    /// matching tool results, so the history stays valid for the provider.
+    /// Returns `false` if no message has that ID.
+    pub fn delete_message(&mut self, id: &str) -> bool {
+        let Some(pos) = self.messages.iter().position(|m| m.id == id) else {
+            return false;
+        };
+        let removed = self.messages.remove(pos);
+        if let Some(calls) = &removed.tool_calls {
+            self.messages.retain(|m| {
+                m.tool_call_id
+                    .as_ref()
+                    .is_none_or(|tc_id| !calls.iter().any(|c| &c.id == tc_id))
+            });
+        }
+        self.updated_at = Utc::now();
+        true
+    }
+
+    /// Rate an assistant message, or clear its rating with `None`.
+    ///
+    /// Returns `false` if no assistant message has that ID.
+    pub fn set_feedback(&mut self, id: &str, feedback: Option<Feedback>) -> bool {
+        let Some(message) = self
+            .messages
+            .iter_mut()
+            .find(|m| m.id == id && m.role == Role::Assistant)
+        else {
+            return false;
+        };
+        message.feedback = feedback;
+        self.updated_at = Utc::now();
+        true
+    }
+
+    /// Drop a message and everything after it, returning the dropped message.
+    ///
+    /// Used to edit or regenerate from an earlier point in the conversation.
+    pub fn truncate_from(&mut self, id: &str) -> Option<Message> {
+        let pos = self.messages.iter().position(|m| m.id == id)?;
+        let mut dropped = self.messages.split_off(pos);
+        self.checkpoints.retain(|c| c.message_count <= pos);
+        self.updated_at = Utc::now();
+        Some(dropped.swap_remove(0))
+    }
+
+    /// Record the current point in the conversation.
+    pub fn checkpoint(&mut self, label: Option<String>) -> &Checkpoint {
+        if self.checkpoints.len() >= MAX_CHECKPOINTS {
+            self.checkpoints.remove(0);
+        }
+        self.checkpoints
+            .push(Checkpoint::new(label, self.messages.len()));
+        self.updated_at = Utc::now();
+        self.checkpoints.last().expect("just pushed")
+    }
+
+    /// Checkpoint taken at each turn boundary; skipped if nothing changed
+    /// since the latest checkpoint.
+    pub fn auto_checkpoint(&mut self) {
+        let unchanged = self
+            .checkpoints
+            .last()
+            .is_some_and(|c| c.message_count == self.messages.len() && c.files.is_empty());
+        if !unchanged {
+            self.checkpoint(None);
+        }
+    }
+
+    /// Attach snapshots of files a turn modified to the latest checkpoint,
+    /// storing their contents in `store`.
+    pub fn record_snapshots(
+        &mut self,
+        snapshots: Vec<FileSnapshot>,
+        store: &SnapshotStore,
+    ) -> Result<(), AgentError> {
+        if snapshots.is_empty() {
+            return Ok(());
+        }
+        if self.checkpoints.is_empty() {
+            self.checkpoint(None);
+        }
+        let latest = self.checkpoints.last_mut().expect("checkpoint ensured above");
+        for snapshot in snapshots {
+            latest.add_snapshot(snapshot, store)?;
+        }
+        Ok(())
+    }
+
+    /// Roll back to the checkpoint with `id`: drop the messages after it,
+    /// and with `restore_files` put back files modified since. Later
+    /// checkpoints are discarded.
+    pub fn rollback(
+        &mut self,
+        id: &str,
+        restore_files: bool,
+        store: &SnapshotStore,
+    ) -> Result<Rollback, AgentError> {
+        let pos = self
+            .checkpoints
+            .iter()
+            .position(|c| c.id == id)
+            .ok_or_else(|| AgentError::Session(format!("Checkpoint not found: {}", id)))?;
+        Ok(self.rollback_to(pos, restore_files, store))
+    }
+
+    /// Roll back to the latest checkpoint with anything to undo, i.e. the
+    /// start of the last turn.
+    pub fn undo(&mut self, restore_files: bool, store: &SnapshotStore) -> Result<Rollback, AgentError> {
+        let pos = self
+            .checkpoints
+            .iter()
+            .rposition(|c| c.message_count < self.messages.len() || !c.files.is_empty())
+            .ok_or_else(|| AgentError::Session("Nothing to undo".into()))?;
+        Ok(self.rollback_to(pos, restore_files, store))
+    }
+
+    fn rollback_to(&mut self, pos: usize, restore_files: bool, store: &SnapshotStore) -> Rollback {
+        let message_count = self.checkpoints[pos].message_count;
+        let messages_removed = self.messages.len().saturating_sub(message_count);
+        self.messages.truncate(message_count);
+
+        let mut files_restored: Vec<PathBuf> = Vec::new();
+        let mut failed = Vec::new();
+        // Newest first, so each file ends up as it was at the checkpoint.
+        let discarded: Vec<FileChange> = self.checkpoints[pos..]
+            .iter()
+            .rev()
+            .flat_map(|c| c.files.iter().cloned())
+            .collect();
+        for change in &discarded {
+            if restore_files {
+                match change.restore(store) {
+                    Ok(()) if !files_restored.contains(&change.path) => {
+                        files_restored.push(change.path.clone())
+                    }
+                    Ok(()) => {}
+                    Err(e) => failed.push(format!("{}: {}", change.path.display(), e)),
+                }
+            }
+            if let Some(blob) = &change.blob {
+                store.remove(blob);
+            }
+        }
+
+        self.checkpoints.truncate(pos + 1);
+        let checkpoint = &mut self.checkpoints[pos];
+        checkpoint.files.clear();
+        self.updated_at = Utc::now();
+        Rollback {
+            checkpoint: checkpoint.id.clone(),
+            messages_removed,
+            files_restored,
+            failed,
+        }
+    }
+
+    /// Every file tools modified since the oldest checkpoint, with its
+    /// contents before the first modification.
+    pub fn file_changes(&self) -> Vec<&FileChange> {
+        let mut changes: Vec<&FileChange> = Vec::new();
+        for change in self.checkpoints.iter().flat_map(|c| c.files.iter()) {
+            if !changes.iter().any(|c| c.path == change.path) {
+                changes.push(change);
+            }
+        }
+        changes
+    }
+
+    /// Put `path` back the way it was before the session first modified
+    /// it, and forget its snapshots. Messages are left alone.
+    pub fn revert_file(&mut self, path: &Path, store: &SnapshotStore) -> Result<(), AgentError> {
+        let change = self
+            .file_changes()
+            .into_iter()
+            .find(|c| c.path == path)
+            .cloned()
+            .ok_or_else(|| {
+                AgentError::Session(format!("No recorded changes to {}", path.display()))
+            })?;
+        change.restore(store)?;
+        self.forget_file(path, store);
+        Ok(())
+    }
+
+    /// Keep `path` as it is now and forget its snapshots, so it no longer
+    /// shows as changed and later rollbacks leave it alone.
+    pub fn accept_file(&mut self, path: &Path, store: &SnapshotStore) -> Result<(), AgentError> {
+        if !self.file_changes().iter().any(|c| c.path == path) {
+            return Err(AgentError::Session(format!(
+                "No recorded changes to {}",
+                path.display()
+            )));
+        }
+        self.forget_file(path, store);
+        Ok(())
+    }
+
+    fn forget_file(&mut self, path: &Path, store: &SnapshotStore) {
+        for checkpoint in &mut self.checkpoints {
+            checkpoint.files.retain(|c| {
+                if c.path != path {
+                    return true;
+                }
+                if let Some(blob) = &c.blob {
+                    store.remove(blob);
+                }
+                false
+            });
+        }
+        self.updated_at = Utc::now();
+    }
+
     /// Check whether a named tool is allowed in this session.
+    /// Copy this session under a new ID and name, keeping its messages,
+    /// tool policy and metadata.
+    pub fn fork(&self, name: impl Into<String>) -> Session {
+        let now = Utc::now();
+        Session {
+            id: Uuid::new_v4().to_string(),
+            name: name.into(),
+            created_at: now,
+            updated_at: now,
+            // File snapshots belong to the original's workspace history.
+            checkpoints: Vec::new(),
+            share_token: None,
+            usage: TokenUsage::default(),
+            tool_calls: HashMap::new(),
+            ..self.clone()
+        }
+    }
+
+    /// The session's share token, creating one if it has none. Tokens are
+    /// 256 random bits, so links cannot be guessed.
+    pub fn share(&mut self) -> &str {
+        if self.share_token.is_none() {
+            self.share_token = Some(format!(
+                "{}{}",
+                Uuid::new_v4().simple(),
+                Uuid::new_v4().simple()
+            ));
+            self.updated_at = Utc::now();
+        }
+        self.share_token.as_deref().unwrap_or_default()
+    }
+
+    /// Revoke the share link. Returns whether the session was shared.
+    pub fn unshare(&mut self) -> bool {
+        let shared = self.share_token.take().is_some();
+        if shared {
+            self.updated_at = Utc::now();
+        }
+        shared
+    }
+
+    /// Render the conversation as Markdown, with tool calls and results
+    /// as fenced blocks.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "# {}\n\n_Session {} · created {}_\n",
+            self.name,
+            self.id,
+            self.created_at.format("%Y-%m-%d %H:%M UTC")
+        );
+        for msg in &self.messages {
+            let heading = match msg.role {
+                Role::System => "System",
+                Role::User => "User",
+                Role::Assistant => "Assistant",
+                Role::Tool => "Tool result",
+            };
+            out.push_str(&format!("\n## {}\n\n", heading));
+            if msg.role == Role::Tool {
+                out.push_str(&format!("```\n{}\n```\n", msg.content.trim_end()));
+                continue;
+            }
+            if !msg.content.is_empty() {
+                out.push_str(msg.content.trim_end());
+                out.push('\n');
+            }
+            for call in msg.tool_calls.iter().flatten() {
+                out.push_str(&format!(
+                    "\n**Tool call** `{}`\n\n```json\n{}\n```\n",
+                    call.name, call.arguments
+                ));
+            }
+        }
+        out
+    }
+
     pub fn is_tool_allowed(&self, tool_name: &str) -> bool {
         if self.tool_denylist.contains(&tool_name.to_string()) {
             return false;
@@ -106,29 +483,46 @@ impl Session {
 
     /// Persist this session to disk as JSON.
     ///
-    /// Uses atomic write (temp file + rename) to prevent corruption on crash.
+    /// Uses atomic write (temp file + rename) to prevent corruption on crash,
+    /// under the directory's advisory lock.
     pub fn save_to(&self, dir: &Path) -> Result<(), AgentError> {
-        std::fs::create_dir_all(dir)?;
-        let path = dir.join(format!("{}.json", self.id));
-        let tmp_path = dir.join(format!(".{}.json.tmp", self.id));
-        let json = serde_json::to_string_pretty(self)?;
-        std::fs::write(&tmp_path, &json)?;
-        std::fs::rename(&tmp_path, &path)?;
+        let _lock = DirLock::acquire(dir)?;
+        self.write_to(dir)?;
         Ok(())
     }
 
     /// Persist this session to disk as JSON (async / non-blocking).
     ///
     /// Preferred inside async contexts; avoids blocking Tokio worker threads.
-    /// Uses atomic write (temp file + rename) to prevent corruption on crash.
+    /// Uses atomic write (temp file + rename) to prevent corruption on crash,
+    /// under the directory's advisory lock.
     pub async fn save_to_async(&self, dir: &Path) -> Result<(), AgentError> {
-        async_fs::create_dir_all(dir).await?;
+        let _lock = DirLock::acquire_async(dir).await?;
+        self.write_to_async(dir).await?;
+        Ok(())
+    }
+
+    /// Write the session file; the caller holds the directory lock.
+    fn write_to(&self, dir: &Path) -> Result<Option<FileStamp>, AgentError> {
+        let path = dir.join(format!("{}.json", self.id));
+        let tmp_path = dir.join(format!(".{}.json.tmp", self.id));
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(&tmp_path, &json)?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(FileStamp::of(&path))
+    }
+
+    /// Async [`Session::write_to`]; the caller holds the directory lock.
+    async fn write_to_async(&self, dir: &Path) -> Result<Option<FileStamp>, AgentError> {
         let path = dir.join(format!("{}.json", self.id));
         let tmp_path = dir.join(format!(".{}.json.tmp", self.id));
         let json = serde_json::to_string_pretty(self)?;
         async_fs::write(&tmp_path, &json).await?;
         async_fs::rename(&tmp_path, &path).await?;
-        Ok(())
+        Ok(async_fs::metadata(&path).await.ok().map(|meta| FileStamp {
+            modified: meta.modified().ok(),
+            len: meta.len(),
+        }))
     }
 
     /// Load a session from a JSON file.
@@ -146,13 +540,48 @@ impl Session {
     }
 }
 
+/// Subdirectory of the sessions directory that pruned sessions are
+/// archived to.
+const ARCHIVE_DIR: &str = "archive";
+
+/// A session removed by [`SessionManager::prune`], or that a dry run would
+/// remove.
+#[derive(Debug, Clone, Serialize)]
+pub struct PrunedSession {
+    pub id: String,
+    pub name: String,
+    pub updated_at: DateTime<Utc>,
+    pub message_count: usize,
+    pub reason: PruneReason,
+}
+
+/// Which retention limit a session exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PruneReason {
+    /// Not updated for `retention_days`.
+    Expired,
+    /// Beyond the `max_sessions` most recently updated.
+    OverLimit,
+}
+
 /// Manages multiple sessions with persistence.
+///
+/// Several processes may share the sessions directory: writes take an
+/// advisory lock, and sessions another process changed on disk are reloaded
+/// before this one modifies them (and by [`SessionManager::reload_changed`])
+/// unless they have messages this one has not saved yet.
 pub struct SessionManager {
     sessions: HashMap<String, Session>,
     active_session_id: Option<String>,
     sessions_dir: PathBuf,
     max_history: usize,
     auto_save: bool,
+    /// Stamp of each session file as this manager last read or wrote it.
+    stamps: Mutex<HashMap<String, FileStamp>>,
+    /// Sessions with messages added since they were last written (without
+    /// `auto_save`), which a reload would drop.
+    unsaved: Mutex<HashSet<String>>,
 }
 
 impl SessionManager {
@@ -171,6 +600,8 @@ impl SessionManager {
             sessions_dir,
             max_history: config.session.max_history,
             auto_save: config.session.auto_save,
+            stamps: Mutex::new(HashMap::new()),
+            unsaved: Mutex::new(HashSet::new()),
         };
         manager.load_all()?;
 
@@ -196,13 +627,18 @@ impl SessionManager {
 
     /// Load all sessions from the sessions directory.
     fn load_all(&mut self) -> Result<(), AgentError> {
+        let _lock = DirLock::acquire(&self.sessions_dir)?;
         let entries = std::fs::read_dir(&self.sessions_dir)?;
         for entry in entries {
             let entry = entry?;
             let path = entry.path();
             if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                let stamp = FileStamp::of(&path);
                 match Session::load_from(&path) {
                     Ok(session) => {
+                        if let Some(stamp) = stamp {
+                            self.stamps().insert(session.id.clone(), stamp);
+                        }
                         self.sessions.insert(session.id.clone(), session);
                     }
                     Err(e) => {
@@ -214,6 +650,132 @@ impl SessionManager {
         Ok(())
     }
 
+    fn stamps(&self) -> std::sync::MutexGuard<'_, HashMap<String, FileStamp>> {
+        self.stamps.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn unsaved(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.unsaved.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn session_path(&self, id: &str) -> PathBuf {
+        self.sessions_dir.join(format!("{}.json", id))
+    }
+
+    /// Pick up sessions that other processes sharing the directory created,
+    /// changed or deleted since this manager last read or wrote them.
+    /// Returns the IDs that changed.
+    pub fn reload_changed(&mut self) -> Result<Vec<String>, AgentError> {
+        let _lock = DirLock::acquire(&self.sessions_dir)?;
+        let mut changed = Vec::new();
+        let mut on_disk = Vec::new();
+        for entry in std::fs::read_dir(&self.sessions_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|s| s.to_str()).map(String::from) else {
+                continue;
+            };
+            if self.reload_if_changed(&id)? {
+                changed.push(id.clone());
+            }
+            on_disk.push(id);
+        }
+
+        // Sessions this manager had on disk that are now gone were deleted
+        // elsewhere. Sessions never saved are kept.
+        let deleted: Vec<String> = self
+            .stamps()
+            .keys()
+            .filter(|id| !on_disk.contains(id))
+            .cloned()
+            .collect();
+        for id in deleted {
+            self.stamps().remove(&id);
+            self.sessions.remove(&id);
+            changed.push(id);
+        }
+        if self
+            .active_session_id
+            .as_ref()
+            .is_some_and(|id| !self.sessions.contains_key(id))
+        {
+            self.active_session_id = self
+                .sessions
+                .values()
+                .max_by_key(|s| s.updated_at)
+                .map(|s| s.id.clone());
+        }
+        Ok(changed)
+    }
+
+    /// Reload session `id` if its file differs from what this manager last
+    /// read or wrote and this manager has no unsaved messages in it; those
+    /// are kept, and saving them overwrites the other copy. The caller
+    /// holds the directory lock.
+    fn reload_if_changed(&mut self, id: &str) -> Result<bool, AgentError> {
+        let path = self.session_path(id);
+        let Some(stamp) = FileStamp::of(&path) else {
+            return Ok(false);
+        };
+        if self.stamps().get(id) == Some(&stamp) {
+            return Ok(false);
+        }
+        if self.unsaved().contains(id) {
+            tracing::debug!("Not reloading session {}: it has unsaved messages", id);
+            return Ok(false);
+        }
+        match Session::load_from(&path) {
+            Ok(session) => {
+                self.stamps().insert(id.to_string(), stamp);
+                self.sessions.insert(id.to_string(), session);
+                Ok(true)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to reload session from {:?}: {}", path, e);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Write a session and remember its stamp. The caller holds the
+    /// directory lock.
+    fn write_session(&self, session: &Session) -> Result<(), AgentError> {
+        self.warn_if_overwriting(&session.id);
+        if let Some(stamp) = session.write_to(&self.sessions_dir)? {
+            self.stamps().insert(session.id.clone(), stamp);
+        }
+        self.unsaved().remove(&session.id);
+        Ok(())
+    }
+
+    /// Async [`SessionManager::write_session`].
+    async fn write_session_async(&self, session: &Session) -> Result<(), AgentError> {
+        self.warn_if_overwriting(&session.id);
+        if let Some(stamp) = session.write_to_async(&self.sessions_dir).await? {
+            self.stamps().insert(session.id.clone(), stamp);
+        }
+        self.unsaved().remove(&session.id);
+        Ok(())
+    }
+
+    /// Whether another process wrote session `id` since this manager last
+    /// read or wrote it.
+    fn changed_on_disk(&self, id: &str) -> bool {
+        let on_disk = FileStamp::of(&self.session_path(id));
+        on_disk.is_some() && on_disk.as_ref() != self.stamps().get(id)
+    }
+
+    fn warn_if_overwriting(&self, id: &str) {
+        if self.changed_on_disk(id) {
+            tracing::warn!(
+                "Session {} was changed by another process; overwriting with this one's copy",
+                id
+            );
+        }
+    }
+
     /// Get the active session.
     pub fn active_session(&self) -> Option<&Session> {
         self.active_session_id
@@ -235,7 +797,17 @@ impl SessionManager {
 
     /// Create a new session and make it active.
     pub fn create_session(&mut self, name: impl Into<String>) -> Result<&Session, AgentError> {
-        let session = Session::new(name);
+        self.create_session_with_agent(name, None)
+    }
+
+    /// Create and switch to a new session whose turns use an agent preset.
+    pub fn create_session_with_agent(
+        &mut self,
+        name: impl Into<String>,
+        agent: Option<String>,
+    ) -> Result<&Session, AgentError> {
+        let mut session = Session::new(name);
+        session.agent = agent;
         let id = session.id.clone();
         self.sessions.insert(id.clone(), session);
         self.active_session_id = Some(id.clone());
@@ -245,6 +817,90 @@ impl SessionManager {
         Ok(self.sessions.get(&id).unwrap())
     }
 
+    /// Add sessions converted by [`crate::import`] without switching to
+    /// them, and save them. Conversations imported before (including ones
+    /// since archived) are skipped. Returns the IDs of the added sessions.
+    pub fn import_sessions(&mut self, sessions: Vec<Session>) -> Result<Vec<String>, AgentError> {
+        let key = crate::import::IMPORTED_FROM;
+        let mut seen: std::collections::HashSet<String> = self
+            .sessions
+            .values()
+            .filter_map(|s| s.metadata.get(key).cloned())
+            .collect();
+        seen.extend(
+            self.archived_sessions()
+                .into_iter()
+                .filter_map(|mut s| s.metadata.remove(key)),
+        );
+        let _lock = DirLock::acquire(&self.sessions_dir)?;
+        let mut added = Vec::new();
+        for session in sessions {
+            if let Some(origin) = session.metadata.get(key) {
+                if !seen.insert(origin.clone()) {
+                    continue;
+                }
+            }
+            self.write_session(&session)?;
+            added.push(session.id.clone());
+            self.sessions.insert(session.id.clone(), session);
+        }
+        Ok(added)
+    }
+
+    /// Get a session by ID.
+    pub fn session(&self, id: &str) -> Option<&Session> {
+        self.sessions.get(id)
+    }
+
+    /// The session shared under `token`.
+    pub fn shared_session(&self, token: &str) -> Option<&Session> {
+        self.sessions
+            .values()
+            .find(|s| s.share_token.as_deref() == Some(token))
+    }
+
+    /// Resolve a unique session ID from a prefix (as typed in `/switch`).
+    pub fn resolve_prefix(&self, prefix: &str) -> Result<String, AgentError> {
+        let mut matches = self.sessions.keys().filter(|id| id.starts_with(prefix));
+        match (matches.next(), matches.next()) {
+            (Some(id), None) => Ok(id.clone()),
+            (None, _) => Err(AgentError::Session(format!(
+                "No session matching '{}'",
+                prefix
+            ))),
+            (Some(_), Some(_)) => Err(AgentError::Session(format!(
+                "Ambiguous prefix '{}', {} matches",
+                prefix,
+                2 + matches.count()
+            ))),
+        }
+    }
+
+    /// Get a session by ID mutably.
+    pub fn session_mut(&mut self, id: &str) -> Option<&mut Session> {
+        self.sessions.get_mut(id)
+    }
+
+    /// Fork an existing session and make the copy active.
+    pub fn fork_session(
+        &mut self,
+        id: &str,
+        name: impl Into<String>,
+    ) -> Result<&Session, AgentError> {
+        let forked = self
+            .sessions
+            .get(id)
+            .ok_or_else(|| AgentError::Session(format!("Session not found: {}", id)))?
+            .fork(name);
+        let new_id = forked.id.clone();
+        self.sessions.insert(new_id.clone(), forked);
+        self.active_session_id = Some(new_id.clone());
+        if self.auto_save {
+            self.save_session(&new_id)?;
+        }
+        Ok(self.sessions.get(&new_id).unwrap())
+    }
+
     /// Switch to an existing session by ID.
     pub fn switch_session(&mut self, id: &str) -> Result<(), AgentError> {
         if self.sessions.contains_key(id) {
@@ -255,13 +911,36 @@ impl SessionManager {
         }
     }
 
+    /// Switch to the most recently updated session called `name`, creating
+    /// it if none exists. Returns the session ID.
+    pub fn resume_or_create(&mut self, name: &str) -> Result<String, AgentError> {
+        let existing = self
+            .sessions
+            .values()
+            .filter(|s| s.name == name)
+            .max_by_key(|s| s.updated_at)
+            .map(|s| s.id.clone());
+        match existing {
+            Some(id) => {
+                self.active_session_id = Some(id.clone());
+                Ok(id)
+            }
+            None => Ok(self.create_session(name)?.id.clone()),
+        }
+    }
+
     /// Delete a session by ID.
     pub fn delete_session(&mut self, id: &str) -> Result<(), AgentError> {
         self.sessions.remove(id);
-        let path = self.sessions_dir.join(format!("{}.json", id));
-        if path.exists() {
-            std::fs::remove_file(path)?;
+        let path = self.session_path(id);
+        {
+            let _lock = DirLock::acquire(&self.sessions_dir)?;
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+            self.stamps().remove(id);
         }
+        self.snapshot_store(id).clear()?;
         // If we deleted the active session, switch to another or create a new default.
         if self.active_session_id.as_deref() == Some(id) {
             self.active_session_id = self.sessions.keys().next().cloned();
@@ -272,6 +951,110 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Sessions over the retention limits in `policy`, least recently
+    /// updated first. The active session and shared sessions are kept; they
+    /// still count toward `max_sessions`.
+    pub fn prune_candidates(&self, policy: &SessionConfig, now: DateTime<Utc>) -> Vec<PrunedSession> {
+        let cutoff = policy
+            .retention_days
+            .map(|days| now - chrono::Duration::days(i64::from(days)));
+        let mut sessions: Vec<&Session> = self.sessions.values().collect();
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        let mut candidates: Vec<PrunedSession> = sessions
+            .into_iter()
+            .enumerate()
+            .filter(|(_, s)| {
+                self.active_session_id.as_deref() != Some(s.id.as_str()) && s.share_token.is_none()
+            })
+            .filter_map(|(rank, s)| {
+                let reason = if policy.max_sessions.is_some_and(|max| rank >= max) {
+                    PruneReason::OverLimit
+                } else if cutoff.is_some_and(|cutoff| s.updated_at < cutoff) {
+                    PruneReason::Expired
+                } else {
+                    return None;
+                };
+                Some(PrunedSession {
+                    id: s.id.clone(),
+                    name: s.name.clone(),
+                    updated_at: s.updated_at,
+                    message_count: s.messages.len(),
+                    reason,
+                })
+            })
+            .collect();
+        candidates.reverse();
+        candidates
+    }
+
+    /// Archive or delete (per `policy.prune_action`) the sessions over the
+    /// retention limits, after picking up changes other processes made.
+    /// With `dry_run`, only reports what would be pruned.
+    pub fn prune(
+        &mut self,
+        policy: &SessionConfig,
+        now: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<Vec<PrunedSession>, AgentError> {
+        self.reload_changed()?;
+        let pruned = self.prune_candidates(policy, now);
+        if dry_run {
+            return Ok(pruned);
+        }
+        for session in &pruned {
+            match policy.prune_action {
+                PruneAction::Delete => self.delete_session(&session.id)?,
+                PruneAction::Archive => self.archive_session(&session.id)?,
+            }
+        }
+        Ok(pruned)
+    }
+
+    /// Move a session to the archive: it is no longer loaded or listed, but
+    /// [`SessionManager::archived_sessions`] still returns it. Its file
+    /// snapshots are removed, so it can no longer be rolled back.
+    pub fn archive_session(&mut self, id: &str) -> Result<(), AgentError> {
+        let archive = self.sessions_dir.join(ARCHIVE_DIR);
+        {
+            let _lock = DirLock::acquire(&self.sessions_dir)?;
+            std::fs::create_dir_all(&archive)?;
+            let path = self.session_path(id);
+            if path.exists() {
+                std::fs::rename(&path, archive.join(format!("{}.json", id)))?;
+            } else if let Some(session) = self.sessions.get(id) {
+                // Never saved; archive the in-memory copy.
+                session.write_to(&archive)?;
+            }
+            self.stamps().remove(id);
+        }
+        self.sessions.remove(id);
+        self.snapshot_store(id).clear()?;
+        if self.active_session_id.as_deref() == Some(id) {
+            self.active_session_id = self
+                .sessions
+                .values()
+                .max_by_key(|s| s.updated_at)
+                .map(|s| s.id.clone());
+            if self.active_session_id.is_none() {
+                self.create_session("default")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sessions moved to the archive by pruning. Analytics counts them along
+    /// with the loaded sessions, so pruning does not rewrite history.
+    pub fn archived_sessions(&self) -> Vec<Session> {
+        let Ok(entries) = std::fs::read_dir(self.sessions_dir.join(ARCHIVE_DIR)) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("json"))
+            .filter_map(|path| Session::load_from(&path).ok())
+            .collect()
+    }
+
     /// List all sessions as (id, name, updated_at, message_count).
     pub fn list_sessions(&self) -> Vec<(&str, &str, DateTime<Utc>, usize)> {
         let mut list: Vec<_> = self
@@ -286,19 +1069,70 @@ impl SessionManager {
                 )
             })
             .collect();
-        list.sort_by(|a, b| b.2.cmp(&a.2));
+        list.sort_by_key(|s| std::cmp::Reverse(s.2));
         list
     }
 
-    /// Add a message to the active session.
+    fn require_active(&self) -> Result<String, AgentError> {
+        self.active_session_id
+            .clone()
+            .ok_or_else(|| AgentError::Session("No active session".into()))
+    }
+
+    /// Add a message to the active session; see
+    /// [`SessionManager::push_message_in`].
     pub fn push_message(&mut self, message: Message) -> Result<(), AgentError> {
-        let session = self
-            .active_session_mut()
-            .ok_or_else(|| AgentError::Session("No active session".into()))?;
-        session.push_message(message);
+        let id = self.require_active()?;
+        self.push_message_in(&id, message)
+    }
+
+    /// Add a message to session `id`.
+    ///
+    /// Changes another process saved to the session are reloaded first, and
+    /// the directory stays locked until the message is saved, so concurrent
+    /// appends from several processes are all kept. Without `auto_save` the
+    /// message is only kept in memory until the session is saved.
+    pub fn push_message_in(&mut self, id: &str, message: Message) -> Result<(), AgentError> {
+        self.update_in(id, |session| session.push_message(message))?
+            .ok_or_else(|| AgentError::Session(format!("Session not found: {}", id)))
+    }
+
+    /// Change session `id` in place, returning `None` if there is no such
+    /// session. Like [`SessionManager::push_message_in`], changes another
+    /// process saved are reloaded first and the directory stays locked
+    /// until the change is saved; without `auto_save` the session is
+    /// marked unsaved instead, so no reload drops the change.
+    pub fn update_in<T>(
+        &mut self,
+        id: &str,
+        change: impl FnOnce(&mut Session) -> T,
+    ) -> Result<Option<T>, AgentError> {
+        let _lock = DirLock::acquire(&self.sessions_dir)?;
+        self.reload_if_changed(id)?;
+        let Some(session) = self.sessions.get_mut(id) else {
+            return Ok(None);
+        };
+        let result = change(session);
         if self.auto_save {
-            self.save_active()?;
+            self.write_session(&self.sessions[id])?;
+        } else {
+            self.unsaved().insert(id.to_string());
         }
+        Ok(Some(result))
+    }
+
+    /// Take an automatic checkpoint in the active session at a turn
+    /// boundary (see [`Session::auto_checkpoint`]).
+    pub fn auto_checkpoint(&mut self) -> Result<(), AgentError> {
+        match self.active_session_id.clone() {
+            Some(id) => self.auto_checkpoint_in(&id),
+            None => Ok(()),
+        }
+    }
+
+    /// Take an automatic checkpoint in session `id`.
+    pub fn auto_checkpoint_in(&mut self, id: &str) -> Result<(), AgentError> {
+        self.update_in(id, Session::auto_checkpoint)?;
         Ok(())
     }
 
@@ -307,19 +1141,43 @@ impl SessionManager {
     /// Preferred inside async contexts (e.g. axum route handlers holding
     /// `tokio::sync::RwLock`) to avoid blocking Tokio worker threads.
     pub async fn push_message_async(&mut self, message: Message) -> Result<(), AgentError> {
+        let id = self.require_active()?;
+        self.push_message_in_async(&id, message).await
+    }
+
+    /// [`SessionManager::push_message_in`] with a non-blocking save.
+    pub async fn push_message_in_async(
+        &mut self,
+        id: &str,
+        message: Message,
+    ) -> Result<(), AgentError> {
+        let _lock = DirLock::acquire_async(&self.sessions_dir).await?;
+        self.reload_if_changed(id)?;
         let session = self
-            .active_session_mut()
-            .ok_or_else(|| AgentError::Session("No active session".into()))?;
+            .sessions
+            .get_mut(id)
+            .ok_or_else(|| AgentError::Session(format!("Session not found: {}", id)))?;
         session.push_message(message);
         if self.auto_save {
-            self.save_active_async().await?;
+            self.write_session_async(&self.sessions[id]).await?;
+        } else {
+            self.unsaved().insert(id.to_string());
         }
         Ok(())
     }
 
     /// Get the recent message history for the active session (for the context window).
     pub fn recent_messages(&self) -> Vec<&Message> {
-        self.active_session()
+        self.active_session_id
+            .as_deref()
+            .map(|id| self.recent_messages_in(id))
+            .unwrap_or_default()
+    }
+
+    /// The recent message history of session `id`.
+    pub fn recent_messages_in(&self, id: &str) -> Vec<&Message> {
+        self.sessions
+            .get(id)
             .map(|s| s.recent_messages(self.max_history).iter().collect())
             .unwrap_or_default()
     }
@@ -327,7 +1185,8 @@ impl SessionManager {
     /// Save the active session to disk.
     pub fn save_active(&self) -> Result<(), AgentError> {
         if let Some(session) = self.active_session() {
-            session.save_to(&self.sessions_dir)?;
+            let _lock = DirLock::acquire(&self.sessions_dir)?;
+            self.write_session(session)?;
         }
         Ok(())
     }
@@ -338,7 +1197,33 @@ impl SessionManager {
     /// to avoid blocking the Tokio runtime on disk I/O.
     pub async fn save_active_async(&self) -> Result<(), AgentError> {
         if let Some(session) = self.active_session() {
-            session.save_to_async(&self.sessions_dir).await?;
+            let _lock = DirLock::acquire_async(&self.sessions_dir).await?;
+            self.write_session_async(session).await?;
+        }
+        Ok(())
+    }
+
+    /// Save a specific session to disk (async / non-blocking).
+    pub async fn save_session_async(&self, id: &str) -> Result<(), AgentError> {
+        if let Some(session) = self.sessions.get(id) {
+            let _lock = DirLock::acquire_async(&self.sessions_dir).await?;
+            self.write_session_async(session).await?;
+        }
+        Ok(())
+    }
+
+    /// Save every loaded session to disk (async / non-blocking).
+    ///
+    /// Used on server shutdown so nothing held only in memory is lost.
+    /// Sessions another process has since written are left as they are.
+    pub async fn save_all_async(&self) -> Result<(), AgentError> {
+        let _lock = DirLock::acquire_async(&self.sessions_dir).await?;
+        for session in self.sessions.values() {
+            if self.changed_on_disk(&session.id) {
+                tracing::debug!("Not saving session {}: newer copy on disk", session.id);
+                continue;
+            }
+            self.write_session_async(session).await?;
         }
         Ok(())
     }
@@ -346,8 +1231,75 @@ impl SessionManager {
     /// Save a specific session to disk.
     fn save_session(&self, id: &str) -> Result<(), AgentError> {
         if let Some(session) = self.sessions.get(id) {
-            session.save_to(&self.sessions_dir)?;
+            let _lock = DirLock::acquire(&self.sessions_dir)?;
+            self.write_session(session)?;
+        }
+        Ok(())
+    }
+
+    /// Shadow directory holding the file snapshots of session `id`.
+    pub fn snapshot_store(&self, id: &str) -> SnapshotStore {
+        SnapshotStore::new(self.sessions_dir.join("snapshots").join(id))
+    }
+
+    /// Record a turn's file snapshots in the active session.
+    pub fn record_snapshots(&mut self, snapshots: Vec<FileSnapshot>) -> Result<(), AgentError> {
+        match self.active_session_id.clone() {
+            Some(id) => self.record_snapshots_in(&id, snapshots),
+            None => Ok(()),
+        }
+    }
+
+    /// Record a turn's file snapshots in session `id`.
+    pub fn record_snapshots_in(
+        &mut self,
+        id: &str,
+        snapshots: Vec<FileSnapshot>,
+    ) -> Result<(), AgentError> {
+        if snapshots.is_empty() {
+            return Ok(());
         }
+        let store = self.snapshot_store(id);
+        self.update_in(id, |session| session.record_snapshots(snapshots, &store))?
+            .unwrap_or(Ok(()))
+    }
+
+    /// Add a turn's token usage to the active session.
+    pub fn record_usage(&mut self, usage: TokenUsage) -> Result<(), AgentError> {
+        match self.active_session_id.clone() {
+            Some(id) => self.record_usage_in(&id, usage),
+            None => Ok(()),
+        }
+    }
+
+    /// Add a turn's token usage to session `id`.
+    pub fn record_usage_in(&mut self, id: &str, usage: TokenUsage) -> Result<(), AgentError> {
+        self.update_in(id, |session| session.usage += usage)?;
+        Ok(())
+    }
+
+    /// Add the tool calls a turn ran to the active session's counts.
+    pub fn record_tool_calls(&mut self, calls: &HashMap<String, u32>) -> Result<(), AgentError> {
+        match self.active_session_id.clone() {
+            Some(id) => self.record_tool_calls_in(&id, calls),
+            None => Ok(()),
+        }
+    }
+
+    /// Add the tool calls a turn ran to session `id`'s counts.
+    pub fn record_tool_calls_in(
+        &mut self,
+        id: &str,
+        calls: &HashMap<String, u32>,
+    ) -> Result<(), AgentError> {
+        if calls.is_empty() {
+            return Ok(());
+        }
+        self.update_in(id, |session| {
+            for (name, count) in calls {
+                *session.tool_calls.entry(name.clone()).or_default() += count;
+            }
+        })?;
         Ok(())
     }
 
@@ -356,3 +1308,426 @@ impl SessionManager {
         self.max_history
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ToolCall;
+
+    #[test]
+    fn test_delete_message_removes_tool_results() {
+        let mut session = Session::new("test");
+        session.push_message(Message::user("list files"));
+        let call = ToolCall {
+            id: "call-1".into(),
+            name: "shell".into(),
+            arguments: "{}".into(),
+        };
+        let assistant = Message::assistant_with_tool_calls("", vec![call]);
+        let assistant_id = assistant.id.clone();
+        session.push_message(assistant);
+        session.push_message(Message::tool_result("call-1", "a.txt"));
+        session.push_message(Message::assistant("There is one file."));
+
+        assert!(session.delete_message(&assistant_id));
+        assert_eq!(session.messages.len(), 2);
+        assert!(session.messages.iter().all(|m| m.tool_call_id.is_none()));
+        assert!(!session.delete_message(&assistant_id));
+    }
+
+    #[test]
+    fn test_set_feedback_only_on_assistant_messages() {
+        let mut session = Session::new("test");
+        let question = Message::user("what is 2 + 2?");
+        let question_id = question.id.clone();
+        session.push_message(question);
+        let answer = Message::assistant("5");
+        let answer_id = answer.id.clone();
+        session.push_message(answer);
+
+        let feedback = Feedback {
+            rating: crate::types::Rating::Down,
+            comment: Some("off by one".into()),
+            timestamp: Utc::now(),
+        };
+        assert!(session.set_feedback(&answer_id, Some(feedback.clone())));
+        assert_eq!(session.messages[1].feedback.as_ref(), Some(&feedback));
+        assert!(!session.set_feedback(&question_id, Some(feedback)));
+        assert!(session.set_feedback(&answer_id, None));
+        assert!(session.messages[1].feedback.is_none());
+    }
+
+    #[test]
+    fn test_truncate_from() {
+        let mut session = Session::new("test");
+        session.push_message(Message::user("first"));
+        session.push_message(Message::assistant("one"));
+        let second = Message::user("second");
+        let second_id = second.id.clone();
+        session.push_message(second);
+        session.push_message(Message::assistant("two"));
+
+        let dropped = session.truncate_from(&second_id).unwrap();
+        assert_eq!(dropped.content, "second");
+        assert_eq!(session.messages.len(), 2);
+        assert!(session.truncate_from("missing").is_none());
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let mut session = Session::new("notes");
+        session.push_message(Message::user("hi"));
+        let call = ToolCall {
+            id: "call-1".into(),
+            name: "shell".into(),
+            arguments: r#"{"cmd":"ls"}"#.into(),
+        };
+        session.push_message(Message::assistant_with_tool_calls("", vec![call]));
+        session.push_message(Message::tool_result("call-1", "a.txt"));
+
+        let md = session.to_markdown();
+        assert!(md.starts_with("# notes\n"));
+        assert!(md.contains("## User\n\nhi\n"));
+        assert!(md.contains("**Tool call** `shell`"));
+        assert!(md.contains("## Tool result\n\n```\na.txt\n```"));
+    }
+
+    #[test]
+    fn test_fork_copies_messages_with_new_id() {
+        let mut session = Session::new("original");
+        session.push_message(Message::user("hello"));
+        session.add_tag("rust");
+
+        let forked = session.fork("original (fork)");
+        assert_ne!(forked.id, session.id);
+        assert_eq!(forked.name, "original (fork)");
+        assert_eq!(forked.messages.len(), 1);
+        assert_eq!(forked.tags, vec!["rust".to_string()]);
+    }
+
+    #[test]
+    fn test_resume_or_create_by_name() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config = AppConfig::default();
+        config.session.history_dir = Some(dir.path().to_path_buf());
+        let mut manager = SessionManager::new(&config).unwrap();
+        let default_id = manager.active_session_id().unwrap().to_string();
+
+        let id = manager.resume_or_create("schedule:nightly").unwrap();
+        assert_ne!(id, default_id);
+        manager.push_message(Message::user("check the repo")).unwrap();
+
+        manager.switch_session(&default_id).unwrap();
+        assert_eq!(manager.resume_or_create("schedule:nightly").unwrap(), id);
+        assert_eq!(manager.active_session().unwrap().messages.len(), 1);
+        assert_eq!(manager.list_sessions().len(), 2);
+    }
+
+    #[test]
+    fn test_managers_sharing_a_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config = AppConfig::default();
+        config.session.history_dir = Some(dir.path().to_path_buf());
+        let mut repl = SessionManager::new(&config).unwrap();
+        let mut server = SessionManager::new(&config).unwrap();
+        assert_eq!(repl.active_session_id(), server.active_session_id());
+
+        // Each push first picks up what the other process appended.
+        repl.push_message(Message::user("a")).unwrap();
+        server.push_message(Message::user("b")).unwrap();
+        repl.push_message(Message::user("c")).unwrap();
+        let texts: Vec<&str> = repl
+            .active_session()
+            .unwrap()
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(texts, ["a", "b", "c"]);
+
+        let default_id = repl.active_session_id().unwrap().to_string();
+        let id = repl.create_session("other").unwrap().id.clone();
+        let mut changed = server.reload_changed().unwrap();
+        changed.sort();
+        let mut expected = vec![default_id.clone(), id.clone()];
+        expected.sort();
+        assert_eq!(changed, expected);
+        assert_eq!(server.session(&default_id).unwrap().messages.len(), 3);
+        assert!(server.session(&id).is_some());
+        assert!(server.reload_changed().unwrap().is_empty());
+
+        repl.delete_session(&id).unwrap();
+        server.switch_session(&id).unwrap();
+        assert_eq!(server.reload_changed().unwrap(), vec![id.clone()]);
+        assert!(server.session(&id).is_none());
+        assert_ne!(server.active_session_id(), Some(id.as_str()));
+    }
+
+    #[test]
+    fn test_unsaved_messages_are_not_reloaded_over() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config = AppConfig::default();
+        config.session.history_dir = Some(dir.path().to_path_buf());
+        config.session.auto_save = false;
+        let mut server = SessionManager::new(&config).unwrap();
+        let mut repl = SessionManager::new(&config).unwrap();
+        let id = server.active_session_id().unwrap().to_string();
+        let texts = |manager: &SessionManager| -> Vec<String> {
+            let session = manager.session(&id).unwrap();
+            session.messages.iter().map(|m| m.content.clone()).collect()
+        };
+
+        server.push_message(Message::user("a")).unwrap();
+        repl.push_message(Message::user("b")).unwrap();
+        repl.save_active().unwrap();
+        server.push_message(Message::user("c")).unwrap();
+        assert!(server.reload_changed().unwrap().is_empty());
+        assert_eq!(texts(&server), ["a", "c"]);
+
+        // Once saved, changes made elsewhere are picked up again.
+        server.save_active().unwrap();
+        assert_eq!(repl.reload_changed().unwrap(), vec![id.clone()]);
+        assert_eq!(texts(&repl), ["a", "c"]);
+        repl.push_message(Message::user("d")).unwrap();
+        repl.save_active().unwrap();
+        server.push_message(Message::user("e")).unwrap();
+        assert_eq!(texts(&server), ["a", "c", "d", "e"]);
+    }
+
+    #[test]
+    fn test_usage_and_snapshots_survive_reloads() {
+        for auto_save in [false, true] {
+            let dir = tempfile::TempDir::new().unwrap();
+            let mut config = AppConfig::default();
+            config.session.history_dir = Some(dir.path().join("sessions"));
+            config.session.auto_save = auto_save;
+            let mut server = SessionManager::new(&config).unwrap();
+            let mut repl = SessionManager::new(&config).unwrap();
+            let id = server.active_session_id().unwrap().to_string();
+            let file = dir.path().join("notes.txt");
+            std::fs::write(&file, "before").unwrap();
+
+            server.auto_checkpoint_in(&id).unwrap();
+            server
+                .record_snapshots_in(&id, FileSnapshot::capture(&file))
+                .unwrap();
+            let usage = TokenUsage {
+                prompt_tokens: 10,
+                ..Default::default()
+            };
+            server.record_usage_in(&id, usage).unwrap();
+            // Another process writes the session in between.
+            repl.push_message(Message::user("from the repl")).unwrap();
+            repl.save_active().unwrap();
+
+            let recorded = |manager: &SessionManager| {
+                let session = manager.session(&id).unwrap();
+                let files = session.checkpoints.last().map_or(0, |c| c.files.len());
+                (session.usage.prompt_tokens, files)
+            };
+            server.reload_changed().unwrap();
+            assert_eq!(recorded(&server), (10, 1), "auto_save = {auto_save}");
+            server.save_active().unwrap();
+            repl.reload_changed().unwrap();
+            assert_eq!(recorded(&repl), (10, 1), "auto_save = {auto_save}");
+        }
+    }
+
+    #[test]
+    fn test_concurrent_appends_are_all_kept() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config = AppConfig::default();
+        config.session.history_dir = Some(dir.path().to_path_buf());
+        let id = SessionManager::new(&config)
+            .unwrap()
+            .active_session_id()
+            .unwrap()
+            .to_string();
+
+        let writers: Vec<_> = (0..4)
+            .map(|w| {
+                let config = config.clone();
+                std::thread::spawn(move || {
+                    let mut manager = SessionManager::new(&config).unwrap();
+                    for i in 0..10 {
+                        manager
+                            .push_message(Message::user(format!("{}-{}", w, i)))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let session = Session::load_from(&dir.path().join(format!("{}.json", id))).unwrap();
+        assert_eq!(session.messages.len(), 40);
+    }
+
+    #[test]
+    fn test_undo_restores_messages_and_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = SnapshotStore::new(dir.path().join("shadow"));
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "v1").unwrap();
+        let mut session = Session::new("undo");
+
+        // Turn 1 edits the file.
+        session.auto_checkpoint();
+        session.push_message(Message::user("edit it"));
+        session.record_snapshots(FileSnapshot::capture(&file), &store).unwrap();
+        std::fs::write(&file, "v2").unwrap();
+        session.push_message(Message::assistant("done"));
+
+        // Turn 2 edits it again. A second checkpoint with nothing new is skipped.
+        session.auto_checkpoint();
+        session.auto_checkpoint();
+        assert_eq!(session.checkpoints.len(), 2);
+        session.push_message(Message::user("again"));
+        session.record_snapshots(FileSnapshot::capture(&file), &store).unwrap();
+        std::fs::write(&file, "v3").unwrap();
+
+        let rollback = session.undo(true, &store).unwrap();
+        assert_eq!(rollback.messages_removed, 1);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v2");
+
+        let first = session.checkpoints[0].id.clone();
+        let rollback = session.rollback(&first, false, &store).unwrap();
+        assert_eq!(rollback.messages_removed, 2);
+        assert!(rollback.files_restored.is_empty());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v2");
+        assert!(session.messages.is_empty());
+        assert!(session.undo(true, &store).is_err());
+        assert!(session.rollback("missing", true, &store).is_err());
+        // Discarded snapshots are removed from the shadow directory.
+        assert_eq!(std::fs::read_dir(dir.path().join("shadow")).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_rollback_restores_oldest_snapshot() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = SnapshotStore::new(dir.path().join("shadow"));
+        let file = dir.path().join("a.rs");
+        let mut session = Session::new("multi");
+
+        session.auto_checkpoint();
+        session.push_message(Message::user("create"));
+        session.record_snapshots(FileSnapshot::capture(&file), &store).unwrap();
+        std::fs::write(&file, "created").unwrap();
+        session.auto_checkpoint();
+        session.push_message(Message::user("change"));
+        session.record_snapshots(FileSnapshot::capture(&file), &store).unwrap();
+        std::fs::write(&file, "changed").unwrap();
+
+        let first = session.checkpoints[0].id.clone();
+        let rollback = session.rollback(&first, true, &store).unwrap();
+        assert_eq!(rollback.files_restored, vec![file.clone()]);
+        assert!(!file.exists());
+        assert_eq!(session.checkpoints.len(), 1);
+    }
+
+    #[test]
+    fn test_file_changes_and_revert() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = SnapshotStore::new(dir.path().join("shadow"));
+        let (a, b) = (dir.path().join("a.txt"), dir.path().join("b.txt"));
+        std::fs::write(&a, "a1").unwrap();
+        std::fs::write(&b, "b1").unwrap();
+        let mut session = Session::new("changes");
+
+        session.auto_checkpoint();
+        session.push_message(Message::user("edit both"));
+        for path in [&a, &b] {
+            session.record_snapshots(FileSnapshot::capture(path), &store).unwrap();
+        }
+        std::fs::write(&a, "a2").unwrap();
+        std::fs::write(&b, "b2").unwrap();
+        session.auto_checkpoint();
+        session.push_message(Message::user("edit a again"));
+        session.record_snapshots(FileSnapshot::capture(&a), &store).unwrap();
+        std::fs::write(&a, "a3").unwrap();
+
+        let changes = session.file_changes();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].original(&store).unwrap().unwrap(), b"a1");
+
+        session.revert_file(&a, &store).unwrap();
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "a1");
+        assert_eq!(std::fs::read_to_string(&b).unwrap(), "b2");
+        assert_eq!(session.file_changes().len(), 1);
+        assert_eq!(session.messages.len(), 2);
+        assert!(session.revert_file(&a, &store).is_err());
+
+        session.accept_file(&b, &store).unwrap();
+        assert_eq!(std::fs::read_to_string(&b).unwrap(), "b2");
+        assert!(session.file_changes().is_empty());
+        assert!(session.accept_file(&b, &store).is_err());
+        // Rolling back no longer touches the accepted file.
+        session.undo(true, &store).unwrap();
+        assert_eq!(std::fs::read_to_string(&b).unwrap(), "b2");
+    }
+
+    #[test]
+    fn test_prune_archives_old_and_excess_sessions() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let now = Utc::now();
+        let mut ids = Vec::new();
+        // Oldest first: 40, 30, 20, 10 and 0 days ago.
+        for (days, name) in [(40, "ancient"), (30, "shared"), (20, "old"), (10, "recent"), (0, "new")] {
+            let mut session = Session::new(name);
+            session.updated_at = now - chrono::Duration::days(days);
+            if name == "shared" {
+                session.share();
+            }
+            session.save_to(dir.path()).unwrap();
+            ids.push(session.id);
+        }
+        let mut config = AppConfig::default();
+        config.session.history_dir = Some(dir.path().to_path_buf());
+        config.session.retention_days = Some(25);
+        config.session.max_sessions = Some(4);
+        let mut manager = SessionManager::new(&config).unwrap();
+
+        let dry = manager.prune(&config.session, now, true).unwrap();
+        let names: Vec<_> = dry.iter().map(|p| (p.name.as_str(), p.reason)).collect();
+        assert_eq!(names, [("ancient", PruneReason::OverLimit)]);
+        assert_eq!(manager.list_sessions().len(), 5);
+
+        config.session.max_sessions = None;
+        let pruned = manager.prune(&config.session, now, false).unwrap();
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].reason, PruneReason::Expired);
+        // The shared session is kept despite its age.
+        assert_eq!(manager.list_sessions().len(), 4);
+        assert!(!dir.path().join(format!("{}.json", ids[0])).exists());
+        assert_eq!(manager.archived_sessions()[0].name, "ancient");
+
+        config.session.prune_action = PruneAction::Delete;
+        config.session.retention_days = Some(5);
+        let pruned = manager.prune(&config.session, now, false).unwrap();
+        let names: Vec<_> = pruned.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["old", "recent"]);
+        assert_eq!(manager.archived_sessions().len(), 1);
+        assert_eq!(SessionManager::new(&config).unwrap().list_sessions().len(), 2);
+    }
+
+    #[test]
+    fn test_import_sessions_skips_already_imported() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut config = AppConfig::default();
+        config.session.history_dir = Some(dir.path().to_path_buf());
+        let mut manager = SessionManager::new(&config).unwrap();
+        let export = "# aider chat started at 2024-05-01 09:30:00\n#### hi\nhello\n";
+        let parse = || crate::import::ImportSource::Aider.parse(export).unwrap();
+
+        let added = manager.import_sessions(parse()).unwrap();
+        assert_eq!(added.len(), 1);
+        assert!(dir.path().join(format!("{}.json", added[0])).exists());
+        assert_ne!(manager.active_session_id(), Some(added[0].as_str()));
+        assert!(manager.import_sessions(parse()).unwrap().is_empty());
+
+        manager.archive_session(&added[0]).unwrap();
+        assert!(manager.import_sessions(parse()).unwrap().is_empty());
+    }
+}
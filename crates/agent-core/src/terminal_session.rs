@@ -15,6 +15,9 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use uuid::Uuid;
 
+/// Maximum bytes of raw PTY output retained per session.
+const MAX_OUTPUT_TAIL_BYTES: usize = 16 * 1024;
+
 /// A terminal session containing multiple commands.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TerminalSession {
@@ -156,6 +159,8 @@ pub struct TerminalSessionManager {
     commands: HashMap<Uuid, Vec<TerminalCommand>>,
     /// Track next command sequence number per session.
     sequence_counters: HashMap<Uuid, u32>,
+    /// Rolling tail of raw PTY output per session (bounded).
+    output_tails: HashMap<Uuid, Vec<u8>>,
     parser: CommandParser,
 }
 
@@ -166,6 +171,7 @@ impl TerminalSessionManager {
             sessions: HashMap::new(),
             commands: HashMap::new(),
             sequence_counters: HashMap::new(),
+            output_tails: HashMap::new(),
             parser: CommandParser::new(),
         }
     }
@@ -251,8 +257,17 @@ impl TerminalSessionManager {
                     session.working_directory = new_directory.clone();
                 }
             }
-            CaptureEvent::Output { .. } => {
-                // Output events are handled by the PTY layer; no-op here.
+            CaptureEvent::Output {
+                session_id, data, ..
+            } => {
+                // Keep only a bounded tail for error context; full output
+                // is handled by the PTY layer.
+                let tail = self.output_tails.entry(*session_id).or_default();
+                tail.extend_from_slice(data);
+                if tail.len() > MAX_OUTPUT_TAIL_BYTES {
+                    let excess = tail.len() - MAX_OUTPUT_TAIL_BYTES;
+                    tail.drain(..excess);
+                }
             }
         }
     }
@@ -294,11 +309,37 @@ impl TerminalSessionManager {
             .values()
             .flat_map(|cmds| cmds.iter())
             .collect();
-        all.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        all.sort_by_key(|c| std::cmp::Reverse(c.started_at));
         all.truncate(max);
         all
     }
 
+    /// Get the most recent N failed commands (non-zero exit) across all
+    /// sessions, newest first.
+    pub fn recent_failures(&self, max: usize) -> Vec<&TerminalCommand> {
+        let mut failed: Vec<&TerminalCommand> = self
+            .commands
+            .values()
+            .flat_map(|cmds| cmds.iter())
+            .filter(|c| c.failed())
+            .collect();
+        failed.sort_by_key(|c| std::cmp::Reverse(c.started_at));
+        failed.truncate(max);
+        failed
+    }
+
+    /// Find a command by ID within a session.
+    pub fn get_command(&self, session_id: &Uuid, command_id: &Uuid) -> Option<&TerminalCommand> {
+        self.commands
+            .get(session_id)
+            .and_then(|cmds| cmds.iter().find(|c| c.id == *command_id))
+    }
+
+    /// Get the raw output tail captured for a session, if any.
+    pub fn output_tail(&self, session_id: &Uuid) -> Option<&[u8]> {
+        self.output_tails.get(session_id).map(|v| v.as_slice())
+    }
+
     /// Get commands matching a specific tool category.
     pub fn commands_by_category(&self, category: &ToolCategory) -> Vec<&TerminalCommand> {
         self.commands
@@ -317,6 +358,7 @@ impl TerminalSessionManager {
     pub fn remove_session(&mut self, id: &Uuid) -> Option<TerminalSession> {
         self.commands.remove(id);
         self.sequence_counters.remove(id);
+        self.output_tails.remove(id);
         self.sessions.remove(id)
     }
 
@@ -600,17 +642,61 @@ mod tests {
     }
 
     #[test]
-    fn test_output_event_is_noop() {
+    fn test_output_event_updates_tail() {
         let mut mgr = TerminalSessionManager::new();
         let sid = Uuid::new_v4();
         mgr.process_event(&make_session_start(sid));
 
-        // Should not panic or change state.
         mgr.process_event(&CaptureEvent::Output {
             session_id: sid,
             data: b"hello".to_vec(),
             timestamp: Utc::now(),
         });
         assert_eq!(mgr.session_count(), 1);
+        assert_eq!(mgr.output_tail(&sid), Some(&b"hello"[..]));
+    }
+
+    #[test]
+    fn test_output_tail_is_bounded() {
+        let mut mgr = TerminalSessionManager::new();
+        let sid = Uuid::new_v4();
+        mgr.process_event(&make_session_start(sid));
+
+        let chunk = vec![b'x'; MAX_OUTPUT_TAIL_BYTES];
+        mgr.process_event(&CaptureEvent::Output {
+            session_id: sid,
+            data: chunk,
+            timestamp: Utc::now(),
+        });
+        mgr.process_event(&CaptureEvent::Output {
+            session_id: sid,
+            data: b"END".to_vec(),
+            timestamp: Utc::now(),
+        });
+
+        let tail = mgr.output_tail(&sid).unwrap();
+        assert_eq!(tail.len(), MAX_OUTPUT_TAIL_BYTES);
+        assert!(tail.ends_with(b"END"));
+    }
+
+    #[test]
+    fn test_recent_failures() {
+        let mut mgr = TerminalSessionManager::new();
+        let sid = Uuid::new_v4();
+        let ok = Uuid::new_v4();
+        let bad = Uuid::new_v4();
+        let running = Uuid::new_v4();
+
+        mgr.process_event(&make_session_start(sid));
+        mgr.process_event(&make_command_start(sid, ok, "ls"));
+        mgr.process_event(&make_command_end(sid, ok, 0));
+        mgr.process_event(&make_command_start(sid, bad, "cargo build"));
+        mgr.process_event(&make_command_end(sid, bad, 101));
+        mgr.process_event(&make_command_start(sid, running, "sleep 10"));
+
+        let failures = mgr.recent_failures(10);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].id, bad);
+        assert_eq!(mgr.get_command(&sid, &bad).unwrap().exit_code, Some(101));
     }
 }
@@ -0,0 +1,383 @@
+//! Tolerant assembly of tool calls from streamed model output.
+//!
+//! Local models served through OpenAI-compatible endpoints stream tool
+//! calls in many slightly wrong ways: deltas without ids, ids or names
+//! repeated in every chunk, cumulative rather than incremental argument
+//! fragments, arguments wrapped in markdown fences or left with a trailing
+//! comma or missing closing brace. Some skip the `tool_calls` field
+//! entirely and write the call into the message text as JSON or inside
+//! `<tool_call>` tags. [`ToolCallAssembler`] and [`extract_from_content`]
+//! turn all of these into well-formed [`ToolCall`]s.
+
+use crate::types::ToolCall;
+use serde_json::Value;
+
+#[derive(Debug, Default)]
+struct PartialCall {
+    index: u32,
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// Collects streamed tool call deltas.
+#[derive(Debug, Default)]
+pub struct ToolCallAssembler {
+    calls: Vec<PartialCall>,
+}
+
+impl ToolCallAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one delta. Returns the id of the call it belongs to (possibly
+    /// empty while the model has not sent one).
+    pub fn push(
+        &mut self,
+        index: u32,
+        id: Option<&str>,
+        name: Option<&str>,
+        arguments: Option<&str>,
+    ) -> String {
+        let id = id.filter(|id| !id.is_empty());
+        // A different id at an index already in use starts a new call
+        // (models that always send index 0).
+        let existing = self.calls.iter().rposition(|c| c.index == index);
+        let pos = match existing {
+            Some(pos)
+                if id
+                    .is_none_or(|id| self.calls[pos].id.is_empty() || self.calls[pos].id == id) =>
+            {
+                pos
+            }
+            _ => {
+                self.calls.push(PartialCall {
+                    index,
+                    ..Default::default()
+                });
+                self.calls.len() - 1
+            }
+        };
+        let call = &mut self.calls[pos];
+        if let Some(id) = id {
+            call.id = id.to_string();
+        }
+        if let Some(name) = name.filter(|n| !n.is_empty()) {
+            // Names usually arrive whole, sometimes repeated, rarely in pieces.
+            if call.name.is_empty() || name.starts_with(call.name.as_str()) {
+                call.name = name.to_string();
+            } else if call.name != name {
+                call.name.push_str(name);
+            }
+        }
+        if let Some(fragment) = arguments.filter(|a| !a.is_empty()) {
+            // Some servers resend everything so far instead of a delta.
+            if !call.arguments.is_empty()
+                && fragment.len() > call.arguments.len()
+                && fragment.starts_with(call.arguments.as_str())
+            {
+                call.arguments = fragment.to_string();
+            } else {
+                call.arguments.push_str(fragment);
+            }
+        }
+        call.id.clone()
+    }
+
+    /// The assembled calls in stream order, with ids filled in and
+    /// arguments repaired. Deltas that never named a tool are dropped.
+    pub fn finish(self) -> Vec<ToolCall> {
+        self.calls
+            .into_iter()
+            .filter(|c| !c.name.trim().is_empty())
+            .enumerate()
+            .map(|(n, c)| ToolCall {
+                id: if c.id.is_empty() {
+                    format!("call_{}", n)
+                } else {
+                    c.id
+                },
+                name: c.name.trim().to_string(),
+                arguments: repair_arguments(&c.arguments),
+            })
+            .collect()
+    }
+}
+
+/// Make tool arguments valid JSON where a common mistake prevents it:
+/// markdown fences, surrounding prose, a JSON-encoded string holding the
+/// object, trailing commas and unclosed braces or brackets. Input that
+/// cannot be repaired is returned trimmed, so the parse error reaches the
+/// model.
+pub fn repair_arguments(raw: &str) -> String {
+    let text = strip_fences(raw.trim());
+    if text.is_empty() {
+        return "{}".into();
+    }
+    if let Ok(value) = serde_json::from_str::<Value>(text) {
+        return match value {
+            // Double-encoded: "{\"path\": \"a\"}".
+            Value::String(inner)
+                if serde_json::from_str::<Value>(&inner).is_ok_and(|v| v.is_object()) =>
+            {
+                inner
+            }
+            _ => text.to_string(),
+        };
+    }
+    let candidate = match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &text[start..=end],
+        (Some(start), _) => &text[start..],
+        _ => text,
+    };
+    let repaired = close_brackets(&remove_trailing_commas(candidate));
+    if serde_json::from_str::<Value>(&repaired).is_ok() {
+        repaired
+    } else {
+        text.to_string()
+    }
+}
+
+/// Remove a surrounding ```` ```json ```` fence.
+fn strip_fences(text: &str) -> &str {
+    let Some(rest) = text.strip_prefix("```") else {
+        return text;
+    };
+    // Skip the info string (`json`, `JSON`, ...) up to the first newline.
+    let body = match rest.find('\n') {
+        Some(nl) if !rest[..nl].contains('{') => &rest[nl + 1..],
+        _ => rest,
+    };
+    body.trim_end().strip_suffix("```").unwrap_or(body).trim()
+}
+
+/// Drop commas directly before a closing brace or bracket, outside strings.
+fn remove_trailing_commas(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            out.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}') | Some(']') | None) {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Close any strings, arrays and objects left open at the end of `text`.
+fn close_brackets(text: &str) -> String {
+    let mut stack = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => stack.push('}'),
+            '[' => stack.push(']'),
+            '}' | ']' => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    let mut out = text.trim_end().trim_end_matches(',').to_string();
+    if in_string {
+        out.push('"');
+    }
+    out.extend(stack.iter().rev());
+    out
+}
+
+/// Tool calls written into the message text instead of `tool_calls`:
+/// `<tool_call>{...}</tool_call>` blocks, fenced JSON blocks, or a message
+/// that is nothing but a JSON call. Each call is an object with `name`
+/// and `arguments` (or `parameters`); only names in `known_tools` count,
+/// so ordinary JSON in an answer is left alone. Returns the remaining
+/// text and the calls, or `None` if there are none.
+pub fn extract_from_content(
+    content: &str,
+    known_tools: &dyn Fn(&str) -> bool,
+) -> Option<(String, Vec<ToolCall>)> {
+    let mut calls = Vec::new();
+    let mut remaining = String::new();
+    let mut rest = content;
+
+    for (open, close) in [("<tool_call>", "</tool_call>"), ("```", "```")] {
+        let mut kept = String::new();
+        while let Some(start) = rest.find(open) {
+            let body_start = start + open.len();
+            let Some(len) = rest[body_start..].find(close) else {
+                break;
+            };
+            let block = &rest[start..body_start + len + close.len()];
+            let body = if open == "```" {
+                strip_fences(block)
+            } else {
+                &rest[body_start..body_start + len]
+            };
+            match parse_call(body, known_tools) {
+                Some(call) => {
+                    kept.push_str(&rest[..start]);
+                    calls.push(call);
+                }
+                None => kept.push_str(&rest[..body_start + len + close.len()]),
+            }
+            rest = &rest[body_start + len + close.len()..];
+        }
+        kept.push_str(rest);
+        remaining = kept;
+        rest = &remaining;
+        if !calls.is_empty() {
+            break;
+        }
+    }
+
+    if calls.is_empty() {
+        let call = parse_call(content, known_tools)?;
+        calls.push(call);
+        remaining.clear();
+    }
+    for (n, call) in calls.iter_mut().enumerate() {
+        call.id = format!("call_{}", n);
+    }
+    Some((remaining.trim().to_string(), calls))
+}
+
+fn parse_call(text: &str, known_tools: &dyn Fn(&str) -> bool) -> Option<ToolCall> {
+    let value: Value = serde_json::from_str(&repair_arguments(text)).ok()?;
+    // Some templates wrap the call: {"function": {...}}.
+    let value = value
+        .get("function")
+        .filter(|f| f.is_object())
+        .unwrap_or(&value);
+    let name = value.get("name")?.as_str()?;
+    if !known_tools(name) {
+        return None;
+    }
+    let arguments = match value.get("arguments").or_else(|| value.get("parameters")) {
+        Some(Value::String(s)) => repair_arguments(s),
+        Some(v) => v.to_string(),
+        None => "{}".into(),
+    };
+    Some(ToolCall {
+        id: String::new(),
+        name: name.to_string(),
+        arguments,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assembles_split_and_repeated_deltas() {
+        let mut asm = ToolCallAssembler::new();
+        asm.push(0, Some("call_a"), Some("file_read"), Some("{\"pa"));
+        asm.push(
+            0,
+            Some("call_a"),
+            Some("file_read"),
+            Some("th\": \"a.rs\"}"),
+        );
+        // Second call reusing index 0 with a new id.
+        asm.push(0, Some("call_b"), Some("file_list"), None);
+        asm.push(0, None, None, Some("{\"path\""));
+        // Cumulative fragment.
+        asm.push(0, None, None, Some("{\"path\": \"src\"}"));
+        // No id at all.
+        asm.push(
+            1,
+            None,
+            Some("calc"),
+            Some("```json\n{\"expression\": \"2+2\",}\n```"),
+        );
+        // Never named: dropped.
+        asm.push(2, None, None, Some("{}"));
+
+        let calls = asm.finish();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(
+            (calls[0].id.as_str(), calls[0].arguments.as_str()),
+            ("call_a", r#"{"path": "a.rs"}"#)
+        );
+        assert_eq!(
+            (calls[1].name.as_str(), calls[1].arguments.as_str()),
+            ("file_list", r#"{"path": "src"}"#)
+        );
+        assert_eq!(calls[2].id, "call_2");
+        assert_eq!(calls[2].arguments, r#"{"expression": "2+2"}"#);
+    }
+
+    #[test]
+    fn test_repair_arguments() {
+        assert_eq!(repair_arguments(""), "{}");
+        assert_eq!(repair_arguments(r#"{"a": 1}"#), r#"{"a": 1}"#);
+        assert_eq!(repair_arguments(r#"{"a": [1, 2,], }"#), r#"{"a": [1, 2] }"#);
+        assert_eq!(
+            repair_arguments(r#"{"cmd": "ls", "args": ["-l""#),
+            r#"{"cmd": "ls", "args": ["-l"]}"#
+        );
+        assert_eq!(
+            repair_arguments(r#""{\"path\": \"x\"}""#),
+            r#"{"path": "x"}"#
+        );
+        assert_eq!(
+            repair_arguments(r#"Here you go: {"path": "x"}"#),
+            r#"{"path": "x"}"#
+        );
+        // Commas and braces inside strings are left alone.
+        assert_eq!(repair_arguments(r#"{"s": "a,}"}"#), r#"{"s": "a,}"}"#);
+        assert_eq!(repair_arguments("not json"), "not json");
+    }
+
+    #[test]
+    fn test_extract_from_content() {
+        let known = |name: &str| name == "file_read";
+        let text = "Let me look.\n<tool_call>\n{\"name\": \"file_read\", \"arguments\": {\"path\": \"a.rs\"}}\n</tool_call>";
+        let (rest, calls) = extract_from_content(text, &known).unwrap();
+        assert_eq!(rest, "Let me look.");
+        assert_eq!(calls[0].name, "file_read");
+        assert_eq!(calls[0].arguments, r#"{"path":"a.rs"}"#);
+        assert_eq!(calls[0].id, "call_0");
+
+        let fenced = "```json\n{\"name\": \"file_read\", \"parameters\": \"{\\\"path\\\": \\\"b\\\"}\"}\n```";
+        let (rest, calls) = extract_from_content(fenced, &known).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(calls[0].arguments, r#"{"path": "b"}"#);
+
+        let bare = r#"{"function": {"name": "file_read", "arguments": {}}}"#;
+        assert_eq!(extract_from_content(bare, &known).unwrap().1.len(), 1);
+
+        // JSON that is not a known tool call stays in the answer.
+        assert!(extract_from_content("```json\n{\"name\": \"Ada\"}\n```", &known).is_none());
+        assert!(extract_from_content("The answer is 4.", &known).is_none());
+    }
+}
@@ -0,0 +1,223 @@
+//! Limits on how often tools may be called (`[tools.quotas]`).
+//!
+//! Counts are keyed by the tool's registered name, whatever alias a call
+//! used, and only calls that passed hooks and validation count. Per-session
+//! counts are kept on the session ([`crate::session::Session::tool_calls`]),
+//! so they survive restarts and outlast the history window. Per-day counts
+//! are kept in a state file under the data directory, locked while it is
+//! updated so every process sharing it counts against one limit, and reset
+//! at local midnight. A refused call gets a tool result starting with
+//! [`QUOTA_EXCEEDED`], which analytics count.
+
+use crate::config::ToolQuota;
+use chrono::{Local, NaiveDate};
+use fs4::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Start of the tool result returned for a call over its quota.
+pub const QUOTA_EXCEEDED: &str = "Tool quota exceeded";
+
+/// Name of the per-day state file in the data directory.
+pub const STATE_FILE: &str = "tool-quotas.json";
+
+/// Calls admitted on one day, by tool name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DayCounts {
+    date: NaiveDate,
+    calls: HashMap<String, u32>,
+}
+
+/// Configured limits by tool name, and today's call counts.
+#[derive(Debug)]
+pub struct ToolQuotas {
+    limits: HashMap<String, ToolQuota>,
+    /// Where today's counts are shared; in memory only when unset.
+    state_file: Option<PathBuf>,
+    today: Mutex<DayCounts>,
+}
+
+impl Default for ToolQuotas {
+    fn default() -> Self {
+        Self::new(HashMap::new())
+    }
+}
+
+impl ToolQuotas {
+    /// Limits keyed by tool name, with today's counts kept in memory.
+    pub fn new(limits: HashMap<String, ToolQuota>) -> Self {
+        Self {
+            limits,
+            state_file: None,
+            today: Mutex::new(DayCounts {
+                date: Local::now().date_naive(),
+                calls: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Keep today's counts in `path`, shared with other processes.
+    pub fn with_state_file(mut self, path: PathBuf) -> Self {
+        self.state_file = Some(path);
+        self
+    }
+
+    pub fn limit(&self, name: &str) -> Option<ToolQuota> {
+        self.limits.get(name).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    /// Count a call to `name` (a registered name), which the session has
+    /// already called `session_calls` times, or explain which limit it is
+    /// over. Refused calls are not counted.
+    pub fn admit(&self, name: &str, session_calls: u32) -> Result<(), String> {
+        self.admit_on(Local::now().date_naive(), name, session_calls)
+    }
+
+    fn admit_on(&self, date: NaiveDate, name: &str, session_calls: u32) -> Result<(), String> {
+        let Some(limit) = self.limit(name) else {
+            return Ok(());
+        };
+        if let Some(max) = limit.per_session.filter(|max| session_calls >= *max) {
+            return Err(format!(
+                "{}: `{}` may be called {} times per session and this session has used them all. \
+                 Continue without it or ask the user to raise the limit.",
+                QUOTA_EXCEEDED, name, max
+            ));
+        }
+        let Some(max) = limit.per_day else {
+            return Ok(());
+        };
+        self.with_day(date, |calls| {
+            let used = calls.entry(name.to_string()).or_default();
+            if *used >= max {
+                return Err(format!(
+                    "{}: `{}` may be called {} times per day and today's calls are used up. \
+                     Continue without it or ask the user to raise the limit.",
+                    QUOTA_EXCEEDED, name, max
+                ));
+            }
+            *used += 1;
+            Ok(())
+        })
+    }
+
+    /// Calls to `name` admitted today, counted for tools with a daily limit.
+    pub fn used_today(&self, name: &str) -> u32 {
+        self.with_day(Local::now().date_naive(), |calls| {
+            calls.get(name).copied().unwrap_or(0)
+        })
+    }
+
+    /// Run `f` on the counts for `date`, read from and written back to the
+    /// state file under its lock. A state file that cannot be used is
+    /// logged, and the counts in memory are used instead.
+    fn with_day<T>(&self, date: NaiveDate, f: impl FnOnce(&mut HashMap<String, u32>) -> T) -> T {
+        let mut today = self.today.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = self.state_file.as_deref().and_then(|path| {
+            open_locked(path)
+                .inspect_err(|e| {
+                    tracing::warn!("Tool quota state {} unavailable: {}", path.display(), e)
+                })
+                .ok()
+        });
+        if let Some(saved) = file.as_mut().and_then(read_counts) {
+            *today = saved;
+        }
+        if today.date != date {
+            *today = DayCounts {
+                date,
+                calls: HashMap::new(),
+            };
+        }
+        let result = f(&mut today.calls);
+        if let Some(file) = file.as_mut() {
+            if let Err(e) = write_counts(file, &today) {
+                tracing::warn!("Failed to save tool quota state: {}", e);
+            }
+        }
+        result
+    }
+}
+
+/// The state file, created if missing and locked until dropped.
+fn open_locked(path: &Path) -> std::io::Result<std::fs::File> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    FileExt::lock_exclusive(&file)?;
+    Ok(file)
+}
+
+/// The saved counts; `None` for a new or unreadable file.
+fn read_counts(file: &mut std::fs::File) -> Option<DayCounts> {
+    let mut json = String::new();
+    file.read_to_string(&mut json).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn write_counts(file: &mut std::fs::File, counts: &DayCounts) -> std::io::Result<()> {
+    file.set_len(0)?;
+    file.rewind()?;
+    file.write_all(&serde_json::to_vec(counts)?)?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> HashMap<String, ToolQuota> {
+        HashMap::from([
+            ("shell_exec".to_string(), ToolQuota { per_session: Some(2), per_day: None }),
+            ("web_fetch".to_string(), ToolQuota { per_session: None, per_day: Some(2) }),
+        ])
+    }
+
+    #[test]
+    fn test_session_and_daily_limits() {
+        let quotas = ToolQuotas::new(limits());
+        let day = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+
+        assert!(quotas.admit_on(day, "shell_exec", 1).is_ok());
+        let err = quotas.admit_on(day, "shell_exec", 2).unwrap_err();
+        assert!(err.starts_with(QUOTA_EXCEEDED), "{}", err);
+        assert!(err.contains("per session"));
+        assert!(quotas.admit_on(day, "file_read", 1000).is_ok());
+
+        assert!(quotas.admit_on(day, "web_fetch", 0).is_ok());
+        assert!(quotas.admit_on(day, "web_fetch", 0).is_ok());
+        assert!(quotas.admit_on(day, "web_fetch", 0).unwrap_err().contains("per day"));
+        // A new day starts from zero.
+        assert!(quotas.admit_on(day.succ_opt().unwrap(), "web_fetch", 0).is_ok());
+    }
+
+    #[test]
+    fn test_daily_counts_are_shared_through_the_state_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(STATE_FILE);
+        let day = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let server = ToolQuotas::new(limits()).with_state_file(path.clone());
+        let repl = ToolQuotas::new(limits()).with_state_file(path.clone());
+
+        assert!(server.admit_on(day, "web_fetch", 0).is_ok());
+        assert!(repl.admit_on(day, "web_fetch", 0).is_ok());
+        assert!(server.admit_on(day, "web_fetch", 0).unwrap_err().contains("per day"));
+        // A restarted process still sees today's calls.
+        let restarted = ToolQuotas::new(limits()).with_state_file(path);
+        assert!(restarted.admit_on(day, "web_fetch", 0).is_err());
+        assert!(restarted.admit_on(day.succ_opt().unwrap(), "web_fetch", 0).is_ok());
+    }
+}
@@ -1,9 +1,27 @@
+//! The tools the agent can call.
+//!
+//! Tools are called by their short name (`file_read`), which must be unique
+//! across built-ins and plugins. Each also has a namespaced ID naming where
+//! it came from — `builtin.file_read`, `plugin.jira.create_issue` — and may
+//! have aliases from `[tools] aliases`; lookups accept any of the three.
+//!
+//! A disabled tool stays registered but is neither offered to the model
+//! nor run, so it can be enabled again without a restart. In read-only mode
+//! (`[tools] read_only`) every tool that does not declare itself
+//! [read-only](Tool::read_only) counts as disabled. Call limits from
+//! `[tools.quotas]` are kept in [`ToolQuotas`] and enforced by the agent
+//! loop, which knows the session.
+
+use crate::config::{AppConfig, ToolsConfig};
 use crate::error::AgentError;
-use crate::types::{ToolOutput, ToolSchema};
+use crate::result_cache::ResultCache;
+use crate::tool_quota::{self, ToolQuotas};
+use crate::types::{ImagePart, ToolOutput, ToolSchema};
 use async_trait::async_trait;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 
 /// Trait that all tools must implement.
 #[async_trait]
@@ -19,39 +37,223 @@ pub trait Tool: Send + Sync {
 
     /// Execute the tool with the given arguments.
     async fn execute(&self, args: Value) -> Result<String, AgentError>;
+
+    /// Execute and also return images for multimodal models. Tools that can
+    /// produce images override this; the default returns none.
+    async fn execute_with_images(
+        &self,
+        args: Value,
+    ) -> Result<(String, Vec<ImagePart>), AgentError> {
+        Ok((self.execute(args).await?, Vec::new()))
+    }
+
+    /// Files a call with `args` would modify, so they can be snapshotted
+    /// first and restored on rollback. The default is none.
+    fn affected_paths(&self, _args: &Value) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Whether the tool leaves files, repositories and other state alone,
+    /// so it stays available in read-only mode. The default is no, which
+    /// also switches off plugin tools.
+    fn read_only(&self) -> bool {
+        false
+    }
 }
 
-/// Maximum size (in bytes) for any single tool output. Outputs exceeding
-/// this limit are truncated with a suffix indicating the truncation.
-const MAX_TOOL_OUTPUT_BYTES: usize = 64 * 1024; // 64 KiB
+/// Namespaced ID of a built-in tool.
+pub fn builtin_id(name: &str) -> String {
+    format!("builtin.{}", name)
+}
+
+/// Namespaced ID of a tool provided by a plugin.
+pub fn plugin_id(plugin: &str, name: &str) -> String {
+    format!("plugin.{}.{}", plugin, name)
+}
 
 /// Central registry for all available tools.
 pub struct ToolRegistry {
     tools: HashMap<String, Arc<dyn Tool>>,
+    /// Namespaced ID of each tool, by name.
+    ids: HashMap<String, String>,
+    /// Tool name of each alias.
+    aliases: HashMap<String, String>,
+    /// IDs of disabled tools.
+    disabled: RwLock<HashSet<String>>,
+    /// Only [read-only](Tool::read_only) tools are enabled.
+    read_only: bool,
+    quotas: ToolQuotas,
+    results: ResultCache,
 }
 
 impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            ids: HashMap::new(),
+            aliases: HashMap::new(),
+            disabled: RwLock::default(),
+            read_only: false,
+            quotas: ToolQuotas::default(),
+            results: ResultCache::new(),
         }
     }
 
-    /// Register a tool. Overwrites any existing tool with the same name.
+    /// Register a built-in tool. Overwrites any existing tool with the same
+    /// name.
     pub fn register(&mut self, tool: Arc<dyn Tool>) {
         let name = tool.name().to_string();
+        if let Some(previous) = self.ids.get(&name) {
+            tracing::warn!("Built-in tool {} replaces {}", name, previous);
+        }
         tracing::debug!("Registered tool: {}", name);
+        self.ids.insert(name.clone(), builtin_id(&name));
         self.tools.insert(name, tool);
     }
 
-    /// Unregister a tool by name.
+    /// Register a tool provided by `plugin`. Fails if another enabled tool
+    /// (or an alias) already has its name: disable the built-in in
+    /// `[tools] disabled` to let the plugin take it over.
+    pub fn register_plugin(&mut self, plugin: &str, tool: Arc<dyn Tool>) -> Result<(), AgentError> {
+        let name = tool.name().to_string();
+        let id = plugin_id(plugin, &name);
+        let taken = match self.ids.get(&name) {
+            Some(existing) if !self.disabled_ids().contains(existing) => Some(existing),
+            Some(_) => None,
+            None => self.aliases.get(&name),
+        };
+        if let Some(existing) = taken {
+            return Err(AgentError::Config(format!(
+                "Tool {} conflicts with {}, which is already called '{}'",
+                id, existing, name
+            )));
+        }
+        tracing::debug!("Registered tool: {}", id);
+        self.ids.insert(name.clone(), id);
+        self.tools.insert(name, tool);
+        Ok(())
+    }
+
+    /// Unregister a tool by name, ID or alias, and drop its aliases.
     pub fn unregister(&mut self, name: &str) -> Option<Arc<dyn Tool>> {
-        self.tools.remove(name)
+        let name = self.resolve(name)?.to_string();
+        self.ids.remove(&name);
+        self.aliases.retain(|_, target| *target != name);
+        self.tools.remove(&name)
     }
 
-    /// Get a tool by name.
+    /// The name of the tool called `name`, with the namespaced ID `name`,
+    /// or aliased as `name`.
+    pub fn resolve<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        if self.tools.contains_key(name) {
+            return Some(name);
+        }
+        if let Some(target) = self.aliases.get(name) {
+            return Some(target);
+        }
+        self.ids
+            .iter()
+            .find(|(_, id)| id.as_str() == name)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// The namespaced ID of a tool.
+    pub fn id(&self, name: &str) -> Option<&str> {
+        self.ids.get(self.resolve(name)?).map(String::as_str)
+    }
+
+    /// Get a tool by name, ID or alias.
     pub fn get(&self, name: &str) -> Option<&Arc<dyn Tool>> {
-        self.tools.get(name)
+        self.tools.get(self.resolve(name)?)
+    }
+
+    /// Call the tool `target` (a name, ID or alias) as `alias` too.
+    pub fn add_alias(&mut self, alias: &str, target: &str) -> Result<(), AgentError> {
+        if self.tools.contains_key(alias) {
+            return Err(AgentError::Config(format!(
+                "Alias '{}' would shadow the tool of that name",
+                alias
+            )));
+        }
+        let target = self
+            .resolve(target)
+            .ok_or_else(|| AgentError::ToolNotFound(target.to_string()))?
+            .to_string();
+        self.aliases.insert(alias.to_string(), target);
+        Ok(())
+    }
+
+    /// Aliases and the tool name each stands for.
+    pub fn aliases(&self) -> BTreeMap<&str, &str> {
+        self.aliases
+            .iter()
+            .map(|(alias, target)| (alias.as_str(), target.as_str()))
+            .collect()
+    }
+
+    fn disabled_ids(&self) -> std::sync::RwLockReadGuard<'_, HashSet<String>> {
+        self.disabled.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether the tool is registered and enabled.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.id(name)
+            .is_some_and(|id| !self.disabled_ids().contains(id))
+            && (!self.read_only || self.get(name).is_some_and(|t| t.read_only()))
+    }
+
+    /// Whether only read-only tools are enabled.
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Enable or disable a tool by name, ID or alias, returning its ID.
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> Result<String, AgentError> {
+        let id = self
+            .id(name)
+            .ok_or_else(|| AgentError::ToolNotFound(name.to_string()))?
+            .to_string();
+        let mut disabled = self.disabled.write().unwrap_or_else(|e| e.into_inner());
+        if enabled {
+            disabled.remove(&id);
+        } else {
+            disabled.insert(id.clone());
+        }
+        Ok(id)
+    }
+
+    /// Apply `[tools] read_only`, `[tools] disabled`, `[tools] aliases` and
+    /// `[tools.quotas]`, whose daily counts are kept in the data directory.
+    /// Entries naming no registered tool are logged and skipped.
+    pub fn configure(&mut self, config: &ToolsConfig) {
+        self.read_only = config.read_only;
+        for name in &config.disabled {
+            match self.set_enabled(name, false) {
+                Ok(_) => tracing::debug!("Disabled tool: {}", name),
+                Err(_) => tracing::warn!("Cannot disable unknown tool: {}", name),
+            }
+        }
+        for (alias, target) in &config.aliases {
+            if let Err(e) = self.add_alias(alias, target) {
+                tracing::warn!("Ignoring tool alias {}: {}", alias, e);
+            }
+        }
+        let mut limits = HashMap::new();
+        for (name, quota) in &config.quotas {
+            match self.resolve(name) {
+                Some(tool) => {
+                    limits.insert(tool.to_string(), *quota);
+                }
+                None => tracing::warn!("Ignoring quota for unknown tool: {}", name),
+            }
+        }
+        self.quotas = ToolQuotas::new(limits)
+            .with_state_file(AppConfig::data_dir().join(tool_quota::STATE_FILE));
+    }
+
+    /// Call limits by tool name, with today's counts.
+    pub fn quotas(&self) -> &ToolQuotas {
+        &self.quotas
     }
 
     /// List all registered tool names.
@@ -59,18 +261,22 @@ impl ToolRegistry {
         self.tools.keys().map(|s| s.as_str()).collect()
     }
 
-    /// Get the tool schemas for all registered tools, suitable for sending to the model.
-    /// Optionally filtered by an allowlist and denylist.
+    /// Get the tool schemas for all enabled tools, suitable for sending to the model.
+    /// Optionally filtered by an allowlist and denylist, whose entries may
+    /// be names, IDs or aliases.
     pub fn schemas(&self, allowlist: Option<&[String]>, denylist: &[String]) -> Vec<ToolSchema> {
+        let listed = |list: &[String], name: &str| {
+            list.iter().any(|entry| self.resolve(entry) == Some(name))
+        };
         self.tools
             .values()
             .filter(|t| {
-                let name = t.name().to_string();
-                if denylist.contains(&name) {
+                let name = t.name();
+                if !self.is_enabled(name) || listed(denylist, name) {
                     return false;
                 }
                 match allowlist {
-                    Some(allow) => allow.contains(&name),
+                    Some(allow) => listed(allow, name),
                     None => true,
                 }
             })
@@ -84,48 +290,66 @@ impl ToolRegistry {
 
     /// Execute a tool by name with the given arguments.
     ///
-    /// Tool output is truncated to `MAX_TOOL_OUTPUT_BYTES` to prevent a single
-    /// misbehaving tool from blowing up the context window or API costs.
+    /// Output longer than one page
+    /// ([`RESULT_PAGE_BYTES`](crate::result_cache::RESULT_PAGE_BYTES)) is cut
+    /// to its first page; the full output stays in the
+    /// [`result_cache`](Self::result_cache) for the `result_page` tool.
     pub async fn execute(&self, tool_name: &str, tool_call_id: &str, args: Value) -> ToolOutput {
-        let mut output = match self.tools.get(tool_name) {
-            Some(tool) => match tool.execute(args).await {
-                Ok(content) => ToolOutput {
+        let start = std::time::Instant::now();
+        let mut output = match self.get(tool_name) {
+            Some(_) if !self.is_enabled(tool_name) => ToolOutput {
+                tool_call_id: tool_call_id.to_string(),
+                content: format!("Tool disabled: {}", tool_name),
+                is_error: true,
+                duration_ms: None,
+                images: Vec::new(),
+            },
+            Some(tool) => match tool.execute_with_images(args).await {
+                Ok((content, images)) => ToolOutput {
                     tool_call_id: tool_call_id.to_string(),
                     content,
                     is_error: false,
+                    duration_ms: Some(start.elapsed().as_millis() as u64),
+                    images,
                 },
                 Err(e) => ToolOutput {
                     tool_call_id: tool_call_id.to_string(),
                     content: format!("Error: {}", e),
                     is_error: true,
+                    duration_ms: Some(start.elapsed().as_millis() as u64),
+                    images: Vec::new(),
                 },
             },
             None => ToolOutput {
                 tool_call_id: tool_call_id.to_string(),
                 content: format!("Tool not found: {}", tool_name),
                 is_error: true,
+                duration_ms: None,
+                images: Vec::new(),
             },
         };
 
-        // Safety net: truncate oversized output to protect the context window.
-        if output.content.len() > MAX_TOOL_OUTPUT_BYTES {
-            let truncation_msg = format!(
-                "\n\n[Output truncated: {} bytes total, showing first {}]",
-                output.content.len(),
-                MAX_TOOL_OUTPUT_BYTES
-            );
-            // Find a valid UTF-8 boundary at or before the limit.
-            let mut boundary = MAX_TOOL_OUTPUT_BYTES;
-            while !output.content.is_char_boundary(boundary) && boundary > 0 {
-                boundary -= 1;
-            }
-            output.content.truncate(boundary);
-            output.content.push_str(&truncation_msg);
+        // Oversized output would blow the context window: send the first
+        // page and keep the rest for `result_page`.
+        if let Some(first_page) = self.results.truncate(&output.content) {
+            output.content = first_page;
         }
 
         output
     }
 
+    /// Files a call to `tool_name` with `args` would modify.
+    pub fn affected_paths(&self, tool_name: &str, args: &Value) -> Vec<PathBuf> {
+        self.get(tool_name)
+            .map(|t| t.affected_paths(args))
+            .unwrap_or_default()
+    }
+
+    /// Full outputs of truncated tool results, shared with `result_page`.
+    pub fn result_cache(&self) -> ResultCache {
+        self.results.clone()
+    }
+
     /// Number of registered tools.
     pub fn len(&self) -> usize {
         self.tools.len()
@@ -158,3 +382,122 @@ pub fn schemas_to_openai_tools(schemas: &[ToolSchema]) -> Vec<Value> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ToolQuota;
+
+    struct Echo(&'static str);
+
+    #[async_trait]
+    impl Tool for Echo {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn description(&self) -> &str {
+            "echo"
+        }
+
+        fn parameters_schema(&self) -> Value {
+            serde_json::json!({ "type": "object" })
+        }
+
+        async fn execute(&self, _args: Value) -> Result<String, AgentError> {
+            Ok(self.0.to_string())
+        }
+
+        fn read_only(&self) -> bool {
+            self.0 != "file_write"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ids_and_aliases_resolve() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(Echo("file_read")));
+        registry
+            .register_plugin("jira", Arc::new(Echo("create_issue")))
+            .unwrap();
+        registry.add_alias("read", "builtin.file_read").unwrap();
+
+        assert_eq!(registry.id("file_read"), Some("builtin.file_read"));
+        assert_eq!(registry.id("create_issue"), Some("plugin.jira.create_issue"));
+        assert_eq!(registry.resolve("plugin.jira.create_issue"), Some("create_issue"));
+        assert_eq!(registry.resolve("read"), Some("file_read"));
+        assert!(registry.get("builtin.nope").is_none());
+
+        let output = registry.execute("read", "c1", Value::Null).await;
+        assert_eq!(output.content, "file_read");
+        let allow = vec!["plugin.jira.create_issue".to_string()];
+        let schemas = registry.schemas(Some(&allow), &[]);
+        assert_eq!(schemas.len(), 1);
+        assert_eq!(schemas[0].name, "create_issue");
+        assert!(registry.add_alias("file_read", "create_issue").is_err());
+    }
+
+    #[test]
+    fn test_plugin_conflicts_until_builtin_disabled() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(Echo("web_fetch")));
+        let err = registry
+            .register_plugin("crawler", Arc::new(Echo("web_fetch")))
+            .unwrap_err();
+        assert!(err.to_string().contains("builtin.web_fetch"));
+
+        registry.configure(&ToolsConfig {
+            disabled: vec!["builtin.web_fetch".into()],
+            aliases: HashMap::from([("fetch".into(), "nope".into())]),
+            quotas: HashMap::from([(
+                "builtin.web_fetch".into(),
+                ToolQuota { per_day: Some(100), ..Default::default() },
+            )]),
+            ..Default::default()
+        });
+        assert!(!registry.is_enabled("web_fetch"));
+        assert_eq!(registry.quotas().limit("web_fetch").unwrap().per_day, Some(100));
+        assert!(registry.aliases().is_empty(), "alias to a missing tool is skipped");
+        registry
+            .register_plugin("crawler", Arc::new(Echo("web_fetch")))
+            .unwrap();
+        assert_eq!(registry.id("web_fetch"), Some("plugin.crawler.web_fetch"));
+        assert!(registry.is_enabled("web_fetch"));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_tools_are_hidden_and_refused() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(Echo("calc")));
+        registry.register(Arc::new(Echo("web_fetch")));
+        assert_eq!(registry.set_enabled("web_fetch", false).unwrap(), "builtin.web_fetch");
+
+        let names: Vec<String> = registry.schemas(None, &[]).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["calc"]);
+        let output = registry.execute("web_fetch", "c1", Value::Null).await;
+        assert!(output.is_error);
+        assert_eq!(output.content, "Tool disabled: web_fetch");
+
+        registry.set_enabled("builtin.web_fetch", true).unwrap();
+        assert_eq!(registry.schemas(None, &[]).len(), 2);
+        assert!(registry.set_enabled("nope", false).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_mode_keeps_only_read_only_tools() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(Echo("file_read")));
+        registry.register(Arc::new(Echo("file_write")));
+        registry.configure(&ToolsConfig {
+            read_only: true,
+            ..Default::default()
+        });
+        assert!(registry.read_only());
+
+        let names: Vec<String> = registry.schemas(None, &[]).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["file_read"]);
+        registry.set_enabled("file_write", true).unwrap();
+        let output = registry.execute("file_write", "c1", Value::Null).await;
+        assert_eq!(output.content, "Tool disabled: file_write");
+    }
+}
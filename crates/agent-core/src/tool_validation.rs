@@ -0,0 +1,256 @@
+//! Checks tool call arguments against the tool's `parameters_schema` before
+//! execution, so a malformed call gets a precise error the model can act on
+//! instead of whatever the tool happens to fail with.
+//!
+//! Only the JSON Schema subset the built-in tools use is understood:
+//! `type` (a name or a list), `properties`, `required`,
+//! `additionalProperties: false`, `enum`, `items`, `minimum`, `maximum`,
+//! `minLength`, `maxLength`, `minItems` and `maxItems`. Anything else is
+//! accepted. The server checks request bodies with the same rules.
+
+use serde_json::Value;
+
+/// Every way `args` violates `schema`, as `path: problem` lines. Empty when
+/// the arguments are valid.
+pub fn validate_arguments(schema: &Value, args: &Value) -> Vec<String> {
+    validate_value(schema, args, "arguments")
+}
+
+/// Every way `value` violates `schema`, with paths starting at `root`.
+pub fn validate_value(schema: &Value, value: &Value, root: &str) -> Vec<String> {
+    let mut issues = Vec::new();
+    check(schema, value, root, &mut issues);
+    issues
+}
+
+fn check(schema: &Value, value: &Value, path: &str, issues: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type") {
+        let names: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !names.is_empty() && !names.iter().any(|t| has_type(value, t)) {
+            issues.push(format!(
+                "{}: expected {}, got {}",
+                path,
+                names.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            let options: Vec<String> = options.iter().map(Value::to_string).collect();
+            issues.push(format!("{}: must be one of {}", path, options.join(", ")));
+        }
+    }
+
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+            if n < min {
+                issues.push(format!("{}: must be at least {}", path, min));
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+            if n > max {
+                issues.push(format!("{}: must be at most {}", path, max));
+            }
+        }
+    }
+
+    if let Value::String(s) = value {
+        let len = s.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if len < min {
+                issues.push(format!("{}: must be at least {} characters long", path, min));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if len > max {
+                issues.push(format!("{}: must be at most {} characters long", path, max));
+            }
+        }
+    }
+
+    if let Value::Array(items) = value {
+        let len = items.len() as u64;
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if len < min {
+                issues.push(format!("{}: must have at least {} items", path, min));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if len > max {
+                issues.push(format!("{}: must have at most {} items", path, max));
+            }
+        }
+    }
+
+    if let Value::Object(map) = value {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !map.contains_key(key) {
+                    issues.push(format!("{}: missing required property `{}`", path, key));
+                }
+            }
+        }
+        for (key, item) in map {
+            match properties.and_then(|p| p.get(key)) {
+                Some(prop) => check(prop, item, &format!("{}.{}", path, key), issues),
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    let known: Vec<&str> = properties
+                        .map(|p| p.keys().map(String::as_str).collect())
+                        .unwrap_or_default();
+                    issues.push(format!(
+                        "{}: unknown property `{}` (expected one of: {})",
+                        path,
+                        key,
+                        known.join(", ")
+                    ));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            check(item_schema, item, &format!("{}[{}]", path, i), issues);
+        }
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        // Models often write `5.0` for an integer.
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Tool result telling the model what was wrong with its call and what
+/// the tool expects, so it can try again.
+pub fn retry_feedback(
+    tool_name: &str,
+    issues: &[String],
+    schema: &Value,
+    attempt: usize,
+    max_attempts: usize,
+) -> String {
+    let mut text = format!(
+        "Invalid arguments for `{}` (attempt {} of {}):\n",
+        tool_name, attempt, max_attempts
+    );
+    for issue in issues {
+        text.push_str(&format!("- {}\n", issue));
+    }
+    text.push_str(&format!(
+        "Expected parameters schema:\n{}\nCall `{}` again with arguments that match the schema.",
+        serde_json::to_string_pretty(schema).unwrap_or_default(),
+        tool_name
+    ));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string" },
+                "mode": { "type": "string", "enum": ["read", "write"] },
+                "limit": { "type": "integer", "minimum": 1 },
+                "tags": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["path"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn test_valid_arguments() {
+        let args = json!({ "path": "a.rs", "mode": "read", "limit": 5.0, "tags": ["x"] });
+        assert!(validate_arguments(&schema(), &args).is_empty());
+        // Schemas without constraints accept anything.
+        assert!(validate_arguments(&json!({}), &json!({ "x": 1 })).is_empty());
+    }
+
+    #[test]
+    fn test_reports_every_issue() {
+        let args = json!({ "mode": "append", "limit": 0, "tags": [1], "pth": "a.rs" });
+        let issues = validate_arguments(&schema(), &args);
+        assert!(issues.contains(&"arguments: missing required property `path`".to_string()));
+        assert!(issues
+            .iter()
+            .any(|i| i.starts_with("arguments.mode: must be one of")));
+        assert!(issues.contains(&"arguments.limit: must be at least 1".to_string()));
+        assert!(issues.contains(&"arguments.tags[0]: expected string, got integer".to_string()));
+        assert!(issues.iter().any(|i| i.contains("unknown property `pth`")));
+        assert_eq!(issues.len(), 5);
+
+        let issues = validate_arguments(&schema(), &json!("a.rs"));
+        assert_eq!(issues, vec!["arguments: expected object, got string"]);
+    }
+
+    #[test]
+    fn test_length_limits() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 1, "maxLength": 3 },
+                "tags": { "type": "array", "minItems": 1, "maxItems": 2 }
+            }
+        });
+        assert!(validate_value(&schema, &json!({ "name": "äbc", "tags": [1, 2] }), "body").is_empty());
+        let issues = validate_value(&schema, &json!({ "name": "", "tags": [] }), "body");
+        assert_eq!(
+            issues,
+            vec![
+                "body.name: must be at least 1 characters long",
+                "body.tags: must have at least 1 items",
+            ]
+        );
+        let issues = validate_value(&schema, &json!({ "name": "abcd", "tags": [1, 2, 3] }), "body");
+        assert_eq!(issues.len(), 2);
+    }
+
+    #[test]
+    fn test_retry_feedback() {
+        let text = retry_feedback(
+            "file_read",
+            &["arguments: missing required property `path`".into()],
+            &schema(),
+            1,
+            3,
+        );
+        assert!(text.starts_with("Invalid arguments for `file_read` (attempt 1 of 3):"));
+        assert!(text.contains("- arguments: missing required property `path`"));
+        assert!(text.contains("\"required\""));
+    }
+}
@@ -14,7 +14,13 @@ pub struct Message {
     /// Tool calls requested by the assistant.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// Images attached to a user message or returned by a tool.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImagePart>,
     pub timestamp: DateTime<Utc>,
+    /// The user's rating of an assistant message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback: Option<Feedback>,
 }
 
 impl Message {
@@ -25,7 +31,9 @@ impl Message {
             content: content.into(),
             tool_call_id: None,
             tool_calls: None,
+            images: Vec::new(),
             timestamp: Utc::now(),
+            feedback: None,
         }
     }
 
@@ -36,7 +44,9 @@ impl Message {
             content: content.into(),
             tool_call_id: None,
             tool_calls: None,
+            images: Vec::new(),
             timestamp: Utc::now(),
+            feedback: None,
         }
     }
 
@@ -50,7 +60,9 @@ impl Message {
             content: content.into(),
             tool_call_id: None,
             tool_calls: Some(tool_calls),
+            images: Vec::new(),
             timestamp: Utc::now(),
+            feedback: None,
         }
     }
 
@@ -61,7 +73,9 @@ impl Message {
             content: content.into(),
             tool_call_id: None,
             tool_calls: None,
+            images: Vec::new(),
             timestamp: Utc::now(),
+            feedback: None,
         }
     }
 
@@ -72,7 +86,83 @@ impl Message {
             content: content.into(),
             tool_call_id: Some(tool_call_id.into()),
             tool_calls: None,
+            images: Vec::new(),
             timestamp: Utc::now(),
+            feedback: None,
+        }
+    }
+
+    /// Attach images to this message.
+    pub fn with_images(mut self, images: Vec<ImagePart>) -> Self {
+        self.images = images;
+        self
+    }
+}
+
+/// Thumbs up or down on an assistant message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Feedback {
+    pub rating: Rating,
+    /// What was good or wrong about the answer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Rating {
+    Up,
+    Down,
+}
+
+impl std::str::FromStr for Rating {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "up" | "+" | "+1" | "good" => Ok(Self::Up),
+            "down" | "-" | "-1" | "bad" => Ok(Self::Down),
+            other => Err(format!("Unknown rating '{}' (expected up or down)", other)),
+        }
+    }
+}
+
+/// An inline image, base64-encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImagePart {
+    /// MIME type, e.g. `image/png`.
+    pub media_type: String,
+    /// Base64-encoded image bytes (standard alphabet, padded).
+    pub data: String,
+}
+
+impl ImagePart {
+    pub fn from_bytes(media_type: impl Into<String>, bytes: &[u8]) -> Self {
+        use base64::Engine;
+        Self {
+            media_type: media_type.into(),
+            data: base64::engine::general_purpose::STANDARD.encode(bytes),
+        }
+    }
+
+    /// `data:` URL, the form OpenAI-compatible APIs accept in `image_url`.
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.data)
+    }
+
+    /// Image MIME type for a file path, judged by extension.
+    pub fn media_type_for_path(path: &std::path::Path) -> Option<&'static str> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "png" => Some("image/png"),
+            "jpg" | "jpeg" => Some("image/jpeg"),
+            "gif" => Some("image/gif"),
+            "webp" => Some("image/webp"),
+            _ => None,
         }
     }
 }
@@ -104,10 +194,46 @@ pub struct ToolSchema {
 
 /// Output from a tool execution.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ToolOutput {
     pub tool_call_id: String,
     pub content: String,
     pub is_error: bool,
+    /// Wall-clock execution time, when the tool actually ran.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Images produced by the tool (e.g. `file_read` on a PNG).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImagePart>,
+}
+
+/// Tokens used by model calls, and what they cost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// In US dollars, at the provider's configured prices (0 when unset).
+    pub cost_usd: f64,
+    /// Some of the counts were estimated because the provider did not
+    /// report usage.
+    #[serde(default)]
+    pub estimated: bool,
+}
+
+impl TokenUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost_usd += other.cost_usd;
+        self.estimated |= other.estimated;
+    }
 }
 
 /// Streaming event emitted during agent execution.
@@ -116,11 +242,17 @@ pub enum AgentEvent {
     /// A chunk of the assistant's response content.
     ContentChunk(String),
     /// The assistant is calling a tool.
-    ToolCallStart { id: String, name: String },
+    ToolCallStart {
+        id: String,
+        name: String,
+        arguments: String,
+    },
     /// Tool call arguments (may arrive in chunks).
     ToolCallArgsChunk { id: String, chunk: String },
     /// Tool execution completed.
     ToolResult(ToolOutput),
+    /// Token usage of one model call.
+    Usage(TokenUsage),
     /// The full assistant message is complete.
     Done(Message),
     /// An error occurred.
@@ -0,0 +1,185 @@
+//! Named workspace roots (`[sandbox.workspaces]`) and the set a session has
+//! active.
+//!
+//! A session lists its active workspaces in [`Session::workspaces`]; the
+//! first is primary: relative paths, commands and project detection start
+//! there. Sessions that select none use `[sandbox] workspace_root`, which is
+//! also listed as [`DEFAULT_WORKSPACE`].
+//!
+//! Tools are built once, so the active roots reach them through a task
+//! local: callers run a turn inside [`scope`], and tools ask
+//! [`allowed_roots`] and [`primary_root`] instead of reading their
+//! configured root directly.
+
+use crate::config::SandboxConfig;
+use crate::error::AgentError;
+use crate::session::Session;
+use serde::Serialize;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+/// Name under which `[sandbox] workspace_root` is listed.
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+/// A configured workspace root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Workspace {
+    pub name: String,
+    pub root: PathBuf,
+}
+
+tokio::task_local! {
+    static ACTIVE: Vec<PathBuf>;
+}
+
+/// Every configured workspace: `default` for `workspace_root` (unless a
+/// named workspace takes the name), then `[sandbox.workspaces]` by name.
+pub fn configured(config: &SandboxConfig) -> Vec<Workspace> {
+    let default = config
+        .workspace_root
+        .as_ref()
+        .filter(|_| !config.workspaces.contains_key(DEFAULT_WORKSPACE))
+        .map(|root| Workspace {
+            name: DEFAULT_WORKSPACE.to_string(),
+            root: root.clone(),
+        });
+    default
+        .into_iter()
+        .chain(config.workspaces.iter().map(|(name, root)| Workspace {
+            name: name.clone(),
+            root: root.clone(),
+        }))
+        .collect()
+}
+
+/// The workspaces named in `names`, in order, or an error naming the first
+/// that is not configured or not a directory.
+pub fn resolve(config: &SandboxConfig, names: &[String]) -> Result<Vec<Workspace>, AgentError> {
+    let all = configured(config);
+    names
+        .iter()
+        .map(|name| {
+            let workspace = all.iter().find(|w| &w.name == name).ok_or_else(|| {
+                let known: Vec<&str> = all.iter().map(|w| w.name.as_str()).collect();
+                AgentError::Config(format!(
+                    "Unknown workspace '{}' (configured: {})",
+                    name,
+                    if known.is_empty() {
+                        "none".to_string()
+                    } else {
+                        known.join(", ")
+                    }
+                ))
+            })?;
+            if !workspace.root.is_dir() {
+                return Err(AgentError::Config(format!(
+                    "Workspace '{}' root {} is not a directory",
+                    name,
+                    workspace.root.display()
+                )));
+            }
+            Ok(workspace.clone())
+        })
+        .collect()
+}
+
+/// Workspaces active for a session that `selected` them: those, or
+/// `default` when it selected none. Names no longer configured are logged
+/// and skipped.
+pub fn active(config: &SandboxConfig, selected: &[String]) -> Vec<Workspace> {
+    let all = configured(config);
+    if selected.is_empty() {
+        return all
+            .into_iter()
+            .filter(|w| w.name == DEFAULT_WORKSPACE)
+            .collect();
+    }
+    selected
+        .iter()
+        .filter_map(|name| {
+            let found = all.iter().find(|w| &w.name == name).cloned();
+            if found.is_none() {
+                tracing::warn!("Ignoring unknown workspace '{}'", name);
+            }
+            found
+        })
+        .collect()
+}
+
+/// Roots of the workspaces active in `session`, primary first, for [`scope`].
+pub fn roots_for(config: &SandboxConfig, session: &Session) -> Vec<PathBuf> {
+    active(config, &session.workspaces)
+        .into_iter()
+        .map(|w| w.root)
+        .collect()
+}
+
+/// Run `f` with `roots` as the active workspace roots, primary first. An
+/// empty list leaves tools on their configured root.
+pub async fn scope<F: Future>(roots: Vec<PathBuf>, f: F) -> F::Output {
+    ACTIVE.scope(roots, f).await
+}
+
+/// Roots active on this task, primary first; empty outside [`scope`].
+pub fn active_roots() -> Vec<PathBuf> {
+    ACTIVE.try_with(Clone::clone).unwrap_or_default()
+}
+
+/// Roots paths must fall under: the active ones, or `configured`.
+pub fn allowed_roots(configured: &Option<PathBuf>) -> Vec<PathBuf> {
+    let active = active_roots();
+    if active.is_empty() {
+        configured.iter().cloned().collect()
+    } else {
+        active
+    }
+}
+
+/// Where relative paths and commands start: the primary active root, or
+/// `configured`.
+pub fn primary_root(configured: &Option<PathBuf>) -> Option<PathBuf> {
+    allowed_roots(configured).into_iter().next()
+}
+
+/// Whether `path` (already canonical) lies under one of `roots`.
+pub fn contains(roots: &[PathBuf], path: &Path) -> bool {
+    roots.iter().any(|root| {
+        root.canonicalize()
+            .is_ok_and(|canon_root| path.starts_with(canon_root))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_session_selection_and_scope() {
+        let a = tempfile::tempdir().unwrap();
+        let b = tempfile::tempdir().unwrap();
+        let mut config = SandboxConfig {
+            workspace_root: Some(a.path().to_path_buf()),
+            ..Default::default()
+        };
+        config
+            .workspaces
+            .insert("repo-b".into(), b.path().to_path_buf());
+
+        let names: Vec<String> = configured(&config).into_iter().map(|w| w.name).collect();
+        assert_eq!(names, ["default", "repo-b"]);
+        assert!(resolve(&config, &["nope".into()]).is_err());
+
+        let mut session = Session::new("s");
+        assert_eq!(active(&config, &session.workspaces)[0].root, a.path());
+        session.workspaces = vec!["repo-b".into(), "default".into()];
+        let roots = roots_for(&config, &session);
+        assert_eq!(roots, [b.path(), a.path()]);
+
+        assert!(active_roots().is_empty());
+        let primary = scope(roots.clone(), async { primary_root(&None) }).await;
+        assert_eq!(primary.as_deref(), Some(b.path()));
+        let canon = a.path().canonicalize().unwrap().join("file");
+        assert!(scope(roots, async { contains(&allowed_roots(&None), &canon) }).await);
+        assert!(!contains(&[b.path().to_path_buf()], &canon));
+    }
+}
@@ -7,9 +7,14 @@
 //! For plugins that require async initialization (database connections,
 //! HTTP binds, etc.), implement `AsyncPlugin` instead of `Plugin`.
 
+pub mod marketplace;
+mod permissions;
+
+pub use permissions::PluginPermissions;
+
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
 // ── Category & Status ──────────────────────────────────────────────────
@@ -48,6 +53,9 @@ pub struct PluginInfo {
     pub category: PluginCategory,
     pub status: PluginStatus,
     pub description: String,
+    /// Resources the plugin has been granted.
+    #[serde(default)]
+    pub permissions: PluginPermissions,
 }
 
 /// Trait that all plugins must implement.
@@ -106,6 +114,24 @@ impl PluginKey {
     }
 }
 
+impl PluginKey {
+    /// `<category>:<name>`, the form [`PluginKey::parse`] reads.
+    pub fn id(&self) -> String {
+        let category = serde_json::to_value(&self.category)
+            .ok()
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_default();
+        format!("{}:{}", category, self.name)
+    }
+
+    /// Parse `<category>:<name>`, e.g. `skill:jira-sync`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (category, name) = s.split_once(':')?;
+        let category = serde_json::from_value(serde_json::Value::String(category.into())).ok()?;
+        (!name.is_empty()).then(|| Self::new(category, name))
+    }
+}
+
 impl fmt::Display for PluginKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}/{}", self.category, self.name)
@@ -114,10 +140,24 @@ impl fmt::Display for PluginKey {
 
 // ── Registry ───────────────────────────────────────────────────────────
 
+/// A plugin's metadata and current status, for listings.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PluginSummary {
+    /// `<category>:<name>`.
+    pub key: String,
+    pub name: String,
+    pub version: String,
+    pub category: PluginCategory,
+    pub status: PluginStatus,
+    pub description: String,
+}
+
 /// Central registry for all plugins.
 pub struct PluginRegistry {
     plugins: HashMap<PluginKey, Box<dyn Plugin>>,
     async_plugins: HashMap<PluginKey, Box<dyn AsyncPlugin>>,
+    /// Plugins started through the registry and not stopped since.
+    running: HashSet<PluginKey>,
 }
 
 impl PluginRegistry {
@@ -126,6 +166,7 @@ impl PluginRegistry {
         Self {
             plugins: HashMap::new(),
             async_plugins: HashMap::new(),
+            running: HashSet::new(),
         }
     }
 
@@ -156,6 +197,7 @@ impl PluginRegistry {
     /// Unregister a plugin by key. Returns an error if not found.
     pub fn unregister(&mut self, key: &PluginKey) -> Result<(), String> {
         if self.plugins.remove(key).is_some() || self.async_plugins.remove(key).is_some() {
+            self.running.remove(key);
             tracing::info!("Unregistered plugin: {key}");
             Ok(())
         } else {
@@ -171,6 +213,23 @@ impl PluginRegistry {
             .or_else(|| self.async_plugins.get(key).map(|p| p.info()))
     }
 
+    /// Resolve `<category>:<name>`, or a bare name that only one registered
+    /// plugin has.
+    pub fn resolve_key(&self, s: &str) -> Option<PluginKey> {
+        if let Some(key) = PluginKey::parse(s) {
+            return self.get_info(&key).is_some().then_some(key);
+        }
+        let mut matches = self
+            .plugins
+            .keys()
+            .chain(self.async_plugins.keys())
+            .filter(|k| k.name == s);
+        match (matches.next(), matches.next()) {
+            (Some(key), None) => Some(key.clone()),
+            _ => None,
+        }
+    }
+
     /// List info for all registered plugins.
     pub fn list(&self) -> Vec<PluginInfo> {
         self.plugins
@@ -195,6 +254,87 @@ impl PluginRegistry {
             .collect()
     }
 
+    /// Status of one plugin: its health check, reported as `Running` while
+    /// it is started and healthy.
+    pub fn status(&self, key: &PluginKey) -> Option<PluginStatus> {
+        let health = self
+            .plugins
+            .get(key)
+            .map(|p| p.health_check())
+            .or_else(|| self.async_plugins.get(key).map(|p| p.health_check()))?;
+        Some(match health {
+            PluginStatus::Available if self.running.contains(key) => PluginStatus::Running,
+            health => health,
+        })
+    }
+
+    /// Every plugin with its [`status`](Self::status), ordered by key.
+    pub fn summaries(&self) -> Vec<PluginSummary> {
+        let mut summaries: Vec<PluginSummary> = self
+            .plugins
+            .iter()
+            .map(|(key, p)| (key, p.info()))
+            .chain(self.async_plugins.iter().map(|(key, p)| (key, p.info())))
+            .map(|(key, info)| PluginSummary {
+                key: key.id(),
+                status: self.status(key).unwrap_or(info.status),
+                name: info.name,
+                version: info.version,
+                category: info.category,
+                description: info.description,
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.key.cmp(&b.key));
+        summaries
+    }
+
+    /// Start one plugin. Fails if it is unknown or already running.
+    pub async fn start(&mut self, key: &PluginKey) -> Result<(), String> {
+        if self.running.contains(key) {
+            return Err(format!("plugin already running: {key}"));
+        }
+        let result = if let Some(plugin) = self.plugins.get_mut(key) {
+            plugin.start()
+        } else if let Some(plugin) = self.async_plugins.get_mut(key) {
+            plugin.start().await
+        } else {
+            return Err(format!("plugin not found: {key}"));
+        };
+        match &result {
+            Ok(()) => {
+                tracing::info!("Started plugin: {key}");
+                self.running.insert(key.clone());
+            }
+            Err(e) => tracing::warn!("Plugin {key} failed to start: {e}"),
+        }
+        result
+    }
+
+    /// Stop one plugin. Fails if it is unknown or not running.
+    pub async fn stop(&mut self, key: &PluginKey) -> Result<(), String> {
+        if !self.plugins.contains_key(key) && !self.async_plugins.contains_key(key) {
+            return Err(format!("plugin not found: {key}"));
+        }
+        if !self.running.contains(key) {
+            return Err(format!("plugin not running: {key}"));
+        }
+        let result = match self.plugins.get_mut(key) {
+            Some(plugin) => plugin.stop(),
+            None => match self.async_plugins.get_mut(key) {
+                Some(plugin) => plugin.stop().await,
+                None => Ok(()),
+            },
+        };
+        match &result {
+            Ok(()) => {
+                tracing::info!("Stopped plugin: {key}");
+                self.running.remove(key);
+            }
+            Err(e) => tracing::warn!("Plugin {key} failed to stop: {e}"),
+        }
+        result
+    }
+
     /// Run health checks on all plugins and return their statuses.
     pub fn health_check_all(&self) -> Vec<(PluginKey, PluginStatus)> {
         self.plugins
@@ -215,8 +355,11 @@ impl PluginRegistry {
         for key in keys {
             if let Some(plugin) = self.plugins.get_mut(&key) {
                 let result = plugin.start();
-                if let Err(ref e) = result {
-                    tracing::warn!("Plugin {key} failed to start: {e}");
+                match result {
+                    Ok(()) => {
+                        self.running.insert(key.clone());
+                    }
+                    Err(ref e) => tracing::warn!("Plugin {key} failed to start: {e}"),
                 }
                 results.push((key, result));
             }
@@ -233,8 +376,11 @@ impl PluginRegistry {
         for key in sync_keys {
             if let Some(plugin) = self.plugins.get_mut(&key) {
                 let result = plugin.start();
-                if let Err(ref e) = result {
-                    tracing::warn!("Plugin {key} failed to start: {e}");
+                match result {
+                    Ok(()) => {
+                        self.running.insert(key.clone());
+                    }
+                    Err(ref e) => tracing::warn!("Plugin {key} failed to start: {e}"),
                 }
                 results.push((key, result));
             }
@@ -245,8 +391,11 @@ impl PluginRegistry {
         for key in async_keys {
             if let Some(plugin) = self.async_plugins.get_mut(&key) {
                 let result = plugin.start().await;
-                if let Err(ref e) = result {
-                    tracing::warn!("Async plugin {key} failed to start: {e}");
+                match result {
+                    Ok(()) => {
+                        self.running.insert(key.clone());
+                    }
+                    Err(ref e) => tracing::warn!("Async plugin {key} failed to start: {e}"),
                 }
                 results.push((key, result));
             }
@@ -262,8 +411,11 @@ impl PluginRegistry {
         for key in keys {
             if let Some(plugin) = self.plugins.get_mut(&key) {
                 let result = plugin.stop();
-                if let Err(ref e) = result {
-                    tracing::warn!("Plugin {key} failed to stop: {e}");
+                match result {
+                    Ok(()) => {
+                        self.running.remove(&key);
+                    }
+                    Err(ref e) => tracing::warn!("Plugin {key} failed to stop: {e}"),
                 }
                 results.push((key, result));
             }
@@ -279,8 +431,11 @@ impl PluginRegistry {
         for key in sync_keys {
             if let Some(plugin) = self.plugins.get_mut(&key) {
                 let result = plugin.stop();
-                if let Err(ref e) = result {
-                    tracing::warn!("Plugin {key} failed to stop: {e}");
+                match result {
+                    Ok(()) => {
+                        self.running.remove(&key);
+                    }
+                    Err(ref e) => tracing::warn!("Plugin {key} failed to stop: {e}"),
                 }
                 results.push((key, result));
             }
@@ -290,8 +445,11 @@ impl PluginRegistry {
         for key in async_keys {
             if let Some(plugin) = self.async_plugins.get_mut(&key) {
                 let result = plugin.stop().await;
-                if let Err(ref e) = result {
-                    tracing::warn!("Async plugin {key} failed to stop: {e}");
+                match result {
+                    Ok(()) => {
+                        self.running.remove(&key);
+                    }
+                    Err(ref e) => tracing::warn!("Async plugin {key} failed to stop: {e}"),
                 }
                 results.push((key, result));
             }
@@ -356,6 +514,7 @@ mod tests {
                     PluginStatus::Available
                 },
                 description: format!("Mock {} plugin", self.name),
+                permissions: PluginPermissions::default(),
             }
         }
 
@@ -386,6 +545,7 @@ mod tests {
                 category: PluginCategory::Tool,
                 status: PluginStatus::Error,
                 description: "Always fails".to_string(),
+                permissions: PluginPermissions::default(),
             }
         }
 
@@ -631,10 +791,66 @@ mod tests {
             category: PluginCategory::Skill,
             status: PluginStatus::Available,
             description: "A test plugin".to_string(),
+            permissions: PluginPermissions::default(),
         };
         let json = serde_json::to_string(&info).unwrap();
         let parsed: PluginInfo = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.name, "test");
         assert_eq!(parsed.category, PluginCategory::Skill);
     }
+
+    #[test]
+    fn test_resolve_key() {
+        let mut registry = PluginRegistry::new();
+        registry
+            .register(Box::new(MockPlugin::new("jira", PluginCategory::Tool)))
+            .unwrap();
+        registry
+            .register(Box::new(MockPlugin::new("notes", PluginCategory::Tool)))
+            .unwrap();
+        registry
+            .register(Box::new(MockPlugin::new("notes", PluginCategory::Skill)))
+            .unwrap();
+
+        let jira = PluginKey::new(PluginCategory::Tool, "jira");
+        assert_eq!(registry.resolve_key("jira"), Some(jira.clone()));
+        assert_eq!(registry.resolve_key("tool:jira"), Some(jira));
+        assert_eq!(registry.resolve_key("skill:jira"), None);
+        // Ambiguous without a category.
+        assert_eq!(registry.resolve_key("notes"), None);
+        assert!(registry.resolve_key("skill:notes").is_some());
+        assert_eq!(PluginKey::parse("bogus:jira"), None);
+    }
+
+    #[tokio::test]
+    async fn test_start_stop_one_and_summaries() {
+        let mut registry = PluginRegistry::new();
+        registry
+            .register(Box::new(MockPlugin::new("jira", PluginCategory::Tool)))
+            .unwrap();
+        registry
+            .register(Box::new(
+                MockPlugin::new("broken", PluginCategory::Skill).with_health(PluginStatus::Error),
+            ))
+            .unwrap();
+        let jira = PluginKey::new(PluginCategory::Tool, "jira");
+        assert_eq!(jira.id(), "tool:jira");
+
+        assert_eq!(registry.status(&jira), Some(PluginStatus::Available));
+        assert!(registry.stop(&jira).await.unwrap_err().contains("not running"));
+        registry.start(&jira).await.unwrap();
+        assert!(registry.start(&jira).await.unwrap_err().contains("already running"));
+        assert_eq!(registry.status(&jira), Some(PluginStatus::Running));
+
+        let summaries = registry.summaries();
+        let keys: Vec<&str> = summaries.iter().map(|s| s.key.as_str()).collect();
+        assert_eq!(keys, ["skill:broken", "tool:jira"]);
+        assert_eq!(summaries[0].status, PluginStatus::Error);
+        assert_eq!(summaries[1].status, PluginStatus::Running);
+
+        registry.stop(&jira).await.unwrap();
+        assert_eq!(registry.status(&jira), Some(PluginStatus::Available));
+        let missing = PluginKey::new(PluginCategory::Tool, "nope");
+        assert!(registry.start(&missing).await.unwrap_err().contains("not found"));
+    }
 }
@@ -0,0 +1,361 @@
+//! Remote plugin index and locally installed plugins.
+//!
+//! The index is a JSON document (`{"plugins": [...]}`) served over HTTPS
+//! listing each release of a WASM or skill plugin with its download URL and
+//! SHA-256 checksum. Installed plugins are recorded in `installed.json` in
+//! the plugins directory and registered with the [`PluginRegistry`] at
+//! startup.
+//!
+//! [`PluginRegistry`]: crate::PluginRegistry
+
+use crate::{Plugin, PluginCategory, PluginInfo, PluginPermissions, PluginStatus};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// File in the plugins directory listing what is installed.
+pub const INSTALLED_FILE: &str = "installed.json";
+
+/// What a plugin release ships.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginKind {
+    /// A WebAssembly module.
+    Wasm,
+    /// A `SKILL.md` file for the skills directory.
+    Skill,
+}
+
+impl PluginKind {
+    pub fn category(self) -> PluginCategory {
+        match self {
+            Self::Wasm => PluginCategory::Extension,
+            Self::Skill => PluginCategory::Skill,
+        }
+    }
+}
+
+/// One release listed in the index.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndexEntry {
+    pub name: String,
+    pub version: String,
+    pub kind: PluginKind,
+    #[serde(default)]
+    pub description: String,
+    /// Download URL of the artifact.
+    pub url: String,
+    /// Hex-encoded SHA-256 of the artifact.
+    pub sha256: String,
+    /// Resources the plugin asks for; granted by the user on install.
+    #[serde(default)]
+    pub permissions: PluginPermissions,
+}
+
+/// The remote plugin index.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginIndex {
+    pub plugins: Vec<IndexEntry>,
+}
+
+impl PluginIndex {
+    /// Latest release of every plugin whose name or description contains
+    /// `term` (case-insensitive), sorted by name. An empty term lists all.
+    pub fn search(&self, term: &str) -> Vec<&IndexEntry> {
+        let term = term.to_lowercase();
+        let mut matches: Vec<&IndexEntry> = Vec::new();
+        for entry in self.plugins.iter().filter(|e| {
+            e.name.to_lowercase().contains(&term) || e.description.to_lowercase().contains(&term)
+        }) {
+            match matches.iter_mut().find(|m| m.name == entry.name) {
+                Some(m) if compare_versions(&entry.version, &m.version) == Ordering::Greater => {
+                    *m = entry
+                }
+                Some(_) => {}
+                None => matches.push(entry),
+            }
+        }
+        matches.sort_by(|a, b| a.name.cmp(&b.name));
+        matches
+    }
+
+    /// The given release of `name`, or its latest when `version` is `None`.
+    pub fn find(&self, name: &str, version: Option<&str>) -> Option<&IndexEntry> {
+        let releases = self.plugins.iter().filter(|e| e.name == name);
+        match version {
+            Some(v) => releases.into_iter().find(|e| e.version == v),
+            None => releases.max_by(|a, b| compare_versions(&a.version, &b.version)),
+        }
+    }
+}
+
+/// Compare dotted version strings numerically where possible
+/// (`1.10.0` > `1.9.2`), falling back to string order per component.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<String> {
+        v.trim_start_matches('v')
+            .split(['.', '-'])
+            .map(str::to_string)
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+    for (x, y) in a.iter().zip(&b) {
+        let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// Hex-encoded SHA-256 of `bytes`.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Check `bytes` against the checksum published in the index.
+pub fn verify_checksum(bytes: &[u8], expected: &str) -> Result<(), String> {
+    let actual = sha256_hex(bytes);
+    if actual.eq_ignore_ascii_case(expected.trim()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "checksum mismatch: expected {}, got {}",
+            expected.trim(),
+            actual
+        ))
+    }
+}
+
+/// A plugin installed from the index.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InstalledPlugin {
+    pub name: String,
+    pub version: String,
+    pub kind: PluginKind,
+    #[serde(default)]
+    pub description: String,
+    pub sha256: String,
+    /// Where the artifact was written.
+    pub path: PathBuf,
+    pub installed_at: chrono::DateTime<chrono::Utc>,
+    /// What the user granted on install.
+    #[serde(default)]
+    pub permissions: PluginPermissions,
+}
+
+impl InstalledPlugin {
+    /// Check the artifact on disk is the one the permissions were granted
+    /// to, so a replaced file cannot inherit them.
+    pub fn verify(&self) -> Result<(), String> {
+        let bytes = std::fs::read(&self.path)
+            .map_err(|e| format!("{}: failed to read {}: {e}", self.name, self.path.display()))?;
+        verify_checksum(&bytes, &self.sha256).map_err(|e| format!("{}: {e}", self.name))
+    }
+}
+
+impl Plugin for InstalledPlugin {
+    fn info(&self) -> PluginInfo {
+        PluginInfo {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            category: self.kind.category(),
+            status: self.health_check(),
+            description: self.description.clone(),
+            permissions: self.permissions.clone(),
+        }
+    }
+
+    fn health_check(&self) -> PluginStatus {
+        if self.path.is_file() {
+            PluginStatus::Available
+        } else {
+            PluginStatus::Unavailable
+        }
+    }
+
+    fn start(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// The `installed.json` manifest of a plugins directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstalledPlugins {
+    pub plugins: Vec<InstalledPlugin>,
+}
+
+impl InstalledPlugins {
+    /// Load the manifest in `dir`; a missing file means nothing is installed.
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(INSTALLED_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|e| format!("invalid {}: {e}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("failed to read {}: {e}", path.display())),
+        }
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+        let path = dir.join(INSTALLED_FILE);
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("failed to write {}: {e}", path.display()))
+    }
+
+    /// Record `plugin`, replacing any installed version of the same name.
+    pub fn upsert(&mut self, plugin: InstalledPlugin) {
+        self.plugins.retain(|p| p.name != plugin.name);
+        self.plugins.push(plugin);
+        self.plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&InstalledPlugin> {
+        self.plugins.iter().find(|p| p.name == name)
+    }
+}
+
+/// Reject names that could escape the plugins or skills directory.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let ok = !name.is_empty()
+        && name.len() <= 64
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if ok {
+        Ok(())
+    } else {
+        Err(format!("invalid plugin name: {name:?}"))
+    }
+}
+
+/// Reject versions that are not semver-like, since they are part of the
+/// artifact's file name.
+pub fn validate_version(version: &str) -> Result<(), String> {
+    let ok = !version.is_empty()
+        && version.len() <= 64
+        && version.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && !version.contains("..")
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'));
+    if ok {
+        Ok(())
+    } else {
+        Err(format!("invalid plugin version: {version:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, version: &str) -> IndexEntry {
+        IndexEntry {
+            name: name.into(),
+            version: version.into(),
+            kind: PluginKind::Wasm,
+            description: format!("{name} plugin"),
+            url: format!("https://plugins.example.com/{name}-{version}.wasm"),
+            sha256: String::new(),
+            permissions: PluginPermissions::default(),
+        }
+    }
+
+    #[test]
+    fn test_search_returns_latest_release() {
+        let index = PluginIndex {
+            plugins: vec![
+                entry("jira", "1.9.2"),
+                entry("jira", "1.10.0"),
+                entry("github", "0.3.0"),
+            ],
+        };
+        let found = index.search("JIRA");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].version, "1.10.0");
+        assert_eq!(index.search("").len(), 2);
+        assert_eq!(index.find("jira", Some("1.9.2")).unwrap().version, "1.9.2");
+        assert_eq!(index.find("jira", None).unwrap().version, "1.10.0");
+        assert!(index.find("jira", Some("2.0.0")).is_none());
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let sum = sha256_hex(b"abc");
+        assert_eq!(
+            sum,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(verify_checksum(b"abc", &sum.to_uppercase()).is_ok());
+        assert!(verify_checksum(b"abd", &sum).unwrap_err().contains("mismatch"));
+    }
+
+    #[test]
+    fn test_installed_manifest_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(InstalledPlugins::load(dir.path()).unwrap().plugins.is_empty());
+
+        let artifact = dir.path().join("jira.wasm");
+        std::fs::write(&artifact, b"\0asm").unwrap();
+        let mut installed = InstalledPlugins::default();
+        for version in ["1.0.0", "1.1.0"] {
+            installed.upsert(InstalledPlugin {
+                name: "jira".into(),
+                version: version.into(),
+                kind: PluginKind::Wasm,
+                description: String::new(),
+                sha256: sha256_hex(b"\0asm"),
+                path: artifact.clone(),
+                installed_at: chrono::Utc::now(),
+                permissions: PluginPermissions {
+                    network: vec!["api.atlassian.com".into()],
+                    ..Default::default()
+                },
+            });
+        }
+        installed.save(dir.path()).unwrap();
+
+        let loaded = InstalledPlugins::load(dir.path()).unwrap();
+        assert_eq!(loaded.plugins.len(), 1);
+        let info = loaded.get("jira").unwrap().info();
+        assert_eq!(info.version, "1.1.0");
+        assert_eq!(info.status, PluginStatus::Available);
+        assert!(info.permissions.check_host("api.atlassian.com").is_ok());
+        assert!(loaded.get("jira").unwrap().verify().is_ok());
+
+        std::fs::write(&artifact, b"tampered").unwrap();
+        assert!(loaded.get("jira").unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("jira-sync_2").is_ok());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name("a/b").is_err());
+        assert!(validate_name("").is_err());
+    }
+
+    #[test]
+    fn test_validate_version() {
+        assert!(validate_version("1.2.0").is_ok());
+        assert!(validate_version("2.0.0-rc.1+build.5").is_ok());
+        assert!(validate_version("../../../.bashrc").is_err());
+        assert!(validate_version("1.0/../../x").is_err());
+        assert!(validate_version("1.0\\..\\x").is_err());
+        assert!(validate_version("").is_err());
+    }
+}
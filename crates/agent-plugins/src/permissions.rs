@@ -0,0 +1,166 @@
+//! Plugin permission manifests.
+//!
+//! A plugin declares the filesystem paths, network hosts and environment
+//! variables it needs, and the user grants them when installing the plugin.
+//! The grant is recorded and reported, not enforced: installed plugins are
+//! only registered, and no host runs their code yet. A host that does must
+//! pass every file, connection and variable lookup it performs on a
+//! plugin's behalf through [`PluginPermissions::check_path`],
+//! [`check_host`] and [`check_env`].
+//!
+//! [`check_host`]: PluginPermissions::check_host
+//! [`check_env`]: PluginPermissions::check_env
+
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// Resources a plugin may use. Empty lists grant nothing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PluginPermissions {
+    /// Directories the plugin may read and write, including everything
+    /// below them. A leading `~/` is the user's home directory.
+    pub filesystem: Vec<String>,
+    /// Hosts the plugin may connect to; `*.example.com` also matches
+    /// subdomains, `*` any host.
+    pub network: Vec<String>,
+    /// Environment variables the plugin may read.
+    pub env: Vec<String>,
+}
+
+impl PluginPermissions {
+    pub fn is_empty(&self) -> bool {
+        self.filesystem.is_empty() && self.network.is_empty() && self.env.is_empty()
+    }
+
+    /// Permissions in `self` that `granted` does not cover.
+    pub fn beyond(&self, granted: &PluginPermissions) -> PluginPermissions {
+        let missing = |wanted: &[String], have: &[String]| -> Vec<String> {
+            wanted
+                .iter()
+                .filter(|w| !have.contains(w))
+                .cloned()
+                .collect()
+        };
+        PluginPermissions {
+            filesystem: missing(&self.filesystem, &granted.filesystem),
+            network: missing(&self.network, &granted.network),
+            env: missing(&self.env, &granted.env),
+        }
+    }
+
+    /// One line per permission, for consent prompts.
+    pub fn describe(&self) -> Vec<String> {
+        let fs = self.filesystem.iter().map(|p| format!("read and write files under {p}"));
+        let net = self.network.iter().map(|h| format!("connect to {h}"));
+        let env = self.env.iter().map(|v| format!("read the environment variable {v}"));
+        fs.chain(net).chain(env).collect()
+    }
+
+    /// Allow access to `path` only below a granted directory. Paths with
+    /// `..` components are refused outright rather than resolved.
+    pub fn check_path(&self, path: &Path) -> Result<(), String> {
+        if path.components().any(|c| c == Component::ParentDir) {
+            return Err(format!("path {} contains '..'", path.display()));
+        }
+        if self
+            .filesystem
+            .iter()
+            .any(|root| path.starts_with(expand_home(root)))
+        {
+            Ok(())
+        } else {
+            Err(format!("no filesystem permission for {}", path.display()))
+        }
+    }
+
+    /// Allow connections to `host` only if it matches a granted pattern.
+    pub fn check_host(&self, host: &str) -> Result<(), String> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let allowed = self.network.iter().any(|pattern| {
+            let pattern = pattern.to_ascii_lowercase();
+            match pattern.strip_prefix("*.") {
+                _ if pattern == "*" => true,
+                Some(domain) => host == domain || host.ends_with(&format!(".{domain}")),
+                None => host == pattern,
+            }
+        });
+        if allowed {
+            Ok(())
+        } else {
+            Err(format!("no network permission for {host}"))
+        }
+    }
+
+    /// Allow reading the environment variable `name` only if granted.
+    pub fn check_env(&self, name: &str) -> Result<(), String> {
+        if self.env.iter().any(|v| v == name) {
+            Ok(())
+        } else {
+            Err(format!("no permission to read ${name}"))
+        }
+    }
+
+    /// The subset of `vars` a plugin may see, e.g. for a host's environment.
+    pub fn filter_env(&self, vars: impl IntoIterator<Item = (String, String)>) -> Vec<(String, String)> {
+        vars.into_iter()
+            .filter(|(name, _)| self.check_env(name).is_ok())
+            .collect()
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn perms() -> PluginPermissions {
+        PluginPermissions {
+            filesystem: vec!["/srv/data".into()],
+            network: vec!["api.github.com".into(), "*.atlassian.net".into()],
+            env: vec!["JIRA_TOKEN".into()],
+        }
+    }
+
+    #[test]
+    fn test_check_path() {
+        let p = perms();
+        assert!(p.check_path(Path::new("/srv/data/issues.json")).is_ok());
+        assert!(p.check_path(Path::new("/srv/database")).is_err());
+        assert!(p.check_path(Path::new("/srv/data/../../etc/passwd")).is_err());
+        assert!(PluginPermissions::default().check_path(Path::new("/tmp")).is_err());
+    }
+
+    #[test]
+    fn test_check_host_and_env() {
+        let p = perms();
+        assert!(p.check_host("API.github.com").is_ok());
+        assert!(p.check_host("acme.atlassian.net").is_ok());
+        assert!(p.check_host("atlassian.net.evil.com").is_err());
+        assert!(p.check_host("github.com").is_err());
+
+        assert!(p.check_env("JIRA_TOKEN").is_ok());
+        assert!(p.check_env("AWS_SECRET_ACCESS_KEY").is_err());
+        let vars = vec![
+            ("JIRA_TOKEN".to_string(), "t".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ];
+        assert_eq!(p.filter_env(vars).len(), 1);
+    }
+
+    #[test]
+    fn test_beyond() {
+        let mut granted = perms();
+        granted.network.pop();
+        let extra = perms().beyond(&granted);
+        assert_eq!(extra.network, vec!["*.atlassian.net".to_string()]);
+        assert!(extra.filesystem.is_empty() && extra.env.is_empty());
+        assert!(perms().beyond(&perms()).is_empty());
+    }
+}
@@ -16,4 +16,4 @@ pub use export::RecordingExporter;
 pub use player::{EventType, PlaybackEvent, PlayerError, ReplayPlayer};
 pub use recorder::{AsciicastEvent, AsciicastHeader, RecorderError, SessionRecorder};
 pub use session::{PtyError, PtySession};
-pub use shell::{default_shell, detect_available_shells, ShellInfo};
+pub use shell::{default_shell, detect_available_shells, find_shell, ShellInfo};
@@ -59,6 +59,7 @@ impl ReplayPlayer {
     }
 
     /// Parse a recording from an in-memory string.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(content: &str) -> Result<Self, PlayerError> {
         let mut lines = content.lines();
 
@@ -112,6 +112,11 @@ pub fn detect_available_shells() -> Vec<ShellInfo> {
     shells
 }
 
+/// Look up an available shell by its identifier.
+pub fn find_shell(id: &str) -> Option<ShellInfo> {
+    detect_available_shells().into_iter().find(|s| s.id == id)
+}
+
 /// Get the default shell for the current platform.
 pub fn default_shell() -> Option<ShellInfo> {
     // Prefer the user's SHELL environment variable on Unix.
@@ -166,6 +171,14 @@ mod tests {
         assert!(shell.path.exists());
     }
 
+    #[test]
+    fn test_find_shell() {
+        let first = detect_available_shells().into_iter().next().unwrap();
+        let found = find_shell(&first.id).unwrap();
+        assert_eq!(found.path, first.path);
+        assert!(find_shell("no-such-shell").is_none());
+    }
+
     #[test]
     fn test_shell_info_new() {
         let info = ShellInfo::new("test-sh", "Test Shell", "/bin/sh");
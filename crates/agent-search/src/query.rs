@@ -235,12 +235,16 @@ mod tests {
     fn test_is_empty() {
         assert!(SearchQuery::default().is_empty());
 
-        let mut q = SearchQuery::default();
-        q.text = "hello".into();
+        let q = SearchQuery {
+            text: "hello".into(),
+            ..Default::default()
+        };
         assert!(!q.is_empty());
 
-        let mut q2 = SearchQuery::default();
-        q2.exit_code = Some(0);
+        let q2 = SearchQuery {
+            exit_code: Some(0),
+            ..Default::default()
+        };
         assert!(!q2.is_empty());
     }
 }
@@ -0,0 +1,11 @@
+//! Rebuild when the web UI is rebuilt, so `embed-ui` binaries embed the
+//! current `crates/agent-ui/dist` (rust-embed only sees it at compile time).
+
+const DIST: &str = "../agent-ui/dist";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if std::env::var_os("CARGO_FEATURE_EMBED_UI").is_some() {
+        println!("cargo:rerun-if-changed={DIST}");
+    }
+}
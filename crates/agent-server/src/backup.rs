@@ -0,0 +1,73 @@
+//! Scheduled backups to the `[backup]` remote.
+//!
+//! When `[backup] interval_hours` and a remote are set, the server takes a
+//! backup (see [`agent_backup::Backups::backup`]) whenever the newest one
+//! on the remote is older than the interval. The remote is the record of
+//! the last backup, so restarts don't trigger an extra one. Config is
+//! re-read at each check, so changes apply without a restart.
+
+use crate::state::AppState;
+use agent_backup::{Backups, Sources};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Back up if one is due. Returns when the newest backup was taken, or
+/// `None` when backups are off or the remote can't be reached.
+pub async fn backup_if_due(
+    state: &AppState,
+    last: Option<DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    let config = state.config.read().await.clone();
+    let hours = config.backup.interval_hours?;
+    config.backup.remote.as_ref()?;
+    let backups = match Backups::from_config(&config.backup) {
+        Ok(backups) => backups,
+        Err(e) => {
+            tracing::warn!("Scheduled backups disabled: {}", e);
+            return None;
+        }
+    };
+    let last = match last {
+        Some(last) => Some(last),
+        None => match backups.list().await {
+            Ok(list) => list.first().map(|b| b.created_at),
+            Err(e) => {
+                tracing::warn!("Could not list backups: {}", e);
+                return None;
+            }
+        },
+    };
+    let now = Utc::now();
+    if last.is_some_and(|last| now - last < chrono::Duration::hours(hours.into())) {
+        return last;
+    }
+
+    let result = match Sources::for_config(&config) {
+        Ok(sources) => backups.backup(&sources, now).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(report) => {
+            tracing::info!("Backed up to {} ({} bytes)", report.name, report.size);
+            Some(now)
+        }
+        Err(e) => {
+            tracing::warn!("Scheduled backup failed: {}", e);
+            last
+        }
+    }
+}
+
+/// Check every ten minutes while the server runs.
+pub fn spawn_scheduler(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        let mut last = None;
+        loop {
+            interval.tick().await;
+            last = backup_if_due(&state, last).await;
+        }
+    });
+}
@@ -0,0 +1,139 @@
+//! Who is making a request, for session ownership.
+//!
+//! [`crate::auth_middleware`] records a [`Caller`] for every authenticated
+//! request. Named keys (`[[server.api_keys]]`) own the sessions they
+//! create and only see those; the legacy `auth_token`, admin keys, the
+//! local socket and an unauthenticated server see every session. A session
+//! the caller may not access is reported as not found, so its ID leaks
+//! nothing.
+//!
+//! Admin scope for the admin API is narrower: `server.admin_token` or a
+//! key with `admin = true`, never the legacy token or the local socket.
+
+use crate::error::{ApiError, ErrorCode};
+use agent_core::config::ServerConfig;
+use agent_core::session::{Session, SessionManager};
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use subtle::ConstantTimeEq;
+
+/// The identity a request authenticated as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caller {
+    /// Name of the API key used, recorded as the owner of new sessions.
+    pub owner: Option<String>,
+    /// Sees and manages every session.
+    pub admin: bool,
+}
+
+impl Caller {
+    /// Full access without a named owner: the legacy token, the local
+    /// socket, or no auth configured.
+    pub fn unrestricted() -> Self {
+        Self {
+            owner: None,
+            admin: true,
+        }
+    }
+
+    /// The caller presenting `token`, if it is a configured credential.
+    /// Every configured key is compared, in constant time.
+    pub fn authenticate(server: &ServerConfig, token: &str) -> Option<Self> {
+        let matches =
+            |expected: &str| -> bool { token.as_bytes().ct_eq(expected.as_bytes()).into() };
+        let mut caller = None;
+        for key in &server.api_keys {
+            if key.resolve_key().is_some_and(|k| matches(&k)) && caller.is_none() {
+                caller = Some(Self {
+                    owner: Some(key.name.clone()),
+                    admin: key.admin,
+                });
+            }
+        }
+        let legacy = server.auth_token.as_deref().is_some_and(matches);
+        caller.or_else(|| legacy.then(Self::unrestricted))
+    }
+
+    /// A named API key with `admin = true`, which grants the admin API.
+    /// The legacy token sees every session but has no admin scope.
+    pub fn is_admin_key(&self) -> bool {
+        self.admin && self.owner.is_some()
+    }
+
+    pub fn can_access(&self, session: &Session) -> bool {
+        self.admin || session.owner == self.owner
+    }
+
+    /// Fail with `session_not_found` unless session `id` is accessible.
+    /// Admins pass unchecked; the handler reports a missing session.
+    pub fn authorize(&self, sm: &SessionManager, id: &str) -> Result<(), ApiError> {
+        if self.admin {
+            return Ok(());
+        }
+        match sm.session(id) {
+            Some(session) if self.can_access(session) => Ok(()),
+            _ => Err(ApiError::session_not_found(id)),
+        }
+    }
+
+    /// Record the caller as the owner of session `id` and save it.
+    pub async fn claim(&self, sm: &mut SessionManager, id: &str) -> Result<(), ApiError> {
+        let Some(session) = sm.session_mut(id) else {
+            return Err(ApiError::session_not_found(id));
+        };
+        if session.owner != self.owner {
+            session.owner = self.owner.clone();
+            sm.save_session_async(id).await.map_err(ApiError::from)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Caller {
+    type Rejection = ApiError;
+
+    /// Every authenticated route records its caller; a handler reached
+    /// without one is a routing bug, so it fails closed.
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Caller>().cloned().ok_or_else(|| {
+            ApiError::new(ErrorCode::Internal, "Request reached a handler without a caller")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::config::ApiKeyConfig;
+
+    fn key(name: &str, key: &str, admin: bool) -> ApiKeyConfig {
+        ApiKeyConfig {
+            name: name.into(),
+            key: Some(key.into()),
+            key_env: None,
+            admin,
+        }
+    }
+
+    #[test]
+    fn test_authenticate_and_access() {
+        let server = ServerConfig {
+            auth_token: Some("legacy".into()),
+            api_keys: vec![key("alice", "a-key", false), key("ops", "o-key", true)],
+            ..Default::default()
+        };
+        let alice = Caller::authenticate(&server, "a-key").unwrap();
+        assert_eq!(alice.owner.as_deref(), Some("alice"));
+        assert!(!alice.admin);
+        assert!(Caller::authenticate(&server, "o-key").unwrap().is_admin_key());
+        assert!(!Caller::authenticate(&server, "legacy").unwrap().is_admin_key());
+        assert_eq!(Caller::authenticate(&server, "legacy"), Some(Caller::unrestricted()));
+        assert_eq!(Caller::authenticate(&server, "nope"), None);
+
+        let mut session = Session::new("mine");
+        assert!(!alice.can_access(&session));
+        session.owner = Some("alice".into());
+        assert!(alice.can_access(&session));
+        assert!(Caller::unrestricted().can_access(&session));
+    }
+}
@@ -0,0 +1,222 @@
+//! Inline editor actions (`POST /v1/editor/actions`).
+//!
+//! An editor plugin sends a file, an optional selection and an instruction;
+//! the model answers with search/replace edits using `file_edit` semantics
+//! (each `old_string` must match the file exactly once). The server never
+//! writes the file: it returns the edits, a unified diff and an explanation,
+//! and the editor decides whether to apply them.
+
+use agent_core::types::Message;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Lines of surrounding code sent to the model on each side of the selection.
+const CONTEXT_LINES: usize = 200;
+
+/// 1-based, inclusive line range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Selection {
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct EditorActionRequest {
+    /// File path, absolute or relative to the session's primary workspace.
+    pub path: String,
+    /// Session whose workspaces the file is in; defaults to the active
+    /// session.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// What to do, e.g. "add error handling".
+    pub instruction: String,
+    #[serde(default)]
+    pub selection: Option<Selection>,
+    /// Unsaved buffer contents; read from disk when omitted.
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+/// One search/replace edit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TextEdit {
+    pub old_string: String,
+    pub new_string: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EditorActionResponse {
+    pub path: String,
+    pub explanation: String,
+    pub edits: Vec<TextEdit>,
+    /// Unified diff of the file before and after the edits.
+    pub diff: String,
+}
+
+/// The JSON object the model is asked to reply with.
+#[derive(Debug, Deserialize)]
+pub struct ModelReply {
+    #[serde(default)]
+    pub explanation: String,
+    #[serde(default)]
+    pub edits: Vec<TextEdit>,
+}
+
+impl Selection {
+    pub fn validate(&self, line_count: usize) -> Result<(), String> {
+        if self.start_line == 0 || self.end_line < self.start_line {
+            return Err("selection must satisfy 1 <= start_line <= end_line".into());
+        }
+        if self.start_line > line_count.max(1) {
+            return Err(format!(
+                "selection starts at line {} but the file has {} lines",
+                self.start_line, line_count
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Apply edits in order. Each `old_string` must occur exactly once in the
+/// text as it stands after the previous edits.
+pub fn apply_edits(content: &str, edits: &[TextEdit]) -> Result<String, String> {
+    let mut text = content.to_string();
+    for (i, edit) in edits.iter().enumerate() {
+        if edit.old_string.is_empty() {
+            return Err(format!("edit {}: old_string is empty", i + 1));
+        }
+        match text.matches(edit.old_string.as_str()).count() {
+            0 => return Err(format!("edit {}: old_string not found", i + 1)),
+            1 => text = text.replacen(&edit.old_string, &edit.new_string, 1),
+            n => {
+                return Err(format!(
+                    "edit {}: old_string matches {} times; it must be unique",
+                    i + 1,
+                    n
+                ))
+            }
+        }
+    }
+    Ok(text)
+}
+
+/// Build the system and user messages for an editor action.
+pub fn build_messages(
+    path: &str,
+    content: &str,
+    selection: Option<Selection>,
+    instruction: &str,
+) -> Vec<Message> {
+    let lines: Vec<&str> = content.lines().collect();
+    let (from, to) = match selection {
+        Some(sel) => (
+            sel.start_line.saturating_sub(CONTEXT_LINES + 1),
+            (sel.end_line + CONTEXT_LINES).min(lines.len()),
+        ),
+        None => (0, lines.len()),
+    };
+    let numbered: String = lines[from..to]
+        .iter()
+        .enumerate()
+        .map(|(i, line)| format!("{:>5} | {}\n", from + i + 1, line))
+        .collect();
+
+    let mut user = format!("File: {path}\n");
+    if from > 0 || to < lines.len() {
+        user.push_str(&format!("(showing lines {}-{} of {})\n", from + 1, to, lines.len()));
+    }
+    user.push_str(&format!("```\n{numbered}```\n"));
+    if let Some(sel) = selection {
+        user.push_str(&format!(
+            "Selected lines: {}-{}\n",
+            sel.start_line, sel.end_line
+        ));
+    }
+    user.push_str(&format!("Instruction: {instruction}\n"));
+
+    let system = "You are editing a file inside the user's editor. Reply with a single JSON \
+object and nothing else: {\"explanation\": string, \"edits\": [{\"old_string\": string, \
+\"new_string\": string}]}. Each old_string must be copied exactly from the file (without the \
+line-number gutter) and must match exactly once, so include enough surrounding lines to make it \
+unique. Keep edits minimal and focused on the selection when one is given. Use an empty edits \
+list if no change is needed.";
+
+    vec![Message::system(system), Message::user(user)]
+}
+
+/// Extract the JSON reply, tolerating a surrounding code fence or prose.
+pub fn parse_reply(text: &str) -> Result<ModelReply, String> {
+    let start = text.find('{');
+    let end = text.rfind('}');
+    let json = match (start, end) {
+        (Some(s), Some(e)) if s < e => &text[s..=e],
+        _ => return Err("model reply did not contain a JSON object".into()),
+    };
+    serde_json::from_str(json).map_err(|e| format!("model reply was not valid JSON: {e}"))
+}
+
+/// Unified diff between `before` and `after`, with `a/` and `b/` headers.
+pub fn unified_diff(path: &str, before: &str, after: &str) -> String {
+    similar::TextDiff::from_lines(before, after)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{path}"), &format!("b/{path}"))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(old: &str, new: &str) -> TextEdit {
+        TextEdit {
+            old_string: old.into(),
+            new_string: new.into(),
+        }
+    }
+
+    #[test]
+    fn test_apply_edits_requires_unique_match() {
+        let src = "let a = 1;\nlet b = 1;\n";
+        assert_eq!(
+            apply_edits(src, &[edit("a = 1", "a = 2")]).unwrap(),
+            "let a = 2;\nlet b = 1;\n"
+        );
+        assert!(apply_edits(src, &[edit("= 1", "= 2")])
+            .unwrap_err()
+            .contains("2 times"));
+        assert!(apply_edits(src, &[edit("c = 1", "c = 2")])
+            .unwrap_err()
+            .contains("not found"));
+    }
+
+    #[test]
+    fn test_parse_reply_and_diff() {
+        let reply = parse_reply(
+            "```json\n{\"explanation\": \"rename\", \"edits\": [{\"old_string\": \"foo\", \"new_string\": \"bar\"}]}\n```",
+        )
+        .unwrap();
+        assert_eq!(reply.explanation, "rename");
+        assert_eq!(reply.edits, vec![edit("foo", "bar")]);
+        assert!(parse_reply("no json here").is_err());
+
+        let diff = unified_diff("src/lib.rs", "fn foo() {}\n", "fn bar() {}\n");
+        assert!(diff.starts_with("--- a/src/lib.rs\n+++ b/src/lib.rs\n"));
+        assert!(diff.contains("-fn foo() {}\n+fn bar() {}\n"));
+    }
+
+    #[test]
+    fn test_build_messages_windows_around_selection() {
+        let content: String = (1..=1000).map(|i| format!("line {i}\n")).collect();
+        let sel = Selection {
+            start_line: 500,
+            end_line: 501,
+        };
+        let messages = build_messages("big.txt", &content, Some(sel), "tidy");
+        let user = &messages[1].content;
+        assert!(user.contains("(showing lines 300-701 of 1000)"));
+        assert!(user.contains("  500 | line 500\n"));
+        assert!(!user.contains("| line 299\n"));
+        assert!(user.contains("Selected lines: 500-501"));
+    }
+}
@@ -0,0 +1,268 @@
+//! The HTTP API's error type.
+//!
+//! Every failed request is answered with an RFC 9457
+//! `application/problem+json` body carrying the standard human-readable
+//! `detail`, a machine-readable `code` and optional structured `details`,
+//! so clients can branch on the code instead of parsing text:
+//!
+//! ```json
+//! {"type":"about:blank","title":"Not Found","status":404,
+//!  "code":"session_not_found","detail":"Session not found: 1a2b…"}
+//! ```
+
+use agent_core::error::AgentError;
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use serde_json::Value;
+
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Machine-readable error codes. Each has a default HTTP status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request is malformed or has invalid parameters.
+    InvalidRequest,
+    /// The JSON body does not match the endpoint's schema.
+    ValidationFailed,
+    PayloadTooLarge,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    SessionNotFound,
+    MessageNotFound,
+    AgentNotFound,
+    ToolNotFound,
+    PluginNotFound,
+    RunNotFound,
+    /// The request conflicts with existing state (e.g. a duplicate name).
+    Conflict,
+    /// Another turn is running in the session; `details.run_id` names it.
+    SessionBusy,
+    /// The session cannot do what was asked (nothing to undo, no active
+    /// session, unknown checkpoint, ...).
+    SessionError,
+    /// The model provider failed or returned an error.
+    ProviderError,
+    /// Another upstream service (speech, editor model, peer) failed.
+    UpstreamError,
+    ToolFailed,
+    SandboxError,
+    ConfigError,
+    Cancelled,
+    /// The feature is not configured or temporarily unavailable.
+    Unavailable,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            Self::InvalidRequest => StatusCode::BAD_REQUEST,
+            Self::ValidationFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::NotFound
+            | Self::SessionNotFound
+            | Self::MessageNotFound
+            | Self::AgentNotFound
+            | Self::ToolNotFound
+            | Self::PluginNotFound
+            | Self::RunNotFound => StatusCode::NOT_FOUND,
+            Self::Conflict | Self::SessionBusy | Self::SessionError | Self::Cancelled => {
+                StatusCode::CONFLICT
+            }
+            Self::ProviderError | Self::UpstreamError => StatusCode::BAD_GATEWAY,
+            Self::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Self::ToolFailed | Self::SandboxError | Self::ConfigError | Self::Internal => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// The generic code for an error status produced outside a handler
+    /// (routing, extractors, middleware).
+    pub fn for_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => Self::Unauthorized,
+            StatusCode::FORBIDDEN => Self::Forbidden,
+            StatusCode::NOT_FOUND => Self::NotFound,
+            StatusCode::CONFLICT => Self::Conflict,
+            StatusCode::PAYLOAD_TOO_LARGE => Self::PayloadTooLarge,
+            StatusCode::UNPROCESSABLE_ENTITY => Self::ValidationFailed,
+            StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => Self::UpstreamError,
+            StatusCode::SERVICE_UNAVAILABLE => Self::Unavailable,
+            s if s.is_client_error() => Self::InvalidRequest,
+            _ => Self::Internal,
+        }
+    }
+}
+
+/// An API error, rendered as problem details.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[schema(as = Problem)]
+pub struct ApiError {
+    #[serde(rename = "type")]
+    #[schema(value_type = String, example = "about:blank")]
+    kind: &'static str,
+    /// The status's reason phrase.
+    title: &'static str,
+    #[serde(serialize_with = "serialize_status")]
+    #[schema(value_type = u16)]
+    pub status: StatusCode,
+    pub code: ErrorCode,
+    /// What went wrong, for people.
+    pub detail: String,
+    /// Structured context, e.g. `errors` listing every schema violation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+    /// ID of the failed request, as in its `x-request-id` header. Quote it
+    /// when reporting a problem; the server log has the same ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+fn serialize_status<S: serde::Serializer>(status: &StatusCode, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_u16(status.as_u16())
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self::with_status(code.status(), code, message)
+    }
+
+    /// An error whose status differs from the code's default.
+    pub fn with_status(status: StatusCode, code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            kind: "about:blank",
+            title: status.canonical_reason().unwrap_or("Error"),
+            status,
+            code,
+            detail: message.into(),
+            details: None,
+            request_id: None,
+        }
+    }
+
+    /// An error for a status produced outside a handler, with the status's
+    /// generic code.
+    pub fn from_status(status: StatusCode, message: impl Into<String>) -> Self {
+        Self::with_status(status, ErrorCode::for_status(status), message)
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidRequest, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn session_not_found(id: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::SessionNotFound, format!("Session not found: {}", id))
+    }
+
+    pub fn internal(error: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::Internal, error.to_string())
+    }
+
+    pub fn upstream(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::UpstreamError, message)
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({:?}): {}", self.status, self.code, self.detail)
+    }
+}
+
+impl From<AgentError> for ApiError {
+    fn from(e: AgentError) -> Self {
+        let message = e.to_string();
+        match e {
+            AgentError::Config(_) => Self::new(ErrorCode::ConfigError, message),
+            AgentError::Provider(_) => Self::new(ErrorCode::ProviderError, message),
+            AgentError::ToolExecution { tool_name, .. } => {
+                Self::new(ErrorCode::ToolFailed, message)
+                    .with_details(serde_json::json!({ "tool": tool_name }))
+            }
+            AgentError::ToolNotFound(_) => Self::new(ErrorCode::ToolNotFound, message),
+            AgentError::Session(msg) => Self::new(ErrorCode::SessionError, msg),
+            AgentError::Sandbox(_) => Self::new(ErrorCode::SandboxError, message),
+            AgentError::Io(io) => match io.kind() {
+                std::io::ErrorKind::NotFound => Self::not_found(message),
+                std::io::ErrorKind::PermissionDenied => Self::new(ErrorCode::Forbidden, message),
+                _ => Self::internal(message),
+            },
+            AgentError::Http(_) => Self::upstream(message),
+            AgentError::Cancelled => Self::new(ErrorCode::Cancelled, message),
+            AgentError::Serialization(_) | AgentError::Schema(_) | AgentError::Other(_) => {
+                Self::internal(message)
+            }
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(mut self) -> Response {
+        if self.request_id.is_none() {
+            self.request_id = crate::request_id::current();
+        }
+        let mut response = (self.status, Json(&self)).into_response();
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(PROBLEM_CONTENT_TYPE),
+        );
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialized_shape() {
+        let e = ApiError::session_not_found("abc");
+        let json = serde_json::to_value(&e).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "code": "session_not_found",
+                "detail": "Session not found: abc",
+            })
+        );
+    }
+
+    #[test]
+    fn test_maps_agent_errors() {
+        let e = ApiError::from(AgentError::Provider("rate limited".into()));
+        assert_eq!(e.status, StatusCode::BAD_GATEWAY);
+        assert_eq!(e.code, ErrorCode::ProviderError);
+
+        let e = ApiError::from(AgentError::ToolExecution {
+            tool_name: "shell_exec".into(),
+            message: "exit 1".into(),
+        });
+        assert_eq!(e.code, ErrorCode::ToolFailed);
+        assert_eq!(e.details.unwrap()["tool"], "shell_exec");
+
+        let e = ApiError::from(AgentError::Session("Nothing to undo".into()));
+        assert_eq!((e.status, e.detail.as_str()), (StatusCode::CONFLICT, "Nothing to undo"));
+
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        assert_eq!(ApiError::from(AgentError::Io(io)).code, ErrorCode::NotFound);
+    }
+}
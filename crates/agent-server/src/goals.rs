@@ -0,0 +1,188 @@
+//! Webhook notifications for missed analytics goals.
+//!
+//! Once an hour the server checks each goal in `[[analytics.goals]]` over
+//! its most recently finished period and POSTs
+//! [`webhook_payload`](agent_analytics::goals::webhook_payload) to
+//! `[analytics] webhook_url` for each one missed. Sent notifications are
+//! recorded in `goal_notifications.json` in the data directory so every
+//! miss is reported once, across restarts; failed deliveries are retried
+//! at the next check. Nothing is sent during `[scheduler] quiet_hours`;
+//! misses wait for the first check after the window.
+
+use crate::state::AppState;
+use agent_analytics::GoalStatus;
+use agent_core::config::AppConfig;
+use agent_core::scheduler::QuietHours;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Delivered notifications remembered; older ones are forgotten.
+const MAX_SENT: usize = 500;
+
+/// Notifications already delivered, oldest first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SentNotifications {
+    keys: Vec<String>,
+}
+
+impl SentNotifications {
+    fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Identifies one goal's miss in one period.
+fn key(status: &GoalStatus) -> String {
+    format!("{}@{}", status.name, status.period_start)
+}
+
+fn sent_path() -> PathBuf {
+    AppConfig::data_dir().join("goal_notifications.json")
+}
+
+/// POST each missed goal not yet in `sent_path` to `url`. Returns how many
+/// were delivered.
+pub async fn notify_missed(
+    client: &reqwest::Client,
+    url: &str,
+    missed: &[GoalStatus],
+    sent_path: &Path,
+) -> usize {
+    let mut sent = SentNotifications::load(sent_path);
+    let mut delivered = 0;
+    for status in missed {
+        let key = key(status);
+        if sent.keys.contains(&key) {
+            continue;
+        }
+        let result = client
+            .post(url)
+            .json(&agent_analytics::goals::webhook_payload(status))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        match result {
+            Ok(_) => {
+                sent.keys.push(key);
+                delivered += 1;
+            }
+            Err(e) => tracing::warn!("Goal webhook for '{}' failed: {}", status.name, e),
+        }
+    }
+    if delivered > 0 {
+        let excess = sent.keys.len().saturating_sub(MAX_SENT);
+        sent.keys.drain(..excess);
+        if let Err(e) = sent.save(sent_path) {
+            tracing::warn!("Failed to record goal notifications: {}", e);
+        }
+    }
+    delivered
+}
+
+/// Check goals every hour while the server runs, if any goals and a
+/// webhook are configured.
+pub fn spawn_watcher(state: AppState) {
+    tokio::spawn(async move {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let (goals, url, quiet_hours) = {
+                let config = state.config.read().await;
+                (
+                    config.analytics.goals.clone(),
+                    config.analytics.webhook_url.clone(),
+                    QuietHours::from_setting(config.scheduler.quiet_hours.as_deref()),
+                )
+            };
+            let Some(url) = url.filter(|_| !goals.is_empty()) else {
+                continue;
+            };
+            if quiet_hours.and_then(|q| q.deferred_now()).is_some() {
+                continue;
+            }
+            let analytics = crate::routes::load_analytics(&state).await;
+            let today = chrono::Utc::now().date_naive();
+            let missed = agent_analytics::goals::missed_goals(&analytics, &goals, today);
+            let delivered = notify_missed(&client, &url, &missed, &sent_path()).await;
+            if delivered > 0 {
+                tracing::info!("Reported {} missed goal(s) to the webhook", delivered);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_analytics::GoalState;
+    use agent_core::config::{GoalMetric, GoalPeriod};
+    use axum::Router;
+    use std::sync::{Arc, Mutex};
+
+    fn missed(name: &str) -> GoalStatus {
+        GoalStatus {
+            name: name.into(),
+            metric: GoalMetric::Sessions,
+            period: GoalPeriod::Week,
+            period_start: chrono::NaiveDate::from_ymd_opt(2026, 3, 9).unwrap(),
+            period_end: chrono::NaiveDate::from_ymd_opt(2026, 3, 15).unwrap(),
+            value: 1.0,
+            target: "≥ 3".into(),
+            state: GoalState::Missed,
+            progress: 1.0 / 3.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notifies_each_miss_once() {
+        let received = Arc::new(Mutex::new(Vec::<serde_json::Value>::new()));
+        let sink = received.clone();
+        let hook = Router::new().route(
+            "/hook",
+            axum::routing::post(move |axum::Json(body): axum::Json<serde_json::Value>| {
+                let sink = sink.clone();
+                async move { sink.lock().unwrap().push(body) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, hook).await.unwrap() });
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("sent.json");
+        let client = reqwest::Client::new();
+
+        let goals = [missed("Sessions"), missed("Deep work")];
+        assert_eq!(notify_missed(&client, &url, &goals, &path).await, 2);
+        assert_eq!(notify_missed(&client, &url, &goals, &path).await, 0);
+
+        {
+            let received = received.lock().unwrap();
+            assert_eq!(received.len(), 2);
+            assert_eq!(received[0]["event"], "goal_missed");
+            assert_eq!(received[1]["goal"]["name"], "Deep work");
+        }
+
+        // Failed deliveries are not recorded, so they are retried.
+        let bad_url = format!("{}/missing", url);
+        assert_eq!(notify_missed(&client, &bad_url, &[missed("Other")], &path).await, 0);
+        assert_eq!(SentNotifications::load(&path).keys.len(), 2);
+    }
+}
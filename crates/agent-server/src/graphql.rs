@@ -0,0 +1,505 @@
+//! Read-only GraphQL admin API at `/v1/admin/graphql`, for dashboards.
+//!
+//! Sessions, messages, analytics, schedules and plugins in one query,
+//! with filters and Relay-style pagination (`first`/`after`,
+//! `last`/`before`). Only mounted when `server.admin_token` is set, and
+//! only that token is accepted: `server.auth_token` does not grant admin
+//! scope. `GET` returns the schema in SDL.
+
+use crate::state::AppState;
+use async_graphql::connection::{query, Connection, Edge};
+use async_graphql::{
+    ComplexObject, Context, EmptyMutation, EmptySubscription, Enum, InputObject, Object,
+    OutputType, Schema, SimpleObject,
+};
+use axum::extract::Extension;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, NaiveDate, Utc};
+
+pub type AdminSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Page size when a query gives neither `first` nor `last`.
+const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Largest page a query may ask for.
+const MAX_PAGE_SIZE: usize = 100;
+
+pub fn schema(state: AppState) -> AdminSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(state)
+        .limit_depth(10)
+        .limit_complexity(1000)
+        .finish()
+}
+
+pub fn graphql_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/v1/admin/graphql", get(sdl).post(execute))
+        .layer(Extension(schema(state.clone())))
+}
+
+async fn execute(
+    Extension(schema): Extension<AdminSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+async fn sdl(Extension(schema): Extension<AdminSchema>) -> String {
+    schema.sdl()
+}
+
+/// Total item count, next to the page of a connection.
+#[derive(SimpleObject)]
+pub struct Total {
+    total_count: usize,
+}
+
+/// Page through `items` with Relay connection arguments. Cursors are
+/// positions in `items`, so they stay valid while the list is unchanged.
+async fn paginate<T: OutputType>(
+    items: Vec<T>,
+    after: Option<String>,
+    before: Option<String>,
+    first: Option<i32>,
+    last: Option<i32>,
+) -> async_graphql::Result<Connection<usize, T, Total>> {
+    query(after, before, first, last, |after, before, first, last| async move {
+        let len = items.len();
+        let mut end = before.unwrap_or(len).min(len);
+        let mut start = after.map(|a| a + 1).unwrap_or(0).min(end);
+        if let Some(first) = first {
+            end = end.min(start + first.min(MAX_PAGE_SIZE));
+        }
+        if let Some(last) = last {
+            start = start.max(end.saturating_sub(last.min(MAX_PAGE_SIZE)));
+        }
+        if first.is_none() && last.is_none() {
+            end = end.min(start + DEFAULT_PAGE_SIZE);
+        }
+
+        let mut connection =
+            Connection::with_additional_fields(start > 0, end < len, Total { total_count: len });
+        connection.edges.extend(
+            items
+                .into_iter()
+                .enumerate()
+                .skip(start)
+                .take(end - start)
+                .map(|(i, item)| Edge::new(i, item)),
+        );
+        Ok::<_, async_graphql::Error>(connection)
+    })
+    .await
+}
+
+fn state<'a>(ctx: &Context<'a>) -> &'a AppState {
+    ctx.data_unchecked::<AppState>()
+}
+
+/// A serde enum's wire name, e.g. `skill` for `PluginCategory::Skill`.
+fn wire_name(value: &impl serde::Serialize) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Saved sessions, most recently updated first.
+    async fn sessions(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<SessionFilter>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<Connection<usize, Session, Total>> {
+        let filter = filter.unwrap_or_default();
+        let mut sm = state(ctx).session_manager.write().await;
+        // Include sessions the REPL or another server created meanwhile.
+        if let Err(e) = sm.reload_changed() {
+            tracing::warn!("Failed to reload sessions: {}", e);
+        }
+        let sessions: Vec<Session> = sm
+            .list_sessions()
+            .into_iter()
+            .filter_map(|(id, ..)| sm.session(id))
+            .filter(|s| filter.matches(s))
+            .map(|s| Session(s.clone()))
+            .collect();
+        drop(sm);
+        paginate(sessions, after, before, first, last).await
+    }
+
+    /// One session by ID.
+    async fn session(&self, ctx: &Context<'_>, id: String) -> Option<Session> {
+        let sm = state(ctx).session_manager.read().await;
+        sm.session(&id).cloned().map(Session)
+    }
+
+    /// Usage analytics over every saved session.
+    async fn analytics(&self, ctx: &Context<'_>) -> Analytics {
+        Analytics(crate::routes::load_analytics(state(ctx)).await)
+    }
+
+    /// Configured schedules and their persisted state.
+    async fn schedules(&self, ctx: &Context<'_>, enabled: Option<bool>) -> Vec<Schedule> {
+        use agent_core::scheduler::{ScheduleHistory, Scheduler};
+        let config = state(ctx).config.read().await;
+        let stored = Scheduler::read_state(&Scheduler::state_path_for(&config));
+        let history = std::sync::Arc::new(ScheduleHistory::for_config(&config));
+        config
+            .schedules
+            .iter()
+            .filter(|s| enabled.is_none_or(|e| s.enabled == e))
+            .map(|s| {
+                let state = stored.get(&s.name);
+                Schedule {
+                    name: s.name.clone(),
+                    cron: s.cron.clone(),
+                    enabled: s.enabled,
+                    task: wire_name(&s.task),
+                    session: s.session_name(),
+                    prompt: s.prompt.clone(),
+                    last_run: state.and_then(|st| st.last_run),
+                    next_run: state.map(|st| st.next_run),
+                    run_count: state.map_or(0, |st| st.run_count),
+                    last_error: state.and_then(|st| st.last_error.clone()),
+                    history: history.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Loaded plugins, optionally of one category (`tool`, `provider`,
+    /// `skill` or `extension`) or status.
+    async fn plugins(
+        &self,
+        ctx: &Context<'_>,
+        category: Option<String>,
+        status: Option<String>,
+    ) -> Vec<Plugin> {
+        let pr = state(ctx).plugin_registry.read().await;
+        pr.list()
+            .into_iter()
+            .map(|info| {
+                let category = wire_name(&info.category);
+                Plugin {
+                    key: format!("{}:{}", category, info.name),
+                    name: info.name,
+                    version: info.version,
+                    category,
+                    status: wire_name(&info.status),
+                    description: info.description,
+                }
+            })
+            .filter(|p| category.as_ref().is_none_or(|c| &p.category == c))
+            .filter(|p| status.as_ref().is_none_or(|s| &p.status == s))
+            .collect()
+    }
+}
+
+/// Which sessions to return; every given condition must hold.
+#[derive(InputObject, Default)]
+pub struct SessionFilter {
+    /// Case-insensitive substring of the name.
+    name_contains: Option<String>,
+    /// Agent preset the session runs with.
+    agent: Option<String>,
+    tag: Option<String>,
+    updated_after: Option<DateTime<Utc>>,
+    updated_before: Option<DateTime<Utc>>,
+    min_messages: Option<usize>,
+}
+
+impl SessionFilter {
+    fn matches(&self, session: &agent_core::session::Session) -> bool {
+        let name = |n: &String| session.name.to_lowercase().contains(&n.to_lowercase());
+        self.name_contains.as_ref().is_none_or(name)
+            && self.agent.as_ref().is_none_or(|a| session.agent.as_ref() == Some(a))
+            && self.tag.as_ref().is_none_or(|t| session.tags.contains(t))
+            && self.updated_after.is_none_or(|t| session.updated_at > t)
+            && self.updated_before.is_none_or(|t| session.updated_at < t)
+            && self.min_messages.is_none_or(|m| session.messages.len() >= m)
+    }
+}
+
+pub struct Session(agent_core::session::Session);
+
+#[Object]
+impl Session {
+    async fn id(&self) -> &str {
+        &self.0.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// Agent preset every turn runs with.
+    async fn agent(&self) -> Option<&str> {
+        self.0.agent.as_deref()
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.tags
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    async fn message_count(&self) -> usize {
+        self.0.messages.len()
+    }
+
+    /// Tokens used by every model call in the session, and their cost.
+    async fn usage(&self) -> Usage {
+        let usage = &self.0.usage;
+        Usage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens(),
+            cost_usd: usage.cost_usd,
+            estimated: usage.estimated,
+        }
+    }
+
+    /// Messages, oldest first, optionally of one role.
+    async fn messages(
+        &self,
+        role: Option<MessageRole>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<Connection<usize, Message, Total>> {
+        let messages: Vec<Message> = self
+            .0
+            .messages
+            .iter()
+            .filter(|m| role.is_none_or(|r| MessageRole::from(&m.role) == r))
+            .map(Message::from)
+            .collect();
+        paginate(messages, after, before, first, last).await
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Usage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    total_tokens: u64,
+    cost_usd: f64,
+    /// Some counts were estimated because the provider did not report usage.
+    estimated: bool,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+pub enum MessageRole {
+    System,
+    User,
+    Assistant,
+    Tool,
+}
+
+impl From<&agent_core::types::Role> for MessageRole {
+    fn from(role: &agent_core::types::Role) -> Self {
+        use agent_core::types::Role;
+        match role {
+            Role::System => Self::System,
+            Role::User => Self::User,
+            Role::Assistant => Self::Assistant,
+            Role::Tool => Self::Tool,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Message {
+    id: String,
+    role: MessageRole,
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    /// Call this message answers, for tool results.
+    tool_call_id: Option<String>,
+    timestamp: DateTime<Utc>,
+}
+
+impl From<&agent_core::types::Message> for Message {
+    fn from(m: &agent_core::types::Message) -> Self {
+        Self {
+            id: m.id.clone(),
+            role: MessageRole::from(&m.role),
+            content: m.content.clone(),
+            tool_calls: m
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|tc| ToolCall {
+                    id: tc.id.clone(),
+                    name: tc.name.clone(),
+                    arguments: tc.arguments.clone(),
+                })
+                .collect(),
+            tool_call_id: m.tool_call_id.clone(),
+            timestamp: m.timestamp,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct ToolCall {
+    id: String,
+    name: String,
+    /// JSON-encoded arguments.
+    arguments: String,
+}
+
+pub struct Analytics(agent_analytics::Analytics);
+
+#[Object]
+impl Analytics {
+    async fn total_sessions(&self) -> usize {
+        self.0.total_sessions()
+    }
+
+    async fn active_days(&self) -> usize {
+        self.0.active_days()
+    }
+
+    async fn average_session_duration_secs(&self) -> Option<u64> {
+        self.0.average_session_duration()
+    }
+
+    async fn deep_work_sessions(&self) -> usize {
+        self.0.deep_work_sessions().len()
+    }
+
+    /// Most used tools, most calls first.
+    async fn top_tools(&self, #[graphql(default = 10)] limit: usize) -> Vec<ToolUsage> {
+        self.0
+            .top_tools(limit)
+            .into_iter()
+            .map(|(name, calls)| ToolUsage { name, calls })
+            .collect()
+    }
+
+    /// Daily activity for the `days` days ending today (1–365).
+    async fn daily(
+        &self,
+        #[graphql(default = 30, validator(minimum = 1, maximum = 365))] days: i64,
+    ) -> Vec<Day> {
+        let today = Utc::now().date_naive();
+        let start = today - chrono::Duration::days(days - 1);
+        self.0
+            .daily_series(start, today)
+            .into_iter()
+            .map(|d| Day {
+                date: d.date,
+                sessions: d.session_count,
+                messages: d.message_count,
+                tokens: d.estimated_tokens,
+                tool_calls: d.tool_call_count,
+                tool_errors: d.tool_error_count,
+                quota_denials: d.quota_denied_count,
+                error_rate: d.tool_error_rate(),
+                active_time_secs: d.total_active_time_secs,
+            })
+            .collect()
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct ToolUsage {
+    name: String,
+    calls: u32,
+}
+
+#[derive(SimpleObject)]
+pub struct Day {
+    date: NaiveDate,
+    sessions: u32,
+    messages: u32,
+    /// Estimated from message lengths.
+    tokens: u64,
+    tool_calls: u32,
+    tool_errors: u32,
+    /// Tool calls refused for being over a quota.
+    quota_denials: u32,
+    error_rate: f64,
+    active_time_secs: u64,
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Schedule {
+    name: String,
+    cron: String,
+    enabled: bool,
+    /// `heartbeat`, `prompt` or `custom`.
+    task: String,
+    /// Session the runs go to.
+    session: String,
+    prompt: Option<String>,
+    last_run: Option<DateTime<Utc>>,
+    /// `null` until the schedule first fires.
+    next_run: Option<DateTime<Utc>>,
+    run_count: u64,
+    last_error: Option<String>,
+    #[graphql(skip)]
+    history: std::sync::Arc<agent_core::scheduler::ScheduleHistory>,
+}
+
+#[ComplexObject]
+impl Schedule {
+    /// Recent runs, newest first.
+    async fn runs(
+        &self,
+        #[graphql(default = 20, validator(minimum = 1, maximum = 200))] limit: usize,
+    ) -> Vec<ScheduleRun> {
+        self.history
+            .recent(&self.name, limit)
+            .into_iter()
+            .map(|run| ScheduleRun {
+                fired_at: run.fired_at,
+                finished_at: run.finished_at,
+                status: wire_name(&run.status),
+                error: run.error,
+                summary: run.summary,
+            })
+            .collect()
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct ScheduleRun {
+    fired_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+    /// `fired`, `succeeded` or `failed`.
+    status: String,
+    error: Option<String>,
+    /// Start of the agent's reply, for prompt tasks.
+    summary: Option<String>,
+}
+
+#[derive(SimpleObject)]
+pub struct Plugin {
+    /// `<category>:<name>`.
+    key: String,
+    name: String,
+    version: String,
+    category: String,
+    status: String,
+    description: String,
+}
@@ -0,0 +1,312 @@
+//! Readiness checks behind `GET /health/ready`.
+//!
+//! Where `/health` only says the process is up, readiness checks what a
+//! turn depends on: the model provider answers, the Docker daemon is
+//! running (in docker sandbox mode), skills are indexed, the sessions
+//! directory has room, and plugins report healthy. Each check gives a
+//! [`ComponentStatus`]; any `error` makes the server unready (503), so an
+//! orchestrator can hold traffic or restart it.
+
+use crate::state::AppState;
+use agent_core::config::{AppConfig, ProviderKind, SandboxMode};
+use agent_plugins::PluginStatus;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// How long a provider or Docker check may take.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Free space below which the disk check fails.
+const DISK_ERROR_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Free space below which the disk check is degraded.
+const DISK_LOW_BYTES: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentStatus {
+    /// Not checked in this configuration (e.g. Docker in unsafe mode).
+    Skipped,
+    Ok,
+    /// Working, but something needs attention.
+    Degraded,
+    /// Turns will fail until this is fixed.
+    Error,
+}
+
+/// The result of one check.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ComponentHealth {
+    pub status: ComponentStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// How long the check took, for checks that leave the process.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+}
+
+impl ComponentHealth {
+    fn new(status: ComponentStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: Some(message.into()),
+            latency_ms: None,
+        }
+    }
+
+    fn timed(mut self, started: Instant) -> Self {
+        self.latency_ms = Some(started.elapsed().as_millis() as u64);
+        self
+    }
+}
+
+/// Readiness of the server and each of its dependencies.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Readiness {
+    /// The worst component status (`skipped` counts as `ok`).
+    pub status: ComponentStatus,
+    pub version: String,
+    /// By component: `provider`, `docker`, `skills`, `disk`, `plugins`.
+    pub components: BTreeMap<String, ComponentHealth>,
+}
+
+impl Readiness {
+    /// Ready unless a component has failed.
+    pub fn is_ready(&self) -> bool {
+        self.status != ComponentStatus::Error
+    }
+}
+
+/// Run every check concurrently.
+pub async fn check(state: &AppState) -> Readiness {
+    let config = state.config.read().await.clone();
+    let (provider, docker) = tokio::join!(check_provider(&config), check_docker(&config));
+    let skills = check_skills(state);
+    let disk = check_disk(&sessions_dir(&config));
+    let plugins = check_plugins(&state.plugin_registry.read().await.health_check_all());
+
+    let components: BTreeMap<String, ComponentHealth> = [
+        ("provider", provider),
+        ("docker", docker),
+        ("skills", skills),
+        ("disk", disk),
+        ("plugins", plugins),
+    ]
+    .into_iter()
+    .map(|(name, health)| (name.to_string(), health))
+    .collect();
+    let status = components
+        .values()
+        .map(|c| c.status)
+        .max()
+        .map_or(ComponentStatus::Ok, |s| s.max(ComponentStatus::Ok));
+    Readiness {
+        status,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        components,
+    }
+}
+
+/// Whether an OpenAI-compatible endpoint answers `GET {api_base}/models`.
+/// Any answer but a server error or a rejected key counts as reachable.
+async fn probe_provider(api_base: &str, api_key: Option<&str>) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(CHECK_TIMEOUT)
+        .build()
+        .unwrap_or_default();
+    let mut request = client.get(format!("{}/models", api_base.trim_end_matches('/')));
+    if let Some(key) = api_key {
+        request = request.bearer_auth(key);
+    }
+    let status = request
+        .send()
+        .await
+        .map_err(|e| e.without_url().to_string())?
+        .status();
+    if status.is_server_error() {
+        Err(format!("HTTP {}", status.as_u16()))
+    } else if matches!(
+        status,
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN
+    ) {
+        Err(format!("HTTP {}: API key rejected", status.as_u16()))
+    } else {
+        Ok(())
+    }
+}
+
+/// The primary provider, falling back to the failover endpoints: degraded
+/// when only a failover answers.
+async fn check_provider(config: &AppConfig) -> ComponentHealth {
+    let started = Instant::now();
+    let provider = &config.provider;
+    if provider.kind == ProviderKind::Mock {
+        return ComponentHealth::new(ComponentStatus::Ok, "mock scenario").timed(started);
+    }
+    let primary = match probe_provider(&provider.api_base, provider.api_key.as_deref()).await {
+        Ok(()) => {
+            return ComponentHealth::new(ComponentStatus::Ok, provider.model.clone())
+                .timed(started)
+        }
+        Err(e) => e,
+    };
+    for failover in &provider.failover {
+        if probe_provider(&failover.api_base, failover.api_key.as_deref())
+            .await
+            .is_ok()
+        {
+            return ComponentHealth::new(
+                ComponentStatus::Degraded,
+                format!(
+                    "primary unreachable ({}); failover {} answers",
+                    primary, failover.api_base
+                ),
+            )
+            .timed(started);
+        }
+    }
+    ComponentHealth::new(ComponentStatus::Error, format!("unreachable: {}", primary))
+        .timed(started)
+}
+
+async fn check_docker(config: &AppConfig) -> ComponentHealth {
+    match config.sandbox.mode {
+        SandboxMode::Docker => {}
+        SandboxMode::Native => {
+            return ComponentHealth::new(ComponentStatus::Skipped, "sandbox mode is native")
+        }
+        SandboxMode::Unsafe => {
+            return ComponentHealth::new(ComponentStatus::Skipped, "sandbox mode is unsafe")
+        }
+    }
+    let started = Instant::now();
+    let output = tokio::process::Command::new("docker")
+        .args(["info", "--format", "{{.ServerVersion}}"])
+        .kill_on_drop(true)
+        .output();
+    let health = match tokio::time::timeout(CHECK_TIMEOUT, output).await {
+        Err(_) => ComponentHealth::new(ComponentStatus::Error, "docker info timed out"),
+        Ok(Err(e)) => {
+            ComponentHealth::new(ComponentStatus::Error, format!("docker not runnable: {}", e))
+        }
+        Ok(Ok(out)) if out.status.success() => ComponentHealth::new(
+            ComponentStatus::Ok,
+            format!("daemon {}", String::from_utf8_lossy(&out.stdout).trim()),
+        ),
+        Ok(Ok(out)) => ComponentHealth::new(
+            ComponentStatus::Error,
+            String::from_utf8_lossy(&out.stderr).trim().to_string(),
+        ),
+    };
+    health.timed(started)
+}
+
+fn check_skills(state: &AppState) -> ComponentHealth {
+    let dir = state.skill_indexer.skills_dir();
+    let count = state.skill_indexer.get_skill_index().len();
+    if !dir.is_dir() {
+        let message = format!("{} does not exist", dir.display());
+        ComponentHealth::new(ComponentStatus::Degraded, message)
+    } else {
+        ComponentHealth::new(ComponentStatus::Ok, format!("{} skills indexed", count))
+    }
+}
+
+fn sessions_dir(config: &AppConfig) -> PathBuf {
+    config
+        .session
+        .history_dir
+        .clone()
+        .unwrap_or_else(|| AppConfig::data_dir().join("sessions"))
+}
+
+fn check_disk(dir: &Path) -> ComponentHealth {
+    // The directory may not exist yet; its nearest ancestor is on the
+    // same filesystem.
+    let existing = dir.ancestors().find(|p| p.exists()).unwrap_or(dir);
+    match free_bytes(existing) {
+        None => ComponentHealth::new(ComponentStatus::Skipped, "free space unknown"),
+        Some(free) => {
+            let status = if free < DISK_ERROR_BYTES {
+                ComponentStatus::Error
+            } else if free < DISK_LOW_BYTES {
+                ComponentStatus::Degraded
+            } else {
+                ComponentStatus::Ok
+            };
+            let message = format!(
+                "{} MiB free in {}",
+                free / (1024 * 1024),
+                dir.display()
+            );
+            ComponentHealth::new(status, message)
+        }
+    }
+}
+
+#[cfg(unix)]
+fn free_bytes(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn free_bytes(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Degraded when a plugin reports an error or is unavailable; plugins are
+/// optional, so they never make the server unready.
+fn check_plugins(results: &[(agent_plugins::PluginKey, PluginStatus)]) -> ComponentHealth {
+    let failing: Vec<String> = results
+        .iter()
+        .filter(|(_, status)| matches!(status, PluginStatus::Error | PluginStatus::Unavailable))
+        .map(|(key, status)| format!("{} ({:?})", key.name, status).to_lowercase())
+        .collect();
+    if failing.is_empty() {
+        ComponentHealth::new(ComponentStatus::Ok, format!("{} plugins healthy", results.len()))
+    } else {
+        ComponentHealth::new(
+            ComponentStatus::Degraded,
+            format!("unhealthy: {}", failing.join(", ")),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_plugins::{PluginCategory, PluginKey};
+
+    #[test]
+    fn test_disk_check_uses_nearest_existing_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let health = check_disk(&dir.path().join("not/yet/created"));
+        if cfg!(unix) {
+            assert_ne!(health.status, ComponentStatus::Skipped);
+            assert!(health.message.unwrap().contains("MiB free"));
+        }
+    }
+
+    #[test]
+    fn test_failing_plugins_degrade() {
+        let key = |name: &str| PluginKey::new(PluginCategory::Extension, name);
+        let health = check_plugins(&[(key("jira"), PluginStatus::Running)]);
+        assert_eq!(health.status, ComponentStatus::Ok);
+        let health = check_plugins(&[
+            (key("jira"), PluginStatus::Running),
+            (key("slack"), PluginStatus::Error),
+        ]);
+        assert_eq!(health.status, ComponentStatus::Degraded);
+        assert_eq!(health.message.as_deref(), Some("unhealthy: slack (error)"));
+    }
+}
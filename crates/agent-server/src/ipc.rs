@@ -7,7 +7,7 @@
 //!
 //! Ported from ShellVault's `shellvault-daemon::server`.
 
-use crate::ipc_handlers::handle_message;
+use crate::ipc_handlers::{handle_message, DaemonCommand, DaemonResponse};
 use crate::state::AppState;
 use anyhow::Result;
 use std::path::{Path, PathBuf};
@@ -196,6 +196,31 @@ async fn handle_tcp_connection(
     Ok(())
 }
 
+/// Send one command to a running server's IPC socket and read the reply.
+///
+/// Fails with the connection error when no server is listening, so callers
+/// can fall back to working locally.
+pub async fn send_command(socket_path: &Path, cmd: &DaemonCommand) -> std::io::Result<DaemonResponse> {
+    #[cfg(unix)]
+    let stream = tokio::net::UnixStream::connect(socket_path).await?;
+    #[cfg(windows)]
+    let stream = {
+        let _ = socket_path;
+        let host = std::env::var("AGENT_SHELL_IPC_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
+        let port = std::env::var("AGENT_SHELL_IPC_PORT").unwrap_or_else(|_| "51842".to_string());
+        tokio::net::TcpStream::connect(format!("{}:{}", host, port)).await?
+    };
+
+    let (reader, mut writer) = stream.into_split();
+    let mut message = serde_json::to_string(cmd)?;
+    message.push('\n');
+    writer.write_all(message.as_bytes()).await?;
+
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+    serde_json::from_str(&line).map_err(std::io::Error::from)
+}
+
 /// Set restrictive permissions on the Unix socket and its parent directory.
 #[cfg(unix)]
 fn set_unix_socket_permissions(path: &Path) {
@@ -220,6 +245,31 @@ mod tests {
         assert!(path_str.ends_with("daemon.sock"));
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_send_command_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.sock");
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut line = String::new();
+            BufReader::new(reader).read_line(&mut line).await.unwrap();
+            assert_eq!(line, "{\"cmd\":\"list_plugins\"}\n");
+            writer
+                .write_all(b"{\"status\":\"ok\",\"data\":{\"plugins\":[]}}\n")
+                .await
+                .unwrap();
+        });
+
+        let response = send_command(&path, &DaemonCommand::ListPlugins).await.unwrap();
+        assert_eq!(response, DaemonResponse::ok(serde_json::json!({ "plugins": [] })));
+        assert!(send_command(&dir.path().join("missing.sock"), &DaemonCommand::Ping)
+            .await
+            .is_err());
+    }
+
     #[test]
     fn test_max_message_bytes() {
         // Ensure the constant is reasonable.
@@ -32,6 +32,18 @@ pub enum DaemonCommand {
     /// End a terminal session.
     #[serde(rename = "end_session")]
     EndSession { id: String },
+
+    /// List plugins with their status.
+    #[serde(rename = "list_plugins")]
+    ListPlugins,
+
+    /// Start a plugin by `<category>:<name>` or a name only one plugin has.
+    #[serde(rename = "start_plugin")]
+    StartPlugin { key: String },
+
+    /// Stop a running plugin.
+    #[serde(rename = "stop_plugin")]
+    StopPlugin { key: String },
 }
 
 /// Response sent back over IPC.
@@ -222,11 +234,41 @@ async fn handle_command(cmd: DaemonCommand, state: &AppState) -> String {
                 Err(_) => DaemonResponse::error("Invalid session ID (expected UUID)"),
             }
         }
+
+        DaemonCommand::ListPlugins => {
+            let pr = state.plugin_registry.read().await;
+            DaemonResponse::ok(serde_json::json!({ "plugins": pr.summaries() }))
+        }
+
+        DaemonCommand::StartPlugin { key } => set_plugin_running(&key, true, state).await,
+
+        DaemonCommand::StopPlugin { key } => set_plugin_running(&key, false, state).await,
     };
 
     serde_json::to_string(&response).unwrap_or_default()
 }
 
+/// Start or stop the plugin `key` names, answering with its new summary.
+async fn set_plugin_running(key: &str, start: bool, state: &AppState) -> DaemonResponse {
+    let mut pr = state.plugin_registry.write().await;
+    let Some(resolved) = pr.resolve_key(key) else {
+        return DaemonResponse::error(format!("Plugin not found: {}", key));
+    };
+    let result = if start {
+        pr.start(&resolved).await
+    } else {
+        pr.stop(&resolved).await
+    };
+    match result {
+        Ok(()) => {
+            let id = resolved.id();
+            let summary = pr.summaries().into_iter().find(|s| s.key == id);
+            DaemonResponse::ok(serde_json::json!({ "plugin": summary }))
+        }
+        Err(e) => DaemonResponse::error(e),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -241,6 +283,7 @@ mod tests {
         let mut config = agent_core::config::AppConfig::default();
         config.session.history_dir = Some(tmp.path().to_path_buf());
         let skill_indexer = Arc::new(agent_skills::SkillIndexer::new(tmp.path().join("skills")));
+        let agents = agent_core::agents::AgentStore::load(tmp.path().join("agents.json"));
 
         // Leak the TempDir so it outlives the test.
         std::mem::forget(tmp);
@@ -260,10 +303,13 @@ mod tests {
             agent_loop: Arc::new(RwLock::new(
                 agent_core::agent_loop::AgentLoop::new(config, registry).unwrap(),
             )),
+            agents: Arc::new(RwLock::new(agents)),
             plugin_registry,
             skill_indexer,
             hook_backend: Arc::new(Mutex::new(hook_backend)),
             terminal_sessions: Arc::new(RwLock::new(TerminalSessionManager::new())),
+            runs: Arc::default(),
+            session_locks: Arc::default(),
             started_at: Utc::now(),
         }
     }
@@ -281,6 +327,13 @@ mod tests {
             DaemonCommand::EndSession {
                 id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
             },
+            DaemonCommand::ListPlugins,
+            DaemonCommand::StartPlugin {
+                key: "skill:jira-sync".to_string(),
+            },
+            DaemonCommand::StopPlugin {
+                key: "jira-sync".to_string(),
+            },
         ];
 
         for cmd in &commands {
@@ -435,4 +488,58 @@ mod tests {
             other => panic!("Expected Error, got {:?}", other),
         }
     }
+
+    #[tokio::test]
+    async fn test_plugin_commands() {
+        use agent_plugins::marketplace::{InstalledPlugin, PluginKind};
+        let state = test_state();
+        let artifact = tempfile::NamedTempFile::new().unwrap();
+        let plugin = InstalledPlugin {
+            name: "jira-sync".into(),
+            version: "1.2.0".into(),
+            kind: PluginKind::Skill,
+            description: "Sync tickets".into(),
+            sha256: String::new(),
+            path: artifact.path().to_path_buf(),
+            installed_at: Utc::now(),
+            permissions: Default::default(),
+        };
+        state
+            .plugin_registry
+            .write()
+            .await
+            .register(Box::new(plugin))
+            .unwrap();
+
+        let send = |msg: &'static str| {
+            let state = state.clone();
+            async move {
+                let response = handle_message(msg, &state).await;
+                serde_json::from_str::<DaemonResponse>(&response).unwrap()
+            }
+        };
+        let DaemonResponse::Ok { data: Some(data) } =
+            send(r#"{"cmd":"start_plugin","key":"jira-sync"}"#).await
+        else {
+            panic!("start failed");
+        };
+        assert_eq!(data["plugin"]["key"], "skill:jira-sync");
+        assert_eq!(data["plugin"]["status"], "running");
+
+        let DaemonResponse::Ok { data: Some(data) } = send(r#"{"cmd":"list_plugins"}"#).await
+        else {
+            panic!("list failed");
+        };
+        assert_eq!(data["plugins"][0]["version"], "1.2.0");
+
+        send(r#"{"cmd":"stop_plugin","key":"skill:jira-sync"}"#).await;
+        assert!(matches!(
+            send(r#"{"cmd":"stop_plugin","key":"jira-sync"}"#).await,
+            DaemonResponse::Error { message } if message.contains("not running")
+        ));
+        assert!(matches!(
+            send(r#"{"cmd":"start_plugin","key":"nope"}"#).await,
+            DaemonResponse::Error { .. }
+        ));
+    }
 }
@@ -1,58 +1,127 @@
+pub mod backup;
+pub mod caller;
+pub mod editor;
+pub mod error;
+pub mod goals;
+pub mod health;
+#[cfg(feature = "graphql")]
+pub mod graphql;
 pub mod ipc;
 pub mod ipc_handlers;
+pub mod local;
+pub mod logs;
+pub mod openapi;
+pub mod preferences;
+pub mod request_id;
+pub mod retention;
 pub mod routes;
+pub mod runs;
+pub mod share;
 pub mod state;
+#[cfg(feature = "embed-ui")]
+pub mod ui;
+pub mod validation;
 
 use agent_core::config::AppConfig;
 use agent_core::tool_registry::ToolRegistry;
 use axum::extract::Request;
-use axum::http::StatusCode;
 use axum::middleware::{self, Next};
 use axum::response::{IntoResponse, Response};
 use axum::Router;
 use std::sync::Arc;
-use subtle::ConstantTimeEq;
 use tower_http::cors::CorsLayer;
+use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::trace::TraceLayer;
 
 pub use state::AppState;
 
-/// Middleware that validates a bearer token from the Authorization header.
+/// Middleware that validates a bearer token from the Authorization header
+/// and records the [`caller::Caller`] it belongs to.
 ///
 /// Uses constant-time comparison (`subtle::ConstantTimeEq`) to prevent
 /// timing-based side-channel attacks that could leak the token.
 async fn auth_middleware(
     State(state): axum::extract::State<AppState>,
-    req: Request,
+    mut req: Request,
     next: Next,
 ) -> Response {
+    if req.extensions().get::<local::LocalTransport>().is_some() {
+        req.extensions_mut().insert(caller::Caller::unrestricted());
+        return next.run(req).await;
+    }
+
     let config = state.config.read().await;
-    let expected = match &config.server.auth_token {
-        Some(t) => t.clone(),
-        None => return next.run(req).await,
+    let caller = if !config.server.auth_enabled() {
+        Some(caller::Caller::unrestricted())
+    } else {
+        bearer_token(&req).and_then(|token| caller::Caller::authenticate(&config.server, token))
     };
     drop(config);
 
-    let auth_header = req
-        .headers()
+    match caller {
+        Some(caller) => {
+            req.extensions_mut().insert(caller);
+            next.run(req).await
+        }
+        None => unauthorized(),
+    }
+}
+
+fn bearer_token(req: &Request) -> Option<&str> {
+    req.headers()
         .get(axum::http::header::AUTHORIZATION)
-        .and_then(|v| v.to_str().ok());
-
-    match auth_header {
-        Some(value) if value.starts_with("Bearer ") => {
-            let provided = &value[7..];
-            // Constant-time comparison: both operands are compared in full,
-            // regardless of where they first differ.
-            if provided.as_bytes().ct_eq(expected.as_bytes()).into() {
-                next.run(req).await
-            } else {
-                (StatusCode::UNAUTHORIZED, "Invalid or missing bearer token").into_response()
-            }
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+}
+
+/// Whether the request's bearer token is `expected`.
+///
+/// Constant-time comparison: both operands are compared in full,
+/// regardless of where they first differ.
+#[cfg(feature = "graphql")]
+fn bearer_matches(req: &Request, expected: &str) -> bool {
+    use subtle::ConstantTimeEq;
+    bearer_token(req).is_some_and(|provided| provided.as_bytes().ct_eq(expected.as_bytes()).into())
+}
+
+/// Middleware for admin-scope routes: only `server.admin_token` and API
+/// keys with `admin = true` are accepted, even over the local socket. The
+/// regular `auth_token` and other API keys are answered with 403 rather
+/// than 401.
+#[cfg(feature = "graphql")]
+async fn admin_middleware(
+    State(state): axum::extract::State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let config = state.config.read().await;
+    let server = config.server.clone();
+    drop(config);
+
+    if server.admin_token.as_deref().is_some_and(|admin| bearer_matches(&req, admin)) {
+        req.extensions_mut().insert(caller::Caller::unrestricted());
+        return next.run(req).await;
+    }
+    let caller = bearer_token(&req).and_then(|token| caller::Caller::authenticate(&server, token));
+    match caller {
+        Some(caller) if caller.is_admin_key() => {
+            req.extensions_mut().insert(caller);
+            next.run(req).await
         }
-        _ => (StatusCode::UNAUTHORIZED, "Invalid or missing bearer token").into_response(),
+        Some(_) => error::ApiError::new(
+            error::ErrorCode::Forbidden,
+            "This endpoint needs admin scope",
+        )
+        .into_response(),
+        None => unauthorized(),
     }
 }
 
+fn unauthorized() -> axum::response::Response {
+    error::ApiError::new(error::ErrorCode::Unauthorized, "Invalid or missing bearer token")
+        .into_response()
+}
+
 use axum::extract::State;
 
 /// Build the axum Router with all routes and middleware.
@@ -61,35 +130,77 @@ use axum::extract::State;
 /// (CORS, terminal auth gate). Runtime config changes are handled via
 /// the `Arc<RwLock<AppConfig>>` in handlers.
 pub fn build_router(state: AppState, config_snapshot: &AppConfig) -> Router {
+    // Bodies over a group's limit are refused with 413 before they are read.
+    fn limited(routes: Router<AppState>, max_bytes: usize) -> Router<AppState> {
+        routes
+            .layer(axum::extract::DefaultBodyLimit::max(max_bytes))
+            .layer(RequestBodyLimitLayer::new(max_bytes))
+    }
+
     // Protected routes (chat, sessions, plugins) — require auth when token is configured.
-    let mut protected = Router::new()
-        .merge(routes::chat_routes())
+    let mut api = Router::new()
         .merge(routes::session_routes())
+        .merge(routes::agent_routes())
         .merge(routes::session_message_routes())
         .merge(routes::config_routes())
         .merge(routes::plugin_routes())
         .merge(routes::skill_routes())
         .merge(routes::context_routes())
+        .merge(routes::schedule_routes())
         .merge(routes::analytics_routes())
-        .merge(routes::terminal_session_routes());
+        .merge(routes::terminal_session_routes())
+        .merge(routes::preference_routes())
+        .merge(routes::editor_routes())
+        .merge(routes::log_routes())
+        .merge(routes::notification_routes())
+        .merge(routes::tool_settings_routes());
 
-    // Terminal routes expose a remote shell — only enable when auth is configured.
-    if config_snapshot.server.auth_token.is_some() {
-        protected = protected.merge(routes::terminal_routes());
+    // Terminal and direct tool routes expose a remote shell — only enable
+    // when auth is configured.
+    if config_snapshot.server.auth_enabled() {
+        api = api
+            .merge(routes::terminal_routes())
+            .merge(routes::tool_routes());
     } else {
         tracing::warn!(
-            "Terminal WebSocket disabled: auth_token is not configured. \
-             Set server.auth_token in config to enable the terminal endpoint."
+            "Terminal WebSocket and tool re-run disabled: auth_token is not configured. \
+             Set server.auth_token in config to enable these endpoints."
         );
     }
 
+    let protected = Router::new()
+        // Inline images arrive base64-encoded, a third larger than the upload limit.
+        .merge(limited(
+            routes::chat_routes(),
+            config_snapshot.server.max_upload_bytes / 3 * 4 + 64 * 1024,
+        ))
+        .merge(limited(routes::audio_routes(), config_snapshot.audio.max_upload_bytes))
+        .merge(limited(routes::file_routes(), config_snapshot.server.max_upload_bytes))
+        .merge(limited(api, config_snapshot.server.max_body_bytes));
+
     let protected = protected.route_layer(middleware::from_fn_with_state(
         state.clone(),
         auth_middleware,
     ));
 
-    // Public routes (health) — never require auth.
-    let public = Router::new().merge(routes::health_routes());
+    // Admin routes — only with an admin token or key, never with auth_token.
+    #[cfg(feature = "graphql")]
+    let protected = if config_snapshot.server.admin_enabled() {
+        let admin = limited(
+            graphql::graphql_routes(&state),
+            config_snapshot.server.max_body_bytes,
+        )
+        .route_layer(middleware::from_fn_with_state(state.clone(), admin_middleware));
+        protected.merge(admin)
+    } else {
+        protected
+    };
+
+    // Public routes (health, share links) — never require auth.
+    let public = Router::new()
+        .merge(routes::health_routes())
+        .merge(routes::share_routes())
+        .merge(openapi::openapi_routes());
 
     let spa = routes::spa_routes();
 
@@ -100,11 +211,14 @@ pub fn build_router(state: AppState, config_snapshot: &AppConfig) -> Router {
         .with_state(state.clone());
 
     // Middleware stack.
-    app = app.layer(TraceLayer::new_for_http());
+    app = app
+        .layer(middleware::from_fn(validation::problem_details))
+        .layer(middleware::from_fn(request_id::assign))
+        .layer(TraceLayer::new_for_http());
 
     // CORS configuration.
     if config_snapshot.server.cors {
-        let cors = if config_snapshot.server.auth_token.is_some() {
+        let cors = if config_snapshot.server.auth_enabled() {
             // Restrictive CORS when auth is enabled.
             let origins: Vec<String> = if config_snapshot.server.cors_origins.is_empty() {
                 // Default: only allow the local UI origin.
@@ -115,10 +229,25 @@ pub fn build_router(state: AppState, config_snapshot: &AppConfig) -> Router {
             let parsed_origins: Vec<axum::http::HeaderValue> =
                 origins.iter().filter_map(|o| o.parse().ok()).collect();
             CorsLayer::new()
-                .allow_methods([axum::http::Method::GET, axum::http::Method::POST, axum::http::Method::PUT])
+                .allow_methods([
+                    axum::http::Method::GET,
+                    axum::http::Method::POST,
+                    axum::http::Method::PUT,
+                    axum::http::Method::DELETE,
+                    axum::http::Method::PATCH,
+                ])
                 .allow_headers([
                     axum::http::header::CONTENT_TYPE,
                     axum::http::header::AUTHORIZATION,
+                    axum::http::HeaderName::from_static(request_id::REQUEST_ID_HEADER),
+                    axum::http::HeaderName::from_static("last-event-id"),
+                ])
+                .expose_headers([
+                    axum::http::HeaderName::from_static(request_id::REQUEST_ID_HEADER),
+                    axum::http::HeaderName::from_static(routes::RUN_ID_HEADER),
+                    axum::http::HeaderName::from_static(routes::RUN_SESSION_HEADER),
+                    axum::http::HeaderName::from_static(routes::TOTAL_COUNT_HEADER),
+                    axum::http::HeaderName::from_static(routes::NEXT_CURSOR_HEADER),
                 ])
                 .allow_origin(parsed_origins)
         } else {
@@ -139,7 +268,9 @@ pub fn build_router(state: AppState, config_snapshot: &AppConfig) -> Router {
 /// 2. **IPC** -- Unix socket (or TCP on Windows) daemon for shell hook messages.
 ///
 /// Also spawns a background task that drains capture events from the
-/// [`HookBackend`] and feeds them into the [`TerminalSessionManager`].
+/// [`HookBackend`] and feeds them into the [`TerminalSessionManager`], and
+/// ones that report missed analytics goals (see [`goals`]) and prune old
+/// sessions (see [`retention`]).
 pub async fn serve(
     config: AppConfig,
     tool_registry: Arc<ToolRegistry>,
@@ -170,6 +301,10 @@ pub async fn serve(
         tracing::debug!("Hook event processor stopped");
     });
 
+    goals::spawn_watcher(state.clone());
+    retention::spawn_pruner(state.clone());
+    backup::spawn_scheduler(state.clone());
+
     // Spawn IPC server.
     let ipc_state = state.clone();
     let socket_path = ipc::default_socket_path();
@@ -181,25 +316,105 @@ pub async fn serve(
     });
 
     // Build and start HTTP server.
-    let router = build_router(state, &config);
-    let addr = format!("{}:{}", config.server.host, config.server.port);
-    tracing::info!("Starting server on {}", addr);
+    let router = build_router(state.clone(), &config);
 
-    if config.server.auth_token.is_none() {
+    if !config.server.auth_enabled() && config.server.listen_socket.is_none() {
         tracing::warn!("No auth_token configured — server is unauthenticated!");
     }
 
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    let http_result = axum::serve(listener, router).await;
+    let http_result = match &config.server.listen_socket {
+        Some(path) => {
+            let router = router.layer(axum::Extension(local::LocalTransport));
+            #[cfg(unix)]
+            let listener = local::bind_unix(path).await?;
+            #[cfg(windows)]
+            let listener = local::NamedPipeListener::bind(path)?;
+            tracing::info!("Starting server on {}", path.display());
+            serve_until_shutdown(listener, router).await
+        }
+        None => {
+            let addr = format!("{}:{}", config.server.host, config.server.port);
+            tracing::info!("Starting server on {}", addr);
+            let listener = tokio::net::TcpListener::bind(&addr).await?;
+            serve_until_shutdown(listener, router).await
+        }
+    };
 
     // When HTTP server stops, signal IPC to shut down too.
     let _ = shutdown_tx.send(true);
     let _ = ipc_handle.await;
 
+    #[cfg(unix)]
+    if let Some(path) = &config.server.listen_socket {
+        let _ = std::fs::remove_file(path);
+    }
+
+    if let Err(e) = state.session_manager.read().await.save_all_async().await {
+        tracing::error!("Failed to flush sessions on shutdown: {}", e);
+    }
+    state.plugin_registry.write().await.stop_all_async().await;
+    tracing::info!("Server stopped");
+
     http_result?;
     Ok(())
 }
 
+/// Serve `router` until SIGTERM/SIGINT, then stop accepting connections and
+/// let in-flight requests (including streamed agent runs) finish, up to
+/// [`DRAIN_TIMEOUT`].
+async fn serve_until_shutdown<L>(listener: L, router: Router) -> std::io::Result<()>
+where
+    L: axum::serve::Listener,
+    L::Addr: std::fmt::Debug,
+{
+    let (draining_tx, mut draining_rx) = tokio::sync::watch::channel(false);
+    let server = axum::serve(listener, router).with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        tracing::info!("Shutting down; draining in-flight requests");
+        let _ = draining_tx.send(true);
+    });
+    tokio::select! {
+        result = server => result,
+        _ = async {
+            let _ = draining_rx.wait_for(|draining| *draining).await;
+            tokio::time::sleep(DRAIN_TIMEOUT).await;
+        } => {
+            tracing::warn!("Drain timed out after {:?}; closing remaining connections", DRAIN_TIMEOUT);
+            Ok(())
+        }
+    }
+}
+
+/// How long shutdown waits for in-flight requests before giving up.
+const DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Resolve when the process receives Ctrl+C or (on Unix) SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to install SIGTERM handler: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,18 +424,29 @@ mod tests {
 
     /// Build a test router with the given auth token and a temp session dir.
     fn test_router(auth_token: Option<String>) -> Router {
+        test_router_with(|config| config.server.auth_token = auth_token)
+    }
+
+    /// Build a test router with a temp session dir and custom config.
+    fn test_router_with(configure: impl FnOnce(&mut AppConfig)) -> Router {
+        test_router_with_plugins(configure, agent_plugins::PluginRegistry::new())
+    }
+
+    fn test_router_with_plugins(
+        configure: impl FnOnce(&mut AppConfig),
+        plugins: agent_plugins::PluginRegistry,
+    ) -> Router {
         let tmp = tempfile::TempDir::new().unwrap();
         let mut config = AppConfig::default();
-        config.server.auth_token = auth_token;
         config.session.history_dir = Some(tmp.path().to_path_buf());
+        config.session.agents_path = Some(tmp.path().join("agents.json"));
+        configure(&mut config);
         let skill_indexer = Arc::new(agent_skills::SkillIndexer::new(tmp.path().join("skills")));
         // Keep the TempDir alive by leaking it (tests are short-lived).
         std::mem::forget(tmp);
 
         let registry = Arc::new(ToolRegistry::new());
-        let plugin_registry = Arc::new(tokio::sync::RwLock::new(
-            agent_plugins::PluginRegistry::new(),
-        ));
+        let plugin_registry = Arc::new(tokio::sync::RwLock::new(plugins));
         let config_snapshot = config.clone();
         let state = AppState::new(config, registry, plugin_registry, skill_indexer)
             .expect("Failed to create test app state");
@@ -240,6 +466,62 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn test_health_ready_reports_components() {
+        let ready = |app: Router| async move {
+            let req = Request::builder()
+                .uri("/health/ready")
+                .body(Body::empty())
+                .unwrap();
+            let resp = app.oneshot(req).await.unwrap();
+            let status = resp.status();
+            let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+        };
+
+        let base = mock_provider("Hi").await;
+        let app = test_router_with(|config| {
+            config.server.auth_token = Some("secret-token".into());
+            config.provider.api_base = base;
+            config.sandbox.mode = agent_core::config::SandboxMode::Unsafe;
+        });
+        let (status, body) = ready(app).await;
+        assert_eq!(status, StatusCode::OK);
+        let components = &body["components"];
+        assert_eq!(components["provider"]["status"], "ok");
+        assert_eq!(components["docker"]["status"], "skipped");
+        assert_eq!(components["plugins"]["status"], "ok");
+        // The test skills directory is never created.
+        assert_eq!(components["skills"]["status"], "degraded");
+        assert_eq!(body["status"], "degraded");
+
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/v1", closed.local_addr().unwrap());
+        drop(closed);
+        let app = test_router_with(|config| {
+            config.provider.api_base = base;
+            config.sandbox.mode = agent_core::config::SandboxMode::Unsafe;
+        });
+        let (status, body) = ready(app).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["components"]["provider"]["status"], "error");
+        assert_eq!(body["status"], "error");
+    }
+
+    #[tokio::test]
+    async fn test_local_transport_skips_token() {
+        let app = test_router(Some("secret-token".into()))
+            .layer(axum::Extension(local::LocalTransport));
+
+        let req = Request::builder()
+            .uri("/v1/sessions")
+            .body(Body::empty())
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
     #[tokio::test]
     async fn test_protected_route_rejects_without_token() {
         let app = test_router(Some("secret-token".into()));
@@ -340,4 +622,1763 @@ mod tests {
         let resp = app.oneshot(req).await.unwrap();
         assert_ne!(resp.status(), StatusCode::BAD_REQUEST);
     }
+
+    #[tokio::test]
+    async fn test_terminal_failures_disabled_by_default() {
+        let app = test_router(None);
+
+        let req = Request::builder()
+            .uri("/v1/terminal-sessions/failures")
+            .body(Body::empty())
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["enabled"], false);
+        assert!(json["failures"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ask_about_failure_forbidden_when_disabled() {
+        let app = test_router(None);
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/terminal-sessions/550e8400-e29b-41d4-a716-446655440000/commands/550e8400-e29b-41d4-a716-446655440001/ask")
+            .body(Body::empty())
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_run_unknown_tool_not_found() {
+        let app = test_router(Some("secret-token".into()));
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/tools/no_such_tool/run")
+            .header("Authorization", "Bearer secret-token")
+            .header("Content-Type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_delete_unknown_message_not_found() {
+        let app = test_router(None);
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/sessions")
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"name":"edit-test"}"#))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let id = json["id"].as_str().unwrap();
+
+        let req = Request::builder()
+            .method("DELETE")
+            .uri(format!("/v1/sessions/{}/messages/missing", id))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = Request::builder()
+            .method("POST")
+            .uri(format!("/v1/sessions/{}/messages/missing/feedback", id))
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"rating":"up"}"#))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = Request::builder()
+            .method("POST")
+            .uri(format!("/v1/sessions/{}/messages/missing/regenerate", id))
+            .header("Content-Type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_into_workspace() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let root = workspace.path().to_path_buf();
+        let app = test_router_with(|config| config.sandbox.workspace_root = Some(root));
+
+        let body = "--XBOUNDARY\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"../data set.csv\"\r\n\
+             Content-Type: text/csv\r\n\r\n\
+             a,b\n1,2\n\r\n\
+             --XBOUNDARY--\r\n";
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/files")
+            .header("Content-Type", "multipart/form-data; boundary=XBOUNDARY")
+            .body(Body::from(body))
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json[0]["name"], "data_set.csv");
+        let path = std::path::PathBuf::from(json[0]["path"].as_str().unwrap());
+        assert!(path.starts_with(workspace.path().join(".agent-shell/uploads")));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "a,b\n1,2\n");
+    }
+
+    #[tokio::test]
+    async fn test_upload_goes_to_session_workspace_and_obeys_path_rules() {
+        let (default, repo) = (tempfile::TempDir::new().unwrap(), tempfile::TempDir::new().unwrap());
+        let (default_root, repo_root) = (default.path().to_path_buf(), repo.path().to_path_buf());
+        let app = test_router_with(|config| {
+            config.sandbox.workspace_root = Some(default_root);
+            config.sandbox.workspaces.insert("repo".into(), repo_root);
+            config.sandbox.paths.insert("*.pem".into(), agent_core::config::PathAccess::Deny);
+        });
+        let upload = |id: &str, name: &str| {
+            let body = format!(
+                "--XBOUNDARY\r\n\
+                 Content-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\r\n\
+                 hi\r\n\
+                 --XBOUNDARY--\r\n",
+                name
+            );
+            Request::builder()
+                .method("POST")
+                .uri(format!("/v1/files?session_id={}", id))
+                .header("Content-Type", "multipart/form-data; boundary=XBOUNDARY")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/sessions")
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"name":"uploads"}"#))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let id = json["id"].as_str().unwrap().to_string();
+        let req = Request::builder()
+            .method("PUT")
+            .uri(format!("/v1/sessions/{}/workspaces", id))
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"workspaces":["repo"]}"#))
+            .unwrap();
+        assert_eq!(app.clone().oneshot(req).await.unwrap().status(), StatusCode::OK);
+
+        let resp = app.clone().oneshot(upload(&id, "notes.txt")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let path = std::path::PathBuf::from(json[0]["path"].as_str().unwrap());
+        let expected = repo.path().canonicalize().unwrap().join(".agent-shell/uploads").join(&id);
+        assert_eq!(path, expected.join("notes.txt"));
+        assert!(!default.path().join(".agent-shell").exists());
+
+        // Files a rule denies are refused.
+        let resp = app.oneshot(upload(&id, "key.pem")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(!expected.join("key.pem").exists());
+    }
+
+    #[tokio::test]
+    async fn test_session_workspaces_in_context() {
+        let (repo_a, repo_b) = (tempfile::TempDir::new().unwrap(), tempfile::TempDir::new().unwrap());
+        let (a, b) = (repo_a.path().to_path_buf(), repo_b.path().to_path_buf());
+        let app = test_router_with(|config| {
+            config.sandbox.workspaces.insert("repo-a".into(), a);
+            config.sandbox.workspaces.insert("repo-b".into(), b);
+        });
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/sessions")
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"name":"multi-root"}"#))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let id = json["id"].as_str().unwrap();
+
+        let put = |body: &'static str| {
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/v1/sessions/{}/workspaces", id))
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let resp = app.clone().oneshot(put(r#"{"workspaces":["nope"]}"#)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = app.clone().oneshot(put(r#"{"workspaces":["repo-b","repo-a"]}"#)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = Request::builder()
+            .uri(format!("/v1/context?session_id={}", id))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let workspaces = json["workspaces"].as_array().unwrap();
+        assert_eq!(workspaces.len(), 2);
+        assert_eq!(workspaces[0]["name"], "repo-a");
+        assert_eq!(workspaces[0]["primary"], false);
+        assert_eq!(workspaces[1]["name"], "repo-b");
+        assert_eq!(workspaces[1]["primary"], true);
+        assert_eq!(workspaces[1]["active"], true);
+    }
+
+    #[tokio::test]
+    async fn test_preferences_roundtrip() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("ui-preferences.json");
+        let app = test_router_with(|config| config.server.preferences_path = Some(path));
+
+        let req = Request::builder()
+            .method("PUT")
+            .uri("/v1/preferences")
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"theme":"light","font_size":15}"#))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = Request::builder()
+            .uri("/v1/preferences")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["theme"], "light");
+        assert_eq!(json["font_size"], 15);
+        assert_eq!(json["collapsed_tool_cards"], true);
+
+        let req = Request::builder()
+            .method("PUT")
+            .uri("/v1/preferences")
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"font_size":99}"#))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_analytics_timeseries_days() {
+        let app = test_router(None);
+
+        let req = Request::builder()
+            .uri("/v1/analytics/timeseries?days=7")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["points"].as_array().unwrap().len(), 7);
+
+        let req = Request::builder()
+            .uri("/v1/analytics/timeseries?days=0")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_fork_session() {
+        let app = test_router(None);
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/sessions")
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"name":"base"}"#))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let id = created["id"].as_str().unwrap();
+
+        let req = Request::builder()
+            .method("POST")
+            .uri(format!("/v1/sessions/{id}/fork"))
+            .header("Content-Type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let forked: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(forked["name"], "base (fork)");
+        assert_ne!(forked["id"], created["id"]);
+
+        let req = Request::builder()
+            .method("POST")
+            .uri(format!("/v1/sessions/{}/fork", uuid::Uuid::new_v4()))
+            .header("Content-Type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_session_rollback() {
+        let app = test_router(None);
+        let send = |method: &str, uri: &str, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let resp = app
+            .clone()
+            .oneshot(send("POST", "/v1/sessions", r#"{"name":"undo"}"#))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let id = created["id"].as_str().unwrap();
+
+        let req = Request::builder()
+            .uri(format!("/v1/sessions/{id}/checkpoints"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body.as_ref(), b"[]");
+
+        let resp = app
+            .clone()
+            .oneshot(send("POST", &format!("/v1/sessions/{id}/rollback"), "{}"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let resp = app
+            .clone()
+            .oneshot(send(
+                "POST",
+                &format!("/v1/sessions/{id}/rollback"),
+                r#"{"checkpoint":"missing","restore_files":false}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = Request::builder()
+            .uri(format!("/v1/sessions/{id}/changes"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body.as_ref(), b"[]");
+
+        let resp = app
+            .clone()
+            .oneshot(send(
+                "POST",
+                &format!("/v1/sessions/{id}/changes/revert"),
+                r#"{"path":"/tmp/untouched.txt"}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let resp = app
+            .clone()
+            .oneshot(send(
+                "POST",
+                &format!("/v1/sessions/{id}/changes/accept"),
+                r#"{"path":"/tmp/untouched.txt"}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let resp = app
+            .oneshot(send("POST", &format!("/v1/sessions/{id}/changes/revert"), "{}"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_analytics_report_formats() {
+        let app = test_router(None);
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let resp = app
+            .clone()
+            .oneshot(get("/v1/analytics/report?format=html"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "text/html; charset=utf-8");
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("<h1>Weekly Report</h1>"));
+
+        let resp = app
+            .clone()
+            .oneshot(get("/v1/analytics/report?period=month&format=pdf"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "application/pdf");
+        assert!(resp.headers()["content-disposition"]
+            .to_str()
+            .unwrap()
+            .starts_with("attachment;"));
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.starts_with(b"%PDF-"));
+
+        let resp = app
+            .oneshot(get("/v1/analytics/report?format=docx"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let app = test_router_with(|config| {
+            config.analytics.goals = vec![agent_core::config::GoalConfig {
+                name: "Few errors".into(),
+                metric: agent_core::config::GoalMetric::ToolErrorRate,
+                period: agent_core::config::GoalPeriod::Week,
+                min: None,
+                max: Some(5.0),
+            }];
+        });
+        let resp = app.oneshot(get("/v1/analytics/goals")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let goals: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(goals[0]["name"], "Few errors");
+        assert_eq!(goals[0]["target"], "≤ 5%");
+    }
+
+    #[tokio::test]
+    async fn test_session_share_link() {
+        let app = test_router(None);
+        let send = |method: &str, uri: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(Body::from("{}"))
+                .unwrap()
+        };
+        let json = |body: axum::body::Bytes| -> serde_json::Value {
+            serde_json::from_slice(&body).unwrap()
+        };
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/sessions")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(r#"{"name":"shared <b>"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let id = json(body)["id"].as_str().unwrap().to_string();
+
+        let share = format!("/v1/sessions/{id}/share");
+        let resp = app.clone().oneshot(send("POST", &share)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let shared = json(body);
+        let url = shared["url"].as_str().unwrap().to_string();
+        assert_eq!(shared["token"].as_str().unwrap().len(), 64);
+
+        // Sharing again returns the same link.
+        let resp = app.clone().oneshot(send("POST", &share)).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(json(body)["url"], url.as_str());
+
+        let resp = app.clone().oneshot(send("GET", &url)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html"));
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("<h1>shared &lt;b&gt;</h1>"));
+
+        let resp = app.clone().oneshot(send("DELETE", &share)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let resp = app.clone().oneshot(send("GET", &url)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let resp = app.oneshot(send("DELETE", &share)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_editor_action_rejects_bad_requests() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        std::fs::write(workspace.path().join("main.rs"), "fn main() {}\n").unwrap();
+        let root = workspace.path().to_path_buf();
+        let app = test_router_with(|config| config.sandbox.workspace_root = Some(root));
+
+        let cases = [
+            (r#"{"path":"../outside.rs","instruction":"fix"}"#, StatusCode::FORBIDDEN),
+            (r#"{"path":"main.rs","instruction":"  "}"#, StatusCode::BAD_REQUEST),
+            (
+                r#"{"path":"main.rs","instruction":"fix","selection":{"start_line":5,"end_line":6}}"#,
+                StatusCode::BAD_REQUEST,
+            ),
+            (r#"{"path":"missing.rs","instruction":"fix"}"#, StatusCode::NOT_FOUND),
+        ];
+        let request = |body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/v1/editor/actions")
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        for (body, expected) in cases {
+            let resp = app.clone().oneshot(request(body)).await.unwrap();
+            assert_eq!(resp.status(), expected, "{body}");
+        }
+
+        // Without a workspace root, absolute paths elsewhere are still refused.
+        let app = test_router(None);
+        let body = r#"{"path":"/etc/hostname","instruction":"fix"}"#;
+        let resp = app.oneshot(request(body)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_audio_endpoints_proxy_to_configured_servers() {
+        // Unconfigured: both endpoints report 503.
+        let app = test_router(None);
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/audio/speech")
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"input":"hi"}"#))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // A stand-in for a Whisper/TTS server.
+        let upstream = Router::new()
+            .route(
+                "/v1/audio/transcriptions",
+                axum::routing::post(|| async { axum::Json(serde_json::json!({"text": " hello "})) }),
+            )
+            .route(
+                "/v1/audio/speech",
+                axum::routing::post(|| async {
+                    ([(axum::http::header::CONTENT_TYPE, "audio/mpeg")], "MP3DATA")
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/v1", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, upstream).await.unwrap() });
+
+        let app = test_router_with(|config| {
+            config.audio.transcription_url = Some(base.clone());
+            config.audio.speech_url = Some(base);
+        });
+
+        let boundary = "XBOUNDARY";
+        let body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.webm\"\r\n\
+             Content-Type: audio/webm\r\n\r\nRIFF\r\n--{boundary}--\r\n"
+        );
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/audio/transcriptions")
+            .header("Content-Type", format!("multipart/form-data; boundary={boundary}"))
+            .body(Body::from(body))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["text"], "hello");
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/audio/speech")
+            .header("Content-Type", "application/json")
+            .body(Body::from(r#"{"input":"hello"}"#))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "audio/mpeg");
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"MP3DATA");
+    }
+
+    /// A stand-in for an OpenAI-compatible provider that streams `reply`
+    /// and reports usage. Returns its base URL.
+    async fn mock_provider(reply: &'static str) -> String {
+        mock_provider_gated(reply, None).await
+    }
+
+    /// A provider that answers each call once `gate` is notified.
+    async fn mock_provider_gated(
+        reply: &'static str,
+        gate: Option<Arc<tokio::sync::Notify>>,
+    ) -> String {
+        let upstream = Router::new().route(
+            "/v1/chat/completions",
+            axum::routing::post(move || async move {
+                if let Some(gate) = gate {
+                    gate.notified().await;
+                }
+                let chunk = |rest: String| {
+                    format!(
+                        "data: {{\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\
+                         \"created\":0,\"model\":\"m\",{}}}\n\n",
+                        rest
+                    )
+                };
+                let delta = serde_json::json!([{"index": 0, "delta": {"content": reply}}]);
+                let body = chunk(format!("\"choices\":{}", delta))
+                    + &chunk(
+                        r#""choices":[],"usage":{"prompt_tokens":1000,"completion_tokens":500,"total_tokens":1500}"#
+                            .to_string(),
+                    )
+                    + "data: [DONE]\n\n";
+                ([(axum::http::header::CONTENT_TYPE, "text/event-stream")], body)
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/v1", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, upstream).await.unwrap() });
+        base
+    }
+
+    #[tokio::test]
+    async fn test_chat_streams_session_usage() {
+        let base = mock_provider("Hi").await;
+        let app = test_router_with(|config| {
+            config.provider.api_base = base;
+            config.provider.input_cost_per_mtok = Some(3.0);
+            config.provider.output_cost_per_mtok = Some(15.0);
+            config.session.budget_usd = Some(0.05);
+        });
+        let post = |uri: &str, body: String| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(post("/v1/sessions", r#"{"name":"metered"}"#.into()))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let id = serde_json::from_slice::<serde_json::Value>(&body).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let chat = serde_json::json!({
+            "messages": [{"role": "user", "content": "hello"}],
+            "stream": true,
+            "session_id": id,
+        });
+        let resp = app
+            .clone()
+            .oneshot(post("/v1/chat/completions", chat.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        let usage_line = text
+            .lines()
+            .skip_while(|l| *l != "event: usage")
+            .nth(1)
+            .expect("usage event");
+        let usage: serde_json::Value =
+            serde_json::from_str(usage_line.trim_start_matches("data: ")).unwrap();
+        assert_eq!(usage["total_tokens"], 1500);
+        assert_eq!(usage["estimated"], false);
+        assert_eq!(usage["budget_usd"], 0.05);
+        assert!((usage["cost_usd"].as_f64().unwrap() - 0.0105).abs() < 1e-9);
+
+        // The total is saved with the session.
+        let req = Request::builder()
+            .uri(format!("/v1/sessions/{}/usage", id))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let saved: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(saved["prompt_tokens"], 1000);
+        assert_eq!(saved["completion_tokens"], 500);
+    }
+
+    #[tokio::test]
+    async fn test_resume_stream_after_last_event_id() {
+        let base = mock_provider("Hi").await;
+        let app = test_router_with(|config| config.provider.api_base = base);
+        let chat = serde_json::json!({
+            "messages": [{"role": "user", "content": "hello"}],
+            "stream": true,
+        });
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions")
+            .header("Content-Type", "application/json")
+            .body(Body::from(chat.to_string()))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        let run_id = resp.headers()["x-run-id"].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let full = String::from_utf8(body.to_vec()).unwrap();
+        assert!(full.starts_with("id: 1\n"));
+        assert!(full.contains("\"content\":\"Hi\""));
+
+        let resume = |last: &str| {
+            Request::builder()
+                .uri(format!("/v1/runs/{}/events", run_id))
+                .header("Last-Event-ID", last)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let resp = app.clone().oneshot(resume("1")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let rest = String::from_utf8(body.to_vec()).unwrap();
+        assert!(rest.starts_with("id: 2\n"));
+        assert!(!rest.contains("\"content\":\"Hi\""));
+        assert!(rest.contains("event: usage"));
+        assert!(rest.contains("data: [DONE]"));
+        assert!(full.ends_with(&rest));
+
+        let resp = app.clone().oneshot(resume("two")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let req = Request::builder()
+            .uri(format!("/v1/runs/{}/events", uuid::Uuid::new_v4()))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_request_ids_correlate_errors_and_runs() {
+        let base = mock_provider("Hi").await;
+        let app = test_router_with(|config| config.provider.api_base = base);
+
+        // A caller's ID is echoed and quoted in error bodies.
+        let req = Request::builder()
+            .uri(format!("/v1/sessions/{}/usage", uuid::Uuid::new_v4()))
+            .header("x-request-id", "lb-42")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.headers()["x-request-id"], "lb-42");
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["request_id"], "lb-42");
+
+        // Otherwise one is generated, and a streamed turn runs under it.
+        let chat = serde_json::json!({
+            "messages": [{"role": "user", "content": "hello"}],
+            "stream": true,
+        });
+        let req = Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions")
+            .header("Content-Type", "application/json")
+            .header("x-request-id", "not a usable id")
+            .body(Body::from(chat.to_string()))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        let request_id = resp.headers()["x-request-id"].to_str().unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&request_id).is_ok());
+        // The run ID is the server's own, never the caller's.
+        let run_id = resp.headers()["x-run-id"].to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(run_id).is_ok());
+        assert_ne!(run_id, request_id);
+    }
+
+    #[tokio::test]
+    async fn test_second_turn_in_busy_session_conflicts() {
+        let gate = Arc::new(tokio::sync::Notify::new());
+        let base = mock_provider_gated("Hi", Some(gate.clone())).await;
+        let app = test_router_with(|config| config.provider.api_base = base);
+        let post = |uri: &str, body: String, request_id: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .header("x-request-id", request_id)
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(post("/v1/sessions", r#"{"name":"busy"}"#.into(), "create"))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let id = serde_json::from_slice::<serde_json::Value>(&body).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let chat = serde_json::json!({
+            "messages": [{"role": "user", "content": "hello"}],
+            "session_id": id,
+            "stream": true,
+        })
+        .to_string();
+
+        let first = app
+            .clone()
+            .oneshot(post("/v1/chat/completions", chat.clone(), "turn-1"))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        let run_id = first.headers()["x-run-id"].to_str().unwrap().to_string();
+
+        let resp = app
+            .clone()
+            .oneshot(post("/v1/chat/completions", chat.clone(), "turn-2"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["code"], "session_busy");
+        assert_eq!(problem["details"]["run_id"], run_id.as_str());
+
+        // Once the first turn ends, the session takes another.
+        gate.notify_one();
+        axum::body::to_bytes(first.into_body(), usize::MAX).await.unwrap();
+        let resp = app
+            .oneshot(post("/v1/chat/completions", chat, "turn-3"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_turn_stays_in_its_session_when_another_is_activated() {
+        let gate = Arc::new(tokio::sync::Notify::new());
+        let base = mock_provider_gated("Hi", Some(gate.clone())).await;
+        let app = test_router_with(|config| config.provider.api_base = base);
+        let send = |method: &str, uri: String, body: String| {
+            let req = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let resp = app.oneshot(req).await.unwrap();
+                let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+        let create = |name: &str| {
+            send("POST", "/v1/sessions".into(), serde_json::json!({ "name": name }).to_string())
+        };
+        let texts = |id: String| {
+            let messages = send("GET", format!("/v1/sessions/{}/messages", id), String::new());
+            async move {
+                let messages = messages.await;
+                messages
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|m| m["content"].as_str().unwrap().to_string())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let a = create("a").await["id"].as_str().unwrap().to_string();
+        let chat = serde_json::json!({
+            "messages": [{"role": "user", "content": "hello"}],
+            "session_id": a,
+        });
+        let turn = tokio::spawn(send("POST", "/v1/chat/completions".into(), chat.to_string()));
+        while texts(a.clone()).await.is_empty() {
+            tokio::task::yield_now().await;
+        }
+
+        // Creating a session makes it the active one mid-turn.
+        let b = create("b").await["id"].as_str().unwrap().to_string();
+        gate.notify_one();
+        let reply = turn.await.unwrap();
+        assert_eq!(reply["session_id"], a.as_str());
+        assert_eq!(texts(a.clone()).await, ["hello", "Hi"]);
+        assert!(texts(b.clone()).await.is_empty());
+        let usage = send("GET", format!("/v1/sessions/{}/usage", b), String::new()).await;
+        assert_eq!(usage["total_tokens"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_problem_details() {
+        let app = test_router_with(|config| config.server.max_body_bytes = 1024);
+        let send = |req: Request<Body>| {
+            let app = app.clone();
+            async move {
+                let resp = app.oneshot(req).await.unwrap();
+                let status = resp.status();
+                assert_eq!(
+                    resp.headers()["content-type"],
+                    error::PROBLEM_CONTENT_TYPE
+                );
+                let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+            }
+        };
+        let post = |uri: &str, body: String| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        // Over the API body limit.
+        let name = "x".repeat(2048);
+        let (status, problem) = send(post("/v1/sessions", format!(r#"{{"name":"{}"}}"#, name))).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(problem["status"], 413);
+        assert_eq!(problem["code"], "payload_too_large");
+
+        // Schema violations are all listed.
+        let (status, problem) =
+            send(post("/v1/sessions", r#"{"name":"","agnet":"x"}"#.into())).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(problem["code"], "validation_failed");
+        assert_eq!(problem["details"]["errors"].as_array().unwrap().len(), 2);
+        let (status, problem) =
+            send(post("/v1/chat/completions", r#"{"messages":[],"model":"m"}"#.into())).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(problem["details"]["errors"][0], "body.messages: must have at least 1 items");
+
+        // Handler errors carry a specific code.
+        let missing = uuid::Uuid::new_v4();
+        let req = Request::builder()
+            .uri(format!("/v1/sessions/{}/usage", missing))
+            .body(Body::empty())
+            .unwrap();
+        let (status, problem) = send(req).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(problem["title"], "Not Found");
+        assert_eq!(problem["code"], "session_not_found");
+        assert_eq!(problem["detail"], format!("Session not found: {}", missing));
+
+        // So do the middleware's own rejections.
+        let app = test_router(Some("secret".into()));
+        let req = Request::builder().uri("/v1/sessions").body(Body::empty()).unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["code"], "unauthorized");
+    }
+
+    #[tokio::test]
+    async fn test_run_task_in_new_session() {
+        let base = mock_provider("Build passes.").await;
+        let app = test_router_with(|config| config.provider.api_base = base);
+        let post = |body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/v1/runs")
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let resp = app.clone().oneshot(post(r#"{"task":"  "}"#)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = app
+            .clone()
+            .oneshot(post(r#"{"task":"x","agent":"missing"}"#))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = app
+            .clone()
+            .oneshot(post(r#"{"task":"run the build","stream":false}"#))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let id = resp.headers()["x-session-id"].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["session_id"], id.as_str());
+        assert_eq!(json["choices"][0]["message"]["content"], "Build passes.");
+
+        let req = Request::builder()
+            .uri("/v1/sessions")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let sessions: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let run = sessions
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["id"] == id.as_str())
+            .unwrap();
+        assert_eq!(run["name"], "Run: run the build");
+        assert_eq!(run["message_count"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_chat_rejects_bad_images() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        std::fs::write(workspace.path().join("notes.txt"), "not an image").unwrap();
+        std::fs::create_dir(workspace.path().join("private")).unwrap();
+        std::fs::write(workspace.path().join("private/shot.png"), "png").unwrap();
+        let root = workspace.path().to_path_buf();
+        let app = test_router_with(|config| {
+            config.sandbox.workspace_root = Some(root);
+            config
+                .sandbox
+                .paths
+                .insert("private/**".into(), agent_core::config::PathAccess::Deny);
+        });
+
+        let cases = [
+            (r#"{"path":"../shot.png"}"#, StatusCode::FORBIDDEN),
+            (r#"{"path":"private/shot.png"}"#, StatusCode::FORBIDDEN),
+            (r#"{"path":"/etc/hostname"}"#, StatusCode::FORBIDDEN),
+            (r#"{"path":"notes.txt"}"#, StatusCode::BAD_REQUEST),
+            (r#"{"path":"missing.png"}"#, StatusCode::NOT_FOUND),
+            (r#"{"data":"iVBORw0KGgo="}"#, StatusCode::BAD_REQUEST),
+            (r#"{"data":"not base64!","media_type":"image/png"}"#, StatusCode::BAD_REQUEST),
+            (r#"{"data":"data:text/plain;base64,aGk="}"#, StatusCode::BAD_REQUEST),
+        ];
+        for (image, expected) in cases {
+            let body = format!(
+                r#"{{"messages":[{{"role":"user","content":"look","images":[{image}]}}]}}"#
+            );
+            let req = Request::builder()
+                .method("POST")
+                .uri("/v1/chat/completions")
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            let resp = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(resp.status(), expected, "{image}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_presets_lifecycle() {
+        let app = test_router_with(|config| {
+            config.agents.insert(
+                "reviewer".into(),
+                agent_core::AgentPreset {
+                    system_prompt: Some("Review code.".into()),
+                    ..Default::default()
+                },
+            );
+        });
+        let send = |method: &str, uri: &str, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(send("POST", "/v1/agents", r#"{"name":"sysadmin","temperature":0.2}"#))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        for (body, expected) in [
+            (r#"{"name":"reviewer"}"#, StatusCode::CONFLICT),
+            (r#"{"name":"bad name"}"#, StatusCode::BAD_REQUEST),
+            (r#"{"name":"hot","temperature":5.0}"#, StatusCode::BAD_REQUEST),
+            (r#"{"name":"x","tools":["rm_rf"]}"#, StatusCode::BAD_REQUEST),
+        ] {
+            let resp = app.clone().oneshot(send("POST", "/v1/agents", body)).await.unwrap();
+            assert_eq!(resp.status(), expected, "{body}");
+        }
+
+        let resp = app.clone().oneshot(send("GET", "/v1/agents", "")).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let agents: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(agents[0]["name"], "reviewer");
+        assert_eq!(agents[0]["source"], "config");
+        assert_eq!(agents[1]["name"], "sysadmin");
+        assert_eq!(agents[1]["temperature"], 0.2);
+
+        let resp = app
+            .clone()
+            .oneshot(send("POST", "/v1/sessions", r#"{"name":"ops","agent":"sysadmin"}"#))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let session: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(session["agent"], "sysadmin");
+        let resp = app
+            .clone()
+            .oneshot(send("POST", "/v1/sessions", r#"{"name":"ops","agent":"nobody"}"#))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = app.clone().oneshot(send("DELETE", "/v1/agents/reviewer", "")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let resp = app.clone().oneshot(send("DELETE", "/v1/agents/sysadmin", "")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let resp = app.oneshot(send("DELETE", "/v1/agents/sysadmin", "")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_validate_schedule() {
+        let app = test_router(None);
+        let validate = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri("/v1/schedules/validate")
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(validate(r#"{"expression":"0 9 * * MON-FRI"}"#))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["valid"], true);
+        assert_eq!(json["normalized"], "0 0 9 * * MON-FRI *");
+        assert_eq!(json["next_runs"].as_array().unwrap().len(), 5);
+
+        let resp = app
+            .clone()
+            .oneshot(validate(r#"{"expression":"every tuesday"}"#))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["valid"], false);
+        assert!(json["error"].as_str().unwrap().contains("Invalid cron expression"));
+
+        let resp = app
+            .oneshot(validate(r#"{"expression":"* * * * *","count":100}"#))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_schedules_and_history() {
+        use agent_core::config::{ScheduleConfig, ScheduleTaskType};
+        use agent_core::scheduler::{RunStatus, ScheduleHistory, ScheduleRun};
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let state_dir = tmp.path().to_path_buf();
+        let app = test_router_with(|config| {
+            config.scheduler.state_dir = Some(state_dir.clone());
+            for (name, cron) in [("digest", "0 9 * * *"), ("broken", "nope")] {
+                config.schedules.push(ScheduleConfig {
+                    name: name.into(),
+                    cron: cron.into(),
+                    workspace: None,
+                    session: None,
+                    workspace_root: None,
+                    task: ScheduleTaskType::Prompt,
+                    skill: None,
+                    prompt: Some("Summarize yesterday's logs.".into()),
+                    enabled: true,
+                    quiet_hours: None,
+                    command: None,
+                    webhook: None,
+                });
+            }
+            let history = ScheduleHistory::for_config(config);
+            history
+                .record(&ScheduleRun {
+                    schedule: "digest".into(),
+                    fired_at: chrono::Utc::now(),
+                    finished_at: None,
+                    status: RunStatus::Fired,
+                    error: None,
+                    summary: None,
+                })
+                .unwrap();
+        });
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let resp = app.clone().oneshot(get("/v1/schedules")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json[0]["name"], "digest");
+        assert_eq!(json[0]["last_status"], "fired");
+        assert!(json[0]["next_run"].is_string());
+        assert!(json[0]["state"].is_null());
+        assert!(json[1]["cron_error"].is_string());
+
+        let resp = app.clone().oneshot(get("/v1/schedules/digest/history?limit=5")).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["status"], "fired");
+
+        let resp = app.oneshot(get("/v1/schedules/missing/history")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_plugin_permissions() {
+        use agent_plugins::marketplace::{InstalledPlugin, PluginKind};
+
+        let mut plugins = agent_plugins::PluginRegistry::new();
+        plugins
+            .register(Box::new(InstalledPlugin {
+                name: "jira-sync".into(),
+                version: "1.2.0".into(),
+                kind: PluginKind::Wasm,
+                description: String::new(),
+                sha256: String::new(),
+                path: "/nonexistent/jira-sync.wasm".into(),
+                installed_at: chrono::Utc::now(),
+                permissions: agent_plugins::PluginPermissions {
+                    network: vec!["*.atlassian.net".into()],
+                    env: vec!["JIRA_TOKEN".into()],
+                    ..Default::default()
+                },
+            }))
+            .unwrap();
+        let app = test_router_with_plugins(|_| {}, plugins);
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        for uri in ["/v1/plugins/jira-sync/permissions", "/v1/plugins/extension:jira-sync/permissions"] {
+            let resp = app.clone().oneshot(get(uri)).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["key"], "extension:jira-sync");
+            assert_eq!(json["permissions"]["network"][0], "*.atlassian.net");
+            assert_eq!(json["permissions"]["filesystem"].as_array().unwrap().len(), 0);
+        }
+
+        let resp = app.oneshot(get("/v1/plugins/skill:jira-sync/permissions")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_listings_page_and_filter() {
+        use agent_core::session::Session;
+        use agent_core::types::Message;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let now = chrono::Utc::now();
+        let mut ids = Vec::new();
+        for (i, name) in ["old", "middle", "new"].into_iter().enumerate() {
+            let mut session = Session::new(name);
+            if name == "old" {
+                session.add_tag("infra");
+                session.messages = vec![
+                    Message::system("Be brief."),
+                    Message::user("Why is the build red?"),
+                    Message::assistant("x".repeat(300)),
+                    Message::user("Fix it."),
+                    Message::assistant("Done."),
+                ];
+            }
+            session.updated_at = now - chrono::Duration::hours(3 - i as i64);
+            session.save_to(dir.path()).unwrap();
+            ids.push(session.id);
+        }
+        let history_dir = dir.path().to_path_buf();
+        let app = test_router_with(|config| config.session.history_dir = Some(history_dir));
+        let get = |uri: String| {
+            let app = app.clone();
+            async move {
+                let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+                let resp = app.oneshot(req).await.unwrap();
+                let status = resp.status();
+                let header = |name: &str| {
+                    resp.headers().get(name).map(|v| v.to_str().unwrap().to_string())
+                };
+                let (total, next) = (header("x-total-count"), header("x-next-cursor"));
+                let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+                let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                (status, body, total, next)
+            }
+        };
+
+        // Newest first, two at a time.
+        let (status, page, total, next) = get("/v1/sessions?limit=2".into()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page[0]["name"], "new");
+        assert_eq!(page.as_array().unwrap().len(), 2);
+        assert_eq!(total.as_deref(), Some("3"));
+        assert_eq!(next.as_deref(), Some(ids[1].as_str()));
+        let (_, page, _, next) = get(format!("/v1/sessions?limit=2&after={}", ids[1])).await;
+        assert_eq!(page[0]["name"], "old");
+        assert_eq!(page[0]["tags"][0], "infra");
+        assert_eq!(next, None);
+
+        let (_, page, total, _) = get("/v1/sessions?tag=infra".into()).await;
+        assert_eq!((page[0]["name"].as_str(), total.as_deref()), (Some("old"), Some("1")));
+        let cutoff = (now - chrono::Duration::minutes(90)).to_rfc3339().replace('+', "%2B");
+        let (_, page, _, _) = get(format!("/v1/sessions?updated_before={}", cutoff)).await;
+        assert_eq!(page.as_array().unwrap().len(), 2);
+        let (status, _, _, _) = get("/v1/sessions?limit=0".into()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _, _, _) = get("/v1/sessions?after=nope".into()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let messages = format!("/v1/sessions/{}/messages", ids[0]);
+        let (_, page, total, _) = get(format!("{}?role=assistant&summary=true", messages)).await;
+        assert_eq!(total.as_deref(), Some("2"));
+        assert_eq!(page[0]["truncated"], true);
+        assert_eq!(page[0]["content"].as_str().unwrap().chars().count(), 201);
+        assert!(page[1].get("truncated").is_none());
+        let (_, page, _, next) = get(format!("{}?limit=1&offset=1", messages)).await;
+        assert_eq!(page[0]["content"], "Why is the build red?");
+        assert!(next.is_some());
+        let (status, _, _, _) = get(format!("{}?role=robot", messages)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[cfg(feature = "graphql")]
+    #[tokio::test]
+    async fn test_log_stream_replays_then_follows() {
+        use futures::StreamExt;
+        use logs::LogLevel;
+        let push = |level, message: &str| {
+            logs::buffer().push(level, "test", message.into(), Default::default())
+        };
+        push(LogLevel::Info, "stream-test quiet");
+        push(LogLevel::Error, "stream-test before");
+
+        let app = test_router(None);
+        let req = Request::builder()
+            .uri("/v1/logs/stream?level=warn&backlog=2000")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "text/event-stream");
+        push(LogLevel::Warn, "stream-test after");
+
+        let mut body = resp.into_body().into_data_stream();
+        let mut text = String::new();
+        while !text.contains("stream-test after") {
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), body.next())
+                .await
+                .expect("log event not streamed")
+                .unwrap()
+                .unwrap();
+            text.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        assert!(text.contains("event: log\n"));
+        assert!(text.contains("\"level\":\"error\",\"target\":\"test\",\"message\":\"stream-test before\""));
+        assert!(!text.contains("stream-test quiet"));
+        assert!(text.find("stream-test before") < text.find("stream-test after"));
+    }
+
+    #[tokio::test]
+    async fn test_notification_stream_follows_published() {
+        use agent_core::notifications::{self, NotificationLevel};
+        use futures::StreamExt;
+        notifications::publish(NotificationLevel::Info, "before", "not replayed");
+
+        let app = test_router(None);
+        let req = Request::builder()
+            .uri("/v1/notifications/stream")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "text/event-stream");
+        notifications::publish(NotificationLevel::Warning, "Nightly scan", "2 findings");
+
+        let mut body = resp.into_body().into_data_stream();
+        let mut text = String::new();
+        while !text.contains("Nightly scan") {
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), body.next())
+                .await
+                .expect("notification not streamed")
+                .unwrap()
+                .unwrap();
+            text.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        assert!(text.contains("event: notification\n"));
+        assert!(text.contains("\"level\":\"warning\",\"title\":\"Nightly scan\""));
+        assert!(!text.contains("not replayed"));
+    }
+
+    #[tokio::test]
+    async fn test_sessions_belong_to_their_api_key() {
+        let key = |name: &str, admin: bool| agent_core::config::ApiKeyConfig {
+            name: name.into(),
+            key: Some(format!("{name}-key")),
+            key_env: None,
+            admin,
+        };
+        let app = test_router_with(|config| {
+            config.server.api_keys = vec![key("alice", false), key("bob", false), key("ops", true)];
+        });
+        let call = |who: &str, method: &str, uri: String, body: &str| {
+            let req = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", format!("Bearer {who}-key"))
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let resp = app.oneshot(req).await.unwrap();
+                let status = resp.status();
+                let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+            }
+        };
+        let names = |list: serde_json::Value| -> Vec<String> {
+            let list = list.as_array().unwrap().iter();
+            list.map(|s| s["name"].as_str().unwrap().to_string()).collect()
+        };
+
+        let (status, created) =
+            call("alice", "POST", "/v1/sessions".into(), r#"{"name":"alice's"}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        let id = created["id"].as_str().unwrap().to_string();
+
+        let (_, list) = call("alice", "GET", "/v1/sessions".into(), "").await;
+        assert_eq!(names(list), ["alice's"]);
+        let (_, list) = call("bob", "GET", "/v1/sessions".into(), "").await;
+        assert!(names(list).is_empty());
+        let (_, list) = call("ops", "GET", "/v1/sessions".into(), "").await;
+        assert!(names(list).contains(&"alice's".to_string()));
+
+        // Bob cannot tell alice's session from a missing one.
+        for (method, uri) in [
+            ("GET", format!("/v1/sessions/{id}/messages")),
+            ("DELETE", format!("/v1/sessions/{id}/messages/m1")),
+            ("POST", format!("/v1/sessions/{id}/fork")),
+            ("GET", format!("/v1/sessions/{id}/usage")),
+            ("POST", format!("/v1/sessions/{id}/share")),
+        ] {
+            let (status, problem) = call("bob", method, uri.clone(), "{}").await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{method} {uri}");
+            assert_eq!(problem["code"], "session_not_found");
+        }
+        let chat = format!(r#"{{"messages":[{{"role":"user","content":"hi"}}],"session_id":"{id}"}}"#);
+        let (status, _) = call("bob", "POST", "/v1/chat/completions".into(), &chat).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = call("alice", "GET", format!("/v1/sessions/{id}/messages"), "").await;
+        assert_eq!(status, StatusCode::OK);
+
+        // A fork belongs to whoever made it.
+        let (status, fork) = call("ops", "POST", format!("/v1/sessions/{id}/fork"), "{}").await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, list) = call("alice", "GET", "/v1/sessions".into(), "").await;
+        assert_eq!(names(list), ["alice's"]);
+        let (_, list) = call("ops", "GET", "/v1/sessions".into(), "").await;
+        assert!(names(list).contains(&fork["name"].as_str().unwrap().to_string()));
+
+        let (status, _) = call("mallory", "GET", "/v1/sessions".into(), "").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_runs_belong_to_their_session_owner() {
+        let base = mock_provider("Hi").await;
+        let key = |name: &str| agent_core::config::ApiKeyConfig {
+            name: name.into(),
+            key: Some(format!("{name}-key")),
+            key_env: None,
+            admin: false,
+        };
+        let app = test_router_with(|config| {
+            config.provider.api_base = base;
+            config.server.api_keys = vec![key("alice"), key("bob")];
+        });
+        let request = |who: &str, method: &str, uri: String, body: String| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", format!("Bearer {who}-key"))
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(request("alice", "POST", "/v1/sessions".into(), r#"{"name":"a"}"#.into()))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let id = serde_json::from_slice::<serde_json::Value>(&body).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let chat = serde_json::json!({
+            "messages": [{"role": "user", "content": "hello"}],
+            "session_id": id,
+            "stream": true,
+        });
+        let resp = app
+            .clone()
+            .oneshot(request("alice", "POST", "/v1/chat/completions".into(), chat.to_string()))
+            .await
+            .unwrap();
+        let run_id = resp.headers()["x-run-id"].to_str().unwrap().to_string();
+        axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+
+        let events = format!("/v1/runs/{run_id}/events");
+        let resp = app
+            .clone()
+            .oneshot(request("bob", "GET", events.clone(), String::new()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["code"], "run_not_found");
+
+        let resp = app
+            .oneshot(request("alice", "GET", events, String::new()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("data: [DONE]"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_toggle_validates() {
+        let app = test_router(None);
+        let req = Request::builder().uri("/v1/tools").body(Body::empty()).unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"[]");
+
+        let patch = |body: &'static str| {
+            Request::builder()
+                .method("PATCH")
+                .uri("/v1/tools/web_fetch")
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let resp = app.clone().oneshot(patch(r#"{"enabled":"no"}"#)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let resp = app.oneshot(patch(r#"{"enabled":false}"#)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["code"], "tool_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_graphql_admin_api() {
+        let app = test_router_with(|config| {
+            config.server.auth_token = Some("user".into());
+            config.server.admin_token = Some("admin".into());
+        });
+        let request = |token: &str, method: &str, uri: &str, body: String| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        for name in ["alpha", "beta", "gamma"] {
+            let body = format!(r#"{{"name":"{}"}}"#, name);
+            let resp = app.clone().oneshot(request("user", "POST", "/v1/sessions", body)).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        let graphql = |token: &'static str, query: &str| {
+            let app = app.clone();
+            let body = serde_json::json!({ "query": query }).to_string();
+            async move {
+                let resp = app
+                    .oneshot(request(token, "POST", "/v1/admin/graphql", body))
+                    .await
+                    .unwrap();
+                let status = resp.status();
+                let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+            }
+        };
+
+        // Pages of two, newest first, then the rest after the cursor.
+        let page = "{ sessions(first: 2) { totalCount pageInfo { hasNextPage endCursor } \
+                    edges { node { name messageCount usage { totalTokens } } } } }";
+        let (status, resp) = graphql("admin", page).await;
+        assert_eq!(status, StatusCode::OK);
+        let sessions = &resp["data"]["sessions"];
+        // The three above and the default session.
+        assert_eq!(sessions["totalCount"], 4);
+        assert_eq!(sessions["edges"][0]["node"]["name"], "gamma");
+        assert_eq!(sessions["edges"][1]["node"]["messageCount"], 0);
+        assert_eq!(sessions["pageInfo"]["hasNextPage"], true);
+        let cursor = sessions["pageInfo"]["endCursor"].as_str().unwrap();
+        let next = format!(r#"{{ sessions(after: "{}") {{ edges {{ node {{ name }} }} }} }}"#, cursor);
+        let (_, resp) = graphql("admin", &next).await;
+        assert_eq!(resp["data"]["sessions"]["edges"][0]["node"]["name"], "alpha");
+
+        let filtered = r#"{ sessions(filter: { nameContains: "ET" }) { totalCount }
+                           analytics { totalSessions } schedules { name } plugins { key } }"#;
+        let (_, resp) = graphql("admin", filtered).await;
+        assert_eq!(resp["data"]["sessions"]["totalCount"], 1);
+        assert_eq!(resp["data"]["schedules"], serde_json::json!([]));
+
+        // The regular token lacks admin scope.
+        let (status, problem) = graphql("user", "{ sessions { totalCount } }").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(problem["code"], "forbidden");
+        let (status, _) = graphql("wrong", "{ sessions { totalCount } }").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // An admin API key has the same scope as the admin token; it alone
+        // mounts the admin API.
+        let app = test_router_with(|config| {
+            config.server.api_keys = vec![
+                agent_core::config::ApiKeyConfig {
+                    name: "ops".into(),
+                    key: Some("ops-key".into()),
+                    admin: true,
+                    ..Default::default()
+                },
+                agent_core::config::ApiKeyConfig {
+                    name: "alice".into(),
+                    key: Some("alice-key".into()),
+                    ..Default::default()
+                },
+            ];
+        });
+        let body = serde_json::json!({ "query": "{ sessions { totalCount } }" }).to_string();
+        let resp = app
+            .clone()
+            .oneshot(request("ops-key", "POST", "/v1/admin/graphql", body.clone()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app
+            .oneshot(request("alice-key", "POST", "/v1/admin/graphql", body))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // Not mounted without an admin token.
+        let app = test_router(Some("user".into()));
+        let resp = app
+            .oneshot(request("user", "GET", "/v1/admin/graphql", String::new()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
 }
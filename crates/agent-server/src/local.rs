@@ -0,0 +1,120 @@
+//! Local-only transport for the HTTP API.
+//!
+//! When `server.listen_socket` is set, the API is served on a Unix domain
+//! socket (a named pipe on Windows) instead of a TCP port. Access is limited
+//! by filesystem permissions (the socket is created `0600`), so requests over
+//! it skip bearer-token auth — see [`LocalTransport`].
+
+use std::path::Path;
+
+/// Request extension marking a connection that arrived over the local socket.
+#[derive(Debug, Clone, Copy)]
+pub struct LocalTransport;
+
+/// Bind a Unix domain socket at `path`, readable only by the current user.
+///
+/// A leftover socket file from a crashed server is replaced; one that still
+/// accepts connections is reported as in use. The socket is created with a
+/// restrictive umask, so it is never briefly open to other users.
+#[cfg(unix)]
+pub async fn bind_unix(path: &Path) -> std::io::Result<tokio::net::UnixListener> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if path.exists() {
+        if tokio::net::UnixStream::connect(path).await.is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("{} is in use by another server", path.display()),
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    // SAFETY: umask only swaps the process's file mode mask; it is put
+    // back right after the bind.
+    let previous = unsafe { libc::umask(0o177) };
+    let listener = tokio::net::UnixListener::bind(path);
+    unsafe { libc::umask(previous) };
+    listener
+}
+
+/// Named pipe listener (`\\.\pipe\<name>`) for Windows.
+///
+/// Keeps one pipe instance waiting for a client; each accepted connection is
+/// handed off and a fresh instance created in its place.
+#[cfg(windows)]
+pub struct NamedPipeListener {
+    name: String,
+    next: tokio::net::windows::named_pipe::NamedPipeServer,
+}
+
+#[cfg(windows)]
+impl NamedPipeListener {
+    pub fn bind(path: &Path) -> std::io::Result<Self> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let name = path.to_string_lossy().into_owned();
+        let next = ServerOptions::new()
+            .first_pipe_instance(true)
+            .reject_remote_clients(true)
+            .create(&name)?;
+        Ok(Self { name, next })
+    }
+}
+
+#[cfg(windows)]
+impl axum::serve::Listener for NamedPipeListener {
+    type Io = tokio::net::windows::named_pipe::NamedPipeServer;
+    type Addr = String;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        loop {
+            let connected = self.next.connect().await;
+            let fresh = match ServerOptions::new()
+                .reject_remote_clients(true)
+                .create(&self.name)
+            {
+                Ok(pipe) => pipe,
+                Err(e) => {
+                    tracing::warn!("Failed to create named pipe instance: {}", e);
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            // Either hand off the connected instance or discard a broken one.
+            let conn = std::mem::replace(&mut self.next, fresh);
+            match connected {
+                Ok(()) => return (conn, self.name.clone()),
+                Err(e) => tracing::warn!("Named pipe connect failed: {}", e),
+            }
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.name.clone())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bind_unix_replaces_stale_socket() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("api.sock");
+
+        let listener = bind_unix(&path).await.unwrap();
+        let mode = std::os::unix::fs::PermissionsExt::mode(&path.metadata().unwrap().permissions());
+        assert_eq!(mode & 0o777, 0o600);
+        let err = bind_unix(&path).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+
+        // Dropping the listener leaves the file behind, as after a crash.
+        drop(listener);
+        assert!(path.exists());
+        bind_unix(&path).await.unwrap();
+    }
+}
@@ -0,0 +1,282 @@
+//! Recent log events for the UI's Logs panel.
+//!
+//! [`LogLayer`] is a tracing layer that keeps the last [`CAPACITY`] events
+//! in a process-wide ring buffer and broadcasts new ones.
+//! `GET /v1/logs/stream` replays the buffer and then follows it, so the
+//! server can be debugged from the browser without access to its terminal.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::broadcast;
+use tracing::field::{Field, Visit};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use utoipa::ToSchema;
+
+/// Events kept in the ring buffer; the oldest are dropped first.
+pub const CAPACITY: usize = 2000;
+
+/// Events a slow follower may fall behind by before it skips ahead.
+const CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl From<&tracing::Level> for LogLevel {
+    fn from(level: &tracing::Level) -> Self {
+        match *level {
+            tracing::Level::TRACE => Self::Trace,
+            tracing::Level::DEBUG => Self::Debug,
+            tracing::Level::INFO => Self::Info,
+            tracing::Level::WARN => Self::Warn,
+            tracing::Level::ERROR => Self::Error,
+        }
+    }
+}
+
+/// One log event.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct LogEntry {
+    /// Increases by one per event; sent as the SSE `id`.
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub level: LogLevel,
+    /// Module the event came from, e.g. `agent_core::agent_loop`.
+    pub target: String,
+    pub message: String,
+    /// Structured fields other than the message.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+}
+
+#[derive(Default)]
+struct Ring {
+    entries: VecDeque<LogEntry>,
+    next_seq: u64,
+}
+
+/// The buffered events and the channel new ones are sent on.
+pub struct LogBuffer {
+    ring: Mutex<Ring>,
+    sender: broadcast::Sender<LogEntry>,
+}
+
+/// Buffered events at or above a level, and where the buffer stood.
+pub struct Replay {
+    pub entries: Vec<LogEntry>,
+    /// `seq` of the newest buffered event (of any level); live events up
+    /// to it are already covered by the replay.
+    pub through: u64,
+}
+
+impl LogBuffer {
+    fn new() -> Self {
+        Self {
+            ring: Mutex::new(Ring::default()),
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+        }
+    }
+
+    fn ring(&self) -> std::sync::MutexGuard<'_, Ring> {
+        self.ring.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record an event, numbering it after the last.
+    pub fn push(
+        &self,
+        level: LogLevel,
+        target: &str,
+        message: String,
+        fields: BTreeMap<String, String>,
+    ) {
+        let mut ring = self.ring();
+        ring.next_seq += 1;
+        let entry = LogEntry {
+            seq: ring.next_seq,
+            timestamp: Utc::now(),
+            level,
+            target: target.to_string(),
+            message,
+            fields,
+        };
+        if ring.entries.len() == CAPACITY {
+            ring.entries.pop_front();
+        }
+        ring.entries.push_back(entry.clone());
+        // Sent under the lock so followers see events in order. No receivers
+        // is fine.
+        let _ = self.sender.send(entry);
+    }
+
+    /// The newest `limit` buffered events at or above `min`, oldest first.
+    pub fn replay(&self, min: LogLevel, limit: usize) -> Replay {
+        let ring = self.ring();
+        let mut entries: Vec<LogEntry> = ring
+            .entries
+            .iter()
+            .rev()
+            .filter(|e| e.level >= min)
+            .take(limit)
+            .cloned()
+            .collect();
+        entries.reverse();
+        Replay {
+            entries,
+            through: ring.next_seq,
+        }
+    }
+
+    /// Receive events pushed from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<LogEntry> {
+        self.sender.subscribe()
+    }
+}
+
+/// The process-wide buffer [`LogLayer`] writes to.
+pub fn buffer() -> &'static LogBuffer {
+    static BUFFER: OnceLock<LogBuffer> = OnceLock::new();
+    BUFFER.get_or_init(LogBuffer::new)
+}
+
+/// Tracing layer that records events in [`buffer`]. Add it to the
+/// subscriber (with its own level filter) to feed the Logs panel.
+///
+/// Fields of the spans an event happened in (such as a request's
+/// `request_id`) are included with the event's own.
+pub struct LogLayer;
+
+/// Fields recorded on a span, kept in its extensions.
+struct SpanFields(BTreeMap<String, String>);
+
+impl<S> tracing_subscriber::Layer<S> for LogLayer
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        id: &tracing::span::Id,
+        ctx: Context<'_, S>,
+    ) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(visitor.fields));
+        }
+    }
+
+    fn on_record(
+        &self,
+        id: &tracing::span::Id,
+        values: &tracing::span::Record<'_>,
+        ctx: Context<'_, S>,
+    ) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        let mut extensions = span.extensions_mut();
+        if let Some(fields) = extensions.get_mut::<SpanFields>() {
+            fields.0.extend(visitor.fields);
+        }
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = BTreeMap::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(span_fields) = span.extensions().get::<SpanFields>() {
+                    fields.extend(span_fields.0.clone());
+                }
+            }
+        }
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        fields.extend(visitor.fields);
+        let metadata = event.metadata();
+        buffer().push(
+            metadata.level().into(),
+            metadata.target(),
+            visitor.message,
+            fields,
+        );
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = value.to_string(),
+            name => {
+                self.fields.insert(name.to_string(), value.to_string());
+            }
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        let mut text = String::new();
+        let _ = write!(text, "{:?}", value);
+        match field.name() {
+            "message" => self.message = text,
+            name => {
+                self.fields.insert(name.to_string(), text);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_layer_records_and_replays_by_level() {
+        let buffer = LogBuffer::new();
+        for (level, message) in [
+            (LogLevel::Debug, "polling"),
+            (LogLevel::Info, "started"),
+            (LogLevel::Warn, "slow provider"),
+            (LogLevel::Error, "tool failed"),
+        ] {
+            buffer.push(level, "agent_core", message.into(), BTreeMap::new());
+        }
+        let replay = buffer.replay(LogLevel::Warn, 10);
+        let messages: Vec<&str> = replay.entries.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, ["slow provider", "tool failed"]);
+        assert_eq!(replay.through, 4);
+        let newest = buffer.replay(LogLevel::Trace, 1);
+        assert_eq!(newest.entries[0].seq, 4);
+
+        let mut live = buffer.subscribe();
+        buffer.push(LogLevel::Info, "x", "after".into(), BTreeMap::new());
+        assert_eq!(live.try_recv().unwrap().seq, 5);
+
+        let subscriber = tracing_subscriber::registry().with(LogLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("request", request_id = "req-1").entered();
+            tracing::warn!(tool = "shell_exec", attempt = 2, "retrying {}", "now");
+        });
+        let entry = super::buffer().replay(LogLevel::Warn, 1).entries.remove(0);
+        assert_eq!(entry.message, "retrying now");
+        assert_eq!(entry.fields["tool"], "shell_exec");
+        assert_eq!(entry.fields["attempt"], "2");
+        assert_eq!(entry.fields["request_id"], "req-1");
+        assert!(entry.target.ends_with("logs::tests"));
+    }
+}
@@ -0,0 +1,197 @@
+//! OpenAPI description of the HTTP API, served at `/openapi.json`.
+//!
+//! Handlers carry `#[utoipa::path]` annotations and [`ApiDoc`] collects
+//! them. A snapshot is checked in at `crates/agent-client/openapi.json`; the
+//! `agent-client` crate and the UI's TypeScript types are generated from it,
+//! and `test_openapi_snapshot_is_current` fails when it falls behind
+//! (`UPDATE_OPENAPI=1 cargo test -p agent-server openapi` rewrites it).
+
+use crate::error::ApiError;
+use crate::routes;
+use crate::state::AppState;
+use axum::routing::get;
+use axum::{Json, Router};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{ContentBuilder, Ref, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "agent-shell",
+        description = "HTTP API of `agent-shell serve`. Every error is an \
+                       `application/problem+json` body with a machine-readable `code`."
+    ),
+    paths(
+        routes::health,
+        routes::health_ready,
+        routes::chat_completions,
+        routes::create_run,
+        routes::run_events,
+        routes::list_sessions,
+        routes::create_session,
+        routes::fork_session,
+        routes::list_checkpoints,
+        routes::rollback_session,
+        routes::session_usage,
+        routes::list_file_changes,
+        routes::revert_file_changes,
+        routes::accept_file_changes,
+        routes::set_session_workspaces,
+        routes::share_session,
+        routes::unshare_session,
+        routes::view_shared_session,
+        routes::get_session_messages,
+        routes::delete_session_message,
+        routes::set_message_feedback,
+        routes::regenerate_from_message,
+        routes::list_agents,
+        routes::create_agent,
+        routes::delete_agent,
+        routes::get_config,
+        routes::update_provider,
+        routes::list_models,
+        routes::list_plugins,
+        routes::plugin_health,
+        routes::plugin_permissions,
+        routes::run_tool,
+        routes::list_tools,
+        routes::update_tool,
+        routes::get_preferences,
+        routes::update_preferences,
+        routes::upload_files,
+        routes::transcribe_audio,
+        routes::synthesize_speech,
+        routes::editor_action,
+        routes::list_skills,
+        routes::search_skills,
+        routes::get_skill,
+        routes::list_terminal_sessions,
+        routes::list_terminal_failures,
+        routes::get_terminal_session,
+        routes::ask_about_failure,
+        routes::list_shells,
+        routes::terminal_ws,
+        routes::get_context,
+        routes::list_schedules,
+        routes::schedule_history,
+        routes::validate_schedule,
+        routes::analytics_summary,
+        routes::analytics_report,
+        routes::analytics_timeseries,
+        routes::analytics_goals,
+        routes::log_stream,
+        routes::notification_stream,
+        openapi_json,
+    ),
+    components(schemas(
+        ApiError,
+        crate::error::ErrorCode,
+        crate::logs::LogEntry,
+        agent_core::notifications::Notification
+    )),
+    modifiers(&Problems, &BearerAuth)
+)]
+pub struct ApiDoc;
+
+/// Every operation may fail with a problem-details body.
+struct Problems;
+
+impl Modify for Problems {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let problem = ResponseBuilder::new()
+            .description("Error, as RFC 9457 problem details")
+            .content(
+                crate::error::PROBLEM_CONTENT_TYPE,
+                ContentBuilder::new()
+                    .schema(Some(Ref::from_schema_name("Problem")))
+                    .build(),
+            )
+            .build();
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.patch,
+                &mut item.delete,
+            ];
+            for operation in operations.into_iter().flatten() {
+                operation
+                    .responses
+                    .responses
+                    .insert("default".into(), problem.clone().into());
+            }
+        }
+    }
+}
+
+/// Bearer token auth (`server.auth_token`), required by every operation
+/// that does not opt out.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        openapi.security = Some(vec![utoipa::openapi::security::SecurityRequirement::new(
+            "bearer",
+            Vec::<String>::new(),
+        )]);
+    }
+}
+
+/// Public: the description holds nothing a client could not learn by
+/// calling the API.
+pub fn openapi_routes() -> Router<AppState> {
+    Router::new().route("/openapi.json", get(openapi_json))
+}
+
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "health",
+    security(()),
+    responses((status = 200, description = "This document", body = Object))
+)]
+async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNAPSHOT: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../agent-client/openapi.json");
+
+    #[test]
+    fn test_openapi_snapshot_is_current() {
+        let current = ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(SNAPSHOT, &current).unwrap();
+            return;
+        }
+        let snapshot = std::fs::read_to_string(SNAPSHOT).unwrap_or_default();
+        assert!(
+            snapshot == current,
+            "{SNAPSHOT} is out of date; rerun with UPDATE_OPENAPI=1"
+        );
+    }
+
+    #[test]
+    fn test_every_operation_is_documented() {
+        let doc = ApiDoc::openapi();
+        let session = &doc.paths.paths["/v1/sessions/{id}/usage"];
+        let get = session.get.as_ref().unwrap();
+        assert!(get.responses.responses.contains_key("default"));
+        assert_eq!(get.operation_id.as_deref(), Some("session_usage"));
+
+        let health = doc.paths.paths["/health"].get.as_ref().unwrap();
+        assert_eq!(health.security.as_ref().map(Vec::len), Some(1));
+        assert!(doc.components.unwrap().schemas.contains_key("SessionUsageResponse"));
+    }
+}
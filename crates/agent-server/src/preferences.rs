@@ -0,0 +1,122 @@
+//! UI preferences persisted server-side.
+//!
+//! The web UI keeps canvas state in the browser, but appearance settings
+//! should follow the user between browsers. The server has a single bearer
+//! token, so one preferences document is stored per server, as JSON at
+//! `server.preferences_path` (default: `<data_dir>/ui-preferences.json`).
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use utoipa::ToSchema;
+
+/// UI colour theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// Preferences shared by every browser talking to this server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct UiPreferences {
+    pub theme: Theme,
+    /// Base font size in pixels.
+    pub font_size: u8,
+    /// View opened on an empty canvas (a card type), or empty for none.
+    pub default_view: String,
+    /// Start tool call cards collapsed in chat.
+    pub collapsed_tool_cards: bool,
+}
+
+impl Default for UiPreferences {
+    fn default() -> Self {
+        Self {
+            theme: Theme::Dark,
+            font_size: 13,
+            default_view: String::new(),
+            collapsed_tool_cards: true,
+        }
+    }
+}
+
+impl UiPreferences {
+    pub const MIN_FONT_SIZE: u8 = 10;
+    pub const MAX_FONT_SIZE: u8 = 24;
+
+    /// Check values a client could send that the UI cannot render.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(Self::MIN_FONT_SIZE..=Self::MAX_FONT_SIZE).contains(&self.font_size) {
+            return Err(format!(
+                "font_size must be between {} and {}",
+                Self::MIN_FONT_SIZE,
+                Self::MAX_FONT_SIZE
+            ));
+        }
+        if self.default_view.len() > 64 {
+            return Err("default_view is too long".into());
+        }
+        Ok(())
+    }
+
+    /// Load preferences, falling back to defaults if the file is missing
+    /// or unreadable.
+    pub async fn load(path: &Path) -> Self {
+        match tokio::fs::read_to_string(path).await {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring invalid UI preferences at {:?}: {}", path, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Persist preferences atomically (temp file + rename).
+    pub async fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let tmp_path = path.with_extension("json.tmp");
+        let json = serde_json::to_string_pretty(self)?;
+        tokio::fs::write(&tmp_path, json).await?;
+        tokio::fs::rename(&tmp_path, path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_load_missing_returns_default() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let prefs = UiPreferences::load(&tmp.path().join("none.json")).await;
+        assert_eq!(prefs, UiPreferences::default());
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load_roundtrip() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("prefs/ui.json");
+        let prefs = UiPreferences {
+            theme: Theme::Light,
+            font_size: 15,
+            default_view: "CHAT".into(),
+            collapsed_tool_cards: false,
+        };
+        prefs.save(&path).await.unwrap();
+        assert_eq!(UiPreferences::load(&path).await, prefs);
+    }
+
+    #[test]
+    fn test_validate_font_size() {
+        let prefs = UiPreferences {
+            font_size: 40,
+            ..Default::default()
+        };
+        assert!(prefs.validate().is_err());
+        assert!(UiPreferences::default().validate().is_ok());
+    }
+}
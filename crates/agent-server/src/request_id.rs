@@ -0,0 +1,94 @@
+//! Correlation IDs for API requests.
+//!
+//! Every request gets an ID — the caller's `x-request-id` when it sends a
+//! usable one, a new UUID otherwise — which is echoed in the response's
+//! `x-request-id` header, added to the `request_id` of error bodies, and
+//! recorded on a `request` tracing span so every log line the request
+//! causes carries it. A streamed turn's run records it too, and the run's
+//! span carries both IDs, so one value ties an error toast to the server
+//! log and the turn's tool calls. Run IDs themselves are always generated
+//! by the server.
+
+use axum::extract::{FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest caller-supplied ID that is kept.
+const MAX_LEN: usize = 64;
+
+tokio::task_local! {
+    static CURRENT: String;
+}
+
+/// The ID of the request being handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestId {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_else(|| RequestId(uuid::Uuid::new_v4().to_string())))
+    }
+}
+
+/// The ID of the request being handled on this task, if any.
+pub fn current() -> Option<String> {
+    CURRENT.try_with(Clone::clone).ok()
+}
+
+/// A caller's ID is kept if it is short and plain enough to log and to use
+/// in a URL path.
+fn usable(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Middleware assigning the request ID; see the module docs.
+pub async fn assign(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| usable(id))
+        .map(String::from)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    req.extensions_mut().insert(RequestId(id.clone()));
+
+    let span = tracing::info_span!("request", request_id = %id);
+    let mut response = CURRENT
+        .scope(id.clone(), next.run(req))
+        .instrument(span)
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usable_ids() {
+        assert!(usable("3f9c1a2b-7d4e-4b1a-9c2d-0123456789ab"));
+        assert!(usable("lb.req_42"));
+        assert!(!usable(""));
+        assert!(!usable("has space"));
+        assert!(!usable("../etc"));
+        assert!(!usable(&"a".repeat(MAX_LEN + 1)));
+    }
+}
@@ -0,0 +1,51 @@
+//! Background session pruning.
+//!
+//! When `[session] retention_days` or `max_sessions` is set, the server
+//! prunes sessions over the limits at startup and then hourly, archiving or
+//! deleting them per `prune_action` (see
+//! [`SessionManager::prune`](agent_core::session::SessionManager::prune)).
+//! The limits are re-read each time, so config changes apply without a
+//! restart. `agent-shell sessions prune --dry-run` previews a run.
+
+use crate::state::AppState;
+use std::time::Duration;
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Prune once with the current config. Returns how many sessions went.
+pub async fn prune_once(state: &AppState) -> usize {
+    let policy = state.config.read().await.session.clone();
+    if !policy.has_retention() {
+        return 0;
+    }
+    let mut sm = state.session_manager.write().await;
+    match sm.prune(&policy, chrono::Utc::now(), false) {
+        Ok(pruned) => {
+            for session in &pruned {
+                tracing::info!(
+                    "Pruned session {} ({}): {:?}, last updated {}",
+                    session.name,
+                    session.id,
+                    session.reason,
+                    session.updated_at
+                );
+            }
+            pruned.len()
+        }
+        Err(e) => {
+            tracing::warn!("Session pruning failed: {}", e);
+            0
+        }
+    }
+}
+
+/// Prune at startup and every hour while the server runs.
+pub fn spawn_pruner(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            prune_once(&state).await;
+        }
+    });
+}
@@ -1,24 +1,37 @@
+use crate::caller::Caller;
+use crate::editor::{self, EditorActionRequest, EditorActionResponse};
+use crate::error::{ApiError, ErrorCode};
+use crate::health::{self, Readiness};
+use crate::preferences::UiPreferences;
+use crate::request_id::RequestId;
+use crate::runs::SessionLock;
 use crate::state::AppState;
+use crate::validation::{RequestSchema, ValidJson};
+use agent_core::agents::{self, AgentPreset, AgentSource};
 use agent_core::context::ContextLinker;
-use agent_core::types::{AgentEvent, Message};
-use agent_plugins::PluginInfo;
+use agent_core::checkpoint::{FileDiff, Rollback};
+use agent_core::path_rules::PathRules;
+use agent_core::scheduler::ScheduleRun;
+use agent_core::types::{AgentEvent, ImagePart, Message, ToolCall, ToolOutput};
+use agent_core::workspace;
+use agent_plugins::{PluginCategory, PluginInfo, PluginPermissions};
 use agent_pty::ShellInfo;
 use agent_skills::SearchOptions;
 use agent_tools::env_detect;
 use axum::extract::State;
 use axum::http::StatusCode;
-use axum::response::sse::{Event, Sse};
+use axum::response::sse::Sse;
 use axum::response::{IntoResponse, Json};
 use axum::routing::{get, post};
 use axum::Router;
-use chrono::Datelike;
 use axum::routing::put;
 use serde::{Deserialize, Serialize};
-use tokio_stream::wrappers::UnboundedReceiverStream;
 use tokio_stream::StreamExt;
+use tracing::Instrument;
+use utoipa::{IntoParams, ToSchema};
 
 /// Validate that a session ID is a valid UUID to prevent path traversal.
-fn validate_session_id(id: &str) -> Result<(), (StatusCode, String)> {
+fn validate_session_id(id: &str) -> Result<(), ApiError> {
     // Accept standard UUID format: 8-4-4-4-12 hex digits.
     let is_valid = id.len() == 36
         && id.chars().enumerate().all(|(i, c)| match i {
@@ -26,10 +39,7 @@ fn validate_session_id(id: &str) -> Result<(), (StatusCode, String)> {
             _ => c.is_ascii_hexdigit(),
         });
     if !is_valid {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            "Invalid session ID format (expected UUID)".into(),
-        ));
+        return Err(ApiError::bad_request("Invalid session ID format (expected UUID)"));
     }
     Ok(())
 }
@@ -37,9 +47,23 @@ fn validate_session_id(id: &str) -> Result<(), (StatusCode, String)> {
 // ── Health ──────────────────────────────────────────────────────────────
 
 pub fn health_routes() -> Router<AppState> {
-    Router::new().route("/health", get(health))
+    Router::new()
+        .route("/health", get(health))
+        .route("/health/ready", get(health_ready))
 }
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    security(()),
+    responses((
+        status = 200,
+        description = "The server is up",
+        body = Object,
+        example = json!({ "status": "ok", "version": "0.1.0" })
+    ))
+)]
 async fn health() -> impl IntoResponse {
     Json(serde_json::json!({
         "status": "ok",
@@ -47,14 +71,201 @@ async fn health() -> impl IntoResponse {
     }))
 }
 
+/// Check the server's dependencies (provider, Docker, skills, disk,
+/// plugins) for orchestrator readiness probes.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "Ready; components may be degraded", body = Readiness),
+        (status = 503, description = "A component has failed", body = Readiness)
+    )
+)]
+async fn health_ready(State(state): State<AppState>) -> impl IntoResponse {
+    let readiness = health::check(&state).await;
+    let status = if readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
 // ── Chat ────────────────────────────────────────────────────────────────
 
 pub fn chat_routes() -> Router<AppState> {
-    Router::new().route("/v1/chat/completions", post(chat_completions))
+    Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/runs", post(create_run))
+        .route("/v1/runs/{id}/events", get(run_events))
 }
 
-#[derive(Debug, Deserialize)]
+/// Header carrying the ID of the session a run was recorded in.
+pub(crate) const RUN_SESSION_HEADER: &str = "x-session-id";
+
+/// Header carrying the ID a streamed turn can be resumed under.
+pub(crate) const RUN_ID_HEADER: &str = "x-run-id";
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct RunRequest {
+    /// The task, sent as the user message of a new session.
+    task: String,
+    /// Agent preset to run with.
+    #[serde(default)]
+    agent: Option<String>,
+    /// Session name. Defaults to "Run: <start of task>".
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default = "default_run_stream")]
+    stream: bool,
+}
+
+fn default_run_stream() -> bool {
+    true
+}
+
+impl RequestSchema for RunRequest {
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "task": { "type": "string", "minLength": 1 },
+                "agent": { "type": ["string", "null"] },
+                "name": { "type": ["string", "null"], "maxLength": MAX_SESSION_NAME_CHARS },
+                "stream": { "type": "boolean" }
+            },
+            "required": ["task"],
+            "additionalProperties": false
+        })
+    }
+}
+
+/// Run a task in a new session, e.g. delegated by another instance's
+/// `remote_agent` tool. Responds like `/v1/chat/completions`, with the
+/// session's ID in the `x-session-id` header.
+#[utoipa::path(
+    post,
+    path = "/v1/runs",
+    tag = "chat",
+    request_body = RunRequest,
+    responses((
+        status = 200,
+        description = "The turn, as server-sent events (default) or one completion",
+        headers(
+            ("x-session-id" = String, description = "ID of the new session"),
+            ("x-run-id" = String, description = "ID to resume a streamed turn under")
+        ),
+        content((ChatResponse = "application/json"), (String = "text/event-stream"))
+    ))
+)]
+async fn create_run(
+    State(state): State<AppState>,
+    caller: Caller,
+    request_id: RequestId,
+    ValidJson(req): ValidJson<RunRequest>,
+) -> Result<axum::response::Response, ApiError> {
+    let task = req.task.trim();
+    if task.is_empty() {
+        return Err(ApiError::bad_request("task is required"));
+    }
+    if let Some(agent) = &req.agent {
+        let config = state.config.read().await;
+        if state.agents.read().await.resolve(&config, agent).is_none() {
+            return Err(ApiError::bad_request(format!("Unknown agent: {}", agent)));
+        }
+    }
+    let name = req.name.unwrap_or_else(|| {
+        let head: String = task.chars().take(40).collect();
+        format!("Run: {}", head.lines().next().unwrap_or_default())
+    });
+
+    let (id, lock) = {
+        let mut sm = state.session_manager.write().await;
+        let id = sm
+            .create_session_with_agent(name, req.agent)
+            .map_err(ApiError::from)?
+            .id
+            .clone();
+        caller.claim(&mut sm, &id).await?;
+        let lock = lock_session(&state, &id)?;
+        sm.push_message_in_async(&id, Message::user(task))
+            .await
+            .map_err(ApiError::from)?;
+        (id, lock)
+    };
+
+    let mut response = run_agent_turn(state, req.stream, request_id, lock).await?;
+    if let Ok(value) = axum::http::HeaderValue::from_str(&id) {
+        response.headers_mut().insert(RUN_SESSION_HEADER, value);
+    }
+    Ok(response)
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct RunEventsQuery {
+    /// Last event ID received; an alternative to the `Last-Event-ID` header.
+    #[serde(default)]
+    after: Option<u64>,
+}
+
+/// Resume a streamed turn after a dropped connection: replays the events
+/// after `Last-Event-ID`, then follows the turn until it ends.
+#[utoipa::path(
+    get,
+    path = "/v1/runs/{id}/events",
+    tag = "chat",
+    params(
+        ("id" = String, Path, description = "Run ID from the `x-run-id` header"),
+        ("Last-Event-ID" = Option<u64>, Header, description = "Last event ID received"),
+        RunEventsQuery
+    ),
+    responses((
+        status = 200,
+        description = "Events after the given ID, then the rest of the turn",
+        body = String,
+        content_type = "text/event-stream"
+    ))
+)]
+async fn run_events(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<RunEventsQuery>,
+    headers: axum::http::HeaderMap,
+) -> Result<axum::response::Response, ApiError> {
+    let not_found = || ApiError::new(ErrorCode::RunNotFound, format!("Run not found: {}", id));
+    let run = state.runs.get(&id).ok_or_else(not_found)?;
+    // Other keys' runs look missing; a run outside any session is admin-only.
+    let authorized = match &run.session_id {
+        Some(session_id) => {
+            let sm = state.session_manager.read().await;
+            caller.authorize(&sm, session_id).is_ok()
+        }
+        None => caller.admin,
+    };
+    if !authorized {
+        return Err(not_found());
+    }
+    let after = match headers.get("last-event-id") {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .ok_or_else(|| ApiError::bad_request("Invalid Last-Event-ID"))?,
+        None => query.after.unwrap_or(0),
+    };
+    let stream = run
+        .follow(after)
+        .map(|frame| Ok::<_, std::convert::Infallible>(frame.to_sse()));
+    Ok(Sse::new(stream).into_response())
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 struct ChatRequest {
+    #[schema(min_items = 1)]
     messages: Vec<ChatMessage>,
     #[serde(default)]
     stream: bool,
@@ -64,13 +275,67 @@ struct ChatRequest {
     session_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 struct ChatMessage {
     role: String,
     content: String,
+    /// Images sent with a user message.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    images: Vec<ChatImage>,
+}
+
+/// An image in a chat request: inline base64 `data` (optionally a full
+/// `data:` URL) or a `path` inside the workspace.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+struct ChatImage {
+    #[serde(default)]
+    data: Option<String>,
+    #[serde(default)]
+    media_type: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+}
+
+impl RequestSchema for ChatRequest {
+    /// Extra fields OpenAI clients send (model, temperature, ...) are allowed.
+    fn schema() -> serde_json::Value {
+        let optional_string = serde_json::json!({ "type": ["string", "null"] });
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "messages": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "role": { "type": "string" },
+                            "content": { "type": "string" },
+                            "images": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "properties": {
+                                        "data": optional_string,
+                                        "media_type": optional_string,
+                                        "path": optional_string
+                                    },
+                                    "additionalProperties": false
+                                }
+                            }
+                        },
+                        "required": ["role", "content"]
+                    }
+                },
+                "stream": { "type": "boolean" },
+                "session_id": optional_string
+            },
+            "required": ["messages"]
+        })
+    }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct ChatResponse {
     id: String,
     /// The session ID that this response belongs to.
@@ -78,129 +343,385 @@ struct ChatResponse {
     choices: Vec<ChatChoice>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct ChatChoice {
     index: usize,
     message: ChatMessage,
     finish_reason: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/v1/chat/completions",
+    tag = "chat",
+    request_body = ChatRequest,
+    responses((
+        status = 200,
+        description = "The reply, as server-sent events when `stream` is set",
+        headers(("x-run-id" = String, description = "ID to resume a streamed reply under")),
+        content((ChatResponse = "application/json"), (String = "text/event-stream"))
+    ))
+)]
 async fn chat_completions(
     State(state): State<AppState>,
-    Json(req): Json<ChatRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+    caller: Caller,
+    request_id: RequestId,
+    ValidJson(req): ValidJson<ChatRequest>,
+) -> Result<axum::response::Response, ApiError> {
     // Only the last message is used as the new user input.
     // Full conversation history is managed server-side via sessions.
     let user_msg = req
         .messages
         .last()
-        .ok_or((StatusCode::BAD_REQUEST, "No messages provided".into()))?;
+        .ok_or_else(|| ApiError::bad_request("No messages provided"))?;
 
-    let message = Message::user(&user_msg.content);
+    let (sandbox, max_image_bytes) = {
+        let cfg = state.config.read().await;
+        (cfg.sandbox.clone(), cfg.server.max_upload_bytes)
+    };
 
-    // If session_id is provided, switch to that session.
-    if let Some(ref sid) = req.session_id {
-        validate_session_id(sid)?;
+    let lock = {
         let mut sm = state.session_manager.write().await;
-        sm.switch_session(sid)
-            .map_err(|e| (StatusCode::NOT_FOUND, format!("Session not found: {}", e)))?;
+        // If session_id is provided, switch to that session.
+        let sid = if let Some(ref sid) = req.session_id {
+            validate_session_id(sid)?;
+            caller.authorize(&sm, sid)?;
+            sm.switch_session(sid)
+                .map_err(|_| ApiError::session_not_found(sid))?;
+            sid.clone()
+        } else {
+            let session = sm
+                .active_session()
+                .ok_or_else(|| ApiError::new(ErrorCode::SessionError, "No active session"))?;
+            // The active session is shared by every client; a named key only
+            // continues it when it owns it.
+            if !caller.admin && !caller.can_access(session) {
+                return Err(ApiError::bad_request("session_id is required with this API key"));
+            }
+            session.id.clone()
+        };
+        let roots = session_roots(&sandbox, &sm, &sid);
+        let images =
+            resolve_chat_images(&user_msg.images, roots, &sandbox, max_image_bytes).await?;
+        let message = Message::user(&user_msg.content).with_images(images);
+        let lock = lock_session(&state, &sid)?;
+
+        // Add message to session (non-blocking async save).
+        sm.auto_checkpoint_in(&sid).map_err(ApiError::from)?;
+        sm.push_message_in_async(&sid, message)
+            .await
+            .map_err(ApiError::from)?;
+        lock
+    };
+
+    run_agent_turn(state, req.stream, request_id, lock).await
+}
+
+/// Claim a session for a turn, or fail with 409 naming the run already
+/// streaming in it, if the turn in progress is streamed.
+/// `session_id` if given and well-formed, else the active session's ID.
+async fn session_or_active(
+    state: &AppState,
+    session_id: Option<String>,
+) -> Result<String, ApiError> {
+    match session_id {
+        Some(sid) => {
+            validate_session_id(&sid)?;
+            Ok(sid)
+        }
+        None => {
+            let sm = state.session_manager.read().await;
+            sm.active_session_id()
+                .map(String::from)
+                .ok_or_else(|| ApiError::bad_request("No active session"))
+        }
     }
+}
 
-    // Add message to session (non-blocking async save).
-    {
-        let mut sm = state.session_manager.write().await;
-        sm.push_message_async(message)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+fn lock_session(state: &AppState, session_id: &str) -> Result<SessionLock, ApiError> {
+    state.session_locks.acquire(session_id).map_err(|holder| {
+        let message = match &holder {
+            Some(run_id) => format!("Session {} is busy with run {}", session_id, run_id),
+            None => format!("Session {} is busy with another turn", session_id),
+        };
+        ApiError::new(ErrorCode::SessionBusy, message)
+            .with_details(serde_json::json!({ "session_id": session_id, "run_id": holder }))
+    })
+}
+
+/// Turn request images into inline image parts, reading paths in the
+/// session's workspaces (see [`resolve_workspace_path`]) and checking that
+/// inline data is valid base64 of an image.
+async fn resolve_chat_images(
+    images: &[ChatImage],
+    roots: Vec<std::path::PathBuf>,
+    sandbox: &agent_core::config::SandboxConfig,
+    max_bytes: usize,
+) -> Result<Vec<ImagePart>, ApiError> {
+    use base64::Engine;
+
+    let bad = |msg: String| ApiError::bad_request(msg);
+    let mut parts = Vec::with_capacity(images.len());
+    for (i, image) in images.iter().enumerate() {
+        let n = i + 1;
+        let (media_type, bytes) = match (&image.data, &image.path) {
+            (Some(data), None) => {
+                let (url_type, data) = match data.strip_prefix("data:") {
+                    Some(rest) => {
+                        let (meta, payload) = rest
+                            .split_once(',')
+                            .ok_or_else(|| bad(format!("image {n}: malformed data URL")))?;
+                        let url_type = meta.strip_suffix(";base64").ok_or_else(|| {
+                            bad(format!("image {n}: data URL must be base64-encoded"))
+                        })?;
+                        (Some(url_type.to_string()), payload)
+                    }
+                    None => (None, data.as_str()),
+                };
+                let media_type = image
+                    .media_type
+                    .clone()
+                    .or(url_type)
+                    .ok_or_else(|| bad(format!("image {n}: media_type is required")))?;
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(data.trim())
+                    .map_err(|e| bad(format!("image {n}: invalid base64: {e}")))?;
+                (media_type, bytes)
+            }
+            (None, Some(path)) => {
+                let (resolved, display) =
+                    resolve_workspace_path(path, roots.clone(), sandbox).await?;
+                let media_type = ImagePart::media_type_for_path(&resolved)
+                    .ok_or_else(|| bad(format!("image {n}: {display} is not a supported image")))?;
+                let bytes = std::fs::read(&resolved).map_err(|e| {
+                    ApiError::not_found(format!("image {n}: failed to read {display}: {e}"))
+                })?;
+                (media_type.to_string(), bytes)
+            }
+            _ => return Err(bad(format!("image {n}: provide exactly one of data or path"))),
+        };
+        if !media_type.starts_with("image/") {
+            return Err(bad(format!("image {n}: unsupported media type {media_type}")));
+        }
+        if bytes.len() > max_bytes {
+            return Err(ApiError::new(ErrorCode::PayloadTooLarge, format!("image {n} is {} bytes; the limit is {max_bytes}", bytes.len())));
+        }
+        parts.push(ImagePart::from_bytes(media_type, &bytes));
     }
+    Ok(parts)
+}
+
+/// Resolve a session's agent preset, with its skills' SKILL.md appended to
+/// the system prompt. A preset deleted since the session was created is
+/// ignored.
+async fn session_agent(state: &AppState, name: &str) -> Option<AgentPreset> {
+    let config = state.config.read().await;
+    let Some((preset, _)) = state.agents.read().await.resolve(&config, name) else {
+        tracing::warn!("Session agent '{}' no longer exists; using defaults", name);
+        return None;
+    };
+    let docs = state.skill_indexer.skill_docs(&preset.skills);
+    Some(preset.with_skill_docs(config.system_prompt.as_deref(), &docs))
+}
 
-    // Get message history and active session ID.
-    let (messages, active_session_id): (Vec<Message>, Option<String>) = {
+/// Run the agent over the history of the session `lock` holds and respond,
+/// either as an SSE stream or a single JSON completion. A streamed turn
+/// gets a run with a fresh ID that records `request_id` for correlation.
+/// The turn reads and saves only that session, whichever one is active,
+/// and `lock` is held until its messages are saved.
+async fn run_agent_turn(
+    state: AppState,
+    stream: bool,
+    RequestId(request_id): RequestId,
+    lock: SessionLock,
+) -> Result<axum::response::Response, ApiError> {
+    // Get message history and the session's tool policy and workspaces.
+    let session_id = lock.session().to_string();
+    let sandbox = state.config.read().await.sandbox.clone();
+    let (messages, agent, allowlist, denylist, tool_calls, roots) = {
         let sm = state.session_manager.read().await;
+        let session = sm.session(&session_id);
         (
-            sm.recent_messages().into_iter().cloned().collect(),
-            sm.active_session_id().map(String::from),
+            sm.recent_messages_in(&session_id).into_iter().cloned().collect::<Vec<Message>>(),
+            session.and_then(|s| s.agent.clone()),
+            session.and_then(|s| s.tool_allowlist.clone()),
+            session.map(|s| s.tool_denylist.clone()).unwrap_or_default(),
+            session.map(|s| s.tool_calls.clone()).unwrap_or_default(),
+            session
+                .map(|s| workspace::roots_for(&sandbox, s))
+                .unwrap_or_default(),
         )
     };
+    let preset = match agent {
+        Some(name) => session_agent(&state, &name).await,
+        None => None,
+    };
+
+    if stream {
+        // Usage events report the session's running total.
+        let mut session_usage = {
+            let sm = state.session_manager.read().await;
+            sm.session(&session_id).map(|s| s.usage).unwrap_or_default()
+        };
+        let (budget_usd, budget_tokens) = {
+            let config = state.config.read().await;
+            (config.session.budget_usd, config.session.budget_tokens)
+        };
 
-    if req.stream {
         // SSE streaming response.
-        let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<AgentEvent>();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<AgentEvent>();
+
+        // Events are recorded as numbered frames whether or not a client is
+        // connected, so a dropped stream can resume via /v1/runs/{id}/events.
+        let (run_id, run) = state.runs.start(&request_id, Some(&session_id));
+        lock.set_run(&run_id);
 
         let agent_loop_lock = state.agent_loop.clone();
         let session_manager = state.session_manager.clone();
+        let turn_session = session_id.clone();
+        // The turn outlives the request, so it gets its own span.
+        let span = tracing::info_span!("run", run_id = %run_id, request_id = %request_id);
         tokio::spawn(async move {
             let agent_loop = agent_loop_lock.read().await;
-            let result = agent_loop.run(&messages, None, &[], tx.clone()).await;
+            let turn = agent_loop.run_as(
+                preset.as_ref(),
+                &messages,
+                allowlist.as_deref(),
+                &denylist,
+                &tool_calls,
+                tx.clone(),
+            );
+            let result = workspace::scope(roots, turn).await;
             match result {
                 Ok(turn_result) => {
                     let mut sm = session_manager.write().await;
+                    if let Err(e) = sm.record_snapshots_in(&turn_session, turn_result.snapshots) {
+                        tracing::warn!("Failed to record file snapshots: {}", e);
+                    }
+                    if let Err(e) = sm.record_tool_calls_in(&turn_session, &turn_result.tool_calls)
+                    {
+                        tracing::warn!("Failed to record tool calls: {}", e);
+                    }
+                    if let Err(e) = sm.record_usage_in(&turn_session, turn_result.usage) {
+                        tracing::warn!("Failed to record token usage: {}", e);
+                    }
                     for msg in turn_result.messages {
-                        let _ = sm.push_message_async(msg).await;
+                        let _ = sm.push_message_in_async(&turn_session, msg).await;
                     }
                 }
                 Err(e) => {
+                    tracing::warn!("Agent turn failed: {}", e);
                     let _ = tx.send(AgentEvent::Error(e.to_string()));
                 }
             }
-        });
+            drop(lock);
+        }.instrument(span));
 
-        let stream = UnboundedReceiverStream::new(rx).map(|event| {
-            let sse_event: Result<Event, std::convert::Infallible> = match event {
-                AgentEvent::ContentChunk(token) => Ok(Event::default()
-                    .json_data(serde_json::json!({
-                        "choices": [{"delta": {"content": token}}]
-                    }))
-                    .unwrap()),
-                AgentEvent::ToolCallStart { name, .. } => Ok(Event::default()
-                    .event("tool_call")
-                    .json_data(serde_json::json!({"tool": name, "status": "started"}))
-                    .unwrap()),
-                AgentEvent::ToolResult(output) => Ok(Event::default()
-                    .event("tool_result")
-                    .json_data(serde_json::json!({
-                        "tool_call_id": output.tool_call_id,
-                        "content": output.content,
-                        "is_error": output.is_error,
-                    }))
-                    .unwrap()),
-                AgentEvent::Done(_) => Ok(Event::default().data("[DONE]")),
-                AgentEvent::Error(e) => Ok(Event::default().event("error").data(e)),
-                _ => Ok(Event::default().comment("ping")),
-            };
-            sse_event
+        let recorder = run.clone();
+        let frame_run_id = run_id.clone();
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                let json = |value: serde_json::Value| Some(value.to_string());
+                let (name, data) = match event {
+                    AgentEvent::ContentChunk(token) => (
+                        None,
+                        json(serde_json::json!({
+                            "choices": [{"delta": {"content": token}}]
+                        })),
+                    ),
+                    AgentEvent::ToolCallStart { id, name, arguments } => (
+                        Some("tool_call"),
+                        json(serde_json::json!({
+                            "id": id,
+                            "tool": name,
+                            "arguments": arguments,
+                            "status": "started",
+                            "run_id": frame_run_id,
+                        })),
+                    ),
+                    AgentEvent::ToolResult(output) => (
+                        Some("tool_result"),
+                        json(serde_json::json!({
+                            "tool_call_id": output.tool_call_id,
+                            "content": output.content,
+                            "is_error": output.is_error,
+                            "duration_ms": output.duration_ms,
+                            "run_id": frame_run_id,
+                        })),
+                    ),
+                    AgentEvent::Usage(usage) => {
+                        session_usage += usage;
+                        let usage =
+                            SessionUsageResponse::new(session_usage, budget_usd, budget_tokens);
+                        (Some("usage"), serde_json::to_string(&usage).ok())
+                    }
+                    AgentEvent::Done(_) => (None, Some("[DONE]".to_string())),
+                    AgentEvent::Error(e) => (Some("error"), Some(e)),
+                    _ => (None, None),
+                };
+                recorder.push(name, data);
+            }
+            recorder.finish();
         });
 
-        Ok(Sse::new(stream).into_response())
+        let stream = run
+            .follow(0)
+            .map(|frame| Ok::<_, std::convert::Infallible>(frame.to_sse()));
+        let mut response = Sse::new(stream).into_response();
+        if let Ok(value) = axum::http::HeaderValue::from_str(&run_id) {
+            response.headers_mut().insert(RUN_ID_HEADER, value);
+        }
+        Ok(response)
     } else {
         // Non-streaming response.
         let (tx, _rx) = tokio::sync::mpsc::unbounded_channel::<AgentEvent>();
 
         let result = {
             let agent_loop = state.agent_loop.read().await;
-            agent_loop
-                .run(&messages, None, &[], tx)
+            let turn = agent_loop.run_as(
+                preset.as_ref(),
+                &messages,
+                allowlist.as_deref(),
+                &denylist,
+                &tool_calls,
+                tx,
+            );
+            workspace::scope(roots, turn)
                 .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+                .map_err(|e| {
+                    tracing::warn!("Agent turn failed: {}", e);
+                    ApiError::from(e)
+                })?
         };
 
         // Save all messages (including intermediate tool calls) for complete history.
         let final_msg = result.final_message().clone();
         {
             let mut sm = state.session_manager.write().await;
+            if let Err(e) = sm.record_snapshots_in(&session_id, result.snapshots) {
+                tracing::warn!("Failed to record file snapshots: {}", e);
+            }
+            if let Err(e) = sm.record_tool_calls_in(&session_id, &result.tool_calls) {
+                tracing::warn!("Failed to record tool calls: {}", e);
+            }
+            if let Err(e) = sm.record_usage_in(&session_id, result.usage) {
+                tracing::warn!("Failed to record token usage: {}", e);
+            }
             for msg in result.messages {
-                let _ = sm.push_message_async(msg).await;
+                let _ = sm.push_message_in_async(&session_id, msg).await;
             }
         }
+        drop(lock);
 
         let response = ChatResponse {
             id: final_msg.id.clone(),
-            session_id: active_session_id,
+            session_id: Some(session_id),
             choices: vec![ChatChoice {
                 index: 0,
                 message: ChatMessage {
                     role: "assistant".into(),
                     content: final_msg.content.clone(),
+                    images: Vec::new(),
                 },
                 finish_reason: "stop".into(),
             }],
@@ -213,35 +734,543 @@ async fn chat_completions(
 // ── Sessions ────────────────────────────────────────────────────────────
 
 pub fn session_routes() -> Router<AppState> {
-    Router::new().route("/v1/sessions", get(list_sessions).post(create_session))
+    Router::new()
+        .route("/v1/sessions", get(list_sessions).post(create_session))
+        .route("/v1/sessions/{id}/fork", post(fork_session))
+        .route("/v1/sessions/{id}/checkpoints", get(list_checkpoints))
+        .route("/v1/sessions/{id}/rollback", post(rollback_session))
+        .route("/v1/sessions/{id}/usage", get(session_usage))
+        .route("/v1/sessions/{id}/changes", get(list_file_changes))
+        .route("/v1/sessions/{id}/changes/revert", post(revert_file_changes))
+        .route("/v1/sessions/{id}/changes/accept", post(accept_file_changes))
+        .route(
+            "/v1/sessions/{id}/share",
+            post(share_session).delete(unshare_session),
+        )
+        .route("/v1/sessions/{id}/workspaces", put(set_session_workspaces))
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct SessionInfo {
     id: String,
     name: String,
     message_count: usize,
     updated_at: String,
+    tags: Vec<String>,
 }
 
-async fn list_sessions(State(state): State<AppState>) -> impl IntoResponse {
-    let sm = state.session_manager.read().await;
+/// Largest page a listing returns.
+const MAX_PAGE_LIMIT: usize = 500;
+
+/// Header with the number of items matching a listing's filters.
+pub(crate) const TOTAL_COUNT_HEADER: &str = "x-total-count";
+
+/// Header with the `after` cursor for the next page, when there is one.
+pub(crate) const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+/// Where a listing page starts and how long it is.
+struct Page<'a> {
+    limit: Option<usize>,
+    offset: usize,
+    after: Option<&'a str>,
+}
+
+impl Page<'_> {
+    /// Cut this page from `items` (already filtered), returning it with
+    /// the total-count and next-cursor headers.
+    fn apply<T>(
+        &self,
+        items: Vec<T>,
+        id: impl Fn(&T) -> &str,
+    ) -> Result<(axum::http::HeaderMap, Vec<T>), ApiError> {
+        if self.limit.is_some_and(|l| !(1..=MAX_PAGE_LIMIT).contains(&l)) {
+            return Err(ApiError::bad_request(format!(
+                "limit must be between 1 and {}",
+                MAX_PAGE_LIMIT
+            )));
+        }
+        let total = items.len();
+        let start = match self.after {
+            Some(after) => items
+                .iter()
+                .position(|item| id(item) == after)
+                .map(|i| i + 1)
+                .ok_or_else(|| ApiError::bad_request(format!("Unknown cursor: {}", after)))?,
+            None => 0,
+        };
+        let start = (start + self.offset).min(total);
+        let end = self.limit.map_or(total, |l| (start + l).min(total));
+
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(TOTAL_COUNT_HEADER, total.into());
+        if end < total && end > 0 {
+            if let Ok(cursor) = id(&items[end - 1]).parse() {
+                headers.insert(NEXT_CURSOR_HEADER, cursor);
+            }
+        }
+        let page = items.into_iter().skip(start).take(end - start).collect();
+        Ok((headers, page))
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SessionListQuery {
+    /// Sessions to return (1–500); all when unset.
+    limit: Option<usize>,
+    /// Sessions to skip, after `after` when both are given.
+    #[serde(default)]
+    offset: usize,
+    /// Start after this session ID (the previous page's `x-next-cursor`).
+    after: Option<String>,
+    /// Only sessions updated at or after this time (RFC 3339).
+    updated_after: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only sessions updated before this time (RFC 3339).
+    updated_before: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only sessions with this tag.
+    tag: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/sessions",
+    tag = "sessions",
+    params(SessionListQuery),
+    responses((
+        status = 200,
+        description = "Saved sessions, most recently updated first",
+        body = Vec<SessionInfo>,
+        headers(
+            ("x-total-count" = usize, description = "Sessions matching the filters"),
+            ("x-next-cursor" = String, description = "`after` for the next page, if any")
+        )
+    ))
+)]
+async fn list_sessions(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Query(query): axum::extract::Query<SessionListQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut sm = state.session_manager.write().await;
+    // Include sessions the REPL or another server created meanwhile.
+    if let Err(e) = sm.reload_changed() {
+        tracing::warn!("Failed to reload sessions: {}", e);
+    }
     let sessions: Vec<SessionInfo> = sm
         .list_sessions()
         .into_iter()
-        .map(|(id, name, updated, count)| SessionInfo {
-            id: id.to_string(),
-            name: name.to_string(),
-            message_count: count,
-            updated_at: updated.to_rfc3339(),
+        .filter_map(|(id, ..)| sm.session(id))
+        .filter(|s| caller.can_access(s))
+        .filter(|s| query.updated_after.is_none_or(|t| s.updated_at >= t))
+        .filter(|s| query.updated_before.is_none_or(|t| s.updated_at < t))
+        .filter(|s| query.tag.as_ref().is_none_or(|t| s.tags.contains(t)))
+        .map(|s| SessionInfo {
+            id: s.id.clone(),
+            name: s.name.clone(),
+            message_count: s.messages.len(),
+            updated_at: s.updated_at.to_rfc3339(),
+            tags: s.tags.clone(),
         })
         .collect();
-    Json(sessions)
+    drop(sm);
+
+    let page = Page {
+        limit: query.limit,
+        offset: query.offset,
+        after: query.after.as_deref(),
+    };
+    let (headers, sessions) = page.apply(sessions, |s| &s.id)?;
+    Ok((headers, Json(sessions)))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, ToSchema)]
+struct SessionCreated {
+    id: String,
+    name: String,
+    /// Agent preset for every turn in the session.
+    agent: Option<String>,
+}
+
+/// Longest session name accepted by the API.
+const MAX_SESSION_NAME_CHARS: u64 = 200;
+
+#[derive(Debug, Deserialize, ToSchema)]
 struct CreateSessionRequest {
     name: String,
+    /// Agent preset for every turn in the session.
+    #[serde(default)]
+    agent: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+struct ForkSessionRequest {
+    /// Name for the copy. Defaults to "<original> (fork)".
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct RollbackRequest {
+    /// Checkpoint to roll back to. Defaults to undoing the last turn.
+    #[serde(default)]
+    checkpoint: Option<String>,
+    /// Also restore files tools modified since the checkpoint.
+    #[serde(default = "default_restore_files")]
+    restore_files: bool,
+}
+
+fn default_restore_files() -> bool {
+    true
+}
+
+impl RequestSchema for CreateSessionRequest {
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 1, "maxLength": MAX_SESSION_NAME_CHARS },
+                "agent": { "type": ["string", "null"] }
+            },
+            "required": ["name"],
+            "additionalProperties": false
+        })
+    }
+}
+
+impl RequestSchema for ForkSessionRequest {
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": ["string", "null"],
+                    "minLength": 1,
+                    "maxLength": MAX_SESSION_NAME_CHARS
+                }
+            },
+            "additionalProperties": false
+        })
+    }
+}
+
+impl RequestSchema for RollbackRequest {
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "checkpoint": { "type": ["string", "null"] },
+                "restore_files": { "type": "boolean" }
+            },
+            "additionalProperties": false
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+struct FileChangeRequest {
+    /// File to revert or accept. Defaults to every changed file.
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    path: Option<std::path::PathBuf>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct CheckpointInfo {
+    id: String,
+    label: Option<String>,
+    created_at: String,
+    message_count: usize,
+    /// Files modified since the checkpoint.
+    files: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct SessionWorkspacesRequest {
+    /// Workspace names, primary first; empty for `[sandbox] workspace_root`.
+    workspaces: Vec<String>,
+}
+
+impl RequestSchema for SessionWorkspacesRequest {
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "workspaces": { "type": "array", "items": { "type": "string", "minLength": 1 } }
+            },
+            "required": ["workspaces"],
+            "additionalProperties": false
+        })
+    }
+}
+
+/// Select the workspaces a session's tools work in.
+#[utoipa::path(
+    put,
+    path = "/v1/sessions/{id}/workspaces",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID (UUID)")),
+    request_body = SessionWorkspacesRequest,
+    responses((status = 200, description = "The session's active workspaces, primary first", body = Vec<WorkspaceInfo>))
+)]
+async fn set_session_workspaces(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    ValidJson(req): ValidJson<SessionWorkspacesRequest>,
+) -> Result<Json<Vec<WorkspaceInfo>>, ApiError> {
+    validate_session_id(&id)?;
+    let sandbox = state.config.read().await.sandbox.clone();
+    workspace::resolve(&sandbox, &req.workspaces)
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+
+    let mut sm = state.session_manager.write().await;
+    caller.authorize(&sm, &id)?;
+    let session = sm
+        .session_mut(&id)
+        .ok_or_else(|| ApiError::session_not_found(&id))?;
+    session.workspaces = req.workspaces;
+    let active = workspace::active(&sandbox, &session.workspaces);
+    sm.save_session_async(&id)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(
+        active
+            .into_iter()
+            .enumerate()
+            .map(|(i, ws)| WorkspaceInfo {
+                name: ws.name,
+                path: ws.root.display().to_string(),
+                active: true,
+                primary: i == 0,
+            })
+            .collect(),
+    ))
+}
+
+// ── Sharing ─────────────────────────────────────────────────────────────
+
+/// Public read-only session views. Served without auth: the token in the
+/// URL is the credential.
+pub fn share_routes() -> Router<AppState> {
+    Router::new().route("/share/{token}", get(view_shared_session))
+}
+
+/// Create (or return the existing) read-only share link for a session.
+#[utoipa::path(
+    post,
+    path = "/v1/sessions/{id}/share",
+    tag = "sharing",
+    params(("id" = String, Path, description = "Session ID (UUID)")),
+    responses((status = 200, description = "The share link", body = Object, example = json!({ "token": "k3y", "url": "/share/k3y" })))
+)]
+async fn share_session(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    validate_session_id(&id)?;
+    let mut sm = state.session_manager.write().await;
+    caller.authorize(&sm, &id)?;
+    let token = sm
+        .session_mut(&id)
+        .ok_or_else(|| ApiError::session_not_found(&id))?
+        .share()
+        .to_string();
+    sm.save_session_async(&id)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(serde_json::json!({
+        "token": token,
+        "url": format!("/share/{}", token),
+    })))
+}
+
+/// Revoke a session's share link.
+#[utoipa::path(
+    delete,
+    path = "/v1/sessions/{id}/share",
+    tag = "sharing",
+    params(("id" = String, Path, description = "Session ID (UUID)")),
+    responses((status = 204, description = "The link was revoked"))
+)]
+async fn unshare_session(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<StatusCode, ApiError> {
+    validate_session_id(&id)?;
+    let mut sm = state.session_manager.write().await;
+    caller.authorize(&sm, &id)?;
+    let shared = sm
+        .session_mut(&id)
+        .ok_or_else(|| ApiError::session_not_found(&id))?
+        .unshare();
+    if !shared {
+        return Err(ApiError::not_found(format!("Session {} is not shared", id)));
+    }
+    sm.save_session_async(&id)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/share/{token}",
+    tag = "sharing",
+    security(()),
+    params(("token" = String, Path, description = "Share token")),
+    responses((status = 200, description = "Read-only transcript", body = String, content_type = "text/html"))
+)]
+async fn view_shared_session(
+    State(state): State<AppState>,
+    axum::extract::Path(token): axum::extract::Path<String>,
+) -> axum::response::Response {
+    let sm = state.session_manager.read().await;
+    let Some(session) = sm.shared_session(&token) else {
+        return (
+            StatusCode::NOT_FOUND,
+            axum::response::Html("<p>This share link does not exist or was revoked.</p>"),
+        )
+            .into_response();
+    };
+    (
+        [
+            (axum::http::header::CONTENT_TYPE, "text/html; charset=utf-8"),
+            (
+                axum::http::header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; style-src 'unsafe-inline'",
+            ),
+            (axum::http::header::REFERRER_POLICY, "no-referrer"),
+            (axum::http::header::CACHE_CONTROL, "no-store"),
+        ],
+        crate::share::render_session(session),
+    )
+        .into_response()
+}
+
+// ── Agents ─────────────────────────────────────────────────────────────
+
+pub fn agent_routes() -> Router<AppState> {
+    Router::new()
+        .route("/v1/agents", get(list_agents).post(create_agent))
+        .route("/v1/agents/{name}", axum::routing::delete(delete_agent))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct AgentInfo {
+    name: String,
+    source: AgentSource,
+    #[serde(flatten)]
+    preset: AgentPreset,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct CreateAgentRequest {
+    name: String,
+    #[serde(flatten)]
+    preset: AgentPreset,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/agents",
+    tag = "agents",
+    responses((status = 200, description = "Agent presets from the config file and the API", body = Vec<AgentInfo>))
+)]
+async fn list_agents(State(state): State<AppState>) -> impl IntoResponse {
+    let config = state.config.read().await;
+    let agents: Vec<AgentInfo> = state
+        .agents
+        .read()
+        .await
+        .list(&config)
+        .into_iter()
+        .map(|(name, preset, source)| AgentInfo {
+            name,
+            source,
+            preset,
+        })
+        .collect();
+    Json(agents)
+}
+
+/// Create or replace an API-defined preset. Config presets are read-only.
+#[utoipa::path(
+    post,
+    path = "/v1/agents",
+    tag = "agents",
+    request_body = CreateAgentRequest,
+    responses((status = 201, description = "The stored preset", body = AgentInfo))
+)]
+async fn create_agent(
+    State(state): State<AppState>,
+    Json(req): Json<CreateAgentRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    agents::validate_agent_name(&req.name).map_err(ApiError::bad_request)?;
+    req.preset
+        .validate()
+        .map_err(ApiError::bad_request)?;
+    if let Some(tools) = &req.preset.tools {
+        if let Some(unknown) = tools.iter().find(|t| state.tool_registry.get(t).is_none()) {
+            return Err(ApiError::bad_request(format!("Unknown tool: {}", unknown)));
+        }
+    }
+    if let Some(unknown) = req
+        .preset
+        .skills
+        .iter()
+        .find(|s| !state.skill_indexer.skill_exists(s))
+    {
+        return Err(ApiError::bad_request(format!("Unknown skill: {}", unknown)));
+    }
+
+    let config = state.config.read().await;
+    if config.agents.contains_key(&req.name) {
+        return Err(ApiError::new(ErrorCode::Conflict, format!("Agent '{}' is defined in the config file", req.name)));
+    }
+    state
+        .agents
+        .write()
+        .await
+        .insert(req.name.clone(), req.preset.clone())
+        .map_err(ApiError::internal)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(AgentInfo {
+            name: req.name,
+            source: AgentSource::Api,
+            preset: req.preset,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/agents/{name}",
+    tag = "agents",
+    params(("name" = String, Path, description = "Preset name")),
+    responses((status = 204, description = "The preset was deleted"))
+)]
+async fn delete_agent(
+    State(state): State<AppState>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if state.config.read().await.agents.contains_key(&name) {
+        return Err(ApiError::new(ErrorCode::Conflict, format!("Agent '{}' is defined in the config file", name)));
+    }
+    let removed = state
+        .agents
+        .write()
+        .await
+        .remove(&name)
+        .map_err(ApiError::internal)?;
+    if removed {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::new(ErrorCode::AgentNotFound, format!("Agent not found: {}", name)))
+    }
 }
 
 // ── Config ─────────────────────────────────────────────────────────────
@@ -253,16 +1282,17 @@ pub fn config_routes() -> Router<AppState> {
         .route("/v1/models", get(list_models))
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct ConfigResponse {
     provider: ProviderConfigResponse,
     server: ServerConfigResponse,
     session: SessionConfigResponse,
     sandbox: SandboxConfigResponse,
+    audio: AudioConfigResponse,
     tools: Vec<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct ProviderConfigResponse {
     api_base: String,
     model: String,
@@ -272,7 +1302,7 @@ struct ProviderConfigResponse {
     has_api_key: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct ServerConfigResponse {
     host: String,
     port: u16,
@@ -280,19 +1310,32 @@ struct ServerConfigResponse {
     has_auth_token: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct SessionConfigResponse {
     max_history: usize,
     auto_save: bool,
 }
 
-#[derive(Debug, Serialize)]
+/// Which voice features the UI should offer.
+#[derive(Debug, Serialize, ToSchema)]
+struct AudioConfigResponse {
+    transcription: bool,
+    speech: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 struct SandboxConfigResponse {
     mode: String,
     docker_image: String,
     timeout_secs: u64,
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/config",
+    tag = "config",
+    responses((status = 200, description = "Effective configuration, without secrets", body = ConfigResponse))
+)]
 async fn get_config(State(state): State<AppState>) -> impl IntoResponse {
     let c = state.config.read().await;
     let tools: Vec<String> = state
@@ -325,13 +1368,17 @@ async fn get_config(State(state): State<AppState>) -> impl IntoResponse {
             docker_image: c.sandbox.docker_image.clone(),
             timeout_secs: c.sandbox.timeout_secs,
         },
+        audio: AudioConfigResponse {
+            transcription: c.audio.transcription_url.is_some(),
+            speech: c.audio.speech_url.is_some(),
+        },
         tools,
     })
 }
 
 // ── Model Switching ───────────────────────────────────────────────────
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 struct UpdateProviderRequest {
     model: String,
     #[serde(default)]
@@ -342,10 +1389,17 @@ struct UpdateProviderRequest {
     max_tokens: Option<u32>,
 }
 
+#[utoipa::path(
+    put,
+    path = "/v1/config/provider",
+    tag = "config",
+    request_body = UpdateProviderRequest,
+    responses((status = 200, description = "The updated provider settings", body = Object))
+)]
 async fn update_provider(
     State(state): State<AppState>,
     Json(req): Json<UpdateProviderRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ApiError> {
     // Update config under write lock.
     {
         let mut config = state.config.write().await;
@@ -363,14 +1417,14 @@ async fn update_provider(
         // Save config to disk.
         config
             .save()
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save config: {}", e)))?;
+            .map_err(|e| ApiError::internal(format!("Failed to save config: {}", e)))?;
     }
 
     // Reconstruct AgentLoop with updated config.
     {
         let config = state.config.read().await;
         let new_loop = agent_core::agent_loop::AgentLoop::new(config.clone(), state.tool_registry.clone())
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create agent loop: {}", e)))?;
+            .map_err(|e| ApiError::internal(format!("Failed to create agent loop: {}", e)))?;
         let mut agent_loop = state.agent_loop.write().await;
         *agent_loop = new_loop;
     }
@@ -385,14 +1439,28 @@ async fn update_provider(
     })))
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct OllamaModel {
     name: String,
     size: Option<u64>,
     modified_at: Option<String>,
 }
 
-async fn list_models(State(state): State<AppState>) -> impl IntoResponse {
+#[derive(Debug, Serialize, ToSchema)]
+struct ModelsResponse {
+    models: Vec<OllamaModel>,
+    /// Why the list is empty, when the Ollama server could not be queried.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/models",
+    tag = "config",
+    responses((status = 200, description = "Models the Ollama server has pulled", body = ModelsResponse))
+)]
+async fn list_models(State(state): State<AppState>) -> Json<ModelsResponse> {
     let api_base = {
         let config = state.config.read().await;
         config.provider.api_base.clone()
@@ -436,34 +1504,131 @@ async fn list_models(State(state): State<AppState>) -> impl IntoResponse {
                             .collect()
                     })
                     .unwrap_or_default();
-                Json(serde_json::json!({ "models": models })).into_response()
+                Json(ModelsResponse {
+                    models,
+                    error: None,
+                })
             }
-            Err(e) => Json(serde_json::json!({
-                "models": [],
-                "error": format!("Failed to parse Ollama response: {}", e)
-            }))
-            .into_response(),
+            Err(e) => Json(ModelsResponse {
+                models: Vec::new(),
+                error: Some(format!("Failed to parse Ollama response: {}", e)),
+            }),
         },
-        Err(e) => Json(serde_json::json!({
-            "models": [],
-            "error": format!("Ollama not reachable: {}", e)
-        }))
-        .into_response(),
+        Err(e) => Json(ModelsResponse {
+            models: Vec::new(),
+            error: Some(format!("Ollama not reachable: {}", e)),
+        }),
     }
 }
 
 // ── Session Messages ──────────────────────────────────────────────────
 
 pub fn session_message_routes() -> Router<AppState> {
-    Router::new().route("/v1/sessions/{id}/messages", get(get_session_messages))
-}
-
-async fn get_session_messages(
+    Router::new()
+        .route("/v1/sessions/{id}/messages", get(get_session_messages))
+        .route(
+            "/v1/sessions/{id}/messages/{msg_id}",
+            axum::routing::delete(delete_session_message),
+        )
+        .route(
+            "/v1/sessions/{id}/messages/{msg_id}/feedback",
+            post(set_message_feedback),
+        )
+        .route(
+            "/v1/sessions/{id}/messages/{msg_id}/regenerate",
+            post(regenerate_from_message),
+        )
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct MessageResponse {
+    id: String,
+    /// `system`, `user`, `assistant` or `tool`.
+    role: String,
+    content: String,
+    tool_calls: Option<Vec<ToolCallResponse>>,
+    tool_call_id: Option<String>,
+    timestamp: String,
+    /// The user's rating, on assistant messages.
+    feedback: Option<agent_core::types::Feedback>,
+    /// Content or tool call arguments were shortened (`summary` mode).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    truncated: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ToolCallResponse {
+    id: String,
+    name: String,
+    /// JSON-encoded arguments.
+    arguments: String,
+}
+
+/// Characters of content kept per message in `summary` mode.
+const SUMMARY_CHARS: usize = 200;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct MessageListQuery {
+    /// Messages to return (1–500); all when unset.
+    limit: Option<usize>,
+    /// Messages to skip, after `after` when both are given.
+    #[serde(default)]
+    offset: usize,
+    /// Start after this message ID (the previous page's `x-next-cursor`).
+    after: Option<String>,
+    /// Only messages at or after this time (RFC 3339).
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only messages before this time (RFC 3339).
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only messages with this role: `system`, `user`, `assistant` or `tool`.
+    role: Option<String>,
+    /// Shorten content and tool call arguments to 200 characters.
+    #[serde(default)]
+    summary: bool,
+}
+
+/// `text` cut to `max` characters, and whether it was cut.
+fn shorten(text: &str, max: usize) -> (String, bool) {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => (format!("{}…", &text[..end]), true),
+        None => (text.to_string(), false),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/sessions/{id}/messages",
+    tag = "messages",
+    params(("id" = String, Path, description = "Session ID (UUID)"), MessageListQuery),
+    responses((
+        status = 200,
+        description = "The session's messages, oldest first",
+        body = Vec<MessageResponse>,
+        headers(
+            ("x-total-count" = usize, description = "Messages matching the filters"),
+            ("x-next-cursor" = String, description = "`after` for the next page, if any")
+        )
+    ))
+)]
+async fn get_session_messages(
     State(state): State<AppState>,
+    caller: Caller,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+    axum::extract::Query(query): axum::extract::Query<MessageListQuery>,
+) -> Result<impl IntoResponse, ApiError> {
     // Validate session ID is a UUID to prevent path traversal attacks.
     validate_session_id(&id)?;
+    let role = match query.role.as_deref() {
+        None => None,
+        Some(r @ ("system" | "user" | "assistant" | "tool")) => Some(r),
+        Some(other) => {
+            return Err(ApiError::bad_request(format!(
+                "Unknown role '{}' (expected system, user, assistant or tool)",
+                other
+            )))
+        }
+    };
 
     let sessions_dir = {
         let cfg = state.config.read().await;
@@ -475,45 +1640,232 @@ async fn get_session_messages(
 
     let path = sessions_dir.join(format!("{}.json", id));
     let session = agent_core::session::Session::load_from(&path)
-        .map_err(|e| (StatusCode::NOT_FOUND, format!("Session not found: {}", e)))?;
-
-    #[derive(Serialize)]
-    struct MessageResponse {
-        id: String,
-        role: String,
-        content: String,
-        tool_calls: Option<Vec<ToolCallResponse>>,
-        tool_call_id: Option<String>,
-        timestamp: String,
-    }
-
-    #[derive(Serialize)]
-    struct ToolCallResponse {
-        id: String,
-        name: String,
-    }
+        .ok()
+        .filter(|s| caller.can_access(s))
+        .ok_or_else(|| ApiError::session_not_found(&id))?;
 
+    let max = if query.summary { SUMMARY_CHARS } else { usize::MAX };
     let messages: Vec<MessageResponse> = session
         .messages
         .iter()
-        .map(|m| MessageResponse {
-            id: m.id.clone(),
-            role: format!("{:?}", m.role).to_lowercase(),
-            content: m.content.clone(),
-            tool_calls: m.tool_calls.as_ref().map(|tcs| {
+        .filter(|m| query.since.is_none_or(|t| m.timestamp >= t))
+        .filter(|m| query.until.is_none_or(|t| m.timestamp < t))
+        .filter(|m| role.is_none_or(|r| format!("{:?}", m.role).eq_ignore_ascii_case(r)))
+        .map(|m| {
+            let (content, mut truncated) = shorten(&m.content, max);
+            let tool_calls = m.tool_calls.as_ref().map(|tcs| {
                 tcs.iter()
-                    .map(|tc| ToolCallResponse {
-                        id: tc.id.clone(),
-                        name: tc.name.clone(),
+                    .map(|tc| {
+                        let (arguments, cut) = shorten(&tc.arguments, max);
+                        truncated |= cut;
+                        ToolCallResponse {
+                            id: tc.id.clone(),
+                            name: tc.name.clone(),
+                            arguments,
+                        }
                     })
                     .collect()
-            }),
-            tool_call_id: m.tool_call_id.clone(),
-            timestamp: m.timestamp.to_rfc3339(),
+            });
+            MessageResponse {
+                id: m.id.clone(),
+                role: format!("{:?}", m.role).to_lowercase(),
+                content,
+                tool_calls,
+                tool_call_id: m.tool_call_id.clone(),
+                timestamp: m.timestamp.to_rfc3339(),
+                feedback: m.feedback.clone(),
+                truncated,
+            }
         })
         .collect();
 
-    Ok(Json(messages))
+    let page = Page {
+        limit: query.limit,
+        offset: query.offset,
+        after: query.after.as_deref(),
+    };
+    let (headers, messages) = page.apply(messages, |m| &m.id)?;
+    Ok((headers, Json(messages)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/sessions/{id}/messages/{msg_id}",
+    tag = "messages",
+    params(("id" = String, Path, description = "Session ID (UUID)"), ("msg_id" = String, Path, description = "Message ID")),
+    responses((status = 204, description = "The message was deleted"))
+)]
+async fn delete_session_message(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path((id, msg_id)): axum::extract::Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    validate_session_id(&id)?;
+
+    let mut sm = state.session_manager.write().await;
+    caller.authorize(&sm, &id)?;
+    let session = sm
+        .session_mut(&id)
+        .ok_or_else(|| ApiError::session_not_found(&id))?;
+    if !session.delete_message(&msg_id) {
+        return Err(ApiError::new(ErrorCode::MessageNotFound, format!("Message not found: {}", msg_id)));
+    }
+    sm.save_session_async(&id)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct FeedbackRequest {
+    /// `up` or `down`; `null` clears the rating.
+    rating: Option<agent_core::types::Rating>,
+    /// What was good or wrong about the answer.
+    #[serde(default)]
+    comment: Option<String>,
+}
+
+impl RequestSchema for FeedbackRequest {
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "rating": { "type": ["string", "null"], "enum": ["up", "down", null] },
+                "comment": { "type": ["string", "null"], "maxLength": 2000 }
+            },
+            "required": ["rating"],
+            "additionalProperties": false
+        })
+    }
+}
+
+/// Rate an assistant message thumbs up or down, for the quality figures in
+/// analytics.
+#[utoipa::path(
+    post,
+    path = "/v1/sessions/{id}/messages/{msg_id}/feedback",
+    tag = "messages",
+    params(("id" = String, Path, description = "Session ID (UUID)"), ("msg_id" = String, Path, description = "Message ID")),
+    request_body = FeedbackRequest,
+    responses((status = 204, description = "The rating was saved"))
+)]
+async fn set_message_feedback(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path((id, msg_id)): axum::extract::Path<(String, String)>,
+    ValidJson(req): ValidJson<FeedbackRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    validate_session_id(&id)?;
+
+    let mut sm = state.session_manager.write().await;
+    caller.authorize(&sm, &id)?;
+    let session = sm
+        .session_mut(&id)
+        .ok_or_else(|| ApiError::session_not_found(&id))?;
+    match session.messages.iter().find(|m| m.id == msg_id) {
+        None => {
+            return Err(ApiError::new(ErrorCode::MessageNotFound, format!("Message not found: {}", msg_id)))
+        }
+        Some(m) if m.role != agent_core::types::Role::Assistant => {
+            return Err(ApiError::bad_request("Only assistant messages can be rated"))
+        }
+        Some(_) => {}
+    }
+    let feedback = req.rating.map(|rating| agent_core::types::Feedback {
+        rating,
+        comment: req.comment.filter(|c| !c.trim().is_empty()),
+        timestamp: chrono::Utc::now(),
+    });
+    session.set_feedback(&msg_id, feedback);
+    sm.save_session_async(&id)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct RegenerateRequest {
+    /// Replacement text for the user message. Omit to re-send it unchanged.
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    stream: bool,
+}
+
+impl RequestSchema for RegenerateRequest {
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "content": { "type": ["string", "null"], "minLength": 1 },
+                "stream": { "type": "boolean" }
+            },
+            "additionalProperties": false
+        })
+    }
+}
+
+/// Truncate a session back to a user message and run the agent again,
+/// optionally with edited message text.
+#[utoipa::path(
+    post,
+    path = "/v1/sessions/{id}/messages/{msg_id}/regenerate",
+    tag = "messages",
+    params(("id" = String, Path, description = "Session ID (UUID)"), ("msg_id" = String, Path, description = "Message ID")),
+    request_body = RegenerateRequest,
+    responses((
+        status = 200,
+        description = "The new reply, as server-sent events when `stream` is set",
+        content((ChatResponse = "application/json"), (String = "text/event-stream"))
+    ))
+)]
+async fn regenerate_from_message(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path((id, msg_id)): axum::extract::Path<(String, String)>,
+    request_id: RequestId,
+    ValidJson(req): ValidJson<RegenerateRequest>,
+) -> Result<axum::response::Response, ApiError> {
+    validate_session_id(&id)?;
+
+    let lock = {
+        let mut sm = state.session_manager.write().await;
+        caller.authorize(&sm, &id)?;
+        if sm.session(&id).is_none() {
+            return Err(ApiError::session_not_found(&id));
+        }
+        let lock = lock_session(&state, &id)?;
+        // Truncated on the latest saved copy, and kept over later reloads.
+        let message = sm
+            .update_in(&id, |session| {
+                match session.messages.iter().find(|m| m.id == msg_id) {
+                    None => {
+                        return Err(ApiError::new(ErrorCode::MessageNotFound, format!("Message not found: {}", msg_id)))
+                    }
+                    Some(m) if m.role != agent_core::types::Role::User => {
+                        return Err(ApiError::bad_request("Only user messages can be regenerated"))
+                    }
+                    Some(_) => {}
+                }
+                let original = session
+                    .truncate_from(&msg_id)
+                    .expect("message existence checked above");
+                let content = req.content.unwrap_or(original.content);
+                session.auto_checkpoint();
+                Ok(Message::user(content).with_images(original.images))
+            })
+            .map_err(ApiError::from)?
+            .ok_or_else(|| ApiError::session_not_found(&id))??;
+
+        sm.switch_session(&id)
+            .map_err(|_| ApiError::session_not_found(&id))?;
+        sm.push_message_in_async(&id, message)
+            .await
+            .map_err(ApiError::from)?;
+        lock
+    };
+
+    run_agent_turn(state, req.stream, request_id, lock).await
 }
 
 // ── Plugins ────────────────────────────────────────────────────────────
@@ -522,21 +1874,34 @@ pub fn plugin_routes() -> Router<AppState> {
     Router::new()
         .route("/v1/plugins", get(list_plugins))
         .route("/v1/plugins/health", get(plugin_health))
+        .route("/v1/plugins/{key}/permissions", get(plugin_permissions))
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/plugins",
+    tag = "plugins",
+    responses((status = 200, description = "Loaded plugins", body = Vec<Object>))
+)]
 async fn list_plugins(State(state): State<AppState>) -> impl IntoResponse {
     let pr = state.plugin_registry.read().await;
     let plugins: Vec<PluginInfo> = pr.list();
     Json(plugins)
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct PluginHealthEntry {
     category: String,
     name: String,
     status: String,
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/plugins/health",
+    tag = "plugins",
+    responses((status = 200, description = "Health of every plugin", body = Vec<PluginHealthEntry>))
+)]
 async fn plugin_health(State(state): State<AppState>) -> impl IntoResponse {
     let pr = state.plugin_registry.read().await;
     let entries: Vec<PluginHealthEntry> = pr
@@ -555,6 +1920,704 @@ async fn plugin_health(State(state): State<AppState>) -> impl IntoResponse {
     Json(entries)
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+struct PluginPermissionsResponse {
+    key: String,
+    name: String,
+    #[schema(value_type = String)]
+    category: PluginCategory,
+    #[schema(value_type = Object)]
+    permissions: PluginPermissions,
+}
+
+/// `key` is `<category>:<name>` (e.g. `skill:jira-sync`) or a bare name
+/// when only one plugin has it.
+#[utoipa::path(
+    get,
+    path = "/v1/plugins/{key}/permissions",
+    tag = "plugins",
+    params(("key" = String, Path, description = "`<category>:<name>`, or a name only one plugin has")),
+    responses((status = 200, description = "What the plugin may access", body = PluginPermissionsResponse))
+)]
+async fn plugin_permissions(
+    State(state): State<AppState>,
+    axum::extract::Path(key): axum::extract::Path<String>,
+) -> Result<Json<PluginPermissionsResponse>, ApiError> {
+    let pr = state.plugin_registry.read().await;
+    let info = pr
+        .resolve_key(&key)
+        .and_then(|k| pr.get_info(&k))
+        .ok_or_else(|| ApiError::new(ErrorCode::PluginNotFound, format!("Plugin not found: {}", key)))?;
+    let category = serde_json::to_value(&info.category)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default();
+    Ok(Json(PluginPermissionsResponse {
+        key: format!("{}:{}", category, info.name),
+        name: info.name,
+        category: info.category,
+        permissions: info.permissions,
+    }))
+}
+
+// ── Tools ──────────────────────────────────────────────────────────────
+
+pub fn tool_routes() -> Router<AppState> {
+    Router::new().route("/v1/tools/{name}/run", post(run_tool))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct RunToolQuery {
+    /// Session the call runs in; defaults to the active session.
+    session_id: Option<String>,
+}
+
+/// Run a registered tool directly with the given JSON arguments.
+///
+/// Used by the UI to re-run a tool call from the chat history. The call
+/// bypasses the model but nothing else: it runs in the session's
+/// workspace, under its tool policy, hooks and quotas, and the files it
+/// changes are snapshotted like a turn's. The session is locked meanwhile.
+#[utoipa::path(
+    post,
+    path = "/v1/tools/{name}/run",
+    tag = "tools",
+    params(("name" = String, Path, description = "Tool name"), RunToolQuery),
+    request_body = Object,
+    responses((status = 200, description = "The tool's output", body = ToolOutput))
+)]
+async fn run_tool(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<RunToolQuery>,
+    Json(args): Json<serde_json::Value>,
+) -> Result<impl IntoResponse, ApiError> {
+    if state.tool_registry.get(&name).is_none() {
+        return Err(ApiError::new(ErrorCode::ToolNotFound, format!("Tool not found: {}", name)));
+    }
+    let session_id = session_or_active(&state, query.session_id).await?;
+    let lock = lock_session(&state, &session_id)?;
+    let sandbox = state.config.read().await.sandbox.clone();
+    let (allowlist, denylist, tool_calls, roots) = {
+        let sm = state.session_manager.read().await;
+        caller.authorize(&sm, &session_id)?;
+        let session = sm
+            .session(&session_id)
+            .ok_or_else(|| ApiError::session_not_found(&session_id))?;
+        (
+            session.tool_allowlist.clone(),
+            session.tool_denylist.clone(),
+            session.tool_calls.clone(),
+            workspace::roots_for(&sandbox, session),
+        )
+    };
+    let call = ToolCall {
+        id: format!("rerun-{}", uuid::Uuid::new_v4()),
+        name,
+        arguments: args.to_string(),
+    };
+    let result = {
+        let agent_loop = state.agent_loop.read().await;
+        let run = agent_loop.run_tool_call(call, allowlist.as_deref(), &denylist, &tool_calls);
+        workspace::scope(roots, run).await
+    };
+    {
+        let mut sm = state.session_manager.write().await;
+        if let Err(e) = sm.record_snapshots_in(&session_id, result.snapshots) {
+            tracing::warn!("Failed to record file snapshots: {}", e);
+        }
+        if let Err(e) = sm.record_tool_calls_in(&session_id, &result.tool_calls) {
+            tracing::warn!("Failed to record tool calls: {}", e);
+        }
+    }
+    drop(lock);
+    Ok(Json(result.output))
+}
+
+/// Listing and enabling tools. These run nothing, so unlike
+/// [`tool_routes`] they are served without an auth token too.
+pub fn tool_settings_routes() -> Router<AppState> {
+    Router::new()
+        .route("/v1/tools", get(list_tools))
+        .route("/v1/tools/{name}", axum::routing::patch(update_tool))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ToolInfo {
+    /// What the model calls the tool.
+    name: String,
+    /// Namespaced ID: `builtin.<name>` or `plugin.<plugin>.<name>`.
+    id: String,
+    description: String,
+    /// Offered to the model; disabled tools are neither offered nor run.
+    enabled: bool,
+}
+
+fn tool_info(registry: &agent_core::tool_registry::ToolRegistry, name: &str) -> Option<ToolInfo> {
+    let tool = registry.get(name)?;
+    Some(ToolInfo {
+        name: tool.name().to_string(),
+        id: registry.id(name)?.to_string(),
+        description: tool.description().to_string(),
+        enabled: registry.is_enabled(name),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/tools",
+    tag = "tools",
+    responses((status = 200, description = "Registered tools, by name", body = Vec<ToolInfo>))
+)]
+async fn list_tools(State(state): State<AppState>) -> Json<Vec<ToolInfo>> {
+    let registry = &state.tool_registry;
+    let mut tools: Vec<ToolInfo> = registry
+        .list_names()
+        .into_iter()
+        .filter_map(|name| tool_info(registry, name))
+        .collect();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    Json(tools)
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct UpdateToolRequest {
+    enabled: bool,
+}
+
+impl RequestSchema for UpdateToolRequest {
+    fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": { "enabled": { "type": "boolean" } },
+            "required": ["enabled"],
+            "additionalProperties": false
+        })
+    }
+}
+
+/// Enable or disable a tool for every session. Takes effect from the next
+/// model call and is saved to `[tools] disabled` in the config file.
+#[utoipa::path(
+    patch,
+    path = "/v1/tools/{name}",
+    tag = "tools",
+    params(("name" = String, Path, description = "Tool name, ID or alias")),
+    request_body = UpdateToolRequest,
+    responses((status = 200, description = "The tool", body = ToolInfo))
+)]
+async fn update_tool(
+    State(state): State<AppState>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    ValidJson(req): ValidJson<UpdateToolRequest>,
+) -> Result<Json<ToolInfo>, ApiError> {
+    let registry = &state.tool_registry;
+    let not_found = || ApiError::new(ErrorCode::ToolNotFound, format!("Tool not found: {}", name));
+    let id = registry.id(&name).ok_or_else(not_found)?.to_string();
+    {
+        let mut config = state.config.write().await;
+        let disabled = &mut config.tools.disabled;
+        disabled.retain(|entry| registry.id(entry) != Some(id.as_str()));
+        if !req.enabled {
+            disabled.push(id.clone());
+        }
+        config
+            .save()
+            .map_err(|e| ApiError::internal(format!("Failed to save config: {}", e)))?;
+    }
+    registry
+        .set_enabled(&id, req.enabled)
+        .map_err(|_| not_found())?;
+    tracing::info!(tool = %id, enabled = req.enabled, "Tool toggled");
+    tool_info(registry, &id).map(Json).ok_or_else(not_found)
+}
+
+// ── Preferences ────────────────────────────────────────────────────────
+
+pub fn preference_routes() -> Router<AppState> {
+    Router::new().route(
+        "/v1/preferences",
+        get(get_preferences).put(update_preferences),
+    )
+}
+
+async fn preferences_path(state: &AppState) -> std::path::PathBuf {
+    let cfg = state.config.read().await;
+    cfg.server
+        .preferences_path
+        .clone()
+        .unwrap_or_else(|| agent_core::config::AppConfig::data_dir().join("ui-preferences.json"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/preferences",
+    tag = "preferences",
+    responses((status = 200, description = "Stored UI preferences", body = UiPreferences))
+)]
+async fn get_preferences(State(state): State<AppState>) -> impl IntoResponse {
+    let path = preferences_path(&state).await;
+    Json(UiPreferences::load(&path).await)
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/preferences",
+    tag = "preferences",
+    request_body = UiPreferences,
+    responses((status = 200, description = "The saved preferences", body = UiPreferences))
+)]
+async fn update_preferences(
+    State(state): State<AppState>,
+    Json(prefs): Json<UiPreferences>,
+) -> Result<impl IntoResponse, ApiError> {
+    prefs.validate().map_err(ApiError::bad_request)?;
+    let path = preferences_path(&state).await;
+    prefs
+        .save(&path)
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(Json(prefs))
+}
+
+// ── Files ──────────────────────────────────────────────────────────────
+
+/// Upload directory, relative to the workspace root.
+const UPLOAD_DIR: &str = ".agent-shell/uploads";
+
+pub fn file_routes() -> Router<AppState> {
+    Router::new().route("/v1/files", post(upload_files))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct UploadQuery {
+    /// Session the files belong to; defaults to the active session.
+    session_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct UploadedFile {
+    name: String,
+    path: String,
+    size: usize,
+}
+
+/// Store multipart `file` fields under a per-session directory inside the
+/// session's primary workspace, returning where each one landed.
+/// `[sandbox.paths]` rules apply as they do to `file_write`.
+#[utoipa::path(
+    post,
+    path = "/v1/files",
+    tag = "files",
+    params(UploadQuery),
+    request_body(content_type = "multipart/form-data", description = "One or more `file` fields"),
+    responses((status = 200, description = "Where each file was stored", body = Vec<UploadedFile>))
+)]
+async fn upload_files(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Query(query): axum::extract::Query<UploadQuery>,
+    mut multipart: axum::extract::Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    let session_id = session_or_active(&state, query.session_id).await?;
+    let sandbox = state.config.read().await.sandbox.clone();
+    let root = {
+        let sm = state.session_manager.read().await;
+        caller.authorize(&sm, &session_id)?;
+        match sm.session(&session_id) {
+            Some(session) => workspace::roots_for(&sandbox, session).into_iter().next(),
+            None => sandbox.workspace_root.clone(),
+        }
+    };
+    let root = match root {
+        Some(r) => r,
+        None => std::env::current_dir()
+            .map_err(ApiError::internal)?,
+    };
+    let rules = PathRules::new(&sandbox.paths);
+    let dir = root.join(UPLOAD_DIR).join(&session_id);
+
+    let mut uploaded = Vec::new();
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::from_status(e.status(), e.body_text()))?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let name = field
+            .file_name()
+            .and_then(sanitize_upload_name)
+            .ok_or_else(|| ApiError::bad_request("Invalid file name"))?;
+        let data = field
+            .bytes()
+            .await
+            .map_err(|e| ApiError::from_status(e.status(), e.body_text()))?;
+
+        let path = unique_upload_path(&dir, &name);
+        let path = agent_tools::file_ops::validate_write_path(
+            &path.to_string_lossy(),
+            &Some(root.clone()),
+            &rules,
+        )
+        .map_err(forbidden_path)?;
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(ApiError::internal)?;
+        tokio::fs::write(&path, &data)
+            .await
+            .map_err(ApiError::internal)?;
+
+        uploaded.push(UploadedFile {
+            name,
+            path: path.display().to_string(),
+            size: data.len(),
+        });
+    }
+
+    if uploaded.is_empty() {
+        return Err(ApiError::bad_request("No file field in upload"));
+    }
+    Ok(Json(uploaded))
+}
+
+/// Reduce a client-supplied file name to a safe single path component.
+fn sanitize_upload_name(raw: &str) -> Option<String> {
+    let base = raw.rsplit(['/', '\\']).next().unwrap_or("");
+    let name: String = base
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = name.trim_start_matches('.').to_string();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// Pick `name` in `dir`, or `stem-N.ext` if that is already taken.
+fn unique_upload_path(dir: &std::path::Path, name: &str) -> std::path::PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{}-{}{}", stem, n, ext)))
+        .find(|p| !p.exists())
+        .expect("unbounded range always yields a free name")
+}
+
+// ── Audio ──────────────────────────────────────────────────────────────
+
+/// OpenAI's speech endpoint rejects longer input.
+const MAX_SPEECH_CHARS: usize = 4096;
+
+pub fn audio_routes() -> Router<AppState> {
+    Router::new()
+        .route("/v1/audio/transcriptions", post(transcribe_audio))
+        .route("/v1/audio/speech", post(synthesize_speech))
+}
+
+fn audio_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(120))
+        .build()
+        .unwrap_or_default()
+}
+
+fn audio_endpoint(base: &str, path: &str) -> String {
+    format!("{}/{}", base.trim_end_matches('/'), path)
+}
+
+/// Forward a recording (multipart `file`, optional `language`) to the
+/// configured Whisper-compatible server and return `{ "text": ... }`.
+#[utoipa::path(
+    post,
+    path = "/v1/audio/transcriptions",
+    tag = "audio",
+    request_body(
+        content_type = "multipart/form-data",
+        description = "A `file` recording and an optional `language`"
+    ),
+    responses((
+        status = 200,
+        description = "The transcript",
+        body = Object,
+        example = json!({ "text": "run the tests" })
+    ))
+)]
+async fn transcribe_audio(
+    State(state): State<AppState>,
+    mut multipart: axum::extract::Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    let audio = state.config.read().await.audio.clone();
+    let base = audio.transcription_url.as_deref().ok_or_else(|| ApiError::new(ErrorCode::Unavailable, "Transcription is not configured. Set [audio] transcription_url in config."))?;
+
+    let mut file = None;
+    let mut language = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::from_status(e.status(), e.body_text()))?
+    {
+        match field.name() {
+            Some("file") => {
+                let name = field.file_name().unwrap_or("recording.webm").to_string();
+                let mime = field
+                    .content_type()
+                    .unwrap_or("application/octet-stream")
+                    .to_string();
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|e| ApiError::from_status(e.status(), e.body_text()))?;
+                file = Some((name, mime, data));
+            }
+            Some("language") => {
+                language = Some(
+                    field
+                        .text()
+                        .await
+                        .map_err(|e| ApiError::from_status(e.status(), e.body_text()))?,
+                );
+            }
+            _ => {}
+        }
+    }
+    let (name, mime, data) =
+        file.ok_or_else(|| ApiError::bad_request("No file field in upload"))?;
+
+    let part = reqwest::multipart::Part::bytes(data.to_vec())
+        .file_name(name)
+        .mime_str(&mime)
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let mut form = reqwest::multipart::Form::new()
+        .text("model", audio.transcription_model.clone())
+        .part("file", part);
+    if let Some(language) = language.filter(|l| !l.is_empty()) {
+        form = form.text("language", language);
+    }
+
+    let mut request = audio_client()
+        .post(audio_endpoint(base, "audio/transcriptions"))
+        .multipart(form);
+    if let Some(key) = &audio.api_key {
+        request = request.bearer_auth(key);
+    }
+    let resp = request.send().await.map_err(|e| {
+        ApiError::upstream(format!("Transcription server unreachable: {}", e))
+    })?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(ApiError::upstream(format!("Transcription failed ({}): {}", status, body)));
+    }
+    let body: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| ApiError::upstream(format!("Invalid transcription response: {}", e)))?;
+    let text = body
+        .get("text")
+        .and_then(|t| t.as_str())
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    Ok(Json(serde_json::json!({ "text": text })))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct SpeechRequest {
+    input: String,
+    /// Overrides `[audio] voice`.
+    voice: Option<String>,
+}
+
+/// Synthesize `input` with the configured TTS server and stream the audio back.
+#[utoipa::path(
+    post,
+    path = "/v1/audio/speech",
+    tag = "audio",
+    request_body = SpeechRequest,
+    responses((status = 200, description = "Synthesized audio", content(("audio/mpeg"))))
+)]
+async fn synthesize_speech(
+    State(state): State<AppState>,
+    Json(req): Json<SpeechRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let audio = state.config.read().await.audio.clone();
+    let base = audio.speech_url.as_deref().ok_or_else(|| ApiError::new(ErrorCode::Unavailable, "Speech is not configured. Set [audio] speech_url in config."))?;
+    let input = req.input.trim();
+    if input.is_empty() {
+        return Err(ApiError::bad_request("input is required"));
+    }
+    if input.chars().count() > MAX_SPEECH_CHARS {
+        return Err(ApiError::bad_request(format!("input exceeds {} characters", MAX_SPEECH_CHARS)));
+    }
+
+    let mut request = audio_client()
+        .post(audio_endpoint(base, "audio/speech"))
+        .json(&serde_json::json!({
+            "model": audio.speech_model,
+            "input": input,
+            "voice": req.voice.unwrap_or(audio.voice),
+            "response_format": "mp3",
+        }));
+    if let Some(key) = &audio.api_key {
+        request = request.bearer_auth(key);
+    }
+    let resp = request.send().await.map_err(|e| {
+        ApiError::upstream(format!("Speech server unreachable: {}", e))
+    })?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(ApiError::upstream(format!("Speech synthesis failed ({}): {}", status, body)));
+    }
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("audio/mpeg")
+        .to_string();
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, content_type)],
+        axum::body::Body::from_stream(resp.bytes_stream()),
+    ))
+}
+
+// ── Editor ─────────────────────────────────────────────────────────────
+
+pub fn editor_routes() -> Router<AppState> {
+    Router::new().route("/v1/editor/actions", post(editor_action))
+}
+
+/// Ask the model for an edit to a file and return it as search/replace edits
+/// plus a unified diff. Nothing is written; the editor applies the result.
+#[utoipa::path(
+    post,
+    path = "/v1/editor/actions",
+    tag = "editor",
+    request_body = EditorActionRequest,
+    responses((status = 200, description = "Proposed edits and their diff", body = EditorActionResponse))
+)]
+async fn editor_action(
+    State(state): State<AppState>,
+    caller: Caller,
+    Json(req): Json<EditorActionRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if req.instruction.trim().is_empty() {
+        return Err(ApiError::bad_request("instruction is required"));
+    }
+    let session_id = session_or_active(&state, req.session_id.clone()).await?;
+    let sandbox = state.config.read().await.sandbox.clone();
+    let roots = {
+        let sm = state.session_manager.read().await;
+        caller.authorize(&sm, &session_id)?;
+        session_roots(&sandbox, &sm, &session_id)
+    };
+    let (path, display) = resolve_workspace_path(&req.path, roots, &sandbox).await?;
+
+    let content = match req.content {
+        Some(c) => c,
+        None => tokio::fs::read_to_string(&path).await.map_err(|e| {
+            ApiError::not_found(format!("Failed to read {}: {}", display, e))
+        })?,
+    };
+    if let Some(sel) = req.selection {
+        sel.validate(content.lines().count())
+            .map_err(ApiError::bad_request)?;
+    }
+
+    let messages = editor::build_messages(&display, &content, req.selection, &req.instruction);
+    let (tx, _rx) = tokio::sync::mpsc::unbounded_channel::<AgentEvent>();
+    let result = {
+        let agent_loop = state.agent_loop.read().await;
+        // No tools: the model only proposes edits.
+        agent_loop
+            .run(&messages, Some(&[]), &[], &Default::default(), tx)
+            .await
+            .map_err(ApiError::from)?
+    };
+
+    let reply = editor::parse_reply(&result.final_message().content)
+        .map_err(ApiError::upstream)?;
+    let updated = editor::apply_edits(&content, &reply.edits)
+        .map_err(|e| ApiError::new(ErrorCode::ValidationFailed, e))?;
+
+    Ok(Json(EditorActionResponse {
+        diff: editor::unified_diff(&display, &content, &updated),
+        path: display,
+        explanation: reply.explanation,
+        edits: reply.edits,
+    }))
+}
+
+/// The workspace roots of session `id`, or `[sandbox] workspace_root` if
+/// there is no such session.
+fn session_roots(
+    sandbox: &agent_core::config::SandboxConfig,
+    sm: &agent_core::session::SessionManager,
+    id: &str,
+) -> Vec<std::path::PathBuf> {
+    match sm.session(id) {
+        Some(session) => workspace::roots_for(sandbox, session),
+        None => sandbox.workspace_root.iter().cloned().collect(),
+    }
+}
+
+/// Resolve a path to read as tools would inside the workspace `roots`:
+/// relative paths start from the first, the file must live inside one of
+/// them, and `[sandbox.paths]` must not deny it. Without roots the current
+/// directory is the only one. The display path is relative to its root.
+async fn resolve_workspace_path(
+    raw: &str,
+    roots: Vec<std::path::PathBuf>,
+    sandbox: &agent_core::config::SandboxConfig,
+) -> Result<(std::path::PathBuf, String), ApiError> {
+    let roots = if roots.is_empty() {
+        vec![std::env::current_dir().map_err(ApiError::internal)?]
+    } else {
+        roots
+    };
+    let rules = PathRules::new(&sandbox.paths);
+    let canonical = workspace::scope(roots.clone(), async {
+        agent_tools::file_ops::validate_path(raw, &None, &rules)
+    })
+    .await
+    .map_err(forbidden_path)?;
+    let display = roots
+        .iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .find_map(|root| Some(canonical.strip_prefix(root).ok()?.display().to_string()))
+        .unwrap_or_else(|| canonical.display().to_string());
+    Ok((canonical, display))
+}
+
+/// A path check's refusal as 403, other failures as they are.
+fn forbidden_path(e: agent_core::AgentError) -> ApiError {
+    match e {
+        agent_core::AgentError::ToolExecution { message, .. } => {
+            ApiError::new(ErrorCode::Forbidden, message)
+        }
+        e => ApiError::from(e),
+    }
+}
+
 // ── Skills ─────────────────────────────────────────────────────────────
 
 pub fn skill_routes() -> Router<AppState> {
@@ -564,7 +2627,7 @@ pub fn skill_routes() -> Router<AppState> {
         .route("/v1/skills/{name}", get(get_skill))
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct SkillInfo {
     name: String,
     description: String,
@@ -573,6 +2636,12 @@ struct SkillInfo {
     source: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/skills",
+    tag = "skills",
+    responses((status = 200, description = "Indexed skills", body = Vec<SkillInfo>))
+)]
 async fn list_skills(State(state): State<AppState>) -> impl IntoResponse {
     let index = state.skill_indexer.get_skill_index();
     let skills: Vec<SkillInfo> = index
@@ -589,7 +2658,8 @@ async fn list_skills(State(state): State<AppState>) -> impl IntoResponse {
     Json(skills)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct SearchQuery {
     q: String,
     #[serde(default = "default_limit")]
@@ -600,6 +2670,13 @@ fn default_limit() -> usize {
     10
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/skills/search",
+    tag = "skills",
+    params(SearchQuery),
+    responses((status = 200, description = "Matching skills and sub-skills", body = Object))
+)]
 async fn search_skills(
     State(state): State<AppState>,
     axum::extract::Query(query): axum::extract::Query<SearchQuery>,
@@ -610,14 +2687,21 @@ async fn search_skills(
     Json(results)
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/skills/{name}",
+    tag = "skills",
+    params(("name" = String, Path, description = "Skill name")),
+    responses((status = 200, description = "The skill's documents", body = Object))
+)]
 async fn get_skill(
     State(state): State<AppState>,
     axum::extract::Path(name): axum::extract::Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ApiError> {
     let content = state
         .skill_indexer
         .read_skill_content(&name)
-        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+        .map_err(|e| ApiError::not_found(e.to_string()))?;
     Ok(Json(content))
 }
 
@@ -626,10 +2710,15 @@ async fn get_skill(
 pub fn terminal_session_routes() -> Router<AppState> {
     Router::new()
         .route("/v1/terminal-sessions", get(list_terminal_sessions))
+        .route("/v1/terminal-sessions/failures", get(list_terminal_failures))
         .route("/v1/terminal-sessions/{id}", get(get_terminal_session))
+        .route(
+            "/v1/terminal-sessions/{id}/commands/{command_id}/ask",
+            post(ask_about_failure),
+        )
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct TerminalSessionInfo {
     id: String,
     shell: String,
@@ -642,6 +2731,12 @@ struct TerminalSessionInfo {
     command_count: usize,
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/terminal-sessions",
+    tag = "terminal-sessions",
+    responses((status = 200, description = "Shell sessions reported by the shell hooks", body = Vec<TerminalSessionInfo>))
+)]
 async fn list_terminal_sessions(State(state): State<AppState>) -> impl IntoResponse {
     let tsm = state.terminal_sessions.read().await;
     let sessions: Vec<TerminalSessionInfo> = tsm
@@ -665,13 +2760,13 @@ async fn list_terminal_sessions(State(state): State<AppState>) -> impl IntoRespo
     Json(sessions)
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct TerminalSessionDetail {
     session: TerminalSessionInfo,
     commands: Vec<TerminalCommandInfo>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct TerminalCommandInfo {
     id: String,
     sequence: u32,
@@ -683,19 +2778,26 @@ struct TerminalCommandInfo {
     duration_ms: Option<u64>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/terminal-sessions/{id}",
+    tag = "terminal-sessions",
+    params(("id" = String, Path, description = "Terminal session ID (UUID)")),
+    responses((status = 200, description = "The session and its commands", body = TerminalSessionDetail))
+)]
 async fn get_terminal_session(
     State(state): State<AppState>,
     axum::extract::Path(id): axum::extract::Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ApiError> {
     validate_session_id(&id)?;
 
     let uuid = uuid::Uuid::parse_str(&id)
-        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid UUID format".to_string()))?;
+        .map_err(|_| ApiError::bad_request("Invalid UUID format"))?;
 
     let tsm = state.terminal_sessions.read().await;
     let session = tsm
         .get_session(&uuid)
-        .ok_or((StatusCode::NOT_FOUND, "Terminal session not found".to_string()))?;
+        .ok_or_else(|| ApiError::not_found("Terminal session not found"))?;
 
     let cmd_count = tsm.get_commands(&uuid).map(|c| c.len()).unwrap_or(0);
     let info = TerminalSessionInfo {
@@ -726,10 +2828,139 @@ async fn get_terminal_session(
         })
         .collect();
 
-    Ok(Json(TerminalSessionDetail {
-        session: info,
-        commands,
-    }))
+    Ok(Json(TerminalSessionDetail {
+        session: info,
+        commands,
+    }))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FailuresQuery {
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct FailedCommandInfo {
+    id: String,
+    session_id: String,
+    command_text: String,
+    working_directory: String,
+    exit_code: Option<i32>,
+    ended_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct FailuresResponse {
+    /// Whether error assistance is enabled (`[error_assist] enabled`).
+    enabled: bool,
+    failures: Vec<FailedCommandInfo>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/terminal-sessions/failures",
+    tag = "terminal-sessions",
+    params(FailuresQuery),
+    responses((status = 200, description = "Recent failed commands", body = FailuresResponse))
+)]
+async fn list_terminal_failures(
+    State(state): State<AppState>,
+    axum::extract::Query(query): axum::extract::Query<FailuresQuery>,
+) -> impl IntoResponse {
+    let enabled = state.config.read().await.error_assist.enabled;
+    if !enabled {
+        return Json(FailuresResponse {
+            enabled,
+            failures: Vec::new(),
+        });
+    }
+
+    let scrubber = agent_core::SecretScrubber::new();
+    let tsm = state.terminal_sessions.read().await;
+    let failures = tsm
+        .recent_failures(query.limit)
+        .into_iter()
+        .map(|c| FailedCommandInfo {
+            id: c.id.to_string(),
+            session_id: c.session_id.to_string(),
+            command_text: scrubber.scrub_command(&c.command_text),
+            working_directory: c.working_directory.display().to_string(),
+            exit_code: c.exit_code,
+            ended_at: c.ended_at.map(|t| t.to_rfc3339()),
+        })
+        .collect();
+
+    Json(FailuresResponse { enabled, failures })
+}
+
+/// Create a new conversation session seeded with a failed command's context.
+///
+/// The returned `prompt` is the first user message for the session; the
+/// client sends it through `/v1/chat/completions` with the new `session_id`.
+#[utoipa::path(
+    post,
+    path = "/v1/terminal-sessions/{id}/commands/{command_id}/ask",
+    tag = "terminal-sessions",
+    params(
+        ("id" = String, Path, description = "Terminal session ID (UUID)"),
+        ("command_id" = String, Path, description = "Command ID (UUID)")
+    ),
+    responses((status = 200, description = "A new session and the prompt to send in it", body = Object, example = json!({ "id": "…", "name": "Error: cargo test", "prompt": "…" })))
+)]
+async fn ask_about_failure(
+    State(state): State<AppState>,
+    axum::extract::Path((id, command_id)): axum::extract::Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let assist_config = state.config.read().await.error_assist.clone();
+    if !assist_config.enabled {
+        return Err(ApiError::new(ErrorCode::Forbidden, "Error assistance is disabled. Set [error_assist] enabled = true in config."));
+    }
+
+    validate_session_id(&id)?;
+    validate_session_id(&command_id)?;
+    let session_uuid = uuid::Uuid::parse_str(&id)
+        .map_err(|_| ApiError::bad_request("Invalid UUID format"))?;
+    let command_uuid = uuid::Uuid::parse_str(&command_id)
+        .map_err(|_| ApiError::bad_request("Invalid UUID format"))?;
+
+    let context = {
+        let tsm = state.terminal_sessions.read().await;
+        agent_core::error_assist::FailedCommandContext::collect(
+            &tsm,
+            &session_uuid,
+            &command_uuid,
+            &assist_config,
+        )
+        .ok_or_else(|| ApiError::not_found("Failed command not found"))?
+    };
+
+    let mut sm = state.session_manager.write().await;
+    let session_id = sm
+        .create_session(context.session_name())
+        .map_err(ApiError::from)?
+        .id
+        .clone();
+    if let Some(session) = sm.active_session_mut() {
+        session.working_directory = Some(context.working_directory.clone());
+        session.add_tag("error-assist");
+        session
+            .metadata
+            .insert("terminal_session_id".into(), id.clone());
+        session
+            .metadata
+            .insert("terminal_command_id".into(), command_id.clone());
+    }
+    sm.save_active_async()
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(serde_json::json!({
+        "id": session_id,
+        "name": context.session_name(),
+        "prompt": context.to_prompt(),
+    })))
 }
 
 // ── Terminal ───────────────────────────────────────────────────────────
@@ -740,6 +2971,12 @@ pub fn terminal_routes() -> Router<AppState> {
         .route("/v1/terminal", get(terminal_ws))
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/terminal/shells",
+    tag = "terminal",
+    responses((status = 200, description = "Shells available on the server", body = Vec<Object>))
+)]
 async fn list_shells() -> impl IntoResponse {
     let shells: Vec<ShellInfo> = agent_pty::detect_available_shells();
     Json(shells)
@@ -755,20 +2992,58 @@ enum TerminalInput {
     Resize { cols: u16, rows: u16 },
 }
 
-async fn terminal_ws(ws: axum::extract::WebSocketUpgrade) -> impl IntoResponse {
-    ws.on_upgrade(handle_terminal_socket)
+/// Query parameters for opening a terminal.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct TerminalQuery {
+    /// Shell id from `/v1/terminal/shells`; defaults to the platform shell.
+    shell: Option<String>,
+    #[serde(default = "default_terminal_cols")]
+    cols: u16,
+    #[serde(default = "default_terminal_rows")]
+    rows: u16,
+}
+
+fn default_terminal_cols() -> u16 {
+    80
+}
+
+fn default_terminal_rows() -> u16 {
+    24
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/terminal",
+    tag = "terminal",
+    params(TerminalQuery),
+    responses((status = 101, description = "WebSocket attached to a new PTY"))
+)]
+async fn terminal_ws(
+    ws: axum::extract::WebSocketUpgrade,
+    axum::extract::Query(query): axum::extract::Query<TerminalQuery>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_terminal_socket(socket, query))
 }
 
-async fn handle_terminal_socket(mut socket: axum::extract::ws::WebSocket) {
+async fn handle_terminal_socket(mut socket: axum::extract::ws::WebSocket, query: TerminalQuery) {
     use axum::extract::ws::Message as WsMessage;
     use base64::Engine as _;
 
-    let shell = match agent_pty::default_shell() {
+    let shell = match &query.shell {
+        Some(id) => agent_pty::find_shell(id),
+        None => agent_pty::default_shell(),
+    };
+    let shell = match shell {
         Some(s) => s,
         None => {
+            let message = match &query.shell {
+                Some(id) => format!("Unknown shell: {id}"),
+                None => "No shell found".to_string(),
+            };
             let _ = socket
                 .send(WsMessage::Text(
-                    serde_json::json!({"type": "error", "message": "No shell found"})
+                    serde_json::json!({"type": "error", "message": message})
                         .to_string()
                         .into(),
                 ))
@@ -777,7 +3052,9 @@ async fn handle_terminal_socket(mut socket: axum::extract::ws::WebSocket) {
         }
     };
 
-    let session = match agent_pty::PtySession::new(&shell, 80, 24) {
+    let cols = query.cols.max(1);
+    let rows = query.rows.max(1);
+    let session = match agent_pty::PtySession::new(&shell, cols, rows) {
         Ok(s) => s,
         Err(e) => {
             let _ = socket
@@ -797,8 +3074,9 @@ async fn handle_terminal_socket(mut socket: axum::extract::ws::WebSocket) {
             serde_json::json!({
                 "type": "session_start",
                 "shell": shell.id,
-                "cols": 80,
-                "rows": 24,
+                "name": shell.name,
+                "cols": cols,
+                "rows": rows,
             })
             .to_string()
             .into(),
@@ -880,14 +3158,26 @@ pub fn context_routes() -> Router<AppState> {
     Router::new().route("/v1/context", get(get_context))
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct ContextResponse {
     project: Option<ProjectInfo>,
     git: Option<GitInfo>,
     environments: Vec<EnvInfo>,
+    /// Configured workspaces, marked as the session selects them.
+    workspaces: Vec<WorkspaceInfo>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct WorkspaceInfo {
+    name: String,
+    path: String,
+    /// Whether the session's tools may use it.
+    active: bool,
+    /// Whether relative paths and commands start here.
+    primary: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct ProjectInfo {
     name: String,
     project_type: String,
@@ -896,7 +3186,7 @@ struct ProjectInfo {
     git_branch: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct GitInfo {
     branch: Option<String>,
     remote: Option<String>,
@@ -905,7 +3195,7 @@ struct GitInfo {
     repo_root: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct EnvInfo {
     name: String,
     env_type: String,
@@ -913,12 +3203,49 @@ struct EnvInfo {
     path: String,
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/context",
+    tag = "context",
+    params(ContextQuery),
+    responses((status = 200, description = "Project, git and toolchain context", body = ContextResponse))
+)]
 async fn get_context(
+    State(state): State<AppState>,
+    caller: Caller,
     axum::extract::Query(params): axum::extract::Query<ContextQuery>,
-) -> impl IntoResponse {
+) -> Result<Json<ContextResponse>, ApiError> {
+    let sandbox = state.config.read().await.sandbox.clone();
+    let active = {
+        let sm = state.session_manager.read().await;
+        let session = match &params.session_id {
+            Some(id) => {
+                validate_session_id(id)?;
+                caller.authorize(&sm, id)?;
+                sm.session(id)
+            }
+            None => sm.active_session().filter(|s| caller.can_access(s)),
+        };
+        let selected = session.map(|s| s.workspaces.as_slice()).unwrap_or_default();
+        workspace::active(&sandbox, selected)
+    };
+    let workspaces = workspace::configured(&sandbox)
+        .into_iter()
+        .map(|ws| {
+            let position = active.iter().position(|a| a.name == ws.name);
+            WorkspaceInfo {
+                path: ws.root.display().to_string(),
+                name: ws.name,
+                active: position.is_some(),
+                primary: position == Some(0),
+            }
+        })
+        .collect();
+
     let dir = params
         .directory
         .map(std::path::PathBuf::from)
+        .or_else(|| active.first().map(|ws| ws.root.clone()))
         .unwrap_or_else(|| {
             std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."))
         });
@@ -950,52 +3277,183 @@ async fn get_context(
         })
         .collect();
 
-    Json(ContextResponse {
+    Ok(Json(ContextResponse {
         project,
         git,
         environments,
-    })
+        workspaces,
+    }))
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct ContextQuery {
+    /// Directory to inspect; defaults to the session's primary workspace.
     directory: Option<String>,
+    /// Session whose workspaces to report; defaults to the active session.
+    session_id: Option<String>,
 }
 
-// ── Analytics ──────────────────────────────────────────────────────────
+// ── Schedules ──────────────────────────────────────────────────────────
 
-pub fn analytics_routes() -> Router<AppState> {
+pub fn schedule_routes() -> Router<AppState> {
     Router::new()
-        .route("/v1/analytics/summary", get(analytics_summary))
-        .route("/v1/analytics/report", get(analytics_report))
+        .route("/v1/schedules", get(list_schedules))
+        .route("/v1/schedules/validate", post(validate_schedule))
+        .route("/v1/schedules/{name}/history", get(schedule_history))
 }
 
-#[derive(Debug, Serialize)]
-struct AnalyticsSummaryResponse {
-    total_sessions: usize,
-    active_days: usize,
-    average_session_duration_secs: Option<u64>,
-    top_tools: Vec<(String, u32)>,
-    deep_work_sessions: usize,
-    today: Option<DaySummaryResponse>,
+#[derive(Debug, Serialize, ToSchema)]
+struct ScheduleInfo {
+    #[serde(flatten)]
+    config: agent_core::config::ScheduleConfig,
+    /// Cron parse error; invalid schedules never fire.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cron_error: Option<String>,
+    /// Persisted scheduler state; `null` until the schedule first fires.
+    state: Option<agent_core::scheduler::ScheduleState>,
+    /// Next fire time computed from the expression (when it has not fired yet).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_run: Option<chrono::DateTime<chrono::Utc>>,
+    /// Status of the most recent recorded run.
+    last_status: Option<agent_core::scheduler::RunStatus>,
 }
 
-#[derive(Debug, Serialize)]
-struct DaySummaryResponse {
-    sessions: u32,
-    messages: u32,
-    active_time: String,
-    tool_calls: u32,
-    tool_errors: u32,
+#[utoipa::path(
+    get,
+    path = "/v1/schedules",
+    tag = "schedules",
+    responses((status = 200, description = "Configured schedules and their state", body = Vec<ScheduleInfo>))
+)]
+async fn list_schedules(State(state): State<AppState>) -> Json<Vec<ScheduleInfo>> {
+    use agent_core::scheduler::{self, ScheduleHistory, Scheduler};
+    let config = state.config.read().await;
+    let stored = Scheduler::read_state(&Scheduler::state_path_for(&config));
+    let history = ScheduleHistory::for_config(&config);
+    let now = chrono::Utc::now();
+
+    let schedules = config
+        .schedules
+        .iter()
+        .map(|schedule| {
+            let parsed = scheduler::preview_cron(&schedule.cron, now, 1);
+            let stored = stored.get(&schedule.name).cloned();
+            ScheduleInfo {
+                config: schedule.clone(),
+                cron_error: parsed.as_ref().err().map(|e| e.to_string()),
+                next_run: match (&stored, &parsed) {
+                    (None, Ok(preview)) if schedule.enabled => preview.next_runs.first().copied(),
+                    _ => None,
+                },
+                state: stored,
+                last_status: history.recent(&schedule.name, 1).first().map(|run| run.status),
+            }
+        })
+        .collect();
+    Json(schedules)
 }
 
-async fn analytics_summary(State(state): State<AppState>) -> impl IntoResponse {
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct HistoryQuery {
+    /// Runs to return, newest first (1–200, default 20).
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/schedules/{name}/history",
+    tag = "schedules",
+    params(("name" = String, Path, description = "Schedule name"), HistoryQuery),
+    responses((status = 200, description = "Recent runs, newest first", body = Vec<ScheduleRun>))
+)]
+async fn schedule_history(
+    State(state): State<AppState>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<HistoryQuery>,
+) -> Result<Json<Vec<agent_core::scheduler::ScheduleRun>>, ApiError> {
+    let limit = query.limit.unwrap_or(20);
+    if !(1..=200).contains(&limit) {
+        return Err(ApiError::bad_request("limit must be between 1 and 200"));
+    }
+    let config = state.config.read().await;
+    if !config.schedules.iter().any(|s| s.name == name) {
+        return Err(ApiError::not_found(format!("No schedule named '{}'", name)));
+    }
+    let history = agent_core::scheduler::ScheduleHistory::for_config(&config);
+    Ok(Json(history.recent(&name, limit)))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct ValidateScheduleRequest {
+    expression: String,
+    /// Upcoming fire times to return (1–20, default 5).
+    count: Option<usize>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ValidateScheduleResponse {
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    normalized: Option<String>,
+    /// RFC 3339 UTC timestamps.
+    next_runs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Check a cron expression exactly as the scheduler will parse it. An
+/// invalid expression is a normal answer (`valid: false`), not a request
+/// error, so editors can validate as the user types.
+#[utoipa::path(
+    post,
+    path = "/v1/schedules/validate",
+    tag = "schedules",
+    request_body = ValidateScheduleRequest,
+    responses((status = 200, description = "Whether the expression parses, and when it fires", body = ValidateScheduleResponse))
+)]
+async fn validate_schedule(
+    Json(req): Json<ValidateScheduleRequest>,
+) -> Result<Json<ValidateScheduleResponse>, ApiError> {
+    let count = req.count.unwrap_or(5);
+    if !(1..=20).contains(&count) {
+        return Err(ApiError::bad_request("count must be between 1 and 20"));
+    }
+    let response = match agent_core::scheduler::preview_cron(&req.expression, chrono::Utc::now(), count) {
+        Ok(preview) => ValidateScheduleResponse {
+            valid: true,
+            normalized: Some(preview.normalized),
+            next_runs: preview.next_runs.iter().map(|t| t.to_rfc3339()).collect(),
+            error: None,
+        },
+        Err(e) => ValidateScheduleResponse {
+            valid: false,
+            normalized: None,
+            next_runs: Vec::new(),
+            error: Some(e.to_string()),
+        },
+    };
+    Ok(Json(response))
+}
+
+// ── Analytics ──────────────────────────────────────────────────────────
+
+pub fn analytics_routes() -> Router<AppState> {
+    Router::new()
+        .route("/v1/analytics/summary", get(analytics_summary))
+        .route("/v1/analytics/report", get(analytics_report))
+        .route("/v1/analytics/timeseries", get(analytics_timeseries))
+        .route("/v1/analytics/goals", get(analytics_goals))
+}
+
+/// Load every session from disk, archived ones included, and run it
+/// through the analytics engine.
+pub(crate) async fn load_analytics(state: &AppState) -> agent_analytics::Analytics {
     let sm = state.session_manager.read().await;
     let sessions = sm.list_sessions();
 
     let mut analytics = agent_analytics::Analytics::default();
 
-    // Load and process all sessions from disk.
     let sessions_dir = {
         let cfg = state.config.read().await;
         cfg.session
@@ -1010,7 +3468,49 @@ async fn analytics_summary(State(state): State<AppState>) -> impl IntoResponse {
             analytics.process_session(&session);
         }
     }
+    // Pruned sessions still count.
+    for session in sm.archived_sessions() {
+        analytics.process_session(&session);
+    }
     analytics.finalize_all();
+    analytics
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct AnalyticsSummaryResponse {
+    total_sessions: usize,
+    active_days: usize,
+    average_session_duration_secs: Option<u64>,
+    /// `[tool, calls]` pairs, most used first.
+    #[schema(value_type = Vec<Vec<Object>>)]
+    top_tools: Vec<(String, u32)>,
+    deep_work_sessions: usize,
+    today: Option<DaySummaryResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DaySummaryResponse {
+    sessions: u32,
+    messages: u32,
+    active_time: String,
+    tool_calls: u32,
+    tool_errors: u32,
+    /// Tool calls refused for being over a `[tools.quotas]` limit.
+    quota_denials: u32,
+    /// Assistant messages rated thumbs up.
+    thumbs_up: u32,
+    /// Assistant messages rated thumbs down.
+    thumbs_down: u32,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/analytics/summary",
+    tag = "analytics",
+    responses((status = 200, description = "Usage totals and today's activity", body = AnalyticsSummaryResponse))
+)]
+async fn analytics_summary(State(state): State<AppState>) -> impl IntoResponse {
+    let analytics = load_analytics(&state).await;
 
     let today = chrono::Utc::now().date_naive();
     let today_summary = analytics
@@ -1021,6 +3521,9 @@ async fn analytics_summary(State(state): State<AppState>) -> impl IntoResponse {
             active_time: agent_analytics::aggregations::format_duration(s.total_active_time_secs),
             tool_calls: s.tool_call_count,
             tool_errors: s.tool_error_count,
+            quota_denials: s.quota_denied_count,
+            thumbs_up: s.thumbs_up_count,
+            thumbs_down: s.thumbs_down_count,
         });
 
     Json(AnalyticsSummaryResponse {
@@ -1033,89 +3536,636 @@ async fn analytics_summary(State(state): State<AppState>) -> impl IntoResponse {
     })
 }
 
-#[derive(Debug, Deserialize)]
+/// Configured goals evaluated over their current periods.
+#[utoipa::path(
+    get,
+    path = "/v1/analytics/goals",
+    tag = "analytics",
+    responses((status = 200, description = "Progress toward each configured goal", body = Vec<Object>))
+)]
+async fn analytics_goals(State(state): State<AppState>) -> Json<Vec<agent_analytics::GoalStatus>> {
+    let goals = state.config.read().await.analytics.goals.clone();
+    if goals.is_empty() {
+        return Json(Vec::new());
+    }
+    let analytics = load_analytics(&state).await;
+    let today = chrono::Utc::now().date_naive();
+    Json(agent_analytics::goals::current_statuses(
+        &analytics, &goals, today,
+    ))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct ReportQuery {
     /// "week" or "month"
     #[serde(default = "default_period")]
     period: String,
+    /// "markdown" (default), "html" or "pdf"
+    #[serde(default = "default_report_format")]
+    format: String,
+}
+
+fn default_report_format() -> String {
+    "markdown".to_string()
 }
 
 fn default_period() -> String {
     "week".to_string()
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/analytics/report",
+    tag = "analytics",
+    params(ReportQuery),
+    responses((
+        status = 200,
+        description = "The report in the requested format",
+        content(("text/markdown"), ("text/html"), ("application/pdf"))
+    ))
+)]
 async fn analytics_report(
     State(state): State<AppState>,
     axum::extract::Query(query): axum::extract::Query<ReportQuery>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let sm = state.session_manager.read().await;
-    let sessions = sm.list_sessions();
+) -> Result<impl IntoResponse, ApiError> {
+    let format: agent_analytics::ReportFormat = query
+        .format
+        .parse()
+        .map_err(ApiError::bad_request)?;
+    let analytics = load_analytics(&state).await;
+    let goals = state.config.read().await.analytics.goals.clone();
 
-    let mut analytics = agent_analytics::Analytics::default();
-
-    let sessions_dir = {
-        let cfg = state.config.read().await;
-        cfg.session
-            .history_dir
-            .clone()
-            .unwrap_or_else(|| agent_core::config::AppConfig::data_dir().join("sessions"))
+    let today = chrono::Utc::now().date_naive();
+    let report = agent_analytics::ReportGenerator::period_report(
+        &analytics,
+        &query.period,
+        today,
+        &goals,
+    )
+    .map_err(ApiError::bad_request)?;
+
+    if format == agent_analytics::ReportFormat::Markdown {
+        return Ok(report.into_response());
+    }
+    let disposition = match format {
+        agent_analytics::ReportFormat::Pdf => format!(
+            "attachment; filename=\"agent-shell-{}-report-{}.pdf\"",
+            query.period, today
+        ),
+        _ => "inline".to_string(),
     };
+    Ok((
+        [
+            (axum::http::header::CONTENT_TYPE, format.content_type().to_string()),
+            (axum::http::header::CONTENT_DISPOSITION, disposition),
+        ],
+        agent_analytics::ReportGenerator::render(&report, format),
+    )
+        .into_response())
+}
 
-    for (id, _, _, _) in &sessions {
-        let path = sessions_dir.join(format!("{}.json", id));
-        if let Ok(session) = agent_core::session::Session::load_from(&path) {
-            analytics.process_session(&session);
-        }
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct TimeseriesQuery {
+    /// Number of days ending today (1..=365).
+    #[serde(default = "default_timeseries_days")]
+    days: i64,
+}
+
+fn default_timeseries_days() -> i64 {
+    30
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct TimeseriesPoint {
+    date: chrono::NaiveDate,
+    sessions: u32,
+    messages: u32,
+    tokens: u64,
+    tool_calls: u32,
+    tool_errors: u32,
+    /// Tool calls refused for being over a `[tools.quotas]` limit.
+    quota_denials: u32,
+    error_rate: f64,
+    thumbs_up: u32,
+    thumbs_down: u32,
+    /// Fraction of rated messages rated thumbs up; `null` when none were.
+    approval_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct TimeseriesResponse {
+    days: i64,
+    points: Vec<TimeseriesPoint>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/analytics/timeseries",
+    tag = "analytics",
+    params(TimeseriesQuery),
+    responses((status = 200, description = "Daily activity", body = TimeseriesResponse))
+)]
+async fn analytics_timeseries(
+    State(state): State<AppState>,
+    axum::extract::Query(query): axum::extract::Query<TimeseriesQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !(1..=365).contains(&query.days) {
+        return Err(ApiError::bad_request("days must be between 1 and 365"));
     }
-    analytics.finalize_all();
 
+    let analytics = load_analytics(&state).await;
     let today = chrono::Utc::now().date_naive();
-    let report = match query.period.as_str() {
-        "week" => {
-            let weekday = today.weekday().num_days_from_monday();
-            let monday = today - chrono::Duration::days(weekday as i64);
-            agent_analytics::ReportGenerator::weekly_report(&analytics, monday)
+    let start = today - chrono::Duration::days(query.days - 1);
+
+    let points = analytics
+        .daily_series(start, today)
+        .into_iter()
+        .map(|d| TimeseriesPoint {
+            date: d.date,
+            sessions: d.session_count,
+            messages: d.message_count,
+            tokens: d.estimated_tokens,
+            tool_calls: d.tool_call_count,
+            tool_errors: d.tool_error_count,
+            quota_denials: d.quota_denied_count,
+            error_rate: d.tool_error_rate(),
+            thumbs_up: d.thumbs_up_count,
+            thumbs_down: d.thumbs_down_count,
+            approval_rate: d.approval_rate(),
+        })
+        .collect();
+
+    Ok(Json(TimeseriesResponse {
+        days: query.days,
+        points,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/sessions",
+    tag = "sessions",
+    request_body = CreateSessionRequest,
+    responses((status = 200, description = "The new session, now active", body = SessionCreated))
+)]
+async fn create_session(
+    State(state): State<AppState>,
+    caller: Caller,
+    ValidJson(req): ValidJson<CreateSessionRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if let Some(agent) = &req.agent {
+        let config = state.config.read().await;
+        if state.agents.read().await.resolve(&config, agent).is_none() {
+            return Err(ApiError::bad_request(format!("Unknown agent: {}", agent)));
         }
-        "month" => agent_analytics::ReportGenerator::monthly_report(
-            &analytics,
-            today.year(),
-            today.month(),
-        ),
-        other => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                format!("Unknown period: '{}'. Use 'week' or 'month'.", other),
-            ));
+    }
+    let mut sm = state.session_manager.write().await;
+    let id = sm
+        .create_session_with_agent(req.name, req.agent)
+        .map_err(ApiError::from)?
+        .id
+        .clone();
+    caller.claim(&mut sm, &id).await?;
+    let session = sm
+        .session(&id)
+        .ok_or_else(|| ApiError::session_not_found(&id))?;
+    Ok(Json(SessionCreated {
+        id: session.id.clone(),
+        name: session.name.clone(),
+        agent: session.agent.clone(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/sessions/{id}/fork",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID (UUID)")),
+    request_body = ForkSessionRequest,
+    responses((status = 200, description = "The copy", body = SessionCreated))
+)]
+async fn fork_session(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    ValidJson(req): ValidJson<ForkSessionRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    validate_session_id(&id)?;
+
+    let mut sm = state.session_manager.write().await;
+    caller.authorize(&sm, &id)?;
+    let name = match req.name {
+        Some(name) => name,
+        None => {
+            let original = sm
+                .session_mut(&id)
+                .ok_or_else(|| ApiError::session_not_found(&id))?;
+            format!("{} (fork)", original.name)
         }
     };
+    let fork_id = sm
+        .fork_session(&id, name)
+        .map_err(|_| ApiError::session_not_found(&id))?
+        .id
+        .clone();
+    // The copy belongs to whoever forked it.
+    caller.claim(&mut sm, &fork_id).await?;
+    let session = sm
+        .session(&fork_id)
+        .ok_or_else(|| ApiError::session_not_found(&fork_id))?;
+    Ok(Json(SessionCreated {
+        id: session.id.clone(),
+        name: session.name.clone(),
+        agent: session.agent.clone(),
+    }))
+}
 
-    Ok(report)
+#[utoipa::path(
+    get,
+    path = "/v1/sessions/{id}/checkpoints",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID (UUID)")),
+    responses((status = 200, description = "Checkpoints, oldest first", body = Vec<CheckpointInfo>))
+)]
+async fn list_checkpoints(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<Vec<CheckpointInfo>>, ApiError> {
+    validate_session_id(&id)?;
+    let sm = state.session_manager.read().await;
+    caller.authorize(&sm, &id)?;
+    let session = sm
+        .session(&id)
+        .ok_or_else(|| ApiError::session_not_found(&id))?;
+    Ok(Json(
+        session
+            .checkpoints
+            .iter()
+            .map(|c| CheckpointInfo {
+                id: c.id.clone(),
+                label: c.label.clone(),
+                created_at: c.created_at.to_rfc3339(),
+                message_count: c.message_count,
+                files: c.files.iter().map(|f| f.path.display().to_string()).collect(),
+            })
+            .collect(),
+    ))
 }
 
-async fn create_session(
+/// Restore a session's messages, and optionally workspace files, to a
+/// checkpoint.
+#[utoipa::path(
+    post,
+    path = "/v1/sessions/{id}/rollback",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID (UUID)")),
+    request_body = RollbackRequest,
+    responses((status = 200, description = "What was undone", body = Rollback))
+)]
+async fn rollback_session(
     State(state): State<AppState>,
-    Json(req): Json<CreateSessionRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+    caller: Caller,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    ValidJson(req): ValidJson<RollbackRequest>,
+) -> Result<Json<agent_core::checkpoint::Rollback>, ApiError> {
+    validate_session_id(&id)?;
     let mut sm = state.session_manager.write().await;
+    caller.authorize(&sm, &id)?;
+    let store = sm.snapshot_store(&id);
     let session = sm
-        .create_session(req.name)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    Ok(Json(serde_json::json!({
-        "id": session.id,
-        "name": session.name,
-    })))
+        .session_mut(&id)
+        .ok_or_else(|| ApiError::session_not_found(&id))?;
+    let rollback = match &req.checkpoint {
+        Some(checkpoint) => session
+            .rollback(checkpoint, req.restore_files, &store)
+            .map_err(|e| ApiError::not_found(e.to_string()))?,
+        None => session
+            .undo(req.restore_files, &store)
+            .map_err(|e| ApiError::new(ErrorCode::SessionError, e.to_string()))?,
+    };
+    sm.save_session_async(&id)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(Json(rollback))
+}
+
+/// A session's token usage and estimated cost, with the configured budget.
+#[derive(Debug, Serialize, ToSchema)]
+struct SessionUsageResponse {
+    #[serde(flatten)]
+    usage: agent_core::types::TokenUsage,
+    total_tokens: u64,
+    budget_usd: Option<f64>,
+    budget_tokens: Option<u64>,
+}
+
+impl SessionUsageResponse {
+    fn new(
+        usage: agent_core::types::TokenUsage,
+        budget_usd: Option<f64>,
+        budget_tokens: Option<u64>,
+    ) -> Self {
+        Self {
+            usage,
+            total_tokens: usage.total_tokens(),
+            budget_usd,
+            budget_tokens,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/sessions/{id}/usage",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID (UUID)")),
+    responses((status = 200, description = "Token usage and cost so far", body = SessionUsageResponse))
+)]
+async fn session_usage(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<SessionUsageResponse>, ApiError> {
+    validate_session_id(&id)?;
+    let usage = {
+        let sm = state.session_manager.read().await;
+        caller.authorize(&sm, &id)?;
+        sm.session(&id)
+            .ok_or_else(|| ApiError::session_not_found(&id))?
+            .usage
+    };
+    let config = state.config.read().await;
+    Ok(Json(SessionUsageResponse::new(
+        usage,
+        config.session.budget_usd,
+        config.session.budget_tokens,
+    )))
+}
+
+/// Files the session's tools modified, each diffed against its contents
+/// before the first modification.
+#[utoipa::path(
+    get,
+    path = "/v1/sessions/{id}/changes",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID (UUID)")),
+    responses((status = 200, description = "Files the session changed", body = Vec<FileDiff>))
+)]
+async fn list_file_changes(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<Vec<agent_core::checkpoint::FileDiff>>, ApiError> {
+    validate_session_id(&id)?;
+    let sm = state.session_manager.read().await;
+    caller.authorize(&sm, &id)?;
+    let store = sm.snapshot_store(&id);
+    let session = sm
+        .session(&id)
+        .ok_or_else(|| ApiError::session_not_found(&id))?;
+    let diffs = session
+        .file_changes()
+        .into_iter()
+        .map(|change| change.diff(&store))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(ApiError::internal)?;
+    Ok(Json(diffs))
+}
+
+/// Restore one changed file, or all of them, to its state before the
+/// session modified it. The conversation is left as is.
+#[utoipa::path(
+    post,
+    path = "/v1/sessions/{id}/changes/revert",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID (UUID)")),
+    request_body = FileChangeRequest,
+    responses((status = 200, description = "The reverted paths", body = Object, example = json!({ "reverted": ["src/main.rs"] })))
+)]
+async fn revert_file_changes(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(req): Json<FileChangeRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let reverted = resolve_file_changes(&state, &caller, &id, req, agent_core::session::Session::revert_file).await?;
+    Ok(Json(serde_json::json!({ "reverted": reverted })))
+}
+
+/// Keep one changed file, or all of them, as it is now and drop its
+/// snapshots.
+#[utoipa::path(
+    post,
+    path = "/v1/sessions/{id}/changes/accept",
+    tag = "sessions",
+    params(("id" = String, Path, description = "Session ID (UUID)")),
+    request_body = FileChangeRequest,
+    responses((status = 200, description = "The accepted paths", body = Object, example = json!({ "accepted": ["src/main.rs"] })))
+)]
+async fn accept_file_changes(
+    State(state): State<AppState>,
+    caller: Caller,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(req): Json<FileChangeRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let accepted = resolve_file_changes(&state, &caller, &id, req, agent_core::session::Session::accept_file).await?;
+    Ok(Json(serde_json::json!({ "accepted": accepted })))
+}
+
+/// Apply `resolve` to the requested changed files and save the session.
+async fn resolve_file_changes(
+    state: &AppState,
+    caller: &Caller,
+    id: &str,
+    req: FileChangeRequest,
+    resolve: fn(
+        &mut agent_core::session::Session,
+        &std::path::Path,
+        &agent_core::checkpoint::SnapshotStore,
+    ) -> Result<(), agent_core::AgentError>,
+) -> Result<Vec<std::path::PathBuf>, ApiError> {
+    validate_session_id(id)?;
+    let mut sm = state.session_manager.write().await;
+    caller.authorize(&sm, id)?;
+    let store = sm.snapshot_store(id);
+    let session = sm
+        .session_mut(id)
+        .ok_or_else(|| ApiError::session_not_found(id))?;
+    let paths: Vec<std::path::PathBuf> = match req.path {
+        Some(path) => vec![path],
+        None => session.file_changes().iter().map(|c| c.path.clone()).collect(),
+    };
+    for path in &paths {
+        resolve(session, path, &store).map_err(|e| match e {
+            agent_core::AgentError::Session(msg) => ApiError::not_found(msg),
+            e => ApiError::from(e),
+        })?;
+    }
+    sm.save_session_async(id)
+        .await
+        .map_err(ApiError::from)?;
+    Ok(paths)
+}
+
+// ── Logs ───────────────────────────────────────────────────────────────
+
+pub fn log_routes() -> Router<AppState> {
+    Router::new().route("/v1/logs/stream", get(log_stream))
+}
+
+/// Buffered events replayed when a log stream opens, by default.
+const DEFAULT_LOG_BACKLOG: usize = 200;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct LogStreamQuery {
+    /// Least severe level sent (default `info`).
+    #[serde(default)]
+    level: Option<crate::logs::LogLevel>,
+    /// Buffered events replayed first (default 200, at most 2000).
+    #[serde(default)]
+    backlog: Option<usize>,
+}
+
+/// Server log events: the most recent buffered ones, then new ones as they
+/// happen. Each is a `log` event with a JSON `LogEntry`; a `lagged` event
+/// reports events skipped because the client fell behind.
+#[utoipa::path(
+    get,
+    path = "/v1/logs/stream",
+    tag = "logs",
+    params(LogStreamQuery),
+    responses((
+        status = 200,
+        description = "`log` events with a LogEntry each",
+        body = String,
+        content_type = "text/event-stream"
+    ))
+)]
+async fn log_stream(
+    axum::extract::Query(query): axum::extract::Query<LogStreamQuery>,
+) -> axum::response::Response {
+    use axum::response::sse::{Event, KeepAlive};
+    use tokio::sync::broadcast::error::RecvError;
+
+    let min = query.level.unwrap_or(crate::logs::LogLevel::Info);
+    let backlog = query
+        .backlog
+        .unwrap_or(DEFAULT_LOG_BACKLOG)
+        .min(crate::logs::CAPACITY);
+    let buffer = crate::logs::buffer();
+    // Subscribe before replaying so nothing falls in between.
+    let live = buffer.subscribe();
+    let replay = buffer.replay(min, backlog);
+    let through = replay.through;
+
+    let entry_event = |entry: &crate::logs::LogEntry| {
+        Event::default()
+            .id(entry.seq.to_string())
+            .event("log")
+            .json_data(entry)
+            .unwrap_or_default()
+    };
+    let replayed = tokio_stream::iter(replay.entries.iter().map(entry_event).collect::<Vec<_>>());
+    let live = futures::stream::unfold(live, move |mut live| async move {
+        loop {
+            match live.recv().await {
+                Ok(entry) if entry.seq > through && entry.level >= min => {
+                    return Some((entry_event(&entry), live));
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    let event = Event::default()
+                        .event("lagged")
+                        .data(serde_json::json!({ "skipped": skipped }).to_string());
+                    return Some((event, live));
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    let stream = replayed
+        .chain(live)
+        .map(Ok::<_, std::convert::Infallible>);
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+// ── Notifications ──────────────────────────────────────────────────────
+
+pub fn notification_routes() -> Router<AppState> {
+    Router::new().route("/v1/notifications/stream", get(notification_stream))
+}
+
+/// Notifications the agent sends with `notify_user`, as they happen. Each
+/// is a `notification` event with a JSON `Notification`; a `lagged` event
+/// reports notifications skipped because the client fell behind. Nothing
+/// is replayed, so a page opened later does not pop up stale toasts.
+#[utoipa::path(
+    get,
+    path = "/v1/notifications/stream",
+    tag = "notifications",
+    responses((
+        status = 200,
+        description = "`notification` events with a Notification each",
+        body = String,
+        content_type = "text/event-stream"
+    ))
+)]
+async fn notification_stream() -> axum::response::Response {
+    use axum::response::sse::{Event, KeepAlive};
+    use tokio::sync::broadcast::error::RecvError;
+
+    let live = agent_core::notifications::subscribe();
+    let stream = futures::stream::unfold(live, |mut live| async move {
+        match live.recv().await {
+            Ok(notification) => {
+                let event = Event::default()
+                    .id(notification.seq.to_string())
+                    .event("notification")
+                    .json_data(&notification)
+                    .unwrap_or_default();
+                Some((event, live))
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                let event = Event::default()
+                    .event("lagged")
+                    .data(serde_json::json!({ "skipped": skipped }).to_string());
+                Some((event, live))
+            }
+            Err(RecvError::Closed) => None,
+        }
+    })
+    .map(Ok::<_, std::convert::Infallible>);
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
 }
 
 // ── SPA Static Files ────────────────────────────────────────────────────
 //
 // Serves the built React UI from crates/agent-ui/dist/.
 // Falls back to index.html for all unmatched GET requests (client-side routing).
-// The AGENT_UI_DIST env var overrides the default path.
+// The AGENT_UI_DIST env var overrides the default path. With the `embed-ui`
+// feature the UI compiled into the binary is served instead, unless
+// AGENT_UI_DIST is set.
 
 pub fn spa_routes() -> Router<AppState> {
     use std::path::PathBuf;
     use tower_http::services::{ServeDir, ServeFile};
 
+    #[cfg(feature = "embed-ui")]
+    if std::env::var_os("AGENT_UI_DIST").is_none() {
+        if !crate::ui::is_embedded() {
+            tracing::warn!("Built with embed-ui but the UI was not built; run npm run build");
+        }
+        return Router::new().fallback(crate::ui::serve);
+    }
+
     let dist_path = std::env::var("AGENT_UI_DIST")
         .map(PathBuf::from)
         .unwrap_or_else(|_| {
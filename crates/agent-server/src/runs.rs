@@ -0,0 +1,274 @@
+//! Replay buffers for streamed agent turns.
+//!
+//! Each streamed turn is a run: its SSE frames are numbered from 1 and kept
+//! while the turn is live and for a while after, independent of the client
+//! connection. A client that loses the stream reconnects to
+//! `GET /v1/runs/{id}/events` with `Last-Event-ID` and receives what it
+//! missed, followed by the rest of the turn as it happens.
+//!
+//! [`SessionLocks`] keeps one turn per session at a time, so two clients
+//! cannot interleave turns in the same conversation.
+
+use futures::Stream;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// How long a finished run stays available for replay.
+const RETAIN_FINISHED: Duration = Duration::from_secs(300);
+
+/// Finished runs kept at most; the oldest are dropped first.
+const MAX_FINISHED: usize = 64;
+
+/// One buffered server-sent event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// Sequence number, sent as the SSE `id`.
+    pub id: u64,
+    /// SSE event name; `None` for the default `message` event.
+    pub event: Option<&'static str>,
+    /// Payload; `None` sends a keep-alive comment.
+    pub data: Option<String>,
+}
+
+impl Frame {
+    pub fn to_sse(&self) -> axum::response::sse::Event {
+        let mut event = axum::response::sse::Event::default().id(self.id.to_string());
+        if let Some(name) = self.event {
+            event = event.event(name);
+        }
+        match &self.data {
+            Some(data) => event.data(data),
+            None => event.comment("ping"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct RunLog {
+    frames: Vec<Frame>,
+    finished_at: Option<Instant>,
+}
+
+/// The frames of one run.
+pub struct Run {
+    /// ID of the request that started the turn, for correlation.
+    pub request_id: String,
+    /// Session the turn runs in; only callers who may access it can follow
+    /// the run.
+    pub session_id: Option<String>,
+    log: Mutex<RunLog>,
+    /// Bumped on every change so followers wake up.
+    changed: watch::Sender<u64>,
+}
+
+impl Run {
+    fn new(request_id: String, session_id: Option<String>) -> Self {
+        Self {
+            request_id,
+            session_id,
+            log: Mutex::new(RunLog::default()),
+            changed: watch::Sender::new(0),
+        }
+    }
+
+    fn log(&self) -> std::sync::MutexGuard<'_, RunLog> {
+        self.log.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Append a frame, numbering it after the last.
+    pub fn push(&self, event: Option<&'static str>, data: Option<String>) {
+        let mut log = self.log();
+        let id = log.frames.len() as u64 + 1;
+        log.frames.push(Frame { id, event, data });
+        drop(log);
+        self.changed.send_modify(|n| *n += 1);
+    }
+
+    /// Mark the turn as over; followers end after the last frame.
+    pub fn finish(&self) {
+        self.log().finished_at = Some(Instant::now());
+        self.changed.send_modify(|n| *n += 1);
+    }
+
+    /// Frames after sequence number `after`, then new ones as they are
+    /// pushed, ending once the run is finished.
+    pub fn follow(self: Arc<Self>, after: u64) -> impl Stream<Item = Frame> {
+        let changed = self.changed.subscribe();
+        futures::stream::unfold((self, changed, after), |(run, mut changed, next)| async move {
+            loop {
+                changed.borrow_and_update();
+                {
+                    let log = run.log();
+                    if let Some(frame) = log.frames.get(next as usize) {
+                        let frame = frame.clone();
+                        drop(log);
+                        return Some((frame, (run, changed, next + 1)));
+                    }
+                    if log.finished_at.is_some() {
+                        return None;
+                    }
+                }
+                changed.changed().await.ok()?;
+            }
+        })
+    }
+}
+
+/// Runs by ID, shared by every request.
+#[derive(Default)]
+pub struct RunStore {
+    runs: Mutex<HashMap<String, Arc<Run>>>,
+}
+
+impl RunStore {
+    /// Register a new run for request `request_id` in session `session_id`
+    /// under a fresh ID, dropping finished runs past their retention.
+    pub fn start(&self, request_id: &str, session_id: Option<&str>) -> (String, Arc<Run>) {
+        let mut runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+        let mut finished: Vec<(Instant, String)> = runs
+            .iter()
+            .filter_map(|(id, run)| run.log().finished_at.map(|at| (at, id.clone())))
+            .collect();
+        finished.sort();
+        let excess = finished.len().saturating_sub(MAX_FINISHED);
+        for (i, (at, id)) in finished.into_iter().enumerate() {
+            if i < excess || at.elapsed() > RETAIN_FINISHED {
+                runs.remove(&id);
+            }
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let run = Arc::new(Run::new(
+            request_id.to_string(),
+            session_id.map(String::from),
+        ));
+        runs.insert(id.clone(), run.clone());
+        (id, run)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Run>> {
+        self.runs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(id)
+            .cloned()
+    }
+}
+
+/// Sessions with a turn in progress, and the run streaming each turn once
+/// it has one.
+#[derive(Default)]
+pub struct SessionLocks {
+    busy: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl SessionLocks {
+    fn busy(&self) -> std::sync::MutexGuard<'_, HashMap<String, Option<String>>> {
+        self.busy.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Claim `session` until the returned lock is dropped. Fails with the
+    /// ID of the run streaming the turn that holds it, if there is one.
+    pub fn acquire(self: &Arc<Self>, session: &str) -> Result<SessionLock, Option<String>> {
+        let mut busy = self.busy();
+        if let Some(holder) = busy.get(session) {
+            return Err(holder.clone());
+        }
+        busy.insert(session.to_string(), None);
+        Ok(SessionLock {
+            locks: self.clone(),
+            session: session.to_string(),
+        })
+    }
+
+    /// The run streaming the turn in `session`, if one does.
+    pub fn holder(&self, session: &str) -> Option<String> {
+        self.busy().get(session).cloned().flatten()
+    }
+}
+
+/// A session claimed for one turn; released on drop.
+pub struct SessionLock {
+    locks: Arc<SessionLocks>,
+    session: String,
+}
+
+impl SessionLock {
+    /// The claimed session.
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// Record the run streaming the turn, for callers who find the session
+    /// busy.
+    pub fn set_run(&self, run_id: &str) {
+        if let Some(holder) = self.locks.busy().get_mut(&self.session) {
+            *holder = Some(run_id.to_string());
+        }
+    }
+}
+
+impl Drop for SessionLock {
+    fn drop(&mut self) {
+        self.locks.busy().remove(&self.session);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_follow_replays_then_streams_live() {
+        let store = RunStore::default();
+        let (id, run) = store.start("req-1", None);
+        run.push(None, Some("a".into()));
+        run.push(Some("tool_call"), Some("b".into()));
+
+        let follower = tokio::spawn(store.get(&id).unwrap().follow(1).collect::<Vec<_>>());
+        tokio::task::yield_now().await;
+        run.push(None, Some("c".into()));
+        run.finish();
+
+        let frames = follower.await.unwrap();
+        let ids: Vec<u64> = frames.iter().map(|f| f.id).collect();
+        assert_eq!(ids, [2, 3]);
+        assert_eq!(frames[0].event, Some("tool_call"));
+
+        // A finished run still replays in full.
+        assert_eq!(run.clone().follow(0).collect::<Vec<_>>().await.len(), 3);
+        assert!(run.follow(3).collect::<Vec<_>>().await.is_empty());
+    }
+
+    #[test]
+    fn test_start_drops_expired_runs() {
+        let store = RunStore::default();
+        let (old, run) = store.start("req-1", None);
+        run.finish();
+        run.log().finished_at = Instant::now().checked_sub(RETAIN_FINISHED * 2);
+        let (live, _) = store.start("req-2", None);
+        let (other, _) = store.start("req-2", None);
+        assert_ne!(live, other);
+        assert!(store.get(&old).is_none());
+        assert!(store.get(&live).is_some());
+    }
+
+    #[test]
+    fn test_session_lock_is_exclusive_until_dropped() {
+        let locks = Arc::new(SessionLocks::default());
+        let lock = locks.acquire("s1").unwrap();
+        assert_eq!(locks.acquire("s1").err(), Some(None));
+        lock.set_run("run-a");
+        assert_eq!(locks.acquire("s1").err(), Some(Some("run-a".to_string())));
+        let other = locks.acquire("s2").unwrap();
+        other.set_run("run-b");
+        drop(lock);
+        assert_eq!(locks.holder("s1"), None);
+        assert_eq!(locks.holder("s2").as_deref(), Some("run-b"));
+        assert!(locks.acquire("s1").is_ok());
+        drop(other);
+    }
+}
@@ -0,0 +1,114 @@
+//! Read-only public view of a shared session.
+//!
+//! `POST /v1/sessions/{id}/share` gives a session an unguessable token;
+//! `/share/{token}` then serves its transcript as a self-contained HTML page
+//! that needs no bearer token and no JavaScript, so it can be sent to
+//! someone without API access.
+
+use agent_core::session::Session;
+use agent_core::types::Role;
+
+/// Escape text for use in HTML element content and attribute values.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:820px;margin:2em auto;padding:0 1em;\
+color:#1f2328;line-height:1.5}h1{font-size:1.4em;margin-bottom:0}.meta{color:#656d76;font-size:.85em}\
+.msg{border:1px solid #d0d7de;border-radius:8px;margin:1em 0;padding:.6em .9em}\
+.user{background:#f6f8fa}.role{font-size:.75em;text-transform:uppercase;letter-spacing:.05em;color:#656d76}\
+.content{white-space:pre-wrap;word-wrap:break-word}pre{background:#f6f8fa;padding:.6em;overflow-x:auto;\
+border-radius:6px;font-size:.85em}summary{cursor:pointer;color:#656d76;font-size:.85em}";
+
+/// Render `session` as a standalone HTML transcript. System prompts are
+/// left out; tool calls and results are collapsed.
+pub fn render_session(session: &Session) -> String {
+    let mut body = String::new();
+    for msg in &session.messages {
+        match msg.role {
+            Role::System => continue,
+            Role::Tool => {
+                body.push_str(&format!(
+                    "<details class=\"msg\"><summary>Tool result</summary><pre>{}</pre></details>\n",
+                    escape(msg.content.trim_end())
+                ));
+                continue;
+            }
+            Role::User | Role::Assistant => {}
+        }
+        let (class, role) = match msg.role {
+            Role::User => ("msg user", "User"),
+            _ => ("msg", "Assistant"),
+        };
+        body.push_str(&format!(
+            "<div class=\"{}\"><div class=\"role\">{}</div>",
+            class, role
+        ));
+        if !msg.content.is_empty() {
+            body.push_str(&format!(
+                "<div class=\"content\">{}</div>",
+                escape(msg.content.trim_end())
+            ));
+        }
+        if !msg.images.is_empty() {
+            body.push_str(&format!(
+                "<div class=\"meta\">[{} image(s) not shown]</div>",
+                msg.images.len()
+            ));
+        }
+        for call in msg.tool_calls.iter().flatten() {
+            body.push_str(&format!(
+                "<details><summary>Tool call: {}</summary><pre>{}</pre></details>",
+                escape(&call.name),
+                escape(&call.arguments)
+            ));
+        }
+        body.push_str("</div>\n");
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\"><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <meta name=\"robots\" content=\"noindex\">\
+         <title>{title}</title><style>{style}</style></head><body>\
+         <h1>{title}</h1><p class=\"meta\">Shared session · {count} messages · last updated {updated}</p>\n\
+         {body}</body></html>\n",
+        title = escape(&session.name),
+        style = STYLE,
+        count = session.messages.len(),
+        updated = session.updated_at.format("%Y-%m-%d %H:%M UTC"),
+        body = body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::types::Message;
+
+    #[test]
+    fn test_render_escapes_and_skips_system() {
+        let mut session = Session::new("<script>alert(1)</script>");
+        session.push_message(Message::system("secret system prompt"));
+        session.push_message(Message::user("is 1 < 2 && \"yes\"?"));
+        session.push_message(Message::assistant("Yes."));
+
+        let html = render_session(&session);
+        assert!(html.contains("<title>&lt;script&gt;alert(1)&lt;/script&gt;</title>"));
+        assert!(html.contains("is 1 &lt; 2 &amp;&amp; &quot;yes&quot;?"));
+        assert!(html.contains("Yes."));
+        assert!(!html.contains("secret system prompt"));
+        assert!(!html.contains("<script>"));
+    }
+}
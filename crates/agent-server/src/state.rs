@@ -1,4 +1,6 @@
+use crate::runs::{RunStore, SessionLocks};
 use agent_core::agent_loop::AgentLoop;
+use agent_core::agents::AgentStore;
 use agent_core::capture::HookBackend;
 use agent_core::config::AppConfig;
 use agent_core::session::SessionManager;
@@ -17,12 +19,18 @@ pub struct AppState {
     pub tool_registry: Arc<ToolRegistry>,
     pub session_manager: Arc<RwLock<SessionManager>>,
     pub agent_loop: Arc<RwLock<AgentLoop>>,
+    /// Agent presets created through the API (config presets live in `config`).
+    pub agents: Arc<RwLock<AgentStore>>,
     pub plugin_registry: Arc<RwLock<PluginRegistry>>,
     pub skill_indexer: Arc<SkillIndexer>,
     /// Hook backend for processing shell hook IPC messages.
     pub hook_backend: Arc<Mutex<HookBackend>>,
     /// In-memory terminal session manager (fed by hook events).
     pub terminal_sessions: Arc<RwLock<TerminalSessionManager>>,
+    /// Replay buffers of streamed turns, for clients that reconnect.
+    pub runs: Arc<RunStore>,
+    /// Sessions with a turn in progress; a second turn gets 409.
+    pub session_locks: Arc<SessionLocks>,
     /// Timestamp when the server started (for uptime calculation).
     pub started_at: DateTime<Utc>,
 }
@@ -36,6 +44,7 @@ impl AppState {
     ) -> anyhow::Result<Self> {
         let session_manager = SessionManager::new(&config)?;
         let agent_loop = AgentLoop::new(config.clone(), tool_registry.clone())?;
+        let agents = AgentStore::load(AgentStore::path_for(&config));
 
         let mut hook_backend = HookBackend::new();
         hook_backend.start();
@@ -45,10 +54,13 @@ impl AppState {
             tool_registry,
             session_manager: Arc::new(RwLock::new(session_manager)),
             agent_loop: Arc::new(RwLock::new(agent_loop)),
+            agents: Arc::new(RwLock::new(agents)),
             plugin_registry,
             skill_indexer,
             hook_backend: Arc::new(Mutex::new(hook_backend)),
             terminal_sessions: Arc::new(RwLock::new(TerminalSessionManager::new())),
+            runs: Arc::new(RunStore::default()),
+            session_locks: Arc::new(SessionLocks::default()),
             started_at: Utc::now(),
         })
     }
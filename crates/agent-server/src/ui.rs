@@ -0,0 +1,64 @@
+//! The web UI compiled into the binary (`embed-ui` feature).
+//!
+//! `crates/agent-ui/dist` is embedded at build time, so build the UI
+//! (`npm run build`) before `cargo build --features embed-ui`. Paths that
+//! match no asset get `index.html` for client-side routing, like the
+//! on-disk fallback in [`crate::routes::spa_routes`].
+
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use rust_embed::RustEmbed;
+
+#[derive(RustEmbed)]
+#[folder = "../agent-ui/dist/"]
+#[allow_missing = true]
+struct Assets;
+
+/// Vite fingerprints everything under `assets/`, so those never change.
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Whether a UI was embedded (the dist folder existed at build time).
+pub fn is_embedded() -> bool {
+    Assets::get("index.html").is_some()
+}
+
+/// Serve an embedded asset, or `index.html` for unknown paths.
+pub async fn serve(uri: Uri, headers: HeaderMap) -> Response {
+    let path = uri.path().trim_start_matches('/');
+    let (path, file) = match Assets::get(path).filter(|_| !path.is_empty()) {
+        Some(file) => (path, file),
+        None => match Assets::get("index.html") {
+            Some(file) => ("index.html", file),
+            None => return (StatusCode::NOT_FOUND, "UI not built").into_response(),
+        },
+    };
+
+    let etag = format!("\"{}\"", hex(&file.metadata.sha256_hash()[..16]));
+    let cache_control = if path.starts_with("assets/") {
+        IMMUTABLE
+    } else {
+        "no-cache"
+    };
+    let mut response = if headers
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|v| v.as_bytes() == etag.as_bytes())
+    {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        (
+            [(header::CONTENT_TYPE, file.metadata.mimetype().to_string())],
+            file.data.into_owned(),
+        )
+            .into_response()
+    };
+    let headers = response.headers_mut();
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(cache_control));
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, value);
+    }
+    response
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
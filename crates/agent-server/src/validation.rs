@@ -0,0 +1,177 @@
+//! Request hardening shared by every route.
+//!
+//! JSON payloads of the chat and session endpoints are checked against a
+//! schema before they reach a handler, so a malformed request is told every
+//! problem at once. Error responses that are not already an [`ApiError`]
+//! (extractor rejections, the body size limit, routing) are rewritten as one.
+
+use crate::error::{ApiError, ErrorCode};
+use agent_core::tool_validation::validate_value;
+use axum::extract::{FromRequest, Request};
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Largest error body kept as an error's message.
+const MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// A request payload with a JSON Schema it must satisfy.
+pub trait RequestSchema {
+    fn schema() -> Value;
+}
+
+/// Like [`Json`], but the body is checked against `T::schema()` before it is
+/// deserialized; violations are rejected with `validation_failed` and listed
+/// in `details.errors`.
+pub struct ValidJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + RequestSchema,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<Value>::from_request(req, state)
+            .await
+            .map_err(|rejection| ApiError::from_status(rejection.status(), rejection.body_text()))?;
+        let issues = validate_value(&T::schema(), &value, "body");
+        if !issues.is_empty() {
+            return Err(ApiError::new(
+                ErrorCode::ValidationFailed,
+                "The request body does not match the expected schema",
+            )
+            .with_details(serde_json::json!({ "errors": issues })));
+        }
+        serde_json::from_value(value)
+            .map(ValidJson)
+            .map_err(|e| ApiError::new(ErrorCode::ValidationFailed, e.to_string()))
+    }
+}
+
+/// Middleware rewriting error responses that are not already JSON (or an
+/// HTML page) as an [`ApiError`] with the status's generic code, keeping
+/// their text as the message.
+pub async fn problem_details(req: Request, next: Next) -> Response {
+    let response = next.run(req).await;
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return response;
+    }
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if content_type.contains("json") || content_type.starts_with("text/html") {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let message = match axum::body::to_bytes(body, MAX_MESSAGE_BYTES).await {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).trim().to_string(),
+        Err(_) => String::new(),
+    };
+    let message = if message.is_empty() {
+        status.canonical_reason().unwrap_or("Error").to_string()
+    } else {
+        message
+    };
+    let mut problem = ApiError::from_status(status, message).into_response();
+    for (name, value) in &parts.headers {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            problem.headers_mut().insert(name.clone(), value.clone());
+        }
+    }
+    problem
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PROBLEM_CONTENT_TYPE;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::Router;
+    use serde::Deserialize;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    #[derive(Deserialize)]
+    struct Greeting {
+        name: String,
+    }
+
+    impl RequestSchema for Greeting {
+        fn schema() -> Value {
+            json!({
+                "type": "object",
+                "properties": { "name": { "type": "string", "minLength": 1 } },
+                "required": ["name"],
+                "additionalProperties": false
+            })
+        }
+    }
+
+    async fn send(body: &str) -> (StatusCode, String, Value) {
+        let app = Router::new()
+            .route(
+                "/",
+                post(|ValidJson(g): ValidJson<Greeting>| async move { format!("hi {}", g.name) }),
+            )
+            .layer(axum::middleware::from_fn(problem_details));
+        let req = Request::builder()
+            .method("POST")
+            .uri("/")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        let status = resp.status();
+        let content_type = resp
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .map(|v| v.to_str().unwrap().to_string())
+            .unwrap_or_default();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (
+            status,
+            content_type,
+            serde_json::from_slice(&body).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_schema_violations_are_listed() {
+        let (status, content_type, problem) = send(r#"{"name":"","extra":1}"#).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(content_type, PROBLEM_CONTENT_TYPE);
+        assert_eq!(problem["status"], 422);
+        assert_eq!(problem["code"], "validation_failed");
+        assert_eq!(problem["details"]["errors"].as_array().unwrap().len(), 2);
+        assert!(problem["details"]["errors"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("body.name: must be at least 1 characters long")));
+
+        let (status, _, _) = send(r#"{"name":"Ada"}"#).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_json_is_a_problem() {
+        let (status, content_type, problem) = send("{").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(content_type, PROBLEM_CONTENT_TYPE);
+        assert_eq!(problem["title"], "Bad Request");
+        assert_eq!(problem["code"], "invalid_request");
+        assert!(!problem["detail"].as_str().unwrap().is_empty());
+    }
+}
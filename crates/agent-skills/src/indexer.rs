@@ -377,6 +377,21 @@ impl SkillIndexer {
             .with_references(has_references))
     }
 
+    /// `(name, SKILL.md)` pairs for the named skills, skipping (and logging)
+    /// any that cannot be read.
+    pub fn skill_docs(&self, names: &[String]) -> Vec<(String, String)> {
+        names
+            .iter()
+            .filter_map(|name| match self.read_skill_content(name) {
+                Ok(skill) => Some((skill.name, skill.content)),
+                Err(e) => {
+                    tracing::warn!("Skipping skill '{}': {}", name, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
     /// Read sub-skill content.
     pub fn read_sub_skill_content(
         &self,
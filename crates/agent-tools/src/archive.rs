@@ -0,0 +1,881 @@
+//! Archive tools: `archive_extract` unpacks a zip, tar or tar.gz into the
+//! workspace and `archive_create` bundles workspace files into one.
+//!
+//! Every entry must land inside the destination directory: names with
+//! absolute or `..` components are refused (zip-slip), links and special
+//! files are skipped, existing files are kept, and the entry count and
+//! unpacked size are capped so a zip bomb stops early. Entries that
+//! `[sandbox.paths]` does not let the tool write are skipped, and files it
+//! denies are left out of new archives.
+
+use crate::file_ops::{validate_path, validate_write_path};
+use agent_core::error::AgentError;
+use agent_core::path_rules::PathRules;
+use agent_core::tool_registry::Tool;
+use agent_core::workspace;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Most entries read from or written to one archive.
+const MAX_ENTRIES: usize = 10_000;
+
+/// Most bytes unpacked from, or packed into, one archive.
+const MAX_TOTAL_BYTES: u64 = 1024 * 1024 * 1024; // 1 GiB
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl Format {
+    /// `explicit` if given, otherwise from the file name.
+    fn detect(path: &Path, explicit: Option<&str>) -> Result<Self, String> {
+        let name = match explicit {
+            Some(format) => format!(".{}", format.to_ascii_lowercase()),
+            None => path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_ascii_lowercase())
+                .unwrap_or_default(),
+        };
+        if name.ends_with(".zip") {
+            Ok(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Ok(Self::Tar)
+        } else {
+            Err(format!(
+                "Cannot tell the archive format of {}; pass format: zip, tar or tar.gz",
+                path.display()
+            ))
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::Tar => "tar",
+            Self::TarGz => "tar.gz",
+        }
+    }
+}
+
+/// `name` as a path relative to the destination, or `None` if it would
+/// escape it.
+fn entry_path(name: &Path) -> Option<PathBuf> {
+    let mut rel = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => rel.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(rel)
+}
+
+/// Running entry and byte counts, checked against the limits.
+#[derive(Default)]
+struct Budget {
+    entries: usize,
+    bytes: u64,
+}
+
+impl Budget {
+    fn admit(&mut self) -> Result<(), String> {
+        self.entries += 1;
+        if self.entries > MAX_ENTRIES {
+            return Err(format!("Archive has more than {} entries", MAX_ENTRIES));
+        }
+        Ok(())
+    }
+
+    /// Copy `reader` to `writer`, failing once the total passes the limit
+    /// whatever the archive claims the size is.
+    fn copy(&mut self, reader: &mut dyn Read, writer: &mut dyn Write) -> Result<u64, String> {
+        let remaining = MAX_TOTAL_BYTES - self.bytes;
+        let copied = std::io::copy(&mut reader.take(remaining + 1), writer)
+            .map_err(|e| format!("Failed to copy entry: {}", e))?;
+        if copied > remaining {
+            return Err(format!("Archive unpacks to more than {} bytes", MAX_TOTAL_BYTES));
+        }
+        self.bytes += copied;
+        Ok(copied)
+    }
+}
+
+/// What [`extract`] did.
+#[derive(Debug, Default)]
+struct Extracted {
+    files: usize,
+    bytes: u64,
+    /// Files left alone because they already existed.
+    existing: usize,
+    /// Symlinks, hard links and special files, never unpacked.
+    links: usize,
+    /// Entries `[sandbox.paths]` does not let the tool write.
+    refused: usize,
+}
+
+/// Where one entry goes inside `dest` (canonical), or an error if its name
+/// or a symlink already in `dest` would take it outside. `None` if
+/// `writable` refuses the target, before or after resolving symlinks.
+fn target_in(
+    dest: &Path,
+    name: &Path,
+    writable: &dyn Fn(&Path) -> bool,
+) -> Result<Option<PathBuf>, String> {
+    let rel = entry_path(name)
+        .ok_or_else(|| format!("Refusing unsafe path in archive: {}", name.display()))?;
+    let target = dest.join(&rel);
+    if !writable(&target) {
+        return Ok(None);
+    }
+    let (Some(parent), Some(file_name)) = (target.parent(), target.file_name()) else {
+        return Ok(Some(target));
+    };
+    std::fs::create_dir_all(parent)
+        .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    let canon = parent
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", parent.display(), e))?;
+    if !canon.starts_with(dest) {
+        return Err(format!(
+            "Refusing {}: it resolves outside {}",
+            name.display(),
+            dest.display()
+        ));
+    }
+    let target = canon.join(file_name);
+    Ok(writable(&target).then_some(target))
+}
+
+/// Write one regular file entry unless `target` already exists.
+fn write_entry(
+    target: &Path,
+    reader: &mut dyn Read,
+    mode: Option<u32>,
+    budget: &mut Budget,
+    out: &mut Extracted,
+) -> Result<(), String> {
+    // `create_new` also refuses to follow a symlink at `target`.
+    let mut file = match std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)
+    {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            out.existing += 1;
+            return Ok(());
+        }
+        Err(e) => return Err(format!("Failed to create {}: {}", target.display(), e)),
+    };
+    out.bytes += budget.copy(reader, &mut file)?;
+    out.files += 1;
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = file.set_permissions(std::fs::Permissions::from_mode(mode & 0o755));
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    Ok(())
+}
+
+/// Unpack `archive` into `dest`, skipping entries `writable` refuses.
+fn extract(
+    archive: &Path,
+    format: Format,
+    dest: &Path,
+    writable: &dyn Fn(&Path) -> bool,
+) -> Result<Extracted, String> {
+    std::fs::create_dir_all(dest)
+        .map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    let dest = dest
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", dest.display(), e))?;
+    let file = std::fs::File::open(archive)
+        .map_err(|e| format!("Failed to open {}: {}", archive.display(), e))?;
+    let mut budget = Budget::default();
+    let mut out = Extracted::default();
+
+    match format {
+        Format::Zip => {
+            let mut zip = zip::ZipArchive::new(file)
+                .map_err(|e| format!("Not a valid zip archive: {}", e))?;
+            for i in 0..zip.len() {
+                budget.admit()?;
+                let mut entry = zip
+                    .by_index(i)
+                    .map_err(|e| format!("Failed to read zip entry {}: {}", i, e))?;
+                let Some(target) = target_in(&dest, Path::new(entry.name()), writable)? else {
+                    out.refused += 1;
+                    continue;
+                };
+                if entry.is_dir() {
+                    std::fs::create_dir_all(&target)
+                        .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+                } else if entry.is_symlink() {
+                    out.links += 1;
+                } else {
+                    let mode = entry.unix_mode();
+                    write_entry(&target, &mut entry, mode, &mut budget, &mut out)?;
+                }
+            }
+        }
+        Format::Tar | Format::TarGz => {
+            let reader: Box<dyn Read> = if format == Format::TarGz {
+                Box::new(flate2::read::GzDecoder::new(file))
+            } else {
+                Box::new(file)
+            };
+            let mut tar = tar::Archive::new(reader);
+            let entries = tar
+                .entries()
+                .map_err(|e| format!("Not a valid tar archive: {}", e))?;
+            for entry in entries {
+                budget.admit()?;
+                let mut entry = entry.map_err(|e| format!("Failed to read tar entry: {}", e))?;
+                let name = entry
+                    .path()
+                    .map_err(|e| format!("Bad entry name: {}", e))?
+                    .into_owned();
+                let Some(target) = target_in(&dest, &name, writable)? else {
+                    out.refused += 1;
+                    continue;
+                };
+                match entry.header().entry_type() {
+                    tar::EntryType::Directory => std::fs::create_dir_all(&target)
+                        .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?,
+                    tar::EntryType::Regular | tar::EntryType::Continuous => {
+                        let mode = entry.header().mode().ok();
+                        write_entry(&target, &mut entry, mode, &mut budget, &mut out)?;
+                    }
+                    _ => out.links += 1,
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Paths, relative to the destination, of the regular files in `archive`
+/// that [`extract`] would write; unsafe names are left out.
+fn list_files(archive: &Path, format: Format) -> Result<Vec<PathBuf>, String> {
+    let file = std::fs::File::open(archive)
+        .map_err(|e| format!("Failed to open {}: {}", archive.display(), e))?;
+    let mut files = Vec::new();
+    match format {
+        Format::Zip => {
+            let mut zip = zip::ZipArchive::new(file)
+                .map_err(|e| format!("Not a valid zip archive: {}", e))?;
+            for i in 0..zip.len().min(MAX_ENTRIES) {
+                let entry = zip
+                    .by_index_raw(i)
+                    .map_err(|e| format!("Failed to read zip entry {}: {}", i, e))?;
+                if !entry.is_dir() && !entry.is_symlink() {
+                    files.extend(entry_path(Path::new(entry.name())));
+                }
+            }
+        }
+        Format::Tar | Format::TarGz => {
+            let reader: Box<dyn Read> = if format == Format::TarGz {
+                Box::new(flate2::read::GzDecoder::new(file))
+            } else {
+                Box::new(file)
+            };
+            let mut tar = tar::Archive::new(reader);
+            let entries = tar
+                .entries()
+                .map_err(|e| format!("Not a valid tar archive: {}", e))?;
+            for entry in entries.take(MAX_ENTRIES) {
+                let entry = entry.map_err(|e| format!("Failed to read tar entry: {}", e))?;
+                if matches!(
+                    entry.header().entry_type(),
+                    tar::EntryType::Regular | tar::EntryType::Continuous
+                ) {
+                    if let Ok(name) = entry.path() {
+                        files.extend(entry_path(&name));
+                    }
+                }
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Files under `sources` with their names in the archive: a file by its
+/// name, a directory's files under the directory's name. Symlinks, paths
+/// `[sandbox.paths]` denies and the archive being written are left out.
+fn collect_sources(
+    sources: &[PathBuf],
+    output: &Path,
+    denied: &dyn Fn(&Path) -> bool,
+) -> Result<Vec<(PathBuf, String)>, String> {
+    let mut files = Vec::new();
+    let mut bytes = 0u64;
+    for source in sources {
+        let base = source.parent().unwrap_or(Path::new(""));
+        let mut stack = vec![source.clone()];
+        while let Some(path) = stack.pop() {
+            if denied(&path) {
+                continue;
+            }
+            let meta = std::fs::symlink_metadata(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if meta.is_dir() {
+                let entries = std::fs::read_dir(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                for entry in entries.flatten() {
+                    stack.push(entry.path());
+                }
+            } else if meta.is_file() && path != output {
+                bytes += meta.len();
+                let name = path
+                    .strip_prefix(base)
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push((path, name));
+            }
+            if files.len() > MAX_ENTRIES {
+                return Err(format!("Sources hold more than {} files", MAX_ENTRIES));
+            }
+            if bytes > MAX_TOTAL_BYTES {
+                return Err(format!("Sources hold more than {} bytes", MAX_TOTAL_BYTES));
+            }
+        }
+    }
+    files.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(files)
+}
+
+/// Returns the number of files and bytes packed.
+fn create(
+    output: &Path,
+    format: Format,
+    sources: &[PathBuf],
+    denied: &dyn Fn(&Path) -> bool,
+) -> Result<(usize, u64), String> {
+    let files = collect_sources(sources, output, denied)?;
+    if files.is_empty() {
+        return Err("Nothing to archive: the sources hold no files".into());
+    }
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let file = std::fs::File::create(output)
+        .map_err(|e| format!("Failed to create {}: {}", output.display(), e))?;
+    let mut budget = Budget::default();
+    let open = |path: &Path| {
+        std::fs::File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    };
+
+    match format {
+        Format::Zip => {
+            let mut zip = zip::ZipWriter::new(file);
+            for (path, name) in &files {
+                budget.admit()?;
+                let mut options = zip::write::SimpleFileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated)
+                    .large_file(true);
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    if let Ok(meta) = std::fs::metadata(path) {
+                        options = options.unix_permissions(meta.permissions().mode() & 0o755);
+                    }
+                }
+                zip.start_file(name.as_str(), options)
+                    .map_err(|e| format!("Failed to add {}: {}", name, e))?;
+                budget.copy(&mut open(path)?, &mut zip)?;
+            }
+            zip.finish()
+                .map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+        }
+        Format::Tar => {
+            pack_tar(file, &files, &mut budget)?;
+        }
+        Format::TarGz => {
+            let gz = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            pack_tar(gz, &files, &mut budget)?
+                .finish()
+                .map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+        }
+    }
+    Ok((files.len(), budget.bytes))
+}
+
+/// Write `files` as a tar stream to `writer` and return it.
+fn pack_tar<W: Write>(
+    writer: W,
+    files: &[(PathBuf, String)],
+    budget: &mut Budget,
+) -> Result<W, String> {
+    let mut tar = tar::Builder::new(writer);
+    for (path, name) in files {
+        budget.admit()?;
+        budget.bytes += std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        tar.append_path_with_name(path, name)
+            .map_err(|e| format!("Failed to add {}: {}", name, e))?;
+    }
+    tar.into_inner()
+        .map_err(|e| format!("Failed to finish the archive: {}", e))
+}
+
+// ── archive_extract ────────────────────────────────────────────────────
+
+pub struct ArchiveExtractTool {
+    pub workspace_root: Option<PathBuf>,
+    pub path_rules: Arc<PathRules>,
+}
+
+/// The canonical workspace roots `[sandbox.paths]` rules are relative to,
+/// resolved here since the task-local workspace scope does not reach
+/// blocking threads.
+fn rule_roots(workspace_root: &Option<PathBuf>) -> Vec<PathBuf> {
+    workspace::allowed_roots(workspace_root)
+        .iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .collect()
+}
+
+fn tool_err(tool: &str, message: impl Into<String>) -> AgentError {
+    AgentError::ToolExecution {
+        tool_name: tool.into(),
+        message: message.into(),
+    }
+}
+
+impl ArchiveExtractTool {
+    /// The directory to unpack `archive` into: `destination`, or the
+    /// archive's name without its extension, next to it.
+    fn destination(
+        &self,
+        archive: &Path,
+        destination: Option<&str>,
+    ) -> Result<PathBuf, AgentError> {
+        match destination {
+            Some(dest) => validate_write_path(dest, &self.workspace_root, &self.path_rules),
+            None => {
+                let name = archive.file_name().unwrap_or_default().to_string_lossy();
+                let stem = [".tar.gz", ".tgz", ".tar", ".zip"]
+                    .iter()
+                    .find_map(|ext| {
+                        let cut = name.len().checked_sub(ext.len())?;
+                        name[cut..].eq_ignore_ascii_case(ext).then(|| &name[..cut])
+                    })
+                    .filter(|stem| !stem.is_empty())
+                    .unwrap_or("extracted");
+                let dest = archive.with_file_name(stem);
+                validate_write_path(&dest.to_string_lossy(), &self.workspace_root, &self.path_rules)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ArchiveExtractTool {
+    fn name(&self) -> &str {
+        "archive_extract"
+    }
+
+    fn description(&self) -> &str {
+        "Unpack a zip, tar or tar.gz archive into a directory in the workspace. \
+         Existing files are kept, links are skipped, and entries that would land \
+         outside the destination are refused."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The archive to unpack"
+                },
+                "destination": {
+                    "type": "string",
+                    "description": "Directory to unpack into (created if missing). Defaults to the archive's name without its extension, next to it."
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["zip", "tar", "tar.gz"],
+                    "description": "Archive format, when the file name does not show it"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    /// The files the extraction would create. Existing files are kept, so
+    /// removing the new ones undoes it.
+    fn affected_paths(&self, args: &Value) -> Vec<PathBuf> {
+        let arg = |name: &str| args.get(name).and_then(Value::as_str);
+        let Some(archive) = arg("path")
+            .and_then(|raw| validate_path(raw, &self.workspace_root, &self.path_rules).ok())
+        else {
+            return Vec::new();
+        };
+        let Ok(format) = Format::detect(&archive, arg("format")) else {
+            return Vec::new();
+        };
+        let Some(dest) = self
+            .destination(&archive, arg("destination"))
+            .ok()
+            .filter(|dest| dest.is_absolute())
+        else {
+            return Vec::new();
+        };
+        list_files(&archive, format)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|rel| dest.join(rel))
+            .filter(|target| std::fs::symlink_metadata(target).is_err())
+            .collect()
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            path: String,
+            destination: Option<String>,
+            format: Option<String>,
+        }
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| tool_err("archive_extract", format!("Invalid arguments: {}", e)))?;
+
+        let archive = validate_path(&args.path, &self.workspace_root, &self.path_rules)?;
+        let format = Format::detect(&archive, args.format.as_deref())
+            .map_err(|e| tool_err("archive_extract", e))?;
+        let dest = self.destination(&archive, args.destination.as_deref())?;
+
+        let (from, to) = (archive.clone(), dest.clone());
+        let (rules, roots) = (self.path_rules.clone(), rule_roots(&self.workspace_root));
+        let writable = move |path: &Path| rules.check(path, &roots, true).is_ok();
+        let out = tokio::task::spawn_blocking(move || extract(&from, format, &to, &writable))
+            .await
+            .map_err(|e| tool_err("archive_extract", format!("Extraction failed: {}", e)))?
+            .map_err(|e| tool_err("archive_extract", e))?;
+
+        let mut summary = format!(
+            "Extracted {} files ({} bytes) from {} into {}",
+            out.files,
+            out.bytes,
+            args.path,
+            dest.display()
+        );
+        if out.existing > 0 {
+            summary.push_str(&format!("\nKept {} existing files", out.existing));
+        }
+        if out.links > 0 {
+            summary.push_str(&format!("\nSkipped {} links or special files", out.links));
+        }
+        if out.refused > 0 {
+            summary.push_str(&format!(
+                "\nSkipped {} entries that [sandbox.paths] does not allow writing",
+                out.refused
+            ));
+        }
+        Ok(summary)
+    }
+}
+
+// ── archive_create ─────────────────────────────────────────────────────
+
+pub struct ArchiveCreateTool {
+    pub workspace_root: Option<PathBuf>,
+    pub path_rules: Arc<PathRules>,
+}
+
+#[async_trait]
+impl Tool for ArchiveCreateTool {
+    fn name(&self) -> &str {
+        "archive_create"
+    }
+
+    fn description(&self) -> &str {
+        "Bundle files and directories from the workspace into a zip, tar or tar.gz archive. \
+         Directories are added with their contents under their own name; symlinks are left out."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The archive to write, e.g. dist.zip or build.tar.gz"
+                },
+                "sources": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Files and directories to include"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["zip", "tar", "tar.gz"],
+                    "description": "Archive format, when the file name does not show it"
+                }
+            },
+            "required": ["path", "sources"]
+        })
+    }
+
+    fn affected_paths(&self, args: &Value) -> Vec<PathBuf> {
+        args.get("path")
+            .and_then(Value::as_str)
+            .and_then(|raw| validate_path(raw, &self.workspace_root, &self.path_rules).ok())
+            .filter(|path| path.is_absolute())
+            .into_iter()
+            .collect()
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            path: String,
+            sources: Vec<String>,
+            format: Option<String>,
+        }
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| tool_err("archive_create", format!("Invalid arguments: {}", e)))?;
+        if args.sources.is_empty() {
+            return Err(tool_err("archive_create", "Give at least one source"));
+        }
+
+        let output = validate_write_path(&args.path, &self.workspace_root, &self.path_rules)?;
+        let format = Format::detect(&output, args.format.as_deref())
+            .map_err(|e| tool_err("archive_create", e))?;
+        let sources = args
+            .sources
+            .iter()
+            .map(|source| validate_path(source, &self.workspace_root, &self.path_rules))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let to = output.clone();
+        let (rules, roots) = (self.path_rules.clone(), rule_roots(&self.workspace_root));
+        let denied = move |path: &Path| rules.is_denied(path, &roots);
+        let (files, bytes) =
+            tokio::task::spawn_blocking(move || create(&to, format, &sources, &denied))
+                .await
+                .map_err(|e| tool_err("archive_create", format!("Archiving failed: {}", e)))?
+                .map_err(|e| tool_err("archive_create", e))?;
+
+        let size = std::fs::metadata(&output).map(|m| m.len()).unwrap_or(0);
+        Ok(format!(
+            "Created {} ({}, {} files, {} bytes packed into {})",
+            args.path,
+            format.label(),
+            files,
+            bytes,
+            size
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_create_then_extract_round_trip() {
+        let tmp = TempDir::new().unwrap();
+        let ws = tmp.path().to_path_buf();
+        std::fs::create_dir_all(ws.join("dist/assets")).unwrap();
+        std::fs::write(ws.join("dist/index.html"), "<html>").unwrap();
+        std::fs::write(ws.join("dist/assets/app.js"), "console.log(1)").unwrap();
+
+        let create = ArchiveCreateTool {
+            workspace_root: Some(ws.clone()),
+            path_rules: Default::default(),
+        };
+        let extract = ArchiveExtractTool {
+            workspace_root: Some(ws.clone()),
+            path_rules: Default::default(),
+        };
+        for name in ["bundle.zip", "bundle.tar.gz"] {
+            let archive = ws.join(name);
+            let out = create
+                .execute(json!({"path": archive, "sources": [ws.join("dist")]}))
+                .await
+                .unwrap();
+            assert!(out.contains("2 files"), "got: {out}");
+
+            let dest = ws.join(format!("out-{name}"));
+            let out = extract
+                .execute(json!({"path": archive, "destination": dest}))
+                .await
+                .unwrap();
+            assert!(out.contains("Extracted 2 files"), "got: {out}");
+            let js = std::fs::read_to_string(dest.join("dist/assets/app.js")).unwrap();
+            assert_eq!(js, "console.log(1)");
+
+            // Unpacking again keeps what is there.
+            let again = extract
+                .execute(json!({"path": archive, "destination": dest}))
+                .await
+                .unwrap();
+            assert!(again.contains("Kept 2 existing files"), "got: {again}");
+        }
+
+        let err = create
+            .execute(json!({"path": "/tmp/evil.zip", "sources": [ws.join("dist")]}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("outside the workspace root"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_extract_refuses_zip_slip() {
+        let tmp = TempDir::new().unwrap();
+        let ws = tmp.path().join("ws");
+        std::fs::create_dir(&ws).unwrap();
+
+        // Tar entries are written raw so the `..` name survives.
+        let mut data = Vec::new();
+        {
+            let mut tar = tar::Builder::new(&mut data);
+            let mut header = tar::Header::new_gnu();
+            let name = b"../escaped.txt";
+            header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name);
+            header.set_size(4);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append(&header, &b"evil"[..]).unwrap();
+            tar.finish().unwrap();
+        }
+        std::fs::write(ws.join("slip.tar"), data).unwrap();
+
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(ws.join("slip.zip")).unwrap());
+        zip.start_file("/etc/escaped.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"evil").unwrap();
+        zip.finish().unwrap();
+
+        let tool = ArchiveExtractTool {
+            workspace_root: Some(ws.clone()),
+            path_rules: Default::default(),
+        };
+        for name in ["slip.tar", "slip.zip"] {
+            let err = tool
+                .execute(json!({"path": ws.join(name), "destination": ws.join("out")}))
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("unsafe path"), "{name}: {err}");
+        }
+        assert!(!tmp.path().join("escaped.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_path_rules_apply_to_entries() {
+        use agent_core::config::PathAccess;
+
+        let tmp = TempDir::new().unwrap();
+        let ws = tmp.path().canonicalize().unwrap();
+        std::fs::create_dir_all(ws.join("src/secrets")).unwrap();
+        std::fs::write(ws.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(ws.join("src/secrets/key"), "hunter2").unwrap();
+        let rules = Arc::new(PathRules::new(&std::collections::BTreeMap::from([
+            ("src/secrets/**".to_string(), PathAccess::Deny),
+            ("out/src/secrets/**".to_string(), PathAccess::Deny),
+        ])));
+
+        // Denied files are left out of a new archive.
+        let out = ArchiveCreateTool {
+            workspace_root: Some(ws.clone()),
+            path_rules: rules.clone(),
+        }
+        .execute(json!({"path": ws.join("src.tar"), "sources": [ws.join("src")]}))
+        .await
+        .unwrap();
+        assert!(out.contains("1 files"), "got: {out}");
+
+        // And entries landing on denied paths are not unpacked.
+        ArchiveCreateTool {
+            workspace_root: Some(ws.clone()),
+            path_rules: Default::default(),
+        }
+        .execute(json!({"path": ws.join("all.tar"), "sources": [ws.join("src")]}))
+        .await
+        .unwrap();
+        let out = ArchiveExtractTool {
+            workspace_root: Some(ws.clone()),
+            path_rules: rules,
+        }
+        .execute(json!({"path": ws.join("all.tar"), "destination": ws.join("out")}))
+        .await
+        .unwrap();
+        assert!(out.contains("Skipped 1 entries"), "got: {out}");
+        assert!(ws.join("out/src/main.rs").exists());
+        assert!(!ws.join("out/src/secrets/key").exists());
+    }
+
+    #[tokio::test]
+    async fn test_rollback_undoes_extraction() {
+        use agent_core::checkpoint::{FileSnapshot, SnapshotStore};
+        use agent_core::session::Session;
+
+        let tmp = TempDir::new().unwrap();
+        let ws = tmp.path().canonicalize().unwrap();
+        std::fs::create_dir_all(ws.join("src")).unwrap();
+        std::fs::write(ws.join("src/a.txt"), "a").unwrap();
+        std::fs::write(ws.join("src/b.txt"), "b").unwrap();
+        ArchiveCreateTool {
+            workspace_root: Some(ws.clone()),
+            path_rules: Default::default(),
+        }
+        .execute(json!({"path": ws.join("src.tar"), "sources": [ws.join("src")]}))
+        .await
+        .unwrap();
+        // One file is already in place and is kept by the extraction.
+        std::fs::create_dir_all(ws.join("out/src")).unwrap();
+        std::fs::write(ws.join("out/src/a.txt"), "mine").unwrap();
+
+        let tool = ArchiveExtractTool {
+            workspace_root: Some(ws.clone()),
+            path_rules: Default::default(),
+        };
+        let args = json!({"path": ws.join("src.tar"), "destination": ws.join("out")});
+        let affected = tool.affected_paths(&args);
+        assert_eq!(affected, [ws.join("out/src/b.txt")]);
+
+        let store = SnapshotStore::new(tmp.path().join("shadow"));
+        let mut session = Session::new("extract");
+        session.auto_checkpoint();
+        let snapshots = affected
+            .iter()
+            .flat_map(|p| FileSnapshot::capture(p))
+            .collect();
+        session.record_snapshots(snapshots, &store).unwrap();
+        tool.execute(args).await.unwrap();
+        assert!(ws.join("out/src/b.txt").exists());
+
+        session.undo(true, &store).unwrap();
+        assert!(!ws.join("out/src/b.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(ws.join("out/src/a.txt")).unwrap(),
+            "mine"
+        );
+    }
+
+    #[test]
+    fn test_format_detection() {
+        assert_eq!(Format::detect(Path::new("a.TGZ"), None), Ok(Format::TarGz));
+        assert_eq!(Format::detect(Path::new("a.bin"), Some("zip")), Ok(Format::Zip));
+        assert!(Format::detect(Path::new("a.rar"), None).is_err());
+        assert_eq!(entry_path(Path::new("./a/b")), Some(PathBuf::from("a/b")));
+        assert_eq!(entry_path(Path::new("a/../../b")), None);
+    }
+}
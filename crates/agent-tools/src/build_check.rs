@@ -0,0 +1,343 @@
+//! Build/lint tool.
+//!
+//! Runs the compiler or linter for the detected environment (cargo check or
+//! clippy, tsc, ruff) in the sandbox and returns parsed diagnostics — path,
+//! line, column, severity and message — rather than raw output.
+
+use crate::env_detect::detect_environments;
+use crate::sandbox::{shell_quote, ExecResult, SandboxExecutor};
+use agent_core::error::AgentError;
+use agent_core::tool_registry::Tool;
+use agent_core::workspace;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// At most this many diagnostics are listed in the summary.
+const MAX_DIAGNOSTICS: usize = 50;
+/// Output lines shown when a check fails without parseable diagnostics.
+const TAIL_LINES: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Checker {
+    Cargo,
+    Clippy,
+    Tsc,
+    Ruff,
+}
+
+impl Checker {
+    /// Pick a checker for `dir`; `lint` selects clippy over cargo check.
+    pub fn detect(dir: &Path, lint: bool) -> Option<Self> {
+        let envs = detect_environments(dir);
+        let has = |prefix: &str| envs.iter().any(|e| e.env_type.starts_with(prefix));
+        if has("rust") {
+            Some(if lint { Self::Clippy } else { Self::Cargo })
+        } else if has("node") && dir.join("tsconfig.json").exists() {
+            Some(Self::Tsc)
+        } else if has("python")
+            || ["pyproject.toml", "setup.py", "ruff.toml", "requirements.txt"]
+                .iter()
+                .any(|f| dir.join(f).exists())
+        {
+            Some(Self::Ruff)
+        } else {
+            None
+        }
+    }
+
+    fn command(self, dir: &Path) -> String {
+        let cmd = match self {
+            Self::Cargo => "cargo check --all-targets --message-format=short",
+            Self::Clippy => "cargo clippy --all-targets --message-format=short",
+            Self::Tsc => "npx --no-install tsc --noEmit --pretty false",
+            Self::Ruff => "ruff check --output-format=concise",
+        };
+        format!("cd {} && {}", shell_quote(&dir.to_string_lossy()), cmd)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo check",
+            Self::Clippy => "cargo clippy",
+            Self::Tsc => "tsc",
+            Self::Ruff => "ruff",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub path: String,
+    pub line: u32,
+    pub column: u32,
+    /// `error` or `warning`; ruff reports everything as `warning`.
+    pub severity: String,
+    pub message: String,
+}
+
+/// Parse one line of checker output.
+///
+/// - rustc short: `src/lib.rs:3:5: error[E0308]: mismatched types`
+/// - tsc: `src/app.ts(3,5): error TS2322: Type 'string' is not ...`
+/// - ruff concise: `app.py:1:8: F401 [*] `os` imported but unused`
+pub fn parse_diagnostic(checker: Checker, line: &str) -> Option<Diagnostic> {
+    match checker {
+        Checker::Cargo | Checker::Clippy | Checker::Ruff => {
+            let mut parts = line.splitn(4, ':');
+            let path = parts.next()?.trim();
+            let line_no = parts.next()?.trim().parse().ok()?;
+            let column = parts.next()?.trim().parse().ok()?;
+            let rest = parts.next()?.trim();
+            let (severity, message) = if checker == Checker::Ruff {
+                ("warning".to_string(), rest.to_string())
+            } else {
+                let (kind, message) = rest.split_once(": ")?;
+                let severity = if kind.starts_with("error") {
+                    "error"
+                } else if kind.starts_with("warning") {
+                    "warning"
+                } else {
+                    return None;
+                };
+                // Keep the lint/error code: "error[E0308]: msg" -> "E0308: msg".
+                let message = match kind.find('[') {
+                    Some(i) => format!("{}: {}", kind[i + 1..].trim_end_matches(']'), message),
+                    None => message.to_string(),
+                };
+                (severity.to_string(), message)
+            };
+            Some(Diagnostic {
+                path: path.to_string(),
+                line: line_no,
+                column,
+                severity,
+                message,
+            })
+        }
+        Checker::Tsc => {
+            let (path, rest) = line.split_once('(')?;
+            let (position, rest) = rest.split_once("): ")?;
+            let (line_no, column) = position.split_once(',')?;
+            let (severity, message) = rest.split_once(' ')?;
+            if severity != "error" && severity != "warning" {
+                return None;
+            }
+            Some(Diagnostic {
+                path: path.to_string(),
+                line: line_no.parse().ok()?,
+                column: column.parse().ok()?,
+                severity: severity.to_string(),
+                message: message.to_string(),
+            })
+        }
+    }
+}
+
+/// All diagnostics in a run's output, without duplicates (cargo repeats
+/// diagnostics shared between targets).
+pub fn parse_diagnostics(checker: Checker, result: &ExecResult) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for line in result.stdout.lines().chain(result.stderr.lines()) {
+        if let Some(d) = parse_diagnostic(checker, line) {
+            if !diagnostics.contains(&d) {
+                diagnostics.push(d);
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Compact summary for the model.
+fn summarize(checker: Checker, result: &ExecResult, diagnostics: &[Diagnostic]) -> String {
+    let errors = diagnostics.iter().filter(|d| d.severity == "error").count();
+    let warnings = diagnostics.len() - errors;
+    let status = if result.exit_code == 0 { "passed" } else { "FAILED" };
+    let mut out = format!(
+        "{} {}: {} errors, {} warnings (exit code {})\n",
+        checker.label(),
+        status,
+        errors,
+        warnings,
+        result.exit_code
+    );
+    // Errors first: they are what blocks the build.
+    let mut sorted: Vec<&Diagnostic> = diagnostics.iter().collect();
+    sorted.sort_by_key(|d| d.severity != "error");
+    for d in sorted.iter().take(MAX_DIAGNOSTICS) {
+        out.push_str(&format!(
+            "{}:{}:{} {} {}\n",
+            d.path, d.line, d.column, d.severity, d.message
+        ));
+    }
+    if diagnostics.len() > MAX_DIAGNOSTICS {
+        out.push_str(&format!(
+            "... and {} more\n",
+            diagnostics.len() - MAX_DIAGNOSTICS
+        ));
+    }
+    if result.exit_code != 0 && diagnostics.is_empty() {
+        out.push_str("\nOutput (last lines):\n");
+        out.push_str(&result.tail(TAIL_LINES));
+    }
+    out
+}
+
+/// Run the project's compiler or linter and report diagnostics.
+pub struct BuildCheckTool {
+    executor: Arc<SandboxExecutor>,
+    workspace_root: Option<PathBuf>,
+}
+
+impl BuildCheckTool {
+    pub fn new(executor: Arc<SandboxExecutor>, workspace_root: Option<PathBuf>) -> Self {
+        Self {
+            executor,
+            workspace_root,
+        }
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        AgentError::ToolExecution {
+            tool_name: "build_check".into(),
+            message: msg.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for BuildCheckTool {
+    fn name(&self) -> &str {
+        "build_check"
+    }
+
+    fn description(&self) -> &str {
+        "Type-check or lint the project (cargo check/clippy, tsc or ruff, detected \
+         automatically) and return diagnostics as `path:line:column severity message`. \
+         Run it after editing code to find compile errors without reading raw build logs."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "directory": {
+                    "type": "string",
+                    "description": "Project directory (defaults to the workspace root or current directory)"
+                },
+                "lint": {
+                    "type": "boolean",
+                    "description": "For Rust, run clippy instead of cargo check. Default: false"
+                },
+                "checker": {
+                    "type": "string",
+                    "enum": ["cargo", "clippy", "tsc", "ruff"],
+                    "description": "Override checker detection"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            directory: Option<String>,
+            #[serde(default)]
+            lint: bool,
+            checker: Option<Checker>,
+        }
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| Self::err(format!("Invalid arguments: {}", e)))?;
+
+        let dir = match (args.directory, workspace::primary_root(&self.workspace_root)) {
+            (Some(dir), _) => crate::file_ops::validate_path(
+                &dir,
+                &self.workspace_root,
+                self.executor.path_rules(),
+            )?,
+            (None, Some(root)) => root,
+            (None, None) => std::env::current_dir()
+                .map_err(|e| Self::err(format!("No current directory: {}", e)))?,
+        };
+        if !dir.is_dir() {
+            return Err(Self::err(format!("Not a directory: {}", dir.display())));
+        }
+        let checker = match args.checker.or_else(|| Checker::detect(&dir, args.lint)) {
+            Some(checker) => checker,
+            None => {
+                return Err(Self::err(format!(
+                    "No checker detected in {} (looked for Cargo.toml, tsconfig.json, Python project files)",
+                    dir.display()
+                )))
+            }
+        };
+
+        let result = self.executor.exec_shell(&checker.command(&dir)).await?;
+        let diagnostics = parse_diagnostics(checker, &result);
+        Ok(summarize(checker, &result, &diagnostics))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_checker() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+        assert_eq!(Checker::detect(dir.path(), false), None);
+        std::fs::write(dir.path().join("tsconfig.json"), "{}").unwrap();
+        assert_eq!(Checker::detect(dir.path(), false), Some(Checker::Tsc));
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        assert_eq!(Checker::detect(dir.path(), true), Some(Checker::Clippy));
+    }
+
+    #[test]
+    fn test_parse_diagnostics() {
+        let result = ExecResult {
+            stdout: String::new(),
+            stderr: "\
+    Checking demo v0.1.0
+src/lib.rs:3:5: error[E0308]: mismatched types
+src/lib.rs:9:9: warning: unused variable: `x`
+src/lib.rs:3:5: error[E0308]: mismatched types
+error: could not compile `demo` (lib) due to 1 previous error
+"
+            .into(),
+            exit_code: 101,
+        };
+        let diagnostics = parse_diagnostics(Checker::Cargo, &result);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0],
+            Diagnostic {
+                path: "src/lib.rs".into(),
+                line: 3,
+                column: 5,
+                severity: "error".into(),
+                message: "E0308: mismatched types".into(),
+            }
+        );
+        let summary = summarize(Checker::Cargo, &result, &diagnostics);
+        assert!(summary.starts_with("cargo check FAILED: 1 errors, 1 warnings (exit code 101)\n"));
+        assert!(summary.contains("src/lib.rs:9:9 warning unused variable: `x`"));
+
+        let tsc = parse_diagnostic(
+            Checker::Tsc,
+            "src/app.ts(12,7): error TS2322: Type 'string' is not assignable to type 'number'.",
+        )
+        .unwrap();
+        assert_eq!((tsc.line, tsc.column), (12, 7));
+        assert_eq!(tsc.message, "TS2322: Type 'string' is not assignable to type 'number'.");
+
+        let ruff = parse_diagnostic(Checker::Ruff, "app.py:1:8: F401 [*] `os` imported but unused")
+            .unwrap();
+        assert_eq!(ruff.path, "app.py");
+        assert_eq!(ruff.message, "F401 [*] `os` imported but unused");
+        assert!(parse_diagnostic(Checker::Ruff, "Found 1 error.").is_none());
+    }
+}
@@ -0,0 +1,604 @@
+//! Deterministic calculator.
+//!
+//! Evaluates arithmetic (`(3 + 4) * 2^10`, `sqrt(2)`), unit conversions
+//! (`5 km to mi`, `98.6 F to C`, `3 GiB in MB`) and date math
+//! (`2026-10-15 + 30 days`, `2026-12-25 - today`) in-process, so trivial
+//! computations neither rely on the model's arithmetic nor start a sandbox.
+
+use agent_core::error::AgentError;
+use agent_core::tool_registry::Tool;
+use async_trait::async_trait;
+use chrono::{Duration, Months, NaiveDate};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+// ── Arithmetic ─────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(char),
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '_') {
+                i += 1;
+            }
+            // Exponent: 1e3, 2.5E-4
+            if i + 1 < chars.len()
+                && (chars[i] == 'e' || chars[i] == 'E')
+                && (chars[i + 1].is_ascii_digit()
+                    || (matches!(chars[i + 1], '+' | '-')
+                        && chars.get(i + 2).is_some_and(|c| c.is_ascii_digit())))
+            {
+                i += 2;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+            let value = text
+                .parse()
+                .map_err(|_| format!("invalid number '{}'", text))?;
+            tokens.push(Token::Num(value));
+        } else if c.is_alphabetic() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect::<String>().to_lowercase()));
+        } else if "+-*/%^(),".contains(c) {
+            tokens.push(Token::Op(c));
+            i += 1;
+        } else if c == '×' {
+            tokens.push(Token::Op('*'));
+            i += 1;
+        } else if c == '÷' {
+            tokens.push(Token::Op('/'));
+            i += 1;
+        } else {
+            return Err(format!("unexpected character '{}'", c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                let rhs = self.unary()?;
+                if rhs == 0.0 {
+                    return Err("division by zero".into());
+                }
+                value /= rhs;
+            } else if self.eat('%') {
+                let rhs = self.unary()?;
+                if rhs == 0.0 {
+                    return Err("division by zero".into());
+                }
+                value %= rhs;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<f64, String> {
+        if self.eat('-') {
+            Ok(-self.unary()?)
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<f64, String> {
+        let base = self.primary()?;
+        if self.eat('^') {
+            // Right-associative, binds tighter than unary minus on the left.
+            Ok(base.powf(self.unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self) -> Result<f64, String> {
+        match self.tokens.get(self.pos).cloned() {
+            Some(Token::Num(n)) => {
+                self.pos += 1;
+                Ok(n)
+            }
+            Some(Token::Op('(')) => {
+                self.pos += 1;
+                let value = self.expr()?;
+                if !self.eat(')') {
+                    return Err("missing ')'".into());
+                }
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                if self.eat('(') {
+                    let mut args = vec![self.expr()?];
+                    while self.eat(',') {
+                        args.push(self.expr()?);
+                    }
+                    if !self.eat(')') {
+                        return Err("missing ')'".into());
+                    }
+                    call(&name, &args)
+                } else {
+                    match name.as_str() {
+                        "pi" => Ok(std::f64::consts::PI),
+                        "e" => Ok(std::f64::consts::E),
+                        "tau" => Ok(std::f64::consts::TAU),
+                        _ => Err(format!("unknown name '{}'", name)),
+                    }
+                }
+            }
+            Some(Token::Op(c)) => Err(format!("unexpected '{}'", c)),
+            None => Err("unexpected end of expression".into()),
+        }
+    }
+}
+
+fn call(name: &str, args: &[f64]) -> Result<f64, String> {
+    let one = |f: fn(f64) -> f64| match args {
+        [x] => Ok(f(*x)),
+        _ => Err(format!("{}() takes one argument", name)),
+    };
+    match name {
+        "sqrt" => one(f64::sqrt),
+        "cbrt" => one(f64::cbrt),
+        "abs" => one(f64::abs),
+        "ln" => one(f64::ln),
+        "log" | "log10" => one(f64::log10),
+        "log2" => one(f64::log2),
+        "exp" => one(f64::exp),
+        "sin" => one(f64::sin),
+        "cos" => one(f64::cos),
+        "tan" => one(f64::tan),
+        "asin" => one(f64::asin),
+        "acos" => one(f64::acos),
+        "atan" => one(f64::atan),
+        "floor" => one(f64::floor),
+        "ceil" => one(f64::ceil),
+        "round" => one(f64::round),
+        "min" | "max" if !args.is_empty() => {
+            let pick = if name == "min" { f64::min } else { f64::max };
+            Ok(args.iter().copied().fold(args[0], pick))
+        }
+        _ => Err(format!("unknown function '{}'", name)),
+    }
+}
+
+/// Evaluate an arithmetic expression.
+pub fn eval_arithmetic(expr: &str) -> Result<f64, String> {
+    let mut parser = Parser {
+        tokens: tokenize(expr)?,
+        pos: 0,
+    };
+    let value = parser.expr()?;
+    if let Some(token) = parser.peek() {
+        return Err(format!("unexpected {:?}", token));
+    }
+    if !value.is_finite() {
+        return Err("result is not a finite number".into());
+    }
+    Ok(value)
+}
+
+/// Integers without a fraction, everything else to 10 significant digits.
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    let digits = 10 - (value.abs().log10().floor() as i32 + 1);
+    if !(0..=15).contains(&digits) {
+        return format!("{:.6e}", value);
+    }
+    let text = format!("{:.*}", digits as usize, value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+// ── Units ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+    Time,
+    Data,
+    Volume,
+    Speed,
+    Area,
+    Temperature,
+}
+
+/// (names, dimension, size in the dimension's base unit). Temperatures are
+/// handled separately because they have offsets.
+const UNITS: &[(&[&str], Dimension, f64)] = &[
+    (&["mm", "millimeter", "millimeters"], Dimension::Length, 0.001),
+    (&["cm", "centimeter", "centimeters"], Dimension::Length, 0.01),
+    (&["m", "meter", "meters", "metre", "metres"], Dimension::Length, 1.0),
+    (&["km", "kilometer", "kilometers"], Dimension::Length, 1000.0),
+    (&["in", "inch", "inches"], Dimension::Length, 0.0254),
+    (&["ft", "foot", "feet"], Dimension::Length, 0.3048),
+    (&["yd", "yard", "yards"], Dimension::Length, 0.9144),
+    (&["mi", "mile", "miles"], Dimension::Length, 1609.344),
+    (&["nmi", "nautical mile", "nautical miles"], Dimension::Length, 1852.0),
+    (&["mg", "milligram", "milligrams"], Dimension::Mass, 0.001),
+    (&["g", "gram", "grams"], Dimension::Mass, 1.0),
+    (&["kg", "kilogram", "kilograms"], Dimension::Mass, 1000.0),
+    (&["t", "tonne", "tonnes"], Dimension::Mass, 1_000_000.0),
+    (&["oz", "ounce", "ounces"], Dimension::Mass, 28.349523125),
+    (&["lb", "lbs", "pound", "pounds"], Dimension::Mass, 453.59237),
+    (&["ns", "nanosecond", "nanoseconds"], Dimension::Time, 1e-9),
+    (&["us", "µs", "microsecond", "microseconds"], Dimension::Time, 1e-6),
+    (&["ms", "millisecond", "milliseconds"], Dimension::Time, 0.001),
+    (&["s", "sec", "secs", "second", "seconds"], Dimension::Time, 1.0),
+    (&["min", "mins", "minute", "minutes"], Dimension::Time, 60.0),
+    (&["h", "hr", "hrs", "hour", "hours"], Dimension::Time, 3600.0),
+    (&["d", "day", "days"], Dimension::Time, 86400.0),
+    (&["wk", "week", "weeks"], Dimension::Time, 604_800.0),
+    (&["yr", "year", "years"], Dimension::Time, 31_557_600.0),
+    (&["bit", "bits"], Dimension::Data, 0.125),
+    (&["B", "byte", "bytes"], Dimension::Data, 1.0),
+    (&["KB", "kB"], Dimension::Data, 1e3),
+    (&["MB"], Dimension::Data, 1e6),
+    (&["GB"], Dimension::Data, 1e9),
+    (&["TB"], Dimension::Data, 1e12),
+    (&["KiB"], Dimension::Data, 1024.0),
+    (&["MiB"], Dimension::Data, 1_048_576.0),
+    (&["GiB"], Dimension::Data, 1_073_741_824.0),
+    (&["TiB"], Dimension::Data, 1_099_511_627_776.0),
+    (&["ml", "mL", "milliliter", "milliliters"], Dimension::Volume, 0.001),
+    (&["l", "L", "liter", "liters", "litre", "litres"], Dimension::Volume, 1.0),
+    (&["gal", "gallon", "gallons"], Dimension::Volume, 3.785411784),
+    (&["qt", "quart", "quarts"], Dimension::Volume, 0.946352946),
+    (&["cup", "cups"], Dimension::Volume, 0.2365882365),
+    (&["floz", "fl oz"], Dimension::Volume, 0.0295735295625),
+    (&["m/s"], Dimension::Speed, 1.0),
+    (&["km/h", "kph"], Dimension::Speed, 1000.0 / 3600.0),
+    (&["mph"], Dimension::Speed, 1609.344 / 3600.0),
+    (&["knot", "knots", "kn"], Dimension::Speed, 1852.0 / 3600.0),
+    (&["m2", "sqm"], Dimension::Area, 1.0),
+    (&["km2"], Dimension::Area, 1e6),
+    (&["ft2", "sqft"], Dimension::Area, 0.09290304),
+    (&["acre", "acres"], Dimension::Area, 4046.8564224),
+    (&["ha", "hectare", "hectares"], Dimension::Area, 10_000.0),
+];
+
+const TEMPERATURES: &[(&[&str], char)] = &[
+    (&["C", "°C", "celsius"], 'C'),
+    (&["F", "°F", "fahrenheit"], 'F'),
+    (&["K", "kelvin"], 'K'),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Scaled(Dimension, f64),
+    Temperature(char),
+}
+
+impl Unit {
+    fn dimension(self) -> Dimension {
+        match self {
+            Self::Scaled(d, _) => d,
+            Self::Temperature(_) => Dimension::Temperature,
+        }
+    }
+}
+
+/// Look a unit up, exactly first and then ignoring case.
+fn find_unit(name: &str) -> Option<Unit> {
+    let name = name.trim();
+    let exact = |n: &str, candidate: &str| n == candidate;
+    let loose = |n: &str, candidate: &str| n.eq_ignore_ascii_case(candidate);
+    for matches in [&exact as &dyn Fn(&str, &str) -> bool, &loose] {
+        if let Some((_, dim, factor)) = UNITS.iter().find(|(names, _, _)| names.iter().any(|c| matches(name, c))) {
+            return Some(Unit::Scaled(*dim, *factor));
+        }
+        if let Some((_, t)) = TEMPERATURES.iter().find(|(names, _)| names.iter().any(|c| matches(name, c))) {
+            return Some(Unit::Temperature(*t));
+        }
+    }
+    None
+}
+
+fn to_kelvin(value: f64, scale: char) -> f64 {
+    match scale {
+        'C' => value + 273.15,
+        'F' => (value - 32.0) * 5.0 / 9.0 + 273.15,
+        _ => value,
+    }
+}
+
+fn from_kelvin(value: f64, scale: char) -> f64 {
+    match scale {
+        'C' => value - 273.15,
+        'F' => (value - 273.15) * 9.0 / 5.0 + 32.0,
+        _ => value,
+    }
+}
+
+/// Split `"<expression> <unit>"` into its parts, e.g. `"(2 + 3) km"`.
+fn split_quantity(text: &str) -> Option<(&str, Unit)> {
+    let text = text.trim();
+    // Longest unit suffix wins ("fl oz" over "oz", "km/h" over "h").
+    let mut best: Option<(usize, Unit)> = None;
+    for (i, _) in text.char_indices() {
+        let (number, unit) = text.split_at(i);
+        let number = number.trim_end();
+        let boundary = number
+            .chars()
+            .last()
+            .is_some_and(|c| c.is_ascii_digit() || c == ')' || c == '.');
+        if boundary {
+            if let Some(u) = find_unit(unit) {
+                best = Some((number.len(), u));
+                break;
+            }
+        }
+    }
+    best.map(|(end, unit)| (&text[..end], unit))
+}
+
+/// Convert `"5 km to mi"`-style requests.
+pub fn convert(from: &str, to: &str) -> Result<String, String> {
+    let from = from.trim();
+    let (quantity, from_unit) =
+        split_quantity(from).ok_or_else(|| format!("no known unit in '{}'", from.trim()))?;
+    let to_unit = find_unit(to).ok_or_else(|| format!("unknown unit '{}'", to.trim()))?;
+    if from_unit.dimension() != to_unit.dimension() {
+        return Err(format!(
+            "cannot convert {:?} to {:?}",
+            from_unit.dimension(),
+            to_unit.dimension()
+        ));
+    }
+    let value = eval_arithmetic(quantity)?;
+    let result = match (from_unit, to_unit) {
+        (Unit::Scaled(_, a), Unit::Scaled(_, b)) => value * a / b,
+        (Unit::Temperature(a), Unit::Temperature(b)) => from_kelvin(to_kelvin(value, a), b),
+        _ => unreachable!("dimensions checked above"),
+    };
+    Ok(format!(
+        "{} {} = {} {}",
+        format_number(value),
+        from[quantity.len()..].trim(),
+        format_number(result),
+        to.trim()
+    ))
+}
+
+// ── Dates ──────────────────────────────────────────────────────────────
+
+fn parse_date(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match text.trim().to_lowercase().as_str() {
+        "today" | "now" => Some(today),
+        "tomorrow" => today.succ_opt(),
+        "yesterday" => today.pred_opt(),
+        other => NaiveDate::parse_from_str(other, "%Y-%m-%d").ok(),
+    }
+}
+
+fn format_date(date: NaiveDate) -> String {
+    format!("{} ({})", date.format("%Y-%m-%d"), date.format("%A"))
+}
+
+/// Date math: `DATE ± N unit [± N unit ...]` or `DATE - DATE`.
+pub fn eval_date(expr: &str, today: NaiveDate) -> Option<Result<String, String>> {
+    // Operators need surrounding spaces so the dashes inside dates survive.
+    let mut parts = expr.split(" + ").flat_map(|p| {
+        let mut pieces: Vec<(char, &str)> = Vec::new();
+        for (i, piece) in p.split(" - ").enumerate() {
+            pieces.push((if i == 0 { '+' } else { '-' }, piece));
+        }
+        pieces
+    });
+    let (_, first) = parts.next()?;
+    let mut date = parse_date(first, today)?;
+
+    let rest: Vec<(char, &str)> = parts.collect();
+    if let [('-', other)] = rest.as_slice() {
+        if let Some(other) = parse_date(other, today) {
+            return Some(Ok(format!("{} days", (date - other).num_days())));
+        }
+    }
+    for (sign, part) in rest {
+        let mut words = part.split_whitespace();
+        let (Some(count), Some(unit), None) = (words.next(), words.next(), words.next()) else {
+            return Some(Err(format!("expected '<number> <unit>', got '{}'", part.trim())));
+        };
+        let Ok(count) = count.parse::<i64>() else {
+            return Some(Err(format!("'{}' is not a whole number", count)));
+        };
+        let count = if sign == '-' { -count } else { count };
+        let unit = unit.to_lowercase();
+        let next = match unit.trim_end_matches('s') {
+            "day" | "d" => date.checked_add_signed(Duration::days(count)),
+            "week" | "wk" | "w" => date.checked_add_signed(Duration::weeks(count)),
+            "month" | "mo" => add_months(date, count),
+            "year" | "yr" | "y" => add_months(date, count * 12),
+            _ => return Some(Err(format!("unknown date unit '{}'", unit))),
+        };
+        match next {
+            Some(next) => date = next,
+            None => return Some(Err("date out of range".into())),
+        }
+    }
+    Some(Ok(format_date(date)))
+}
+
+/// Add calendar months, clamping to the end of shorter months.
+fn add_months(date: NaiveDate, months: i64) -> Option<NaiveDate> {
+    let magnitude = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+    if months >= 0 {
+        date.checked_add_months(magnitude)
+    } else {
+        date.checked_sub_months(magnitude)
+    }
+}
+
+// ── Entry point ────────────────────────────────────────────────────────
+
+/// Evaluate any supported expression; `today` anchors relative dates.
+pub fn evaluate(expr: &str, today: NaiveDate) -> Result<String, String> {
+    let expr = expr.trim();
+    if let Some(result) = eval_date(expr, today) {
+        return result;
+    }
+    for keyword in [" to ", " in ", " as "] {
+        if let Some(i) = expr.to_lowercase().rfind(keyword) {
+            let (from, to) = (&expr[..i], &expr[i + keyword.len()..]);
+            if find_unit(to).is_some() {
+                return convert(from, to);
+            }
+        }
+    }
+    eval_arithmetic(expr).map(format_number)
+}
+
+/// Evaluate arithmetic, unit conversions and date math.
+pub struct CalcTool;
+
+#[async_trait]
+impl Tool for CalcTool {
+    fn name(&self) -> &str {
+        "calc"
+    }
+
+    fn description(&self) -> &str {
+        "Evaluate a calculation exactly instead of doing it in your head: arithmetic \
+         (`(1.5e3 + 250) * 12 / 7`, `sqrt(2)`, `2^32 % 1000`), unit conversions \
+         (`65 mph to km/h`, `98.6 F to C`, `3 GiB in MB`) and date math \
+         (`2026-10-15 + 90 days`, `2026-12-25 - today`)."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": {
+                    "type": "string",
+                    "description": "The expression to evaluate"
+                }
+            },
+            "required": ["expression"]
+        })
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            expression: String,
+        }
+        let err = |message: String| AgentError::ToolExecution {
+            tool_name: "calc".into(),
+            message,
+        };
+        let args: Args =
+            serde_json::from_value(args).map_err(|e| err(format!("Invalid arguments: {}", e)))?;
+        let today = chrono::Local::now().date_naive();
+        evaluate(&args.expression, today).map_err(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 15).unwrap()
+    }
+
+    fn eval(expr: &str) -> String {
+        evaluate(expr, today()).unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(eval("1 + 2 * 3"), "7");
+        assert_eq!(eval("(1 + 2) * 3"), "9");
+        assert_eq!(eval("-2^2"), "-4");
+        assert_eq!(eval("2^3^2"), "512");
+        assert_eq!(eval("10 / 4"), "2.5");
+        assert_eq!(eval("1_000_000 * 1.5e-3"), "1500");
+        assert_eq!(eval("sqrt(2)"), "1.414213562");
+        assert_eq!(eval("max(3, 9, 4) % 5"), "4");
+        assert_eq!(eval("0.1 + 0.2"), "0.3");
+        assert!(evaluate("1 / 0", today()).is_err());
+        assert!(evaluate("2 +", today()).is_err());
+        assert!(evaluate("foo(1)", today()).is_err());
+    }
+
+    #[test]
+    fn test_unit_conversions() {
+        assert_eq!(eval("5 km to mi"), "5 km = 3.106855961 mi");
+        assert_eq!(eval("98.6 F to C"), "98.6 F = 37 C");
+        assert_eq!(eval("1 GiB in MB"), "1 GiB = 1073.741824 MB");
+        assert_eq!(eval("(60 + 5) mph to km/h"), "65 mph = 104.60736 km/h");
+        assert_eq!(eval("2 fl oz to ml"), "2 fl oz = 59.14705912 ml");
+        assert!(evaluate("5 km to kg", today()).is_err());
+    }
+
+    #[test]
+    fn test_date_math() {
+        assert_eq!(eval("2026-10-15 + 30 days"), "2026-11-14 (Saturday)");
+        assert_eq!(eval("today + 1 week - 1 day"), "2026-10-21 (Wednesday)");
+        assert_eq!(eval("2026-01-31 + 1 month"), "2026-02-28 (Saturday)");
+        assert_eq!(eval("2026-12-25 - today"), "71 days");
+        assert!(evaluate("2026-10-15 + 3 fortnights", today()).is_err());
+    }
+}
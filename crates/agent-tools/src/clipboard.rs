@@ -0,0 +1,273 @@
+//! Clipboard tools for desktop sessions.
+//!
+//! `clipboard_read` returns the text the user copied (an error message, a
+//! stack trace) and `clipboard_write` places generated text on the clipboard.
+//! Both go through the platform's clipboard utility: pbpaste/pbcopy on macOS,
+//! PowerShell on Windows, wl-clipboard on Wayland and xclip or xsel on X11.
+//! Registered only with `[tools] clipboard = true`.
+
+use agent_core::error::AgentError;
+use agent_core::scrubber::SecretScrubber;
+use agent_core::tool_registry::Tool;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Clipboard utilities are local and quick; anything slower is stuck.
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// Largest text `clipboard_write` accepts.
+const MAX_WRITE_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    MacOs,
+    Windows,
+    Wayland,
+    Xclip,
+    Xsel,
+}
+
+impl Backend {
+    /// The backend for this machine, if a clipboard utility is available.
+    fn detect() -> Option<Self> {
+        Self::detect_with(std::env::consts::OS, |k| std::env::var_os(k).is_some(), in_path)
+    }
+
+    fn detect_with(
+        os: &str,
+        has_env: impl Fn(&str) -> bool,
+        in_path: impl Fn(&str) -> bool,
+    ) -> Option<Self> {
+        match os {
+            "macos" => Some(Self::MacOs),
+            "windows" => Some(Self::Windows),
+            _ if has_env("WAYLAND_DISPLAY") && in_path("wl-paste") => Some(Self::Wayland),
+            _ if has_env("DISPLAY") && in_path("xclip") => Some(Self::Xclip),
+            _ if has_env("DISPLAY") && in_path("xsel") => Some(Self::Xsel),
+            _ => None,
+        }
+    }
+
+    fn read_command(self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            Self::MacOs => ("pbpaste", &[]),
+            Self::Windows => ("powershell", &["-NoProfile", "-Command", "Get-Clipboard -Raw"]),
+            Self::Wayland => ("wl-paste", &["--no-newline"]),
+            Self::Xclip => ("xclip", &["-selection", "clipboard", "-o"]),
+            Self::Xsel => ("xsel", &["--clipboard", "--output"]),
+        }
+    }
+
+    fn write_command(self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            Self::MacOs => ("pbcopy", &[]),
+            Self::Windows => (
+                "powershell",
+                &[
+                    "-NoProfile",
+                    "-Command",
+                    "[Console]::InputEncoding = [Text.Encoding]::UTF8; \
+                     Set-Clipboard -Value ([Console]::In.ReadToEnd())",
+                ],
+            ),
+            Self::Wayland => ("wl-copy", &[]),
+            Self::Xclip => ("xclip", &["-selection", "clipboard", "-i"]),
+            Self::Xsel => ("xsel", &["--clipboard", "--input"]),
+        }
+    }
+}
+
+fn in_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+fn err(tool: &str, msg: impl Into<String>) -> AgentError {
+    AgentError::ToolExecution {
+        tool_name: tool.into(),
+        message: msg.into(),
+    }
+}
+
+fn backend(tool: &str) -> Result<Backend, AgentError> {
+    Backend::detect().ok_or_else(|| {
+        err(
+            tool,
+            "No clipboard available (needs a desktop session with wl-clipboard, xclip or xsel on Linux)",
+        )
+    })
+}
+
+/// Read text from the system clipboard.
+pub struct ClipboardReadTool {
+    scrubber: SecretScrubber,
+}
+
+impl ClipboardReadTool {
+    pub fn new() -> Self {
+        Self {
+            scrubber: SecretScrubber::new(),
+        }
+    }
+}
+
+impl Default for ClipboardReadTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for ClipboardReadTool {
+    fn name(&self) -> &str {
+        "clipboard_read"
+    }
+
+    fn description(&self) -> &str {
+        "Read the text currently on the user's clipboard, e.g. an error message or log \
+         excerpt they just copied."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({ "type": "object", "properties": {} })
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, _args: Value) -> Result<String, AgentError> {
+        let (program, args) = backend("clipboard_read")?.read_command();
+        let output = tokio::time::timeout(
+            TIMEOUT,
+            Command::new(program)
+                .args(args)
+                .stdin(Stdio::null())
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await
+        .map_err(|_| err("clipboard_read", format!("{} timed out", program)))?
+        .map_err(|e| err("clipboard_read", format!("Failed to run {}: {}", program, e)))?;
+        if !output.status.success() {
+            return Err(err(
+                "clipboard_read",
+                format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim()),
+            ));
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        if text.trim().is_empty() {
+            return Ok("The clipboard is empty.".into());
+        }
+        // Clipboards routinely hold passwords and tokens.
+        Ok(self.scrubber.scrub(&text).into_owned())
+    }
+}
+
+/// Place text on the system clipboard.
+pub struct ClipboardWriteTool;
+
+#[async_trait]
+impl Tool for ClipboardWriteTool {
+    fn name(&self) -> &str {
+        "clipboard_write"
+    }
+
+    fn description(&self) -> &str {
+        "Copy text to the user's clipboard so they can paste it elsewhere, e.g. a command \
+         or code snippet. Replaces the current clipboard contents."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": {
+                    "type": "string",
+                    "description": "Text to copy"
+                }
+            },
+            "required": ["text"]
+        })
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            text: String,
+        }
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| err("clipboard_write", format!("Invalid arguments: {}", e)))?;
+        if args.text.len() > MAX_WRITE_BYTES {
+            return Err(err("clipboard_write", "Text is too large for the clipboard (max 1 MiB)"));
+        }
+
+        let (program, cmd_args) = backend("clipboard_write")?.write_command();
+        // xclip and wl-copy fork to keep serving the selection, so their
+        // output is not captured (it would never reach EOF).
+        let mut child = Command::new(program)
+            .args(cmd_args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| err("clipboard_write", format!("Failed to run {}: {}", program, e)))?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let text = args.text.clone();
+        let status = tokio::time::timeout(TIMEOUT, async move {
+            stdin.write_all(text.as_bytes()).await?;
+            drop(stdin);
+            child.wait().await
+        })
+        .await
+        .map_err(|_| err("clipboard_write", format!("{} timed out", program)))?
+        .map_err(|e| err("clipboard_write", format!("{} failed: {}", program, e)))?;
+        if !status.success() {
+            return Err(err("clipboard_write", format!("{} exited with {}", program, status)));
+        }
+        Ok(format!(
+            "Copied {} characters to the clipboard.",
+            args.text.chars().count()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_backend() {
+        let none = |_: &str| false;
+        assert_eq!(Backend::detect_with("macos", none, none), Some(Backend::MacOs));
+        assert_eq!(Backend::detect_with("linux", none, none), None);
+
+        let wayland = |k: &str| k == "WAYLAND_DISPLAY" || k == "DISPLAY";
+        assert_eq!(
+            Backend::detect_with("linux", wayland, |p| p == "wl-paste" || p == "xclip"),
+            Some(Backend::Wayland)
+        );
+        // XWayland session without wl-clipboard falls back to X11 tools.
+        assert_eq!(
+            Backend::detect_with("linux", wayland, |p| p == "xsel"),
+            Some(Backend::Xsel)
+        );
+        assert_eq!(
+            Backend::detect_with("linux", |k| k == "DISPLAY", |p| p == "wl-paste"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_rejects_oversized_text() {
+        let text = "x".repeat(MAX_WRITE_BYTES + 1);
+        let result = ClipboardWriteTool.execute(json!({ "text": text })).await;
+        assert!(result.unwrap_err().to_string().contains("too large"));
+    }
+}
@@ -0,0 +1,494 @@
+//! Tabular data preview tool.
+//!
+//! Loads a CSV, TSV or Parquet file and reports its schema, row count,
+//! first rows and per-column statistics, so a quick look at a dataset
+//! does not need a Python session. Files are streamed: statistics cover
+//! the first `sample_rows` rows, and CSV row counts read to the end.
+
+use crate::file_ops::validate_path;
+use agent_core::error::AgentError;
+use agent_core::path_rules::PathRules;
+use agent_core::tool_registry::Tool;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Head rows shown by default, and at most.
+const DEFAULT_HEAD_ROWS: usize = 5;
+const MAX_HEAD_ROWS: usize = 50;
+
+/// Rows statistics are computed over by default.
+const DEFAULT_SAMPLE_ROWS: usize = 100_000;
+
+/// Distinct values tracked per column before counting stops.
+const MAX_DISTINCT: usize = 10_000;
+
+/// Longest cell shown in the head table, in characters.
+const MAX_CELL_CHARS: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Csv(u8),
+    Parquet,
+}
+
+impl Format {
+    fn detect(path: &Path, delimiter: Option<&str>) -> Result<Self, String> {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        if let Some(delimiter) = delimiter {
+            return match delimiter {
+                "\\t" | "\t" | "tab" => Ok(Self::Csv(b'\t')),
+                d if d.len() == 1 => Ok(Self::Csv(d.as_bytes()[0])),
+                d => Err(format!("Delimiter must be one character, got '{}'", d)),
+            };
+        }
+        match ext.as_str() {
+            "csv" | "txt" => Ok(Self::Csv(b',')),
+            "tsv" | "tab" => Ok(Self::Csv(b'\t')),
+            "parquet" | "pq" => Ok(Self::Parquet),
+            _ => Err(format!(
+                "Unsupported file type '{}'; expected .csv, .tsv or .parquet",
+                ext
+            )),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Csv(b'\t') => "TSV",
+            Self::Csv(_) => "CSV",
+            Self::Parquet => "Parquet",
+        }
+    }
+}
+
+/// Running statistics for one column.
+#[derive(Debug, Default)]
+struct ColumnStats {
+    nulls: usize,
+    values: usize,
+    /// Values that parsed as numbers, and their sum/min/max.
+    numeric: usize,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+    integers: usize,
+    booleans: usize,
+    distinct: HashMap<String, usize>,
+    distinct_capped: bool,
+    max_len: usize,
+}
+
+impl ColumnStats {
+    fn add(&mut self, cell: Option<&str>) {
+        let Some(cell) = cell.filter(|c| !c.is_empty()) else {
+            self.nulls += 1;
+            return;
+        };
+        self.values += 1;
+        self.max_len = self.max_len.max(cell.chars().count());
+        if let Ok(n) = cell.trim().parse::<f64>() {
+            if n.is_finite() {
+                self.numeric += 1;
+                self.sum += n;
+                self.min = Some(self.min.map_or(n, |m| m.min(n)));
+                self.max = Some(self.max.map_or(n, |m| m.max(n)));
+                if cell.trim().parse::<i64>().is_ok() {
+                    self.integers += 1;
+                }
+            }
+        } else if matches!(cell.to_ascii_lowercase().as_str(), "true" | "false") {
+            self.booleans += 1;
+        }
+        if let Some(count) = self.distinct.get_mut(cell) {
+            *count += 1;
+        } else if self.distinct.len() < MAX_DISTINCT {
+            self.distinct.insert(cell.to_string(), 1);
+        } else {
+            self.distinct_capped = true;
+        }
+    }
+
+    /// Type inferred from the values seen.
+    fn inferred_type(&self) -> &'static str {
+        match self.values {
+            0 => "empty",
+            n if self.integers == n => "integer",
+            n if self.numeric == n => "float",
+            n if self.booleans == n => "boolean",
+            _ => "string",
+        }
+    }
+
+    fn summary(&self) -> String {
+        let mut parts = vec![format!("{} nulls", self.nulls)];
+        parts.push(format!(
+            "{}{} distinct",
+            self.distinct.len(),
+            if self.distinct_capped { "+" } else { "" }
+        ));
+        if self.values > 0 && self.numeric == self.values {
+            parts.push(format!(
+                "min {}, max {}, mean {}",
+                fmt_number(self.min.unwrap_or(0.0)),
+                fmt_number(self.max.unwrap_or(0.0)),
+                fmt_number(self.sum / self.numeric as f64)
+            ));
+        } else if self.values > 0 {
+            let common = self
+                .distinct
+                .iter()
+                .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                .filter(|(_, count)| **count > 1);
+            if let Some((value, count)) = common {
+                parts.push(format!("most common {:?} ({})", truncate(value), count));
+            }
+            parts.push(format!("longest {} chars", self.max_len));
+        }
+        parts.join(", ")
+    }
+}
+
+fn fmt_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        format!("{:.4}", n)
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
+
+fn truncate(cell: &str) -> String {
+    if cell.chars().count() <= MAX_CELL_CHARS {
+        cell.to_string()
+    } else {
+        let cut: String = cell.chars().take(MAX_CELL_CHARS - 1).collect();
+        format!("{}…", cut)
+    }
+}
+
+/// A file's schema, first rows and column statistics.
+#[derive(Debug, Default)]
+struct Preview {
+    columns: Vec<String>,
+    /// Declared types (Parquet); CSV types are inferred from the stats.
+    types: Vec<Option<String>>,
+    rows: u64,
+    head: Vec<Vec<Option<String>>>,
+    stats: Vec<ColumnStats>,
+    sampled: usize,
+}
+
+impl Preview {
+    fn new(columns: Vec<String>, types: Vec<Option<String>>) -> Self {
+        let stats = columns.iter().map(|_| ColumnStats::default()).collect();
+        Self {
+            columns,
+            types,
+            stats,
+            ..Default::default()
+        }
+    }
+
+    fn push_row(&mut self, row: Vec<Option<String>>, head_rows: usize, sample_rows: usize) {
+        if self.sampled < sample_rows {
+            for (stats, cell) in self.stats.iter_mut().zip(&row) {
+                stats.add(cell.as_deref());
+            }
+            self.sampled += 1;
+        }
+        if self.head.len() < head_rows {
+            self.head.push(row);
+        }
+        self.rows += 1;
+    }
+
+    fn render(&self, name: &str, format: Format) -> String {
+        let mut out = format!(
+            "{} ({}, {} rows, {} columns)\n\nSchema:\n",
+            name,
+            format.label(),
+            self.rows,
+            self.columns.len()
+        );
+        for (i, column) in self.columns.iter().enumerate() {
+            let ty = self.types.get(i).cloned().flatten();
+            let ty = ty.unwrap_or_else(|| self.stats[i].inferred_type().to_string());
+            out.push_str(&format!("  {}: {}\n", column, ty));
+        }
+
+        if !self.head.is_empty() {
+            out.push_str(&format!("\nFirst {} rows:\n", self.head.len()));
+            let header: Vec<String> = self.columns.iter().map(|c| truncate(c)).collect();
+            out.push_str(&format!("| {} |\n", header.join(" | ")));
+            out.push_str(&format!("|{}\n", "---|".repeat(header.len())));
+            for row in &self.head {
+                let cells: Vec<String> = row
+                    .iter()
+                    .map(|c| c.as_deref().map(truncate).unwrap_or_default().replace('|', "\\|"))
+                    .collect();
+                out.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+        }
+
+        if self.sampled > 0 {
+            if (self.sampled as u64) < self.rows {
+                out.push_str(&format!("\nColumn statistics (first {} rows):\n", self.sampled));
+            } else {
+                out.push_str("\nColumn statistics:\n");
+            }
+            for (column, stats) in self.columns.iter().zip(&self.stats) {
+                out.push_str(&format!("  {}: {}\n", column, stats.summary()));
+            }
+        }
+        out.trim_end().to_string()
+    }
+}
+
+fn preview_csv(
+    path: &Path,
+    delimiter: u8,
+    head_rows: usize,
+    sample_rows: usize,
+) -> Result<Preview, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let columns: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("Failed to read the header row: {}", e))?
+        .iter()
+        .map(String::from)
+        .collect();
+    let mut preview = Preview::new(columns.clone(), vec![None; columns.len()]);
+
+    let mut record = csv::StringRecord::new();
+    loop {
+        match reader.read_record(&mut record) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => return Err(format!("Failed to read row {}: {}", preview.rows + 2, e)),
+        }
+        // Past the sample and the head, only the row count is needed.
+        if preview.sampled >= sample_rows && preview.head.len() >= head_rows {
+            preview.rows += 1;
+            continue;
+        }
+        let row = (0..columns.len())
+            .map(|i| record.get(i).map(String::from))
+            .collect();
+        preview.push_row(row, head_rows, sample_rows);
+    }
+    Ok(preview)
+}
+
+#[cfg(feature = "parquet")]
+fn preview_parquet(path: &Path, head_rows: usize, sample_rows: usize) -> Result<Preview, String> {
+    use arrow_cast::display::{ArrayFormatter, FormatOptions};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    let file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+        .map_err(|e| format!("Not a valid Parquet file: {}", e))?;
+    let total = builder.metadata().file_metadata().num_rows().max(0) as u64;
+    let schema = builder.schema().clone();
+    let columns = schema.fields().iter().map(|f| f.name().clone()).collect();
+    let types = schema
+        .fields()
+        .iter()
+        .map(|f| Some(f.data_type().to_string()))
+        .collect();
+    let mut preview = Preview::new(columns, types);
+
+    let reader = builder
+        .with_limit(sample_rows.max(head_rows))
+        .build()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let options = FormatOptions::default();
+    for batch in reader {
+        let batch = batch.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let formatters = batch
+            .columns()
+            .iter()
+            .map(|column| ArrayFormatter::try_new(column.as_ref(), &options))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to format column: {}", e))?;
+        for row in 0..batch.num_rows() {
+            let cells = batch
+                .columns()
+                .iter()
+                .zip(&formatters)
+                .map(|(column, fmt)| (!column.is_null(row)).then(|| fmt.value(row).to_string()))
+                .collect();
+            preview.push_row(cells, head_rows, sample_rows);
+        }
+    }
+    preview.rows = total;
+    Ok(preview)
+}
+
+#[cfg(not(feature = "parquet"))]
+fn preview_parquet(_: &Path, _: usize, _: usize) -> Result<Preview, String> {
+    Err("This build has no Parquet support (enable the agent-tools `parquet` feature)".into())
+}
+
+pub struct DataPreviewTool {
+    pub workspace_root: Option<PathBuf>,
+    pub path_rules: Arc<PathRules>,
+}
+
+#[async_trait]
+impl Tool for DataPreviewTool {
+    fn name(&self) -> &str {
+        "data_preview"
+    }
+
+    fn description(&self) -> &str {
+        "Preview a tabular data file (CSV, TSV or Parquet): schema with column types, \
+         row count, the first rows, and per-column statistics (nulls, distinct values, \
+         min/max/mean for numbers, most common value otherwise). Use this before reaching \
+         for Python to look at a dataset."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The .csv, .tsv or .parquet file"
+                },
+                "head": {
+                    "type": "integer",
+                    "description": "Rows to show (default 5, at most 50)"
+                },
+                "sample_rows": {
+                    "type": "integer",
+                    "description": "Rows to compute statistics over (default 100000)"
+                },
+                "delimiter": {
+                    "type": "string",
+                    "description": "Field delimiter for delimited text, e.g. ';' or 'tab'. Defaults from the file extension."
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            path: String,
+            head: Option<usize>,
+            sample_rows: Option<usize>,
+            delimiter: Option<String>,
+        }
+        let err = |message: String| AgentError::ToolExecution {
+            tool_name: "data_preview".into(),
+            message,
+        };
+        let args: Args =
+            serde_json::from_value(args).map_err(|e| err(format!("Invalid arguments: {}", e)))?;
+
+        let path = validate_path(&args.path, &self.workspace_root, &self.path_rules)?;
+        let format = Format::detect(&path, args.delimiter.as_deref()).map_err(err)?;
+        let head_rows = args.head.unwrap_or(DEFAULT_HEAD_ROWS).min(MAX_HEAD_ROWS);
+        let sample_rows = args.sample_rows.unwrap_or(DEFAULT_SAMPLE_ROWS);
+
+        let name = args.path.clone();
+        tokio::task::spawn_blocking(move || {
+            let preview = match format {
+                Format::Csv(delimiter) => preview_csv(&path, delimiter, head_rows, sample_rows),
+                Format::Parquet => preview_parquet(&path, head_rows, sample_rows),
+            }?;
+            Ok(preview.render(&name, format))
+        })
+        .await
+        .map_err(|e| err(format!("Preview failed: {}", e)))?
+        .map_err(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_csv_preview() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("sales.csv");
+        std::fs::write(
+            &file,
+            "region,units,price,note\nnorth,3,9.5,\nsouth,5,10,\"a, b\"\nnorth,,12.25,x\n",
+        )
+        .unwrap();
+        let tool = DataPreviewTool {
+            workspace_root: Some(tmp.path().to_path_buf()),
+            path_rules: Default::default(),
+        };
+
+        let out = tool
+            .execute(json!({"path": file, "head": 2}))
+            .await
+            .unwrap();
+        assert!(out.contains("(CSV, 3 rows, 4 columns)"), "got:\n{out}");
+        assert!(out.contains("  units: integer\n  price: float"), "got:\n{out}");
+        assert!(out.contains("| north | 3 | 9.5 |  |\n| south | 5 | 10 | a, b |"), "got:\n{out}");
+        assert!(out.contains("units: 1 nulls, 2 distinct, min 3, max 5, mean 4"), "got:\n{out}");
+        assert!(out.contains("region: 0 nulls, 2 distinct, most common \"north\" (2)"), "got:\n{out}");
+
+        let out = tool
+            .execute(json!({"path": file, "sample_rows": 1}))
+            .await
+            .unwrap();
+        assert!(out.contains("Column statistics (first 1 rows)"), "got:\n{out}");
+    }
+
+    #[cfg(feature = "parquet")]
+    #[tokio::test]
+    async fn test_parquet_preview() {
+        use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+        use std::sync::Arc;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("users.parquet");
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef),
+            ("name", Arc::new(StringArray::from(vec![Some("ann"), None, Some("cy")])) as ArrayRef),
+        ])
+        .unwrap();
+        let mut writer = parquet::arrow::ArrowWriter::try_new(
+            std::fs::File::create(&file).unwrap(),
+            batch.schema(),
+            None,
+        )
+        .unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let tool = DataPreviewTool {
+            workspace_root: Some(tmp.path().to_path_buf()),
+            path_rules: Default::default(),
+        };
+        let out = tool.execute(json!({"path": file})).await.unwrap();
+        assert!(out.contains("(Parquet, 3 rows, 2 columns)"), "got:\n{out}");
+        assert!(out.contains("  id: Int64\n  name: Utf8"), "got:\n{out}");
+        assert!(out.contains("id: 0 nulls, 3 distinct, min 1, max 3, mean 2"), "got:\n{out}");
+        assert!(out.contains("name: 1 nulls, 2 distinct"), "got:\n{out}");
+    }
+}
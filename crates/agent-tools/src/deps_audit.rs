@@ -0,0 +1,650 @@
+//! Dependency audit tool.
+//!
+//! Reads lockfiles (Cargo.lock, package-lock.json, requirements.txt) and
+//! reports unpinned requirements and packages locked at several versions.
+//! On request it also looks up the latest release of each direct dependency
+//! in its registry and queries [OSV](https://osv.dev) for known
+//! vulnerabilities. The result is a JSON report for the agent to summarize.
+
+use agent_core::error::AgentError;
+use agent_core::path_rules::PathRules;
+use agent_core::tool_registry::Tool;
+use agent_core::workspace;
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const OSV_BATCH_URL: &str = "https://api.osv.dev/v1/querybatch";
+const OSV_VULN_URL: &str = "https://api.osv.dev/v1/vulns";
+/// Registry lookups for `outdated` are capped to keep the tool fast.
+const MAX_REGISTRY_LOOKUPS: usize = 50;
+/// Vulnerabilities whose summary is fetched; the rest are reported by ID.
+const MAX_VULN_DETAILS: usize = 20;
+const CONCURRENT_REQUESTS: usize = 8;
+
+/// A locked package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// Listed in the manifest rather than pulled in transitively.
+    pub direct: bool,
+}
+
+/// Packages from one lockfile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lockfile {
+    pub path: PathBuf,
+    /// OSV ecosystem name: `crates.io`, `npm` or `PyPI`.
+    pub ecosystem: &'static str,
+    pub packages: Vec<LockedPackage>,
+    /// Requirements without an exact `==` pin (requirements.txt only).
+    pub unpinned: Vec<Unpinned>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Unpinned {
+    pub name: String,
+    pub spec: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AuditReport {
+    pub lockfiles: Vec<LockfileSummary>,
+    pub unpinned: Vec<Unpinned>,
+    pub duplicates: Vec<Duplicate>,
+    pub outdated: Vec<Outdated>,
+    pub vulnerabilities: Vec<Vulnerability>,
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LockfileSummary {
+    pub path: String,
+    pub ecosystem: &'static str,
+    pub packages: usize,
+    pub direct: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Duplicate {
+    pub name: String,
+    pub versions: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Outdated {
+    pub name: String,
+    pub ecosystem: &'static str,
+    pub current: String,
+    pub latest: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Vulnerability {
+    pub name: String,
+    pub ecosystem: &'static str,
+    pub version: String,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+// ── Lockfile parsing ───────────────────────────────────────────────────
+
+/// `Cargo.lock`, with direct dependencies taken from `Cargo.toml`.
+pub fn parse_cargo_lock(lock: &str, manifest: Option<&str>) -> Result<Vec<LockedPackage>, String> {
+    #[derive(Deserialize)]
+    struct Lock {
+        #[serde(default)]
+        package: Vec<Package>,
+    }
+    #[derive(Deserialize)]
+    struct Package {
+        name: String,
+        version: String,
+        source: Option<String>,
+    }
+    let lock: Lock = toml::from_str(lock).map_err(|e| format!("Invalid Cargo.lock: {}", e))?;
+
+    let mut direct = BTreeSet::new();
+    if let Some(manifest) = manifest.and_then(|m| m.parse::<toml::Table>().ok()) {
+        let workspace = manifest.get("workspace").and_then(|w| w.as_table());
+        let tables = ["dependencies", "dev-dependencies", "build-dependencies"]
+            .iter()
+            .filter_map(|t| manifest.get(*t))
+            .chain(workspace.and_then(|w| w.get("dependencies")));
+        for table in tables.filter_map(|t| t.as_table()) {
+            for (key, spec) in table {
+                // `alias = { package = "real-name", ... }`
+                let name = spec
+                    .get("package")
+                    .and_then(|p| p.as_str())
+                    .unwrap_or(key);
+                direct.insert(name.to_string());
+            }
+        }
+    }
+
+    // Workspace members have no source; only registry packages are audited.
+    Ok(lock
+        .package
+        .into_iter()
+        .filter(|p| p.source.as_deref().is_some_and(|s| s.starts_with("registry+")))
+        .map(|p| LockedPackage {
+            direct: direct.contains(&p.name),
+            name: p.name,
+            version: p.version,
+        })
+        .collect())
+}
+
+/// `package-lock.json` (lockfile v1, v2 or v3).
+pub fn parse_package_lock(lock: &str) -> Result<Vec<LockedPackage>, String> {
+    let json: Value =
+        serde_json::from_str(lock).map_err(|e| format!("Invalid package-lock.json: {}", e))?;
+    let mut packages = Vec::new();
+
+    if let Some(map) = json.get("packages").and_then(Value::as_object) {
+        let root = map.get("").cloned().unwrap_or(Value::Null);
+        let direct: BTreeSet<&str> = ["dependencies", "devDependencies", "optionalDependencies"]
+            .iter()
+            .filter_map(|k| root.get(*k).and_then(Value::as_object))
+            .flat_map(|deps| deps.keys().map(String::as_str))
+            .collect();
+        for (key, info) in map {
+            // "node_modules/a/node_modules/@scope/b" -> "@scope/b"
+            let Some(idx) = key.rfind("node_modules/") else { continue };
+            let name = &key[idx + "node_modules/".len()..];
+            let (Some(version), false) = (
+                info.get("version").and_then(Value::as_str),
+                info.get("link").and_then(Value::as_bool).unwrap_or(false),
+            ) else {
+                continue;
+            };
+            packages.push(LockedPackage {
+                name: name.to_string(),
+                version: version.to_string(),
+                direct: key == &format!("node_modules/{}", name) && direct.contains(name),
+            });
+        }
+    } else if let Some(deps) = json.get("dependencies").and_then(Value::as_object) {
+        // v1: only the top level is listed as direct.
+        for (name, info) in deps {
+            if let Some(version) = info.get("version").and_then(Value::as_str) {
+                packages.push(LockedPackage {
+                    name: name.clone(),
+                    version: version.to_string(),
+                    direct: true,
+                });
+            }
+        }
+    }
+    Ok(packages)
+}
+
+/// `requirements.txt`: `name==version` lines are locked, anything else is
+/// reported as unpinned. Options (`-r`, `--hash`) and comments are skipped.
+pub fn parse_requirements(content: &str) -> (Vec<LockedPackage>, Vec<Unpinned>) {
+    let mut packages = Vec::new();
+    let mut unpinned = Vec::new();
+    for line in content.lines() {
+        let line = line.split(" #").next().unwrap_or("").trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('-') {
+            continue;
+        }
+        // Drop environment markers and trailing options.
+        let requirement = line.split(';').next().unwrap_or("").trim();
+        let requirement = requirement.split(" --").next().unwrap_or("").trim();
+        let name_end = requirement
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'))
+            .unwrap_or(requirement.len());
+        let name = &requirement[..name_end];
+        if name.is_empty() {
+            continue;
+        }
+        // Skip extras: "requests[socks]==2.31" -> "==2.31".
+        let spec = match requirement[name_end..].find(']') {
+            Some(i) if requirement[name_end..].starts_with('[') => &requirement[name_end + i + 1..],
+            _ => &requirement[name_end..],
+        }
+        .trim();
+        match spec.strip_prefix("==") {
+            Some(version) if !version.contains(['*', ',']) => packages.push(LockedPackage {
+                name: name.to_string(),
+                version: version.trim().to_string(),
+                direct: true,
+            }),
+            _ => unpinned.push(Unpinned {
+                name: name.to_string(),
+                spec: if spec.is_empty() { "*".into() } else { spec.to_string() },
+            }),
+        }
+    }
+    (packages, unpinned)
+}
+
+/// Read every supported lockfile in `dir`.
+pub fn read_lockfiles(dir: &Path) -> Result<Vec<Lockfile>, String> {
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).ok();
+    let mut lockfiles = Vec::new();
+    if let Some(lock) = read("Cargo.lock") {
+        lockfiles.push(Lockfile {
+            path: dir.join("Cargo.lock"),
+            ecosystem: "crates.io",
+            packages: parse_cargo_lock(&lock, read("Cargo.toml").as_deref())?,
+            unpinned: Vec::new(),
+        });
+    }
+    if let Some(lock) = read("package-lock.json") {
+        lockfiles.push(Lockfile {
+            path: dir.join("package-lock.json"),
+            ecosystem: "npm",
+            packages: parse_package_lock(&lock)?,
+            unpinned: Vec::new(),
+        });
+    }
+    if let Some(reqs) = read("requirements.txt") {
+        let (packages, unpinned) = parse_requirements(&reqs);
+        lockfiles.push(Lockfile {
+            path: dir.join("requirements.txt"),
+            ecosystem: "PyPI",
+            packages,
+            unpinned,
+        });
+    }
+    Ok(lockfiles)
+}
+
+/// Packages locked at more than one version.
+pub fn find_duplicates(packages: &[LockedPackage]) -> Vec<Duplicate> {
+    let mut versions: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for p in packages {
+        versions.entry(&p.name).or_default().insert(&p.version);
+    }
+    versions
+        .into_iter()
+        .filter(|(_, v)| v.len() > 1)
+        .map(|(name, v)| Duplicate {
+            name: name.to_string(),
+            versions: v.into_iter().map(String::from).collect(),
+        })
+        .collect()
+}
+
+/// Whether `current` is older than `latest`, comparing numeric
+/// dot-separated components (pre-release suffixes are ignored).
+pub fn is_older(current: &str, latest: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or("")
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    let (mut a, mut b) = (parse(current), parse(latest));
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    a < b
+}
+
+// ── Network lookups ────────────────────────────────────────────────────
+
+/// Latest stable release of a package in its registry.
+async fn latest_version(client: &reqwest::Client, ecosystem: &str, name: &str) -> Option<String> {
+    let (url, pointer) = match ecosystem {
+        "crates.io" => (
+            format!("https://crates.io/api/v1/crates/{}", name),
+            "/crate/max_stable_version",
+        ),
+        "npm" => (format!("https://registry.npmjs.org/{}/latest", name), "/version"),
+        "PyPI" => (format!("https://pypi.org/pypi/{}/json", name), "/info/version"),
+        _ => return None,
+    };
+    let json: Value = client.get(url).send().await.ok()?.json().await.ok()?;
+    json.pointer(pointer)?.as_str().map(String::from)
+}
+
+/// Query OSV for every package in one batch request.
+async fn osv_vulnerabilities(
+    client: &reqwest::Client,
+    lockfiles: &[Lockfile],
+) -> Result<Vec<Vulnerability>, String> {
+    let packages: Vec<(&'static str, &LockedPackage)> = lockfiles
+        .iter()
+        .flat_map(|l| l.packages.iter().map(move |p| (l.ecosystem, p)))
+        .collect();
+    if packages.is_empty() {
+        return Ok(Vec::new());
+    }
+    let queries: Vec<Value> = packages
+        .iter()
+        .map(|(ecosystem, p)| {
+            json!({"package": {"name": p.name, "ecosystem": ecosystem}, "version": p.version})
+        })
+        .collect();
+    let response: Value = client
+        .post(OSV_BATCH_URL)
+        .json(&json!({ "queries": queries }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("OSV query failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid OSV response: {}", e))?;
+
+    let mut vulns = Vec::new();
+    let results = response["results"].as_array().cloned().unwrap_or_default();
+    for ((ecosystem, package), result) in packages.iter().zip(results) {
+        for vuln in result["vulns"].as_array().into_iter().flatten() {
+            if let Some(id) = vuln["id"].as_str() {
+                vulns.push(Vulnerability {
+                    name: package.name.clone(),
+                    ecosystem,
+                    version: package.version.clone(),
+                    id: id.to_string(),
+                    summary: None,
+                });
+            }
+        }
+    }
+
+    // The batch API returns IDs only; fetch summaries for the first few.
+    let ids: Vec<String> = vulns.iter().take(MAX_VULN_DETAILS).map(|v| v.id.clone()).collect();
+    let summaries: Vec<Option<String>> = futures::stream::iter(ids)
+        .map(|id| {
+            let client = client.clone();
+            async move {
+                let url = format!("{}/{}", OSV_VULN_URL, id);
+                let json: Value = client.get(url).send().await.ok()?.json().await.ok()?;
+                json["summary"].as_str().map(String::from)
+            }
+        })
+        .buffered(CONCURRENT_REQUESTS)
+        .collect()
+        .await;
+    for (vuln, summary) in vulns.iter_mut().zip(summaries) {
+        vuln.summary = summary;
+    }
+    Ok(vulns)
+}
+
+// ── Tool ───────────────────────────────────────────────────────────────
+
+/// Audit the dependencies of a project.
+pub struct DepsAuditTool {
+    workspace_root: Option<PathBuf>,
+    path_rules: Arc<PathRules>,
+    client: reqwest::Client,
+}
+
+impl DepsAuditTool {
+    pub fn new(workspace_root: Option<PathBuf>, path_rules: Arc<PathRules>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .user_agent("agent-shell/0.1 (deps_audit)")
+            .build()
+            .unwrap_or_default();
+        Self {
+            workspace_root,
+            path_rules,
+            client,
+        }
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        AgentError::ToolExecution {
+            tool_name: "deps_audit".into(),
+            message: msg.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for DepsAuditTool {
+    fn name(&self) -> &str {
+        "deps_audit"
+    }
+
+    fn description(&self) -> &str {
+        "Audit project dependencies from Cargo.lock, package-lock.json and requirements.txt. \
+         Reports unpinned requirements and packages locked at several versions; optionally \
+         checks direct dependencies for newer releases (`outdated`) and all locked packages \
+         for known vulnerabilities via OSV (`vulnerabilities`). Returns a JSON report."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "directory": {
+                    "type": "string",
+                    "description": "Project directory (defaults to the workspace root or current directory)"
+                },
+                "outdated": {
+                    "type": "boolean",
+                    "description": "Look up the latest release of each direct dependency (network). Default: false"
+                },
+                "vulnerabilities": {
+                    "type": "boolean",
+                    "description": "Query OSV for known vulnerabilities (network). Default: false"
+                }
+            }
+        })
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            directory: Option<String>,
+            #[serde(default)]
+            outdated: bool,
+            #[serde(default)]
+            vulnerabilities: bool,
+        }
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| Self::err(format!("Invalid arguments: {}", e)))?;
+
+        let dir = match (args.directory, workspace::primary_root(&self.workspace_root)) {
+            (Some(dir), _) => {
+                crate::file_ops::validate_path(&dir, &self.workspace_root, &self.path_rules)?
+            }
+            (None, Some(root)) => root,
+            (None, None) => std::env::current_dir()
+                .map_err(|e| Self::err(format!("No current directory: {}", e)))?,
+        };
+        let lockfiles = read_lockfiles(&dir).map_err(Self::err)?;
+        if lockfiles.is_empty() {
+            return Err(Self::err(format!(
+                "No Cargo.lock, package-lock.json or requirements.txt in {}",
+                dir.display()
+            )));
+        }
+
+        let mut report = AuditReport::default();
+        for lockfile in &lockfiles {
+            report.lockfiles.push(LockfileSummary {
+                path: lockfile.path.display().to_string(),
+                ecosystem: lockfile.ecosystem,
+                packages: lockfile.packages.len(),
+                direct: lockfile.packages.iter().filter(|p| p.direct).count(),
+            });
+            report.unpinned.extend(lockfile.unpinned.iter().cloned());
+            report.duplicates.extend(find_duplicates(&lockfile.packages));
+        }
+
+        if args.outdated {
+            let direct: Vec<(&'static str, &LockedPackage)> = lockfiles
+                .iter()
+                .flat_map(|l| l.packages.iter().filter(|p| p.direct).map(move |p| (l.ecosystem, p)))
+                .collect();
+            if direct.len() > MAX_REGISTRY_LOOKUPS {
+                report.notes.push(format!(
+                    "Checked the first {} of {} direct dependencies for newer releases",
+                    MAX_REGISTRY_LOOKUPS,
+                    direct.len()
+                ));
+            }
+            let lookups: Vec<(String, String)> = direct
+                .iter()
+                .take(MAX_REGISTRY_LOOKUPS)
+                .map(|(ecosystem, p)| (ecosystem.to_string(), p.name.clone()))
+                .collect();
+            let latest: Vec<Option<String>> = futures::stream::iter(lookups)
+                .map(|(ecosystem, name)| {
+                    let client = self.client.clone();
+                    async move { latest_version(&client, &ecosystem, &name).await }
+                })
+                .buffered(CONCURRENT_REQUESTS)
+                .collect()
+                .await;
+            for ((ecosystem, package), latest) in direct.iter().zip(latest) {
+                match latest {
+                    Some(latest) if is_older(&package.version, &latest) => {
+                        report.outdated.push(Outdated {
+                            name: package.name.clone(),
+                            ecosystem,
+                            current: package.version.clone(),
+                            latest,
+                        })
+                    }
+                    Some(_) => {}
+                    None => report
+                        .notes
+                        .push(format!("Could not look up the latest version of {}", package.name)),
+                }
+            }
+        } else {
+            report
+                .notes
+                .push("Newer releases not checked (set outdated: true)".into());
+        }
+
+        if args.vulnerabilities {
+            match osv_vulnerabilities(&self.client, &lockfiles).await {
+                Ok(vulns) => report.vulnerabilities = vulns,
+                Err(e) => report.notes.push(e),
+            }
+        } else {
+            report
+                .notes
+                .push("Vulnerabilities not checked (set vulnerabilities: true)".into());
+        }
+
+        serde_json::to_string_pretty(&report).map_err(|e| Self::err(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_lock() {
+        let lock = r#"
+version = 3
+
+[[package]]
+name = "demo"
+version = "0.1.0"
+
+[[package]]
+name = "serde"
+version = "1.0.190"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "syn"
+version = "1.0.109"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "syn"
+version = "2.0.48"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+        let manifest = "[package]\nname = \"demo\"\n\n[dependencies]\nserde = \"1\"\n";
+        let packages = parse_cargo_lock(lock, Some(manifest)).unwrap();
+        assert_eq!(packages.len(), 3);
+        assert!(packages[0].direct && packages[0].name == "serde");
+        assert!(!packages[1].direct);
+        assert_eq!(
+            find_duplicates(&packages),
+            vec![Duplicate {
+                name: "syn".into(),
+                versions: vec!["1.0.109".into(), "2.0.48".into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_package_lock_and_requirements() {
+        let lock = json!({
+            "lockfileVersion": 3,
+            "packages": {
+                "": {"dependencies": {"react": "^18.2.0"}},
+                "node_modules/react": {"version": "18.2.0"},
+                "node_modules/loose-envify": {"version": "1.4.0"},
+                "node_modules/a/node_modules/@scope/b": {"version": "2.0.0"}
+            }
+        });
+        let packages = parse_package_lock(&lock.to_string()).unwrap();
+        let react = packages.iter().find(|p| p.name == "react").unwrap();
+        assert!(react.direct);
+        assert!(packages.iter().any(|p| p.name == "@scope/b" && !p.direct));
+
+        let (pinned, unpinned) = parse_requirements(
+            "# deps\nrequests[socks]==2.31.0\nflask>=2.0  # web\nnumpy\n-r dev.txt\nidna==3.4; python_version >= \"3.8\"\n",
+        );
+        let names: Vec<_> = pinned.iter().map(|p| (p.name.as_str(), p.version.as_str())).collect();
+        assert_eq!(names, vec![("requests", "2.31.0"), ("idna", "3.4")]);
+        assert_eq!(
+            unpinned,
+            vec![
+                Unpinned { name: "flask".into(), spec: ">=2.0".into() },
+                Unpinned { name: "numpy".into(), spec: "*".into() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_older() {
+        assert!(is_older("1.0.190", "1.0.193"));
+        assert!(is_older("0.9", "0.10.0"));
+        assert!(!is_older("2.0.0", "2.0.0"));
+        assert!(!is_older("2.1.0-beta.1", "2.0.9"));
+    }
+
+    #[tokio::test]
+    async fn test_offline_report() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("requirements.txt"), "flask>=2\nidna==3.4\n").unwrap();
+        let tool = DepsAuditTool::new(None, Default::default());
+        let out = tool
+            .execute(json!({"directory": dir.path().to_string_lossy()}))
+            .await
+            .unwrap();
+        let report: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(report["lockfiles"][0]["packages"], 1);
+        assert_eq!(report["unpinned"][0]["name"], "flask");
+        assert_eq!(report["notes"].as_array().unwrap().len(), 2);
+
+        let empty = tempfile::TempDir::new().unwrap();
+        assert!(tool
+            .execute(json!({"directory": empty.path().to_string_lossy()}))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,607 @@
+//! Document text extraction tool.
+//!
+//! Pulls the text out of PDF, Word (.docx) and PowerPoint (.pptx) files,
+//! keeping their structure as markdown headings: one per PDF page or
+//! slide, and the document's own headings for Word. Long documents come
+//! back in chunks sized to fit a tool result, with a contents line on the
+//! first chunk so the model can jump to the part it needs.
+
+use crate::file_ops::validate_path;
+use agent_core::error::AgentError;
+use agent_core::path_rules::PathRules;
+use agent_core::tool_registry::Tool;
+use async_trait::async_trait;
+use quick_xml::events::{BytesStart, Event};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Largest document accepted.
+const MAX_DOC_BYTES: u64 = 100 * 1024 * 1024; // 100 MiB
+
+/// Most XML read from one part of a docx/pptx, against zip bombs.
+const MAX_XML_BYTES: u64 = 64 * 1024 * 1024; // 64 MiB
+
+/// Text per chunk; under the tool result page size so a chunk is never
+/// truncated again.
+const CHUNK_BYTES: usize = 12 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Pdf,
+    Docx,
+    Pptx,
+}
+
+impl Kind {
+    fn detect(path: &Path) -> Result<Self, String> {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "pdf" => Ok(Self::Pdf),
+            "docx" => Ok(Self::Docx),
+            "pptx" => Ok(Self::Pptx),
+            _ => Err(format!(
+                "Unsupported document type '{}'; expected .pdf, .docx or .pptx",
+                ext
+            )),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Pdf => "PDF",
+            Self::Docx => "Word document",
+            Self::Pptx => "PowerPoint deck",
+        }
+    }
+}
+
+/// A heading and the text under it.
+#[derive(Debug, Default, PartialEq)]
+struct Section {
+    /// Markdown heading, e.g. `## Page 3`; empty for text before the first.
+    heading: String,
+    body: String,
+}
+
+impl Section {
+    fn render(&self) -> String {
+        match (self.heading.is_empty(), self.body.trim().is_empty()) {
+            (true, _) => format!("{}\n\n", self.body.trim()),
+            (false, true) => format!("{}\n\n", self.heading),
+            (false, false) => format!("{}\n\n{}\n\n", self.heading, self.body.trim()),
+        }
+    }
+
+    /// The heading without its `#` marks, for the contents line.
+    fn title(&self) -> &str {
+        self.heading.trim_start_matches('#').trim()
+    }
+}
+
+fn pdf_sections(bytes: &[u8]) -> Result<Vec<Section>, String> {
+    let pages = pdf_extract::extract_text_from_mem_by_pages(bytes)
+        .map_err(|e| format!("Failed to read PDF: {}", e))?;
+    Ok(pages
+        .into_iter()
+        .enumerate()
+        .map(|(i, text)| Section {
+            heading: format!("## Page {}", i + 1),
+            body: collapse_blank_lines(&text),
+        })
+        .collect())
+}
+
+/// At most one blank line in a row, and no trailing spaces.
+fn collapse_blank_lines(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank = false;
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() {
+            if !blank && !out.is_empty() {
+                out.push('\n');
+            }
+            blank = true;
+        } else {
+            out.push_str(line);
+            out.push('\n');
+            blank = false;
+        }
+    }
+    out
+}
+
+/// A part of a docx/pptx package, as text.
+fn read_part(zip: &mut zip::ZipArchive<std::fs::File>, name: &str) -> Result<String, String> {
+    let part = zip
+        .by_name(name)
+        .map_err(|e| format!("Missing {} in the document: {}", name, e))?;
+    let mut xml = String::new();
+    part.take(MAX_XML_BYTES)
+        .read_to_string(&mut xml)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    Ok(xml)
+}
+
+/// Value of the attribute whose local name is `name`, e.g. `val` for `w:val`.
+fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+/// Text events plus resolved entity references (`&amp;`, `&#8217;`).
+fn push_text(out: &mut String, event: &Event) {
+    match event {
+        Event::Text(t) => {
+            if let Ok(text) = t.xml_content() {
+                out.push_str(&text);
+            }
+        }
+        Event::GeneralRef(r) => {
+            if let Ok(Some(c)) = r.resolve_char_ref() {
+                out.push(c);
+            } else if let Ok(name) = r.decode() {
+                out.push_str(quick_xml::escape::resolve_predefined_entity(&name).unwrap_or(""));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Heading level of a Word paragraph style ID such as `Heading2` or `Title`.
+fn heading_level(style: &str) -> Option<usize> {
+    let style = style.to_ascii_lowercase().replace(' ', "");
+    if style == "title" {
+        return Some(1);
+    }
+    let level: usize = style.strip_prefix("heading")?.parse().ok()?;
+    Some(level.clamp(1, 6))
+}
+
+fn docx_sections(path: &Path) -> Result<Vec<Section>, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open: {}", e))?;
+    let mut zip =
+        zip::ZipArchive::new(file).map_err(|e| format!("Not a valid .docx file: {}", e))?;
+    let xml = read_part(&mut zip, "word/document.xml")?;
+
+    let mut reader = quick_xml::Reader::from_str(&xml);
+    let mut sections = vec![Section::default()];
+    let mut para = String::new();
+    let mut style: Option<String> = None;
+    let mut in_text = false;
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("Malformed document XML: {}", e))?;
+        match &event {
+            Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                b"p" => {
+                    para.clear();
+                    style = None;
+                }
+                b"pStyle" => style = attr(e, b"val"),
+                b"t" => in_text = matches!(event, Event::Start(_)),
+                b"tab" => para.push('\t'),
+                b"br" | b"cr" => para.push('\n'),
+                _ => {}
+            },
+            Event::End(e) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"p" => {
+                    let text = para.trim();
+                    match style.as_deref().and_then(heading_level) {
+                        Some(level) if !text.is_empty() => sections.push(Section {
+                            heading: format!("{} {}", "#".repeat(level), text),
+                            body: String::new(),
+                        }),
+                        _ if !text.is_empty() => {
+                            let section = sections.last_mut().expect("never empty");
+                            section.body.push_str(text);
+                            section.body.push('\n');
+                        }
+                        _ => {}
+                    }
+                    para.clear();
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ if in_text => push_text(&mut para, &event),
+            _ => {}
+        }
+    }
+    if sections[0].body.is_empty() {
+        sections.remove(0);
+    }
+    Ok(sections)
+}
+
+fn pptx_sections(path: &Path) -> Result<Vec<Section>, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open: {}", e))?;
+    let mut zip =
+        zip::ZipArchive::new(file).map_err(|e| format!("Not a valid .pptx file: {}", e))?;
+    // ppt/slides/slide12.xml, in slide number order.
+    let mut slides: Vec<(usize, String)> = zip
+        .file_names()
+        .filter_map(|name| {
+            let n = name
+                .strip_prefix("ppt/slides/slide")?
+                .strip_suffix(".xml")?
+                .parse()
+                .ok()?;
+            Some((n, name.to_string()))
+        })
+        .collect();
+    slides.sort();
+
+    let mut sections = Vec::new();
+    for (n, name) in slides {
+        let xml = read_part(&mut zip, &name)?;
+        let mut reader = quick_xml::Reader::from_str(&xml);
+        let (mut title, mut body) = (Vec::new(), Vec::new());
+        let mut shape: Vec<String> = Vec::new();
+        let mut para = String::new();
+        let mut is_title = false;
+        let mut in_text = false;
+        loop {
+            let event = reader
+                .read_event()
+                .map_err(|e| format!("Malformed slide XML in {}: {}", name, e))?;
+            match &event {
+                Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                    b"sp" => {
+                        shape.clear();
+                        is_title = false;
+                    }
+                    b"ph" => {
+                        is_title = matches!(attr(e, b"type").as_deref(), Some("title" | "ctrTitle"))
+                    }
+                    b"p" => para.clear(),
+                    b"t" => in_text = matches!(event, Event::Start(_)),
+                    b"br" => para.push('\n'),
+                    _ => {}
+                },
+                Event::End(e) => match e.local_name().as_ref() {
+                    b"t" => in_text = false,
+                    b"p" if !para.trim().is_empty() => shape.push(para.trim().to_string()),
+                    b"sp" if is_title => title.append(&mut shape),
+                    b"sp" => body.append(&mut shape),
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ if in_text => push_text(&mut para, &event),
+                _ => {}
+            }
+        }
+        let heading = match title.join(" ") {
+            t if t.is_empty() => format!("## Slide {}", n),
+            t => format!("## Slide {}: {}", n, t),
+        };
+        sections.push(Section {
+            heading,
+            body: body.join("\n"),
+        });
+    }
+    Ok(sections)
+}
+
+/// Split `sections` into chunks of about `max` bytes, at section breaks
+/// where possible and line breaks otherwise. Each chunk carries the
+/// sections it covers, for the contents line.
+fn chunk(sections: &[Section], max: usize) -> Vec<(String, Vec<&Section>)> {
+    let mut chunks: Vec<(String, Vec<&Section>)> = Vec::new();
+    let mut text = String::new();
+    let mut covered = Vec::new();
+    for section in sections {
+        let rendered = section.render();
+        if !text.is_empty() && text.len() + rendered.len() > max {
+            chunks.push((std::mem::take(&mut text), std::mem::take(&mut covered)));
+        }
+        covered.push(section);
+        if rendered.len() <= max {
+            text.push_str(&rendered);
+            continue;
+        }
+        for line in rendered.split_inclusive('\n') {
+            let mut line = line;
+            while !line.is_empty() {
+                if !text.is_empty() && text.len() + line.len() > max {
+                    chunks.push((std::mem::take(&mut text), std::mem::take(&mut covered)));
+                    covered.push(section);
+                }
+                let mut take = line.len().min(max);
+                while !line.is_char_boundary(take) {
+                    take -= 1;
+                }
+                text.push_str(&line[..take]);
+                line = &line[take..];
+            }
+        }
+    }
+    if !text.is_empty() || chunks.is_empty() {
+        chunks.push((text, covered));
+    }
+    chunks
+}
+
+/// `first – last` titles of the sections a chunk covers.
+fn span(covered: &[&Section]) -> String {
+    let titles: Vec<&str> = covered
+        .iter()
+        .map(|s| s.title())
+        .filter(|t| !t.is_empty())
+        .collect();
+    match titles.as_slice() {
+        [] => "untitled".to_string(),
+        [one] => one.to_string(),
+        [first, .., last] => format!("{} – {}", first, last),
+    }
+}
+
+fn extract(path: &Path, kind: Kind) -> Result<Vec<Section>, String> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    if size > MAX_DOC_BYTES {
+        return Err(format!(
+            "{} is {} bytes; the limit is {}",
+            path.display(),
+            size,
+            MAX_DOC_BYTES
+        ));
+    }
+    match kind {
+        Kind::Pdf => {
+            let bytes = std::fs::read(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            pdf_sections(&bytes)
+        }
+        Kind::Docx => docx_sections(path),
+        Kind::Pptx => pptx_sections(path),
+    }
+}
+
+pub struct DocExtractTool {
+    pub workspace_root: Option<PathBuf>,
+    pub path_rules: Arc<PathRules>,
+}
+
+#[async_trait]
+impl Tool for DocExtractTool {
+    fn name(&self) -> &str {
+        "doc_extract"
+    }
+
+    fn description(&self) -> &str {
+        "Extract the text of a PDF, Word (.docx) or PowerPoint (.pptx) document as markdown, \
+         with a heading per page or slide and the document's own headings. Long documents are \
+         returned in chunks: the first lists what every chunk covers; pass chunk to read another."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "The .pdf, .docx or .pptx file"
+                },
+                "chunk": {
+                    "type": "integer",
+                    "description": "1-based chunk to return (default 1)"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            path: String,
+            chunk: Option<usize>,
+        }
+        let err = |message: String| AgentError::ToolExecution {
+            tool_name: "doc_extract".into(),
+            message,
+        };
+        let args: Args =
+            serde_json::from_value(args).map_err(|e| err(format!("Invalid arguments: {}", e)))?;
+
+        let path = validate_path(&args.path, &self.workspace_root, &self.path_rules)?;
+        let kind = Kind::detect(&path).map_err(err)?;
+        // Parsers for untrusted files may panic; that surfaces as a join error.
+        let sections = tokio::task::spawn_blocking(move || extract(&path, kind))
+            .await
+            .map_err(|e| err(format!("Failed to extract {}: {}", args.path, e)))?
+            .map_err(err)?;
+
+        if sections.iter().all(|s| s.body.trim().is_empty()) {
+            return Ok(format!(
+                "No text found in {} ({}, {} sections). It may be scanned images without a text layer.",
+                args.path,
+                kind.label(),
+                sections.len()
+            ));
+        }
+
+        let chunks = chunk(&sections, CHUNK_BYTES);
+        let n = args.chunk.unwrap_or(1);
+        let Some((text, covered)) = chunks.get(n.wrapping_sub(1)) else {
+            return Err(err(format!(
+                "{} has {} chunks; ask for 1 to {}",
+                args.path,
+                chunks.len(),
+                chunks.len()
+            )));
+        };
+
+        let mut out = format!(
+            "{} ({}, {} sections) — chunk {} of {}: {}\n",
+            args.path,
+            kind.label(),
+            sections.len(),
+            n,
+            chunks.len(),
+            span(covered)
+        );
+        if n == 1 && chunks.len() > 1 {
+            out.push_str("Contents:\n");
+            for (i, (_, covered)) in chunks.iter().enumerate() {
+                out.push_str(&format!("  chunk {}: {}\n", i + 1, span(covered)));
+            }
+        }
+        out.push('\n');
+        out.push_str(text.trim_end());
+        if n < chunks.len() {
+            out.push_str(&format!("\n\n[Continue with chunk: {}]", n + 1));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_zip(path: &Path, parts: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, content) in parts {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    /// A one-page PDF showing `text` in Helvetica.
+    fn minimal_pdf(text: &str) -> Vec<u8> {
+        let stream = format!("BT /F1 24 Tf 72 700 Td ({}) Tj ET", text);
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R \
+             /Resources << /Font << /F1 5 0 R >> >> >>"
+                .to_string(),
+            format!("<< /Length {} >>\nstream\n{}\nendstream", stream.len(), stream),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        ];
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .as_bytes(),
+        );
+        pdf
+    }
+
+    #[tokio::test]
+    async fn test_extracts_docx_headings_and_pptx_slides() {
+        let tmp = tempfile::tempdir().unwrap();
+        let w = r#"xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main""#;
+        write_zip(
+            &tmp.path().join("spec.docx"),
+            &[(
+                "word/document.xml",
+                &format!(
+                    r#"<w:document {w}><w:body>
+                    <w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Scope</w:t></w:r></w:p>
+                    <w:p><w:r><w:t xml:space="preserve">Covers </w:t></w:r><w:r><w:t>A &amp; B.</w:t></w:r></w:p>
+                    <w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>Limits</w:t></w:r></w:p>
+                    <w:p><w:r><w:t>None.</w:t></w:r></w:p>
+                    </w:body></w:document>"#
+                ),
+            )],
+        );
+        let a = r#"xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main""#;
+        let slide = |title: &str, body: &str| {
+            format!(
+                r#"<p:sld {a}><p:cSld><p:spTree>
+                <p:sp><p:nvSpPr><p:nvPr><p:ph type="title"/></p:nvPr></p:nvSpPr><p:txBody><a:p><a:r><a:t>{title}</a:t></a:r></a:p></p:txBody></p:sp>
+                <p:sp><p:txBody><a:p><a:r><a:t>{body}</a:t></a:r></a:p></p:txBody></p:sp>
+                </p:spTree></p:cSld></p:sld>"#
+            )
+        };
+        let (s1, s2) = (slide("Intro", "Hello"), slide("Plan", "Ship it"));
+        write_zip(
+            &tmp.path().join("deck.pptx"),
+            &[("ppt/slides/slide10.xml", &s2), ("ppt/slides/slide2.xml", &s1)],
+        );
+
+        let tool = DocExtractTool {
+            workspace_root: Some(tmp.path().to_path_buf()),
+            path_rules: Default::default(),
+        };
+        let out = tool
+            .execute(json!({"path": tmp.path().join("spec.docx")}))
+            .await
+            .unwrap();
+        assert!(out.contains("(Word document, 2 sections) — chunk 1 of 1: Scope – Limits"), "got:\n{out}");
+        assert!(out.ends_with("# Scope\n\nCovers A & B.\n\n## Limits\n\nNone."), "got:\n{out}");
+
+        let out = tool
+            .execute(json!({"path": tmp.path().join("deck.pptx")}))
+            .await
+            .unwrap();
+        assert!(
+            out.ends_with("## Slide 2: Intro\n\nHello\n\n## Slide 10: Plan\n\nShip it"),
+            "got:\n{out}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extracts_pdf_pages() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("memo.pdf"), minimal_pdf("Hello PDF")).unwrap();
+        let tool = DocExtractTool {
+            workspace_root: Some(tmp.path().to_path_buf()),
+            path_rules: Default::default(),
+        };
+        let out = tool
+            .execute(json!({"path": tmp.path().join("memo.pdf")}))
+            .await
+            .unwrap();
+        assert!(out.contains("## Page 1\n\nHello PDF"), "got:\n{out}");
+    }
+
+    #[test]
+    fn test_chunks_split_at_sections_and_lines() {
+        let sections: Vec<Section> = (1..=3)
+            .map(|i| Section {
+                heading: format!("## Page {i}"),
+                body: "x\n".repeat(50),
+            })
+            .collect();
+        let chunks = chunk(&sections, 80);
+        assert!(chunks.iter().all(|(text, _)| text.len() <= 80));
+        assert_eq!(span(&chunks[0].1), "Page 1");
+        assert_eq!(span(&chunks[1].1), "Page 1");
+        assert_eq!(span(&chunks[2].1), "Page 2");
+        assert_eq!(chunks.iter().map(|(t, _)| t.len()).sum::<usize>(), 3 * (9 + 2 + 99 + 2));
+    }
+}
@@ -1,28 +1,58 @@
+use crate::ignore_rules::IgnoreRules;
 use agent_core::error::AgentError;
+use agent_core::path_rules::PathRules;
 use agent_core::tool_registry::Tool;
+use agent_core::types::ImagePart;
+use agent_core::workspace;
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-/// Validate that a path is within the allowed workspace root.
+/// Validate that a path is within the allowed workspace root, or one of
+/// the session's active workspaces when a turn set them (relative paths
+/// then start from the primary one), and that no `[sandbox.paths]` rule
+/// denies it.
 /// Returns the canonicalized absolute path if valid.
-pub(crate) fn validate_path(
+pub fn validate_path(
     raw: &str,
     workspace_root: &Option<PathBuf>,
+    rules: &PathRules,
 ) -> Result<PathBuf, AgentError> {
-    let root = match workspace_root {
-        Some(r) => r,
-        None => return Ok(PathBuf::from(raw)), // No restriction
-    };
+    check_path(raw, workspace_root, rules, false)
+}
+
+/// [`validate_path`] for a path the tool will create or modify, which
+/// `ro` rules also refuse.
+pub fn validate_write_path(
+    raw: &str,
+    workspace_root: &Option<PathBuf>,
+    rules: &PathRules,
+) -> Result<PathBuf, AgentError> {
+    check_path(raw, workspace_root, rules, true)
+}
+
+fn check_path(
+    raw: &str,
+    workspace_root: &Option<PathBuf>,
+    rules: &PathRules,
+    write: bool,
+) -> Result<PathBuf, AgentError> {
+    let roots = workspace::allowed_roots(workspace_root);
+    if roots.is_empty() {
+        return Ok(PathBuf::from(raw)); // No restriction
+    }
 
     // Make path absolute.
     let abs = if Path::new(raw).is_absolute() {
         PathBuf::from(raw)
     } else {
-        std::env::current_dir()
-            .unwrap_or_else(|_| PathBuf::from("."))
-            .join(raw)
+        match workspace::active_roots().into_iter().next() {
+            Some(primary) => primary,
+            None => std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+        }
+        .join(raw)
     };
 
     // Canonicalize what exists; for non-existent paths walk up to find an
@@ -59,29 +89,87 @@ pub(crate) fn validate_path(
         canon
     };
 
-    let canon_root = root.canonicalize().map_err(|e| AgentError::ToolExecution {
-        tool_name: "file_ops".into(),
-        message: format!("Failed to canonicalize workspace_root: {}", e),
-    })?;
+    let mut canon_roots = Vec::with_capacity(roots.len());
+    for root in &roots {
+        canon_roots.push(root.canonicalize().map_err(|e| AgentError::ToolExecution {
+            tool_name: "file_ops".into(),
+            message: format!("Failed to canonicalize workspace root {}: {}", root.display(), e),
+        })?);
+    }
 
-    if !canonical.starts_with(&canon_root) {
+    if !canon_roots.iter().any(|root| canonical.starts_with(root)) {
+        let roots: Vec<String> = canon_roots.iter().map(|r| format!("'{}'", r.display())).collect();
         return Err(AgentError::ToolExecution {
             tool_name: "file_ops".into(),
-            message: format!(
-                "Path '{}' is outside the workspace root '{}'",
-                canonical.display(),
-                canon_root.display()
-            ),
+            message: match roots.as_slice() {
+                [root] => format!(
+                    "Path '{}' is outside the workspace root {}",
+                    canonical.display(),
+                    root
+                ),
+                _ => format!(
+                    "Path '{}' is outside the active workspaces {}",
+                    canonical.display(),
+                    roots.join(", ")
+                ),
+            },
         });
     }
+    rules
+        .check(&canonical, &canon_roots, write)
+        .map_err(|message| AgentError::ToolExecution {
+            tool_name: "file_ops".into(),
+            message,
+        })?;
 
     Ok(canonical)
 }
 
 // ── file_read ──────────────────────────────────────────────────────────
 
+/// Largest image `file_read` will attach for a multimodal model.
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024; // 5 MiB
+
+/// How much text `file_read` returns when `max_bytes` is not given, and
+/// the most it returns at all; larger files are read a piece at a time.
+const DEFAULT_READ_BYTES: usize = 256 * 1024; // 256 KiB
+const MAX_READ_BYTES: usize = 1024 * 1024; // 1 MiB
+
+/// Chunk size for reading backwards from the end of a file.
+const TAIL_CHUNK_BYTES: u64 = 64 * 1024;
+
 pub struct FileReadTool {
     pub workspace_root: Option<PathBuf>,
+    pub path_rules: Arc<PathRules>,
+}
+
+impl FileReadTool {
+    /// Read `raw` as an image if its extension says it is one.
+    async fn read_image(&self, raw: &str) -> Result<Option<(String, ImagePart)>, AgentError> {
+        let Some(media_type) = ImagePart::media_type_for_path(Path::new(raw)) else {
+            return Ok(None);
+        };
+        let validated_path = validate_path(raw, &self.workspace_root, &self.path_rules)?;
+        let bytes = tokio::fs::read(&validated_path)
+            .await
+            .map_err(|e| AgentError::ToolExecution {
+                tool_name: "file_read".into(),
+                message: format!("Failed to read {}: {}", raw, e),
+            })?;
+        if bytes.len() > MAX_IMAGE_BYTES {
+            return Err(AgentError::ToolExecution {
+                tool_name: "file_read".into(),
+                message: format!(
+                    "Image {} is {} bytes; the limit is {}",
+                    raw,
+                    bytes.len(),
+                    MAX_IMAGE_BYTES
+                ),
+            });
+        }
+        let text = format!("Image file {} ({}, {} bytes)", raw, media_type, bytes.len());
+        Ok(Some((text, ImagePart::from_bytes(media_type, &bytes))))
+    }
 }
 
 #[async_trait]
@@ -92,7 +180,10 @@ impl Tool for FileReadTool {
 
     fn description(&self) -> &str {
         "Read the contents of a file. Returns the file's text content. \
-         Use this to inspect source code, configuration files, logs, etc."
+         Use this to inspect source code, configuration files, logs, etc. \
+         Large files are returned in pieces with a note on how to read the rest; \
+         use head, tail, a line range or a byte offset to read just the part you need. \
+         Image files (png, jpg, gif, webp) are attached for viewing when the model supports images."
     }
 
     fn parameters_schema(&self) -> Value {
@@ -110,55 +201,300 @@ impl Tool for FileReadTool {
                 "end_line": {
                     "type": "integer",
                     "description": "Optional 1-based end line (inclusive)"
+                },
+                "head": {
+                    "type": "integer",
+                    "description": "Read only the first N lines"
+                },
+                "tail": {
+                    "type": "integer",
+                    "description": "Read only the last N lines, e.g. the end of a log"
+                },
+                "offset": {
+                    "type": "integer",
+                    "description": "Byte offset to start reading from, to page through a large file"
+                },
+                "max_bytes": {
+                    "type": "integer",
+                    "description": "Most bytes to return (default 262144, at most 1048576)"
                 }
             },
             "required": ["path"]
         })
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, args: Value) -> Result<String, AgentError> {
         #[derive(Deserialize)]
         struct Args {
             path: String,
             start_line: Option<usize>,
             end_line: Option<usize>,
+            head: Option<usize>,
+            tail: Option<usize>,
+            offset: Option<u64>,
+            max_bytes: Option<usize>,
         }
         let args: Args = serde_json::from_value(args).map_err(|e| AgentError::ToolExecution {
             tool_name: "file_read".into(),
             message: format!("Invalid arguments: {}", e),
         })?;
 
-        let validated_path = validate_path(&args.path, &self.workspace_root)?;
+        if let Some((text, _)) = self.read_image(&args.path).await? {
+            return Ok(text);
+        }
 
-        let content = tokio::fs::read_to_string(&validated_path)
-            .await
-            .map_err(|e| AgentError::ToolExecution {
+        let validated_path = validate_path(&args.path, &self.workspace_root, &self.path_rules)?;
+        let max_bytes = args.max_bytes.unwrap_or(DEFAULT_READ_BYTES).clamp(1, MAX_READ_BYTES);
+        let io_err = |e: std::io::Error| AgentError::ToolExecution {
+            tool_name: "file_read".into(),
+            message: format!("Failed to read {}: {}", args.path, e),
+        };
+        let mut file = tokio::fs::File::open(&validated_path).await.map_err(io_err)?;
+        let len = file.metadata().await.map_err(io_err)?.len();
+
+        let line_range = args.start_line.is_some() || args.end_line.is_some();
+        let modes = [line_range, args.head.is_some(), args.tail.is_some(), args.offset.is_some()];
+        if modes.iter().filter(|m| **m).count() > 1 {
+            return Err(AgentError::ToolExecution {
                 tool_name: "file_read".into(),
-                message: format!("Failed to read {}: {}", args.path, e),
-            })?;
+                message: "Use only one of start_line/end_line, head, tail or offset".into(),
+            });
+        }
+
+        let excerpt = if let Some(n) = args.tail {
+            read_tail(&mut file, len, n, max_bytes).await
+        } else if let Some(n) = args.head {
+            read_lines(file, 1, Some(n), max_bytes).await
+        } else if line_range {
+            read_lines(file, args.start_line.unwrap_or(1), args.end_line, max_bytes).await
+        } else {
+            read_bytes(&mut file, len, args.offset.unwrap_or(0), max_bytes).await
+        }
+        .map_err(io_err)?;
 
-        // Apply line range if specified.
-        match (args.start_line, args.end_line) {
-            (Some(start), Some(end)) => {
-                let lines: Vec<&str> = content.lines().collect();
-                let start = start.saturating_sub(1).min(lines.len());
-                let end = end.min(lines.len());
-                Ok(lines[start..end].join("\n"))
+        if excerpt.text.as_bytes().iter().take(8192).any(|b| *b == 0) {
+            return Err(AgentError::ToolExecution {
+                tool_name: "file_read".into(),
+                message: format!("{} looks like a binary file", args.path),
+            });
+        }
+        Ok(match excerpt.hint {
+            Some(hint) => format!("{}\n\n[{}]", excerpt.text.trim_end_matches('\n'), hint),
+            None => excerpt.text,
+        })
+    }
+
+    async fn execute_with_images(
+        &self,
+        args: Value,
+    ) -> Result<(String, Vec<ImagePart>), AgentError> {
+        if let Some(path) = args.get("path").and_then(Value::as_str) {
+            if let Some((text, image)) = self.read_image(path).await? {
+                return Ok((text, vec![image]));
             }
-            (Some(start), None) => {
-                let lines: Vec<&str> = content.lines().collect();
-                let start = start.saturating_sub(1).min(lines.len());
-                Ok(lines[start..].join("\n"))
+        }
+        Ok((self.execute(args).await?, Vec::new()))
+    }
+}
+
+/// Part of a file read by `file_read`, with a note on how to read more
+/// when there is more.
+struct Excerpt {
+    text: String,
+    hint: Option<String>,
+}
+
+/// Up to `max_bytes` from `offset`. A read that stops short of the end is
+/// cut back to a line break when there is one in the piece read.
+async fn read_bytes(
+    file: &mut tokio::fs::File,
+    len: u64,
+    offset: u64,
+    max_bytes: usize,
+) -> std::io::Result<Excerpt> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    file.seek(std::io::SeekFrom::Start(offset)).await?;
+    let mut buf = Vec::new();
+    file.take(max_bytes as u64).read_to_end(&mut buf).await?;
+    let mut end = offset + buf.len() as u64;
+    if end < len {
+        if let Some(nl) = buf.iter().rposition(|b| *b == b'\n') {
+            buf.truncate(nl + 1);
+            end = offset + buf.len() as u64;
+        }
+    }
+    let hint = (end < len).then(|| {
+        format!(
+            "Showing bytes {}-{} of {}. Continue with offset: {}, or use tail to read the end",
+            offset, end, len, end
+        )
+    });
+    Ok(Excerpt {
+        text: String::from_utf8_lossy(&buf).into_owned(),
+        hint,
+    })
+}
+
+/// Lines `start..=end` (1-based), or from `start` to the end of the file,
+/// stopping once `max_bytes` have been collected. Skipped lines are not
+/// buffered, so a range deep inside a huge file costs only the range.
+async fn read_lines(
+    file: tokio::fs::File,
+    start: usize,
+    end: Option<usize>,
+    max_bytes: usize,
+) -> std::io::Result<Excerpt> {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+    let start = start.max(1);
+    let mut reader = tokio::io::BufReader::new(file);
+    let mut line_no = 1;
+    let mut pos: u64 = 0;
+
+    // Skip to `start` a buffer at a time.
+    while line_no < start {
+        let chunk = reader.fill_buf().await?;
+        if chunk.is_empty() {
+            return Ok(Excerpt { text: String::new(), hint: None });
+        }
+        let used = match chunk.iter().position(|b| *b == b'\n') {
+            Some(nl) => {
+                line_no += 1;
+                nl + 1
+            }
+            None => chunk.len(),
+        };
+        reader.consume(used);
+        pos += used as u64;
+    }
+
+    let mut out: Vec<u8> = Vec::new();
+    let mut line = Vec::new();
+    let mut hint = None;
+    while end.is_none_or(|end| line_no <= end) {
+        line.clear();
+        let read = (&mut reader)
+            .take(max_bytes.saturating_sub(out.len()) as u64 + 1)
+            .read_until(b'\n', &mut line)
+            .await?;
+        if read == 0 {
+            break;
+        }
+        if !line.ends_with(b"\n") && out.len() + line.len() > max_bytes {
+            hint = Some(if out.is_empty() {
+                format!(
+                    "Line {} is longer than {} bytes; read it in pieces with offset: {} and max_bytes",
+                    line_no, max_bytes, pos
+                )
+            } else {
+                format!(
+                    "Stopped at the {}-byte limit after line {}. Continue with start_line: {}",
+                    max_bytes,
+                    line_no - 1,
+                    line_no
+                )
+            });
+            if out.is_empty() {
+                out.extend_from_slice(&line[..max_bytes]);
             }
-            _ => Ok(content),
+            break;
         }
+        pos += read as u64;
+        out.extend_from_slice(&line);
+        line_no += 1;
+    }
+    if hint.is_none() && !reader.fill_buf().await?.is_empty() {
+        hint = Some(format!("More lines follow. Continue with start_line: {}", line_no));
+    }
+
+    // Lines are joined without a trailing line break.
+    while out.last().is_some_and(|b| *b == b'\n' || *b == b'\r') {
+        out.pop();
     }
+    Ok(Excerpt {
+        text: String::from_utf8_lossy(&out).into_owned(),
+        hint,
+    })
+}
+
+/// The last `n` lines, read backwards from the end of the file a chunk at
+/// a time and limited to the final `max_bytes`.
+async fn read_tail(
+    file: &mut tokio::fs::File,
+    len: u64,
+    n: usize,
+    max_bytes: usize,
+) -> std::io::Result<Excerpt> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let mut pos = len;
+    let mut buf: Vec<u8> = Vec::new();
+    // A final line break does not start another line.
+    let newlines_needed = |buf: &[u8]| {
+        let body = buf.strip_suffix(b"\n").unwrap_or(buf);
+        body.iter().filter(|b| **b == b'\n').count() < n
+    };
+    while pos > 0 && buf.len() <= max_bytes && newlines_needed(&buf) {
+        let size = TAIL_CHUNK_BYTES.min(pos);
+        pos -= size;
+        file.seek(std::io::SeekFrom::Start(pos)).await?;
+        let mut chunk = vec![0; size as usize];
+        file.read_exact(&mut chunk).await?;
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+    }
+
+    let body = buf.strip_suffix(b"\n").unwrap_or(&buf);
+    let mut start = body.len();
+    for _ in 0..n {
+        match body[..start].iter().rposition(|b| *b == b'\n') {
+            Some(nl) => start = nl,
+            None => {
+                start = 0;
+                break;
+            }
+        }
+    }
+    if body[start..].first() == Some(&b'\n') {
+        start += 1;
+    }
+    let mut capped = false;
+    if body.len() - start > max_bytes {
+        capped = true;
+        start = body.len() - max_bytes;
+        // Drop the partial line the cut landed in.
+        if let Some(nl) = body[start..].iter().position(|b| *b == b'\n') {
+            start += nl + 1;
+        }
+    }
+
+    let from = pos + start as u64;
+    let hint = (from > 0).then(|| {
+        format!(
+            "Showing bytes {}-{} of {}{}. Read what comes before with offset and max_bytes",
+            from,
+            len,
+            len,
+            if capped { format!(" (cut to the {}-byte limit)", max_bytes) } else { String::new() }
+        )
+    });
+    let text = body[start..].strip_suffix(b"\r").unwrap_or(&body[start..]);
+    Ok(Excerpt {
+        text: String::from_utf8_lossy(text).into_owned(),
+        hint,
+    })
 }
 
 // ── file_write ─────────────────────────────────────────────────────────
 
 pub struct FileWriteTool {
     pub workspace_root: Option<PathBuf>,
+    pub path_rules: Arc<PathRules>,
 }
 
 #[async_trait]
@@ -193,6 +529,19 @@ impl Tool for FileWriteTool {
         })
     }
 
+    fn affected_paths(&self, args: &Value) -> Vec<PathBuf> {
+        let Some(raw) = args.get("path").and_then(Value::as_str) else {
+            return Vec::new();
+        };
+        match validate_write_path(raw, &self.workspace_root, &self.path_rules) {
+            Ok(path) if path.is_absolute() => vec![path],
+            Ok(path) => std::env::current_dir()
+                .map(|cwd| vec![cwd.join(path)])
+                .unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
+    }
+
     async fn execute(&self, args: Value) -> Result<String, AgentError> {
         #[derive(Deserialize)]
         struct Args {
@@ -206,7 +555,8 @@ impl Tool for FileWriteTool {
             message: format!("Invalid arguments: {}", e),
         })?;
 
-        let validated_path = validate_path(&args.path, &self.workspace_root)?;
+        let validated_path =
+            validate_write_path(&args.path, &self.workspace_root, &self.path_rules)?;
 
         if let Some(parent) = validated_path.parent() {
             tokio::fs::create_dir_all(parent)
@@ -252,6 +602,7 @@ impl Tool for FileWriteTool {
 
 pub struct FileListTool {
     pub workspace_root: Option<PathBuf>,
+    pub path_rules: Arc<PathRules>,
 }
 
 #[async_trait]
@@ -261,7 +612,7 @@ impl Tool for FileListTool {
     }
 
     fn description(&self) -> &str {
-        "List files and directories at a given path. Returns names with a trailing / for directories."
+        "List files and directories at a given path. Returns names with a trailing / for directories. Skips .git/, node_modules/, target/ and anything .gitignore or .agentignore excludes unless include_ignored is set."
     }
 
     fn parameters_schema(&self) -> Value {
@@ -275,12 +626,20 @@ impl Tool for FileListTool {
                 "recursive": {
                     "type": "boolean",
                     "description": "If true, list recursively. Default: false"
+                },
+                "include_ignored": {
+                    "type": "boolean",
+                    "description": "If true, also list ignored entries such as node_modules/ and target/. Default: false"
                 }
             },
             "required": []
         })
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, args: Value) -> Result<String, AgentError> {
         #[derive(Deserialize)]
         struct Args {
@@ -288,6 +647,8 @@ impl Tool for FileListTool {
             path: String,
             #[serde(default)]
             recursive: bool,
+            #[serde(default)]
+            include_ignored: bool,
         }
         fn default_path() -> String {
             ".".into()
@@ -298,18 +659,44 @@ impl Tool for FileListTool {
             message: format!("Invalid arguments: {}", e),
         })?;
 
-        let validated_path = validate_path(&args.path, &self.workspace_root)?;
+        let validated_path = validate_path(&args.path, &self.workspace_root, &self.path_rules)?;
         let path_str = validated_path.to_string_lossy().to_string();
+        let rules = (!args.include_ignored).then(|| {
+            IgnoreRules::for_dir(&validated_path, &workspace::allowed_roots(&self.workspace_root))
+        });
+
+        // Entries `[sandbox.paths]` denies are left out altogether.
+        let roots: Vec<PathBuf> = workspace::allowed_roots(&self.workspace_root)
+            .iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .collect();
+        let denied = |path: &Path| self.path_rules.is_denied(path, &roots);
 
-        if args.recursive {
-            list_recursive(&path_str, &self.workspace_root).await
+        let (mut listing, ignored) = if args.recursive {
+            list_recursive(&path_str, &self.workspace_root, rules, &denied).await?
         } else {
-            list_flat(&path_str).await
+            list_flat(&path_str, rules.as_ref(), &denied).await?
+        };
+        if ignored > 0 {
+            if !listing.is_empty() {
+                listing.push('\n');
+            }
+            listing.push_str(&format!(
+                "[{} ignored {} hidden; pass include_ignored: true to list them]",
+                ignored,
+                if ignored == 1 { "entry" } else { "entries" }
+            ));
         }
+        Ok(listing)
     }
 }
 
-async fn list_flat(path: &str) -> Result<String, AgentError> {
+/// Returns the listing and how many entries the ignore rules hid.
+async fn list_flat(
+    path: &str,
+    rules: Option<&IgnoreRules>,
+    denied: &(dyn Fn(&Path) -> bool + Sync),
+) -> Result<(String, usize), AgentError> {
     let mut entries = tokio::fs::read_dir(path)
         .await
         .map_err(|e| AgentError::ToolExecution {
@@ -318,6 +705,7 @@ async fn list_flat(path: &str) -> Result<String, AgentError> {
         })?;
 
     let mut names = Vec::new();
+    let mut ignored = 0;
     while let Some(entry) = entries
         .next_entry()
         .await
@@ -326,28 +714,39 @@ async fn list_flat(path: &str) -> Result<String, AgentError> {
             message: format!("Failed to read entry: {}", e),
         })?
     {
+        if denied(&entry.path()) {
+            continue;
+        }
         let name = entry.file_name().to_string_lossy().to_string();
-        let meta = entry.metadata().await.ok();
-        if meta.map(|m| m.is_dir()).unwrap_or(false) {
+        let is_dir = entry.metadata().await.is_ok_and(|m| m.is_dir());
+        if rules.is_some_and(|r| r.is_ignored(&entry.path(), is_dir)) {
+            ignored += 1;
+        } else if is_dir {
             names.push(format!("{}/", name));
         } else {
             names.push(name);
         }
     }
     names.sort();
-    Ok(names.join("\n"))
+    Ok((names.join("\n"), ignored))
 }
 
 /// Maximum recursion depth to prevent runaway traversals.
 const MAX_RECURSION_DEPTH: usize = 20;
 
+/// Returns the listing and how many entries the ignore rules hid; ignored
+/// directories are neither listed nor descended into.
 async fn list_recursive(
     current: &str,
     workspace_root: &Option<PathBuf>,
-) -> Result<String, AgentError> {
+    rules: Option<IgnoreRules>,
+    denied: &(dyn Fn(&Path) -> bool + Sync),
+) -> Result<(String, usize), AgentError> {
     let mut result = Vec::new();
-    // (directory path, depth)
-    let mut stack: Vec<(String, usize)> = vec![(current.to_string(), 0)];
+    let mut ignored = 0;
+    // (directory path, depth, rules for its entries)
+    let mut stack: Vec<(String, usize, Option<IgnoreRules>)> =
+        vec![(current.to_string(), 0, rules)];
     // Track visited canonical paths to detect symlink cycles.
     let mut visited = std::collections::HashSet::new();
 
@@ -356,7 +755,7 @@ async fn list_recursive(
         visited.insert(canon);
     }
 
-    while let Some((dir, depth)) = stack.pop() {
+    while let Some((dir, depth, rules)) = stack.pop() {
         if depth >= MAX_RECURSION_DEPTH {
             continue;
         }
@@ -379,6 +778,9 @@ async fn list_recursive(
                 })?
         {
             let path = entry.path();
+            if denied(&path) {
+                continue;
+            }
             let display = path.to_string_lossy().to_string();
 
             // Use file_type() which does NOT follow symlinks (like lstat).
@@ -387,6 +789,19 @@ async fn list_recursive(
                 Err(_) => continue,
             };
 
+            if let Some(rules) = &rules {
+                let is_dir = if ft.is_symlink() {
+                    tokio::fs::metadata(&path).await.is_ok_and(|m| m.is_dir())
+                } else {
+                    ft.is_dir()
+                };
+                if rules.is_ignored(&path, is_dir) {
+                    ignored += 1;
+                    continue;
+                }
+            }
+            let child_rules = || rules.as_ref().map(|r| r.descend(&path));
+
             if ft.is_symlink() {
                 // For symlinks, resolve the target and validate it's within workspace.
                 let target = match tokio::fs::canonicalize(&path).await {
@@ -398,17 +813,14 @@ async fn list_recursive(
                     }
                 };
 
-                if let Some(root) = workspace_root {
-                    if let Ok(canon_root) = root.canonicalize() {
-                        if !target.starts_with(&canon_root) {
-                            // Symlink points outside workspace — skip.
-                            result.push(format!(
-                                "{} -> [symlink outside workspace, skipped]",
-                                display
-                            ));
-                            continue;
-                        }
-                    }
+                let roots = workspace::allowed_roots(workspace_root);
+                if !roots.is_empty() && !workspace::contains(&roots, &target) {
+                    // Symlink points outside workspace — skip.
+                    result.push(format!(
+                        "{} -> [symlink outside workspace, skipped]",
+                        display
+                    ));
+                    continue;
                 }
 
                 // Symlink target is within workspace. Check if it's a directory.
@@ -420,7 +832,7 @@ async fn list_recursive(
                     // Cycle detection: only traverse if we haven't visited this canonical path.
                     if visited.insert(target) {
                         result.push(format!("{}/", display));
-                        stack.push((display, depth + 1));
+                        stack.push((display, depth + 1, child_rules()));
                     } else {
                         result.push(format!("{} -> [symlink cycle, skipped]", display));
                     }
@@ -433,7 +845,7 @@ async fn list_recursive(
                 let is_new = canon.is_none_or(|c| visited.insert(c));
                 if is_new {
                     result.push(format!("{}/", display));
-                    stack.push((display, depth + 1));
+                    stack.push((display, depth + 1, child_rules()));
                 }
             } else {
                 result.push(display);
@@ -442,12 +854,13 @@ async fn list_recursive(
     }
 
     result.sort();
-    Ok(result.join("\n"))
+    Ok((result.join("\n"), ignored))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use agent_core::config::PathAccess;
     use agent_core::tool_registry::Tool;
     use serde_json::json;
     use tempfile::TempDir;
@@ -456,7 +869,7 @@ mod tests {
 
     #[test]
     fn test_validate_path_no_restriction() {
-        let result = validate_path("/any/path/at/all", &None);
+        let result = validate_path("/any/path/at/all", &None, &PathRules::default());
         assert!(result.is_ok());
     }
 
@@ -467,7 +880,7 @@ mod tests {
         let file = tmp.path().join("hello.txt");
         std::fs::write(&file, "hi").unwrap();
 
-        let result = validate_path(file.to_str().unwrap(), &Some(workspace));
+        let result = validate_path(file.to_str().unwrap(), &Some(workspace), &PathRules::default());
         assert!(result.is_ok());
     }
 
@@ -476,7 +889,7 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         let workspace = tmp.path().to_path_buf();
 
-        let result = validate_path("/etc/passwd", &Some(workspace));
+        let result = validate_path("/etc/passwd", &Some(workspace), &PathRules::default());
         assert!(result.is_err());
         let msg = result.unwrap_err().to_string();
         assert!(msg.contains("outside the workspace root"), "got: {msg}");
@@ -488,7 +901,7 @@ mod tests {
         let workspace = tmp.path().to_path_buf();
         let traversal = format!("{}/../../etc/passwd", workspace.display());
 
-        let result = validate_path(&traversal, &Some(workspace));
+        let result = validate_path(&traversal, &Some(workspace), &PathRules::default());
         assert!(result.is_err());
         let msg = result.unwrap_err().to_string();
         assert!(msg.contains("outside the workspace root"), "got: {msg}");
@@ -500,10 +913,35 @@ mod tests {
         let workspace = tmp.path().to_path_buf();
         let nonexistent = tmp.path().join("does_not_exist.txt");
 
-        let result = validate_path(nonexistent.to_str().unwrap(), &Some(workspace));
+        let result = validate_path(
+            nonexistent.to_str().unwrap(),
+            &Some(workspace),
+            &PathRules::default(),
+        );
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_validate_path_applies_path_rules() {
+        let tmp = TempDir::new().unwrap();
+        let workspace = Some(tmp.path().to_path_buf());
+        std::fs::create_dir_all(tmp.path().join("locked-migrations")).unwrap();
+        let rules = PathRules::new(&std::collections::BTreeMap::from([
+            ("locked-migrations/**".to_string(), PathAccess::Ro),
+            ("locked-secrets/**".to_string(), PathAccess::Deny),
+        ]));
+
+        let sql = tmp.path().join("locked-migrations/001.sql");
+        assert!(validate_path(sql.to_str().unwrap(), &workspace, &rules).is_ok());
+        let err = validate_write_path(sql.to_str().unwrap(), &workspace, &rules).unwrap_err();
+        assert!(err.to_string().contains("read-only"), "{}", err);
+        let key = tmp.path().join("locked-secrets/key");
+        let err = validate_path(key.to_str().unwrap(), &workspace, &rules).unwrap_err();
+        assert!(err.to_string().contains("denied"), "{}", err);
+        let readme = tmp.path().join("README.md");
+        assert!(validate_write_path(readme.to_str().unwrap(), &workspace, &rules).is_ok());
+    }
+
     // ── Tool::execute integration tests ─────────────────────────────
 
     #[tokio::test]
@@ -511,6 +949,7 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         let tool = FileReadTool {
             workspace_root: Some(tmp.path().to_path_buf()),
+            path_rules: Default::default(),
         };
         let result = tool.execute(json!({"path": "/etc/hostname"})).await;
         assert!(result.is_err());
@@ -523,6 +962,7 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         let tool = FileWriteTool {
             workspace_root: Some(tmp.path().to_path_buf()),
+            path_rules: Default::default(),
         };
         let result = tool
             .execute(json!({"path": "/tmp/evil.txt", "content": "pwned"}))
@@ -537,6 +977,7 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         let tool = FileListTool {
             workspace_root: Some(tmp.path().to_path_buf()),
+            path_rules: Default::default(),
         };
         let result = tool.execute(json!({"path": "/etc"})).await;
         assert!(result.is_err());
@@ -552,12 +993,77 @@ mod tests {
 
         let tool = FileReadTool {
             workspace_root: Some(tmp.path().to_path_buf()),
+            path_rules: Default::default(),
         };
         let result = tool.execute(json!({"path": file.to_str().unwrap()})).await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "hello world");
     }
 
+    #[tokio::test]
+    async fn test_file_read_modes_and_paging_hints() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("app.log");
+        let log: String = (1..=1000).map(|i| format!("line {i}\n")).collect();
+        std::fs::write(&file, &log).unwrap();
+        let path = file.to_str().unwrap();
+        let tool = FileReadTool {
+            workspace_root: Some(tmp.path().to_path_buf()),
+            path_rules: Default::default(),
+        };
+        let read = |args: Value| tool.execute(args);
+
+        let head = read(json!({"path": path, "head": 2})).await.unwrap();
+        assert_eq!(head, "line 1\nline 2\n\n[More lines follow. Continue with start_line: 3]");
+
+        let tail = read(json!({"path": path, "tail": 2})).await.unwrap();
+        assert!(tail.starts_with("line 999\nline 1000\n\n[Showing bytes "), "got: {tail}");
+
+        let range = read(json!({"path": path, "start_line": 500, "end_line": 501}))
+            .await
+            .unwrap();
+        assert!(range.starts_with("line 500\nline 501\n"), "got: {range}");
+
+        // A capped read stops at a line break and says where to continue.
+        let first = read(json!({"path": path, "max_bytes": 20})).await.unwrap();
+        assert_eq!(
+            first,
+            "line 1\nline 2\n\n[Showing bytes 0-14 of 8893. Continue with offset: 14, or use tail to read the end]"
+        );
+        let next = read(json!({"path": path, "offset": 14, "max_bytes": 7})).await.unwrap();
+        assert!(next.starts_with("line 3\n\n[Showing bytes 14-21"), "got: {next}");
+
+        let capped = read(json!({"path": path, "start_line": 10, "max_bytes": 16}))
+            .await
+            .unwrap();
+        assert!(capped.ends_with("Continue with start_line: 12]"), "got: {capped}");
+
+        let err = read(json!({"path": path, "head": 1, "tail": 1})).await.unwrap_err();
+        assert!(err.to_string().contains("only one of"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_file_read_attaches_images() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("diagram.PNG");
+        std::fs::write(&file, b"\x89PNG\r\n\x1a\n").unwrap();
+
+        let tool = FileReadTool {
+            workspace_root: Some(tmp.path().to_path_buf()),
+            path_rules: Default::default(),
+        };
+        let (text, images) = tool
+            .execute_with_images(json!({"path": file.to_str().unwrap()}))
+            .await
+            .unwrap();
+        assert!(text.contains("image/png, 8 bytes"), "got: {text}");
+        assert_eq!(images, vec![ImagePart::from_bytes("image/png", b"\x89PNG\r\n\x1a\n")]);
+
+        // Text-only callers get the description instead of a UTF-8 error.
+        let plain = tool.execute(json!({"path": file.to_str().unwrap()})).await;
+        assert_eq!(plain.unwrap(), text);
+    }
+
     #[tokio::test]
     async fn test_recursive_list_skips_symlink_outside_workspace() {
         let tmp = TempDir::new().unwrap();
@@ -575,6 +1081,7 @@ mod tests {
 
         let tool = FileListTool {
             workspace_root: Some(workspace.clone()),
+            path_rules: Default::default(),
         };
         let result = tool
             .execute(json!({"path": workspace.to_str().unwrap(), "recursive": true}))
@@ -609,6 +1116,7 @@ mod tests {
 
         let tool = FileListTool {
             workspace_root: Some(workspace.clone()),
+            path_rules: Default::default(),
         };
         let result = tool
             .execute(json!({"path": workspace.to_str().unwrap(), "recursive": true}))
@@ -616,4 +1124,67 @@ mod tests {
         // Should complete without infinite loop.
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_list_skips_ignored_entries() {
+        let tmp = TempDir::new().unwrap();
+        let workspace = tmp.path().to_path_buf();
+        std::fs::create_dir_all(workspace.join("node_modules/react")).unwrap();
+        std::fs::create_dir_all(workspace.join("src/generated")).unwrap();
+        std::fs::write(workspace.join("src/main.rs"), "").unwrap();
+        std::fs::write(workspace.join("src/generated/api.rs"), "").unwrap();
+        std::fs::write(workspace.join(".agentignore"), "generated/\n").unwrap();
+
+        let tool = FileListTool {
+            workspace_root: Some(workspace.clone()),
+            path_rules: Default::default(),
+        };
+        let listing = tool
+            .execute(json!({"path": workspace, "recursive": true}))
+            .await
+            .unwrap();
+        assert!(listing.contains("src/main.rs"), "got:\n{listing}");
+        assert!(!listing.contains("node_modules"), "got:\n{listing}");
+        assert!(!listing.contains("generated"), "got:\n{listing}");
+        assert!(listing.ends_with("[2 ignored entries hidden; pass include_ignored: true to list them]"));
+
+        // Listing a subdirectory still honours the workspace's .agentignore.
+        let sub = workspace::scope(vec![workspace.clone()], tool.execute(json!({"path": "src"})))
+            .await
+            .unwrap();
+        assert!(!sub.contains("generated"), "got:\n{sub}");
+
+        let all = tool
+            .execute(json!({"path": workspace, "recursive": true, "include_ignored": true}))
+            .await
+            .unwrap();
+        assert!(all.contains("node_modules/react/"), "got:\n{all}");
+        assert!(all.contains("generated/api.rs"), "got:\n{all}");
+    }
+
+    #[tokio::test]
+    async fn test_active_workspaces_replace_configured_root() {
+        let configured = TempDir::new().unwrap();
+        let (repo_a, repo_b) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        std::fs::write(repo_a.path().join("a.txt"), "from a").unwrap();
+        std::fs::write(repo_b.path().join("b.txt"), "from b").unwrap();
+
+        let tool = FileReadTool {
+            workspace_root: Some(configured.path().to_path_buf()),
+            path_rules: Default::default(),
+        };
+        let roots = vec![repo_a.path().to_path_buf(), repo_b.path().to_path_buf()];
+        workspace::scope(roots, async {
+            // Relative paths start in the primary workspace.
+            assert_eq!(tool.execute(json!({"path": "a.txt"})).await.unwrap(), "from a");
+            let b = repo_b.path().join("b.txt");
+            assert_eq!(tool.execute(json!({"path": b})).await.unwrap(), "from b");
+            let err = tool
+                .execute(json!({"path": configured.path().join("x")}))
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("outside the active workspaces"), "{err}");
+        })
+        .await;
+    }
 }
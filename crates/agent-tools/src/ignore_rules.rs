@@ -0,0 +1,129 @@
+//! Ignore rules for tools that walk the workspace.
+//!
+//! Listings skip `.git/`, `node_modules/` and `target/` plus whatever the
+//! `.gitignore` and `.agentignore` files on the way down exclude, so a
+//! recursive listing is not buried under build output. Rules nest the way
+//! git's do: a deeper file overrides a shallower one, and `!pattern`
+//! re-includes. Tools take an `include_ignored` flag for the cases where
+//! the agent really needs those files.
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Skipped everywhere unless an ignore file re-includes them.
+pub const DEFAULT_PATTERNS: &[&str] = &[".git/", "node_modules/", "target/"];
+
+/// Ignore files read in each directory, in increasing precedence.
+pub const IGNORE_FILES: &[&str] = &[".gitignore", ".agentignore"];
+
+/// The rules in effect for one directory: the defaults, then one layer per
+/// ancestor with an ignore file, shallowest first.
+#[derive(Clone)]
+pub struct IgnoreRules {
+    layers: Vec<Arc<Gitignore>>,
+}
+
+impl IgnoreRules {
+    /// Rules for walking `dir`, picking up ignore files from its ancestors
+    /// up to the enclosing git repository or one of `roots`, whichever
+    /// comes first.
+    pub fn for_dir(dir: &Path, roots: &[PathBuf]) -> Self {
+        let mut defaults = GitignoreBuilder::new(dir);
+        for pattern in DEFAULT_PATTERNS {
+            // The patterns are constant and valid.
+            let _ = defaults.add_line(None, pattern);
+        }
+        let mut rules = Self {
+            layers: defaults.build().ok().map(Arc::new).into_iter().collect(),
+        };
+
+        let roots: Vec<PathBuf> = roots.iter().filter_map(|r| r.canonicalize().ok()).collect();
+        let ancestors: Vec<&Path> = dir.ancestors().collect();
+        let top = ancestors
+            .iter()
+            .position(|a| a.join(".git").exists() || roots.iter().any(|r| r == a))
+            // Not inside a repository or workspace: only `dir`'s own files.
+            .unwrap_or(0);
+        for ancestor in ancestors[..=top].iter().rev() {
+            rules = rules.descend(ancestor);
+        }
+        rules
+    }
+
+    /// Rules for `dir`, a subdirectory of the one these rules are for.
+    pub fn descend(&self, dir: &Path) -> Self {
+        let files: Vec<PathBuf> = IGNORE_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .filter(|path| path.is_file())
+            .collect();
+        if files.is_empty() {
+            return self.clone();
+        }
+
+        let mut builder = GitignoreBuilder::new(dir);
+        for file in &files {
+            if let Some(err) = builder.add(file) {
+                tracing::warn!("Ignoring bad pattern in {}: {}", file.display(), err);
+            }
+        }
+        let mut rules = self.clone();
+        match builder.build() {
+            Ok(layer) => rules.layers.push(Arc::new(layer)),
+            Err(e) => tracing::warn!("Failed to load ignore rules in {}: {}", dir.display(), e),
+        }
+        rules
+    }
+
+    /// Whether `path`, an entry of the directory these rules are for, is
+    /// ignored. The deepest layer with an opinion decides.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for layer in self.layers.iter().rev() {
+            match layer.matched(path, is_dir) {
+                Match::None => continue,
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_ignore_files_and_whitelist() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("app/dist")).unwrap();
+        std::fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(root.join("app/.agentignore"), "dist/\n!keep.log\n").unwrap();
+
+        let rules = IgnoreRules::for_dir(&root.join("app"), &[]);
+        assert!(rules.is_ignored(&root.join("app/node_modules"), true));
+        assert!(rules.is_ignored(&root.join("app/dist"), true));
+        assert!(rules.is_ignored(&root.join("app/debug.log"), false));
+        assert!(!rules.is_ignored(&root.join("app/keep.log"), false));
+        assert!(!rules.is_ignored(&root.join("app/main.rs"), false));
+        // A file named like a default directory is kept.
+        assert!(!rules.is_ignored(&root.join("app/target"), false));
+    }
+
+    #[test]
+    fn test_ancestors_stop_outside_repositories() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join(".agentignore"), "secret.txt\n").unwrap();
+
+        let rules = IgnoreRules::for_dir(&root.join("sub"), &[]);
+        assert!(!rules.is_ignored(&root.join("sub/secret.txt"), false));
+        let rules = IgnoreRules::for_dir(&root.join("sub"), &[root.to_path_buf()]);
+        assert!(rules.is_ignored(&root.join("sub/secret.txt"), false));
+    }
+}
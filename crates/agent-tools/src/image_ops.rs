@@ -0,0 +1,796 @@
+//! Image tools.
+//!
+//! `image_generate` sends a prompt to the `[images]` endpoint, either an
+//! OpenAI-compatible images API or a Stable Diffusion WebUI, saves the
+//! results under the workspace and attaches them for vision-capable models.
+//! It is registered only when `[images] url` is set. `image_transform`
+//! crops, resizes, rotates, flips and converts image files locally.
+
+use agent_core::config::{ImageBackend, ImagesConfig};
+use agent_core::error::AgentError;
+use agent_core::path_rules::PathRules;
+use agent_core::tool_registry::Tool;
+use agent_core::types::ImagePart;
+use agent_core::workspace;
+use async_trait::async_trait;
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::file_ops::{validate_path, validate_write_path};
+
+/// Most images one `image_generate` call asks for.
+const MAX_COUNT: u32 = 4;
+/// Largest image attached for the model; bigger files are only saved.
+const MAX_ATTACH_BYTES: usize = 5 * 1024 * 1024;
+/// Largest file `image_transform` decodes.
+const MAX_INPUT_BYTES: u64 = 64 * 1024 * 1024;
+/// Largest width or height either tool produces.
+const MAX_DIMENSION: u32 = 8192;
+
+fn err(tool: &str, msg: impl Into<String>) -> AgentError {
+    AgentError::ToolExecution {
+        tool_name: tool.into(),
+        message: msg.into(),
+    }
+}
+
+/// Parse `WIDTHxHEIGHT`.
+fn parse_size(size: &str) -> Option<(u32, u32)> {
+    let (w, h) = size.trim().split_once(['x', 'X'])?;
+    let (w, h) = (w.trim().parse().ok()?, h.trim().parse().ok()?);
+    (w > 0 && h > 0 && w <= MAX_DIMENSION && h <= MAX_DIMENSION).then_some((w, h))
+}
+
+/// File name stem from the first words of a prompt.
+fn slug(prompt: &str) -> String {
+    let mut slug = String::new();
+    for c in prompt.chars() {
+        if slug.len() >= 40 {
+            break;
+        }
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "image".into()
+    } else {
+        slug.into()
+    }
+}
+
+/// Decode a base64 image, with or without a `data:` URL prefix.
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let data = match data.split_once(";base64,") {
+        Some((_, rest)) => rest,
+        None => data,
+    };
+    base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .ok()
+}
+
+/// Generate images from a prompt through the configured endpoint.
+pub struct ImageGenerateTool {
+    config: ImagesConfig,
+    workspace_root: Option<PathBuf>,
+    path_rules: Arc<PathRules>,
+    client: reqwest::Client,
+}
+
+/// One generated image, written to `path`.
+struct Generated {
+    path: PathBuf,
+    bytes: Vec<u8>,
+    format: ImageFormat,
+}
+
+impl ImageGenerateTool {
+    pub fn new(
+        config: &ImagesConfig,
+        workspace_root: Option<PathBuf>,
+        path_rules: Arc<PathRules>,
+    ) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .user_agent("agent-shell/0.1 (image_generate)")
+            .build()
+            .unwrap_or_default();
+        Self {
+            config: config.clone(),
+            workspace_root,
+            path_rules,
+            client,
+        }
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        err("image_generate", msg)
+    }
+
+    /// Ask the endpoint for `n` images; returns the encoded files.
+    async fn request(
+        &self,
+        prompt: &str,
+        negative_prompt: Option<&str>,
+        (width, height): (u32, u32),
+        n: u32,
+    ) -> Result<Vec<Vec<u8>>, AgentError> {
+        let base = self
+            .config
+            .url
+            .as_deref()
+            .ok_or_else(|| Self::err("No [images] url configured"))?
+            .trim_end_matches('/');
+        let (url, body) = match self.config.backend {
+            ImageBackend::Openai => {
+                let mut prompt = prompt.to_string();
+                if let Some(negative) = negative_prompt {
+                    prompt.push_str(&format!("\n\nAvoid: {}", negative));
+                }
+                (
+                    format!("{}/images/generations", base),
+                    json!({
+                        "model": self.config.model,
+                        "prompt": prompt,
+                        "n": n,
+                        "size": format!("{}x{}", width, height),
+                    }),
+                )
+            }
+            ImageBackend::SdWebui => (
+                format!("{}/sdapi/v1/txt2img", base),
+                json!({
+                    "prompt": prompt,
+                    "negative_prompt": negative_prompt.unwrap_or_default(),
+                    "width": width,
+                    "height": height,
+                    "batch_size": n,
+                }),
+            ),
+        };
+
+        let mut req = self.client.post(&url).json(&body);
+        if let Some(key) = &self.config.api_key {
+            req = req.bearer_auth(key);
+        }
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| Self::err(format!("Request to {} failed: {}", url, e)))?;
+        let status = resp.status();
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| Self::err(format!("Failed to read response: {}", e)))?;
+        if !status.is_success() {
+            let detail: String = text.chars().take(300).collect();
+            return Err(Self::err(format!(
+                "Image endpoint returned {}: {}",
+                status, detail
+            )));
+        }
+        let value: Value = serde_json::from_str(&text)
+            .map_err(|e| Self::err(format!("Invalid response from {}: {}", url, e)))?;
+
+        let mut images = Vec::new();
+        match self.config.backend {
+            ImageBackend::Openai => {
+                for item in value["data"].as_array().into_iter().flatten() {
+                    if let Some(data) = item["b64_json"].as_str() {
+                        images.push(
+                            decode_base64(data)
+                                .ok_or_else(|| Self::err("Response has invalid base64 data"))?,
+                        );
+                    } else if let Some(link) = item["url"].as_str() {
+                        let bytes = self
+                            .client
+                            .get(link)
+                            .send()
+                            .await
+                            .and_then(|r| r.error_for_status())
+                            .map_err(|e| Self::err(format!("Failed to download {}: {}", link, e)))?
+                            .bytes()
+                            .await
+                            .map_err(|e| Self::err(format!("Failed to download {}: {}", link, e)))?;
+                        images.push(bytes.to_vec());
+                    }
+                }
+            }
+            ImageBackend::SdWebui => {
+                for data in value["images"].as_array().into_iter().flatten() {
+                    let data = data.as_str().unwrap_or_default();
+                    images.push(
+                        decode_base64(data)
+                            .ok_or_else(|| Self::err("Response has invalid base64 data"))?,
+                    );
+                }
+            }
+        }
+        if images.is_empty() {
+            return Err(Self::err("Image endpoint returned no images"));
+        }
+        Ok(images)
+    }
+
+    /// Generate and save the images; returns the description and the files.
+    async fn generate(&self, args: Value) -> Result<(String, Vec<Generated>), AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            prompt: String,
+            negative_prompt: Option<String>,
+            size: Option<String>,
+            n: Option<u32>,
+            path: Option<String>,
+        }
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| Self::err(format!("Invalid arguments: {}", e)))?;
+        if args.prompt.trim().is_empty() {
+            return Err(Self::err("prompt must not be empty"));
+        }
+        let size_str = args.size.as_deref().unwrap_or(&self.config.size);
+        let size = parse_size(size_str).ok_or_else(|| {
+            Self::err(format!(
+                "Invalid size '{}': use WIDTHxHEIGHT, at most {} each",
+                size_str, MAX_DIMENSION
+            ))
+        })?;
+        let n = args.n.unwrap_or(1).clamp(1, MAX_COUNT);
+
+        let images = self
+            .request(&args.prompt, args.negative_prompt.as_deref(), size, n)
+            .await?;
+
+        let stem = match &args.path {
+            Some(path) => {
+                let path = Path::new(path);
+                path.with_extension("").to_string_lossy().into_owned()
+            }
+            None => {
+                let base = match workspace::primary_root(&self.workspace_root) {
+                    Some(root) => root,
+                    None => std::env::current_dir()
+                        .map_err(|e| Self::err(format!("No current directory: {}", e)))?,
+                };
+                let name = format!(
+                    "{}-{}",
+                    slug(&args.prompt),
+                    chrono::Local::now().format("%Y%m%d-%H%M%S")
+                );
+                base.join(&self.config.output_dir)
+                    .join(name)
+                    .to_string_lossy()
+                    .into_owned()
+            }
+        };
+
+        let mut generated = Vec::new();
+        let mut lines = Vec::new();
+        let count = images.len();
+        for (i, bytes) in images.into_iter().enumerate() {
+            let format = image::guess_format(&bytes)
+                .map_err(|_| Self::err("Image endpoint returned data that is not an image"))?;
+            let ext = format.extensions_str().first().copied().unwrap_or("img");
+            let raw = if count == 1 {
+                format!("{}.{}", stem, ext)
+            } else {
+                format!("{}-{}.{}", stem, i + 1, ext)
+            };
+            let path = validate_write_path(&raw, &self.workspace_root, &self.path_rules)?;
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                    Self::err(format!("Failed to create {}: {}", parent.display(), e))
+                })?;
+            }
+            tokio::fs::write(&path, &bytes)
+                .await
+                .map_err(|e| Self::err(format!("Failed to write {}: {}", path.display(), e)))?;
+            let dims = ImageReader::with_format(Cursor::new(&bytes), format)
+                .into_dimensions()
+                .map(|(w, h)| format!("{}x{}, ", w, h))
+                .unwrap_or_default();
+            lines.push(format!("  {} ({}{} bytes)", path.display(), dims, bytes.len()));
+            generated.push(Generated {
+                path,
+                bytes,
+                format,
+            });
+        }
+
+        let text = format!(
+            "Generated {} image{}:\n{}",
+            generated.len(),
+            if generated.len() == 1 { "" } else { "s" },
+            lines.join("\n")
+        );
+        Ok((text, generated))
+    }
+}
+
+#[async_trait]
+impl Tool for ImageGenerateTool {
+    fn name(&self) -> &str {
+        "image_generate"
+    }
+
+    fn description(&self) -> &str {
+        "Generate images from a text prompt with the configured image model and save them in \
+         the workspace (default: .agent-shell/images/). The images are attached for viewing \
+         when the model supports images. Use it for mockups, icons and illustrations."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "prompt": {
+                    "type": "string",
+                    "description": "What the image should show"
+                },
+                "negative_prompt": {
+                    "type": "string",
+                    "description": "What to keep out of the image"
+                },
+                "size": {
+                    "type": "string",
+                    "description": "WIDTHxHEIGHT, e.g. 1024x1024 (default from config)"
+                },
+                "n": {
+                    "type": "integer",
+                    "description": "Number of images (1-4, default 1)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Output path; the extension follows the returned format and several images get -1, -2, ... suffixes"
+                }
+            },
+            "required": ["prompt"]
+        })
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        Ok(self.generate(args).await?.0)
+    }
+
+    async fn execute_with_images(
+        &self,
+        args: Value,
+    ) -> Result<(String, Vec<ImagePart>), AgentError> {
+        let (mut text, generated) = self.generate(args).await?;
+        let mut images = Vec::new();
+        for image in generated {
+            if image.bytes.len() > MAX_ATTACH_BYTES {
+                text.push_str(&format!(
+                    "\n{} is too large to attach",
+                    image.path.display()
+                ));
+                continue;
+            }
+            images.push(ImagePart::from_bytes(
+                image.format.to_mime_type(),
+                &image.bytes,
+            ));
+        }
+        Ok((text, images))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Crop {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Resize {
+    width: Option<u32>,
+    height: Option<u32>,
+    /// With both sides given: fit inside the box instead of stretching.
+    #[serde(default)]
+    keep_aspect: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransformArgs {
+    path: String,
+    output: Option<String>,
+    crop: Option<Crop>,
+    resize: Option<Resize>,
+    rotate: Option<u32>,
+    flip: Option<String>,
+    format: Option<String>,
+}
+
+/// Crop, resize, rotate, flip and convert image files.
+pub struct ImageTransformTool {
+    pub workspace_root: Option<PathBuf>,
+    pub path_rules: Arc<PathRules>,
+}
+
+impl ImageTransformTool {
+    fn err(msg: impl Into<String>) -> AgentError {
+        err("image_transform", msg)
+    }
+
+    /// Output path and format: `output` and `format` as given, otherwise
+    /// derived from each other or from the input, with `-edited` added so
+    /// the source is not overwritten by default.
+    fn target(&self, args: &TransformArgs) -> Result<(PathBuf, ImageFormat), AgentError> {
+        let input = validate_path(&args.path, &self.workspace_root, &self.path_rules)?;
+        let requested = match &args.format {
+            Some(name) => Some(
+                ImageFormat::from_extension(name.trim_start_matches('.'))
+                    .filter(|f| writable(*f))
+                    .ok_or_else(|| {
+                        Self::err(format!(
+                            "Unsupported format '{}': use png, jpeg, webp, gif or bmp",
+                            name
+                        ))
+                    })?,
+            ),
+            None => None,
+        };
+
+        let output = match &args.output {
+            Some(raw) => validate_write_path(raw, &self.workspace_root, &self.path_rules)?,
+            None => {
+                let stem = input
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "image".into());
+                let ext = match requested {
+                    Some(format) => format.extensions_str()[0].to_string(),
+                    None => input
+                        .extension()
+                        .map(|e| e.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| "png".into()),
+                };
+                let output = input.with_file_name(format!("{}-edited.{}", stem, ext));
+                validate_write_path(
+                    &output.to_string_lossy(),
+                    &self.workspace_root,
+                    &self.path_rules,
+                )?
+            }
+        };
+        let format = match requested {
+            Some(format) => format,
+            None => ImageFormat::from_path(&output)
+                .ok()
+                .filter(|f| writable(*f))
+                .ok_or_else(|| {
+                    Self::err(format!(
+                        "Cannot tell the output format of {}; pass format",
+                        output.display()
+                    ))
+                })?,
+        };
+        Ok((output, format))
+    }
+}
+
+/// Formats `image_transform` can write.
+fn writable(format: ImageFormat) -> bool {
+    matches!(
+        format,
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP | ImageFormat::Gif | ImageFormat::Bmp
+    )
+}
+
+/// Apply the operations in order: crop, resize, rotate, flip.
+fn transform(mut img: DynamicImage, args: &TransformArgs) -> Result<DynamicImage, String> {
+    if let Some(crop) = &args.crop {
+        let fits = crop.width > 0
+            && crop.height > 0
+            && crop.x.checked_add(crop.width).is_some_and(|r| r <= img.width())
+            && crop.y.checked_add(crop.height).is_some_and(|b| b <= img.height());
+        if !fits {
+            return Err(format!(
+                "Crop {}x{}+{}+{} is outside the {}x{} image",
+                crop.width,
+                crop.height,
+                crop.x,
+                crop.y,
+                img.width(),
+                img.height()
+            ));
+        }
+        img = img.crop_imm(crop.x, crop.y, crop.width, crop.height);
+    }
+
+    if let Some(resize) = &args.resize {
+        let (w, h) = (img.width() as f64, img.height() as f64);
+        let (width, height) = match (resize.width, resize.height) {
+            (Some(width), Some(height)) => (width, height),
+            (Some(width), None) => (width, (h * width as f64 / w).round().max(1.0) as u32),
+            (None, Some(height)) => ((w * height as f64 / h).round().max(1.0) as u32, height),
+            (None, None) => return Err("resize needs width, height or both".into()),
+        };
+        if width == 0 || height == 0 || width > MAX_DIMENSION || height > MAX_DIMENSION {
+            return Err(format!(
+                "Resize to {}x{} is out of range (1-{} per side)",
+                width, height, MAX_DIMENSION
+            ));
+        }
+        img = if resize.keep_aspect {
+            img.resize(width, height, FilterType::Lanczos3)
+        } else {
+            img.resize_exact(width, height, FilterType::Lanczos3)
+        };
+    }
+
+    img = match args.rotate {
+        None | Some(0) => img,
+        Some(90) => img.rotate90(),
+        Some(180) => img.rotate180(),
+        Some(270) => img.rotate270(),
+        Some(other) => return Err(format!("rotate must be 90, 180 or 270, not {}", other)),
+    };
+
+    match args.flip.as_deref() {
+        None => {}
+        Some("horizontal") => img = img.fliph(),
+        Some("vertical") => img = img.flipv(),
+        Some(other) => {
+            return Err(format!(
+                "flip must be \"horizontal\" or \"vertical\", not \"{}\"",
+                other
+            ))
+        }
+    }
+    Ok(img)
+}
+
+#[async_trait]
+impl Tool for ImageTransformTool {
+    fn name(&self) -> &str {
+        "image_transform"
+    }
+
+    fn description(&self) -> &str {
+        "Crop, resize, rotate, flip or convert an image file (PNG, JPEG, WebP, GIF, BMP). \
+         Operations apply in that order. Writes <name>-edited.<ext> next to the input unless \
+         output is given."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Image to transform"
+                },
+                "output": {
+                    "type": "string",
+                    "description": "Output path (default: <name>-edited.<ext> next to the input)"
+                },
+                "crop": {
+                    "type": "object",
+                    "description": "Region to keep, in pixels from the top-left corner",
+                    "properties": {
+                        "x": { "type": "integer" },
+                        "y": { "type": "integer" },
+                        "width": { "type": "integer" },
+                        "height": { "type": "integer" }
+                    },
+                    "required": ["x", "y", "width", "height"]
+                },
+                "resize": {
+                    "type": "object",
+                    "description": "New size; give one side to keep the aspect ratio",
+                    "properties": {
+                        "width": { "type": "integer" },
+                        "height": { "type": "integer" },
+                        "keep_aspect": {
+                            "type": "boolean",
+                            "description": "With both sides: fit inside them instead of stretching"
+                        }
+                    }
+                },
+                "rotate": {
+                    "type": "integer",
+                    "enum": [90, 180, 270],
+                    "description": "Clockwise rotation in degrees"
+                },
+                "flip": {
+                    "type": "string",
+                    "enum": ["horizontal", "vertical"]
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["png", "jpeg", "webp", "gif", "bmp"],
+                    "description": "Output format (default: from the output extension)"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn affected_paths(&self, args: &Value) -> Vec<PathBuf> {
+        serde_json::from_value::<TransformArgs>(args.clone())
+            .ok()
+            .and_then(|args| self.target(&args).ok())
+            .map(|(path, _)| path)
+            .filter(|path| path.is_absolute())
+            .into_iter()
+            .collect()
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        let args: TransformArgs = serde_json::from_value(args)
+            .map_err(|e| Self::err(format!("Invalid arguments: {}", e)))?;
+        let input = validate_path(&args.path, &self.workspace_root, &self.path_rules)?;
+        let (output, format) = self.target(&args)?;
+
+        tokio::task::spawn_blocking(move || {
+            let size = std::fs::metadata(&input)
+                .map_err(|e| Self::err(format!("Cannot read {}: {}", input.display(), e)))?
+                .len();
+            if size > MAX_INPUT_BYTES {
+                return Err(Self::err(format!(
+                    "{} is {} bytes; image_transform handles up to {}",
+                    input.display(),
+                    size,
+                    MAX_INPUT_BYTES
+                )));
+            }
+            let img = ImageReader::open(&input)
+                .and_then(|r| r.with_guessed_format())
+                .map_err(|e| Self::err(format!("Cannot read {}: {}", input.display(), e)))?
+                .decode()
+                .map_err(|e| Self::err(format!("Cannot decode {}: {}", input.display(), e)))?;
+            let before = (img.width(), img.height());
+
+            let mut img = transform(img, &args).map_err(Self::err)?;
+            // JPEG has no alpha channel.
+            if format == ImageFormat::Jpeg && img.color().has_alpha() {
+                img = DynamicImage::ImageRgb8(img.to_rgb8());
+            }
+            if let Some(parent) = output.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| {
+                    Self::err(format!("Failed to create {}: {}", parent.display(), e))
+                })?;
+            }
+            img.save_with_format(&output, format)
+                .map_err(|e| Self::err(format!("Failed to write {}: {}", output.display(), e)))?;
+
+            let written = std::fs::metadata(&output).map(|m| m.len()).unwrap_or(0);
+            Ok(format!(
+                "Wrote {} ({}, {}x{} -> {}x{}, {} bytes)",
+                output.display(),
+                format.extensions_str()[0].to_ascii_uppercase(),
+                before.0,
+                before.1,
+                img.width(),
+                img.height(),
+                written
+            ))
+        })
+        .await
+        .map_err(|e| Self::err(format!("Task failed: {}", e)))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let img = image::RgbaImage::from_fn(width, height, |x, _| {
+            image::Rgba([(x * 10) as u8, 0, 0, 255])
+        });
+        let mut out = Cursor::new(Vec::new());
+        DynamicImage::ImageRgba8(img)
+            .write_to(&mut out, ImageFormat::Png)
+            .unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn test_parse_size_and_slug() {
+        assert_eq!(parse_size("512x768"), Some((512, 768)));
+        assert_eq!(parse_size("0x10"), None);
+        assert_eq!(parse_size("big"), None);
+        assert_eq!(slug("A red Fox, jumping!"), "a-red-fox-jumping");
+        assert_eq!(slug("!!!"), "image");
+    }
+
+    #[tokio::test]
+    async fn test_transform_crop_resize_convert() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::write(root.join("in.png"), png(40, 20)).unwrap();
+        let tool = ImageTransformTool {
+            workspace_root: Some(root.clone()),
+            path_rules: Default::default(),
+        };
+
+        let input = root.join("in.png").to_string_lossy().into_owned();
+        let args = json!({
+            "path": input,
+            "crop": {"x": 0, "y": 0, "width": 20, "height": 20},
+            "resize": {"width": 10},
+            "rotate": 90,
+            "format": "jpeg"
+        });
+        assert_eq!(tool.affected_paths(&args), [root.join("in-edited.jpg")]);
+        let out = tool.execute(args).await.unwrap();
+        assert!(out.contains("40x20 -> 10x10"), "{out}");
+        let img = image::open(root.join("in-edited.jpg")).unwrap();
+        assert_eq!((img.width(), img.height()), (10, 10));
+
+        let out = tool
+            .execute(json!({
+                "path": input,
+                "output": root.join("out/small.webp"),
+                "resize": {"height": 5}
+            }))
+            .await
+            .unwrap();
+        assert!(out.contains("10x5"), "{out}");
+        assert!(root.join("out/small.webp").exists());
+
+        let err = tool
+            .execute(json!({"path": input, "crop": {"x": 30, "y": 0, "width": 20, "height": 5}}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("outside the 40x20 image"), "{err}");
+        assert!(tool
+            .execute(json!({"path": input, "output": "/etc/x.png"}))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_generate_saves_and_attaches_images() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(png(8, 4));
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 8192];
+            let n = stream.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            assert!(request.starts_with("POST /sdapi/v1/txt2img"), "{request}");
+            let body = json!({ "images": [encoded.clone(), encoded] }).to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let config = ImagesConfig {
+            backend: ImageBackend::SdWebui,
+            url: Some(format!("http://{}", addr)),
+            ..Default::default()
+        };
+        let tool = ImageGenerateTool::new(&config, Some(root.clone()), Default::default());
+        let (text, images) = tool
+            .execute_with_images(json!({"prompt": "a cat", "n": 2, "path": root.join("art/cat.png")}))
+            .await
+            .unwrap();
+        assert!(text.starts_with("Generated 2 images"), "{text}");
+        assert!(text.contains("8x4"), "{text}");
+        assert_eq!(images.len(), 2);
+        assert!(root.join("art/cat-1.png").exists());
+        assert!(root.join("art/cat-2.png").exists());
+    }
+}
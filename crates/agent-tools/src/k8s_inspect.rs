@@ -0,0 +1,393 @@
+//! Read-only Kubernetes inspection tool.
+//!
+//! Wraps `kubectl get`, `describe` and `logs` against the configured (or
+//! current) kube context. Only these verbs exist, arguments are passed
+//! without a shell, secrets are not readable, and namespaces are limited to
+//! `[kubernetes] namespaces` when that list is set — so the agent can triage
+//! pods without any way to change the cluster.
+
+use agent_core::config::KubernetesConfig;
+use agent_core::error::AgentError;
+use agent_core::scrubber::SecretScrubber;
+use agent_core::tool_registry::Tool;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::process::Command;
+
+/// Resource types `get` and `describe` accept. Secrets are deliberately
+/// absent.
+const READABLE_RESOURCES: &[&str] = &[
+    "pods",
+    "deployments",
+    "replicasets",
+    "statefulsets",
+    "daemonsets",
+    "jobs",
+    "cronjobs",
+    "services",
+    "endpoints",
+    "ingresses",
+    "configmaps",
+    "events",
+    "nodes",
+    "namespaces",
+    "persistentvolumeclaims",
+    "persistentvolumes",
+    "horizontalpodautoscalers",
+];
+
+/// Short names kubectl users (and models) commonly use.
+const RESOURCE_ALIASES: &[(&str, &str)] = &[
+    ("po", "pods"),
+    ("deploy", "deployments"),
+    ("rs", "replicasets"),
+    ("sts", "statefulsets"),
+    ("ds", "daemonsets"),
+    ("cj", "cronjobs"),
+    ("svc", "services"),
+    ("ep", "endpoints"),
+    ("ing", "ingresses"),
+    ("cm", "configmaps"),
+    ("ev", "events"),
+    ("no", "nodes"),
+    ("ns", "namespaces"),
+    ("pvc", "persistentvolumeclaims"),
+    ("pv", "persistentvolumes"),
+    ("hpa", "horizontalpodautoscalers"),
+];
+
+/// Resources that are not namespaced.
+const CLUSTER_RESOURCES: &[&str] = &["nodes", "namespaces", "persistentvolumes"];
+
+/// Largest output returned to the model.
+const MAX_OUTPUT_BYTES: usize = 50 * 1024;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Action {
+    Get,
+    Describe,
+    Logs,
+}
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    action: Action,
+    resource: Option<String>,
+    name: Option<String>,
+    namespace: Option<String>,
+    selector: Option<String>,
+    container: Option<String>,
+    tail: Option<u32>,
+    #[serde(default)]
+    previous: bool,
+    output: Option<String>,
+}
+
+pub struct K8sInspectTool {
+    config: KubernetesConfig,
+    scrubber: SecretScrubber,
+}
+
+impl K8sInspectTool {
+    pub fn new(config: KubernetesConfig) -> Self {
+        Self {
+            config,
+            scrubber: SecretScrubber::new(),
+        }
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        AgentError::ToolExecution {
+            tool_name: "k8s_inspect".into(),
+            message: msg.into(),
+        }
+    }
+
+    /// kubectl arguments for a request, after validating every value.
+    fn build_args(&self, args: &Args) -> Result<Vec<String>, AgentError> {
+        for value in [&args.resource, &args.name, &args.namespace, &args.container]
+            .into_iter()
+            .flatten()
+        {
+            check_value(value, false)?;
+        }
+        if let Some(selector) = &args.selector {
+            check_value(selector, true)?;
+        }
+
+        let resource = match args.action {
+            Action::Logs => "pods".to_string(),
+            _ => {
+                let raw = args
+                    .resource
+                    .as_deref()
+                    .ok_or_else(|| Self::err("`resource` is required for get and describe"))?
+                    .to_ascii_lowercase();
+                canonical_resource(&raw).ok_or_else(|| {
+                    Self::err(format!(
+                        "Resource '{}' is not readable; allowed: {}",
+                        raw,
+                        READABLE_RESOURCES.join(", ")
+                    ))
+                })?
+            }
+        };
+
+        let mut cmd = Vec::new();
+        if let Some(context) = &self.config.context {
+            cmd.push(format!("--context={}", context));
+        }
+        cmd.push(
+            match args.action {
+                Action::Get => "get",
+                Action::Describe => "describe",
+                Action::Logs => "logs",
+            }
+            .to_string(),
+        );
+
+        match args.action {
+            Action::Get | Action::Describe => {
+                cmd.push(resource.clone());
+                if let Some(name) = &args.name {
+                    cmd.push(name.clone());
+                }
+                if let Some(selector) = &args.selector {
+                    cmd.push(format!("--selector={}", selector));
+                }
+                if args.action == Action::Get {
+                    match args.output.as_deref() {
+                        None => {}
+                        Some(o @ ("wide" | "yaml" | "json")) => cmd.push(format!("--output={}", o)),
+                        Some(o) => {
+                            return Err(Self::err(format!(
+                                "Unsupported output '{}': use wide, yaml or json",
+                                o
+                            )))
+                        }
+                    }
+                }
+            }
+            Action::Logs => {
+                let name = args
+                    .name
+                    .as_ref()
+                    .ok_or_else(|| Self::err("`name` (the pod) is required for logs"))?;
+                cmd.push(name.clone());
+                if let Some(container) = &args.container {
+                    cmd.push(format!("--container={}", container));
+                }
+                let tail = args.tail.unwrap_or(100).min(self.config.max_log_lines);
+                cmd.push(format!("--tail={}", tail));
+                if args.previous {
+                    cmd.push("--previous".into());
+                }
+            }
+        }
+
+        if !CLUSTER_RESOURCES.contains(&resource.as_str()) {
+            cmd.push(format!("--namespace={}", self.namespace(args.namespace.as_deref())?));
+        }
+        Ok(cmd)
+    }
+
+    /// The requested namespace if allowed, else the default.
+    fn namespace(&self, requested: Option<&str>) -> Result<String, AgentError> {
+        let allowed = &self.config.namespaces;
+        match requested {
+            Some(ns) if allowed.is_empty() || allowed.iter().any(|a| a == ns) => Ok(ns.to_string()),
+            Some(ns) => Err(Self::err(format!(
+                "Namespace '{}' is not allowed; allowed: {}",
+                ns,
+                allowed.join(", ")
+            ))),
+            None => Ok(allowed.first().cloned().unwrap_or_else(|| "default".into())),
+        }
+    }
+}
+
+/// Accept plural, singular and short resource names.
+fn canonical_resource(raw: &str) -> Option<String> {
+    if let Some((_, full)) = RESOURCE_ALIASES.iter().find(|(alias, _)| *alias == raw) {
+        return Some(full.to_string());
+    }
+    READABLE_RESOURCES
+        .iter()
+        .find(|r| **r == raw || r.strip_suffix('s') == Some(raw) || r.strip_suffix("es") == Some(raw))
+        .map(|r| r.to_string())
+}
+
+/// Reject values that could be read as flags or that contain characters no
+/// Kubernetes name has. Selectors may also use `=,!()` and spaces.
+fn check_value(value: &str, selector: bool) -> Result<(), AgentError> {
+    let ok = !value.is_empty()
+        && !value.starts_with('-')
+        && value.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || matches!(c, '-' | '.' | '_' | '/')
+                || (selector && matches!(c, '=' | ',' | '!' | '(' | ')' | ' '))
+        });
+    if ok {
+        Ok(())
+    } else {
+        Err(K8sInspectTool::err(format!("Invalid value: {:?}", value)))
+    }
+}
+
+#[async_trait]
+impl Tool for K8sInspectTool {
+    fn name(&self) -> &str {
+        "k8s_inspect"
+    }
+
+    fn description(&self) -> &str {
+        "Inspect the Kubernetes cluster read-only: `get` or `describe` resources (pods, \
+         deployments, services, events, ...) and read pod `logs`. Cannot modify anything or \
+         read secrets. Start with `get pods` or `get events` when triaging."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["get", "describe", "logs"]
+                },
+                "resource": {
+                    "type": "string",
+                    "description": "Resource type for get/describe, e.g. pods, deployments, events"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "Resource name (required for logs: the pod name)"
+                },
+                "namespace": {
+                    "type": "string",
+                    "description": "Namespace (defaults to the first allowed namespace)"
+                },
+                "selector": {
+                    "type": "string",
+                    "description": "Label selector for get/describe, e.g. app=web"
+                },
+                "container": {
+                    "type": "string",
+                    "description": "Container name for logs in multi-container pods"
+                },
+                "tail": {
+                    "type": "integer",
+                    "description": "Log lines from the end (default 100)"
+                },
+                "previous": {
+                    "type": "boolean",
+                    "description": "Logs of the previous (crashed) container instance"
+                },
+                "output": {
+                    "type": "string",
+                    "enum": ["wide", "yaml", "json"],
+                    "description": "Output format for get"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| Self::err(format!("Invalid arguments: {}", e)))?;
+        let cmd_args = self.build_args(&args)?;
+
+        let output = tokio::time::timeout(
+            std::time::Duration::from_secs(self.config.timeout_secs),
+            Command::new(&self.config.kubectl).args(&cmd_args).output(),
+        )
+        .await
+        .map_err(|_| Self::err("kubectl timed out"))?
+        .map_err(|e| Self::err(format!("Failed to run {}: {}", self.config.kubectl, e)))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Self::err(format!("kubectl failed: {}", stderr.trim())));
+        }
+        let mut text = self.scrubber.scrub(&stdout).into_owned();
+        if text.len() > MAX_OUTPUT_BYTES {
+            let mut end = MAX_OUTPUT_BYTES;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            text.truncate(end);
+            text.push_str("\n... [truncated]");
+        }
+        if text.trim().is_empty() {
+            text = "No resources found.".into();
+        }
+        Ok(format!("$ kubectl {}\n{}", cmd_args.join(" "), text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(namespaces: &[&str]) -> K8sInspectTool {
+        K8sInspectTool::new(KubernetesConfig {
+            enabled: true,
+            context: Some("staging".into()),
+            namespaces: namespaces.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        })
+    }
+
+    fn args(value: Value) -> Args {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_build_args() {
+        let t = tool(&["web", "jobs"]);
+        assert_eq!(
+            t.build_args(&args(json!({"action": "get", "resource": "po", "selector": "app=web"})))
+                .unwrap(),
+            vec!["--context=staging", "get", "pods", "--selector=app=web", "--namespace=web"]
+        );
+        assert_eq!(
+            t.build_args(&args(json!({
+                "action": "logs", "name": "api-7d9", "namespace": "jobs", "tail": 100000, "previous": true
+            })))
+            .unwrap(),
+            vec!["--context=staging", "logs", "api-7d9", "--tail=500", "--previous", "--namespace=jobs"]
+        );
+        assert_eq!(
+            t.build_args(&args(json!({"action": "describe", "resource": "node", "name": "n1"})))
+                .unwrap(),
+            vec!["--context=staging", "describe", "nodes", "n1"]
+        );
+    }
+
+    #[test]
+    fn test_rejects_unsafe_requests() {
+        let t = tool(&["web"]);
+        let rejected = [
+            json!({"action": "get", "resource": "secrets"}),
+            json!({"action": "get", "resource": "pods", "namespace": "kube-system"}),
+            json!({"action": "get", "resource": "pods", "name": "--all-namespaces"}),
+            json!({"action": "get", "resource": "pods", "output": "go-template={{.}}"}),
+            json!({"action": "logs"}),
+        ];
+        for request in rejected {
+            assert!(t.build_args(&args(request.clone())).is_err(), "{request}");
+        }
+        assert!(serde_json::from_value::<Args>(json!({"action": "delete"})).is_err());
+        // Without an allowlist any namespace works.
+        assert!(tool(&[])
+            .build_args(&args(json!({"action": "get", "resource": "pods", "namespace": "kube-system"})))
+            .is_ok());
+    }
+}
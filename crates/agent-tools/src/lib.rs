@@ -1,10 +1,31 @@
+pub mod archive;
+pub mod build_check;
+pub mod calc;
+pub mod clipboard;
+pub mod data_preview;
+pub mod deps_audit;
+pub mod doc_extract;
 pub mod env_detect;
 pub mod file_ops;
+pub mod ignore_rules;
+pub mod image_ops;
+pub mod k8s_inspect;
+pub mod memory_write;
+mod native_sandbox;
+pub mod notify_user;
 pub mod python_exec;
+pub mod remote_agent;
+pub mod result_page;
+pub mod run_tests;
 pub mod sandbox;
+pub mod screen_capture;
 pub mod shell_exec;
 pub mod skill_load;
+pub mod ssh_exec;
+pub mod system_info;
+pub mod web_cache;
 pub mod web_fetch;
+pub mod web_policy;
 
 use agent_core::config::AppConfig;
 use agent_core::tool_registry::ToolRegistry;
@@ -19,21 +40,126 @@ pub fn register_all(
 ) {
     let executor = Arc::new(sandbox::SandboxExecutor::new(config));
     let workspace_root = config.sandbox.workspace_root.clone();
+    let path_rules = executor.path_rules().clone();
 
-    registry.register(Arc::new(shell_exec::ShellExecTool::new(executor.clone())));
+    registry.register(Arc::new(
+        shell_exec::ShellExecTool::new(executor.clone()).with_read_only(config.tools.read_only),
+    ));
     registry.register(Arc::new(file_ops::FileReadTool {
         workspace_root: workspace_root.clone(),
+        path_rules: path_rules.clone(),
     }));
     registry.register(Arc::new(file_ops::FileWriteTool {
         workspace_root: workspace_root.clone(),
+        path_rules: path_rules.clone(),
     }));
-    registry.register(Arc::new(file_ops::FileListTool { workspace_root }));
-    registry.register(Arc::new(web_fetch::WebFetchTool::new()));
+    registry.register(Arc::new(file_ops::FileListTool {
+        workspace_root: workspace_root.clone(),
+        path_rules: path_rules.clone(),
+    }));
+    registry.register(Arc::new(archive::ArchiveExtractTool {
+        workspace_root: workspace_root.clone(),
+        path_rules: path_rules.clone(),
+    }));
+    registry.register(Arc::new(archive::ArchiveCreateTool {
+        workspace_root: workspace_root.clone(),
+        path_rules: path_rules.clone(),
+    }));
+    registry.register(Arc::new(data_preview::DataPreviewTool {
+        workspace_root: workspace_root.clone(),
+        path_rules: path_rules.clone(),
+    }));
+    registry.register(Arc::new(doc_extract::DocExtractTool {
+        workspace_root: workspace_root.clone(),
+        path_rules: path_rules.clone(),
+    }));
+    registry.register(Arc::new(image_ops::ImageTransformTool {
+        workspace_root: workspace_root.clone(),
+        path_rules: path_rules.clone(),
+    }));
+    if config.images.url.is_some() {
+        registry.register(Arc::new(image_ops::ImageGenerateTool::new(
+            &config.images,
+            workspace_root.clone(),
+            path_rules.clone(),
+        )));
+    }
+    if config.memory.enabled {
+        registry.register(Arc::new(memory_write::MemoryWriteTool::new(
+            &config.memory,
+            workspace_root,
+        )));
+    }
+    if config.tools.clipboard {
+        registry.register(Arc::new(clipboard::ClipboardReadTool::new()));
+        registry.register(Arc::new(clipboard::ClipboardWriteTool));
+    }
+    registry.register(Arc::new(notify_user::NotifyUserTool::new(
+        config.tools.desktop_notifications,
+        agent_core::scheduler::QuietHours::from_setting(config.scheduler.quiet_hours.as_deref()),
+    )));
+    if config.tools.screen_capture {
+        registry.register(Arc::new(screen_capture::ScreenCaptureTool::new(
+            config.sandbox.workspace_root.clone(),
+            path_rules.clone(),
+        )));
+    }
+    let mut fetch_tool = web_fetch::WebFetchTool::new()
+        .with_network(config.network.clone())
+        .with_limits(
+            config.web_fetch.requests_per_minute,
+            config.web_fetch.max_concurrent_per_domain,
+        )
+        .with_robots_txt(config.web_fetch.respect_robots_txt);
+    if config.web_fetch.cache {
+        let dir = config
+            .web_fetch
+            .cache_dir
+            .clone()
+            .unwrap_or_else(|| AppConfig::data_dir().join("web_cache"));
+        fetch_tool = fetch_tool.with_cache(web_cache::WebCache::new(
+            dir,
+            std::time::Duration::from_secs(config.web_fetch.cache_ttl_secs),
+        ));
+    }
+    registry.register(Arc::new(fetch_tool));
+    registry.register(Arc::new(deps_audit::DepsAuditTool::new(
+        config.sandbox.workspace_root.clone(),
+        path_rules,
+    )));
+    registry.register(Arc::new(run_tests::RunTestsTool::new(
+        executor.clone(),
+        config.sandbox.workspace_root.clone(),
+    )));
+    registry.register(Arc::new(build_check::BuildCheckTool::new(
+        executor.clone(),
+        config.sandbox.workspace_root.clone(),
+    )));
+    if config.kubernetes.enabled {
+        registry.register(Arc::new(k8s_inspect::K8sInspectTool::new(
+            config.kubernetes.clone(),
+        )));
+    }
+    if config.ssh.enabled {
+        registry.register(Arc::new(ssh_exec::SshExecTool::new(config.ssh.clone())));
+    }
+    if !config.remote_agents.is_empty() {
+        registry.register(Arc::new(remote_agent::RemoteAgentTool::new(
+            config.remote_agents.clone(),
+        )));
+    }
     registry.register(Arc::new(python_exec::PythonExecTool::new(executor)));
     registry.register(Arc::new(env_detect::EnvDetectTool::new()));
+    registry.register(Arc::new(system_info::SystemInfoTool));
+    registry.register(Arc::new(calc::CalcTool));
+    registry.register(Arc::new(result_page::ResultPageTool::new(
+        registry.result_cache(),
+    )));
 
     // Register skill_load tool if a skill indexer is available.
     if let Some(indexer) = skill_indexer {
         registry.register(Arc::new(skill_load::SkillLoadTool::new(indexer)));
     }
+
+    registry.configure(&config.tools);
 }
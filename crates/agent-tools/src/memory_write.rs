@@ -0,0 +1,139 @@
+//! Project memory tool.
+//!
+//! Appends durable facts ("this repo uses pnpm") to the notes file at the
+//! root of the detected project. The agent loop loads that file into the
+//! system prompt on every turn; the tool output shows the change as a
+//! unified diff so the user sees exactly what was remembered.
+
+use agent_core::config::MemoryConfig;
+use agent_core::context::{append_project_note, ContextLinker};
+use agent_core::error::AgentError;
+use agent_core::tool_registry::Tool;
+use agent_core::workspace;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+/// Longest fact accepted, in characters.
+const MAX_FACT_CHARS: usize = 500;
+
+pub struct MemoryWriteTool {
+    /// Project lookup starts here (default: current directory).
+    pub workspace_root: Option<PathBuf>,
+    pub file_name: String,
+}
+
+impl MemoryWriteTool {
+    pub fn new(config: &MemoryConfig, workspace_root: Option<PathBuf>) -> Self {
+        Self {
+            workspace_root,
+            file_name: config.file_name.clone(),
+        }
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        AgentError::ToolExecution {
+            tool_name: "memory_write".into(),
+            message: msg.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for MemoryWriteTool {
+    fn name(&self) -> &str {
+        "memory_write"
+    }
+
+    fn description(&self) -> &str {
+        "Remember a durable fact about the current project (e.g. 'this repo uses pnpm', \
+         'run tests with make check'). The fact is appended to the project's notes file, \
+         which is included in the system prompt of future sessions. Record only stable, \
+         project-wide facts, one per call."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "fact": {
+                    "type": "string",
+                    "description": "A single short fact, written as a standalone sentence"
+                }
+            },
+            "required": ["fact"]
+        })
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            fact: String,
+        }
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| Self::err(format!("Invalid arguments: {}", e)))?;
+
+        let fact = args.fact.trim();
+        if fact.is_empty() {
+            return Err(Self::err("fact must not be empty"));
+        }
+        if fact.chars().count() > MAX_FACT_CHARS {
+            return Err(Self::err(format!(
+                "fact is longer than {} characters; record a shorter summary",
+                MAX_FACT_CHARS
+            )));
+        }
+
+        let dir = match workspace::primary_root(&self.workspace_root) {
+            Some(root) => root,
+            None => std::env::current_dir()
+                .map_err(|e| Self::err(format!("No current directory: {}", e)))?,
+        };
+        let path = ContextLinker::new()
+            .project_notes_path(&dir, &self.file_name)
+            .ok_or_else(|| Self::err(format!("No project detected at {}", dir.display())))?;
+
+        let (before, after) = append_project_note(&path, fact)
+            .map_err(|e| Self::err(format!("Failed to write {}: {}", path.display(), e)))?;
+        if before == after {
+            return Ok(format!("Already noted in {}", path.display()));
+        }
+
+        let diff = similar::TextDiff::from_lines(&before, &after)
+            .unified_diff()
+            .context_radius(1)
+            .header(&format!("a/{}", self.file_name), &format!("b/{}", self.file_name))
+            .to_string();
+        Ok(format!("Saved to {}:\n```diff\n{}```", path.display(), diff))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_write_appends_with_diff() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("Cargo.toml"), "[package]").unwrap();
+        let tool = MemoryWriteTool::new(&MemoryConfig::default(), Some(tmp.path().to_path_buf()));
+
+        let out = tool
+            .execute(json!({"fact": "this repo uses pnpm"}))
+            .await
+            .unwrap();
+        assert!(out.contains("+++ b/AGENT.md"));
+        assert!(out.contains("+- this repo uses pnpm"));
+
+        let out = tool
+            .execute(json!({"fact": "this repo uses pnpm"}))
+            .await
+            .unwrap();
+        assert!(out.starts_with("Already noted"));
+        assert!(tool.execute(json!({"fact": "  "})).await.is_err());
+
+        let notes = std::fs::read_to_string(tmp.path().join("AGENT.md")).unwrap();
+        assert_eq!(notes, "# Project notes\n\n- this repo uses pnpm\n");
+    }
+}
@@ -0,0 +1,576 @@
+//! Platform-native restrictions for `sandbox.mode = "native"`, for hosts
+//! without Docker. Commands still run on the host, in the workspace root
+//! when one is set, but:
+//!
+//! - on macOS they run under `sandbox-exec` with a profile that denies
+//!   network access, writes outside the working and temp directories, and
+//!   reads of `~/.ssh`, `~/.aws` and `~/.gnupg`, plus writes to the paths
+//!   `ro` rules in `[sandbox.paths]` name and any access to `deny` ones;
+//! - on Windows they run with a restricted token (no privileges,
+//!   administrator groups deny-only) inside a Job Object that caps memory,
+//!   blocks desktop and clipboard access, and kills every process in the
+//!   job when the run ends or times out;
+//! - on other Unix systems only resource limits apply (memory, CPU time,
+//!   no core dumps), which macOS runs get as well.
+
+use crate::sandbox::ExecResult;
+use agent_core::config::PathAccess;
+use agent_core::error::AgentError;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Runs programs on the host under the native restrictions.
+pub(crate) struct NativeSandbox {
+    pub timeout_secs: u64,
+    pub memory_limit: Option<u64>,
+    pub work_dir: PathBuf,
+    /// Literal `[sandbox.paths]` rules: paths to keep read-only or out of
+    /// reach.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pub path_rules: Vec<(PathBuf, PathAccess)>,
+}
+
+impl NativeSandbox {
+    /// Run `program` with `args`, writing `input` to its stdin.
+    pub async fn run(
+        &self,
+        program: &str,
+        args: &[&str],
+        input: Option<&str>,
+    ) -> Result<ExecResult, AgentError> {
+        #[cfg(windows)]
+        {
+            let job = windows::JobRun {
+                program: program.to_string(),
+                args: args.iter().map(|a| a.to_string()).collect(),
+                input: input.map(str::to_string),
+                work_dir: self.work_dir.clone(),
+                memory_limit: self.memory_limit,
+                timeout: Duration::from_secs(self.timeout_secs),
+            };
+            tokio::task::spawn_blocking(move || job.run())
+                .await
+                .map_err(|e| AgentError::Sandbox(format!("Native sandbox task failed: {}", e)))?
+        }
+        #[cfg(not(windows))]
+        {
+            self.run_unix(program, args, input).await
+        }
+    }
+
+    #[cfg(not(windows))]
+    async fn run_unix(
+        &self,
+        program: &str,
+        args: &[&str],
+        input: Option<&str>,
+    ) -> Result<ExecResult, AgentError> {
+        use std::process::Stdio;
+        use tokio::io::AsyncWriteExt;
+        use tokio::process::Command;
+
+        #[cfg(target_os = "macos")]
+        let mut cmd = {
+            let mut cmd = Command::new("sandbox-exec");
+            cmd.arg("-p").arg(self.seatbelt_profile());
+            for (key, value) in self.seatbelt_params() {
+                cmd.arg("-D").arg(format!("{}={}", key, value.display()));
+            }
+            cmd.arg(program).args(args);
+            cmd
+        };
+        #[cfg(not(target_os = "macos"))]
+        let mut cmd = {
+            let mut cmd = Command::new(program);
+            cmd.args(args);
+            cmd
+        };
+
+        let memory_limit = self.memory_limit;
+        // CPU time a little past the wall-clock timeout, so a run that
+        // outlives it in the background is stopped too.
+        let cpu_secs = self.timeout_secs + 5;
+        // SAFETY: the closure only calls `setrlimit`, which is
+        // async-signal-safe, between fork and exec.
+        unsafe {
+            cmd.pre_exec(move || set_limits(memory_limit, cpu_secs));
+        }
+
+        let mut child = cmd
+            .current_dir(&self.work_dir)
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| AgentError::Sandbox(format!("Failed to spawn {}: {}", program, e)))?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input.as_bytes()).await.map_err(|e| {
+                AgentError::Sandbox(format!("Failed to write to {} stdin: {}", program, e))
+            })?;
+        }
+
+        let output = tokio::time::timeout(
+            Duration::from_secs(self.timeout_secs),
+            child.wait_with_output(),
+        )
+        .await
+        .map_err(|_| AgentError::Sandbox("Command timed out".into()))?
+        .map_err(|e| AgentError::Sandbox(format!("Failed to run {}: {}", program, e)))?;
+
+        Ok(ExecResult {
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            exit_code: output.status.code().unwrap_or(-1),
+        })
+    }
+
+    /// [`SEATBELT_PROFILE`] with the `[sandbox.paths]` rules appended; later
+    /// rules take precedence in Seatbelt.
+    #[cfg(target_os = "macos")]
+    fn seatbelt_profile(&self) -> String {
+        let mut profile = SEATBELT_PROFILE.to_string();
+        for (path, access) in &self.path_rules {
+            let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            let path = path.to_string_lossy().replace('\\', "\\\\").replace('"', "\\\"");
+            let operations = match access {
+                PathAccess::Deny => "file-read* file-write*",
+                PathAccess::Ro => "file-write*",
+                PathAccess::Rw => continue,
+            };
+            profile.push_str(&format!("(deny {} (subpath \"{}\"))\n", operations, path));
+        }
+        profile
+    }
+
+    /// Values for the `(param ...)` references in [`SEATBELT_PROFILE`],
+    /// canonicalized since the profile matches real paths (`/tmp` is
+    /// `/private/tmp` on macOS).
+    #[cfg(target_os = "macos")]
+    fn seatbelt_params(&self) -> Vec<(&'static str, PathBuf)> {
+        let real = |p: PathBuf| std::fs::canonicalize(&p).unwrap_or(p);
+        vec![
+            ("WORKSPACE", real(self.work_dir.clone())),
+            ("TMPDIR", real(std::env::temp_dir())),
+            ("HOME", real(std::env::var_os("HOME").unwrap_or_else(|| "/var/empty".into()).into())),
+        ]
+    }
+}
+
+/// Seatbelt profile for `sandbox-exec`: everything not denied below is
+/// allowed, so ordinary tools keep working.
+#[cfg(target_os = "macos")]
+const SEATBELT_PROFILE: &str = r#"(version 1)
+(allow default)
+(deny network*)
+(allow network* (remote unix-socket))
+(deny file-write*)
+(allow file-write*
+    (subpath (param "WORKSPACE"))
+    (subpath (param "TMPDIR"))
+    (subpath "/private/tmp")
+    (literal "/dev/null")
+    (literal "/dev/tty")
+    (regex #"^/dev/fd/"))
+(deny file-read*
+    (subpath (string-append (param "HOME") "/.ssh"))
+    (subpath (string-append (param "HOME") "/.aws"))
+    (subpath (string-append (param "HOME") "/.gnupg")))
+"#;
+
+/// Resource limits for the child, applied between fork and exec.
+#[cfg(unix)]
+fn set_limits(memory_limit: Option<u64>, cpu_secs: u64) -> std::io::Result<()> {
+    let set = |resource, value: u64| {
+        let limit = libc::rlimit {
+            rlim_cur: value as libc::rlim_t,
+            rlim_max: value as libc::rlim_t,
+        };
+        // SAFETY: `limit` is a valid rlimit for the duration of the call.
+        if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    };
+    if let Some(bytes) = memory_limit {
+        set(libc::RLIMIT_AS, bytes)?;
+    }
+    set(libc::RLIMIT_CPU, cpu_secs)?;
+    set(libc::RLIMIT_CORE, 0)
+}
+
+#[cfg(windows)]
+mod windows {
+    use crate::sandbox::ExecResult;
+    use agent_core::error::AgentError;
+    use std::ffi::OsStr;
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::{FromRawHandle, RawHandle};
+    use std::path::PathBuf;
+    use std::ptr::{null, null_mut};
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use windows_sys::Win32::Foundation::{
+        CloseHandle, SetHandleInformation, HANDLE, HANDLE_FLAG_INHERIT, WAIT_TIMEOUT,
+    };
+    use windows_sys::Win32::Security::{
+        CreateRestrictedToken, DISABLE_MAX_PRIVILEGE, LUA_TOKEN, SECURITY_ATTRIBUTES,
+        TOKEN_ADJUST_DEFAULT, TOKEN_ASSIGN_PRIMARY, TOKEN_DUPLICATE, TOKEN_QUERY,
+    };
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectBasicUIRestrictions,
+        JobObjectExtendedLimitInformation, SetInformationJobObject, TerminateJobObject,
+        JOBOBJECT_BASIC_UI_RESTRICTIONS, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION, JOB_OBJECT_LIMIT_JOB_MEMORY,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE, JOB_OBJECT_UILIMIT_DESKTOP,
+        JOB_OBJECT_UILIMIT_DISPLAYSETTINGS, JOB_OBJECT_UILIMIT_EXITWINDOWS,
+        JOB_OBJECT_UILIMIT_GLOBALATOMS, JOB_OBJECT_UILIMIT_HANDLES,
+        JOB_OBJECT_UILIMIT_READCLIPBOARD, JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS,
+        JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+    };
+    use windows_sys::Win32::System::Pipes::CreatePipe;
+    use windows_sys::Win32::System::Threading::{
+        CreateProcessAsUserW, GetCurrentProcess, GetExitCodeProcess, OpenProcessToken,
+        ResumeThread, TerminateProcess, WaitForSingleObject, CREATE_NO_WINDOW, CREATE_SUSPENDED,
+        CREATE_UNICODE_ENVIRONMENT, PROCESS_INFORMATION, STARTF_USESTDHANDLES, STARTUPINFOW,
+    };
+
+    /// Held while inheritable pipe ends exist, so concurrent runs do not
+    /// inherit each other's pipes and keep them open.
+    static SPAWN_LOCK: Mutex<()> = Mutex::new(());
+
+    /// A handle closed on drop.
+    struct Owned(HANDLE);
+
+    impl Drop for Owned {
+        fn drop(&mut self) {
+            if !self.0.is_null() {
+                // SAFETY: the handle is owned and not used after this.
+                unsafe { CloseHandle(self.0) };
+            }
+        }
+    }
+
+    impl Owned {
+        /// Give up ownership, e.g. to a `File`.
+        fn into_raw(self) -> HANDLE {
+            let handle = self.0;
+            std::mem::forget(self);
+            handle
+        }
+    }
+
+    fn last_error(what: &str) -> AgentError {
+        AgentError::Sandbox(format!("{} failed: {}", what, std::io::Error::last_os_error()))
+    }
+
+    fn check(ok: i32, what: &str) -> Result<(), AgentError> {
+        if ok == 0 {
+            Err(last_error(what))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn wide(s: &OsStr) -> Vec<u16> {
+        s.encode_wide().chain(Some(0)).collect()
+    }
+
+    /// Quote `arg` for a Windows command line, as `CommandLineToArgvW`
+    /// splits it.
+    pub(super) fn quote_arg(arg: &str) -> String {
+        if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '"']) {
+            return arg.to_string();
+        }
+        let mut quoted = String::from('"');
+        let mut backslashes = 0;
+        for c in arg.chars() {
+            match c {
+                '\\' => backslashes += 1,
+                '"' => {
+                    quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                    quoted.push('"');
+                    backslashes = 0;
+                }
+                _ => {
+                    quoted.push_str(&"\\".repeat(backslashes));
+                    quoted.push(c);
+                    backslashes = 0;
+                }
+            }
+        }
+        quoted.push_str(&"\\".repeat(backslashes * 2));
+        quoted.push('"');
+        quoted
+    }
+
+    /// One run: a process with a restricted token in a fresh Job Object.
+    pub(super) struct JobRun {
+        pub program: String,
+        pub args: Vec<String>,
+        pub input: Option<String>,
+        pub work_dir: PathBuf,
+        pub memory_limit: Option<u64>,
+        pub timeout: Duration,
+    }
+
+    impl JobRun {
+        pub fn run(self) -> Result<ExecResult, AgentError> {
+            let job = self.create_job()?;
+            let token = restricted_token()?;
+
+            let guard = SPAWN_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            let (stdin_read, stdin_write) = pipe(true)?;
+            let (stdout_read, stdout_write) = pipe(false)?;
+            let (stderr_read, stderr_write) = pipe(false)?;
+
+            let mut command_line: Vec<u16> = wide(OsStr::new(
+                &std::iter::once(self.program.as_str())
+                    .chain(self.args.iter().map(String::as_str))
+                    .map(quote_arg)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ));
+            let work_dir = wide(self.work_dir.as_os_str());
+            // SAFETY: zeroed STARTUPINFOW/PROCESS_INFORMATION are valid
+            // initial values for CreateProcessAsUserW.
+            let mut startup: STARTUPINFOW = unsafe { std::mem::zeroed() };
+            startup.cb = std::mem::size_of::<STARTUPINFOW>() as u32;
+            startup.dwFlags = STARTF_USESTDHANDLES;
+            startup.hStdInput = stdin_read.0;
+            startup.hStdOutput = stdout_write.0;
+            startup.hStdError = stderr_write.0;
+            let mut info: PROCESS_INFORMATION = unsafe { std::mem::zeroed() };
+
+            // SAFETY: every pointer is valid for the call; the command line
+            // buffer is mutable and NUL-terminated as the API requires.
+            check(
+                unsafe {
+                    CreateProcessAsUserW(
+                        token.0,
+                        null(),
+                        command_line.as_mut_ptr(),
+                        null(),
+                        null(),
+                        1,
+                        CREATE_SUSPENDED | CREATE_NO_WINDOW | CREATE_UNICODE_ENVIRONMENT,
+                        null(),
+                        work_dir.as_ptr(),
+                        &startup,
+                        &mut info,
+                    )
+                },
+                &format!("Starting {}", self.program),
+            )?;
+            // The child has its ends; close ours so reads see EOF.
+            drop((stdin_read, stdout_write, stderr_write));
+            drop(guard);
+
+            let process = Owned(info.hProcess);
+            let thread = Owned(info.hThread);
+            // SAFETY: both handles are valid; the process is suspended, so
+            // nothing runs before it is in the job.
+            if unsafe { AssignProcessToJobObject(job.0, process.0) } == 0 {
+                let err = last_error("Assigning the process to its job");
+                unsafe { TerminateJobObject(job.0, 1) };
+                unsafe { TerminateProcess(process.0, 1) };
+                return Err(err);
+            }
+            unsafe { ResumeThread(thread.0) };
+            drop(thread);
+
+            let input = self.input.unwrap_or_default();
+            // SAFETY: ownership of each pipe end moves into its File.
+            let mut stdin = unsafe { File::from_raw_handle(stdin_write.into_raw() as RawHandle) };
+            let writer = std::thread::spawn(move || {
+                let _ = stdin.write_all(input.as_bytes());
+            });
+            let stdout = read_all(stdout_read);
+            let stderr = read_all(stderr_read);
+
+            let millis = u32::try_from(self.timeout.as_millis()).unwrap_or(u32::MAX);
+            // SAFETY: the process handle is valid until `process` drops.
+            if unsafe { WaitForSingleObject(process.0, millis) } == WAIT_TIMEOUT {
+                unsafe { TerminateJobObject(job.0, 1) };
+                return Err(AgentError::Sandbox("Command timed out".into()));
+            }
+            let mut exit_code = 0u32;
+            unsafe { GetExitCodeProcess(process.0, &mut exit_code) };
+            // Background processes the command left behind go with the job.
+            unsafe { TerminateJobObject(job.0, 0) };
+            let _ = writer.join();
+
+            Ok(ExecResult {
+                stdout: String::from_utf8_lossy(&stdout.join().unwrap_or_default()).to_string(),
+                stderr: String::from_utf8_lossy(&stderr.join().unwrap_or_default()).to_string(),
+                exit_code: exit_code as i32,
+            })
+        }
+
+        /// A job that kills its processes when closed, caps their memory
+        /// and keeps them away from the desktop and clipboard.
+        fn create_job(&self) -> Result<Owned, AgentError> {
+            // SAFETY: null attributes and name create an anonymous job.
+            let job = Owned(unsafe { CreateJobObjectW(null(), null()) });
+            if job.0.is_null() {
+                return Err(last_error("Creating a job object"));
+            }
+
+            // SAFETY: all-zero is a valid "no limits" value for this struct.
+            let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+            limits.BasicLimitInformation.LimitFlags =
+                JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE | JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION;
+            if let Some(bytes) = self.memory_limit {
+                limits.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+                limits.JobMemoryLimit = usize::try_from(bytes).unwrap_or(usize::MAX);
+            }
+            // SAFETY: the pointer and size describe `limits`.
+            check(
+                unsafe {
+                    SetInformationJobObject(
+                        job.0,
+                        JobObjectExtendedLimitInformation,
+                        &limits as *const _ as *const _,
+                        std::mem::size_of_val(&limits) as u32,
+                    )
+                },
+                "Setting job limits",
+            )?;
+
+            let ui = JOBOBJECT_BASIC_UI_RESTRICTIONS {
+                UIRestrictionsClass: JOB_OBJECT_UILIMIT_DESKTOP
+                    | JOB_OBJECT_UILIMIT_DISPLAYSETTINGS
+                    | JOB_OBJECT_UILIMIT_EXITWINDOWS
+                    | JOB_OBJECT_UILIMIT_GLOBALATOMS
+                    | JOB_OBJECT_UILIMIT_HANDLES
+                    | JOB_OBJECT_UILIMIT_READCLIPBOARD
+                    | JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS
+                    | JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+            };
+            // SAFETY: the pointer and size describe `ui`.
+            check(
+                unsafe {
+                    SetInformationJobObject(
+                        job.0,
+                        JobObjectBasicUIRestrictions,
+                        &ui as *const _ as *const _,
+                        std::mem::size_of_val(&ui) as u32,
+                    )
+                },
+                "Setting job UI restrictions",
+            )?;
+            Ok(job)
+        }
+    }
+
+    /// This process's token with every privilege removed and the
+    /// administrator groups made deny-only.
+    fn restricted_token() -> Result<Owned, AgentError> {
+        let mut current: HANDLE = null_mut();
+        // SAFETY: `current` receives a token handle we then own.
+        check(
+            unsafe {
+                OpenProcessToken(
+                    GetCurrentProcess(),
+                    TOKEN_DUPLICATE | TOKEN_QUERY | TOKEN_ASSIGN_PRIMARY | TOKEN_ADJUST_DEFAULT,
+                    &mut current,
+                )
+            },
+            "Opening the process token",
+        )?;
+        let current = Owned(current);
+        let mut restricted: HANDLE = null_mut();
+        // SAFETY: no SIDs or privileges are passed by pointer; the flags
+        // alone strip privileges and administrator rights.
+        check(
+            unsafe {
+                CreateRestrictedToken(
+                    current.0,
+                    DISABLE_MAX_PRIVILEGE | LUA_TOKEN,
+                    0,
+                    null(),
+                    0,
+                    null(),
+                    0,
+                    null(),
+                    &mut restricted,
+                )
+            },
+            "Creating a restricted token",
+        )?;
+        Ok(Owned(restricted))
+    }
+
+    /// An anonymous pipe as (read, write). The child's end is inheritable:
+    /// the read end for stdin, the write end for output.
+    fn pipe(child_reads: bool) -> Result<(Owned, Owned), AgentError> {
+        let attributes = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: null_mut(),
+            bInheritHandle: 1,
+        };
+        let (mut read, mut write): (HANDLE, HANDLE) = (null_mut(), null_mut());
+        // SAFETY: both out pointers are valid.
+        check(unsafe { CreatePipe(&mut read, &mut write, &attributes, 0) }, "Creating a pipe")?;
+        let (read, write) = (Owned(read), Owned(write));
+        let ours = if child_reads { &write } else { &read };
+        // SAFETY: `ours` is a valid handle owned above.
+        check(
+            unsafe { SetHandleInformation(ours.0, HANDLE_FLAG_INHERIT, 0) },
+            "Configuring a pipe",
+        )?;
+        Ok((read, write))
+    }
+
+    fn read_all(handle: Owned) -> std::thread::JoinHandle<Vec<u8>> {
+        // SAFETY: ownership of the handle moves into the File.
+        let mut file = unsafe { File::from_raw_handle(handle.into_raw() as RawHandle) };
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = file.read_to_end(&mut buf);
+            buf
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_quote_arg() {
+            assert_eq!(quote_arg("plain"), "plain");
+            assert_eq!(quote_arg(""), "\"\"");
+            assert_eq!(quote_arg("echo hi"), "\"echo hi\"");
+            assert_eq!(quote_arg(r#"say "hi""#), r#""say \"hi\"""#);
+            assert_eq!(quote_arg(r"C:\dir with space\"), r#""C:\dir with space\\""#);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_runs_in_work_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = NativeSandbox {
+            timeout_secs: 10,
+            memory_limit: Some(512 * 1024 * 1024),
+            work_dir: dir.path().to_path_buf(),
+            path_rules: Vec::new(),
+        };
+        let result = sandbox
+            .run("bash", &["-c", "pwd; touch made-here; ulimit -c"], None)
+            .await
+            .unwrap();
+        assert_eq!(result.exit_code, 0, "{}", result.stderr);
+        assert!(dir.path().join("made-here").exists());
+        assert_eq!(result.stdout.lines().last(), Some("0"), "core dumps are off");
+
+        let result = sandbox.run("cat", &[], Some("from stdin")).await.unwrap();
+        assert_eq!(result.stdout, "from stdin");
+    }
+}
@@ -0,0 +1,219 @@
+//! `notify_user`: let the agent tap the user on the shoulder.
+//!
+//! Meant for long-running and scheduled work whose results the user is not
+//! watching for. The notification goes to the web UI as a toast (see
+//! [`agent_core::notifications`]) and, with `[tools] desktop_notifications`,
+//! to the desktop through the platform's notification service. During
+//! `[scheduler] quiet_hours` notifications below error level are held
+//! until the window ends.
+
+use agent_core::error::AgentError;
+use agent_core::notifications::{self, NotificationLevel};
+use agent_core::scheduler::QuietHours;
+use agent_core::tool_registry::Tool;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+const MAX_TITLE_CHARS: usize = 100;
+const MAX_MESSAGE_CHARS: usize = 1000;
+
+/// Send the user a notification.
+pub struct NotifyUserTool {
+    desktop: bool,
+    quiet_hours: Option<QuietHours>,
+}
+
+impl NotifyUserTool {
+    pub fn new(desktop: bool, quiet_hours: Option<QuietHours>) -> Self {
+        Self {
+            desktop,
+            quiet_hours,
+        }
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        AgentError::ToolExecution {
+            tool_name: "notify_user".into(),
+            message: msg.into(),
+        }
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max - 1).collect();
+    out.push('…');
+    out
+}
+
+/// Show a desktop notification; blocks until the service accepts it.
+fn show_desktop(level: NotificationLevel, title: &str, message: &str) -> Result<(), String> {
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .appname("agent-shell")
+        .summary(title)
+        .body(message);
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if level == NotificationLevel::Error {
+        notification.urgency(notify_rust::Urgency::Critical);
+    }
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    let _ = level;
+    notification.show().map(|_| ()).map_err(|e| e.to_string())
+}
+
+#[async_trait]
+impl Tool for NotifyUserTool {
+    fn name(&self) -> &str {
+        "notify_user"
+    }
+
+    fn description(&self) -> &str {
+        "Send the user a short notification (web UI toast and desktop notification). Use it \
+         when a long-running or scheduled task finishes or finds something that needs their \
+         attention, not for ordinary replies."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {
+                    "type": "string",
+                    "description": "Short headline (up to 100 characters)"
+                },
+                "message": {
+                    "type": "string",
+                    "description": "What happened and what, if anything, the user should do (up to 1000 characters)"
+                },
+                "level": {
+                    "type": "string",
+                    "enum": ["info", "success", "warning", "error"],
+                    "description": "How the notification is styled (default info)"
+                }
+            },
+            "required": ["title", "message"]
+        })
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            title: String,
+            message: String,
+            #[serde(default)]
+            level: NotificationLevel,
+        }
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| Self::err(format!("Invalid arguments: {}", e)))?;
+        let title = truncate(&args.title, MAX_TITLE_CHARS);
+        let message = truncate(&args.message, MAX_MESSAGE_CHARS);
+        if title.is_empty() {
+            return Err(Self::err("title must not be empty"));
+        }
+
+        if args.level != NotificationLevel::Error {
+            if let Some(until) = self.quiet_hours.and_then(|q| q.deferred_now()) {
+                let (desktop, level) = (self.desktop, args.level);
+                let (t, m) = (title.clone(), message.clone());
+                tokio::spawn(async move {
+                    let wait = (until - chrono::Utc::now()).to_std().unwrap_or_default();
+                    tokio::time::sleep(wait).await;
+                    if let Err(e) = deliver(desktop, level, t, m).await {
+                        tracing::debug!("Desktop notification failed: {}", e);
+                    }
+                });
+                return Ok(format!(
+                    "Quiet hours: the notification \"{}\" will be delivered at {}",
+                    title,
+                    until.with_timezone(&chrono::Local).format("%H:%M")
+                ));
+            }
+        }
+
+        let mut result = format!("Notified the user: {}", title);
+        if let Err(e) = deliver(self.desktop, args.level, title, message).await {
+            // Headless servers have no notification service; the toast
+            // still went out.
+            tracing::debug!("Desktop notification failed: {}", e);
+            result.push_str(" (desktop notification unavailable)");
+        }
+        Ok(result)
+    }
+}
+
+/// Publish the toast and, if `desktop`, show a desktop notification.
+async fn deliver(
+    desktop: bool,
+    level: NotificationLevel,
+    title: String,
+    message: String,
+) -> Result<(), String> {
+    notifications::publish(level, &title, &message);
+    if !desktop {
+        return Ok(());
+    }
+    tokio::task::spawn_blocking(move || show_desktop(level, &title, &message))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_notify_publishes_toast() {
+        let mut rx = notifications::subscribe();
+        let tool = NotifyUserTool::new(false, None);
+        let out = tool
+            .execute(json!({"title": "Backup done", "message": "3 files", "level": "success"}))
+            .await
+            .unwrap();
+        assert_eq!(out, "Notified the user: Backup done");
+        let n = loop {
+            let n = rx.recv().await.unwrap();
+            if n.title == "Backup done" {
+                break n;
+            }
+        };
+        assert_eq!(n.level, NotificationLevel::Success);
+        assert_eq!(n.message, "3 files");
+
+        assert!(tool
+            .execute(json!({"title": " ", "message": "x"}))
+            .await
+            .is_err());
+        assert_eq!(truncate("abcdef", 4), "abc…");
+    }
+
+    #[tokio::test]
+    async fn test_quiet_hours_hold_all_but_errors() {
+        let now = chrono::Local::now().time();
+        let window = format!(
+            "{}-{}",
+            (now - chrono::Duration::hours(1)).format("%H:%M"),
+            (now + chrono::Duration::hours(1)).format("%H:%M")
+        );
+        let tool = NotifyUserTool::new(false, window.parse().ok());
+        let out = tool
+            .execute(json!({"title": "Nightly sync", "message": "ok"}))
+            .await
+            .unwrap();
+        assert!(out.starts_with("Quiet hours:"), "{}", out);
+        let out = tool
+            .execute(json!({"title": "Disk full", "message": "/", "level": "error"}))
+            .await
+            .unwrap();
+        assert_eq!(out, "Notified the user: Disk full");
+    }
+}
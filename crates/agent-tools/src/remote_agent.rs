@@ -0,0 +1,332 @@
+//! Delegation of sub-tasks to other agent-shell instances.
+//!
+//! Each peer under `[remote_agents.<name>]` is another instance's HTTP
+//! server. A task is posted to its `/v1/runs` API, which runs it in a new
+//! session there; the streamed reply is read as it arrives and returned
+//! with the tools the remote agent used, so a laptop can hand heavy jobs to
+//! a bigger machine.
+
+use agent_core::config::RemoteAgentConfig;
+use agent_core::error::AgentError;
+use agent_core::tool_registry::Tool;
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Delegate a task to a configured remote instance.
+pub struct RemoteAgentTool {
+    peers: HashMap<String, RemoteAgentConfig>,
+    client: reqwest::Client,
+    description: String,
+}
+
+impl RemoteAgentTool {
+    pub fn new(peers: HashMap<String, RemoteAgentConfig>) -> Self {
+        let mut names: Vec<&String> = peers.keys().collect();
+        names.sort();
+        let listed: Vec<String> = names
+            .iter()
+            .map(|name| match &peers[*name].description {
+                Some(desc) => format!("{} ({})", name, desc),
+                None => name.to_string(),
+            })
+            .collect();
+        let description = format!(
+            "Delegate a self-contained task to another agent-shell instance, which works on it \
+             with its own tools and returns its final answer. Use it for heavy or remote work. \
+             Available peers: {}.",
+            if listed.is_empty() { "none".to_string() } else { listed.join(", ") }
+        );
+        Self {
+            peers,
+            client: reqwest::Client::new(),
+            description,
+        }
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        AgentError::ToolExecution {
+            tool_name: "remote_agent".into(),
+            message: msg.into(),
+        }
+    }
+}
+
+/// What a run streamed back.
+#[derive(Debug, Default)]
+struct RunReport {
+    answer: String,
+    /// Tool names in call order.
+    tools: Vec<String>,
+    failed_tools: usize,
+    error: Option<String>,
+}
+
+impl RunReport {
+    /// Apply one server-sent event.
+    fn apply(&mut self, event: &str, data: &str) {
+        match event {
+            "tool_call" => {
+                if let Ok(v) = serde_json::from_str::<Value>(data) {
+                    self.tools
+                        .push(v["tool"].as_str().unwrap_or("?").to_string());
+                }
+            }
+            "tool_result" => {
+                if let Ok(v) = serde_json::from_str::<Value>(data) {
+                    if v["is_error"].as_bool() == Some(true) {
+                        self.failed_tools += 1;
+                    }
+                }
+            }
+            "error" => self.error = Some(data.to_string()),
+            _ => {
+                if let Ok(v) = serde_json::from_str::<Value>(data) {
+                    if let Some(text) = v["choices"][0]["delta"]["content"].as_str() {
+                        self.answer.push_str(text);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Incremental parser for a `text/event-stream` body. Bytes are buffered
+/// until a full line arrives, so characters split across chunks survive.
+#[derive(Default)]
+struct EventParser {
+    buffer: Vec<u8>,
+    event: String,
+    data: Vec<String>,
+}
+
+impl EventParser {
+    /// Feed bytes; returns the `(event, data)` pairs they complete.
+    fn push(&mut self, chunk: &[u8]) -> Vec<(String, String)> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let bytes: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&bytes);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push((std::mem::take(&mut self.event), self.data.join("\n")));
+                    self.data.clear();
+                }
+                self.event.clear();
+            } else if let Some(event) = line.strip_prefix("event:") {
+                self.event = event.trim().to_string();
+            } else if let Some(data) = line.strip_prefix("data:") {
+                self.data.push(data.strip_prefix(' ').unwrap_or(data).to_string());
+            }
+        }
+        events
+    }
+}
+
+#[async_trait]
+impl Tool for RemoteAgentTool {
+    fn name(&self) -> &str {
+        "remote_agent"
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        let mut names: Vec<&String> = self.peers.keys().collect();
+        names.sort();
+        json!({
+            "type": "object",
+            "properties": {
+                "peer": {
+                    "type": "string",
+                    "enum": names,
+                    "description": "Configured remote instance"
+                },
+                "task": {
+                    "type": "string",
+                    "description": "The task, with all context the remote agent needs; it cannot see this conversation"
+                }
+            },
+            "required": ["peer", "task"]
+        })
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            peer: String,
+            task: String,
+        }
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| Self::err(format!("Invalid arguments: {}", e)))?;
+        let peer = self
+            .peers
+            .get(&args.peer)
+            .ok_or_else(|| Self::err(format!("Unknown peer '{}'", args.peer)))?;
+
+        let url = format!("{}/v1/runs", peer.url.trim_end_matches('/'));
+        let mut request = self.client.post(&url).json(&json!({
+            "task": args.task,
+            "agent": peer.agent,
+            "stream": true,
+        }));
+        if let Some(token) = peer.resolve_token() {
+            request = request.bearer_auth(token);
+        }
+
+        let timeout = Duration::from_secs(peer.timeout_secs);
+        let run = async {
+            let response = request
+                .send()
+                .await
+                .map_err(|e| Self::err(format!("{}: {}", args.peer, e)))?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                // Peers answer errors as problem details; prefer their code
+                // and detail.
+                let detail = serde_json::from_str::<Value>(&body)
+                    .ok()
+                    .and_then(|v| {
+                        let message = v["detail"].as_str()?;
+                        Some(match v["code"].as_str() {
+                            Some(code) => format!("[{}] {}", code, message),
+                            None => message.to_string(),
+                        })
+                    })
+                    .unwrap_or(body);
+                return Err(Self::err(format!("{}: {} {}", args.peer, status, detail.trim())));
+            }
+            let session = response
+                .headers()
+                .get("x-session-id")
+                .and_then(|v| v.to_str().ok())
+                .map(String::from);
+
+            let mut report = RunReport::default();
+            let mut parser = EventParser::default();
+            let mut body = response.bytes_stream();
+            while let Some(chunk) = body.next().await {
+                let chunk = chunk.map_err(|e| Self::err(format!("{}: {}", args.peer, e)))?;
+                for (event, data) in parser.push(&chunk) {
+                    tracing::debug!("remote_agent {}: {} event", args.peer, event);
+                    report.apply(&event, &data);
+                }
+            }
+            Ok((session, report))
+        };
+        let (session, report) = tokio::time::timeout(timeout, run)
+            .await
+            .map_err(|_| {
+                Self::err(format!(
+                    "{}: no answer within {}s",
+                    args.peer, peer.timeout_secs
+                ))
+            })??;
+
+        if let Some(error) = report.error {
+            return Err(Self::err(format!("{}: {}", args.peer, error)));
+        }
+        let mut out = match session {
+            Some(id) => format!("Remote agent '{}' (session {}):\n", args.peer, id),
+            None => format!("Remote agent '{}':\n", args.peer),
+        };
+        if !report.tools.is_empty() {
+            out.push_str(&format!("Tools used: {}", report.tools.join(", ")));
+            if report.failed_tools > 0 {
+                out.push_str(&format!(" ({} failed)", report.failed_tools));
+            }
+            out.push('\n');
+        }
+        out.push('\n');
+        out.push_str(report.answer.trim());
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_event_parser_handles_split_chunks() {
+        let mut parser = EventParser::default();
+        let text = "event: tool_call\ndata: {\"tool\":\"é\"}\n\ndata: [DONE]\n\n".as_bytes();
+        let split = text.iter().position(|b| *b > 0x7f).unwrap() + 1;
+        assert!(parser.push(&text[..split]).is_empty());
+        let events = parser.push(&text[split..]);
+        assert_eq!(
+            events,
+            vec![
+                ("tool_call".to_string(), "{\"tool\":\"é\"}".to_string()),
+                (String::new(), "[DONE]".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delegates_to_peer_runs_api() {
+        // A stand-in for a peer's /v1/runs that streams a short run.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !String::from_utf8_lossy(&request).ends_with('}') {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let body = "event: tool_call\ndata: {\"tool\":\"build_check\"}\n\n\
+                        event: tool_result\ndata: {\"is_error\":true}\n\n\
+                        data: {\"choices\":[{\"delta\":{\"content\":\"Build \"}}]}\n\n\
+                        data: {\"choices\":[{\"delta\":{\"content\":\"passes.\"}}]}\n\n\
+                        data: [DONE]\n\n";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nx-session-id: s-1\r\n\
+                 content-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+
+        let peers = HashMap::from([(
+            "homelab".to_string(),
+            RemoteAgentConfig {
+                url,
+                token: Some("secret".into()),
+                ..Default::default()
+            },
+        )]);
+        let tool = RemoteAgentTool::new(peers);
+        let out = tool
+            .execute(json!({"peer": "homelab", "task": "run the build"}))
+            .await
+            .unwrap();
+        assert_eq!(
+            out,
+            "Remote agent 'homelab' (session s-1):\nTools used: build_check (1 failed)\n\nBuild passes."
+        );
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/runs "));
+        assert!(request.to_lowercase().contains("authorization: bearer secret"));
+        assert!(request.contains("\"task\":\"run the build\""));
+
+        let err = tool
+            .execute(json!({"peer": "nope", "task": "x"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown peer"));
+    }
+}
@@ -0,0 +1,117 @@
+//! Result paging tool — reads the rest of a tool output that was truncated
+//! to its first page, by the reference ID given in the truncation note.
+
+use agent_core::error::AgentError;
+use agent_core::result_cache::{self, ResultCache};
+use agent_core::tool_registry::Tool;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+pub struct ResultPageTool {
+    cache: ResultCache,
+}
+
+impl ResultPageTool {
+    pub fn new(cache: ResultCache) -> Self {
+        Self { cache }
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        AgentError::ToolExecution {
+            tool_name: "result_page".into(),
+            message: msg.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ResultPageTool {
+    fn name(&self) -> &str {
+        "result_page"
+    }
+
+    fn description(&self) -> &str {
+        "Read more of a tool result that was truncated. Pass the reference ID from the truncation note with a page number, or with a grep pattern to list matching lines."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "ref": {
+                    "type": "string",
+                    "description": "Reference ID of the cached result, e.g. res_0123456789abcdef"
+                },
+                "page": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Page to read (default: 2)"
+                },
+                "grep": {
+                    "type": "string",
+                    "description": "Case-insensitive regex; returns matching lines with line numbers instead of a page"
+                }
+            },
+            "required": ["ref"]
+        })
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        let id = args
+            .get("ref")
+            .and_then(Value::as_str)
+            .ok_or_else(|| Self::err("Missing 'ref' parameter"))?;
+        let content = self.cache.get(id).ok_or_else(|| {
+            Self::err(format!(
+                "No cached result '{}'; it may have expired. Re-run the original tool.",
+                id
+            ))
+        })?;
+
+        if let Some(pattern) = args.get("grep").and_then(Value::as_str) {
+            return Ok(result_cache::grep(&content, pattern));
+        }
+
+        let number = args.get("page").and_then(Value::as_f64).unwrap_or(2.0) as usize;
+        let (text, pages) = result_cache::page(&content, number);
+        if text.is_empty() {
+            return Err(Self::err(format!(
+                "Page {} does not exist; '{}' has {} page(s)",
+                number, id, pages
+            )));
+        }
+        let next = if number < pages {
+            format!(", next: {{\"ref\": \"{}\", \"page\": {}}}", id, number + 1)
+        } else {
+            String::new()
+        };
+        Ok(format!("{}\n\n[Page {} of {}{}]", text, number, pages, next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reads_pages_and_greps() {
+        let cache = ResultCache::new();
+        let content: String = (1..=5000).map(|i| format!("row {i}\n")).collect();
+        let id = cache.store(&content);
+        let tool = ResultPageTool::new(cache);
+
+        let second = tool.execute(json!({ "ref": id })).await.unwrap();
+        assert!(second.contains("[Page 2 of"));
+        assert!(second.contains("\"page\": 3"));
+
+        let found = tool.execute(json!({ "ref": id, "grep": "^row 4999$" })).await.unwrap();
+        assert_eq!(found, "4999: row 4999");
+
+        assert!(tool.execute(json!({ "ref": id, "page": 99 })).await.is_err());
+        assert!(tool.execute(json!({ "ref": "res_unknown" })).await.is_err());
+    }
+}
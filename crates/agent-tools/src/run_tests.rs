@@ -0,0 +1,485 @@
+//! Test runner tool.
+//!
+//! Detects the project's test framework (cargo test, pytest or jest) from
+//! the environments [`env_detect`](crate::env_detect) finds, runs it in the
+//! sandbox and parses the output into structured failures (test name, file,
+//! message). The agent gets a compact summary instead of the full log, which
+//! keeps fix-and-rerun loops cheap.
+
+use crate::env_detect::detect_environments;
+use crate::sandbox::{shell_quote, ExecResult, SandboxExecutor};
+use agent_core::error::AgentError;
+use agent_core::tool_registry::Tool;
+use agent_core::workspace;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// At most this many failures are listed in the summary.
+const MAX_FAILURES: usize = 20;
+/// Each failure message is cut to this many lines.
+const MAX_MESSAGE_LINES: usize = 12;
+/// Output lines shown when a run fails without parseable failures.
+const TAIL_LINES: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestFramework {
+    Cargo,
+    Pytest,
+    Jest,
+}
+
+impl TestFramework {
+    /// Pick a framework for `dir`: Rust and Node come from env_detect, Python
+    /// from a detected virtualenv or the usual project files.
+    pub fn detect(dir: &Path) -> Option<Self> {
+        let envs = detect_environments(dir);
+        let has = |prefix: &str| envs.iter().any(|e| e.env_type.starts_with(prefix));
+        if has("rust") {
+            Some(Self::Cargo)
+        } else if has("python")
+            || ["pyproject.toml", "setup.py", "pytest.ini", "tox.ini", "requirements.txt"]
+                .iter()
+                .any(|f| dir.join(f).exists())
+        {
+            Some(Self::Pytest)
+        } else if has("node") {
+            Some(Self::Jest)
+        } else {
+            None
+        }
+    }
+
+    /// Shell command running the tests in `dir`, optionally filtered by name.
+    fn command(self, dir: &Path, filter: Option<&str>) -> String {
+        let cd = format!("cd {}", shell_quote(&dir.to_string_lossy()));
+        let filter = filter.map(shell_quote);
+        let cmd = match (self, filter) {
+            (Self::Cargo, None) => "cargo test --no-fail-fast".to_string(),
+            (Self::Cargo, Some(f)) => format!("cargo test --no-fail-fast {f}"),
+            (Self::Pytest, None) => "python3 -m pytest -q -rfE --tb=short".to_string(),
+            (Self::Pytest, Some(f)) => format!("python3 -m pytest -q -rfE --tb=short -k {f}"),
+            (Self::Jest, None) => "npx --no-install jest --ci --json".to_string(),
+            (Self::Jest, Some(f)) => format!("npx --no-install jest --ci --json -t {f}"),
+        };
+        format!("{cd} && {cmd}")
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo test",
+            Self::Pytest => "pytest",
+            Self::Jest => "jest",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TestFailure {
+    pub name: String,
+    pub file: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TestReport {
+    pub framework: TestFramework,
+    pub passed: usize,
+    pub failed: usize,
+    pub failures: Vec<TestFailure>,
+    pub exit_code: i32,
+}
+
+impl TestReport {
+    /// Parse a run's output with the framework's parser.
+    pub fn parse(framework: TestFramework, result: &ExecResult) -> Self {
+        let mut report = Self {
+            framework,
+            passed: 0,
+            failed: 0,
+            failures: Vec::new(),
+            exit_code: result.exit_code,
+        };
+        match framework {
+            TestFramework::Cargo => {
+                report.parse_cargo(&format!("{}\n{}", result.stdout, result.stderr))
+            }
+            TestFramework::Pytest => report.parse_pytest(&result.stdout),
+            TestFramework::Jest => report.parse_jest(&result.stdout),
+        }
+        report.failed = report.failed.max(report.failures.len());
+        report
+    }
+
+    /// `test result:` lines, `---- name stdout ----` sections with the panic
+    /// location, and compiler errors when the build fails.
+    fn parse_cargo(&mut self, output: &str) {
+        let lines: Vec<&str> = output.lines().collect();
+        for line in &lines {
+            if let Some(rest) = line.strip_prefix("test result: ") {
+                self.passed += count_before(rest, " passed");
+                self.failed += count_before(rest, " failed");
+            }
+        }
+
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            if let Some(name) = line
+                .strip_prefix("---- ")
+                .and_then(|l| l.strip_suffix(" stdout ----"))
+            {
+                let mut file = None;
+                let mut message = Vec::new();
+                i += 1;
+                while i < lines.len()
+                    && !lines[i].starts_with("---- ")
+                    && !lines[i].starts_with("failures:")
+                {
+                    let l = lines[i];
+                    if let Some(at) = l.split(" panicked at ").nth(1) {
+                        file = Some(at.trim_end_matches(':').to_string());
+                    } else if !l.starts_with("note: run with `RUST_BACKTRACE") && !l.trim().is_empty() {
+                        message.push(l);
+                    }
+                    i += 1;
+                }
+                self.failures.push(TestFailure {
+                    name: name.to_string(),
+                    file,
+                    message: message.join("\n"),
+                });
+                continue;
+            }
+            if line.starts_with("error") && !line.starts_with("error: test failed") {
+                let file = lines
+                    .get(i + 1)
+                    .and_then(|l| l.trim_start().strip_prefix("--> "))
+                    .map(String::from);
+                if file.is_some() {
+                    self.failures.push(TestFailure {
+                        name: "(build)".into(),
+                        file,
+                        message: line.to_string(),
+                    });
+                }
+            }
+            i += 1;
+        }
+    }
+
+    /// The `-rfE` short summary (`FAILED path::test - message`) and the
+    /// final `N failed, M passed` line.
+    fn parse_pytest(&mut self, output: &str) {
+        for line in output.lines() {
+            let entry = line
+                .strip_prefix("FAILED ")
+                .or_else(|| line.strip_prefix("ERROR "));
+            if let Some(entry) = entry {
+                let (id, message) = entry.split_once(" - ").unwrap_or((entry, ""));
+                let file = id.split("::").next().map(String::from);
+                self.failures.push(TestFailure {
+                    name: id.to_string(),
+                    file,
+                    message: message.to_string(),
+                });
+            }
+        }
+        if let Some(last) = output
+            .lines()
+            .rev()
+            .find(|l| l.contains(" passed") || l.contains(" failed") || l.contains(" error"))
+        {
+            let last = last.trim_matches(|c: char| c == '=' || c.is_whitespace());
+            self.passed = count_before(last, " passed");
+            self.failed = count_before(last, " failed") + count_before(last, " error");
+        }
+    }
+
+    /// The `--json` report jest prints on stdout.
+    fn parse_jest(&mut self, output: &str) {
+        let Some(start) = output.find('{') else { return };
+        let Ok(json) = serde_json::from_str::<Value>(&output[start..]) else {
+            return;
+        };
+        let count = |key: &str| json.get(key).and_then(Value::as_u64).unwrap_or(0) as usize;
+        self.passed = count("numPassedTests");
+        self.failed = count("numFailedTests");
+        for suite in json["testResults"].as_array().into_iter().flatten() {
+            let file = suite["name"].as_str().map(String::from);
+            let mut suite_failed = false;
+            for test in suite["assertionResults"].as_array().into_iter().flatten() {
+                if test["status"] != "failed" {
+                    continue;
+                }
+                suite_failed = true;
+                let message = test["failureMessages"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                self.failures.push(TestFailure {
+                    name: test["fullName"].as_str().unwrap_or("").to_string(),
+                    file: file.clone(),
+                    message,
+                });
+            }
+            // A suite that fails to load has no assertion results.
+            if !suite_failed && suite["status"] == "failed" {
+                self.failures.push(TestFailure {
+                    name: "(suite)".into(),
+                    file,
+                    message: suite["message"].as_str().unwrap_or("").to_string(),
+                });
+            }
+        }
+    }
+
+    /// Compact summary for the model.
+    pub fn summary(&self, result: &ExecResult) -> String {
+        let status = if self.exit_code == 0 { "passed" } else { "FAILED" };
+        let mut out = format!(
+            "{} {}: {} passed, {} failed (exit code {})\n",
+            self.framework.label(),
+            status,
+            self.passed,
+            self.failed,
+            self.exit_code
+        );
+        for failure in self.failures.iter().take(MAX_FAILURES) {
+            out.push_str(&format!("\nFAILED {}", failure.name));
+            if let Some(file) = &failure.file {
+                out.push_str(&format!(" ({})", file));
+            }
+            out.push('\n');
+            for line in failure.message.lines().take(MAX_MESSAGE_LINES) {
+                out.push_str(&format!("  {}\n", line));
+            }
+        }
+        if self.failures.len() > MAX_FAILURES {
+            out.push_str(&format!(
+                "\n... and {} more failures\n",
+                self.failures.len() - MAX_FAILURES
+            ));
+        }
+        if self.exit_code != 0 && self.failures.is_empty() {
+            // Nothing parsed (missing tool, crash, timeout): show the tail.
+            out.push_str("\nOutput (last lines):\n");
+            out.push_str(&result.tail(TAIL_LINES));
+        }
+        out
+    }
+}
+
+/// The number directly before `suffix` in `text`, e.g. 3 in "3 passed".
+fn count_before(text: &str, suffix: &str) -> usize {
+    text.find(suffix)
+        .and_then(|end| {
+            let start = text[..end]
+                .rfind(|c: char| !c.is_ascii_digit())
+                .map_or(0, |i| i + 1);
+            text[start..end].parse().ok()
+        })
+        .unwrap_or(0)
+}
+
+/// Run the project's tests and summarize failures.
+pub struct RunTestsTool {
+    executor: Arc<SandboxExecutor>,
+    workspace_root: Option<PathBuf>,
+}
+
+impl RunTestsTool {
+    pub fn new(executor: Arc<SandboxExecutor>, workspace_root: Option<PathBuf>) -> Self {
+        Self {
+            executor,
+            workspace_root,
+        }
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        AgentError::ToolExecution {
+            tool_name: "run_tests".into(),
+            message: msg.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for RunTestsTool {
+    fn name(&self) -> &str {
+        "run_tests"
+    }
+
+    fn description(&self) -> &str {
+        "Run the project's test suite (cargo test, pytest or jest, detected automatically) \
+         and return a compact summary: pass/fail counts and each failing test's name, file \
+         and message. Use `filter` to rerun only the tests you are fixing."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "directory": {
+                    "type": "string",
+                    "description": "Project directory (defaults to the workspace root or current directory)"
+                },
+                "filter": {
+                    "type": "string",
+                    "description": "Only run tests whose name matches (cargo test filter, pytest -k, jest -t)"
+                },
+                "framework": {
+                    "type": "string",
+                    "enum": ["cargo", "pytest", "jest"],
+                    "description": "Override framework detection"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            directory: Option<String>,
+            filter: Option<String>,
+            framework: Option<TestFramework>,
+        }
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| Self::err(format!("Invalid arguments: {}", e)))?;
+
+        let dir = match (args.directory, workspace::primary_root(&self.workspace_root)) {
+            (Some(dir), _) => crate::file_ops::validate_path(
+                &dir,
+                &self.workspace_root,
+                self.executor.path_rules(),
+            )?,
+            (None, Some(root)) => root,
+            (None, None) => std::env::current_dir()
+                .map_err(|e| Self::err(format!("No current directory: {}", e)))?,
+        };
+        if !dir.is_dir() {
+            return Err(Self::err(format!("Not a directory: {}", dir.display())));
+        }
+        let framework = match args.framework.or_else(|| TestFramework::detect(&dir)) {
+            Some(framework) => framework,
+            None => {
+                return Err(Self::err(format!(
+                    "No test framework detected in {} (looked for Cargo.toml, Python project files, package.json)",
+                    dir.display()
+                )))
+            }
+        };
+        let filter = args.filter.as_deref().filter(|f| !f.trim().is_empty());
+
+        let result = self
+            .executor
+            .exec_shell(&framework.command(&dir, filter))
+            .await?;
+        let report = TestReport::parse(framework, &result);
+        Ok(report.summary(&result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exec(stdout: &str, stderr: &str, exit_code: i32) -> ExecResult {
+        ExecResult {
+            stdout: stdout.into(),
+            stderr: stderr.into(),
+            exit_code,
+        }
+    }
+
+    #[test]
+    fn test_detect_framework() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(TestFramework::detect(dir.path()), None);
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+        assert_eq!(TestFramework::detect(dir.path()), Some(TestFramework::Jest));
+        std::fs::write(dir.path().join("pyproject.toml"), "").unwrap();
+        assert_eq!(TestFramework::detect(dir.path()), Some(TestFramework::Pytest));
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        assert_eq!(TestFramework::detect(dir.path()), Some(TestFramework::Cargo));
+
+        let cmd = TestFramework::Pytest.command(Path::new("/p/it's"), Some("login"));
+        assert_eq!(cmd, r"cd '/p/it'\''s' && python3 -m pytest -q -rfE --tb=short -k 'login'");
+    }
+
+    #[test]
+    fn test_parse_cargo_failures() {
+        let stdout = "\
+running 2 tests
+test tests::adds ... ok
+test tests::subtracts ... FAILED
+
+failures:
+
+---- tests::subtracts stdout ----
+
+thread 'tests::subtracts' panicked at src/lib.rs:12:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+failures:
+    tests::subtracts
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+        let result = exec(stdout, "error: test failed, to rerun pass `--lib`", 101);
+        let report = TestReport::parse(TestFramework::Cargo, &result);
+        assert_eq!((report.passed, report.failed), (1, 1));
+        assert_eq!(report.failures[0].name, "tests::subtracts");
+        assert_eq!(report.failures[0].file.as_deref(), Some("src/lib.rs:12:9"));
+        assert!(report.failures[0].message.starts_with("assertion `left == right` failed"));
+
+        let summary = report.summary(&result);
+        assert!(summary.starts_with("cargo test FAILED: 1 passed, 1 failed (exit code 101)"));
+        assert!(summary.contains("FAILED tests::subtracts (src/lib.rs:12:9)\n  assertion"));
+
+        let build = exec("", "error[E0308]: mismatched types\n --> src/lib.rs:3:5\n", 101);
+        let report = TestReport::parse(TestFramework::Cargo, &build);
+        assert_eq!(report.failures[0].file.as_deref(), Some("src/lib.rs:3:5"));
+    }
+
+    #[test]
+    fn test_parse_pytest_and_jest() {
+        let stdout = "\
+.F.
+=========================== short test summary info ============================
+FAILED tests/test_math.py::test_div - ZeroDivisionError: division by zero
+1 failed, 2 passed in 0.03s
+";
+        let report = TestReport::parse(TestFramework::Pytest, &exec(stdout, "", 1));
+        assert_eq!((report.passed, report.failed), (2, 1));
+        assert_eq!(report.failures[0].name, "tests/test_math.py::test_div");
+        assert_eq!(report.failures[0].file.as_deref(), Some("tests/test_math.py"));
+        assert_eq!(report.failures[0].message, "ZeroDivisionError: division by zero");
+
+        let jest = json!({
+            "numPassedTests": 3,
+            "numFailedTests": 1,
+            "testResults": [{
+                "name": "/app/src/sum.test.js",
+                "status": "failed",
+                "assertionResults": [
+                    {"fullName": "sum adds", "status": "passed", "failureMessages": []},
+                    {"fullName": "sum handles NaN", "status": "failed", "failureMessages": ["Expected: 0\nReceived: NaN"]}
+                ]
+            }]
+        });
+        let report = TestReport::parse(TestFramework::Jest, &exec(&jest.to_string(), "", 1));
+        assert_eq!((report.passed, report.failed), (3, 1));
+        assert_eq!(report.failures[0].name, "sum handles NaN");
+        assert_eq!(report.failures[0].file.as_deref(), Some("/app/src/sum.test.js"));
+    }
+}
@@ -1,15 +1,23 @@
+use crate::native_sandbox::NativeSandbox;
 use agent_core::config::{AppConfig, SandboxMode};
 use agent_core::error::AgentError;
+use agent_core::path_rules::PathRules;
+use agent_core::workspace;
+use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::process::Command;
 use tracing::debug;
 
-/// Unified executor that dispatches to Docker or unsafe (direct) execution.
+/// Unified executor that dispatches to Docker, native (restricted host) or
+/// unsafe (direct) execution.
 pub struct SandboxExecutor {
     mode: SandboxMode,
     docker_image: String,
     timeout_secs: u64,
     memory_limit: Option<u64>,
     work_dir: String,
+    workspace_root: Option<PathBuf>,
+    path_rules: Arc<PathRules>,
 }
 
 impl SandboxExecutor {
@@ -20,13 +28,30 @@ impl SandboxExecutor {
             timeout_secs: config.sandbox.timeout_secs,
             memory_limit: config.sandbox.memory_limit,
             work_dir: config.sandbox.work_dir.clone(),
+            workspace_root: config.sandbox.workspace_root.clone(),
+            path_rules: Arc::new(PathRules::new(&config.sandbox.paths)),
         }
     }
 
+    /// The `[sandbox.paths]` rules, shared with the tools that check paths.
+    pub fn path_rules(&self) -> &Arc<PathRules> {
+        &self.path_rules
+    }
+
+    /// Whether commands run directly on the host, where they can modify
+    /// workspace files (Docker runs use a throwaway filesystem).
+    pub fn runs_on_host(&self) -> bool {
+        self.mode != SandboxMode::Docker
+    }
+
     /// Execute a command string, returning stdout+stderr.
     pub async fn exec_shell(&self, command: &str) -> Result<ExecResult, AgentError> {
         match self.mode {
             SandboxMode::Unsafe => self.exec_shell_unsafe(command).await,
+            SandboxMode::Native => {
+                debug!("Executing shell command (native mode): {}", command);
+                self.native().run("bash", &["-c", command], None).await
+            }
             SandboxMode::Docker => self.exec_shell_docker(command).await,
         }
     }
@@ -35,10 +60,42 @@ impl SandboxExecutor {
     pub async fn exec_python(&self, code: &str) -> Result<ExecResult, AgentError> {
         match self.mode {
             SandboxMode::Unsafe => self.exec_python_unsafe(code).await,
+            SandboxMode::Native => {
+                debug!("Executing Python code (native mode)");
+                self.native().run("python3", &["-"], Some(code)).await
+            }
             SandboxMode::Docker => self.exec_python_docker(code).await,
         }
     }
 
+    /// Roots `[sandbox.paths]` rules are relative to: the active workspace
+    /// roots, or the configured one.
+    pub fn workspace_roots(&self) -> Vec<PathBuf> {
+        workspace::allowed_roots(&self.workspace_root)
+    }
+
+    /// Directory host runs start in: the primary workspace root for native
+    /// runs, otherwise the current directory.
+    pub fn host_dir(&self) -> PathBuf {
+        match (workspace::primary_root(&self.workspace_root), self.mode) {
+            (Some(root), SandboxMode::Native) => root,
+            _ => std::env::current_dir().unwrap_or_default(),
+        }
+    }
+
+    fn native(&self) -> NativeSandbox {
+        NativeSandbox {
+            timeout_secs: self.timeout_secs,
+            memory_limit: self.memory_limit,
+            work_dir: self.host_dir(),
+            path_rules: self
+                .workspace_roots()
+                .iter()
+                .flat_map(|root| self.path_rules.literal_paths(root))
+                .collect(),
+        }
+    }
+
     // ── Unsafe (direct) execution ──────────────────────────────────────
 
     async fn exec_shell_unsafe(&self, command: &str) -> Result<ExecResult, AgentError> {
@@ -245,6 +302,11 @@ fn truncate_output(s: &str, max_bytes: usize) -> String {
     format!("{}... [truncated, {} total bytes]", &s[..end], s.len())
 }
 
+/// Single-quote `s` for `bash -c`.
+pub(crate) fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
 /// Result of executing code or a shell command.
 #[derive(Debug, Clone)]
 pub struct ExecResult {
@@ -275,4 +337,15 @@ impl ExecResult {
         parts.push(format!("exit_code: {}", self.exit_code));
         parts.join("\n")
     }
+
+    /// The last `n` non-blank lines of stdout followed by stderr, indented,
+    /// for when a tool's output could not be parsed.
+    pub fn tail(&self, n: usize) -> String {
+        let combined = format!("{}\n{}", self.stdout, self.stderr);
+        let lines: Vec<&str> = combined.lines().filter(|l| !l.trim().is_empty()).collect();
+        lines[lines.len().saturating_sub(n)..]
+            .iter()
+            .map(|line| format!("  {}\n", line))
+            .collect()
+    }
 }
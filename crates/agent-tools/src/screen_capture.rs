@@ -0,0 +1,306 @@
+//! Screenshot tool for multimodal debugging.
+//!
+//! Captures the screen or the focused window to a PNG in the workspace and
+//! attaches it for vision-capable models, so the agent can look at the
+//! failing UI the user is describing. Uses the platform's screenshot
+//! utility: `screencapture` on macOS, PowerShell on Windows and grim,
+//! gnome-screenshot, scrot or ImageMagick `import` on Linux. Registered only
+//! with `[tools] screen_capture = true`.
+
+use agent_core::error::AgentError;
+use agent_core::path_rules::PathRules;
+use agent_core::tool_registry::Tool;
+use agent_core::types::ImagePart;
+use agent_core::workspace;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Default directory for captures, relative to the workspace root.
+const DEFAULT_DIR: &str = ".agent-shell/screenshots";
+/// Largest capture attached for the model; bigger files are only saved.
+const MAX_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(20);
+const MAX_DELAY_SECS: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Target {
+    Screen,
+    Window,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    MacOs,
+    Windows,
+    Grim,
+    GnomeScreenshot,
+    Scrot,
+    Import,
+}
+
+impl Backend {
+    fn detect() -> Option<Self> {
+        Self::detect_with(std::env::consts::OS, |k| std::env::var_os(k).is_some(), in_path)
+    }
+
+    fn detect_with(
+        os: &str,
+        has_env: impl Fn(&str) -> bool,
+        in_path: impl Fn(&str) -> bool,
+    ) -> Option<Self> {
+        match os {
+            "macos" => Some(Self::MacOs),
+            "windows" => Some(Self::Windows),
+            _ if has_env("WAYLAND_DISPLAY") && in_path("grim") => Some(Self::Grim),
+            _ if in_path("gnome-screenshot") => Some(Self::GnomeScreenshot),
+            _ if has_env("DISPLAY") && in_path("scrot") => Some(Self::Scrot),
+            _ if has_env("DISPLAY") && in_path("import") => Some(Self::Import),
+            _ => None,
+        }
+    }
+
+    /// Program and arguments writing a PNG to `path`, or `None` when the
+    /// backend cannot capture `target`.
+    fn command(self, target: Target, path: &Path) -> Option<(&'static str, Vec<String>)> {
+        let path = path.to_string_lossy().into_owned();
+        let window = target == Target::Window;
+        let (program, flags): (&str, &[&str]) = match self {
+            Self::MacOs if !window => ("screencapture", &["-x", "-t", "png"]),
+            Self::Windows if !window => {
+                let script = format!(
+                    "Add-Type -AssemblyName System.Windows.Forms,System.Drawing; \
+                     $b = [System.Windows.Forms.SystemInformation]::VirtualScreen; \
+                     $bmp = New-Object System.Drawing.Bitmap $b.Width, $b.Height; \
+                     $g = [System.Drawing.Graphics]::FromImage($bmp); \
+                     $g.CopyFromScreen($b.Left, $b.Top, 0, 0, $bmp.Size); \
+                     $bmp.Save('{}', [System.Drawing.Imaging.ImageFormat]::Png)",
+                    path.replace('\'', "''")
+                );
+                return Some(("powershell", vec!["-NoProfile".into(), "-Command".into(), script]));
+            }
+            Self::Grim if !window => ("grim", &[]),
+            Self::GnomeScreenshot if window => ("gnome-screenshot", &["-w", "-f"]),
+            Self::GnomeScreenshot => ("gnome-screenshot", &["-f"]),
+            Self::Scrot if window => ("scrot", &["-u", "-o"]),
+            Self::Scrot => ("scrot", &["-o"]),
+            Self::Import if !window => ("import", &["-window", "root"]),
+            // No way to address the focused window without extra tools.
+            Self::MacOs | Self::Windows | Self::Grim | Self::Import => return None,
+        };
+        let mut args: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        args.push(path);
+        Some((program, args))
+    }
+}
+
+fn in_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// Capture the screen or focused window to a workspace file.
+pub struct ScreenCaptureTool {
+    workspace_root: Option<PathBuf>,
+    path_rules: Arc<PathRules>,
+}
+
+impl ScreenCaptureTool {
+    pub fn new(workspace_root: Option<PathBuf>, path_rules: Arc<PathRules>) -> Self {
+        Self {
+            workspace_root,
+            path_rules,
+        }
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        AgentError::ToolExecution {
+            tool_name: "screen_capture".into(),
+            message: msg.into(),
+        }
+    }
+
+    /// Take the screenshot; returns the description and the saved file.
+    async fn capture(&self, args: Value) -> Result<(String, PathBuf), AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            target: Option<Target>,
+            path: Option<String>,
+            #[serde(default)]
+            delay_secs: u64,
+        }
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| Self::err(format!("Invalid arguments: {}", e)))?;
+        let target = args.target.unwrap_or(Target::Screen);
+
+        let raw = match args.path {
+            Some(path) => path,
+            None => {
+                let base = match workspace::primary_root(&self.workspace_root) {
+                    Some(root) => root,
+                    None => std::env::current_dir()
+                        .map_err(|e| Self::err(format!("No current directory: {}", e)))?,
+                };
+                let name = format!("screen-{}.png", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+                base.join(DEFAULT_DIR).join(name).to_string_lossy().into_owned()
+            }
+        };
+        if !raw.to_ascii_lowercase().ends_with(".png") {
+            return Err(Self::err("Captures are saved as PNG; use a .png path"));
+        }
+        let path =
+            crate::file_ops::validate_write_path(&raw, &self.workspace_root, &self.path_rules)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| Self::err(format!("Failed to create {}: {}", parent.display(), e)))?;
+        }
+
+        let backend = Backend::detect().ok_or_else(|| {
+            Self::err("No screenshot utility found (Linux needs grim, gnome-screenshot, scrot or ImageMagick)")
+        })?;
+        let (program, cmd_args) = backend.command(target, &path).ok_or_else(|| {
+            Self::err(format!(
+                "{:?} cannot capture a single window here; use target \"screen\"",
+                backend
+            ))
+        })?;
+
+        if args.delay_secs > 0 {
+            tokio::time::sleep(Duration::from_secs(args.delay_secs.min(MAX_DELAY_SECS))).await;
+        }
+        let output = tokio::time::timeout(
+            TIMEOUT,
+            Command::new(program)
+                .args(&cmd_args)
+                .stdin(Stdio::null())
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await
+        .map_err(|_| Self::err(format!("{} timed out", program)))?
+        .map_err(|e| Self::err(format!("Failed to run {}: {}", program, e)))?;
+        if !output.status.success() || !path.exists() {
+            return Err(Self::err(format!(
+                "{} failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let text = format!(
+            "Captured the {} to {} ({} bytes)",
+            if target == Target::Window { "focused window" } else { "screen" },
+            path.display(),
+            size
+        );
+        Ok((text, path))
+    }
+}
+
+#[async_trait]
+impl Tool for ScreenCaptureTool {
+    fn name(&self) -> &str {
+        "screen_capture"
+    }
+
+    fn description(&self) -> &str {
+        "Take a screenshot of the screen or the focused window and save it as a PNG in the \
+         workspace. The image is attached for viewing when the model supports images. Use it \
+         to see a UI problem the user describes."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "target": {
+                    "type": "string",
+                    "enum": ["screen", "window"],
+                    "description": "Whole screen (default) or the focused window"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Output .png path (default: .agent-shell/screenshots/ in the workspace)"
+                },
+                "delay_secs": {
+                    "type": "integer",
+                    "description": "Wait before capturing so the user can bring the window forward (max 10)"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        Ok(self.capture(args).await?.0)
+    }
+
+    async fn execute_with_images(
+        &self,
+        args: Value,
+    ) -> Result<(String, Vec<ImagePart>), AgentError> {
+        let (text, path) = self.capture(args).await?;
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if size > MAX_IMAGE_BYTES {
+            return Ok((format!("{}; too large to attach", text), Vec::new()));
+        }
+        let bytes = tokio::fs::read(&path)
+            .await
+            .map_err(|e| Self::err(format!("Failed to read {}: {}", path.display(), e)))?;
+        Ok((text, vec![ImagePart::from_bytes("image/png", &bytes)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_backend() {
+        let none = |_: &str| false;
+        assert_eq!(Backend::detect_with("macos", none, none), Some(Backend::MacOs));
+        assert_eq!(Backend::detect_with("linux", none, none), None);
+        assert_eq!(
+            Backend::detect_with("linux", |k| k == "WAYLAND_DISPLAY", |p| p == "grim"),
+            Some(Backend::Grim)
+        );
+        assert_eq!(
+            Backend::detect_with("linux", |k| k == "DISPLAY", |p| p == "scrot" || p == "import"),
+            Some(Backend::Scrot)
+        );
+    }
+
+    #[test]
+    fn test_backend_command() {
+        let path = Path::new("/ws/shot.png");
+        assert_eq!(
+            Backend::Scrot.command(Target::Window, path),
+            Some(("scrot", vec!["-u".into(), "-o".into(), "/ws/shot.png".into()]))
+        );
+        assert_eq!(
+            Backend::MacOs.command(Target::Screen, path).unwrap().1,
+            vec!["-x", "-t", "png", "/ws/shot.png"]
+        );
+        assert_eq!(Backend::Grim.command(Target::Window, path), None);
+    }
+
+    #[tokio::test]
+    async fn test_capture_rejects_paths_outside_workspace() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let tool = ScreenCaptureTool::new(Some(dir.path().to_path_buf()), Default::default());
+        let err = tool
+            .execute(json!({"path": "/etc/shot.png"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("outside the workspace root"), "{err}");
+        assert!(tool.execute(json!({"path": "shot.jpg"})).await.is_err());
+    }
+}
@@ -1,19 +1,98 @@
 use crate::sandbox::SandboxExecutor;
 use agent_core::error::AgentError;
+use agent_core::path_rules::PathRules;
 use agent_core::tool_registry::Tool;
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+/// Programs read-only mode lets `shell_exec` run, each with the options
+/// that would make it write files or run other programs. Anything else is
+/// refused.
+const READ_ONLY_PROGRAMS: &[(&str, &[&str])] = &[
+    ("ls", &[]),
+    ("cat", &[]),
+    ("head", &[]),
+    ("tail", &[]),
+    ("wc", &[]),
+    ("grep", &[]),
+    ("egrep", &[]),
+    ("fgrep", &[]),
+    ("rg", &["--pre"]),
+    ("find", &["-delete", "-fprint", "-fls"]),
+    ("tree", &["-o"]),
+    ("stat", &[]),
+    ("file", &["-C", "--compile"]),
+    ("du", &[]),
+    ("df", &[]),
+    ("pwd", &[]),
+    ("cd", &[]),
+    ("echo", &[]),
+    ("printf", &[]),
+    ("true", &[]),
+    ("false", &[]),
+    ("test", &[]),
+    ("which", &[]),
+    ("type", &[]),
+    ("whoami", &[]),
+    ("id", &[]),
+    ("uname", &[]),
+    ("date", &["-s", "--set"]),
+    ("basename", &[]),
+    ("dirname", &[]),
+    ("realpath", &[]),
+    ("readlink", &[]),
+    ("cmp", &[]),
+    ("diff", &[]),
+    ("cut", &[]),
+    ("sort", &["-o", "--output", "--compress-program"]),
+    ("uniq", &[]),
+    ("tr", &[]),
+    ("nl", &[]),
+    ("tac", &[]),
+    ("rev", &[]),
+    ("paste", &[]),
+    ("fold", &[]),
+    ("od", &[]),
+    ("hexdump", &[]),
+    ("strings", &[]),
+    ("md5sum", &[]),
+    ("sha1sum", &[]),
+    ("sha256sum", &[]),
+    ("sha512sum", &[]),
+    ("cksum", &[]),
+    ("jq", &[]),
+    ("git", &["--output", "-O", "--open-files-in-pager", "--ext-diff", "--textconv"]),
+];
+
+/// Git subcommands that only read the repository.
+const GIT_READ_COMMANDS: &[&str] = &[
+    "status", "log", "show", "diff", "blame", "annotate", "grep", "ls-files", "ls-tree",
+    "ls-remote", "rev-parse", "rev-list", "describe", "shortlog", "cat-file", "merge-base",
+    "name-rev", "whatchanged", "show-ref", "for-each-ref", "count-objects", "version", "help",
+];
+
 /// Execute shell commands via the sandbox.
 pub struct ShellExecTool {
     executor: Arc<SandboxExecutor>,
+    read_only: bool,
 }
 
 impl ShellExecTool {
     pub fn new(executor: Arc<SandboxExecutor>) -> Self {
-        Self { executor }
+        Self {
+            executor,
+            read_only: false,
+        }
+    }
+
+    /// Run only commands known to leave files and the repository alone
+    /// (`[tools] read_only`).
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
     }
 }
 
@@ -41,6 +120,25 @@ impl Tool for ShellExecTool {
         })
     }
 
+    fn affected_paths(&self, args: &Value) -> Vec<PathBuf> {
+        let Some(command) = args.get("command").and_then(Value::as_str) else {
+            return Vec::new();
+        };
+        if !self.executor.runs_on_host() {
+            return Vec::new();
+        }
+        let cwd = self.executor.host_dir();
+        destructive_paths(command)
+            .into_iter()
+            .map(|p| cwd.join(p))
+            .collect()
+    }
+
+    fn read_only(&self) -> bool {
+        // In read-only mode writing commands are refused.
+        self.read_only
+    }
+
     async fn execute(&self, args: Value) -> Result<String, AgentError> {
         #[derive(Deserialize)]
         struct Args {
@@ -50,8 +148,453 @@ impl Tool for ShellExecTool {
             tool_name: "shell_exec".into(),
             message: format!("Invalid arguments: {}", e),
         })?;
+        if self.read_only {
+            if let Some(reason) = write_reason(&args.command) {
+                return Err(AgentError::ToolExecution {
+                    tool_name: "shell_exec".into(),
+                    message: format!(
+                        "Read-only mode: {}. Only commands that read are allowed.",
+                        reason
+                    ),
+                });
+            }
+        }
+
+        if self.executor.runs_on_host() {
+            let roots = self.executor.workspace_roots();
+            let cwd = self.executor.host_dir();
+            let rules = self.executor.path_rules();
+            if let Some(message) = path_rule_violation(rules, &args.command, &cwd, &roots) {
+                return Err(AgentError::ToolExecution {
+                    tool_name: "shell_exec".into(),
+                    message,
+                });
+            }
+        }
 
         let result = self.executor.exec_shell(&args.command).await?;
         Ok(result.to_display_string())
     }
 }
+
+/// Files a shell command would delete or overwrite: the operands of `rm`,
+/// `unlink`, `shred`, `truncate`, `mv` and `sed -i`, the destination of
+/// `cp`, `tee` targets and `>`/`>>` redirections. Best effort: operands
+/// with globs, variables or command substitutions are skipped.
+fn destructive_paths(command: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    for segment in command.split(['\n', ';', '|', '&']) {
+        let words = split_words(segment);
+        let mut operands: Vec<&str> = Vec::new();
+        let mut iter = words.iter().map(String::as_str).peekable();
+        while let Some(word) = iter.next() {
+            // Redirections: `> file`, `>>file`, `2> file`.
+            let redirect = word.trim_start_matches(|c: char| c.is_ascii_digit());
+            if let Some(target) = redirect.strip_prefix(">>").or(redirect.strip_prefix('>')) {
+                let target = if target.is_empty() { iter.next() } else { Some(target) };
+                if let Some(target) = target.filter(|t| !t.starts_with('&')) {
+                    paths.push(target.to_string());
+                }
+                continue;
+            }
+            operands.push(word);
+        }
+
+        // Skip `sudo`, `command` and env assignments before the program.
+        let start = operands
+            .iter()
+            .position(|w| !w.contains('=') && !matches!(*w, "sudo" | "command" | "exec" | "time"))
+            .unwrap_or(operands.len());
+        let Some((program, args)) = operands[start..].split_first() else {
+            continue;
+        };
+        let program = program.rsplit('/').next().unwrap_or(program);
+        let files: Vec<&str> = args.iter().copied().filter(|a| !a.starts_with('-')).collect();
+        match program {
+            "rm" | "unlink" | "shred" | "mv" | "tee" => paths.extend(files.iter().map(|f| f.to_string())),
+            "truncate" => {
+                // `-s SIZE` takes a value.
+                let mut skip = false;
+                for a in args {
+                    if skip {
+                        skip = false;
+                    } else if *a == "-s" || *a == "--size" {
+                        skip = true;
+                    } else if !a.starts_with('-') {
+                        paths.push(a.to_string());
+                    }
+                }
+            }
+            "cp" => {
+                if let Some(dest) = files.last().filter(|_| files.len() > 1) {
+                    paths.push(dest.to_string());
+                }
+            }
+            "sed" if args.iter().any(|a| a.starts_with("-i") || a.starts_with("--in-place")) => {
+                // Without -e/-f the first operand is the script.
+                let has_script_flag = args.iter().any(|a| *a == "-e" || *a == "-f");
+                let mut skip = false;
+                let mut operands = Vec::new();
+                for a in args {
+                    if skip {
+                        skip = false;
+                    } else if *a == "-e" || *a == "-f" {
+                        skip = true;
+                    } else if !a.starts_with('-') {
+                        operands.push(*a);
+                    }
+                }
+                let files = if has_script_flag { &operands[..] } else { operands.get(1..).unwrap_or(&[]) };
+                paths.extend(files.iter().map(|f| f.to_string()));
+            }
+            _ => {}
+        }
+    }
+    paths.retain(|p| {
+        !p.is_empty() && p != "/dev/null" && !p.contains(['*', '?', '[', '$', '`', '~'])
+    });
+    paths.dedup();
+    paths
+}
+
+/// Why `[sandbox.paths]` refuses a command run on the host in `cwd`: it
+/// would modify (see [`destructive_paths`]) a path under an `ro` or `deny`
+/// rule, or names an existing path under a `deny` rule. Best effort; the
+/// macOS native sandbox also enforces literal rules itself.
+fn path_rule_violation(
+    rules: &PathRules,
+    command: &str,
+    cwd: &Path,
+    roots: &[PathBuf],
+) -> Option<String> {
+    if rules.is_empty() {
+        return None;
+    }
+    let roots: Vec<PathBuf> = roots.iter().filter_map(|r| r.canonicalize().ok()).collect();
+    // Canonical form of a path that may not exist yet.
+    let resolve = |raw: &str| {
+        let path = cwd.join(raw);
+        path.canonicalize().ok().or_else(|| {
+            let parent = path.parent()?.canonicalize().ok()?;
+            Some(parent.join(path.file_name()?))
+        })
+    };
+    for raw in destructive_paths(command) {
+        if let Some(err) = resolve(&raw).and_then(|p| rules.check(&p, &roots, true).err()) {
+            return Some(err);
+        }
+    }
+    split_words(command)
+        .iter()
+        .filter(|word| !word.starts_with('-') && cwd.join(word.as_str()).exists())
+        .find_map(|word| resolve(word).and_then(|p| rules.check(&p, &roots, false).err()))
+}
+
+/// Why read-only mode refuses a command: unless every command in it is a
+/// [`READ_ONLY_PROGRAMS`] entry without its writing options (`git` only
+/// with a read-only subcommand), it may change files or the repository.
+/// Redirects other than to `/dev/null`, command and process substitution
+/// and environment assignments are refused too, as are `find -exec` and
+/// `xargs` of programs that have writing options, since the file names
+/// they pass could be taken for those options.
+fn write_reason(command: &str) -> Option<String> {
+    if substitutes_commands(command) {
+        return Some("it runs commands through `$(...)`, backticks or `<(...)`".to_string());
+    }
+    // `2>&1` and the like only duplicate descriptors.
+    let command = command.replace(">&1", ">/dev/null").replace(">&2", ">/dev/null");
+    for segment in command.split(['\n', ';', '|', '&']) {
+        let words = split_words(segment);
+        let mut operands: Vec<&str> = Vec::new();
+        let mut iter = words.iter().map(String::as_str);
+        while let Some(word) = iter.next() {
+            let redirect = word.trim_start_matches(|c: char| c.is_ascii_digit());
+            if let Some(target) = redirect.strip_prefix(">>").or(redirect.strip_prefix('>')) {
+                let target = if target.is_empty() { iter.next() } else { Some(target) };
+                if let Some(target) = target.filter(|t| *t != "/dev/null") {
+                    return Some(format!("it redirects output to {}", target));
+                }
+                continue;
+            }
+            if let Some(source) = redirect.strip_prefix('<') {
+                if source.is_empty() {
+                    iter.next();
+                }
+                continue;
+            }
+            operands.push(word);
+        }
+
+        // Skip wrappers before the program; `xargs` runs the program after
+        // its flags.
+        let mut rest = &operands[..];
+        let mut xargs = false;
+        while let Some((word, tail)) = rest.split_first() {
+            if word.contains('=') {
+                return Some("it sets environment variables".to_string());
+            } else if matches!(*word, "sudo" | "command" | "exec" | "time" | "env" | "nice" | "nohup") {
+                rest = tail;
+            } else if *word == "xargs" {
+                let start = tail.iter().position(|a| !a.starts_with('-')).unwrap_or(tail.len());
+                rest = &tail[start..];
+                xargs = true;
+            } else {
+                break;
+            }
+        }
+        let Some((program, args)) = rest.split_first() else {
+            continue;
+        };
+        let program = program.rsplit('/').next().unwrap_or(program);
+        if let Some(reason) = program_reason(program, args) {
+            return Some(reason);
+        }
+        if xargs && writing_options(program).is_some_and(|options| !options.is_empty()) {
+            return Some(format!("`xargs` may pass `{}` options that write", program));
+        }
+        if program == "find" {
+            // `-exec`, `-execdir`, `-ok` and `-okdir`, up to `;` or `+`.
+            let mut args = args.iter();
+            while let Some(arg) = args.next() {
+                if !matches!(*arg, "-exec" | "-execdir" | "-ok" | "-okdir") {
+                    continue;
+                }
+                let inner: Vec<&str> =
+                    args.by_ref().take_while(|a| !matches!(**a, ";" | "+")).copied().collect();
+                let Some((inner, inner_args)) = inner.split_first() else {
+                    continue;
+                };
+                let inner = inner.rsplit('/').next().unwrap_or(inner);
+                if let Some(reason) = program_reason(inner, inner_args) {
+                    return Some(reason);
+                }
+                if writing_options(inner).is_some_and(|options| !options.is_empty()) {
+                    return Some(format!("`find {}` may pass `{}` options that write", arg, inner));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The writing options of a [`READ_ONLY_PROGRAMS`] entry; `None` for
+/// programs that are not read-only.
+fn writing_options(program: &str) -> Option<&'static [&'static str]> {
+    READ_ONLY_PROGRAMS
+        .iter()
+        .find(|(name, _)| *name == program)
+        .map(|(_, options)| *options)
+}
+
+/// Why running `program` with `args` may write, if it may.
+fn program_reason(program: &str, args: &[&str]) -> Option<String> {
+    let Some(options) = writing_options(program) else {
+        return Some(format!("`{}` is not a known read-only command", program));
+    };
+    let mut args = args;
+    if program == "git" {
+        let sub = git_subcommand(args);
+        let globals = &args[..sub.unwrap_or(args.len())];
+        if let Some(option) = globals.iter().find(|a| {
+            a.starts_with("-c") || a.starts_with("--config-env") || a.starts_with("--exec-path")
+        }) {
+            return Some(format!("`git {}` may run other programs", option));
+        }
+        // `git --version` and the like.
+        let sub = sub?;
+        if !GIT_READ_COMMANDS.contains(&args[sub]) {
+            return Some(format!("`git {}` changes the repository", args[sub]));
+        }
+        args = &args[sub + 1..];
+    }
+    if program == "uniq" && args.iter().filter(|a| !a.starts_with('-')).count() > 1 {
+        return Some("`uniq` with an output file changes files".to_string());
+    }
+    args.iter()
+        .find_map(|arg| options.iter().find(|option| has_option(arg, option)))
+        .map(|option| format!("`{} {}` may write files or run other programs", program, option))
+}
+
+/// Whether `arg` sets `option`: a long option, possibly with `=value`, or
+/// a single-dash `find` action by prefix, or a short option, possibly in a
+/// cluster such as `-ro`.
+fn has_option(arg: &str, option: &str) -> bool {
+    if option.starts_with("--") || option.len() > 2 {
+        return arg.starts_with(option);
+    }
+    let flag = option.trim_start_matches('-');
+    arg.starts_with('-') && !arg.starts_with("--") && arg[1..].contains(flag)
+}
+
+/// Whether `command` substitutes command output, with `$(...)` or
+/// backticks outside single quotes or unquoted `<(...)`/`>(...)`.
+fn substitutes_commands(command: &str) -> bool {
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => {
+                chars.next();
+            }
+            (Some('"'), '"') => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            (_, '`') => return true,
+            (_, '$') if chars.peek() == Some(&'(') => return true,
+            (None, '<' | '>') if chars.peek() == Some(&'(') => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Where the subcommand of a `git` invocation is, after global options.
+fn git_subcommand(args: &[&str]) -> Option<usize> {
+    let mut iter = args.iter().enumerate();
+    while let Some((i, arg)) = iter.next() {
+        if matches!(*arg, "-C" | "-c" | "--git-dir" | "--work-tree" | "--namespace") {
+            iter.next();
+        } else if !arg.starts_with('-') {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Split a command into words, honouring single and double quotes.
+fn split_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_word = false;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => word.extend(chars.next()),
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                word.extend(chars.next());
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::config::PathAccess;
+
+    #[test]
+    fn test_destructive_paths() {
+        assert_eq!(destructive_paths("rm -rf build 'my notes.txt'"), vec!["build", "my notes.txt"]);
+        assert_eq!(destructive_paths("mv a.rs b.rs && cargo build"), vec!["a.rs", "b.rs"]);
+        assert_eq!(destructive_paths("cp -r src backup"), vec!["backup"]);
+        assert_eq!(destructive_paths("sed -i 's/a/b/' x.txt y.txt"), vec!["x.txt", "y.txt"]);
+        assert_eq!(destructive_paths("sed -i.bak -e 's/a/b/' z.txt"), vec!["z.txt"]);
+        assert_eq!(destructive_paths("echo hi > out.log 2>/dev/null"), vec!["out.log"]);
+        assert_eq!(destructive_paths("date >>log.txt; sudo truncate -s 0 big.log"), vec!["log.txt", "big.log"]);
+        assert_eq!(destructive_paths("cat a | tee -a copy.txt"), vec!["copy.txt"]);
+        assert_eq!(destructive_paths("ls -la && grep -r foo ."), Vec::<String>::new());
+        assert_eq!(destructive_paths("rm *.o $TMP/x"), Vec::<String>::new());
+        assert_eq!(destructive_paths("sed 's/a/b/' x.txt"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_path_rule_violation() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("migrations")).unwrap();
+        std::fs::create_dir_all(root.join("secrets")).unwrap();
+        std::fs::write(root.join("secrets/key"), "k").unwrap();
+        let rules = PathRules::new(&std::collections::BTreeMap::from([
+            ("migrations/**".to_string(), PathAccess::Ro),
+            ("secrets/**".to_string(), PathAccess::Deny),
+        ]));
+        let roots = vec![root.clone()];
+        let check = |command: &str| path_rule_violation(&rules, command, &root, &roots);
+
+        assert_eq!(check("ls migrations && wc -l migrations/*.sql"), None);
+        let err = check("echo x > migrations/002.sql").unwrap();
+        assert!(err.contains("read-only"), "{}", err);
+        let err = check("cat secrets/key").unwrap();
+        assert!(err.contains("denied"), "{}", err);
+        assert_eq!(check("cat secrets/missing"), None);
+    }
+
+    #[test]
+    fn test_write_reason() {
+        for command in [
+            "ls -la && grep -rn foo src | head",
+            "git -C repo log --oneline -5 && git diff HEAD~1 2>&1",
+            "cd src; find . -name '*.rs' -exec wc -l {} + 2>/dev/null",
+            "git --version",
+            "sort -u < names.txt | uniq -c",
+            "grep -rn '$(echo)' src",
+            "find . -name '*.rs' | xargs -0 grep -l TODO",
+        ] {
+            assert_eq!(write_reason(command), None, "{}", command);
+        }
+        assert_eq!(
+            write_reason("git add -A && git commit -m wip").unwrap(),
+            "`git add` changes the repository"
+        );
+        assert_eq!(write_reason("echo hi >> notes.txt").unwrap(), "it redirects output to notes.txt");
+        assert_eq!(write_reason("find . -delete").unwrap(), "`find -delete` may write files or run other programs");
+        assert_eq!(write_reason("sort -ro out.txt in.txt").unwrap(), "`sort -o` may write files or run other programs");
+        assert_eq!(write_reason("uniq in.txt out.txt").unwrap(), "`uniq` with an output file changes files");
+        assert_eq!(write_reason("git -c core.pager=sh log").unwrap(), "`git -c` may run other programs");
+        assert_eq!(write_reason("PAGER=./evil git log").unwrap(), "it sets environment variables");
+        assert_eq!(
+            write_reason("find . | xargs sort").unwrap(),
+            "`xargs` may pass `sort` options that write"
+        );
+        assert_eq!(
+            write_reason("find . -exec sort {} +").unwrap(),
+            "`find -exec` may pass `sort` options that write"
+        );
+
+        // Anything not known to only read is refused, however it is run.
+        for (command, reason) in [
+            ("echo $(rm -rf x)", "it runs commands through `$(...)`, backticks or `<(...)`"),
+            ("echo \"`touch x`\"", "it runs commands through `$(...)`, backticks or `<(...)`"),
+            ("diff <(make) x", "it runs commands through `$(...)`, backticks or `<(...)`"),
+            ("sudo rm -rf build", "`rm` is not a known read-only command"),
+            ("sed -i 's/a/b/' x", "`sed` is not a known read-only command"),
+            ("make", "`make` is not a known read-only command"),
+            ("cargo build", "`cargo` is not a known read-only command"),
+            ("npm test", "`npm` is not a known read-only command"),
+            ("tar -xf a.tar", "`tar` is not a known read-only command"),
+            ("unzip a.zip", "`unzip` is not a known read-only command"),
+            ("curl -o page.html https://example.com", "`curl` is not a known read-only command"),
+            ("wget https://example.com", "`wget` is not a known read-only command"),
+            ("git diff --output=patch.diff", "`git --output` may write files or run other programs"),
+            ("git log --output patch.diff", "`git --output` may write files or run other programs"),
+            ("/usr/bin/python3 -c \"open('f','w').write('x')\"", "`python3` is not a known read-only command"),
+            ("ls | xargs -n1 node -e 'fs.rmSync(process.argv[1])'", "`node` is not a known read-only command"),
+            ("find . -name '*.tmp' -exec sh -c 'rm \"$1\"' _ {} \\;", "`sh` is not a known read-only command"),
+        ] {
+            assert_eq!(write_reason(command).unwrap(), reason, "{}", command);
+        }
+    }
+}
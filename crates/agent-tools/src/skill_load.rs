@@ -68,6 +68,10 @@ impl Tool for SkillLoadTool {
         })
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, args: Value) -> Result<String, AgentError> {
         let action = args
             .get("action")
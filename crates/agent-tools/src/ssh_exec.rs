@@ -0,0 +1,299 @@
+//! Remote command execution over SSH.
+//!
+//! Runs commands through the system `ssh` client, so the user's agent, keys
+//! and `~/.ssh/config` apply. Only hosts configured under
+//! `[ssh.hosts.<name>]` can be reached, and each host's `allow`/`deny`
+//! patterns decide which commands may run there. Output is read as it
+//! arrives, so a command that hits the timeout still returns what it printed.
+
+use crate::sandbox::ExecResult;
+use agent_core::config::{SshConfig, SshHostConfig};
+use agent_core::error::AgentError;
+use agent_core::scrubber::SecretScrubber;
+use agent_core::tool_registry::Tool;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+
+/// Bytes kept per stream; the display string truncates further.
+const MAX_CAPTURE_BYTES: usize = 1024 * 1024;
+
+/// Characters that chain, substitute or redirect commands. Refused on hosts
+/// with an `allow` list, where they would let a permitted prefix smuggle in
+/// anything else.
+const SHELL_OPERATORS: &[char] = &[';', '&', '|', '<', '>', '`', '$', '\n', '\r'];
+
+/// Whether `text` matches `pattern`, where `*` matches any run of characters.
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && p[pi] == '*' {
+            backtrack = Some((pi, ti));
+            pi += 1;
+        } else if pi < p.len() && p[pi] == t[ti] {
+            pi += 1;
+            ti += 1;
+        } else if let Some((star, matched)) = backtrack {
+            pi = star + 1;
+            ti = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+/// Check `command` against the host's policy.
+fn check_policy(host: &SshHostConfig, command: &str) -> Result<(), String> {
+    if let Some(pattern) = host.deny.iter().find(|p| matches_pattern(p, command)) {
+        return Err(format!("command is denied by pattern '{}'", pattern));
+    }
+    if host.allow.is_empty() {
+        return Ok(());
+    }
+    if command.contains(SHELL_OPERATORS) {
+        return Err("shell operators (; & | < > ` $) are not allowed on this host".into());
+    }
+    if host.allow.iter().any(|p| matches_pattern(p, command)) {
+        Ok(())
+    } else {
+        Err(format!(
+            "command is not in the host's allow list: {}",
+            host.allow.join(", ")
+        ))
+    }
+}
+
+/// ssh arguments for running `command` on `host`.
+fn ssh_args(host: &SshHostConfig, command: &str) -> Vec<String> {
+    let mut args = vec![
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        "ConnectTimeout=10".to_string(),
+    ];
+    if let Some(port) = host.port {
+        args.extend(["-p".to_string(), port.to_string()]);
+    }
+    if let Some(identity) = &host.identity_file {
+        args.extend(["-i".to_string(), identity.to_string_lossy().into_owned()]);
+    }
+    if let Some(user) = &host.user {
+        args.extend(["-l".to_string(), user.clone()]);
+    }
+    args.extend(["--".to_string(), host.host.clone(), command.to_string()]);
+    args
+}
+
+/// Read a stream to the end, keeping the first `MAX_CAPTURE_BYTES`.
+async fn drain(mut reader: impl AsyncRead + Unpin, buf: Arc<Mutex<Vec<u8>>>) {
+    let mut chunk = [0u8; 8192];
+    while let Ok(n) = reader.read(&mut chunk).await {
+        if n == 0 {
+            break;
+        }
+        let mut buf = buf.lock().unwrap();
+        let room = MAX_CAPTURE_BYTES.saturating_sub(buf.len());
+        buf.extend_from_slice(&chunk[..n.min(room)]);
+    }
+}
+
+/// Run commands on allowlisted remote hosts.
+pub struct SshExecTool {
+    config: SshConfig,
+    scrubber: SecretScrubber,
+    description: String,
+}
+
+impl SshExecTool {
+    pub fn new(config: SshConfig) -> Self {
+        let mut names: Vec<&str> = config.hosts.keys().map(|k| k.as_str()).collect();
+        names.sort();
+        let description = format!(
+            "Run a command on a configured remote host over SSH and return its output. \
+             Available hosts: {}. Each host may restrict which commands are allowed.",
+            if names.is_empty() { "none".to_string() } else { names.join(", ") }
+        );
+        Self {
+            config,
+            scrubber: SecretScrubber::new(),
+            description,
+        }
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        AgentError::ToolExecution {
+            tool_name: "ssh_exec".into(),
+            message: msg.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for SshExecTool {
+    fn name(&self) -> &str {
+        "ssh_exec"
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        let mut names: Vec<&String> = self.config.hosts.keys().collect();
+        names.sort();
+        json!({
+            "type": "object",
+            "properties": {
+                "host": {
+                    "type": "string",
+                    "enum": names,
+                    "description": "Configured host name"
+                },
+                "command": {
+                    "type": "string",
+                    "description": "Command to run on the host"
+                }
+            },
+            "required": ["host", "command"]
+        })
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            host: String,
+            command: String,
+        }
+        let args: Args = serde_json::from_value(args)
+            .map_err(|e| Self::err(format!("Invalid arguments: {}", e)))?;
+
+        let host = self
+            .config
+            .hosts
+            .get(&args.host)
+            .ok_or_else(|| Self::err(format!("Unknown host '{}'", args.host)))?;
+        let command = args.command.split_whitespace().collect::<Vec<_>>().join(" ");
+        if command.is_empty() {
+            return Err(Self::err("Empty command"));
+        }
+        check_policy(host, &command).map_err(|e| Self::err(format!("{}: {}", args.host, e)))?;
+
+        let mut child = Command::new(&self.config.ssh)
+            .args(ssh_args(host, &command))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| Self::err(format!("Failed to run {}: {}", self.config.ssh, e)))?;
+
+        let stdout = Arc::new(Mutex::new(Vec::new()));
+        let stderr = Arc::new(Mutex::new(Vec::new()));
+        let readers = [
+            tokio::spawn(drain(child.stdout.take().unwrap(), stdout.clone())),
+            tokio::spawn(drain(child.stderr.take().unwrap(), stderr.clone())),
+        ];
+
+        let timeout = std::time::Duration::from_secs(self.config.timeout_secs);
+        let (exit_code, timed_out) = match tokio::time::timeout(timeout, child.wait()).await {
+            Ok(Ok(status)) => (status.code().unwrap_or(-1), false),
+            Ok(Err(e)) => return Err(Self::err(format!("ssh failed: {}", e))),
+            Err(_) => {
+                let _ = child.kill().await;
+                (-1, true)
+            }
+        };
+        for reader in readers {
+            let _ = reader.await;
+        }
+
+        let text = |buf: &Arc<Mutex<Vec<u8>>>| {
+            let bytes = buf.lock().unwrap();
+            self.scrubber
+                .scrub(&String::from_utf8_lossy(&bytes))
+                .into_owned()
+        };
+        let result = ExecResult {
+            stdout: text(&stdout),
+            stderr: text(&stderr),
+            exit_code,
+        };
+        let mut out = result.to_display_string();
+        if timed_out {
+            out.push_str(&format!(
+                "\n[timed out after {}s; connection closed]",
+                self.config.timeout_secs
+            ));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(allow: &[&str], deny: &[&str]) -> SshHostConfig {
+        SshHostConfig {
+            host: "web1.example.com".into(),
+            user: Some("deploy".into()),
+            port: Some(2222),
+            allow: allow.iter().map(|s| s.to_string()).collect(),
+            deny: deny.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("systemctl status *", "systemctl status nginx"));
+        assert!(matches_pattern("df -h", "df -h"));
+        assert!(matches_pattern("*reboot*", "sudo reboot now"));
+        assert!(!matches_pattern("df -h", "df -h /"));
+        assert!(!matches_pattern("journalctl -u *", "journalctl -f"));
+    }
+
+    #[test]
+    fn test_check_policy() {
+        let h = host(&["systemctl status *", "uptime"], &["*nginx*"]);
+        assert!(check_policy(&h, "uptime").is_ok());
+        assert!(check_policy(&h, "systemctl status postgres").is_ok());
+        assert!(check_policy(&h, "systemctl status nginx").is_err());
+        assert!(check_policy(&h, "systemctl restart postgres").is_err());
+        assert!(check_policy(&h, "systemctl status x; rm -rf /").is_err());
+        assert!(check_policy(&h, "systemctl status $(reboot)").is_err());
+
+        let open = host(&[], &["rm *"]);
+        assert!(check_policy(&open, "ls /var/log | tail").is_ok());
+        assert!(check_policy(&open, "rm -rf /tmp/x").is_err());
+    }
+
+    #[test]
+    fn test_ssh_args() {
+        assert_eq!(
+            ssh_args(&host(&[], &[]), "uptime"),
+            vec![
+                "-o", "BatchMode=yes", "-o", "ConnectTimeout=10", "-p", "2222", "-l", "deploy",
+                "--", "web1.example.com", "uptime"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_host_rejected() {
+        let tool = SshExecTool::new(SshConfig::default());
+        let result = tool
+            .execute(json!({"host": "prod", "command": "uptime"}))
+            .await;
+        assert!(result.unwrap_err().to_string().contains("Unknown host"));
+    }
+}
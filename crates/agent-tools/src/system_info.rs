@@ -0,0 +1,625 @@
+//! System information tool.
+//!
+//! Answers "why is my machine slow" and "what is using port 3001" directly:
+//! OS, CPU load, memory, disk usage, the busiest processes and listening
+//! ports. Linux is read from `/proc` where possible; elsewhere (and for
+//! disks, processes and ports) the standard utilities are used — `df`, `ps`,
+//! `ss` or `lsof`, `sysctl`/`vm_stat` on macOS.
+
+use agent_core::error::AgentError;
+use agent_core::tool_registry::Tool;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fmt::Write as _;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+const SECTIONS: &[&str] = &["os", "cpu", "memory", "disk", "processes", "ports"];
+const DEFAULT_TOP: usize = 10;
+const MAX_TOP: usize = 50;
+/// Pseudo filesystems left out of the disk section.
+const SKIP_FILESYSTEMS: &[&str] = &["tmpfs", "devtmpfs", "overlay", "shm", "udev", "devfs", "map"];
+
+/// Run a utility and return its stdout, or `None` if it is missing or fails.
+async fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = tokio::time::timeout(
+        Duration::from_secs(10),
+        Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// ── Parsers ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Memory {
+    total: u64,
+    available: u64,
+    swap_total: u64,
+    swap_free: u64,
+}
+
+/// `/proc/meminfo` (values in KiB).
+fn parse_meminfo(text: &str) -> Option<Memory> {
+    let field = |name: &str| {
+        text.lines()
+            .find_map(|l| l.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|v| v.split_whitespace().next()?.parse::<u64>().ok())
+            .map(|kib| kib * 1024)
+    };
+    Some(Memory {
+        total: field("MemTotal")?,
+        available: field("MemAvailable").or_else(|| field("MemFree"))?,
+        swap_total: field("SwapTotal").unwrap_or(0),
+        swap_free: field("SwapFree").unwrap_or(0),
+    })
+}
+
+/// Free plus inactive pages from macOS `vm_stat`, in bytes.
+fn parse_vm_stat(text: &str) -> Option<u64> {
+    let page_size: u64 = text
+        .split("page size of ")
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    let pages = |name: &str| {
+        text.lines()
+            .find_map(|l| l.strip_prefix(name))
+            .and_then(|v| v.trim().trim_end_matches('.').parse::<u64>().ok())
+            .unwrap_or(0)
+    };
+    Some((pages("Pages free:") + pages("Pages inactive:")) * page_size)
+}
+
+/// Busy and total jiffies from the aggregate `cpu` line of `/proc/stat`.
+fn parse_cpu_times(stat: &str) -> Option<(u64, u64)> {
+    let values: Vec<u64> = stat
+        .lines()
+        .find(|l| l.starts_with("cpu "))?
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    let total: u64 = values.iter().sum();
+    // idle + iowait
+    let idle = values.get(3)? + values.get(4).unwrap_or(&0);
+    Some((total - idle, total))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Disk {
+    filesystem: String,
+    mount: String,
+    size: u64,
+    used: u64,
+    available: u64,
+}
+
+/// `df -kP` output.
+fn parse_df(text: &str) -> Vec<Disk> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let f: Vec<&str> = line.split_whitespace().collect();
+            if f.len() < 6 || SKIP_FILESYSTEMS.contains(&f[0]) {
+                return None;
+            }
+            let kib = |s: &str| s.parse::<u64>().ok().map(|v| v * 1024);
+            Some(Disk {
+                filesystem: f[0].to_string(),
+                mount: f[5..].join(" "),
+                size: kib(f[1])?,
+                used: kib(f[2])?,
+                available: kib(f[3])?,
+            })
+        })
+        .filter(|d| d.size > 0)
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Process {
+    pid: u32,
+    cpu: f32,
+    mem: f32,
+    rss: u64,
+    command: String,
+}
+
+/// `ps -eo pid=,pcpu=,pmem=,rss=,comm=` output (rss in KiB).
+fn parse_ps(text: &str) -> Vec<Process> {
+    text.lines()
+        .filter_map(|line| {
+            let mut f = line.split_whitespace();
+            Some(Process {
+                pid: f.next()?.parse().ok()?,
+                cpu: f.next()?.parse().ok()?,
+                mem: f.next()?.parse().ok()?,
+                rss: f.next()?.parse::<u64>().ok()? * 1024,
+                command: f.collect::<Vec<_>>().join(" "),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Listener {
+    protocol: String,
+    address: String,
+    port: u16,
+    /// `name (pid N)` when known.
+    process: Option<String>,
+}
+
+/// Split `addr:port`, `[::]:port` or `*:port`.
+fn split_host_port(s: &str) -> Option<(String, u16)> {
+    let (host, port) = s.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    // ss appends the interface: 127.0.0.53%lo
+    let host = host.split('%').next().unwrap_or(host);
+    Some((host.to_string(), port.parse().ok()?))
+}
+
+/// `ss -ltnupH` output.
+fn parse_ss(text: &str) -> Vec<Listener> {
+    text.lines()
+        .filter_map(|line| {
+            let f: Vec<&str> = line.split_whitespace().collect();
+            let (address, port) = split_host_port(f.get(4)?)?;
+            let process = f.get(6).and_then(|users| {
+                // users:(("node",pid=1234,fd=20))
+                let name = users.split('"').nth(1)?;
+                let pid = users.split("pid=").nth(1)?.split(',').next()?;
+                Some(format!("{} (pid {})", name, pid))
+            });
+            Some(Listener {
+                protocol: f[0].to_string(),
+                address,
+                port,
+                process,
+            })
+        })
+        .collect()
+}
+
+/// `lsof -nP -iTCP -sTCP:LISTEN` output.
+fn parse_lsof(text: &str) -> Vec<Listener> {
+    let mut listeners: Vec<Listener> = text
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let f: Vec<&str> = line.split_whitespace().collect();
+            if f.last() != Some(&"(LISTEN)") || f.len() < 4 {
+                return None;
+            }
+            let (address, port) = split_host_port(f[f.len() - 2])?;
+            Some(Listener {
+                protocol: "tcp".into(),
+                address,
+                port,
+                process: Some(format!("{} (pid {})", f[0], f[1])),
+            })
+        })
+        .collect();
+    // lsof lists one row per file descriptor.
+    listeners.dedup();
+    listeners
+}
+
+/// `/proc/net/tcp` or `/proc/net/tcp6` (listening sockets, no process info).
+fn parse_proc_net_tcp(text: &str, protocol: &str) -> Vec<Listener> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let f: Vec<&str> = line.split_whitespace().collect();
+            if f.get(3) != Some(&"0A") {
+                return None;
+            }
+            let (addr, port) = f.get(1)?.split_once(':')?;
+            let words: Option<Vec<[u8; 4]>> = addr
+                .as_bytes()
+                .chunks(8)
+                .map(|c| {
+                    let word = u32::from_str_radix(std::str::from_utf8(c).ok()?, 16).ok()?;
+                    Some(word.to_le_bytes())
+                })
+                .collect();
+            let words = words?;
+            let address = match words.len() {
+                1 => Ipv4Addr::from(words[0]).to_string(),
+                4 => Ipv6Addr::from(<[u8; 16]>::try_from(words.concat()).ok()?).to_string(),
+                _ => return None,
+            };
+            Some(Listener {
+                protocol: protocol.into(),
+                address,
+                port: u16::from_str_radix(port, 16).ok()?,
+                process: None,
+            })
+        })
+        .collect()
+}
+
+// ── Sections ───────────────────────────────────────────────────────────
+
+async fn os_section() -> String {
+    let mut out = String::from("## OS\n");
+    let pretty = std::fs::read_to_string("/etc/os-release").ok().and_then(|t| {
+        t.lines()
+            .find_map(|l| l.strip_prefix("PRETTY_NAME="))
+            .map(|v| v.trim_matches('"').to_string())
+    });
+    let _ = writeln!(
+        out,
+        "System: {}",
+        pretty.unwrap_or_else(|| std::env::consts::OS.to_string())
+    );
+    if let Some(uname) = run("uname", &["-srm"]).await {
+        let _ = writeln!(out, "Kernel: {}", uname.trim());
+    }
+    if let Some(host) = run("hostname", &[]).await {
+        let _ = writeln!(out, "Hostname: {}", host.trim());
+    }
+    if let Some(secs) = std::fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|t| t.split_whitespace().next()?.parse::<f64>().ok())
+    {
+        let secs = secs as u64;
+        let _ = writeln!(
+            out,
+            "Uptime: {}d {}h {}m",
+            secs / 86400,
+            secs % 86400 / 3600,
+            secs % 3600 / 60
+        );
+    }
+    out
+}
+
+async fn cpu_section() -> String {
+    let mut out = String::from("## CPU\n");
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let _ = writeln!(out, "Cores: {}", cores);
+    let load = match std::fs::read_to_string("/proc/loadavg") {
+        Ok(text) => Some(text.split_whitespace().take(3).collect::<Vec<_>>().join(" ")),
+        Err(_) => run("sysctl", &["-n", "vm.loadavg"])
+            .await
+            .map(|t| t.trim_matches(|c: char| c == '{' || c == '}' || c.is_whitespace()).to_string()),
+    };
+    if let Some(load) = load {
+        let _ = writeln!(out, "Load average (1/5/15 min): {}", load);
+    }
+    // Usage over a short sample, Linux only.
+    if let Some(before) = std::fs::read_to_string("/proc/stat").ok().and_then(|s| parse_cpu_times(&s)) {
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        if let Some(after) = std::fs::read_to_string("/proc/stat").ok().and_then(|s| parse_cpu_times(&s)) {
+            let total = after.1.saturating_sub(before.1);
+            if total > 0 {
+                let busy = after.0.saturating_sub(before.0);
+                let _ = writeln!(out, "Usage: {:.0}%", busy as f64 * 100.0 / total as f64);
+            }
+        }
+    }
+    out
+}
+
+async fn memory_section() -> String {
+    let mut out = String::from("## Memory\n");
+    let memory = match std::fs::read_to_string("/proc/meminfo") {
+        Ok(text) => parse_meminfo(&text),
+        Err(_) => {
+            let total = run("sysctl", &["-n", "hw.memsize"])
+                .await
+                .and_then(|t| t.trim().parse::<u64>().ok());
+            let available = run("vm_stat", &[]).await.and_then(|t| parse_vm_stat(&t));
+            total.zip(available).map(|(total, available)| Memory {
+                total,
+                available,
+                swap_total: 0,
+                swap_free: 0,
+            })
+        }
+    };
+    match memory {
+        Some(m) => {
+            let used = m.total.saturating_sub(m.available);
+            let _ = writeln!(
+                out,
+                "RAM: {} used of {} ({:.0}%), {} available",
+                human_bytes(used),
+                human_bytes(m.total),
+                used as f64 * 100.0 / m.total.max(1) as f64,
+                human_bytes(m.available)
+            );
+            if m.swap_total > 0 {
+                let _ = writeln!(
+                    out,
+                    "Swap: {} used of {}",
+                    human_bytes(m.swap_total.saturating_sub(m.swap_free)),
+                    human_bytes(m.swap_total)
+                );
+            }
+        }
+        None => out.push_str("Unavailable on this platform\n"),
+    }
+    out
+}
+
+async fn disk_section() -> String {
+    let mut out = String::from("## Disk\n");
+    let disks = run("df", &["-kP"]).await.map(|t| parse_df(&t)).unwrap_or_default();
+    if disks.is_empty() {
+        out.push_str("Unavailable (df not found)\n");
+    }
+    for d in disks {
+        let _ = writeln!(
+            out,
+            "{}: {} used of {} ({:.0}%), {} free [{}]",
+            d.mount,
+            human_bytes(d.used),
+            human_bytes(d.size),
+            d.used as f64 * 100.0 / (d.used + d.available).max(1) as f64,
+            human_bytes(d.available),
+            d.filesystem
+        );
+    }
+    out
+}
+
+async fn processes_section(sort_by_memory: bool, top: usize) -> String {
+    let mut out = format!(
+        "## Top processes by {}\n",
+        if sort_by_memory { "memory" } else { "CPU" }
+    );
+    let mut processes = run("ps", &["-eo", "pid=,pcpu=,pmem=,rss=,comm="])
+        .await
+        .map(|t| parse_ps(&t))
+        .unwrap_or_default();
+    if processes.is_empty() {
+        out.push_str("Unavailable (ps not found)\n");
+        return out;
+    }
+    if sort_by_memory {
+        processes.sort_by_key(|p| std::cmp::Reverse(p.rss));
+    } else {
+        processes.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+    }
+    out.push_str("PID      CPU%   MEM%  RSS        COMMAND\n");
+    for p in processes.iter().take(top) {
+        let _ = writeln!(
+            out,
+            "{:<8} {:>5.1} {:>5.1}  {:<10} {}",
+            p.pid,
+            p.cpu,
+            p.mem,
+            human_bytes(p.rss),
+            p.command
+        );
+    }
+    out
+}
+
+async fn ports_section(port: Option<u16>) -> String {
+    let mut out = String::from("## Listening ports\n");
+    let mut listeners = match run("ss", &["-ltnupH"]).await {
+        Some(text) => parse_ss(&text),
+        None => match run("lsof", &["-nP", "-iTCP", "-sTCP:LISTEN"]).await {
+            Some(text) => parse_lsof(&text),
+            None => [("/proc/net/tcp", "tcp"), ("/proc/net/tcp6", "tcp6")]
+                .iter()
+                .filter_map(|(path, proto)| {
+                    std::fs::read_to_string(path)
+                        .ok()
+                        .map(|t| parse_proc_net_tcp(&t, proto))
+                })
+                .flatten()
+                .collect(),
+        },
+    };
+    if let Some(port) = port {
+        listeners.retain(|l| l.port == port);
+        if listeners.is_empty() {
+            let _ = writeln!(out, "Nothing is listening on port {}", port);
+            return out;
+        }
+    }
+    listeners.sort_by_key(|l| l.port);
+    if listeners.is_empty() {
+        out.push_str("None found (ss, lsof and /proc/net unavailable or nothing listening)\n");
+    }
+    for l in listeners {
+        let _ = writeln!(
+            out,
+            "{} {}:{}{}",
+            l.protocol,
+            l.address,
+            l.port,
+            l.process.map(|p| format!("  {}", p)).unwrap_or_default()
+        );
+    }
+    out
+}
+
+// ── Tool ───────────────────────────────────────────────────────────────
+
+/// Report OS, CPU, memory, disk, process and port information.
+pub struct SystemInfoTool;
+
+#[async_trait]
+impl Tool for SystemInfoTool {
+    fn name(&self) -> &str {
+        "system_info"
+    }
+
+    fn description(&self) -> &str {
+        "Report information about this machine: OS, CPU load, memory, disk usage, top \
+         processes and listening ports. Use it for questions like \"why is my machine slow\" \
+         or \"what is using port 3001\" (pass `port`)."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "sections": {
+                    "type": "array",
+                    "items": { "type": "string", "enum": SECTIONS },
+                    "description": "Sections to include (default: all except ports, or only ports when `port` is given)"
+                },
+                "port": {
+                    "type": "integer",
+                    "description": "Only report what is listening on this port"
+                },
+                "sort_by": {
+                    "type": "string",
+                    "enum": ["cpu", "memory"],
+                    "description": "Process ordering (default cpu)"
+                },
+                "top": {
+                    "type": "integer",
+                    "description": "Number of processes to list (default 10, max 50)"
+                }
+            }
+        })
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        #[derive(Deserialize)]
+        struct Args {
+            sections: Option<Vec<String>>,
+            port: Option<u16>,
+            sort_by: Option<String>,
+            top: Option<usize>,
+        }
+        let args: Args = serde_json::from_value(args).map_err(|e| AgentError::ToolExecution {
+            tool_name: "system_info".into(),
+            message: format!("Invalid arguments: {}", e),
+        })?;
+
+        let sections = match (args.sections, args.port) {
+            (Some(sections), _) => sections,
+            (None, Some(_)) => vec!["ports".into()],
+            (None, None) => SECTIONS[..5].iter().map(|s| s.to_string()).collect(),
+        };
+        if let Some(unknown) = sections.iter().find(|s| !SECTIONS.contains(&s.as_str())) {
+            return Err(AgentError::ToolExecution {
+                tool_name: "system_info".into(),
+                message: format!("Unknown section '{}'; use {}", unknown, SECTIONS.join(", ")),
+            });
+        }
+        let top = args.top.unwrap_or(DEFAULT_TOP).clamp(1, MAX_TOP);
+        let by_memory = args.sort_by.as_deref() == Some("memory");
+
+        let mut parts = Vec::new();
+        for section in SECTIONS.iter().filter(|s| sections.iter().any(|x| x == *s)) {
+            parts.push(match *section {
+                "os" => os_section().await,
+                "cpu" => cpu_section().await,
+                "memory" => memory_section().await,
+                "disk" => disk_section().await,
+                "processes" => processes_section(by_memory, top).await,
+                _ => ports_section(args.port).await,
+            });
+        }
+        Ok(parts.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_meminfo_and_cpu() {
+        let meminfo = "MemTotal:       16000000 kB\nMemFree:         1000000 kB\nMemAvailable:    8000000 kB\nSwapTotal:       2000000 kB\nSwapFree:        2000000 kB\n";
+        let m = parse_meminfo(meminfo).unwrap();
+        assert_eq!(m.total, 16_000_000 * 1024);
+        assert_eq!(m.available, 8_000_000 * 1024);
+
+        let stat = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 50 0 25 400 25 0 0 0 0 0\n";
+        assert_eq!(parse_cpu_times(stat), Some((150, 1000)));
+
+        let vm_stat = "Mach Virtual Memory Statistics: (page size of 16384 bytes)\nPages free:                               1000.\nPages inactive:                            500.\n";
+        assert_eq!(parse_vm_stat(vm_stat), Some(1500 * 16384));
+    }
+
+    #[test]
+    fn test_parse_df_and_ps() {
+        let df = "Filesystem     1024-blocks     Used Available Capacity Mounted on\n\
+                  /dev/nvme0n1p2   100000000 40000000  60000000      40% /\n\
+                  tmpfs              8000000        0   8000000       0% /dev/shm\n\
+                  /dev/sdb1          2000000  1000000   1000000      50% /mnt/My Disk\n";
+        let disks = parse_df(df);
+        assert_eq!(disks.len(), 2);
+        assert_eq!(disks[1].mount, "/mnt/My Disk");
+        assert_eq!(disks[0].used, 40_000_000 * 1024);
+
+        let ps = "  1234  95.0  3.2 524288 node\n     1   0.0  0.1  12000 systemd\n";
+        let processes = parse_ps(ps);
+        assert_eq!(processes[0].pid, 1234);
+        assert_eq!(processes[0].rss, 524288 * 1024);
+        assert_eq!(processes[1].command, "systemd");
+    }
+
+    #[test]
+    fn test_parse_listeners() {
+        let ss = "tcp   LISTEN 0      511          0.0.0.0:3001      0.0.0.0:*    users:((\"node\",pid=4242,fd=21))\n\
+                  tcp   LISTEN 0      4096   127.0.0.53%lo:53        0.0.0.0:*\n\
+                  tcp   LISTEN 0      128             [::]:22           [::]:*\n";
+        let listeners = parse_ss(ss);
+        assert_eq!(listeners.len(), 3);
+        assert_eq!(listeners[0].port, 3001);
+        assert_eq!(listeners[0].process.as_deref(), Some("node (pid 4242)"));
+        assert_eq!(listeners[1].address, "127.0.0.53");
+        assert_eq!(listeners[2].address, "::");
+
+        let lsof = "COMMAND   PID USER   FD   TYPE DEVICE SIZE/OFF NODE NAME\n\
+                    node    4242 me   21u  IPv4 0x1      0t0  TCP *:3001 (LISTEN)\n\
+                    node    4242 me   22u  IPv4 0x2      0t0  TCP *:3001 (LISTEN)\n";
+        let listeners = parse_lsof(lsof);
+        assert_eq!(listeners.len(), 1);
+        assert_eq!((listeners[0].address.as_str(), listeners[0].port), ("*", 3001));
+
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue\n\
+                     0: 0100007F:0BB9 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000\n\
+                     1: 0100007F:1F90 0100007F:C350 01 00000000:00000000 00:00000000 00000000  1000\n";
+        let listeners = parse_proc_net_tcp(tcp, "tcp");
+        assert_eq!(listeners.len(), 1);
+        assert_eq!((listeners[0].address.as_str(), listeners[0].port), ("127.0.0.1", 3001));
+    }
+}
@@ -0,0 +1,184 @@
+//! On-disk cache of `web_fetch` responses (`[web_fetch] cache`).
+//!
+//! Each URL is kept in one JSON file named by its SHA-256. Within the TTL
+//! a page is returned without a request; after that it is revalidated with
+//! `If-None-Match` / `If-Modified-Since`, and a `304 Not Modified` keeps it
+//! for another TTL. Only complete `200` responses without
+//! `Cache-Control: no-store` are stored.
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{self, HeaderMap, HeaderName};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// A stored response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedPage {
+    pub url: String,
+    pub status: u16,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// When the page was fetched or last revalidated.
+    pub fetched_at: DateTime<Utc>,
+    pub body: String,
+}
+
+impl CachedPage {
+    /// A page for `url` with the validators of the response `headers`.
+    pub fn new(url: &str, status: u16, headers: &HeaderMap, body: String) -> Self {
+        Self {
+            url: url.to_string(),
+            status,
+            etag: header_value(headers, header::ETAG),
+            last_modified: header_value(headers, header::LAST_MODIFIED),
+            fetched_at: Utc::now(),
+            body,
+        }
+    }
+
+    /// Headers that turn a request into a revalidation of this page.
+    pub fn validators(&self) -> Vec<(HeaderName, String)> {
+        let mut headers = Vec::new();
+        if let Some(etag) = &self.etag {
+            headers.push((header::IF_NONE_MATCH, etag.clone()));
+        }
+        if let Some(date) = &self.last_modified {
+            headers.push((header::IF_MODIFIED_SINCE, date.clone()));
+        }
+        headers
+    }
+
+    /// Mark the page as confirmed by a `304` with `headers`, which may
+    /// carry new validators.
+    pub fn revalidated(&mut self, headers: &HeaderMap) {
+        self.fetched_at = Utc::now();
+        if let Some(etag) = header_value(headers, header::ETAG) {
+            self.etag = Some(etag);
+        }
+        if let Some(date) = header_value(headers, header::LAST_MODIFIED) {
+            self.last_modified = Some(date);
+        }
+    }
+}
+
+fn header_value(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Whether a response with `headers` may be stored.
+pub fn storable(headers: &HeaderMap) -> bool {
+    !headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-store"))
+}
+
+/// Cached pages in one directory.
+#[derive(Debug, Clone)]
+pub struct WebCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl WebCache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        let hash: String = Sha256::digest(url.as_bytes())
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        self.dir.join(format!("{}.json", &hash[..32]))
+    }
+
+    /// Whether `page` can be returned without asking the site.
+    pub fn is_fresh(&self, page: &CachedPage) -> bool {
+        Utc::now()
+            .signed_duration_since(page.fetched_at)
+            .to_std()
+            .is_ok_and(|age| age < self.ttl)
+    }
+
+    /// The stored page for `url`; a missing or unreadable file is none.
+    pub async fn get(&self, url: &str) -> Option<CachedPage> {
+        let contents = tokio::fs::read(self.path(url)).await.ok()?;
+        serde_json::from_slice::<CachedPage>(&contents)
+            .ok()
+            .filter(|page| page.url == url)
+    }
+
+    /// Store `page`. Failures are logged: a fetch never fails because
+    /// its result could not be cached.
+    pub async fn put(&self, page: &CachedPage) {
+        let path = self.path(&page.url);
+        let tmp = path.with_extension("json.tmp");
+        let result = async {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            tokio::fs::write(&tmp, serde_json::to_vec(page)?).await?;
+            tokio::fs::rename(&tmp, &path).await
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::debug!("Could not cache {}: {}", page.url, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[tokio::test]
+    async fn test_put_get_and_freshness() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cache = WebCache::new(dir.path().to_path_buf(), Duration::from_secs(60));
+        let url = "https://docs.rs/serde";
+        assert!(cache.get(url).await.is_none());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ETAG, HeaderValue::from_static("\"v1\""));
+        let mut page = CachedPage::new(url, 200, &headers, "<html>serde</html>".into());
+        cache.put(&page).await;
+        let stored = cache.get(url).await.unwrap();
+        assert_eq!(stored, page);
+        assert!(cache.is_fresh(&stored));
+        assert_eq!(
+            stored.validators(),
+            [(header::IF_NONE_MATCH, "\"v1\"".to_string())]
+        );
+        assert!(cache.get("https://docs.rs/tokio").await.is_none());
+
+        page.fetched_at = Utc::now() - chrono::Duration::minutes(5);
+        assert!(!cache.is_fresh(&page));
+        headers.insert(header::ETAG, HeaderValue::from_static("\"v2\""));
+        page.revalidated(&headers);
+        assert!(cache.is_fresh(&page));
+        assert_eq!(page.etag.as_deref(), Some("\"v2\""));
+    }
+
+    #[test]
+    fn test_no_store_is_not_storable() {
+        let mut headers = HeaderMap::new();
+        assert!(storable(&headers));
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("max-age=60, public"),
+        );
+        assert!(storable(&headers));
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("private, No-Store"),
+        );
+        assert!(!storable(&headers));
+    }
+}
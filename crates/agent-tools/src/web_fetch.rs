@@ -1,9 +1,14 @@
+use crate::web_cache::{self, CachedPage, WebCache};
+use crate::web_policy::{self, DomainLimits, Robots, RobotsCache, ROBOTS_AGENT};
+use agent_core::config::NetworkConfig;
 use agent_core::error::AgentError;
+use agent_core::network::{self, DohResolver};
 use agent_core::tool_registry::Tool;
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 use url::Url;
 
 /// Fetch a web page and return its text content.
@@ -11,17 +16,116 @@ use url::Url;
 /// Uses per-request DNS pinning to prevent TOCTOU / DNS-rebinding SSRF:
 /// we resolve DNS once, validate every returned IP, then force `reqwest`
 /// to connect to the already-validated addresses.
-pub struct WebFetchTool;
-
-impl Default for WebFetchTool {
-    fn default() -> Self {
-        Self::new()
-    }
+///
+/// With `[network] doh_url` names are resolved over DNS-over-HTTPS, and
+/// redirect targets are checked when they are resolved. Through a
+/// `[network] proxy` the addresses are still validated first, but the
+/// proxy connects by name.
+///
+/// With a [`WebCache`] repeated fetches of a page are served from disk
+/// and revalidated once the TTL has passed. Requests keep to per-domain
+/// rate and concurrency limits and, when enabled, to robots.txt (see
+/// [`web_policy`]).
+#[derive(Default)]
+pub struct WebFetchTool {
+    network: NetworkConfig,
+    cache: Option<WebCache>,
+    limits: DomainLimits,
+    robots: Option<RobotsCache>,
 }
 
 impl WebFetchTool {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Route requests through the `[network]` proxy and DoH resolver.
+    pub fn with_network(mut self, network: NetworkConfig) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Keep fetched pages in `cache`.
+    pub fn with_cache(mut self, cache: WebCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Limit requests per domain to `requests_per_minute` and
+    /// `max_concurrent` at a time; zero means no limit.
+    pub fn with_limits(mut self, requests_per_minute: u32, max_concurrent: usize) -> Self {
+        self.limits = DomainLimits::new(requests_per_minute, max_concurrent);
+        self
+    }
+
+    /// Refuse pages the site's robots.txt disallows.
+    pub fn with_robots_txt(mut self, respect: bool) -> Self {
+        self.robots = respect.then(RobotsCache::default);
+        self
+    }
+
+    /// The robots.txt of `url`'s site, read with `client`. A missing file
+    /// allows everything; one that cannot be read allows everything too,
+    /// but is asked for again next time.
+    async fn robots(
+        &self,
+        cache: &RobotsCache,
+        client: &reqwest::Client,
+        url: &Url,
+    ) -> Arc<Robots> {
+        let origin = url.origin().ascii_serialization();
+        if let Some(robots) = cache.get(&origin) {
+            return robots;
+        }
+        let robots = match client.get(format!("{}/robots.txt", origin)).send().await {
+            Ok(response) if response.status().is_success() => {
+                match read_body(response, MAX_ROBOTS_BYTES).await {
+                    Ok((body, _)) => Robots::parse(&String::from_utf8_lossy(&body)),
+                    Err(e) => {
+                        tracing::debug!("Could not read {}/robots.txt: {}", origin, e);
+                        return Arc::default();
+                    }
+                }
+            }
+            Ok(response) if response.status().is_client_error() => Robots::default(),
+            Ok(response) => {
+                tracing::debug!("{}/robots.txt answered {}", origin, response.status());
+                return Arc::default();
+            }
+            Err(e) => {
+                tracing::debug!("Could not fetch {}/robots.txt: {}", origin, e);
+                return Arc::default();
+            }
+        };
+        cache.insert(&origin, robots)
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        AgentError::ToolExecution {
+            tool_name: "web_fetch".into(),
+            message: msg.into(),
+        }
+    }
+
+    /// Client settings shared by every request: timeout, SSRF-safe
+    /// redirects, the proxy and, with DoH, a resolver that refuses
+    /// internal addresses.
+    fn client_builder(
+        &self,
+        doh: Option<&DohResolver>,
+    ) -> Result<reqwest::ClientBuilder, AgentError> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .user_agent("agent-shell/0.1")
+            .redirect(ssrf_safe_redirect_policy(doh.is_some()));
+        let proxy = network::proxy(&self.network).map_err(|e| Self::err(e.to_string()))?;
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(doh) = doh {
+            builder = builder.dns_resolver(Arc::new(PublicOnly(doh.clone())));
+        }
+        Ok(builder)
     }
 
     /// Build a per-request client whose DNS is pinned to the pre-validated
@@ -29,11 +133,14 @@ impl WebFetchTool {
     ///
     /// Uses a custom redirect policy that validates each redirect target
     /// through the same SSRF checks as the initial request.
-    fn build_pinned_client(domain: &str, port: u16, addrs: &[SocketAddr]) -> reqwest::Client {
-        let mut builder = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .user_agent("agent-shell/0.1")
-            .redirect(ssrf_safe_redirect_policy());
+    fn build_pinned_client(
+        &self,
+        doh: Option<&DohResolver>,
+        domain: &str,
+        port: u16,
+        addrs: &[SocketAddr],
+    ) -> Result<reqwest::Client, AgentError> {
+        let mut builder = self.client_builder(doh)?;
 
         // Pin every validated address so reqwest never re-resolves.
         for addr in addrs {
@@ -45,25 +152,76 @@ impl WebFetchTool {
             builder = builder.resolve(&host_with_port, *addr);
         }
 
-        builder.build().unwrap_or_default()
+        builder
+            .build()
+            .map_err(|e| Self::err(format!("Failed to build HTTP client: {}", e)))
+    }
+}
+
+/// A DoH resolver that fails for names with an internal address, so
+/// redirect targets get the same check as the first URL.
+struct PublicOnly(DohResolver);
+
+impl reqwest::dns::Resolve for PublicOnly {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let resolver = self.0.clone();
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let ips = resolver.lookup(&host).await?;
+            let addrs = ips.into_iter().map(|ip| SocketAddr::new(ip, 0)).collect();
+            let addrs = check_addrs(&host, addrs)?;
+            let addrs: reqwest::dns::Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
     }
 }
 
 /// Maximum response body size to buffer (2 MB). Prevents OOM from huge responses.
 const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
 
+/// Maximum robots.txt size read; RFC 9309 asks crawlers to read at least
+/// 500 KiB.
+const MAX_ROBOTS_BYTES: usize = 512 * 1024;
+
+/// Stream a response body with a hard byte cap to prevent OOM. The flag
+/// says whether the cap was hit.
+async fn read_body(response: reqwest::Response, cap: usize) -> Result<(Vec<u8>, bool), AgentError> {
+    use futures::StreamExt;
+    let mut stream = response.bytes_stream();
+    let mut buf = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| AgentError::ToolExecution {
+            tool_name: "web_fetch".into(),
+            message: format!("Failed to read response body: {}", e),
+        })?;
+        buf.extend_from_slice(&chunk);
+        if buf.len() >= cap {
+            buf.truncate(cap);
+            return Ok((buf, true));
+        }
+    }
+    Ok((buf, false))
+}
+
 /// Custom redirect policy that validates each redirect target through SSRF checks.
 ///
 /// Without this, a public URL could 302 to `http://127.0.0.1/...` and reqwest
-/// would follow it, bypassing the initial SSRF validation.
-fn ssrf_safe_redirect_policy() -> reqwest::redirect::Policy {
-    reqwest::redirect::Policy::custom(|attempt| {
+/// would follow it, bypassing the initial SSRF validation. With `doh` the
+/// policy cannot wait for a lookup, so it checks the URL itself and leaves
+/// the addresses to the [`PublicOnly`] resolver.
+fn ssrf_safe_redirect_policy(doh: bool) -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(move |attempt| {
         if attempt.previous().len() >= 5 {
             attempt.error("too many redirects")
         } else {
             let url = attempt.url();
             // Validate the redirect target through the same SSRF checks.
-            match validate_url_not_internal(url.as_str()) {
+            let checked = if doh {
+                check_url(url.as_str()).map(|_| ())
+            } else {
+                validate_url_not_internal(url.as_str()).map(|_| ())
+            };
+            match checked {
                 Ok(_) => attempt.follow(),
                 Err(e) => attempt.error(format!("redirect blocked by SSRF check: {}", e)),
             }
@@ -107,11 +265,10 @@ pub(crate) struct ValidatedUrl {
     pub resolved_addrs: Vec<SocketAddr>,
 }
 
-/// Validate that a URL is safe to fetch (not an internal/SSRF target).
-///
-/// Returns a `ValidatedUrl` with pre-resolved addresses so the caller
-/// can pin DNS in `reqwest`, eliminating the TOCTOU / DNS-rebinding window.
-pub(crate) fn validate_url_not_internal(raw_url: &str) -> Result<ValidatedUrl, AgentError> {
+/// The checks that need no DNS lookup: the scheme, blocked host names and
+/// literal IP addresses. A domain comes back without addresses, for the
+/// caller to resolve and pass through [`check_addrs`].
+fn check_url(raw_url: &str) -> Result<ValidatedUrl, AgentError> {
     let parsed = Url::parse(raw_url).map_err(|e| AgentError::ToolExecution {
         tool_name: "web_fetch".into(),
         message: format!("Invalid URL: {}", e),
@@ -181,57 +338,73 @@ pub(crate) fn validate_url_not_internal(raw_url: &str) -> Result<ValidatedUrl, A
         });
     }
 
+    let domain = match parsed.host() {
+        Some(url::Host::Domain(domain)) => Some(domain.to_string()),
+        _ => None,
+    };
+    Ok(ValidatedUrl {
+        url: parsed,
+        domain,
+        port,
+        resolved_addrs: Vec::new(),
+    })
+}
+
+/// Validate that a URL is safe to fetch (not an internal/SSRF target).
+///
+/// Returns a `ValidatedUrl` with pre-resolved addresses so the caller
+/// can pin DNS in `reqwest`, eliminating the TOCTOU / DNS-rebinding window.
+pub(crate) fn validate_url_not_internal(raw_url: &str) -> Result<ValidatedUrl, AgentError> {
+    let mut validated = check_url(raw_url)?;
     // For domain names, perform DNS resolution and check ALL resolved IPs.
     // NOTE: This uses blocking DNS resolution (std::net::ToSocketAddrs).
     // In a high-concurrency scenario, consider wrapping with
     // tokio::task::spawn_blocking or using tokio::net::lookup_host in an
     // async version of this function.
-    let mut safe_addrs = Vec::new();
-    if let Some(url::Host::Domain(domain)) = parsed.host() {
-        let domain_owned = domain.to_string();
-        let addr_str = format!("{}:{}", domain_owned, port);
+    if let Some(domain) = &validated.domain {
+        let addr_str = format!("{}:{}", domain, validated.port);
         let addrs: Vec<SocketAddr> = std::net::ToSocketAddrs::to_socket_addrs(&addr_str)
             .map_err(|e| AgentError::ToolExecution {
                 tool_name: "web_fetch".into(),
-                message: format!("DNS resolution failed for '{}': {}", domain_owned, e),
+                message: format!("DNS resolution failed for '{}': {}", domain, e),
             })?
             .collect();
+        validated.resolved_addrs = check_addrs(domain, addrs).map_err(WebFetchTool::err)?;
+    }
+    Ok(validated)
+}
 
-        if addrs.is_empty() {
-            return Err(AgentError::ToolExecution {
-                tool_name: "web_fetch".into(),
-                message: format!("DNS returned no addresses for '{}'", domain_owned),
-            });
-        }
-
-        for addr in &addrs {
-            if is_private_ip(&addr.ip()) {
-                return Err(AgentError::ToolExecution {
-                    tool_name: "web_fetch".into(),
-                    message: format!(
-                        "Host '{}' resolves to private/internal address {}",
-                        domain_owned,
-                        addr.ip()
-                    ),
-                });
-            }
-        }
-        safe_addrs = addrs;
-
-        return Ok(ValidatedUrl {
-            url: parsed,
-            domain: Some(domain_owned),
-            port,
-            resolved_addrs: safe_addrs,
-        });
+/// [`validate_url_not_internal`] resolving names with DNS-over-HTTPS.
+async fn validate_url_with_doh(
+    raw_url: &str,
+    doh: &DohResolver,
+) -> Result<ValidatedUrl, AgentError> {
+    let mut validated = check_url(raw_url)?;
+    if let Some(domain) = &validated.domain {
+        let ips = doh.lookup(domain).await.map_err(WebFetchTool::err)?;
+        let addrs = ips
+            .into_iter()
+            .map(|ip| SocketAddr::new(ip, validated.port))
+            .collect();
+        validated.resolved_addrs = check_addrs(domain, addrs).map_err(WebFetchTool::err)?;
     }
+    Ok(validated)
+}
 
-    Ok(ValidatedUrl {
-        url: parsed,
-        domain: None,
-        port,
-        resolved_addrs: safe_addrs,
-    })
+/// The addresses `domain` resolved to, unless there are none or one of
+/// them is private/internal.
+fn check_addrs(domain: &str, addrs: Vec<SocketAddr>) -> Result<Vec<SocketAddr>, String> {
+    if addrs.is_empty() {
+        return Err(format!("DNS returned no addresses for '{}'", domain));
+    }
+    if let Some(addr) = addrs.iter().find(|a| is_private_ip(&a.ip())) {
+        return Err(format!(
+            "Host '{}' resolves to private/internal address {}",
+            domain,
+            addr.ip()
+        ));
+    }
+    Ok(addrs)
 }
 
 #[async_trait]
@@ -256,18 +429,28 @@ impl Tool for WebFetchTool {
                 "max_length": {
                     "type": "integer",
                     "description": "Maximum characters to return. Default: 10000"
+                },
+                "refresh": {
+                    "type": "boolean",
+                    "description": "Fetch the page again even if a cached copy is fresh. Default: false"
                 }
             },
             "required": ["url"]
         })
     }
 
+    fn read_only(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, args: Value) -> Result<String, AgentError> {
         #[derive(Deserialize)]
         struct Args {
             url: String,
             #[serde(default = "default_max")]
             max_length: usize,
+            #[serde(default)]
+            refresh: bool,
         }
         fn default_max() -> usize {
             10000
@@ -278,23 +461,79 @@ impl Tool for WebFetchTool {
             message: format!("Invalid arguments: {}", e),
         })?;
 
+        // A fresh copy was validated when it was fetched and needs no request.
+        let cached = match &self.cache {
+            Some(cache) if !args.refresh => cache.get(&args.url).await,
+            _ => None,
+        };
+        if let Some(page) = &cached {
+            if self.cache.as_ref().is_some_and(|c| c.is_fresh(page)) {
+                return Ok(render(
+                    page.status,
+                    &page.body,
+                    false,
+                    args.max_length,
+                    true,
+                ));
+            }
+        }
+
         // SSRF validation — resolve DNS once, validate IPs, then pin them
         // so reqwest cannot re-resolve to a different (malicious) address.
-        let validated = validate_url_not_internal(&args.url)?;
+        let doh = network::doh_resolver(&self.network).map_err(|e| Self::err(e.to_string()))?;
+        let validated = match &doh {
+            Some(doh) => validate_url_with_doh(&args.url, doh).await?,
+            None => validate_url_not_internal(&args.url)?,
+        };
 
         let client = if let Some(ref domain) = validated.domain {
-            Self::build_pinned_client(domain, validated.port, &validated.resolved_addrs)
+            self.build_pinned_client(
+                doh.as_ref(),
+                domain,
+                validated.port,
+                &validated.resolved_addrs,
+            )?
         } else {
-            reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(30))
-                .user_agent("agent-shell/0.1")
-                .redirect(ssrf_safe_redirect_policy())
+            self.client_builder(doh.as_ref())?
                 .build()
-                .unwrap_or_default()
+                .map_err(|e| Self::err(format!("Failed to build HTTP client: {}", e)))?
         };
 
-        let response = client
-            .get(validated.url.as_str())
+        // Polite fetching: domain limits first, so a site that asked for a
+        // pause is not even asked for its robots.txt.
+        let domain = validated
+            .url
+            .host_str()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        self.limits
+            .admit(&domain)
+            .map_err(|e| Self::err(e.to_string()))?;
+        if let Some(cache) = &self.robots {
+            let robots = self.robots(cache, &client, &validated.url).await;
+            let path = match validated.url.query() {
+                Some(query) => format!("{}?{}", validated.url.path(), query),
+                None => validated.url.path().to_string(),
+            };
+            if let Some(rule) = robots.disallowing(&path) {
+                return Err(Self::err(format!(
+                    "Blocked by robots.txt: {} disallows '{}' for {} (rule 'Disallow: {}'). \
+                     The site asks agents not to fetch this page; find the information elsewhere \
+                     or ask the user to fetch it.",
+                    validated.url.origin().ascii_serialization() + "/robots.txt",
+                    path,
+                    ROBOTS_AGENT,
+                    rule
+                )));
+            }
+        }
+        let _slot = self.limits.slot(&domain).await;
+
+        let mut request = client.get(validated.url.as_str());
+        for (name, value) in cached.iter().flat_map(CachedPage::validators) {
+            request = request.header(name, value);
+        }
+        let response = request
             .send()
             .await
             .map_err(|e| AgentError::ToolExecution {
@@ -303,47 +542,81 @@ impl Tool for WebFetchTool {
             })?;
 
         let status = response.status();
+        let headers = response.headers().clone();
+
+        // Honour a request to slow down; without `Retry-After` only a 429
+        // is one.
+        let pause = match (status.as_u16(), web_policy::retry_after(&headers)) {
+            (429, after) => Some(after.unwrap_or(web_policy::DEFAULT_BACK_OFF)),
+            (503, Some(after)) => Some(after),
+            _ => None,
+        };
+        if let Some(pause) = pause {
+            let limited = self.limits.back_off(&domain, pause, status.as_u16());
+            return Err(Self::err(limited.to_string()));
+        }
 
-        // Stream the response body with a hard byte cap to prevent OOM.
-        use futures::StreamExt;
-        let mut stream = response.bytes_stream();
-        let mut buf = Vec::new();
-        let mut hit_limit = false;
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk.map_err(|e| AgentError::ToolExecution {
-                tool_name: "web_fetch".into(),
-                message: format!("Failed to read response body: {}", e),
-            })?;
-            buf.extend_from_slice(&chunk);
-            if buf.len() >= MAX_BODY_BYTES {
-                buf.truncate(MAX_BODY_BYTES);
-                hit_limit = true;
-                break;
+        if status == reqwest::StatusCode::NOT_MODIFIED {
+            if let (Some(cache), Some(mut page)) = (&self.cache, cached) {
+                page.revalidated(&headers);
+                cache.put(&page).await;
+                return Ok(render(
+                    page.status,
+                    &page.body,
+                    false,
+                    args.max_length,
+                    true,
+                ));
             }
         }
 
-        let body = String::from_utf8_lossy(&buf);
-        let truncated = if body.len() > args.max_length {
-            format!(
-                "{}... [truncated, {} total chars{}]",
-                &body[..args.max_length],
-                body.len(),
-                if hit_limit {
-                    ", response exceeded 2MB limit"
-                } else {
-                    ""
-                },
-            )
-        } else if hit_limit {
-            format!("{}... [truncated at 2MB limit]", body)
-        } else {
-            body.into_owned()
-        };
+        let (buf, hit_limit) = read_body(response, MAX_BODY_BYTES).await?;
 
-        Ok(format!("HTTP {}\n\n{}", status.as_u16(), truncated))
+        let body = String::from_utf8_lossy(&buf);
+        if let Some(cache) = &self.cache {
+            if status == reqwest::StatusCode::OK && !hit_limit && web_cache::storable(&headers) {
+                let page = CachedPage::new(&args.url, status.as_u16(), &headers, body.to_string());
+                cache.put(&page).await;
+            }
+        }
+        Ok(render(
+            status.as_u16(),
+            &body,
+            hit_limit,
+            args.max_length,
+            false,
+        ))
     }
 }
 
+/// The tool output for a response: the status line, marked `(cached)` for
+/// pages served from the cache, and the body cut to `max_length` bytes on
+/// a character boundary.
+fn render(status: u16, body: &str, hit_limit: bool, max_length: usize, cached: bool) -> String {
+    let truncated = if body.len() > max_length {
+        let mut end = max_length;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!(
+            "{}... [truncated, {} total chars{}]",
+            &body[..end],
+            body.len(),
+            if hit_limit {
+                ", response exceeded 2MB limit"
+            } else {
+                ""
+            },
+        )
+    } else if hit_limit {
+        format!("{}... [truncated at 2MB limit]", body)
+    } else {
+        body.to_string()
+    };
+    let cached = if cached { " (cached)" } else { "" };
+    format!("HTTP {}{}\n\n{}", status, cached, truncated)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -405,6 +678,21 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_check_url_and_addrs_without_lookup() {
+        let validated = check_url("https://docs.rs/serde").unwrap();
+        assert_eq!(validated.domain.as_deref(), Some("docs.rs"));
+        assert!(validated.resolved_addrs.is_empty());
+        assert!(check_url("http://localhost:8080").is_err());
+
+        let public: SocketAddr = "93.184.216.34:443".parse().unwrap();
+        let private: SocketAddr = "10.0.0.5:443".parse().unwrap();
+        assert_eq!(check_addrs("docs.rs", vec![public]).unwrap(), [public]);
+        let err = check_addrs("evil.example", vec![public, private]).unwrap_err();
+        assert!(err.contains("10.0.0.5"), "{}", err);
+        assert!(check_addrs("empty.example", Vec::new()).is_err());
+    }
+
     #[test]
     fn test_blocked_scheme_ftp() {
         let result = validate_url_not_internal("ftp://example.com/file");
@@ -482,7 +770,7 @@ mod tests {
     #[test]
     fn test_ssrf_redirect_policy_is_custom() {
         // Ensure the custom redirect policy can be constructed without panic.
-        let _policy = ssrf_safe_redirect_policy();
+        let _policy = ssrf_safe_redirect_policy(false);
     }
 
     #[test]
@@ -497,4 +785,13 @@ mod tests {
         let result = validate_url_not_internal("http://169.254.169.254/metadata");
         assert!(result.is_err(), "redirect to link-local should be blocked");
     }
+
+    #[test]
+    fn test_render_truncates_on_char_boundary() {
+        // "é" is two bytes; a limit of 3 falls inside the second one.
+        let out = render(200, "éé€", false, 3, false);
+        assert_eq!(out, "HTTP 200\n\né... [truncated, 7 total chars]");
+        let out = render(200, "€", false, 1, true);
+        assert_eq!(out, "HTTP 200 (cached)\n\n... [truncated, 3 total chars]");
+    }
 }
@@ -0,0 +1,371 @@
+//! Polite fetching for `web_fetch`: robots.txt and per-domain limits.
+//!
+//! Each domain gets at most `requests_per_minute` requests in any 60
+//! seconds and `max_concurrent_per_domain` requests in flight; further
+//! requests wait for a free slot. A domain that answers `429`, or `503`
+//! with `Retry-After`, is left alone for as long as it asks. Refused
+//! requests fail with a [`RateLimited`] message saying when to retry.
+//!
+//! robots.txt is read once an hour per site and applied as RFC 9309
+//! describes: the group for [`ROBOTS_AGENT`], or else `*`, decides, and of
+//! its `Allow` and `Disallow` rules the longest matching one wins, `Allow`
+//! on a tie.
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// The product token matched against robots.txt `User-agent` lines.
+pub const ROBOTS_AGENT: &str = "agent-shell";
+
+/// Start of the tool error for a refused request.
+pub const RATE_LIMITED: &str = "Rate limited";
+
+/// How long a site's robots.txt is trusted.
+const ROBOTS_TTL: Duration = Duration::from_secs(3600);
+
+/// The window `requests_per_minute` counts in.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Pause after a `429` without `Retry-After`, and the longest one honoured.
+pub const DEFAULT_BACK_OFF: Duration = Duration::from_secs(60);
+const MAX_BACK_OFF: Duration = Duration::from_secs(3600);
+
+/// A request that may not be made yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimited {
+    pub domain: String,
+    pub retry_after: Duration,
+    pub reason: String,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Whole seconds, rounded up, so retrying then succeeds.
+        let secs = self.retry_after.as_millis().div_ceil(1000).max(1);
+        write!(
+            f,
+            "{}: retry after {}s. {}. Fetch something else meanwhile, or wait and try again.",
+            RATE_LIMITED, secs, self.reason
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct DomainState {
+    /// Start times of requests within the last [`WINDOW`].
+    recent: VecDeque<Instant>,
+    blocked_until: Option<(Instant, String)>,
+    slots: Option<Arc<Semaphore>>,
+}
+
+/// Request rate and concurrency limits by domain. Zero means no limit.
+#[derive(Debug, Default)]
+pub struct DomainLimits {
+    requests_per_minute: u32,
+    max_concurrent: usize,
+    domains: Mutex<HashMap<String, DomainState>>,
+}
+
+impl DomainLimits {
+    pub fn new(requests_per_minute: u32, max_concurrent: usize) -> Self {
+        Self {
+            requests_per_minute,
+            max_concurrent,
+            domains: Mutex::default(),
+        }
+    }
+
+    /// Count a request to `domain`, or say when one may be made. Refused
+    /// requests are not counted.
+    pub fn admit(&self, domain: &str) -> Result<(), RateLimited> {
+        self.admit_at(domain, Instant::now())
+    }
+
+    fn admit_at(&self, domain: &str, now: Instant) -> Result<(), RateLimited> {
+        let mut domains = self.domains.lock().unwrap_or_else(|e| e.into_inner());
+        let state = domains.entry(domain.to_string()).or_default();
+        if let Some((until, reason)) = &state.blocked_until {
+            if *until > now {
+                return Err(RateLimited {
+                    domain: domain.to_string(),
+                    retry_after: *until - now,
+                    reason: reason.clone(),
+                });
+            }
+            state.blocked_until = None;
+        }
+        while state
+            .recent
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= WINDOW)
+        {
+            state.recent.pop_front();
+        }
+        if self.requests_per_minute > 0 && state.recent.len() >= self.requests_per_minute as usize {
+            let oldest = state.recent[0];
+            return Err(RateLimited {
+                domain: domain.to_string(),
+                retry_after: WINDOW - now.duration_since(oldest),
+                reason: format!(
+                    "web_fetch makes at most {} requests per minute to {}",
+                    self.requests_per_minute, domain
+                ),
+            });
+        }
+        state.recent.push_back(now);
+        Ok(())
+    }
+
+    /// Wait for one of `domain`'s request slots; `None` without a
+    /// concurrency limit. The slot is freed when the permit is dropped.
+    pub async fn slot(&self, domain: &str) -> Option<OwnedSemaphorePermit> {
+        if self.max_concurrent == 0 {
+            return None;
+        }
+        let slots = {
+            let mut domains = self.domains.lock().unwrap_or_else(|e| e.into_inner());
+            let state = domains.entry(domain.to_string()).or_default();
+            state
+                .slots
+                .get_or_insert_with(|| Arc::new(Semaphore::new(self.max_concurrent)))
+                .clone()
+        };
+        slots.acquire_owned().await.ok()
+    }
+
+    /// Leave `domain` alone for `retry_after` (at most an hour), as its
+    /// server asked with `status`.
+    pub fn back_off(&self, domain: &str, retry_after: Duration, status: u16) -> RateLimited {
+        let retry_after = retry_after.min(MAX_BACK_OFF);
+        let reason = format!("{} answered HTTP {} and asked for a pause", domain, status);
+        let mut domains = self.domains.lock().unwrap_or_else(|e| e.into_inner());
+        domains.entry(domain.to_string()).or_default().blocked_until =
+            Some((Instant::now() + retry_after, reason.clone()));
+        RateLimited {
+            domain: domain.to_string(),
+            retry_after,
+            reason,
+        }
+    }
+}
+
+/// The delay a `Retry-After` header asks for, in seconds or as an HTTP
+/// date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        date.with_timezone(&Utc)
+            .signed_duration_since(Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// One `Allow` or `Disallow` rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RobotsRule {
+    allow: bool,
+    pattern: String,
+}
+
+/// The rules of one robots.txt that apply to [`ROBOTS_AGENT`]. The
+/// default allows everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Robots {
+    rules: Vec<RobotsRule>,
+}
+
+impl Robots {
+    pub fn parse(text: &str) -> Self {
+        // Consecutive `User-agent` lines share the rules that follow them.
+        let mut groups: Vec<(Vec<String>, Vec<RobotsRule>)> = Vec::new();
+        let mut in_agents = false;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if !in_agents {
+                        groups.push((Vec::new(), Vec::new()));
+                        in_agents = true;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.0.push(value.to_ascii_lowercase());
+                    }
+                }
+                key @ ("allow" | "disallow") => {
+                    in_agents = false;
+                    if let Some(group) = groups.last_mut().filter(|_| !value.is_empty()) {
+                        group.1.push(RobotsRule {
+                            allow: key == "allow",
+                            pattern: value.to_string(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        let rules_for = |agent: &str| -> Vec<RobotsRule> {
+            groups
+                .iter()
+                .filter(|(agents, _)| agents.iter().any(|a| a == agent))
+                .flat_map(|(_, rules)| rules.iter().cloned())
+                .collect()
+        };
+        let named = groups
+            .iter()
+            .any(|(agents, _)| agents.iter().any(|a| a == ROBOTS_AGENT));
+        Self {
+            rules: rules_for(if named { ROBOTS_AGENT } else { "*" }),
+        }
+    }
+
+    /// The `Disallow` pattern refusing `path` (with its query), if any.
+    pub fn disallowing(&self, path: &str) -> Option<&str> {
+        if path == "/robots.txt" {
+            return None;
+        }
+        self.rules
+            .iter()
+            .filter(|rule| pattern_matches(&rule.pattern, path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+            .filter(|rule| !rule.allow)
+            .map(|rule| rule.pattern.as_str())
+    }
+}
+
+/// Whether a robots.txt path pattern, where `*` matches anything and a
+/// trailing `$` ends the path, matches `path`.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = parts.next().and_then(|first| path.strip_prefix(first)) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i + 1 == parts.len() {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// robots.txt files by origin (`https://host:port`), kept for an hour.
+#[derive(Debug, Default)]
+pub struct RobotsCache {
+    sites: Mutex<HashMap<String, (Instant, Arc<Robots>)>>,
+}
+
+impl RobotsCache {
+    pub fn get(&self, origin: &str) -> Option<Arc<Robots>> {
+        let sites = self.sites.lock().unwrap_or_else(|e| e.into_inner());
+        sites
+            .get(origin)
+            .filter(|(read_at, _)| read_at.elapsed() < ROBOTS_TTL)
+            .map(|(_, robots)| robots.clone())
+    }
+
+    pub fn insert(&self, origin: &str, robots: Robots) -> Arc<Robots> {
+        let robots = Arc::new(robots);
+        let mut sites = self.sites.lock().unwrap_or_else(|e| e.into_inner());
+        sites.insert(origin.to_string(), (Instant::now(), robots.clone()));
+        robots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_robots_groups_and_precedence() {
+        let robots = Robots::parse(
+            "# comment\n\
+             User-agent: *\n\
+             Disallow: /\n\
+             \n\
+             User-agent: Googlebot\n\
+             User-agent: agent-shell\n\
+             Disallow: /private\n\
+             Allow: /private/docs\n\
+             Disallow: /*.pdf$\n\
+             Disallow:\n\
+             Sitemap: https://example.com/sitemap.xml\n",
+        );
+        assert_eq!(robots.disallowing("/guide"), None);
+        assert_eq!(robots.disallowing("/private/keys"), Some("/private"));
+        assert_eq!(robots.disallowing("/private/docs/intro"), None);
+        assert_eq!(robots.disallowing("/files/report.pdf"), Some("/*.pdf$"));
+        assert_eq!(robots.disallowing("/files/report.pdf?page=2"), None);
+
+        // Without a group of its own the agent follows `*`.
+        let everyone = Robots::parse("User-agent: *\nDisallow: /search?\n");
+        assert_eq!(everyone.disallowing("/search?q=rust"), Some("/search?"));
+        assert_eq!(everyone.disallowing("/search"), None);
+        assert_eq!(everyone.disallowing("/robots.txt"), None);
+        assert_eq!(Robots::parse("").disallowing("/anything"), None);
+    }
+
+    #[test]
+    fn test_limits_window_and_back_off() {
+        let limits = DomainLimits::new(2, 1);
+        let start = Instant::now();
+        assert!(limits.admit_at("docs.rs", start).is_ok());
+        assert!(limits.admit_at("docs.rs", start).is_ok());
+        let limited = limits
+            .admit_at("docs.rs", start + Duration::from_secs(15))
+            .unwrap_err();
+        assert_eq!(limited.retry_after, Duration::from_secs(45));
+        assert!(
+            limited
+                .to_string()
+                .starts_with("Rate limited: retry after 45s."),
+            "{}",
+            limited
+        );
+        assert!(limits.admit_at("crates.io", start).is_ok());
+        assert!(limits
+            .admit_at("docs.rs", start + Duration::from_secs(60))
+            .is_ok());
+
+        let limited = limits.back_off("crates.io", Duration::from_secs(30), 429);
+        assert!(limited.reason.contains("HTTP 429"));
+        let err = limits.admit("crates.io").unwrap_err();
+        assert!(err.retry_after <= Duration::from_secs(30));
+        assert!(DomainLimits::default().admit("crates.io").is_ok());
+    }
+
+    #[test]
+    fn test_retry_after_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+}
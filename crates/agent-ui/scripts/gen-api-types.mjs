@@ -0,0 +1,91 @@
+// Generate TypeScript types from the server's OpenAPI description.
+//
+// Reads crates/agent-client/openapi.json (kept current by agent-server's
+// `test_openapi_snapshot_is_current`) and writes src/services/api.gen.ts.
+// Run with `npm run gen:api` after the snapshot changes.
+
+import { readFileSync, writeFileSync } from 'node:fs';
+import { dirname, resolve } from 'node:path';
+import { fileURLToPath } from 'node:url';
+
+const root = resolve(dirname(fileURLToPath(import.meta.url)), '..');
+const spec = JSON.parse(
+  readFileSync(resolve(root, '../agent-client/openapi.json'), 'utf8'),
+);
+const schemas = spec.components.schemas;
+
+function refName(schema) {
+  return schema.$ref?.replace('#/components/schemas/', '');
+}
+
+function tsType(schema) {
+  const ref = refName(schema);
+  if (ref) return ref;
+  if (schema.oneOf) return schema.oneOf.map(tsType).join(' | ');
+  if (schema.enum) return schema.enum.map((v) => JSON.stringify(v)).join(' | ');
+  const kinds = Array.isArray(schema.type) ? schema.type : [schema.type];
+  return kinds
+    .map((kind) => {
+      switch (kind) {
+        case 'string':
+          return 'string';
+        case 'boolean':
+          return 'boolean';
+        case 'integer':
+        case 'number':
+          return 'number';
+        case 'null':
+          return 'null';
+        case 'array':
+          return `${wrap(tsType(schema.items ?? {}))}[]`;
+        case 'object':
+          return schema.properties ? objectType(schema, '') : 'Record<string, unknown>';
+        default:
+          return 'unknown';
+      }
+    })
+    .join(' | ');
+}
+
+function wrap(type) {
+  return type.includes(' | ') ? `(${type})` : type;
+}
+
+function doc(schema, indent) {
+  if (!schema.description) return '';
+  const lines = schema.description.split('\n');
+  if (lines.length === 1) return `${indent}/** ${lines[0]} */\n`;
+  return `${indent}/**\n${lines.map((l) => `${indent} * ${l}`.trimEnd()).join('\n')}\n${indent} */\n`;
+}
+
+function properties(schema) {
+  if (schema.allOf) {
+    return schema.allOf.flatMap((part) => properties(schemas[refName(part)] ?? part));
+  }
+  const required = new Set(schema.required ?? []);
+  return Object.entries(schema.properties ?? {}).map(([name, prop]) => [
+    name,
+    prop,
+    required.has(name),
+  ]);
+}
+
+function objectType(schema, indent) {
+  const fields = properties(schema).map(
+    ([name, prop, required]) =>
+      `${doc(prop, indent + '  ')}${indent}  ${name}${required ? '' : '?'}: ${tsType(prop)};\n`,
+  );
+  return `{\n${fields.join('')}${indent}}`;
+}
+
+let out = '// Generated by scripts/gen-api-types.mjs from openapi.json. Do not edit.\n';
+for (const [name, schema] of Object.entries(schemas)) {
+  out += '\n' + doc(schema, '');
+  if (schema.enum) {
+    out += `export type ${name} = ${tsType(schema)};\n`;
+  } else {
+    out += `export interface ${name} ${objectType(schema, '')}\n`;
+  }
+}
+
+writeFileSync(resolve(root, 'src/services/api.gen.ts'), out);
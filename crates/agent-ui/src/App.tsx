@@ -7,15 +7,25 @@ import { Sidebar } from './components/Sidebar'
 import { ConnectionLines } from './components/ConnectionLines'
 import { SettingsModal } from './components/SettingsModal'
 import { ConfirmationModal } from './components/ConfirmationModal'
-import { CardData, CardType, CardSnapshot, ViewportState, AppSettings, ApiConfig } from './types'
-import { DEFAULT_CARD_SIZES, GRID_SIZE } from './constants'
-import { healthCheck, getConfig, createSession, getAuthToken } from './services/api'
+import { CommandPalette, PaletteCommand } from './components/CommandPalette'
+import { Toasts } from './components/Toasts'
+import { CardData, CardType, CardSnapshot, ViewportState, AppSettings, ApiConfig, ApiSession } from './types'
+import { DEFAULT_CARD_SIZES, GRID_SIZE, CARD_LABELS } from './constants'
+import {
+  healthCheck, getConfig, createSession, getAuthToken, getPreferences, updatePreferences,
+  listSessions, getSessionMessages, forkSession, toLocalHistory,
+} from './services/api'
 import { loadCanvasState, saveCanvasState } from './services/storage'
 
 interface CanvasState { cards: CardData[]; viewport: ViewportState; settings: AppSettings }
 
 const DEFAULT_SETTINGS: AppSettings = {
   theme: 'dark', showGrid: true, snapToGrid: false, authToken: getAuthToken(),
+  fontSize: 13, defaultView: '', collapsedToolCards: true,
+}
+
+function isCardType(v: string): v is CardType {
+  return (Object.values(CardType) as string[]).includes(v)
 }
 
 function createSnapshot(card: CardData): CardSnapshot {
@@ -24,6 +34,24 @@ function createSnapshot(card: CardData): CardSnapshot {
 
 function snapToGrid(v: number): number { return Math.round(v / GRID_SIZE) * GRID_SIZE }
 
+function downloadChatMarkdown(card: CardData) {
+  const lines = [`# ${card.title ?? 'Chat'}`, '']
+  for (const msg of card.chatHistory ?? []) {
+    lines.push(`## ${msg.role === 'user' ? 'User' : 'Assistant'}`, '')
+    for (const tc of msg.toolCalls ?? []) {
+      lines.push(`> Tool \`${tc.name}\`${tc.arguments ? ` \`${tc.arguments}\`` : ''}`, '')
+    }
+    lines.push(msg.content, '')
+  }
+  const blob = new Blob([lines.join('\n')], { type: 'text/markdown' })
+  const url = URL.createObjectURL(blob)
+  const a = document.createElement('a')
+  a.href = url
+  a.download = `${(card.title ?? 'chat').replace(/[^\w.-]+/g, '_')}.md`
+  a.click()
+  URL.revokeObjectURL(url)
+}
+
 // ── Memoized Card to avoid re-rendering all cards on viewport/sibling changes ─
 const MemoizedCard = memo(Card)
 
@@ -37,16 +65,21 @@ export default function App() {
   const [isSelectionMode, setIsSelectionMode] = useState(false)
   const [selectedIds, setSelectedIds] = useState<Set<string>>(new Set())
   const [settingsOpen, setSettingsOpen] = useState(false)
+  const [paletteOpen, setPaletteOpen] = useState(false)
+  const [paletteSessions, setPaletteSessions] = useState<ApiSession[]>([])
   const [confirmModal, setConfirmModal] = useState<{ open: boolean; title: string; message: string; onConfirm: () => void }>({ open: false, title: '', message: '', onConfirm: () => {} })
   const saveTimer = useRef<ReturnType<typeof setTimeout> | null>(null)
+  const canvasLoaded = useRef<Promise<CanvasState | null>>(Promise.resolve(null))
+  const prefsLoaded = useRef(false)
 
   // ── Load state from IndexedDB (with localStorage migration) ────────
   useEffect(() => {
-    loadCanvasState<CanvasState>().then(state => {
+    canvasLoaded.current = loadCanvasState<CanvasState>()
+    canvasLoaded.current.then(state => {
       if (state) {
         setCards(state.cards ?? [])
         setViewport(state.viewport ?? { x: 0, y: 0, scale: 1 })
-        setSettings({ ...DEFAULT_SETTINGS, ...(state.settings ?? {}) })
+        setSettings(s => ({ ...s, ...(state.settings ?? {}) }))
       }
     })
   }, [])
@@ -61,6 +94,26 @@ export default function App() {
     }
   }, [settings.theme])
 
+  useEffect(() => {
+    document.documentElement.style.setProperty('--ui-font-size', `${settings.fontSize}px`)
+  }, [settings.fontSize])
+
+  // ── Sync UI preferences with the server ──────────────────────────────
+  const prefsTimer = useRef<ReturnType<typeof setTimeout> | null>(null)
+  useEffect(() => {
+    if (!prefsLoaded.current) return
+    if (prefsTimer.current) clearTimeout(prefsTimer.current)
+    prefsTimer.current = setTimeout(() => {
+      updatePreferences({
+        theme: settings.theme,
+        font_size: settings.fontSize,
+        default_view: settings.defaultView,
+        collapsed_tool_cards: settings.collapsedToolCards,
+      }).catch(() => {})
+    }, 500)
+    return () => { if (prefsTimer.current) clearTimeout(prefsTimer.current) }
+  }, [settings.theme, settings.fontSize, settings.defaultView, settings.collapsedToolCards])
+
   // ── Persist state with debounce (IndexedDB, no 5 MB quota limit) ───
   useEffect(() => {
     if (saveTimer.current) clearTimeout(saveTimer.current)
@@ -86,13 +139,32 @@ export default function App() {
     healthCheck().then(() => {
       setConnStatus('ok')
       getConfig().then(setServerConfig).catch(() => {})
+      // Server preferences win over the browser's copy; open the default view on an empty canvas.
+      Promise.all([getPreferences(), canvasLoaded.current]).then(([prefs, state]) => {
+        const defaultView = isCardType(prefs.default_view) ? prefs.default_view : ''
+        setSettings(s => ({
+          ...s,
+          theme: prefs.theme,
+          fontSize: prefs.font_size,
+          defaultView,
+          collapsedToolCards: prefs.collapsed_tool_cards,
+        }))
+        prefsLoaded.current = true
+        if (defaultView && !state?.cards?.length) void handleAddCard(defaultView)
+      }).catch(() => {})
     }).catch(() => setConnStatus('err'))
   }, [])
 
   // ── Keyboard shortcuts ────────────────────────────────────────────────
   useEffect(() => {
     const handler = (e: KeyboardEvent) => {
+      if ((e.ctrlKey || e.metaKey) && e.key.toLowerCase() === 'k') {
+        e.preventDefault()
+        setPaletteOpen(open => !open)
+        return
+      }
       if (e.key === 'Escape') {
+        setPaletteOpen(false)
         setSettingsOpen(false)
         setIsSelectionMode(false)
         setSelectedIds(new Set())
@@ -129,6 +201,11 @@ export default function App() {
     return card
   }, [viewport, settings.snapToGrid])
 
+  // Stable wrapper so cards can spawn new cards without re-rendering on viewport changes.
+  const addCardRef = useRef(addCard)
+  addCardRef.current = addCard
+  const spawnCard = useCallback((partial: Partial<CardData>) => { addCardRef.current(partial) }, [])
+
   const updateCard = useCallback((id: string, updates: Partial<CardData>, saveCheckpoint = false) => {
     setCards(prev => prev.map(c => {
       if (c.id !== id) return c
@@ -211,13 +288,7 @@ export default function App() {
         addCard({ type, title: 'Chat' })
       }
     } else {
-      const labels: Partial<Record<CardType, string>> = {
-        [CardType.SESSION]: 'Session Viewer', [CardType.NOTE]: 'Note',
-        [CardType.ANALYTICS]: 'Analytics', [CardType.TERMINAL]: 'Terminal',
-        [CardType.SKILLS]: 'Skills', [CardType.CONTEXT]: 'Context',
-        [CardType.PLUGINS]: 'Plugins', [CardType.ISLAND]: 'Island',
-      }
-      addCard({ type, title: labels[type] ?? type })
+      addCard({ type, title: CARD_LABELS[type] })
     }
   }, [addCard])
 
@@ -235,10 +306,76 @@ export default function App() {
       type: CardType.CHAT,
       title: query.slice(0, 40),
       sessionId,
-      chatHistory: [{ role: 'user', content: query }],
+      chatHistory: [],
+      draft: query,
     })
   }, [addCard])
 
+  // ── Command palette (Ctrl+K) ──────────────────────────────────────────
+  useEffect(() => {
+    if (paletteOpen) listSessions({ limit: 50 }).then(setPaletteSessions).catch(() => {})
+  }, [paletteOpen])
+
+  // Chat actions apply to the front-most chat card.
+  const activeChat = cards
+    .filter(c => c.type === CardType.CHAT)
+    .reduce<CardData | undefined>((top, c) => (!top || c.zIndex > top.zIndex ? c : top), undefined)
+
+  const openSession = useCallback(async (session: ApiSession) => {
+    const existing = cards.find(c => c.type === CardType.CHAT && c.sessionId === session.id)
+    if (existing) { bringToFront(existing.id); return }
+    const messages = await getSessionMessages(session.id).catch(() => [])
+    addCard({ type: CardType.CHAT, title: session.name, sessionId: session.id, chatHistory: toLocalHistory(messages) })
+  }, [cards, addCard, bringToFront])
+
+  const paletteCommands: PaletteCommand[] = paletteOpen ? [
+    { id: 'new', group: 'Chat', title: 'New chat session', slash: '/new', run: () => void handleAddCard(CardType.CHAT) },
+    {
+      id: 'clear', group: 'Chat', title: 'Clear current chat', slash: '/clear', disabled: !activeChat,
+      run: () => {
+        if (!activeChat) return
+        createSession(activeChat.title ?? 'chat')
+          .then(s => updateCard(activeChat.id, { sessionId: s.id, chatHistory: [] }, true))
+          .catch(() => updateCard(activeChat.id, { chatHistory: [] }, true))
+      },
+    },
+    {
+      id: 'fork', group: 'Chat', title: 'Fork current chat', slash: '/fork', disabled: !activeChat?.sessionId,
+      run: () => {
+        if (!activeChat?.sessionId) return
+        forkSession(activeChat.sessionId)
+          .then(s => addCard({ type: CardType.CHAT, title: s.name, sessionId: s.id, chatHistory: activeChat.chatHistory }))
+          .catch(() => {})
+      },
+    },
+    {
+      id: 'export', group: 'Chat', title: 'Export current chat as Markdown', slash: '/export', disabled: !activeChat?.chatHistory?.length,
+      run: () => { if (activeChat) downloadChatMarkdown(activeChat) },
+    },
+    ...paletteSessions.map(s => ({
+      id: `session-${s.id}`, group: 'Switch session', title: s.name, keywords: `${s.id} session switch`,
+      run: () => void openSession(s),
+    })),
+    ...Object.values(CardType).filter(t => t !== CardType.ISLAND && t !== CardType.CHAT).map(t => ({
+      id: `view-${t}`, group: 'Views', title: `Open ${CARD_LABELS[t]}`,
+      slash: ({
+        [CardType.SESSION]: '/sessions', [CardType.SKILLS]: '/skills', [CardType.ANALYTICS]: '/analytics',
+        [CardType.CONTEXT]: '/context', [CardType.TERMINAL]: '/shells', [CardType.CHANGES]: '/diff', [CardType.LOGS]: '/logs',
+      } as Partial<Record<CardType, string>>)[t],
+      run: () => void handleAddCard(t),
+    })),
+    { id: 'settings', group: 'Settings', title: 'Open settings', slash: '/config', run: () => setSettingsOpen(true) },
+    {
+      id: 'theme', group: 'Settings', title: `Switch to ${settings.theme === 'dark' ? 'light' : 'dark'} theme`, keywords: 'theme appearance',
+      run: () => setSettings(s => ({ ...s, theme: s.theme === 'dark' ? 'light' : 'dark' })),
+    },
+  ] : []
+
+  const paletteDynamic = useCallback((query: string): PaletteCommand[] => [
+    { id: 'ask', group: 'Search', title: `Ask agent: “${query}”`, run: q => void handleOmniSubmit(q) },
+    { id: 'skills', group: 'Search', title: `Search skills for “${query}”`, run: q => { addCard({ type: CardType.SKILLS, title: CARD_LABELS[CardType.SKILLS], content: q }) } },
+  ], [handleOmniSubmit, addCard])
+
   // ── Visible cards (hide cards inside collapsed islands) ───────────────
   const collapsedIslandIds = new Set(cards.filter(c => c.type === CardType.ISLAND && c.isCollapsed).map(c => c.id))
   const visibleCards = cards.filter(c => !c.groupId || !collapsedIslandIds.has(c.groupId))
@@ -258,6 +395,9 @@ export default function App() {
             onSelect={handleSelect}
             onBringToFront={bringToFront}
             navigateHistory={navigateHistory}
+            onSpawnCard={spawnCard}
+            collapseToolCards={settings.collapsedToolCards}
+            audio={serverConfig?.audio}
           />
         ))}
       </Canvas>
@@ -289,6 +429,16 @@ export default function App() {
         />
       )}
 
+      {paletteOpen && (
+        <CommandPalette
+          commands={paletteCommands}
+          dynamic={paletteDynamic}
+          onClose={() => setPaletteOpen(false)}
+        />
+      )}
+
+      <Toasts />
+
       {confirmModal.open && (
         <ConfirmationModal
           title={confirmModal.title}
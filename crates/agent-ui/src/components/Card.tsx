@@ -1,11 +1,13 @@
 import React, { useRef, useCallback, Suspense, lazy } from 'react'
-import { CardData, CardType, LocalChatMessage } from '../types'
+import { ApiConfig, CardData, CardType, LocalChatMessage } from '../types'
 import { CARD_COLORS } from '../constants'
 import { NoteCard } from './cards/NoteCard'
 import { SessionCard } from './cards/SessionCard'
 import { SkillsCard } from './cards/SkillsCard'
 import { ContextCard } from './cards/ContextCard'
 import { PluginsCard } from './cards/PluginsCard'
+import { ChangesCard } from './cards/ChangesCard'
+import { LogsCard } from './cards/LogsCard'
 
 // Lazy-load heavy components (xterm ~500 kB, react-markdown ~200 kB)
 const ChatCard = lazy(() => import('./cards/ChatCard').then(m => ({ default: m.ChatCard })))
@@ -21,9 +23,12 @@ interface Props {
   onSelect: (id: string) => void
   onBringToFront: (id: string) => void
   navigateHistory: (id: string, dir: -1 | 1) => void
+  onSpawnCard?: (partial: Partial<CardData>) => void
+  collapseToolCards?: boolean
+  audio?: ApiConfig['audio']
 }
 
-export function Card({ data, isSelected, isSelectionMode, onUpdate, onDelete, onSelect, onBringToFront, navigateHistory }: Props) {
+export function Card({ data, isSelected, isSelectionMode, onUpdate, onDelete, onSelect, onBringToFront, navigateHistory, onSpawnCard, collapseToolCards, audio }: Props) {
   const cardRef = useRef<HTMLDivElement>(null)
   const dragStart = useRef<{ mx: number; my: number; cx: number; cy: number } | null>(null)
   const resizeStart = useRef<{ mx: number; my: number; cw: number; ch: number } | null>(null)
@@ -148,7 +153,10 @@ export function Card({ data, isSelected, isSelectionMode, onUpdate, onDelete, on
             <ChatCard
               history={data.chatHistory ?? []}
               sessionId={data.sessionId}
-              onHistoryUpdate={h => onUpdate(data.id, { chatHistory: h as LocalChatMessage[] }, true)}
+              draft={data.draft}
+              collapseToolCards={collapseToolCards}
+              audio={audio}
+              onHistoryUpdate={h => onUpdate(data.id, { chatHistory: h as LocalChatMessage[], draft: undefined }, true)}
             />
           )}
           {data.type === CardType.SESSION && <SessionCard />}
@@ -159,10 +167,22 @@ export function Card({ data, isSelected, isSelectionMode, onUpdate, onDelete, on
             />
           )}
           {data.type === CardType.ANALYTICS && <AnalyticsCard />}
-          {data.type === CardType.TERMINAL && <TerminalCard />}
-          {data.type === CardType.SKILLS && <SkillsCard />}
+          {data.type === CardType.TERMINAL && (
+            <TerminalCard
+              onAskAgent={onSpawnCard && (session => onSpawnCard({
+                type: CardType.CHAT,
+                title: session.name,
+                sessionId: session.id,
+                chatHistory: [],
+                draft: session.prompt,
+              }))}
+            />
+          )}
+          {data.type === CardType.SKILLS && <SkillsCard initialQuery={data.content} />}
           {data.type === CardType.CONTEXT && <ContextCard />}
           {data.type === CardType.PLUGINS && <PluginsCard />}
+          {data.type === CardType.CHANGES && <ChangesCard sessionId={data.sessionId} />}
+          {data.type === CardType.LOGS && <LogsCard />}
         </Suspense>
       </div>
 
@@ -0,0 +1,49 @@
+import { ApiSessionUsage } from '../types'
+
+/** Share of a budget at which the meter turns amber. */
+const WARN_AT = 0.8
+
+function formatTokens(n: number): string {
+  if (n >= 1_000_000) return `${(n / 1_000_000).toFixed(1)}M`
+  if (n >= 1_000) return `${(n / 1_000).toFixed(1)}k`
+  return String(n)
+}
+
+function formatCost(usd: number): string {
+  return usd > 0 && usd < 0.01 ? `$${usd.toFixed(4)}` : `$${usd.toFixed(2)}`
+}
+
+/** Live token and cost meter for the session, warning as it nears the configured budget. */
+export function ChatHeader({ usage }: { usage: ApiSessionUsage | null }) {
+  if (!usage) return null
+
+  const shares = [
+    usage.budget_usd ? usage.cost_usd / usage.budget_usd : null,
+    usage.budget_tokens ? usage.total_tokens / usage.budget_tokens : null,
+  ].filter((s): s is number => s != null)
+  const used = shares.length > 0 ? Math.max(...shares) : null
+  const color = used == null || used < WARN_AT ? 'var(--accent)' : used < 1 ? 'var(--warn)' : 'var(--error)'
+  const approx = usage.estimated ? '~' : ''
+
+  const parts = [`${approx}${formatTokens(usage.total_tokens)}${usage.budget_tokens ? ` / ${formatTokens(usage.budget_tokens)}` : ''} tokens`]
+  if (usage.cost_usd > 0 || usage.budget_usd) {
+    parts.push(`${approx}${formatCost(usage.cost_usd)}${usage.budget_usd ? ` / ${formatCost(usage.budget_usd)}` : ''}`)
+  }
+
+  return (
+    <div
+      className="chat-header"
+      title={`${usage.prompt_tokens.toLocaleString()} prompt + ${usage.completion_tokens.toLocaleString()} completion tokens${usage.estimated ? ' (partly estimated)' : ''}`}
+    >
+      <span style={{ color: used != null && used >= WARN_AT ? color : undefined }}>
+        {used != null && used >= 1 ? '⚠ Over budget · ' : used != null && used >= WARN_AT ? '⚠ Nearing budget · ' : ''}
+        {parts.join(' · ')}
+      </span>
+      {used != null && (
+        <div className="chat-header-bar">
+          <div style={{ width: `${Math.min(100, Math.round(used * 100))}%`, background: color }} />
+        </div>
+      )}
+    </div>
+  )
+}
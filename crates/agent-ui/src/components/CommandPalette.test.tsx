@@ -0,0 +1,15 @@
+import { describe, it, expect } from 'vitest'
+import { fuzzyScore } from './CommandPalette'
+
+describe('fuzzyScore', () => {
+  it('prefers direct substring matches over scattered ones', () => {
+    const direct = fuzzyScore('Fork current chat', 'fork')!
+    const scattered = fuzzyScore('Open Analytics', 'oa')!
+    expect(direct).toBeLessThan(scattered)
+  })
+
+  it('rejects text missing a query character', () => {
+    expect(fuzzyScore('/export', '/fz')).toBeNull()
+    expect(fuzzyScore('anything', '')).toBe(0)
+  })
+})
@@ -0,0 +1,119 @@
+import React, { useState, useEffect, useRef, useMemo } from 'react'
+
+export interface PaletteCommand {
+  id: string
+  title: string
+  group: string
+  /** Slash alias shown on the right, mirroring the REPL (e.g. "/fork"). */
+  slash?: string
+  /** Extra words that should match this command. */
+  keywords?: string
+  disabled?: boolean
+  run: (query: string) => void
+}
+
+interface Props {
+  commands: PaletteCommand[]
+  /** Commands that depend on the typed text (e.g. "Search skills for …"). */
+  dynamic?: (query: string) => PaletteCommand[]
+  onClose: () => void
+}
+
+/** Score a subsequence match; lower is better, null means no match. */
+export function fuzzyScore(text: string, query: string): number | null {
+  const t = text.toLowerCase()
+  const q = query.toLowerCase().trim()
+  if (!q) return 0
+  const direct = t.indexOf(q)
+  if (direct >= 0) return direct
+  let pos = -1
+  let gaps = 0
+  for (const ch of q) {
+    if (ch === ' ') continue
+    const next = t.indexOf(ch, pos + 1)
+    if (next < 0) return null
+    gaps += next - pos - 1
+    pos = next
+  }
+  return 100 + gaps
+}
+
+export function CommandPalette({ commands, dynamic, onClose }: Props) {
+  const [query, setQuery] = useState('')
+  const [active, setActive] = useState(0)
+  const listRef = useRef<HTMLDivElement>(null)
+
+  const results = useMemo(() => {
+    const slash = query.startsWith('/')
+    // Rank within each group but keep groups in their given order so headers stay together.
+    const groupOrder = new Map<string, number>()
+    commands.forEach(c => { if (!groupOrder.has(c.group)) groupOrder.set(c.group, groupOrder.size) })
+    const scored = commands
+      .map(cmd => {
+        const haystack = slash ? (cmd.slash ?? '') : `${cmd.title} ${cmd.slash ?? ''} ${cmd.keywords ?? ''}`
+        return { cmd, score: fuzzyScore(haystack, query) }
+      })
+      .filter((r): r is { cmd: PaletteCommand; score: number } => r.score !== null)
+      .sort((a, b) => (groupOrder.get(a.cmd.group)! - groupOrder.get(b.cmd.group)!) || a.score - b.score)
+      .map(r => r.cmd)
+    return [...scored, ...(dynamic && query.trim() && !slash ? dynamic(query.trim()) : [])]
+  }, [commands, dynamic, query])
+
+  useEffect(() => { setActive(0) }, [query])
+
+  useEffect(() => {
+    listRef.current?.querySelector('.palette-item.active')?.scrollIntoView({ block: 'nearest' })
+  }, [active])
+
+  const runAt = (i: number) => {
+    const cmd = results[i]
+    if (!cmd || cmd.disabled) return
+    onClose()
+    cmd.run(query.trim())
+  }
+
+  const onKeyDown = (e: React.KeyboardEvent) => {
+    if (e.key === 'ArrowDown') { e.preventDefault(); setActive(a => Math.min(results.length - 1, a + 1)) }
+    else if (e.key === 'ArrowUp') { e.preventDefault(); setActive(a => Math.max(0, a - 1)) }
+    else if (e.key === 'Enter') { e.preventDefault(); runAt(active) }
+    else if (e.key === 'Escape') { e.preventDefault(); onClose() }
+  }
+
+  let lastGroup = ''
+
+  return (
+    <div className="modal-overlay palette-overlay" onClick={onClose}>
+      <div className="palette" onClick={e => e.stopPropagation()}>
+        <input
+          autoFocus
+          className="palette-input"
+          placeholder="Type a command, session or view… (/ for slash commands)"
+          value={query}
+          onChange={e => setQuery(e.target.value)}
+          onKeyDown={onKeyDown}
+        />
+        <div className="palette-list" ref={listRef}>
+          {results.length === 0 && <div className="palette-empty">No matching commands.</div>}
+          {results.map((cmd, i) => {
+            const header = cmd.group !== lastGroup ? cmd.group : null
+            lastGroup = cmd.group
+            return (
+              <div key={cmd.id}>
+                {header && <div className="palette-group">{header}</div>}
+                <div
+                  className={`palette-item${i === active ? ' active' : ''}${cmd.disabled ? ' disabled' : ''}`}
+                  onMouseEnter={() => setActive(i)}
+                  onClick={() => runAt(i)}
+                >
+                  <span>{cmd.title}</span>
+                  {cmd.slash && <span className="palette-slash">{cmd.slash}</span>}
+                </div>
+              </div>
+            )
+          })}
+        </div>
+        <div className="palette-hint">↑↓ navigate · Enter run · Esc close</div>
+      </div>
+    </div>
+  )
+}
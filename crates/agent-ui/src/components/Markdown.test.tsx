@@ -0,0 +1,28 @@
+import { describe, it, expect } from 'vitest'
+import { render, screen } from '@testing-library/react'
+import { Markdown, tokenize } from './Markdown'
+
+describe('Markdown', () => {
+  it('renders pipe tables with their alignment', () => {
+    render(<Markdown>{'| a | b |\n|:--|--:|\n| 1 | 2 |'}</Markdown>)
+    expect(screen.getAllByRole('row')).toHaveLength(2)
+    expect(screen.getByRole('cell', { name: '2' })).toHaveStyle({ textAlign: 'right' })
+  })
+
+  it('leaves paragraphs without a separator row alone', () => {
+    render(<Markdown>{'a | b\nc | d'}</Markdown>)
+    expect(screen.queryByRole('table')).toBeNull()
+  })
+})
+
+describe('tokenize', () => {
+  it('classifies keywords, strings and comments', () => {
+    const kinds = tokenize('let s = "hi" // note', 'rust').map(t => t.kind)
+    expect(kinds).toEqual(['kw', 'text', 'str', 'text', 'com'])
+  })
+
+  it('only treats # as a comment in hash-comment languages', () => {
+    expect(tokenize('# hi', 'bash')[0].kind).toBe('com')
+    expect(tokenize('# hi', 'rust')[0].kind).toBe('text')
+  })
+})
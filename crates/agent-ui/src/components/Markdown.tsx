@@ -0,0 +1,119 @@
+import { useState, ReactNode, isValidElement, Children } from 'react'
+import ReactMarkdown, { Components } from 'react-markdown'
+import remarkGfm from 'remark-gfm'
+
+// ── Syntax highlighting ────────────────────────────────────────────────
+// A small language-agnostic tokenizer: comments, strings, numbers and a
+// shared keyword set cover the shells and languages the agent emits most.
+
+const KEYWORDS = new Set([
+  // Rust
+  'as', 'async', 'await', 'break', 'const', 'continue', 'crate', 'else', 'enum', 'extern',
+  'false', 'fn', 'for', 'if', 'impl', 'in', 'let', 'loop', 'match', 'mod', 'move', 'mut',
+  'pub', 'ref', 'return', 'self', 'Self', 'static', 'struct', 'super', 'trait', 'true',
+  'type', 'unsafe', 'use', 'where', 'while', 'dyn',
+  // TypeScript / JavaScript
+  'class', 'default', 'export', 'extends', 'from', 'function', 'import', 'interface',
+  'new', 'null', 'of', 'this', 'throw', 'try', 'catch', 'typeof', 'undefined', 'var',
+  // Python
+  'def', 'elif', 'except', 'finally', 'is', 'lambda', 'None', 'not', 'and', 'or', 'pass',
+  'raise', 'True', 'False', 'with', 'yield',
+  // Shell
+  'do', 'done', 'esac', 'fi', 'then', 'case', 'echo', 'local',
+])
+
+const HASH_COMMENT_LANGS = new Set(['sh', 'bash', 'zsh', 'shell', 'fish', 'python', 'py', 'toml', 'yaml', 'yml', 'ruby', 'rb'])
+
+export type TokenKind = 'kw' | 'str' | 'com' | 'num' | 'text'
+export interface Token { kind: TokenKind; text: string }
+
+export function tokenize(code: string, lang?: string): Token[] {
+  const hashComments = lang ? HASH_COMMENT_LANGS.has(lang) : false
+  const re = new RegExp(
+    [
+      '(\\/\\/[^\\n]*|\\/\\*[\\s\\S]*?\\*\\/' + (hashComments ? '|#[^\\n]*' : '') + ')',
+      '("(?:[^"\\\\\\n]|\\\\.)*"|\'(?:[^\'\\\\\\n]|\\\\.)*\'|`(?:[^`\\\\]|\\\\.)*`)',
+      '(\\b\\d[\\d_]*(?:\\.\\d+)?\\b)',
+      '([A-Za-z_][A-Za-z0-9_]*)',
+    ].join('|'),
+    'g',
+  )
+
+  const tokens: Token[] = []
+  const push = (kind: TokenKind, text: string) => {
+    const last = tokens[tokens.length - 1]
+    if (last && last.kind === kind && kind === 'text') last.text += text
+    else tokens.push({ kind, text })
+  }
+
+  let pos = 0
+  for (const m of code.matchAll(re)) {
+    const idx = m.index ?? 0
+    if (idx > pos) push('text', code.slice(pos, idx))
+    if (m[1]) push('com', m[1])
+    else if (m[2]) push('str', m[2])
+    else if (m[3]) push('num', m[3])
+    else push(KEYWORDS.has(m[4]) ? 'kw' : 'text', m[4])
+    pos = idx + m[0].length
+  }
+  if (pos < code.length) push('text', code.slice(pos))
+  return tokens
+}
+
+// ── Code blocks ────────────────────────────────────────────────────────
+
+function textOf(node: ReactNode): string {
+  if (typeof node === 'string' || typeof node === 'number') return String(node)
+  if (Array.isArray(node)) return node.map(textOf).join('')
+  if (isValidElement<{ children?: ReactNode }>(node)) return textOf(node.props.children)
+  return ''
+}
+
+function CodeBlock({ code, lang }: { code: string; lang?: string }) {
+  const [copied, setCopied] = useState(false)
+
+  const copy = () => {
+    navigator.clipboard.writeText(code).then(() => {
+      setCopied(true)
+      setTimeout(() => setCopied(false), 1500)
+    }).catch(() => {})
+  }
+
+  return (
+    <div className="code-block">
+      <div className="code-block-bar">
+        <span>{lang ?? ''}</span>
+        <button className="code-copy-btn" onClick={copy}>{copied ? 'Copied' : 'Copy'}</button>
+      </div>
+      <pre><code>
+        {tokenize(code, lang).map((t, i) =>
+          t.kind === 'text' ? t.text : <span key={i} className={`tok-${t.kind}`}>{t.text}</span>
+        )}
+      </code></pre>
+    </div>
+  )
+}
+
+const components: Components = {
+  pre({ children }) {
+    const child = Children.toArray(children)[0]
+    const className = isValidElement<{ className?: string }>(child) ? child.props.className : undefined
+    const lang = /language-([\w-]+)/.exec(className ?? '')?.[1]
+    const code = textOf(children).replace(/\n$/, '')
+    return <CodeBlock code={code} lang={lang} />
+  },
+  a({ href, children }) {
+    return <a href={href} target="_blank" rel="noreferrer noopener">{children}</a>
+  },
+}
+
+// GitHub-flavoured markdown: tables, task lists, strikethrough and autolinks.
+const remarkPlugins = [remarkGfm]
+
+export function Markdown({ children }: { children: string }) {
+  return (
+    <div className="md">
+      <ReactMarkdown remarkPlugins={remarkPlugins} components={components}>{children}</ReactMarkdown>
+    </div>
+  )
+}
@@ -1,7 +1,7 @@
 import { useState, useEffect } from 'react'
 import { Sun, Moon, Grid, Magnet, Key, Trash2, Check, RefreshCw } from 'lucide-react'
-import { AppSettings, ApiConfig } from '../types'
-import { setAuthToken, listModels, updateProvider, type OllamaModel } from '../services/api'
+import { AppSettings, ApiConfig, ApiToolInfo, CardType } from '../types'
+import { setAuthToken, listModels, updateProvider, listTools, setToolEnabled, type OllamaModel } from '../services/api'
 
 interface Props {
   settings: AppSettings
@@ -19,6 +19,8 @@ export function SettingsModal({ settings, config, onUpdate, onClose, onResetCanv
   const [loadingModels, setLoadingModels] = useState(false)
   const [switching, setSwitching] = useState(false)
   const [switchStatus, setSwitchStatus] = useState<'idle' | 'success' | 'error'>('idle')
+  const [tools, setTools] = useState<ApiToolInfo[]>([])
+  const [toolError, setToolError] = useState<string | null>(null)
 
   useEffect(() => { setToken(settings.authToken) }, [settings.authToken])
 
@@ -31,6 +33,20 @@ export function SettingsModal({ settings, config, onUpdate, onClose, onResetCanv
       .finally(() => setLoadingModels(false))
   }, [])
 
+  useEffect(() => {
+    listTools().then(setTools).catch(() => setTools([]))
+  }, [])
+
+  const toggleTool = async (tool: ApiToolInfo) => {
+    setToolError(null)
+    try {
+      const updated = await setToolEnabled(tool.id, !tool.enabled)
+      setTools(prev => prev.map(t => (t.id === updated.id ? updated : t)))
+    } catch (e) {
+      setToolError(e instanceof Error ? e.message : String(e))
+    }
+  }
+
   const refreshModels = () => {
     setLoadingModels(true)
     listModels()
@@ -184,6 +200,24 @@ export function SettingsModal({ settings, config, onUpdate, onClose, onResetCanv
             </div>
           </div>
 
+          {/* Tools */}
+          {tools.length > 0 && (
+            <div className="settings-section">
+              <h3>Tools</h3>
+              <div style={{ maxHeight: 220, overflowY: 'auto' }}>
+                {tools.map(t => (
+                  <Row key={t.id} label={<span title={t.description} style={{ fontFamily: 'monospace', fontSize: 12 }}>{t.name}</span>}>
+                    <Toggle on={t.enabled} onToggle={() => toggleTool(t)} />
+                  </Row>
+                ))}
+              </div>
+              {toolError && <div style={{ fontSize: 11, color: 'var(--error)', marginTop: 6 }}>{toolError}</div>}
+              <div style={{ fontSize: 11, color: 'var(--text-muted)', marginTop: 6 }}>
+                Disabled tools are never offered to the model, in any session. Saved in the server config.
+              </div>
+            </div>
+          )}
+
           {/* Appearance */}
           <div className="settings-section">
             <h3>Appearance</h3>
@@ -215,6 +249,35 @@ export function SettingsModal({ settings, config, onUpdate, onClose, onResetCanv
                 </button>
               </div>
             </Row>
+            <Row label="Chat font size">
+              <select
+                className="setting-input"
+                style={{ width: 90 }}
+                value={settings.fontSize}
+                onChange={e => onUpdate({ fontSize: Number(e.target.value) })}
+              >
+                {[11, 12, 13, 14, 15, 16, 18].map(n => <option key={n} value={n}>{n}px</option>)}
+              </select>
+            </Row>
+            <Row label="Default view on empty canvas">
+              <select
+                className="setting-input"
+                style={{ width: 140 }}
+                value={settings.defaultView}
+                onChange={e => onUpdate({ defaultView: e.target.value as CardType | '' })}
+              >
+                <option value="">None</option>
+                {Object.values(CardType).filter(t => t !== CardType.ISLAND).map(t => (
+                  <option key={t} value={t}>{t.charAt(0) + t.slice(1).toLowerCase()}</option>
+                ))}
+              </select>
+            </Row>
+            <Row label="Collapse tool calls in chat">
+              <Toggle on={settings.collapsedToolCards} onToggle={() => onUpdate({ collapsedToolCards: !settings.collapsedToolCards })} />
+            </Row>
+            <div style={{ fontSize: 11, color: 'var(--text-muted)', marginTop: 6 }}>
+              Appearance is saved on the server and follows you across browsers.
+            </div>
           </div>
 
           {/* Canvas */}
@@ -1,6 +1,6 @@
 import {
   MessageSquare, History, StickyNote, BarChart2,
-  Terminal, BookOpen, GitBranch, Puzzle, Settings,
+  Terminal, BookOpen, GitBranch, Puzzle, FileDiff, ScrollText, Settings,
 } from 'lucide-react'
 import { CardType } from '../types'
 
@@ -19,6 +19,8 @@ const ITEMS: { type: CardType; icon: React.ReactNode; label: string }[] = [
   { type: CardType.SKILLS,    icon: <BookOpen size={16} />,      label: 'Skills' },
   { type: CardType.CONTEXT,   icon: <GitBranch size={16} />,     label: 'Context' },
   { type: CardType.PLUGINS,   icon: <Puzzle size={16} />,        label: 'Plugins' },
+  { type: CardType.CHANGES,   icon: <FileDiff size={16} />,      label: 'Changes' },
+  { type: CardType.LOGS,      icon: <ScrollText size={16} />,    label: 'Logs' },
 ]
 
 export function Sidebar({ connStatus, onAddCard, onSettings }: Props) {
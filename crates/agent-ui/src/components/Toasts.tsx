@@ -0,0 +1,50 @@
+import { useState, useEffect } from 'react'
+import { ApiNotification } from '../types'
+import { streamNotifications } from '../services/api'
+
+/** Toasts close on their own after this long; errors stay until dismissed. */
+const DISMISS_MS = 8000
+const MAX_TOASTS = 5
+const RECONNECT_MS = 5000
+
+/** Shows notifications the agent sends with `notify_user`. */
+export function Toasts() {
+  const [toasts, setToasts] = useState<ApiNotification[]>([])
+
+  const dismiss = (seq: number) => setToasts(prev => prev.filter(t => t.seq !== seq))
+
+  useEffect(() => {
+    const controller = new AbortController()
+    const timers = new Set<ReturnType<typeof setTimeout>>()
+    const follow = () => {
+      streamNotifications(n => {
+        setToasts(prev => [...prev.filter(t => t.seq !== n.seq), n].slice(-MAX_TOASTS))
+        if (n.level !== 'error') {
+          const timer = setTimeout(() => { timers.delete(timer); dismiss(n.seq) }, DISMISS_MS)
+          timers.add(timer)
+        }
+      }, controller.signal)
+        .catch(() => {})
+        .finally(() => {
+          if (!controller.signal.aborted) timers.add(setTimeout(follow, RECONNECT_MS))
+        })
+    }
+    follow()
+    return () => { controller.abort(); timers.forEach(clearTimeout) }
+  }, [])
+
+  if (toasts.length === 0) return null
+  return (
+    <div className="toasts" role="status" aria-live="polite">
+      {toasts.map(t => (
+        <div key={t.seq} className={`toast ${t.level}`}>
+          <div className="toast-body">
+            <strong>{t.title}</strong>
+            {t.message && <p>{t.message}</p>}
+          </div>
+          <button className="toast-close" aria-label="Dismiss" onClick={() => dismiss(t.seq)}>×</button>
+        </div>
+      ))}
+    </div>
+  )
+}
@@ -1,7 +1,7 @@
 import { useState } from 'react'
 import {
   MessageSquare, StickyNote, BarChart2, Terminal,
-  BookOpen, GitBranch, Puzzle, History, BoxSelect, Trash2, FolderPlus,
+  BookOpen, GitBranch, Puzzle, FileDiff, ScrollText, History, BoxSelect, Trash2, FolderPlus,
 } from 'lucide-react'
 import { CardType } from '../types'
 
@@ -24,6 +24,8 @@ const CARD_BTNS: { type: CardType; icon: React.ReactNode; label: string }[] = [
   { type: CardType.SKILLS,    icon: <BookOpen size={15} />,      label: 'Skills' },
   { type: CardType.CONTEXT,   icon: <GitBranch size={15} />,     label: 'Context' },
   { type: CardType.PLUGINS,   icon: <Puzzle size={15} />,        label: 'Plugins' },
+  { type: CardType.CHANGES,   icon: <FileDiff size={15} />,      label: 'Changes' },
+  { type: CardType.LOGS,      icon: <ScrollText size={15} />,    label: 'Logs' },
 ]
 
 export function Toolbar({ onAddCard, onOmniSubmit, isSelectionMode, onToggleSelection, selectedCount, onDeleteSelected, onGroupSelected }: Props) {
@@ -1,25 +1,57 @@
-import { useState, useEffect } from 'react'
-import ReactMarkdown from 'react-markdown'
-import { ApiAnalyticsSummary } from '../../types'
-import { getAnalyticsSummary, getAnalyticsReport } from '../../services/api'
+import { useState, useEffect, useCallback } from 'react'
+import { Markdown } from '../Markdown'
+import { ApiAnalyticsSummary, ApiTimeseriesPoint, ApiGoalStatus } from '../../types'
+import { getAnalyticsSummary, getAnalyticsReport, getAnalyticsTimeseries, getAnalyticsGoals, downloadAnalyticsReport } from '../../services/api'
+
+const REFRESH_MS = 60_000
+const RANGES = [7, 30, 90] as const
+type Range = typeof RANGES[number]
+
+const GOAL_BADGE: Record<ApiGoalStatus['state'], string> = {
+  met: 'badge-green', behind: 'badge-gray', missed: 'badge-red',
+}
 
 export function AnalyticsCard() {
   const [summary, setSummary] = useState<ApiAnalyticsSummary | null>(null)
+  const [points, setPoints] = useState<ApiTimeseriesPoint[]>([])
+  const [goals, setGoals] = useState<ApiGoalStatus[]>([])
+  const [range, setRange] = useState<Range>(30)
   const [report, setReport] = useState<string | null>(null)
   const [period, setPeriod] = useState<'week' | 'month'>('week')
   const [loading, setLoading] = useState(true)
+  const [updatedAt, setUpdatedAt] = useState<Date | null>(null)
 
-  useEffect(() => {
-    setLoading(true)
-    Promise.all([
+  const refresh = useCallback(() => {
+    return Promise.all([
       getAnalyticsSummary(),
-      getAnalyticsReport('week'),
-    ]).then(([s, r]) => {
+      getAnalyticsTimeseries(range),
+      getAnalyticsGoals().catch(() => []),
+    ]).then(([s, ts, g]) => {
       setSummary(s)
-      setReport(r)
-    }).catch(() => {}).finally(() => setLoading(false))
+      setPoints(ts.points)
+      setGoals(g)
+      setUpdatedAt(new Date())
+    }).catch(() => {})
+  }, [range])
+
+  useEffect(() => {
+    getAnalyticsReport('week').then(setReport).catch(() => {})
   }, [])
 
+  // Refresh on range change, then periodically while the tab is visible.
+  useEffect(() => {
+    refresh().finally(() => setLoading(false))
+    const t = setInterval(() => {
+      if (document.visibilityState === 'visible') refresh()
+    }, REFRESH_MS)
+    const onVisible = () => { if (document.visibilityState === 'visible') refresh() }
+    document.addEventListener('visibilitychange', onVisible)
+    return () => {
+      clearInterval(t)
+      document.removeEventListener('visibilitychange', onVisible)
+    }
+  }, [refresh])
+
   const loadReport = (p: 'week' | 'month') => {
     setPeriod(p)
     setReport(null)
@@ -33,7 +65,10 @@ export function AnalyticsCard() {
     ? (() => { const m = Math.floor(summary.average_session_duration_secs! / 60); const h = Math.floor(m / 60); return h > 0 ? `${h}h ${m % 60}m` : `${m}m` })()
     : '—'
 
-  const maxCount = summary.top_tools[0]?.[1] ?? 1
+  const totalCalls = points.reduce((n, p) => n + p.tool_calls, 0)
+  const totalErrors = points.reduce((n, p) => n + p.tool_errors, 0)
+  const totalUp = points.reduce((n, p) => n + p.thumbs_up, 0)
+  const totalRated = totalUp + points.reduce((n, p) => n + p.thumbs_down, 0)
 
   return (
     <div className="card-inner">
@@ -61,21 +96,87 @@ export function AnalyticsCard() {
               summary.today.active_time,
               `${summary.today.tool_calls} tools`,
               ...(summary.today.tool_errors > 0 ? [`${summary.today.tool_errors} errors`] : []),
-            ].map(t => <span key={t} className={`badge ${t.includes('errors') ? 'badge-red' : 'badge-gray'}`}>{t}</span>)}
+              ...(summary.today.quota_denials > 0 ? [`${summary.today.quota_denials} over quota`] : []),
+              ...(summary.today.thumbs_up + summary.today.thumbs_down > 0
+                ? [`${summary.today.thumbs_up} 👍 / ${summary.today.thumbs_down} 👎`] : []),
+            ].map(t => <span key={t} className={`badge ${/errors|quota/.test(t) ? 'badge-red' : 'badge-gray'}`}>{t}</span>)}
+          </div>
+        </>
+      )}
+
+      {goals.length > 0 && (
+        <>
+          <div className="section-title">Goals</div>
+          <div style={{ display: 'flex', flexDirection: 'column', gap: 6, marginBottom: 10 }}>
+            {goals.map(g => (
+              <div key={g.name} title={`${g.period_start} – ${g.period_end}`} style={{ fontSize: 12 }}>
+                <div style={{ display: 'flex', alignItems: 'center', gap: 6 }}>
+                  <span className={`badge ${GOAL_BADGE[g.state]}`}>{g.state}</span>
+                  <span style={{ flex: 1 }}>{g.name}</span>
+                  <span style={{ color: 'var(--text-muted)' }}>
+                    {formatGoalValue(g)} / {g.target} this {g.period}
+                  </span>
+                </div>
+                <div style={{ height: 4, marginTop: 3, background: 'var(--surface2)', borderRadius: 2 }}>
+                  <div style={{
+                    width: `${Math.round(g.progress * 100)}%`, height: '100%', borderRadius: 2,
+                    background: g.state === 'missed' ? 'var(--error)' : g.state === 'met' ? 'var(--success)' : 'var(--accent)',
+                  }} />
+                </div>
+              </div>
+            ))}
           </div>
         </>
       )}
 
+      <div style={{ display: 'flex', alignItems: 'center', justifyContent: 'space-between' }}>
+        <div className="section-title" style={{ margin: 0 }}>Trends</div>
+        <div className="tab-row">
+          {RANGES.map(r => (
+            <button key={r} className={`tab-btn${range === r ? ' active' : ''}`} onClick={() => setRange(r)}>{r}d</button>
+          ))}
+        </div>
+      </div>
+      <Chart
+        label="Sessions / day"
+        total={points.reduce((n, p) => n + p.sessions, 0).toLocaleString()}
+        points={points}
+        value={p => p.sessions}
+        color="var(--accent)"
+      />
+      <Chart
+        label="Tokens / day (est.)"
+        total={points.reduce((n, p) => n + p.tokens, 0).toLocaleString()}
+        points={points}
+        value={p => p.tokens}
+        color="var(--accent2)"
+      />
+      <Chart
+        label="Tool error rate"
+        total={totalCalls > 0 ? `${((totalErrors / totalCalls) * 100).toFixed(1)}%` : '—'}
+        points={points}
+        value={p => p.error_rate}
+        color="var(--error)"
+        max={1}
+      />
+      {totalRated > 0 && (
+        <Chart
+          label={`Answers rated 👍 (${totalRated} rated)`}
+          total={`${((totalUp / totalRated) * 100).toFixed(0)}%`}
+          points={points}
+          value={p => p.approval_rate ?? 0}
+          color="var(--success)"
+          max={1}
+        />
+      )}
+      {updatedAt && <div className="ts-refresh">Updated {updatedAt.toLocaleTimeString()}</div>}
+
       {summary.top_tools.length > 0 && (
         <>
           <div className="section-title">Top Tools</div>
-          <div style={{ marginBottom: 10 }}>
+          <div style={{ display: 'flex', gap: 6, flexWrap: 'wrap', marginBottom: 10 }}>
             {summary.top_tools.slice(0, 8).map(([name, count]) => (
-              <div key={name} className="bar-row">
-                <span className="bar-label">{name}</span>
-                <div className="bar-track"><div className="bar-fill" style={{ width: `${(count / maxCount) * 100}%` }} /></div>
-                <span className="bar-count">{count}</span>
-              </div>
+              <span key={name} className="badge badge-gray">{name} · {count}</span>
             ))}
           </div>
         </>
@@ -86,13 +187,72 @@ export function AnalyticsCard() {
         <div className="tab-row">
           <button className={`tab-btn${period === 'week' ? ' active' : ''}`} onClick={() => loadReport('week')}>Week</button>
           <button className={`tab-btn${period === 'month' ? ' active' : ''}`} onClick={() => loadReport('month')}>Month</button>
+          <button className="tab-btn" title="Download as HTML" onClick={() => downloadAnalyticsReport(period, 'html').catch(() => {})}>HTML</button>
+          <button className="tab-btn" title="Download as PDF" onClick={() => downloadAnalyticsReport(period, 'pdf').catch(() => {})}>PDF</button>
         </div>
       </div>
       <div style={{ marginTop: 8, background: 'var(--bg)', border: '1px solid var(--border)', borderRadius: 7, padding: '10px 12px', fontSize: 12 }}>
         {report
-          ? <div className="md"><ReactMarkdown>{report}</ReactMarkdown></div>
+          ? <Markdown>{report}</Markdown>
           : <span style={{ color: 'var(--text-muted)' }}>Loading…</span>}
       </div>
     </div>
   )
 }
+
+/** Goal value with the metric's unit, matching the server's formatting. */
+function formatGoalValue(g: ApiGoalStatus): string {
+  const n = Number.isInteger(g.value) ? String(g.value) : g.value.toFixed(1)
+  if (g.metric === 'tool_error_rate') return `${n}%`
+  if (g.metric === 'active_hours') return `${n}h`
+  return n
+}
+
+// ── Time-series chart ──────────────────────────────────────────────────
+// Area + line over a fixed 100x40 viewBox, stretched to the card width.
+const W = 100
+const H = 40
+
+function Chart({ label, total, points, value, color, max }: {
+  label: string
+  total: string
+  points: ApiTimeseriesPoint[]
+  value: (p: ApiTimeseriesPoint) => number
+  color: string
+  max?: number
+}) {
+  const values = points.map(value)
+  const top = max ?? Math.max(1, ...values)
+  const step = values.length > 1 ? W / (values.length - 1) : W
+  const coords = values.map((v, i) => `${(i * step).toFixed(2)},${(H - (v / top) * (H - 2)).toFixed(2)}`)
+  const line = coords.join(' ')
+  const area = coords.length ? `0,${H} ${line} ${W},${H}` : ''
+
+  return (
+    <div className="ts-chart">
+      <div className="ts-chart-head">
+        <span>{label}</span>
+        <strong>{total}</strong>
+      </div>
+      <svg viewBox={`0 0 ${W} ${H}`} preserveAspectRatio="none">
+        {points.length > 0 && (
+          <>
+            <polygon points={area} fill={color} fillOpacity={0.15} />
+            <polyline points={line} fill="none" stroke={color} strokeWidth={1.5} vectorEffect="non-scaling-stroke" />
+          </>
+        )}
+        {points.map((p, i) => (
+          <rect key={p.date} x={i * step - step / 2} y={0} width={step} height={H} fill="transparent">
+            <title>{`${p.date}: ${max === 1 ? `${(value(p) * 100).toFixed(1)}%` : value(p).toLocaleString()}`}</title>
+          </rect>
+        ))}
+      </svg>
+      {points.length > 0 && (
+        <div className="ts-chart-axis">
+          <span>{points[0].date}</span>
+          <span>{points[points.length - 1].date}</span>
+        </div>
+      )}
+    </div>
+  )
+}
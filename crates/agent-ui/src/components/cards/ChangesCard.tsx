@@ -0,0 +1,106 @@
+import { useState, useEffect } from 'react'
+import { ApiSession, ApiFileDiff } from '../../types'
+import { listSessions, getSessionChanges, revertSessionChanges, acceptSessionChanges } from '../../services/api'
+
+const STATUS_BADGE: Record<ApiFileDiff['status'], string> = {
+  created: 'badge-green', modified: 'badge-blue', deleted: 'badge-red', unchanged: 'badge-gray',
+}
+
+const btnStyle: React.CSSProperties = {
+  background: 'var(--surface2)', border: '1px solid var(--border)', color: 'var(--text)',
+  padding: '3px 10px', borderRadius: 6, cursor: 'pointer', fontSize: 11,
+}
+
+function lineColor(line: string): string | undefined {
+  if (line.startsWith('+++') || line.startsWith('---')) return 'var(--text-muted)'
+  if (line.startsWith('+')) return 'var(--success)'
+  if (line.startsWith('-')) return 'var(--error)'
+  if (line.startsWith('@')) return 'var(--accent)'
+  return undefined
+}
+
+/** Files the agent modified in a session, as diffs against their snapshots, with accept/revert. */
+export function ChangesCard({ sessionId }: { sessionId?: string }) {
+  const [sessions, setSessions] = useState<ApiSession[]>([])
+  const [selected, setSelected] = useState(sessionId ?? '')
+  const [changes, setChanges] = useState<ApiFileDiff[]>([])
+  const [expanded, setExpanded] = useState<Set<string>>(new Set())
+  const [loading, setLoading] = useState(false)
+  const [error, setError] = useState('')
+
+  useEffect(() => {
+    listSessions().then(s => {
+      setSessions(s)
+      if (s.length > 0 && !selected) setSelected(s[0].id)
+    }).catch(() => {})
+  }, [])
+
+  const load = () => {
+    if (!selected) return
+    setLoading(true)
+    getSessionChanges(selected)
+      .then(setChanges)
+      .catch(() => setChanges([]))
+      .finally(() => setLoading(false))
+  }
+
+  useEffect(load, [selected])
+
+  const resolve = (action: 'accept' | 'revert', path?: string) => {
+    setError('')
+    const call = action === 'accept' ? acceptSessionChanges : revertSessionChanges
+    call(selected, path).then(load).catch(e => setError(String(e)))
+  }
+
+  const toggle = (path: string) => setExpanded(prev => {
+    const next = new Set(prev)
+    if (next.has(path)) next.delete(path); else next.add(path)
+    return next
+  })
+
+  return (
+    <div className="card-inner" style={{ display: 'flex', flexDirection: 'column', gap: 8 }}>
+      <div style={{ display: 'flex', gap: 6 }}>
+        <select
+          value={selected}
+          onChange={e => setSelected(e.target.value)}
+          style={{ flex: 1, background: 'var(--bg)', border: '1px solid var(--border)', color: 'var(--text)', padding: '5px 8px', borderRadius: 6, fontSize: 12 }}
+        >
+          {sessions.map(s => <option key={s.id} value={s.id}>{s.name}</option>)}
+        </select>
+        <button style={btnStyle} onClick={load} title="Refresh">↺</button>
+        <button style={btnStyle} disabled={changes.length === 0} onClick={() => resolve('accept')}>Accept all</button>
+        <button style={btnStyle} disabled={changes.length === 0} onClick={() => resolve('revert')}>Revert all</button>
+      </div>
+
+      {error && <span style={{ color: 'var(--error)', fontSize: 12 }}>{error}</span>}
+
+      <div style={{ flex: 1, overflowY: 'auto', display: 'flex', flexDirection: 'column', gap: 8 }}>
+        {loading && <span style={{ color: 'var(--text-muted)', fontSize: 12 }}>Loading…</span>}
+        {changes.map(c => (
+          <div key={c.path} style={{ border: '1px solid var(--border)', borderRadius: 8, background: 'var(--bg)' }}>
+            <div style={{ display: 'flex', alignItems: 'center', gap: 6, padding: '6px 8px' }}>
+              <span className={`badge ${STATUS_BADGE[c.status]}`}>{c.status}</span>
+              <span
+                onClick={() => toggle(c.path)}
+                style={{ flex: 1, fontFamily: 'monospace', fontSize: 12, wordBreak: 'break-all', cursor: 'pointer' }}
+              >{c.path}</span>
+              <button style={btnStyle} onClick={() => resolve('accept', c.path)}>Accept</button>
+              <button style={btnStyle} onClick={() => resolve('revert', c.path)}>Revert</button>
+            </div>
+            {expanded.has(c.path) && c.diff && (
+              <pre style={{ margin: 0, padding: '6px 8px', borderTop: '1px solid var(--border)', fontSize: 11, overflowX: 'auto' }}>
+                {c.diff.split('\n').map((line, i) => (
+                  <div key={i} style={{ color: lineColor(line) }}>{line || ' '}</div>
+                ))}
+              </pre>
+            )}
+          </div>
+        ))}
+        {!loading && changes.length === 0 && (
+          <span style={{ color: 'var(--text-muted)', fontSize: 12 }}>No file changes in this session.</span>
+        )}
+      </div>
+    </div>
+  )
+}
@@ -0,0 +1,13 @@
+import { describe, it, expect } from 'vitest'
+import { speechText } from './ChatCard'
+
+describe('speechText', () => {
+  it('strips markdown and code blocks before speaking', () => {
+    const md = '## Result\n\nUse **`cargo test`** — see [docs](https://x.dev).\n\n```rust\nfn main() {}\n```\n- done'
+    expect(speechText(md)).toBe('Result Use cargo test — see docs. done')
+  })
+
+  it('truncates to the server limit', () => {
+    expect(speechText('a'.repeat(5000))).toHaveLength(4096)
+  })
+})
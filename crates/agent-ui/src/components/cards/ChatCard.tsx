@@ -1,56 +1,114 @@
-import { useState, useRef, useEffect } from 'react'
-import ReactMarkdown from 'react-markdown'
-import { LocalChatMessage, ToolCallItem } from '../../types'
-import { streamChat } from '../../services/api'
+import React, { useState, useRef, useEffect } from 'react'
+import { Markdown } from '../Markdown'
+import { ChatHeader } from '../ChatHeader'
+import { ApiConfig, ApiMessage, ApiSessionUsage, ChatImage, LocalChatMessage, Rating, ToolCallItem } from '../../types'
+import {
+  streamChat, runTool, regenerateMessage, deleteMessage, getSessionMessages, uploadFiles, StreamEvent,
+  transcribeAudio, synthesizeSpeech, getSessionUsage, setMessageFeedback,
+} from '../../services/api'
 
 interface Props {
   history: LocalChatMessage[]
   sessionId?: string
+  /** Message to start the input with (omnibar, "Ask agent"); sent when the user sends it. */
+  draft?: string
+  collapseToolCards?: boolean
   onHistoryUpdate: (history: LocalChatMessage[]) => void
+  /** Which voice features the server has configured; hidden when absent. */
+  audio?: ApiConfig['audio']
 }
 
-export function ChatCard({ history, onHistoryUpdate }: Props) {
-  const [input, setInput] = useState('')
+/** The server rejects longer speech requests. */
+const MAX_SPEECH_CHARS = 4096
+
+export function ChatCard({ history, sessionId, draft, collapseToolCards = true, onHistoryUpdate, audio }: Props) {
+  const [input, setInput] = useState(draft ?? '')
   const [streaming, setStreaming] = useState(false)
   const [streamBuf, setStreamBuf] = useState('')
+  const [liveTools, setLiveTools] = useState<ToolCallItem[]>([])
   const [localHistory, setLocalHistory] = useState<LocalChatMessage[]>(history)
+  const [editing, setEditing] = useState<{ index: number; text: string } | null>(null)
+  const [dragging, setDragging] = useState(false)
+  const [uploading, setUploading] = useState(false)
+  const [uploadError, setUploadError] = useState<string | null>(null)
+  const [attachments, setAttachments] = useState<ChatImage[]>([])
+  const [recording, setRecording] = useState(false)
+  const [transcribing, setTranscribing] = useState(false)
+  const [speakingIndex, setSpeakingIndex] = useState<number | null>(null)
+  const [usage, setUsage] = useState<ApiSessionUsage | null>(null)
   const bottomRef = useRef<HTMLDivElement>(null)
+  const recorderRef = useRef<MediaRecorder | null>(null)
+  const playerRef = useRef<HTMLAudioElement | null>(null)
+  const speechRequest = useRef(0)
 
   useEffect(() => { setLocalHistory(history) }, [history])
 
+  useEffect(() => {
+    setUsage(null)
+    if (sessionId) getSessionUsage(sessionId).then(setUsage).catch(() => {})
+  }, [sessionId])
+
+  // Release the microphone and stop playback when the card goes away.
+  useEffect(() => () => {
+    recorderRef.current?.stream.getTracks().forEach(t => t.stop())
+    playerRef.current?.pause()
+  }, [])
+
   useEffect(() => {
     bottomRef.current?.scrollIntoView({ behavior: 'smooth' })
-  }, [localHistory, streamBuf])
+  }, [localHistory, streamBuf, liveTools])
 
   const send = async () => {
     const text = input.trim()
-    if (!text || streaming) return
+    if ((!text && attachments.length === 0) || streaming) return
     setInput('')
+    setAttachments([])
 
-    const userMsg: LocalChatMessage = { role: 'user', content: text }
-    const updated = [...localHistory, userMsg]
+    const userMsg: LocalChatMessage = {
+      role: 'user',
+      content: text,
+      images: attachments.length ? attachments : undefined,
+    }
+    await runTurn([...localHistory, userMsg])
+  }
+
+  const runTurn = async (
+    updated: LocalChatMessage[],
+    start?: (onEvent: (e: StreamEvent) => void) => Promise<void>,
+  ) => {
     setLocalHistory(updated)
     setStreaming(true)
     setStreamBuf('')
+    setLiveTools([])
 
-    const apiMessages = updated.map(m => ({ role: m.role, content: m.content }))
+    const apiMessages = updated.map(m => ({ role: m.role, content: m.content, images: m.images }))
     let finalContent = ''
     const pendingTools: ToolCallItem[] = []
 
-    await streamChat(apiMessages, event => {
+    const onEvent = (event: StreamEvent) => {
       if (event.type === 'token') {
         finalContent += event.content
         setStreamBuf(finalContent)
       } else if (event.type === 'tool_start') {
-        pendingTools.push({ name: event.name, status: 'running' })
+        pendingTools.push({ id: event.id, name: event.name, arguments: event.arguments, status: 'running' })
+        setLiveTools([...pendingTools])
       } else if (event.type === 'tool_result') {
-        const t = pendingTools.find(p => p.status === 'running')
-        if (t) { t.status = event.isError ? 'error' : 'done'; t.output = event.content; t.isError = event.isError }
+        const t = pendingTools.find(p => p.id === event.id) ?? pendingTools.find(p => p.status === 'running')
+        if (t) {
+          t.status = event.isError ? 'error' : 'done'
+          t.output = event.content
+          t.isError = event.isError
+          t.durationMs = event.durationMs
+        }
+        setLiveTools([...pendingTools])
+      } else if (event.type === 'usage') {
+        setUsage(event.usage)
       } else if (event.type === 'error') {
-        finalContent = finalContent || `Error: ${event.message}`
+        finalContent = finalContent || `Error: ${event.message}${event.requestId ? ` (request ${event.requestId})` : ''}`
         setStreamBuf(finalContent)
       }
-    })
+    }
+    await (start ? start(onEvent) : streamChat(apiMessages, onEvent, sessionId))
 
     const assistantMsg: LocalChatMessage = {
       role: 'assistant',
@@ -61,27 +119,245 @@ export function ChatCard({ history, onHistoryUpdate }: Props) {
     setLocalHistory(final)
     onHistoryUpdate(final)
     setStreamBuf('')
+    setLiveTools([])
     setStreaming(false)
+
+    // Pick up server message IDs so edit/regenerate/delete can target them.
+    if (sessionId) {
+      getSessionMessages(sessionId)
+        .then(server => setLocalHistory(prev => {
+          const next = withServerIds(prev, server)
+          onHistoryUpdate(next)
+          return next
+        }))
+        .catch(() => {})
+    }
+  }
+
+  const editAndResend = (index: number, text: string) => {
+    const target = localHistory[index]
+    setEditing(null)
+    if (!sessionId || !target.id || !text.trim()) return
+    const id = target.id
+    void runTurn(
+      [...localHistory.slice(0, index), { role: 'user', content: text, images: target.images }],
+      onEvent => regenerateMessage(sessionId, id, onEvent, text),
+    )
+  }
+
+  const regenerate = (index: number) => {
+    let k = index - 1
+    while (k >= 0 && localHistory[k].role !== 'user') k--
+    const prompt = localHistory[k]
+    if (!sessionId || !prompt?.id) return
+    const id = prompt.id
+    void runTurn(
+      [...localHistory.slice(0, k), { role: 'user', content: prompt.content, images: prompt.images }],
+      onEvent => regenerateMessage(sessionId, id, onEvent),
+    )
+  }
+
+  const remove = (index: number) => {
+    const target = localHistory[index]
+    if (!sessionId || !target.id) return
+    deleteMessage(sessionId, target.id)
+      .then(() => {
+        const next = localHistory.filter((_, i) => i !== index)
+        setLocalHistory(next)
+        onHistoryUpdate(next)
+      })
+      .catch(() => {})
+  }
+
+  // Clicking the current rating again clears it.
+  const rate = (index: number, rating: Rating) => {
+    const target = localHistory[index]
+    if (!sessionId || !target.id) return
+    const next = target.feedback === rating ? null : rating
+    setMessageFeedback(sessionId, target.id, next)
+      .then(() => setLocalHistory(prev => {
+        const updated = prev.map((m, i) => (i === index ? { ...m, feedback: next ?? undefined } : m))
+        onHistoryUpdate(updated)
+        return updated
+      }))
+      .catch(() => {})
+  }
+
+  // Re-runs resolve asynchronously, so apply them against the latest history.
+  const updateTool = (msgIndex: number, toolIndex: number, item: ToolCallItem) => {
+    setLocalHistory(prev => {
+      const next = prev.map((m, i) => i !== msgIndex || !m.toolCalls ? m : {
+        ...m,
+        toolCalls: m.toolCalls.map((t, j) => (j === toolIndex ? item : t)),
+      })
+      onHistoryUpdate(next)
+      return next
+    })
+  }
+
+  // Images are sent inline with the next message.
+  const attachImages = (files: File[]) => {
+    Promise.all(files.map(readImage))
+      .then(images => setAttachments(prev => [...prev, ...images]))
+      .catch(err => setUploadError(String(err)))
   }
 
+  // Dropped images are attached; other files are stored in the workspace and
+  // referenced by path in the draft.
+  const onDrop = (e: React.DragEvent) => {
+    e.preventDefault()
+    setDragging(false)
+    const dropped = Array.from(e.dataTransfer.files)
+    const images = dropped.filter(f => f.type.startsWith('image/'))
+    const files = dropped.filter(f => !f.type.startsWith('image/'))
+    if (images.length > 0) attachImages(images)
+    if (files.length === 0) return
+    setUploading(true)
+    setUploadError(null)
+    uploadFiles(files, sessionId)
+      .then(uploaded => {
+        const refs = uploaded.map(f => `Attached file: ${f.path}`).join('\n')
+        setInput(prev => (prev.trim() ? `${prev}\n${refs}` : refs))
+      })
+      .catch(err => setUploadError(String(err)))
+      .finally(() => setUploading(false))
+  }
+
+  // Record until the mic button is pressed again, then transcribe into the draft.
+  const toggleRecording = async () => {
+    if (recording) {
+      recorderRef.current?.stop()
+      return
+    }
+    setUploadError(null)
+    let stream: MediaStream
+    try {
+      stream = await navigator.mediaDevices.getUserMedia({ audio: true })
+    } catch (err) {
+      setUploadError(`Microphone unavailable: ${String(err)}`)
+      return
+    }
+    const recorder = new MediaRecorder(stream)
+    const chunks: Blob[] = []
+    recorder.ondataavailable = e => { if (e.data.size > 0) chunks.push(e.data) }
+    recorder.onstop = () => {
+      stream.getTracks().forEach(t => t.stop())
+      recorderRef.current = null
+      setRecording(false)
+      if (chunks.length === 0) return
+      setTranscribing(true)
+      transcribeAudio(new Blob(chunks, { type: recorder.mimeType }))
+        .then(text => { if (text) setInput(prev => (prev.trim() ? `${prev} ${text}` : text)) })
+        .catch(err => setUploadError(String(err)))
+        .finally(() => setTranscribing(false))
+    }
+    recorderRef.current = recorder
+    recorder.start()
+    setRecording(true)
+  }
+
+  const speak = (index: number) => {
+    const request = ++speechRequest.current
+    playerRef.current?.pause()
+    playerRef.current = null
+    if (speakingIndex === index) {
+      setSpeakingIndex(null)
+      return
+    }
+    const text = speechText(localHistory[index].content)
+    if (!text) return
+    setUploadError(null)
+    setSpeakingIndex(index)
+    synthesizeSpeech(text)
+      .then(blob => {
+        if (request !== speechRequest.current) return
+        const url = URL.createObjectURL(blob)
+        const player = new Audio(url)
+        const done = () => {
+          URL.revokeObjectURL(url)
+          if (playerRef.current === player) {
+            playerRef.current = null
+            setSpeakingIndex(null)
+          }
+        }
+        player.onended = done
+        player.onpause = done
+        playerRef.current = player
+        return player.play()
+      })
+      .catch(err => {
+        if (request !== speechRequest.current) return
+        setUploadError(String(err))
+        setSpeakingIndex(null)
+      })
+  }
+
+  const status = uploading ? 'Uploading…' : transcribing ? 'Transcribing…' : recording ? 'Recording… (click ● to stop)' : null
+
   return (
     <div style={{ display: 'flex', flexDirection: 'column', height: '100%' }}>
+      <ChatHeader usage={usage} />
       <div className="chat-messages">
         {localHistory.map((msg, i) => (
-          <div key={i}>
-            <div className={`chat-bubble ${msg.role}`}>
-              {msg.role === 'assistant'
-                ? <div className="md"><ReactMarkdown>{msg.content}</ReactMarkdown></div>
-                : msg.content}
-            </div>
+          <div key={i} className={`chat-msg ${msg.role}`}>
+            {editing?.index === i ? (
+              <div className="chat-edit">
+                <textarea
+                  value={editing.text}
+                  onChange={e => setEditing({ index: i, text: e.target.value })}
+                  autoFocus
+                />
+                <div className="chat-msg-actions">
+                  <button className="card-btn" onClick={() => editAndResend(i, editing.text)}>Save & resend</button>
+                  <button className="card-btn" onClick={() => setEditing(null)}>Cancel</button>
+                </div>
+              </div>
+            ) : (
+              <div className={`chat-bubble ${msg.role}`}>
+                {msg.images && msg.images.length > 0 && <ImageThumbs images={msg.images} />}
+                {msg.role === 'assistant'
+                  ? <Markdown>{msg.content}</Markdown>
+                  : msg.content}
+              </div>
+            )}
+            {!streaming && editing?.index !== i && ((sessionId && msg.id) || (audio?.speech && msg.role === 'assistant')) && (
+              <div className="chat-msg-actions">
+                {audio?.speech && msg.role === 'assistant' && msg.content && (
+                  <button
+                    className="card-btn"
+                    title={speakingIndex === i ? 'Stop' : 'Read aloud'}
+                    onClick={() => speak(i)}
+                  >{speakingIndex === i ? '■' : '🔊'}</button>
+                )}
+                {sessionId && msg.id && msg.role === 'user' && (
+                  <button className="card-btn" title="Edit" onClick={() => setEditing({ index: i, text: msg.content })}>✎</button>
+                )}
+                {sessionId && msg.id && msg.role === 'assistant' && (
+                  <button className="card-btn" title="Regenerate" onClick={() => regenerate(i)}>↻</button>
+                )}
+                {sessionId && msg.id && msg.role === 'assistant' && (['up', 'down'] as const).map(rating => (
+                  <button
+                    key={rating}
+                    className={`card-btn${msg.feedback === rating ? ' active' : ''}`}
+                    title={rating === 'up' ? 'Good answer' : 'Bad answer'}
+                    aria-pressed={msg.feedback === rating}
+                    onClick={() => rate(i, rating)}
+                  >{rating === 'up' ? '👍' : '👎'}</button>
+                ))}
+                {sessionId && msg.id && (
+                  <button className="card-btn" title="Delete" onClick={() => remove(i)}>🗑</button>
+                )}
+              </div>
+            )}
             {msg.toolCalls?.map((tc, j) => (
-              <ToolCall key={j} item={tc} />
+              <ToolCall key={j} item={tc} sessionId={sessionId} defaultOpen={!collapseToolCards} onUpdate={item => updateTool(i, j, item)} />
             ))}
           </div>
         ))}
+        {liveTools.map((tc, j) => <ToolCall key={`live-${j}`} item={tc} defaultOpen={!collapseToolCards} />)}
         {streamBuf && (
           <div className="chat-bubble assistant streaming">
-            <div className="md"><ReactMarkdown>{streamBuf}</ReactMarkdown></div>
+            <Markdown>{streamBuf}</Markdown>
           </div>
         )}
         {streaming && !streamBuf && (
@@ -90,30 +366,166 @@ export function ChatCard({ history, onHistoryUpdate }: Props) {
         <div ref={bottomRef} />
       </div>
 
-      <div className="chat-input-row">
+      {(status || uploadError) && (
+        <div className={`chat-upload-status${uploadError && !status ? ' error' : ''}`}>
+          {status ?? uploadError}
+        </div>
+      )}
+      {attachments.length > 0 && (
+        <ImageThumbs images={attachments} onRemove={i => setAttachments(prev => prev.filter((_, j) => j !== i))} />
+      )}
+      <div
+        className={`chat-input-row${dragging ? ' dragging' : ''}`}
+        onDragOver={e => { e.preventDefault(); setDragging(true) }}
+        onDragLeave={() => setDragging(false)}
+        onDrop={onDrop}
+      >
         <textarea
           value={input}
           onChange={e => setInput(e.target.value)}
           onKeyDown={e => { if (e.key === 'Enter' && !e.shiftKey) { e.preventDefault(); void send() } }}
-          placeholder="Message the agent… (Enter to send)"
+          onPaste={e => {
+            const images = Array.from(e.clipboardData.files).filter(f => f.type.startsWith('image/'))
+            if (images.length > 0) { e.preventDefault(); attachImages(images) }
+          }}
+          placeholder={dragging ? 'Drop files to attach…' : 'Message the agent… (Enter to send, drop files or paste images to attach)'}
           disabled={streaming}
         />
-        <button className="chat-send-btn" onClick={() => void send()} disabled={streaming || !input.trim()}>↑</button>
+        {audio?.transcription && (
+          <button
+            className={`chat-mic-btn${recording ? ' recording' : ''}`}
+            title={recording ? 'Stop recording' : 'Dictate'}
+            onClick={() => void toggleRecording()}
+            disabled={streaming || transcribing}
+          >{recording ? '●' : '🎤'}</button>
+        )}
+        <button className="chat-send-btn" onClick={() => void send()} disabled={streaming || (!input.trim() && attachments.length === 0)}>↑</button>
       </div>
     </div>
   )
 }
 
-function ToolCall({ item }: { item: ToolCallItem }) {
-  const [open, setOpen] = useState(false)
+/**
+ * Copy server message IDs onto local messages. The server also stores
+ * intermediate tool-call turns, so only user messages and final assistant
+ * replies are matched, aligned from the most recent message backwards.
+ */
+function withServerIds(local: LocalChatMessage[], server: ApiMessage[]): LocalChatMessage[] {
+  const visible = server.filter(m =>
+    m.role === 'user' || (m.role === 'assistant' && !m.tool_calls?.length))
+  const next = [...local]
+  for (let k = 1; k <= Math.min(next.length, visible.length); k++) {
+    const l = next[next.length - k]
+    const r = visible[visible.length - k]
+    if (l.role !== r.role) break
+    if (l.id !== r.id) next[next.length - k] = { ...l, id: r.id }
+  }
+  return next
+}
+
+function ImageThumbs({ images, onRemove }: { images: ChatImage[]; onRemove?: (index: number) => void }) {
+  return (
+    <div className="chat-thumbs">
+      {images.map((img, i) => (
+        <div key={i} className="chat-thumb">
+          <img src={`data:${img.media_type};base64,${img.data}`} alt={`Image ${i + 1}`} />
+          {onRemove && <button className="chat-thumb-remove" title="Remove" onClick={() => onRemove(i)}>×</button>}
+        </div>
+      ))}
+    </div>
+  )
+}
+
+function readImage(file: File): Promise<ChatImage> {
+  return new Promise((resolve, reject) => {
+    const reader = new FileReader()
+    reader.onload = () => {
+      const [meta, data] = String(reader.result).split(',', 2)
+      resolve({ media_type: meta.slice('data:'.length, meta.indexOf(';')), data })
+    }
+    reader.onerror = () => reject(reader.error ?? new Error(`Failed to read ${file.name}`))
+    reader.readAsDataURL(file)
+  })
+}
+
+/** Plain text for speech: drop code blocks and markdown syntax. */
+export function speechText(markdown: string): string {
+  return markdown
+    .replace(/```[\s\S]*?```/g, ' ')
+    .replace(/`([^`]*)`/g, '$1')
+    .replace(/!?\[([^\]]*)\]\([^)]*\)/g, '$1')
+    .replace(/^\s{0,3}(#{1,6}\s+|>\s?|[-*+]\s+|\d+\.\s+)/gm, '')
+    .replace(/[*_~]{1,3}([^*_~]+)[*_~]{1,3}/g, '$1')
+    .replace(/\s+/g, ' ')
+    .trim()
+    .slice(0, MAX_SPEECH_CHARS)
+}
+
+const OUTPUT_PREVIEW_CHARS = 800
+
+function formatArgs(raw?: string): string {
+  if (!raw) return ''
+  try { return JSON.stringify(JSON.parse(raw), null, 2) } catch { return raw }
+}
+
+function formatDuration(ms?: number): string {
+  if (ms == null) return ''
+  return ms < 1000 ? `${ms}ms` : `${(ms / 1000).toFixed(1)}s`
+}
+
+function ToolCall({ item, sessionId, defaultOpen = false, onUpdate }: {
+  item: ToolCallItem
+  sessionId?: string
+  defaultOpen?: boolean
+  onUpdate?: (item: ToolCallItem) => void
+}) {
+  const [open, setOpen] = useState(defaultOpen)
+  const [full, setFull] = useState(false)
+  const output = item.output ?? ''
+  const truncated = !full && output.length > OUTPUT_PREVIEW_CHARS
+
+  const rerun = () => {
+    if (!onUpdate || item.arguments == null) return
+    let args: unknown
+    try { args = JSON.parse(item.arguments || '{}') } catch { return }
+    onUpdate({ ...item, status: 'running', output: undefined, durationMs: undefined })
+    runTool(item.name, args, sessionId)
+      .then(out => onUpdate({
+        ...item,
+        status: out.is_error ? 'error' : 'done',
+        output: out.content,
+        isError: out.is_error,
+        durationMs: out.duration_ms,
+      }))
+      .catch(e => onUpdate({ ...item, status: 'error', output: String(e), isError: true }))
+  }
+
   return (
     <div className="chat-tool">
       <div className="chat-tool-hd" onClick={() => setOpen(o => !o)}>
         <span className="chat-tool-name">⚙ {item.name}</span>
-        <span className={`chat-tool-status ${item.status}`}>{item.status}</span>
+        <span className="chat-tool-meta">
+          {item.durationMs != null && <span className="chat-tool-duration">{formatDuration(item.durationMs)}</span>}
+          <span className={`chat-tool-status ${item.status}`}>{item.status}</span>
+        </span>
       </div>
-      {open && item.output && (
-        <div className="chat-tool-body">{item.output.slice(0, 800)}{item.output.length > 800 ? '…' : ''}</div>
+      {open && (
+        <>
+          {item.arguments && <pre className="chat-tool-args">{formatArgs(item.arguments)}</pre>}
+          {output && (
+            <div className="chat-tool-body">
+              {truncated ? `${output.slice(0, OUTPUT_PREVIEW_CHARS)}…` : output}
+            </div>
+          )}
+          <div className="chat-tool-actions">
+            {output.length > OUTPUT_PREVIEW_CHARS && (
+              <button className="card-btn" onClick={() => setFull(f => !f)}>{full ? 'Collapse' : 'Show full output'}</button>
+            )}
+            {onUpdate && item.arguments != null && (
+              <button className="card-btn" onClick={rerun} disabled={item.status === 'running'}>↻ Re-run</button>
+            )}
+          </div>
+        </>
       )}
     </div>
   )
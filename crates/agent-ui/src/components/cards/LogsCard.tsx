@@ -0,0 +1,88 @@
+import { useState, useEffect, useRef } from 'react'
+import { ApiLogEntry, ApiLogLevel } from '../../types'
+import { streamLogs } from '../../services/api'
+
+const MAX_ENTRIES = 1000
+const LEVELS: ApiLogLevel[] = ['trace', 'debug', 'info', 'warn', 'error']
+
+const LEVEL_COLOR: Record<ApiLogLevel, string> = {
+  trace: 'var(--text-muted)', debug: 'var(--text-muted)', info: 'var(--accent)', warn: 'var(--warn)', error: 'var(--error)',
+}
+
+const btnStyle: React.CSSProperties = {
+  background: 'var(--surface2)', border: '1px solid var(--border)', color: 'var(--text)',
+  padding: '3px 10px', borderRadius: 6, cursor: 'pointer', fontSize: 11,
+}
+
+export function LogsCard() {
+  const [level, setLevel] = useState<ApiLogLevel>('info')
+  const [entries, setEntries] = useState<ApiLogEntry[]>([])
+  const [paused, setPaused] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+  const pausedRef = useRef(paused)
+  const listRef = useRef<HTMLDivElement>(null)
+  const stickRef = useRef(true)
+
+  pausedRef.current = paused
+
+  useEffect(() => {
+    const controller = new AbortController()
+    setEntries([])
+    setError(null)
+    streamLogs(level, entry => {
+      if (pausedRef.current) return
+      setEntries(prev => {
+        if (prev.length > 0 && prev[prev.length - 1].seq >= entry.seq) return prev
+        const next = [...prev, entry]
+        return next.length > MAX_ENTRIES ? next.slice(next.length - MAX_ENTRIES) : next
+      })
+    }, controller.signal).catch(e => {
+      if (!controller.signal.aborted) setError(e instanceof Error ? e.message : String(e))
+    })
+    return () => controller.abort()
+  }, [level])
+
+  // Follow new entries unless the user has scrolled up.
+  useEffect(() => {
+    const el = listRef.current
+    if (el && stickRef.current) el.scrollTop = el.scrollHeight
+  }, [entries])
+
+  const onScroll = () => {
+    const el = listRef.current
+    if (el) stickRef.current = el.scrollHeight - el.scrollTop - el.clientHeight < 24
+  }
+
+  return (
+    <div className="card-inner" style={{ display: 'flex', flexDirection: 'column', gap: 8 }}>
+      <div style={{ display: 'flex', gap: 6 }}>
+        <select
+          value={level}
+          onChange={e => setLevel(e.target.value as ApiLogLevel)}
+          style={{ flex: 1, background: 'var(--bg)', border: '1px solid var(--border)', color: 'var(--text)', padding: '5px 8px', borderRadius: 6, fontSize: 12 }}
+        >
+          {LEVELS.map(l => <option key={l} value={l}>{l} and above</option>)}
+        </select>
+        <button style={btnStyle} onClick={() => setPaused(p => !p)}>{paused ? 'Resume' : 'Pause'}</button>
+        <button style={btnStyle} disabled={entries.length === 0} onClick={() => setEntries([])}>Clear</button>
+      </div>
+
+      {error && <span style={{ color: 'var(--error)', fontSize: 12 }}>{error}</span>}
+
+      <div ref={listRef} onScroll={onScroll} style={{ flex: 1, overflowY: 'auto', fontFamily: 'monospace', fontSize: 11 }}>
+        {entries.length === 0 && !error && <span style={{ color: 'var(--text-muted)' }}>Waiting for log events…</span>}
+        {entries.map(e => (
+          <div key={e.seq} style={{ padding: '2px 0', borderBottom: '1px solid var(--border)', whiteSpace: 'pre-wrap', wordBreak: 'break-word' }}>
+            <span style={{ color: 'var(--text-muted)' }}>{new Date(e.timestamp).toLocaleTimeString()} </span>
+            <span style={{ color: LEVEL_COLOR[e.level], fontWeight: 600 }}>{e.level.toUpperCase().padEnd(5)} </span>
+            <span style={{ color: 'var(--text-muted)' }}>{e.target} </span>
+            <span>{e.message}</span>
+            {e.fields && Object.entries(e.fields).map(([k, v]) => (
+              <span key={k} style={{ color: 'var(--text-muted)' }}> {k}={String(v)}</span>
+            ))}
+          </div>
+        ))}
+      </div>
+    </div>
+  )
+}
@@ -1,23 +1,37 @@
 import { useState, useEffect } from 'react'
-import ReactMarkdown from 'react-markdown'
+import { Markdown } from '../Markdown'
 import { ApiSession, ApiMessage } from '../../types'
-import { listSessions, getSessionMessages } from '../../services/api'
+import { listSessions, getSessionMessages, shareSession, unshareSession } from '../../services/api'
 
 export function SessionCard() {
   const [sessions, setSessions] = useState<ApiSession[]>([])
   const [selected, setSelected] = useState<string>('')
   const [messages, setMessages] = useState<ApiMessage[]>([])
   const [loading, setLoading] = useState(false)
+  const [shareUrl, setShareUrl] = useState('')
 
   useEffect(() => {
-    listSessions().then(s => {
+    listSessions({ limit: 100 }).then(s => {
       setSessions(s)
       if (s.length > 0 && !selected) setSelected(s[0].id)
     }).catch(() => {})
   }, [])
 
+  const share = () => {
+    shareSession(selected).then(({ url }) => {
+      const full = new URL(url, window.location.origin).toString()
+      setShareUrl(full)
+      navigator.clipboard?.writeText(full).catch(() => {})
+    }).catch(() => setShareUrl(''))
+  }
+
+  const unshare = () => {
+    unshareSession(selected).finally(() => setShareUrl(''))
+  }
+
   useEffect(() => {
     if (!selected) return
+    setShareUrl('')
     setLoading(true)
     getSessionMessages(selected)
       .then(setMessages)
@@ -27,15 +41,33 @@ export function SessionCard() {
 
   return (
     <div className="card-inner" style={{ display: 'flex', flexDirection: 'column', gap: 8 }}>
-      <select
-        value={selected}
-        onChange={e => setSelected(e.target.value)}
-        style={{ background: 'var(--bg)', border: '1px solid var(--border)', color: 'var(--text)', padding: '5px 8px', borderRadius: 6, fontSize: 12, width: '100%' }}
-      >
-        {sessions.map(s => (
-          <option key={s.id} value={s.id}>{s.name} ({s.message_count} msgs)</option>
-        ))}
-      </select>
+      <div style={{ display: 'flex', gap: 6 }}>
+        <select
+          value={selected}
+          onChange={e => setSelected(e.target.value)}
+          style={{ flex: 1, background: 'var(--bg)', border: '1px solid var(--border)', color: 'var(--text)', padding: '5px 8px', borderRadius: 6, fontSize: 12 }}
+        >
+          {sessions.map(s => (
+            <option key={s.id} value={s.id}>{s.name} ({s.message_count} msgs)</option>
+          ))}
+        </select>
+        <button
+          onClick={share}
+          disabled={!selected}
+          title="Create a read-only link and copy it"
+          style={{ background: 'var(--surface2)', border: '1px solid var(--border)', color: 'var(--text)', padding: '5px 10px', borderRadius: 6, cursor: 'pointer', fontSize: 12 }}
+        >Share</button>
+      </div>
+
+      {shareUrl && (
+        <div style={{ display: 'flex', gap: 6, alignItems: 'center', fontSize: 11 }}>
+          <a href={shareUrl} target="_blank" rel="noreferrer" style={{ flex: 1, wordBreak: 'break-all', color: 'var(--accent)' }}>{shareUrl}</a>
+          <button
+            onClick={unshare}
+            style={{ background: 'none', border: '1px solid var(--border)', color: 'var(--text-muted)', padding: '2px 8px', borderRadius: 6, cursor: 'pointer', fontSize: 11 }}
+          >Revoke</button>
+        </div>
+      )}
 
       <div style={{ flex: 1, overflowY: 'auto', display: 'flex', flexDirection: 'column', gap: 8 }}>
         {loading && <span style={{ color: 'var(--text-muted)', fontSize: 12 }}>Loading…</span>}
@@ -47,7 +79,7 @@ export function SessionCard() {
           }}>
             <div style={{ fontSize: 10, color: 'var(--text-muted)', textTransform: 'uppercase', marginBottom: 4, letterSpacing: '0.5px' }}>{msg.role}</div>
             {msg.role === 'assistant'
-              ? <div className="md"><ReactMarkdown>{msg.content}</ReactMarkdown></div>
+              ? <Markdown>{msg.content}</Markdown>
               : <span>{msg.content}</span>}
           </div>
         ))}
@@ -1,58 +1,62 @@
-import { useState, useEffect, useCallback } from 'react'
-import ReactMarkdown from 'react-markdown'
-import { ApiSkill } from '../../types'
+import { useState, useEffect, Fragment } from 'react'
+import { Markdown } from '../Markdown'
+import { ApiSkill, ApiSkillContent, ApiSkillSearchResults } from '../../types'
 import { listSkills, searchSkills, getSkillContent } from '../../services/api'
 
-export function SkillsCard() {
-  const [query, setQuery] = useState('')
+export function SkillsCard({ initialQuery = '' }: { initialQuery?: string }) {
+  const [query, setQuery] = useState(initialQuery)
   const [skills, setSkills] = useState<ApiSkill[]>([])
-  const [selected, setSelected] = useState<ApiSkill | null>(null)
-  const [content, setContent] = useState<string | null>(null)
+  const [results, setResults] = useState<ApiSkillSearchResults | null>(null)
+  const [selected, setSelected] = useState<string | null>(null)
+  const [content, setContent] = useState<ApiSkillContent | null>(null)
+  const [error, setError] = useState<string | null>(null)
   const [loading, setLoading] = useState(true)
 
   useEffect(() => {
     listSkills().then(setSkills).catch(() => {}).finally(() => setLoading(false))
   }, [])
 
-  const doSearch = useCallback((q: string) => {
-    if (!q.trim()) {
-      listSkills().then(setSkills).catch(() => {})
-    } else {
-      searchSkills(q).then(setSkills).catch(() => {})
-    }
-  }, [])
-
   useEffect(() => {
-    const t = setTimeout(() => doSearch(query), 280)
-    return () => clearTimeout(t)
-  }, [query, doSearch])
+    const q = query.trim()
+    if (!q) { setResults(null); return }
+    let stale = false
+    const t = setTimeout(() => {
+      searchSkills(q).then(r => { if (!stale) setResults(r) }).catch(() => {})
+    }, 280)
+    return () => { stale = true; clearTimeout(t) }
+  }, [query])
 
-  const openSkill = (skill: ApiSkill) => {
-    setSelected(skill)
+  const openSkill = (name: string) => {
+    setSelected(name)
     setContent(null)
-    getSkillContent(skill.name).then(setContent).catch(() => setContent('Failed to load skill content.'))
+    setError(null)
+    getSkillContent(name).then(setContent).catch(() => setError('Failed to load skill content.'))
   }
 
   if (selected) {
+    const skill = skills.find(s => s.name === selected)
     return (
       <div className="card-inner" style={{ display: 'flex', flexDirection: 'column' }}>
         <button
           onClick={() => { setSelected(null); setContent(null) }}
           style={{ background: 'none', border: 'none', color: 'var(--accent)', cursor: 'pointer', fontSize: 12, marginBottom: 8, textAlign: 'left', padding: 0 }}
         >← Back</button>
-        <div style={{ fontWeight: 600, marginBottom: 4 }}>{selected.name}</div>
+        <div style={{ fontWeight: 600, marginBottom: 4 }}>{selected}</div>
         <div style={{ display: 'flex', gap: 4, flexWrap: 'wrap', marginBottom: 10 }}>
-          {selected.tags.map(t => <span key={t} className="badge badge-blue">{t}</span>)}
+          {skill?.tags.map(t => <span key={t} className="badge badge-blue">{t}</span>)}
+          {content?.sub_skills.map(s => <span key={s} className="badge badge-gray">{s}</span>)}
         </div>
         <div style={{ flex: 1, overflowY: 'auto', background: 'var(--bg)', border: '1px solid var(--border)', borderRadius: 7, padding: '10px 12px', fontSize: 12 }}>
           {content
-            ? <div className="md"><ReactMarkdown>{content}</ReactMarkdown></div>
-            : <span style={{ color: 'var(--text-muted)' }}>Loading…</span>}
+            ? <Markdown>{content.content}</Markdown>
+            : <span style={{ color: 'var(--text-muted)' }}>{error ?? 'Loading…'}</span>}
         </div>
       </div>
     )
   }
 
+  const terms = query.trim().toLowerCase().split(/\s+/).filter(Boolean)
+
   return (
     <div className="card-inner" style={{ display: 'flex', flexDirection: 'column' }}>
       <input
@@ -62,20 +66,60 @@ export function SkillsCard() {
         onChange={e => setQuery(e.target.value)}
       />
       <div className="skill-list" style={{ flex: 1, overflowY: 'auto' }}>
-        {loading && <span style={{ color: 'var(--text-muted)', fontSize: 12 }}>Loading…</span>}
-        {!loading && skills.length === 0 && <span style={{ color: 'var(--text-muted)', fontSize: 12 }}>No skills found.</span>}
-        {skills.map(skill => (
-          <div key={skill.name} className="skill-item" onClick={() => openSkill(skill)}>
-            <div className="skill-name">{skill.name}</div>
-            <div className="skill-desc">{skill.description}</div>
-            {skill.tags.length > 0 && (
-              <div className="skill-tags">
-                {skill.tags.slice(0, 4).map(t => <span key={t} className="badge badge-gray">{t}</span>)}
+        {results ? (
+          <>
+            {results.results.length === 0 && <span style={{ color: 'var(--text-muted)', fontSize: 12 }}>No matches.</span>}
+            {results.results.map((r, i) => (
+              <div key={`${r.domain}-${r.sub_skill ?? ''}-${r.file ?? ''}-${i}`} className="skill-item" onClick={() => openSkill(r.domain)}>
+                <div className="skill-name">
+                  {r.domain}{r.sub_skill && <span style={{ color: 'var(--text-muted)' }}> / {r.sub_skill}</span>}
+                  <span className="badge badge-gray" style={{ marginLeft: 6 }}>{r.match_type}</span>
+                </div>
+                {r.snippet && <div className="skill-snippet"><Highlight text={r.snippet} terms={terms} /></div>}
+                {r.file && <div className="skill-file">{r.file}</div>}
               </div>
+            ))}
+            {results.truncated && (
+              <span style={{ color: 'var(--text-muted)', fontSize: 11 }}>
+                Showing {results.results.length} of {results.total_matches} matches
+              </span>
             )}
-          </div>
-        ))}
+          </>
+        ) : (
+          <>
+            {loading && <span style={{ color: 'var(--text-muted)', fontSize: 12 }}>Loading…</span>}
+            {!loading && skills.length === 0 && <span style={{ color: 'var(--text-muted)', fontSize: 12 }}>No skills found.</span>}
+            {skills.map(skill => (
+              <div key={skill.name} className="skill-item" onClick={() => openSkill(skill.name)}>
+                <div className="skill-name">{skill.name}</div>
+                <div className="skill-desc">{skill.description}</div>
+                {skill.tags.length > 0 && (
+                  <div className="skill-tags">
+                    {skill.tags.slice(0, 4).map(t => <span key={t} className="badge badge-gray">{t}</span>)}
+                  </div>
+                )}
+              </div>
+            ))}
+          </>
+        )}
       </div>
     </div>
   )
 }
+
+// ── Snippet highlighting ───────────────────────────────────────────────
+function escapeRegExp(s: string): string {
+  return s.replace(/[.*+?^${}()|[\]\\]/g, '\\$&')
+}
+
+function Highlight({ text, terms }: { text: string; terms: string[] }) {
+  if (terms.length === 0) return <>{text}</>
+  const re = new RegExp(`(${terms.map(escapeRegExp).join('|')})`, 'gi')
+  return (
+    <>
+      {text.split(re).map((part, i) =>
+        i % 2 === 1 ? <mark key={i}>{part}</mark> : <Fragment key={i}>{part}</Fragment>
+      )}
+    </>
+  )
+}
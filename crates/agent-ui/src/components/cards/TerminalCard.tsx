@@ -1,13 +1,27 @@
-import { useEffect, useRef } from 'react'
+import { useEffect, useRef, useState } from 'react'
 import '@xterm/xterm/css/xterm.css'
 import { Terminal } from '@xterm/xterm'
 import { FitAddon } from '@xterm/addon-fit'
-import { createTerminalSocket } from '../../services/api'
+import { ApiErrorAssistSession, ApiShell, ApiTerminalFailure } from '../../types'
+import { createTerminalSocket, listShells, listTerminalFailures, askAboutFailure } from '../../services/api'
 
-export function TerminalCard() {
+const FAILURE_POLL_MS = 5000
+
+interface Props {
+  onAskAgent?: (session: ApiErrorAssistSession) => void
+}
+
+export function TerminalCard({ onAskAgent }: Props) {
   const divRef = useRef<HTMLDivElement>(null)
   const termRef = useRef<Terminal | null>(null)
   const wsRef = useRef<WebSocket | null>(null)
+  const [shells, setShells] = useState<ApiShell[]>([])
+  const [shell, setShell] = useState<string>('')
+  const [active, setActive] = useState<string | null>(null)
+
+  useEffect(() => {
+    listShells().then(setShells).catch(() => {})
+  }, [])
 
   useEffect(() => {
     if (!divRef.current) return
@@ -24,22 +38,19 @@ export function TerminalCard() {
     fit.fit()
     termRef.current = term
 
-    const ws = createTerminalSocket()
+    const ws = createTerminalSocket({ shell: shell || undefined, cols: term.cols, rows: term.rows })
     ws.binaryType = 'arraybuffer'
     wsRef.current = ws
-
-    ws.onopen = () => {
-      const { cols, rows } = term
-      ws.send(JSON.stringify({ type: 'resize', cols, rows }))
-    }
+    setActive(null)
 
     ws.onmessage = e => {
       if (e.data instanceof ArrayBuffer) {
         term.write(new Uint8Array(e.data))
       } else {
         try {
-          const msg = JSON.parse(e.data as string) as { type: string; message?: string }
+          const msg = JSON.parse(e.data as string) as { type: string; message?: string; shell?: string; name?: string }
           if (msg.type === 'error') term.write(`\r\n\x1b[31mError: ${msg.message}\x1b[0m\r\n`)
+          if (msg.type === 'session_start') setActive(msg.name ?? msg.shell ?? null)
         } catch { /* ignore */ }
       }
     }
@@ -47,12 +58,10 @@ export function TerminalCard() {
     ws.onerror = () => term.write('\r\n\x1b[31mWebSocket error\x1b[0m\r\n')
     ws.onclose = () => term.write('\r\n\x1b[2mConnection closed\x1b[0m\r\n')
 
+    // Keystrokes go out as raw binary frames; JSON text frames are control only.
+    const encoder = new TextEncoder()
     term.onData(data => {
-      if (ws.readyState === WebSocket.OPEN) {
-        const bytes = new TextEncoder().encode(data)
-        const b64 = btoa(String.fromCharCode(...bytes))
-        ws.send(JSON.stringify({ type: 'input', data: b64 }))
-      }
+      if (ws.readyState === WebSocket.OPEN) ws.send(encoder.encode(data))
     })
 
     const ro = new ResizeObserver(() => {
@@ -68,7 +77,73 @@ export function TerminalCard() {
       term.dispose()
       ws.close()
     }
+  }, [shell])
+
+  return (
+    <div style={{ display: 'flex', flexDirection: 'column', height: '100%' }}>
+      {shells.length > 0 && (
+        <div className="terminal-toolbar">
+          <select
+            className="terminal-shell-select"
+            value={shell}
+            onChange={e => setShell(e.target.value)}
+            title="Shell"
+          >
+            <option value="">Default shell</option>
+            {shells.map(s => (
+              <option key={s.id} value={s.id} title={s.path}>{s.name}</option>
+            ))}
+          </select>
+          {active && <span className="terminal-shell-active">{active}</span>}
+        </div>
+      )}
+      <div ref={divRef} className="terminal-wrap" style={{ flex: 1, minHeight: 0 }} />
+      {onAskAgent && <FailureBar onAskAgent={onAskAgent} />}
+    </div>
+  )
+}
+
+// ── Failed-command affordance (opt-in via [error_assist] enabled) ──────
+function FailureBar({ onAskAgent }: { onAskAgent: (session: ApiErrorAssistSession) => void }) {
+  const [failure, setFailure] = useState<ApiTerminalFailure | null>(null)
+  const [dismissed, setDismissed] = useState<Set<string>>(new Set())
+  const [asking, setAsking] = useState(false)
+
+  useEffect(() => {
+    let stopped = false
+    const poll = () => {
+      listTerminalFailures(1).then(res => {
+        if (stopped) return
+        if (!res.enabled) { stopped = true; return }
+        setFailure(res.failures[0] ?? null)
+      }).catch(() => {})
+    }
+    poll()
+    const t = setInterval(() => { if (!stopped) poll() }, FAILURE_POLL_MS)
+    return () => { stopped = true; clearInterval(t) }
   }, [])
 
-  return <div ref={divRef} className="terminal-wrap" />
+  if (!failure || dismissed.has(failure.id)) return null
+
+  const ask = () => {
+    setAsking(true)
+    askAboutFailure(failure.session_id, failure.id)
+      .then(session => {
+        onAskAgent(session)
+        setDismissed(d => new Set(d).add(failure.id))
+      })
+      .catch(() => {})
+      .finally(() => setAsking(false))
+  }
+
+  return (
+    <div className="terminal-failure">
+      <span className="badge badge-red">exit {failure.exit_code ?? '?'}</span>
+      <code className="terminal-failure-cmd" title={failure.working_directory}>{failure.command_text}</code>
+      <button className="terminal-failure-btn" onClick={ask} disabled={asking}>
+        {asking ? 'Opening…' : 'Ask agent'}
+      </button>
+      <button className="card-btn" onClick={() => setDismissed(d => new Set(d).add(failure.id))} title="Dismiss">×</button>
+    </div>
+  )
 }
@@ -13,9 +13,25 @@ export const DEFAULT_CARD_SIZES: Record<CardType, { w: number; h: number }> = {
   [CardType.SKILLS]:    { w: 400, h: 520 },
   [CardType.CONTEXT]:   { w: 380, h: 440 },
   [CardType.PLUGINS]:   { w: 380, h: 460 },
+  [CardType.CHANGES]:   { w: 560, h: 520 },
+  [CardType.LOGS]:      { w: 600, h: 440 },
   [CardType.ISLAND]:    { w: 200, h: 60  },
 }
 
+export const CARD_LABELS: Record<CardType, string> = {
+  [CardType.CHAT]:      'Chat',
+  [CardType.SESSION]:   'Session Viewer',
+  [CardType.NOTE]:      'Note',
+  [CardType.ANALYTICS]: 'Analytics',
+  [CardType.TERMINAL]:  'Terminal',
+  [CardType.SKILLS]:    'Skills',
+  [CardType.CONTEXT]:   'Context',
+  [CardType.PLUGINS]:   'Plugins',
+  [CardType.CHANGES]:   'Changes',
+  [CardType.LOGS]:      'Logs',
+  [CardType.ISLAND]:    'Island',
+}
+
 export const CARD_COLORS: Record<CardType, string> = {
   [CardType.CHAT]:      '#4a9eff',
   [CardType.SESSION]:   '#a78bfa',
@@ -25,5 +41,7 @@ export const CARD_COLORS: Record<CardType, string> = {
   [CardType.SKILLS]:    '#f97316',
   [CardType.CONTEXT]:   '#38bdf8',
   [CardType.PLUGINS]:   '#e879f9',
+  [CardType.CHANGES]:   '#facc15',
+  [CardType.LOGS]:      '#94a3b8',
   [CardType.ISLAND]:    '#6b7280',
 }
@@ -0,0 +1,733 @@
+// Generated by scripts/gen-api-types.mjs from openapi.json. Do not edit.
+
+export interface AgentInfo {
+  /** Human-readable description. */
+  description?: string | null;
+  /** Model used for every provider in the chain. */
+  model?: string | null;
+  /** Skills whose SKILL.md is appended to the system prompt. */
+  skills?: string[];
+  /** Replaces the configured system prompt. */
+  system_prompt?: string | null;
+  /** Sampling temperature. */
+  temperature?: number | null;
+  /** Tools this agent may use; all tools when unset. */
+  tools?: string[] | null;
+  name: string;
+  source: AgentSource;
+}
+
+/** Overrides applied to every turn of a session created with this agent. */
+export interface AgentPreset {
+  /** Human-readable description. */
+  description?: string | null;
+  /** Model used for every provider in the chain. */
+  model?: string | null;
+  /** Skills whose SKILL.md is appended to the system prompt. */
+  skills?: string[];
+  /** Replaces the configured system prompt. */
+  system_prompt?: string | null;
+  /** Sampling temperature. */
+  temperature?: number | null;
+  /** Tools this agent may use; all tools when unset. */
+  tools?: string[] | null;
+}
+
+/** Where a preset is defined. */
+export type AgentSource = "config" | "api";
+
+export interface AnalyticsSummaryResponse {
+  active_days: number;
+  average_session_duration_secs?: number | null;
+  deep_work_sessions: number;
+  today?: null | DaySummaryResponse;
+  /** `[tool, calls]` pairs, most used first. */
+  top_tools: Record<string, unknown>[][];
+  total_sessions: number;
+}
+
+/** Which voice features the UI should offer. */
+export interface AudioConfigResponse {
+  speech: boolean;
+  transcription: boolean;
+}
+
+export type ChangeStatus = "created" | "modified" | "deleted" | "unchanged";
+
+export interface ChatChoice {
+  finish_reason: string;
+  index: number;
+  message: ChatMessage;
+}
+
+/**
+ * An image in a chat request: inline base64 `data` (optionally a full
+ * `data:` URL) or a `path` inside the workspace.
+ */
+export interface ChatImage {
+  data?: string | null;
+  media_type?: string | null;
+  path?: string | null;
+}
+
+export interface ChatMessage {
+  content: string;
+  /** Images sent with a user message. */
+  images?: ChatImage[];
+  role: string;
+}
+
+export interface ChatRequest {
+  messages: ChatMessage[];
+  /**
+   * Optional session ID. If provided, messages are routed to this session.
+   * If absent, the active/default session is used.
+   */
+  session_id?: string | null;
+  stream?: boolean;
+}
+
+export interface ChatResponse {
+  choices: ChatChoice[];
+  id: string;
+  /** The session ID that this response belongs to. */
+  session_id?: string | null;
+}
+
+export interface CheckpointInfo {
+  created_at: string;
+  /** Files modified since the checkpoint. */
+  files: string[];
+  id: string;
+  label?: string | null;
+  message_count: number;
+}
+
+/** The result of one check. */
+export interface ComponentHealth {
+  /** How long the check took, for checks that leave the process. */
+  latency_ms?: number | null;
+  message?: string | null;
+  status: ComponentStatus;
+}
+
+export type ComponentStatus = "skipped" | "ok" | "degraded" | "error";
+
+export interface ConfigResponse {
+  audio: AudioConfigResponse;
+  provider: ProviderConfigResponse;
+  sandbox: SandboxConfigResponse;
+  server: ServerConfigResponse;
+  session: SessionConfigResponse;
+  tools: string[];
+}
+
+export interface ContextResponse {
+  environments: EnvInfo[];
+  git?: null | GitInfo;
+  project?: null | ProjectInfo;
+  /** Configured workspaces, marked as the session selects them. */
+  workspaces: WorkspaceInfo[];
+}
+
+export interface CreateAgentRequest {
+  /** Human-readable description. */
+  description?: string | null;
+  /** Model used for every provider in the chain. */
+  model?: string | null;
+  /** Skills whose SKILL.md is appended to the system prompt. */
+  skills?: string[];
+  /** Replaces the configured system prompt. */
+  system_prompt?: string | null;
+  /** Sampling temperature. */
+  temperature?: number | null;
+  /** Tools this agent may use; all tools when unset. */
+  tools?: string[] | null;
+  name: string;
+}
+
+export interface CreateSessionRequest {
+  /** Agent preset for every turn in the session. */
+  agent?: string | null;
+  name: string;
+}
+
+export interface DaySummaryResponse {
+  active_time: string;
+  messages: number;
+  /** Tool calls refused for being over a `[tools.quotas]` limit. */
+  quota_denials: number;
+  sessions: number;
+  /** Assistant messages rated thumbs down. */
+  thumbs_down: number;
+  /** Assistant messages rated thumbs up. */
+  thumbs_up: number;
+  tool_calls: number;
+  tool_errors: number;
+}
+
+export interface EditorActionRequest {
+  /** Unsaved buffer contents; read from disk when omitted. */
+  content?: string | null;
+  /** What to do, e.g. "add error handling". */
+  instruction: string;
+  /** File path, absolute or relative to the session's primary workspace. */
+  path: string;
+  selection?: null | Selection;
+  /**
+   * Session whose workspaces the file is in; defaults to the active
+   * session.
+   */
+  session_id?: string | null;
+}
+
+export interface EditorActionResponse {
+  /** Unified diff of the file before and after the edits. */
+  diff: string;
+  edits: TextEdit[];
+  explanation: string;
+  path: string;
+}
+
+export interface EnvInfo {
+  env_type: string;
+  name: string;
+  path: string;
+  version?: string | null;
+}
+
+/** Machine-readable error codes. Each has a default HTTP status. */
+export type ErrorCode = "invalid_request" | "validation_failed" | "payload_too_large" | "unauthorized" | "forbidden" | "not_found" | "session_not_found" | "message_not_found" | "agent_not_found" | "tool_not_found" | "plugin_not_found" | "run_not_found" | "conflict" | "session_busy" | "session_error" | "provider_error" | "upstream_error" | "tool_failed" | "sandbox_error" | "config_error" | "cancelled" | "unavailable" | "internal";
+
+export interface FailedCommandInfo {
+  command_text: string;
+  ended_at?: string | null;
+  exit_code?: number | null;
+  id: string;
+  session_id: string;
+  working_directory: string;
+}
+
+export interface FailuresResponse {
+  /** Whether error assistance is enabled (`[error_assist] enabled`). */
+  enabled: boolean;
+  failures: FailedCommandInfo[];
+}
+
+/** Thumbs up or down on an assistant message. */
+export interface Feedback {
+  /** What was good or wrong about the answer. */
+  comment?: string | null;
+  rating: Rating;
+  timestamp: string;
+}
+
+export interface FeedbackRequest {
+  /** What was good or wrong about the answer. */
+  comment?: string | null;
+  rating?: null | Rating;
+}
+
+export interface FileChangeRequest {
+  /** File to revert or accept. Defaults to every changed file. */
+  path?: string | null;
+}
+
+/** A file's current contents compared with its snapshot. */
+export interface FileDiff {
+  /** Unified diff from the snapshot to the current contents. */
+  diff: string;
+  path: string;
+  status: ChangeStatus;
+}
+
+export interface ForkSessionRequest {
+  /** Name for the copy. Defaults to "<original> (fork)". */
+  name?: string | null;
+}
+
+export interface GitInfo {
+  branch?: string | null;
+  head_short?: string | null;
+  is_dirty: boolean;
+  remote?: string | null;
+  repo_root: string;
+}
+
+/** An inline image, base64-encoded. */
+export interface ImagePart {
+  /** Base64-encoded image bytes (standard alphabet, padded). */
+  data: string;
+  /** MIME type, e.g. `image/png`. */
+  media_type: string;
+}
+
+/** One log event. */
+export interface LogEntry {
+  /** Structured fields other than the message. */
+  fields?: Record<string, unknown>;
+  level: LogLevel;
+  message: string;
+  /** Increases by one per event; sent as the SSE `id`. */
+  seq: number;
+  /** Module the event came from, e.g. `agent_core::agent_loop`. */
+  target: string;
+  timestamp: string;
+}
+
+export type LogLevel = "trace" | "debug" | "info" | "warn" | "error";
+
+export interface MessageResponse {
+  content: string;
+  feedback?: null | Feedback;
+  id: string;
+  /** `system`, `user`, `assistant` or `tool`. */
+  role: string;
+  timestamp: string;
+  tool_call_id?: string | null;
+  tool_calls?: ToolCallResponse[] | null;
+  /** Content or tool call arguments were shortened (`summary` mode). */
+  truncated?: boolean;
+}
+
+export interface ModelsResponse {
+  /** Why the list is empty, when the Ollama server could not be queried. */
+  error?: string | null;
+  models: OllamaModel[];
+}
+
+/** One message for the user. */
+export interface Notification {
+  level: NotificationLevel;
+  message: string;
+  /** Increases by one per notification; sent as the SSE `id`. */
+  seq: number;
+  timestamp: string;
+  title: string;
+}
+
+/** How a notification is styled. */
+export type NotificationLevel = "info" | "success" | "warning" | "error";
+
+export interface OllamaModel {
+  modified_at?: string | null;
+  name: string;
+  size?: number | null;
+}
+
+export interface PluginHealthEntry {
+  category: string;
+  name: string;
+  status: string;
+}
+
+export interface PluginPermissionsResponse {
+  category: string;
+  key: string;
+  name: string;
+  permissions: Record<string, unknown>;
+}
+
+/** An API error, rendered as problem details. */
+export interface Problem {
+  code: ErrorCode;
+  /** What went wrong, for people. */
+  detail: string;
+  /** Structured context, e.g. `errors` listing every schema violation. */
+  details?: unknown;
+  /**
+   * ID of the failed request, as in its `x-request-id` header. Quote it
+   * when reporting a problem; the server log has the same ID.
+   */
+  request_id?: string | null;
+  status: number;
+  /** The status's reason phrase. */
+  title: string;
+  type: string;
+}
+
+export interface ProjectInfo {
+  git_branch?: string | null;
+  git_remote?: string | null;
+  name: string;
+  path: string;
+  project_type: string;
+}
+
+export interface ProviderConfigResponse {
+  api_base: string;
+  has_api_key: boolean;
+  max_tokens: number;
+  model: string;
+  temperature: number;
+  top_p: number;
+}
+
+export type Rating = "up" | "down";
+
+/** Readiness of the server and each of its dependencies. */
+export interface Readiness {
+  /** By component: `provider`, `docker`, `skills`, `disk`, `plugins`. */
+  components: Record<string, unknown>;
+  /** The worst component status (`skipped` counts as `ok`). */
+  status: ComponentStatus;
+  version: string;
+}
+
+export interface RegenerateRequest {
+  /** Replacement text for the user message. Omit to re-send it unchanged. */
+  content?: string | null;
+  stream?: boolean;
+}
+
+/** What a rollback undid. */
+export interface Rollback {
+  /** The checkpoint rolled back to. */
+  checkpoint: string;
+  /** Files that could not be restored, with the error. */
+  failed?: string[];
+  files_restored: string[];
+  messages_removed: number;
+}
+
+export interface RollbackRequest {
+  /** Checkpoint to roll back to. Defaults to undoing the last turn. */
+  checkpoint?: string | null;
+  /** Also restore files tools modified since the checkpoint. */
+  restore_files?: boolean;
+}
+
+export interface RunRequest {
+  /** Agent preset to run with. */
+  agent?: string | null;
+  /** Session name. Defaults to "Run: <start of task>". */
+  name?: string | null;
+  stream?: boolean;
+  /** The task, sent as the user message of a new session. */
+  task: string;
+}
+
+/** Outcome of a scheduled run. */
+export type RunStatus = "fired" | "succeeded" | "failed";
+
+export interface SandboxConfigResponse {
+  docker_image: string;
+  mode: string;
+  timeout_secs: number;
+}
+
+/** A scheduled task entry in the `[[schedules]]` array. */
+export interface ScheduleConfig {
+  /** Shell command custom tasks run through the sandbox. */
+  command?: string | null;
+  /** Cron expression (5-field standard or 7-field extended). */
+  cron: string;
+  enabled?: boolean;
+  name: string;
+  /**
+   * Prompt text for prompt-type tasks. For heartbeats, the kickoff
+   * prompt, in which `{schedule}`, `{skill}` and `{now}` are filled in.
+   */
+  prompt?: string | null;
+  /**
+   * This schedule's own quiet hours (`"HH:MM-HH:MM"`), or `"off"` for a
+   * schedule that must run on time (default: `[scheduler] quiet_hours`).
+   */
+  quiet_hours?: string | null;
+  /**
+   * Session the task runs in, resumed by name and created on first run
+   * (default: `schedule:<name>`, so runs never land in the user's session).
+   */
+  session?: string | null;
+  /** Skill whose document heartbeat runs follow. */
+  skill?: string | null;
+  task?: ScheduleTaskType;
+  /** URL custom tasks POST to, after the command if there is one. */
+  webhook?: string | null;
+  workspace?: string | null;
+  /**
+   * Project directory for the run; overrides `[sandbox] workspace_root`
+   * for file tools and project context.
+   */
+  workspace_root?: string | null;
+}
+
+export interface ScheduleInfo {
+  /** Shell command custom tasks run through the sandbox. */
+  command?: string | null;
+  /** Cron expression (5-field standard or 7-field extended). */
+  cron: string;
+  enabled?: boolean;
+  name: string;
+  /**
+   * Prompt text for prompt-type tasks. For heartbeats, the kickoff
+   * prompt, in which `{schedule}`, `{skill}` and `{now}` are filled in.
+   */
+  prompt?: string | null;
+  /**
+   * This schedule's own quiet hours (`"HH:MM-HH:MM"`), or `"off"` for a
+   * schedule that must run on time (default: `[scheduler] quiet_hours`).
+   */
+  quiet_hours?: string | null;
+  /**
+   * Session the task runs in, resumed by name and created on first run
+   * (default: `schedule:<name>`, so runs never land in the user's session).
+   */
+  session?: string | null;
+  /** Skill whose document heartbeat runs follow. */
+  skill?: string | null;
+  task?: ScheduleTaskType;
+  /** URL custom tasks POST to, after the command if there is one. */
+  webhook?: string | null;
+  workspace?: string | null;
+  /**
+   * Project directory for the run; overrides `[sandbox] workspace_root`
+   * for file tools and project context.
+   */
+  workspace_root?: string | null;
+  /** Cron parse error; invalid schedules never fire. */
+  cron_error?: string | null;
+  last_status?: null | RunStatus;
+  /** Next fire time computed from the expression (when it has not fired yet). */
+  next_run?: string | null;
+  state?: null | ScheduleState;
+}
+
+/** One entry in the run history. */
+export interface ScheduleRun {
+  error?: string | null;
+  finished_at?: string | null;
+  fired_at: string;
+  schedule: string;
+  status: RunStatus;
+  /** Start of the agent's reply, for prompt tasks. */
+  summary?: string | null;
+}
+
+/** Persistent state for a single schedule. */
+export interface ScheduleState {
+  last_error?: string | null;
+  last_run?: string | null;
+  next_run: string;
+  run_count: number;
+}
+
+export type ScheduleTaskType = "heartbeat" | "prompt" | "custom";
+
+/** 1-based, inclusive line range. */
+export interface Selection {
+  end_line: number;
+  start_line: number;
+}
+
+export interface ServerConfigResponse {
+  cors: boolean;
+  has_auth_token: boolean;
+  host: string;
+  port: number;
+}
+
+export interface SessionConfigResponse {
+  auto_save: boolean;
+  max_history: number;
+}
+
+export interface SessionCreated {
+  /** Agent preset for every turn in the session. */
+  agent?: string | null;
+  id: string;
+  name: string;
+}
+
+export interface SessionInfo {
+  id: string;
+  message_count: number;
+  name: string;
+  tags: string[];
+  updated_at: string;
+}
+
+/** A session's token usage and estimated cost, with the configured budget. */
+export interface SessionUsageResponse {
+  completion_tokens: number;
+  /** In US dollars, at the provider's configured prices (0 when unset). */
+  cost_usd: number;
+  /**
+   * Some of the counts were estimated because the provider did not
+   * report usage.
+   */
+  estimated?: boolean;
+  prompt_tokens: number;
+  budget_tokens?: number | null;
+  budget_usd?: number | null;
+  total_tokens: number;
+}
+
+export interface SessionWorkspacesRequest {
+  /** Workspace names, primary first; empty for `[sandbox] workspace_root`. */
+  workspaces: string[];
+}
+
+export interface SkillInfo {
+  description: string;
+  name: string;
+  source?: string | null;
+  sub_skills: string[];
+  tags: string[];
+}
+
+export interface SpeechRequest {
+  input: string;
+  /** Overrides `[audio] voice`. */
+  voice?: string | null;
+}
+
+export interface TerminalCommandInfo {
+  command_text: string;
+  duration_ms?: number | null;
+  ended_at?: string | null;
+  exit_code?: number | null;
+  id: string;
+  sequence: number;
+  started_at: string;
+  working_directory: string;
+}
+
+export interface TerminalSessionDetail {
+  commands: TerminalCommandInfo[];
+  session: TerminalSessionInfo;
+}
+
+export interface TerminalSessionInfo {
+  active: boolean;
+  command_count: number;
+  ended_at?: string | null;
+  id: string;
+  shell: string;
+  started_at: string;
+  tags: string[];
+  terminal?: string | null;
+  working_directory: string;
+}
+
+/** One search/replace edit. */
+export interface TextEdit {
+  new_string: string;
+  old_string: string;
+}
+
+/** UI colour theme. */
+export type Theme = "dark" | "light";
+
+export interface TimeseriesPoint {
+  /** Fraction of rated messages rated thumbs up; `null` when none were. */
+  approval_rate?: number | null;
+  date: string;
+  error_rate: number;
+  messages: number;
+  /** Tool calls refused for being over a `[tools.quotas]` limit. */
+  quota_denials: number;
+  sessions: number;
+  thumbs_down: number;
+  thumbs_up: number;
+  tokens: number;
+  tool_calls: number;
+  tool_errors: number;
+}
+
+export interface TimeseriesResponse {
+  days: number;
+  points: TimeseriesPoint[];
+}
+
+/** Tokens used by model calls, and what they cost. */
+export interface TokenUsage {
+  completion_tokens: number;
+  /** In US dollars, at the provider's configured prices (0 when unset). */
+  cost_usd: number;
+  /**
+   * Some of the counts were estimated because the provider did not
+   * report usage.
+   */
+  estimated?: boolean;
+  prompt_tokens: number;
+}
+
+export interface ToolCallResponse {
+  /** JSON-encoded arguments. */
+  arguments: string;
+  id: string;
+  name: string;
+}
+
+export interface ToolInfo {
+  description: string;
+  /** Offered to the model; disabled tools are neither offered nor run. */
+  enabled: boolean;
+  /** Namespaced ID: `builtin.<name>` or `plugin.<plugin>.<name>`. */
+  id: string;
+  /** What the model calls the tool. */
+  name: string;
+}
+
+/** Output from a tool execution. */
+export interface ToolOutput {
+  content: string;
+  /** Wall-clock execution time, when the tool actually ran. */
+  duration_ms?: number | null;
+  /** Images produced by the tool (e.g. `file_read` on a PNG). */
+  images?: ImagePart[];
+  is_error: boolean;
+  tool_call_id: string;
+}
+
+/** Preferences shared by every browser talking to this server. */
+export interface UiPreferences {
+  /** Start tool call cards collapsed in chat. */
+  collapsed_tool_cards?: boolean;
+  /** View opened on an empty canvas (a card type), or empty for none. */
+  default_view?: string;
+  /** Base font size in pixels. */
+  font_size?: number;
+  theme?: Theme;
+}
+
+export interface UpdateProviderRequest {
+  api_base?: string | null;
+  max_tokens?: number | null;
+  model: string;
+  temperature?: number | null;
+}
+
+export interface UpdateToolRequest {
+  enabled: boolean;
+}
+
+export interface UploadedFile {
+  name: string;
+  path: string;
+  size: number;
+}
+
+export interface ValidateScheduleRequest {
+  /** Upcoming fire times to return (1–20, default 5). */
+  count?: number | null;
+  expression: string;
+}
+
+export interface ValidateScheduleResponse {
+  error?: string | null;
+  /** RFC 3339 UTC timestamps. */
+  next_runs: string[];
+  normalized?: string | null;
+  valid: boolean;
+}
+
+export interface WorkspaceInfo {
+  /** Whether the session's tools may use it. */
+  active: boolean;
+  name: string;
+  path: string;
+  /** Whether relative paths and commands start here. */
+  primary: boolean;
+}
@@ -1,6 +1,8 @@
 import type {
-  ApiSession, ApiMessage, ApiConfig, ApiSkill,
-  ApiPlugin, ApiPluginHealth, ApiContext, ApiAnalyticsSummary,
+  ApiSession, ApiCheckpoint, ApiRollback, ApiFileDiff, ApiMessage, ApiConfig, ApiSkill, ApiSkillContent, ApiSkillSearchResults,
+  ApiPlugin, ApiPluginHealth, ApiPluginPermissions, ApiContext, ApiSchedule, ApiScheduleRun, ApiScheduleValidation, ApiAnalyticsSummary, ApiAnalyticsTimeseries, ApiGoalStatus, ApiSessionUsage,
+  ApiTerminalFailures, ApiErrorAssistSession, ApiShell, ApiToolInfo, ApiToolOutput, ApiUploadedFile, ApiPreferences, ApiProblem, ApiErrorCode, ApiLogEntry, ApiLogLevel, ApiNotification,
+  ChatImage, LocalChatMessage, Rating, ToolCallItem,
 } from '../types'
 
 // ── Auth token stored in localStorage ─────────────────────────────────
@@ -21,9 +23,48 @@ function authHeaders(): Record<string, string> {
   return h
 }
 
+/**
+ * Error for a failed request. The server answers with problem details
+ * carrying a machine-readable `code`; branch on that rather than the text.
+ */
+export class ApiRequestError extends Error {
+  readonly status: number
+  readonly code: ApiErrorCode | string
+  readonly details?: ApiProblem['details']
+  /** Server-side ID of the failed request, also found in the server log. */
+  readonly requestId?: string
+  /** The message without the request ID. */
+  readonly reason: string
+
+  constructor(status: number, code: ApiErrorCode | string, message: string, details?: ApiProblem['details'], requestId?: string) {
+    super(requestId ? `${message} (request ${requestId})` : message)
+    this.name = 'ApiRequestError'
+    this.status = status
+    this.code = code
+    this.details = details
+    this.requestId = requestId
+    this.reason = message
+  }
+}
+
+async function responseError(res: Response): Promise<ApiRequestError> {
+  const text = await res.text()
+  const requestId = res.headers.get('x-request-id') ?? undefined
+  try {
+    const problem = JSON.parse(text) as Partial<ApiProblem>
+    if (problem.code && problem.detail) {
+      const parts = [problem.detail, ...(problem.details?.errors ?? [])]
+      return new ApiRequestError(res.status, problem.code, `${res.status} ${parts.join('; ')}`, problem.details, problem.request_id ?? requestId)
+    }
+  } catch {
+    // Not JSON: use the text as is.
+  }
+  return new ApiRequestError(res.status, 'unknown', `${res.status} ${text || res.statusText}`, undefined, requestId)
+}
+
 async function get<T>(path: string): Promise<T> {
   const res = await fetch(path, { headers: authHeaders() })
-  if (!res.ok) throw new Error(`${res.status} ${res.statusText}`)
+  if (!res.ok) throw await responseError(res)
   return res.json() as Promise<T>
 }
 
@@ -38,6 +79,21 @@ export function getConfig(): Promise<ApiConfig> {
   return get<ApiConfig>('/v1/config')
 }
 
+// ── Preferences (server-side, follow the user across browsers) ─────────
+export function getPreferences(): Promise<ApiPreferences> {
+  return get<ApiPreferences>('/v1/preferences')
+}
+
+export async function updatePreferences(prefs: ApiPreferences): Promise<ApiPreferences> {
+  const res = await fetch('/v1/preferences', {
+    method: 'PUT',
+    headers: authHeaders(),
+    body: JSON.stringify(prefs),
+  })
+  if (!res.ok) throw await responseError(res)
+  return res.json() as Promise<ApiPreferences>
+}
+
 // ── Models ────────────────────────────────────────────────────────────
 export interface OllamaModel {
   name: string
@@ -64,9 +120,90 @@ export async function updateProvider(updates: {
   return res.json() as Promise<ApiConfig>
 }
 
+// ── Tools ──────────────────────────────────────────────────────────────
+export function listTools(): Promise<ApiToolInfo[]> {
+  return get<ApiToolInfo[]>('/v1/tools')
+}
+
+/** Enable or disable a tool for every session; saved in the server config. */
+export async function setToolEnabled(name: string, enabled: boolean): Promise<ApiToolInfo> {
+  const res = await fetch(`/v1/tools/${encodeURIComponent(name)}`, {
+    method: 'PATCH',
+    headers: authHeaders(),
+    body: JSON.stringify({ enabled }),
+  })
+  if (!res.ok) throw await responseError(res)
+  return res.json() as Promise<ApiToolInfo>
+}
+
+export async function runTool(name: string, args: unknown, sessionId?: string): Promise<ApiToolOutput> {
+  const qs = sessionId ? `?session_id=${encodeURIComponent(sessionId)}` : ''
+  const res = await fetch(`/v1/tools/${encodeURIComponent(name)}/run${qs}`, {
+    method: 'POST',
+    headers: authHeaders(),
+    body: JSON.stringify(args),
+  })
+  if (!res.ok) throw await responseError(res)
+  return res.json() as Promise<ApiToolOutput>
+}
+
+// ── Files ──────────────────────────────────────────────────────────────
+export async function uploadFiles(files: File[], sessionId?: string): Promise<ApiUploadedFile[]> {
+  const form = new FormData()
+  for (const f of files) form.append('file', f, f.name)
+  const qs = sessionId ? `?session_id=${encodeURIComponent(sessionId)}` : ''
+  // Let the browser set the multipart boundary; only forward auth.
+  const { Authorization } = authHeaders()
+  const res = await fetch(`/v1/files${qs}`, {
+    method: 'POST',
+    headers: Authorization ? { Authorization } : {},
+    body: form,
+  })
+  if (!res.ok) throw await responseError(res)
+  return res.json() as Promise<ApiUploadedFile[]>
+}
+
+// ── Audio ──────────────────────────────────────────────────────────────
+export async function transcribeAudio(audio: Blob, language?: string): Promise<string> {
+  const form = new FormData()
+  form.append('file', audio, 'recording.webm')
+  if (language) form.append('language', language)
+  const { Authorization } = authHeaders()
+  const res = await fetch('/v1/audio/transcriptions', {
+    method: 'POST',
+    headers: Authorization ? { Authorization } : {},
+    body: form,
+  })
+  if (!res.ok) throw await responseError(res)
+  const { text } = await res.json() as { text: string }
+  return text
+}
+
+export async function synthesizeSpeech(input: string): Promise<Blob> {
+  const res = await fetch('/v1/audio/speech', {
+    method: 'POST',
+    headers: authHeaders(),
+    body: JSON.stringify({ input }),
+  })
+  if (!res.ok) throw await responseError(res)
+  return res.blob()
+}
+
 // ── Sessions ───────────────────────────────────────────────────────────
-export function listSessions(): Promise<ApiSession[]> {
-  return get<ApiSession[]>('/v1/sessions')
+/** Listing filters and paging; see `GET /v1/sessions` and `/v1/sessions/{id}/messages`. */
+export type ListQuery = Record<string, string | number | boolean | undefined>
+
+function withQuery(path: string, query?: ListQuery): string {
+  const params = new URLSearchParams()
+  for (const [key, value] of Object.entries(query ?? {})) {
+    if (value !== undefined) params.set(key, String(value))
+  }
+  const qs = params.toString()
+  return qs ? `${path}?${qs}` : path
+}
+
+export function listSessions(query?: ListQuery): Promise<ApiSession[]> {
+  return get<ApiSession[]>(withQuery('/v1/sessions', query))
 }
 
 export async function createSession(name: string): Promise<{ id: string; name: string }> {
@@ -79,30 +216,224 @@ export async function createSession(name: string): Promise<{ id: string; name: s
   return res.json() as Promise<{ id: string; name: string }>
 }
 
-export function getSessionMessages(id: string): Promise<ApiMessage[]> {
-  return get<ApiMessage[]>(`/v1/sessions/${id}/messages`)
+export async function forkSession(id: string, name?: string): Promise<{ id: string; name: string }> {
+  const res = await fetch(`/v1/sessions/${id}/fork`, {
+    method: 'POST',
+    headers: authHeaders(),
+    body: JSON.stringify({ name }),
+  })
+  if (!res.ok) throw await responseError(res)
+  return res.json() as Promise<{ id: string; name: string }>
+}
+
+export function listCheckpoints(id: string): Promise<ApiCheckpoint[]> {
+  return get<ApiCheckpoint[]>(`/v1/sessions/${id}/checkpoints`)
+}
+
+/** Roll back to a checkpoint, or undo the last turn when none is given. */
+export async function rollbackSession(
+  id: string,
+  opts: { checkpoint?: string; restoreFiles?: boolean } = {},
+): Promise<ApiRollback> {
+  const res = await fetch(`/v1/sessions/${id}/rollback`, {
+    method: 'POST',
+    headers: authHeaders(),
+    body: JSON.stringify({ checkpoint: opts.checkpoint, restore_files: opts.restoreFiles ?? true }),
+  })
+  if (!res.ok) throw await responseError(res)
+  return res.json() as Promise<ApiRollback>
+}
+
+export function getSessionChanges(id: string): Promise<ApiFileDiff[]> {
+  return get<ApiFileDiff[]>(`/v1/sessions/${id}/changes`)
+}
+
+/** Revert one changed file, or every one when `path` is omitted. */
+export async function revertSessionChanges(id: string, path?: string): Promise<{ reverted: string[] }> {
+  const res = await fetch(`/v1/sessions/${id}/changes/revert`, {
+    method: 'POST',
+    headers: authHeaders(),
+    body: JSON.stringify({ path }),
+  })
+  if (!res.ok) throw await responseError(res)
+  return res.json() as Promise<{ reverted: string[] }>
+}
+
+/** Keep one changed file as it is (or every one when `path` is omitted) and drop its snapshots. */
+export async function acceptSessionChanges(id: string, path?: string): Promise<{ accepted: string[] }> {
+  const res = await fetch(`/v1/sessions/${id}/changes/accept`, {
+    method: 'POST',
+    headers: authHeaders(),
+    body: JSON.stringify({ path }),
+  })
+  if (!res.ok) throw await responseError(res)
+  return res.json() as Promise<{ accepted: string[] }>
+}
+
+/** Create (or fetch) the session's read-only public link; `url` is relative to the server. */
+export async function shareSession(id: string): Promise<{ token: string; url: string }> {
+  const res = await fetch(`/v1/sessions/${id}/share`, { method: 'POST', headers: authHeaders() })
+  if (!res.ok) throw await responseError(res)
+  return res.json() as Promise<{ token: string; url: string }>
+}
+
+export async function unshareSession(id: string): Promise<void> {
+  const res = await fetch(`/v1/sessions/${id}/share`, { method: 'DELETE', headers: authHeaders() })
+  if (!res.ok) throw new Error(`${res.status} ${res.statusText}`)
+}
+
+export function getSessionMessages(id: string, query?: ListQuery): Promise<ApiMessage[]> {
+  return get<ApiMessage[]>(withQuery(`/v1/sessions/${id}/messages`, query))
+}
+
+/** Convert stored session messages into chat card history (tool results folded into their calls). */
+export function toLocalHistory(messages: ApiMessage[]): LocalChatMessage[] {
+  const results = new Map(messages.filter(m => m.role === 'tool' && m.tool_call_id).map(m => [m.tool_call_id!, m.content]))
+  const history: LocalChatMessage[] = []
+  for (const m of messages) {
+    if (m.role === 'user') {
+      history.push({ id: m.id, role: 'user', content: m.content, images: m.images })
+    } else if (m.role === 'assistant') {
+      const toolCalls: ToolCallItem[] = (m.tool_calls ?? []).map(tc => ({
+        id: tc.id, name: tc.name, arguments: tc.arguments,
+        status: 'done', output: results.get(tc.id),
+      }))
+      const prev = history[history.length - 1]
+      // Tool-calling steps are merged into the assistant reply that follows them.
+      if (prev?.role === 'assistant' && !prev.content) {
+        prev.content = m.content
+        prev.toolCalls = [...(prev.toolCalls ?? []), ...toolCalls]
+        prev.id = m.id
+        prev.feedback = m.feedback?.rating
+      } else {
+        history.push({
+          id: m.id, role: 'assistant', content: m.content,
+          toolCalls: toolCalls.length ? toolCalls : undefined, feedback: m.feedback?.rating,
+        })
+      }
+    }
+  }
+  return history
 }
 
 // ── Chat (SSE streaming) ───────────────────────────────────────────────
-type StreamEvent =
+export type StreamEvent =
   | { type: 'token'; content: string }
-  | { type: 'tool_start'; name: string }
-  | { type: 'tool_result'; content: string; isError: boolean }
+  | { type: 'tool_start'; id: string; name: string; arguments: string }
+  | { type: 'tool_result'; id: string; content: string; isError: boolean; durationMs?: number }
+  | { type: 'usage'; usage: ApiSessionUsage }
   | { type: 'done' }
-  | { type: 'error'; message: string }
+  | { type: 'error'; message: string; requestId?: string }
 
 export async function streamChat(
-  messages: { role: string; content: string }[],
+  messages: { role: string; content: string; images?: ChatImage[] }[],
   onEvent: (e: StreamEvent) => void,
+  sessionId?: string,
 ): Promise<void> {
   const res = await fetch('/v1/chat/completions', {
     method: 'POST',
     headers: authHeaders(),
-    body: JSON.stringify({ messages, stream: true }),
+    body: JSON.stringify({ messages, stream: true, session_id: sessionId }),
   })
+  await readEventStream(res, onEvent)
+}
 
+/** Truncate a session back to a user message and stream a fresh reply. */
+export async function regenerateMessage(
+  sessionId: string,
+  messageId: string,
+  onEvent: (e: StreamEvent) => void,
+  content?: string,
+): Promise<void> {
+  const res = await fetch(`/v1/sessions/${sessionId}/messages/${messageId}/regenerate`, {
+    method: 'POST',
+    headers: authHeaders(),
+    body: JSON.stringify({ content, stream: true }),
+  })
+  await readEventStream(res, onEvent)
+}
+
+export function getSessionUsage(sessionId: string): Promise<ApiSessionUsage> {
+  return get<ApiSessionUsage>(`/v1/sessions/${sessionId}/usage`)
+}
+
+export async function deleteMessage(sessionId: string, messageId: string): Promise<void> {
+  const res = await fetch(`/v1/sessions/${sessionId}/messages/${messageId}`, {
+    method: 'DELETE',
+    headers: authHeaders(),
+  })
+  if (!res.ok) throw new Error(`${res.status} ${res.statusText}`)
+}
+
+/** Rate an assistant message; `null` clears the rating. */
+export async function setMessageFeedback(
+  sessionId: string,
+  messageId: string,
+  rating: Rating | null,
+  comment?: string,
+): Promise<void> {
+  const res = await fetch(`/v1/sessions/${sessionId}/messages/${messageId}/feedback`, {
+    method: 'POST',
+    headers: authHeaders(),
+    body: JSON.stringify({ rating, comment }),
+  })
+  if (!res.ok) throw await responseError(res)
+}
+
+/** Attempts to resume a dropped stream before giving up. */
+const STREAM_RESUME_ATTEMPTS = 5
+
+/**
+ * Read an agent turn's SSE stream. If the connection drops mid-turn, the
+ * stream is resumed from `/v1/runs/{id}/events` after the last event
+ * received, so a network blip loses nothing.
+ */
+async function readEventStream(res: Response, onEvent: (e: StreamEvent) => void): Promise<void> {
+  const runId = res.headers.get('x-run-id')
+  // Errors carry the request ID to look up in the server log.
+  const requestId = res.headers.get('x-request-id') ?? undefined
+  const report = onEvent
+  onEvent = (e) => report(e.type === 'error' ? { ...e, requestId: e.requestId ?? requestId } : e)
+  const position = { lastEventId: '', finished: false }
+  let attempt = 0
+
+  while (true) {
+    try {
+      await readStreamBody(res, onEvent, position)
+      if (position.finished || !runId) return
+    } catch (err) {
+      if (!runId) throw err
+    }
+    if (attempt >= STREAM_RESUME_ATTEMPTS) {
+      onEvent({ type: 'error', message: 'Connection lost' })
+      return
+    }
+    attempt += 1
+    await new Promise((resolve) => setTimeout(resolve, 500 * 2 ** attempt))
+    try {
+      const headers: Record<string, string> = { ...authHeaders() }
+      if (position.lastEventId) headers['Last-Event-ID'] = position.lastEventId
+      res = await fetch(`/v1/runs/${runId}/events`, { headers })
+    } catch {
+      continue
+    }
+    // The run is gone (server restarted or retention passed).
+    if (res.status === 404) {
+      onEvent({ type: 'error', message: 'Connection lost' })
+      return
+    }
+  }
+}
+
+async function readStreamBody(
+  res: Response,
+  onEvent: (e: StreamEvent) => void,
+  position: { lastEventId: string; finished: boolean },
+): Promise<void> {
   if (!res.ok || !res.body) {
-    onEvent({ type: 'error', message: `${res.status} ${res.statusText}` })
+    const err = res.ok ? undefined : await responseError(res)
+    onEvent({ type: 'error', message: err ? err.reason : `${res.status} ${res.statusText}`, requestId: err?.requestId })
+    position.finished = true
     return
   }
 
@@ -120,34 +451,50 @@ export async function streamChat(
     buffer = lines.pop() ?? ''
 
     for (const line of lines) {
-      if (line.startsWith('event: ')) {
+      if (line.startsWith('id: ')) {
+        position.lastEventId = line.slice(4).trim()
+      } else if (line.startsWith('event: ')) {
         currentEvent = line.slice(7).trim()
       } else if (line.startsWith('data: ')) {
         const data = line.slice(6).trim()
         if (data === '[DONE]') {
           onEvent({ type: 'done' })
+          position.finished = true
           currentEvent = ''
           continue
         }
         try {
           const parsed = JSON.parse(data)
           if (currentEvent === 'tool_call') {
-            onEvent({ type: 'tool_start', name: parsed.tool as string })
+            onEvent({
+              type: 'tool_start',
+              id: parsed.id as string,
+              name: parsed.tool as string,
+              arguments: (parsed.arguments as string | undefined) ?? '',
+            })
           } else if (currentEvent === 'tool_result') {
             onEvent({
               type: 'tool_result',
+              id: parsed.tool_call_id as string,
               content: parsed.content as string,
               isError: parsed.is_error as boolean,
+              durationMs: (parsed.duration_ms as number | null) ?? undefined,
             })
+          } else if (currentEvent === 'usage') {
+            onEvent({ type: 'usage', usage: parsed as ApiSessionUsage })
           } else if (currentEvent === 'error') {
             onEvent({ type: 'error', message: data })
+            position.finished = true
           } else {
             // Default: content chunk — {"choices":[{"delta":{"content":"..."}}]}
             const token = parsed?.choices?.[0]?.delta?.content as string | undefined
             if (token != null) onEvent({ type: 'token', content: token })
           }
         } catch {
-          if (currentEvent === 'error') onEvent({ type: 'error', message: data })
+          if (currentEvent === 'error') {
+            onEvent({ type: 'error', message: data })
+            position.finished = true
+          }
         }
         if (!line.startsWith('event: ')) currentEvent = ''
       } else if (line === '') {
@@ -163,14 +510,12 @@ export function listSkills(): Promise<ApiSkill[]> {
   return get<ApiSkill[]>('/v1/skills')
 }
 
-export function searchSkills(q: string, limit = 20): Promise<ApiSkill[]> {
-  return get<ApiSkill[]>(`/v1/skills/search?q=${encodeURIComponent(q)}&limit=${limit}`)
+export function searchSkills(q: string, limit = 20): Promise<ApiSkillSearchResults> {
+  return get<ApiSkillSearchResults>(`/v1/skills/search?q=${encodeURIComponent(q)}&limit=${limit}`)
 }
 
-export async function getSkillContent(name: string): Promise<string> {
-  const res = await fetch(`/v1/skills/${encodeURIComponent(name)}`, { headers: authHeaders() })
-  if (!res.ok) throw new Error(`${res.status}`)
-  return res.json() as Promise<string>
+export function getSkillContent(name: string): Promise<ApiSkillContent> {
+  return get<ApiSkillContent>(`/v1/skills/${encodeURIComponent(name)}`)
 }
 
 // ── Analytics ──────────────────────────────────────────────────────────
@@ -178,12 +523,32 @@ export function getAnalyticsSummary(): Promise<ApiAnalyticsSummary> {
   return get<ApiAnalyticsSummary>('/v1/analytics/summary')
 }
 
+export function getAnalyticsTimeseries(days: number): Promise<ApiAnalyticsTimeseries> {
+  return get<ApiAnalyticsTimeseries>(`/v1/analytics/timeseries?days=${days}`)
+}
+
+export function getAnalyticsGoals(): Promise<ApiGoalStatus[]> {
+  return get<ApiGoalStatus[]>('/v1/analytics/goals')
+}
+
 export async function getAnalyticsReport(period: 'week' | 'month'): Promise<string> {
   const res = await fetch(`/v1/analytics/report?period=${period}`, { headers: authHeaders() })
   if (!res.ok) throw new Error(`${res.status}`)
   return res.text()
 }
 
+/** Download the report as a file in the given format. */
+export async function downloadAnalyticsReport(period: 'week' | 'month', format: 'html' | 'pdf'): Promise<void> {
+  const res = await fetch(`/v1/analytics/report?period=${period}&format=${format}`, { headers: authHeaders() })
+  if (!res.ok) throw new Error(`${res.status}`)
+  const url = URL.createObjectURL(await res.blob())
+  const a = document.createElement('a')
+  a.href = url
+  a.download = `agent-shell-${period}-report.${format}`
+  a.click()
+  URL.revokeObjectURL(url)
+}
+
 // ── Context ────────────────────────────────────────────────────────────
 export function getContext(directory?: string): Promise<ApiContext> {
   const url = directory
@@ -192,6 +557,27 @@ export function getContext(directory?: string): Promise<ApiContext> {
   return get<ApiContext>(url)
 }
 
+// ── Schedules ──────────────────────────────────────────────────────────
+export function listSchedules(): Promise<ApiSchedule[]> {
+  return get<ApiSchedule[]>('/v1/schedules')
+}
+
+export function getScheduleHistory(name: string, limit = 20): Promise<ApiScheduleRun[]> {
+  return get<ApiScheduleRun[]>(`/v1/schedules/${encodeURIComponent(name)}/history?limit=${limit}`)
+}
+
+/** Validate a cron expression with the scheduler's own parser and preview
+ *  its next fire times; invalid expressions resolve with `valid: false`. */
+export async function validateSchedule(expression: string, count = 5): Promise<ApiScheduleValidation> {
+  const res = await fetch('/v1/schedules/validate', {
+    method: 'POST',
+    headers: authHeaders(),
+    body: JSON.stringify({ expression, count }),
+  })
+  if (!res.ok) throw await responseError(res)
+  return res.json() as Promise<ApiScheduleValidation>
+}
+
 // ── Plugins ────────────────────────────────────────────────────────────
 export function listPlugins(): Promise<ApiPlugin[]> {
   return get<ApiPlugin[]>('/v1/plugins')
@@ -201,8 +587,86 @@ export function getPluginHealth(): Promise<ApiPluginHealth[]> {
   return get<ApiPluginHealth[]>('/v1/plugins/health')
 }
 
+/** `key` is `<category>:<name>` or a plugin name that is unique. */
+export function getPluginPermissions(key: string): Promise<ApiPluginPermissions> {
+  return get<ApiPluginPermissions>(`/v1/plugins/${encodeURIComponent(key)}/permissions`)
+}
+
+// ── Terminal failures (error assist) ───────────────────────────────────
+export function listTerminalFailures(limit = 5): Promise<ApiTerminalFailures> {
+  return get<ApiTerminalFailures>(`/v1/terminal-sessions/failures?limit=${limit}`)
+}
+
+export async function askAboutFailure(sessionId: string, commandId: string): Promise<ApiErrorAssistSession> {
+  const res = await fetch(`/v1/terminal-sessions/${sessionId}/commands/${commandId}/ask`, {
+    method: 'POST',
+    headers: authHeaders(),
+  })
+  if (!res.ok) throw new Error(`${res.status} ${res.statusText}`)
+  return res.json() as Promise<ApiErrorAssistSession>
+}
+
+// ── Logs ───────────────────────────────────────────────────────────────
+/**
+ * Read a server-sent event stream, passing the parsed `data` of each `name`
+ * event to `onData`, until `signal` aborts. Resolves when the stream ends.
+ */
+async function followEvents<T>(path: string, name: string, onData: (data: T) => void, signal: AbortSignal): Promise<void> {
+  const res = await fetch(path, { headers: authHeaders(), signal })
+  if (!res.ok || !res.body) throw await responseError(res)
+  const reader = res.body.getReader()
+  const decoder = new TextDecoder()
+  let buffer = ''
+  let event = ''
+  while (true) {
+    const { done, value } = await reader.read()
+    if (done) return
+    buffer += decoder.decode(value, { stream: true })
+    const lines = buffer.split('\n')
+    buffer = lines.pop() ?? ''
+    for (const line of lines) {
+      if (line.startsWith('event: ')) {
+        event = line.slice(7).trim()
+      } else if (line.startsWith('data: ') && event === name) {
+        try { onData(JSON.parse(line.slice(6)) as T) } catch { /* skip malformed */ }
+      } else if (line === '') {
+        event = ''
+      }
+    }
+  }
+}
+
+/**
+ * Follow the server log from `level` up: the recent buffered events first,
+ * then new ones until `signal` aborts. Resolves when the stream ends.
+ */
+export function streamLogs(
+  level: ApiLogLevel,
+  onEntry: (entry: ApiLogEntry) => void,
+  signal: AbortSignal,
+): Promise<void> {
+  return followEvents(`/v1/logs/stream?level=${level}`, 'log', onEntry, signal)
+}
+
+/** Follow notifications the agent sends with `notify_user` until `signal` aborts. */
+export function streamNotifications(
+  onNotification: (notification: ApiNotification) => void,
+  signal: AbortSignal,
+): Promise<void> {
+  return followEvents('/v1/notifications/stream', 'notification', onNotification, signal)
+}
+
 // ── Terminal WebSocket ─────────────────────────────────────────────────
-export function createTerminalSocket(): WebSocket {
+export function listShells(): Promise<ApiShell[]> {
+  return get<ApiShell[]>('/v1/terminal/shells')
+}
+
+export function createTerminalSocket(opts: { shell?: string; cols?: number; rows?: number } = {}): WebSocket {
   const proto = window.location.protocol === 'https:' ? 'wss:' : 'ws:'
-  return new WebSocket(`${proto}//${window.location.host}/v1/terminal`)
+  const params = new URLSearchParams()
+  if (opts.shell) params.set('shell', opts.shell)
+  if (opts.cols) params.set('cols', String(opts.cols))
+  if (opts.rows) params.set('rows', String(opts.rows))
+  const qs = params.toString()
+  return new WebSocket(`${proto}//${window.location.host}/v1/terminal${qs ? `?${qs}` : ''}`)
 }
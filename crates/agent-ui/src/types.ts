@@ -1,3 +1,5 @@
+import type { ErrorCode, LogEntry, LogLevel, Notification, Problem, ToolInfo } from './services/api.gen'
+
 // ── Card Types ───────────────────────────────────────────────────────────
 export enum CardType {
   CHAT = 'CHAT',
@@ -8,6 +10,8 @@ export enum CardType {
   SKILLS = 'SKILLS',
   CONTEXT = 'CONTEXT',
   PLUGINS = 'PLUGINS',
+  CHANGES = 'CHANGES',
+  LOGS = 'LOGS',
   ISLAND = 'ISLAND',
 }
 
@@ -16,16 +20,63 @@ export interface ViewportState { x: number; y: number; scale: number }
 
 // ── Chat ─────────────────────────────────────────────────────────────────
 export interface LocalChatMessage {
+  /** Server-side message ID, once known. */
+  id?: string
   role: 'user' | 'assistant'
   content: string
+  images?: ChatImage[]
   toolCalls?: ToolCallItem[]
+  /** The user's thumbs up/down on an assistant message. */
+  feedback?: Rating
+}
+
+export type Rating = 'up' | 'down'
+
+/** Inline image sent with a chat message (base64, no `data:` prefix). */
+export interface ChatImage {
+  media_type: string
+  data: string
 }
 
 export interface ToolCallItem {
+  id?: string
   name: string
+  /** Raw JSON arguments as sent by the model. */
+  arguments?: string
   status: 'running' | 'done' | 'error'
   output?: string
   isError?: boolean
+  durationMs?: number
+}
+
+export interface ApiPreferences {
+  theme: 'dark' | 'light'; font_size: number; default_view: string; collapsed_tool_cards: boolean
+}
+
+export interface ApiUploadedFile {
+  name: string; path: string; size: number
+}
+
+export interface ApiToolOutput {
+  tool_call_id: string; content: string; is_error: boolean; duration_ms?: number
+}
+
+/** Generated from the server's OpenAPI description (`npm run gen:api`). */
+export type ApiErrorCode = ErrorCode
+
+/** A server log event from `/v1/logs/stream`. */
+export type ApiLogEntry = LogEntry
+export type ApiLogLevel = LogLevel
+
+/** A message the agent sent with `notify_user`, from `/v1/notifications/stream`. */
+export type ApiNotification = Notification
+
+/** A registered tool and whether it is offered to the model (`/v1/tools`). */
+export type ApiToolInfo = ToolInfo
+
+/** Error body of every failed API request (RFC 9457 problem details). */
+export interface ApiProblem extends Omit<Problem, 'details'> {
+  details?: { errors?: string[]; tool?: string }
 }
 
 // ── Card ─────────────────────────────────────────────────────────────────
@@ -49,8 +100,10 @@ export interface CardData {
   // CHAT
   chatHistory?: LocalChatMessage[]
   sessionId?: string
+  /** Unsent message the chat input starts with. */
+  draft?: string
   isLoading?: boolean
-  // NOTE
+  // NOTE (SKILLS: initial search query)
   content?: string
   // ISLAND
   groupId?: string
@@ -66,17 +119,39 @@ export interface AppSettings {
   showGrid: boolean
   snapToGrid: boolean
   authToken: string
+  fontSize: number
+  /** Card type opened on an empty canvas, or '' for none. */
+  defaultView: CardType | ''
+  collapsedToolCards: boolean
 }
 
 // ── API response shapes (mirror agent-server JSON) ────────────────────────
 export interface ApiSession {
-  id: string; name: string; message_count: number; updated_at: string
+  id: string; name: string; message_count: number; updated_at: string; tags?: string[]
+}
+
+export interface ApiCheckpoint {
+  id: string; label: string | null; created_at: string; message_count: number
+  /** Files modified since the checkpoint. */
+  files: string[]
+}
+
+export interface ApiRollback {
+  checkpoint: string; messages_removed: number; files_restored: string[]; failed?: string[]
+}
+
+export interface ApiFileDiff {
+  path: string; status: 'created' | 'modified' | 'deleted' | 'unchanged'; diff: string
 }
 
 export interface ApiMessage {
   id: string; role: string; content: string
-  tool_calls?: { id: string; name: string }[]
+  tool_calls?: { id: string; name: string; arguments?: string }[]
   tool_call_id?: string; timestamp: string
+  images?: ChatImage[]
+  feedback?: { rating: Rating; comment?: string; timestamp: string } | null
+  /** Content was shortened (`summary` listing mode). */
+  truncated?: boolean
 }
 
 export interface ApiConfig {
@@ -85,15 +160,40 @@ export interface ApiConfig {
   session: { max_history: number; auto_save: boolean }
   sandbox: { mode: string; docker_image: string; timeout_secs: number }
   tools: string[]
+  audio: { transcription: boolean; speech: boolean }
 }
 
 export interface ApiSkill {
   name: string; description: string; tags: string[]; sub_skills: string[]; source?: string
 }
 
+export interface ApiSkillContent {
+  name: string; content: string; sub_skills: string[]; has_references: boolean
+}
+
+export type ApiSkillMatchType = 'name' | 'description' | 'tags' | 'triggers' | 'content'
+
+export interface ApiSkillSearchResult {
+  domain: string; sub_skill?: string; score: number; match_type: ApiSkillMatchType
+  snippet?: string; file?: string
+}
+
+export interface ApiSkillSearchResults {
+  results: ApiSkillSearchResult[]; query: string; total_matches: number; truncated: boolean
+}
+
 export interface ApiPlugin {
   name: string; category: string; version?: string; enabled?: boolean
   description?: string; status?: string
+  permissions?: ApiPluginPermissionSet
+}
+
+export interface ApiPluginPermissionSet {
+  filesystem: string[]; network: string[]; env: string[]
+}
+
+export interface ApiPluginPermissions {
+  key: string; name: string; category: string; permissions: ApiPluginPermissionSet
 }
 
 export interface ApiPluginHealth {
@@ -104,6 +204,50 @@ export interface ApiContext {
   project?: { name: string; project_type: string; path: string; git_remote?: string; git_branch?: string }
   git?: { branch?: string; remote?: string; is_dirty: boolean; head_short?: string; repo_root: string }
   environments: { name: string; env_type: string; version?: string; path: string }[]
+  workspaces: { name: string; path: string; active: boolean; primary: boolean }[]
+}
+
+export interface ApiScheduleValidation {
+  valid: boolean
+  /** 7-field form the scheduler runs (`sec min hour dom month dow year`). */
+  normalized?: string
+  /** Upcoming fire times, RFC 3339 UTC. */
+  next_runs: string[]
+  error?: string
+}
+
+export type ApiRunStatus = 'fired' | 'succeeded' | 'failed'
+
+export interface ApiSchedule {
+  name: string; cron: string; task: 'heartbeat' | 'prompt' | 'custom'; enabled: boolean
+  workspace?: string; session?: string; workspace_root?: string; skill?: string; prompt?: string
+  cron_error?: string
+  state: { last_run?: string; next_run: string; run_count: number; last_error?: string } | null
+  /** Computed next fire time for schedules that have not fired yet. */
+  next_run?: string
+  last_status: ApiRunStatus | null
+}
+
+export interface ApiScheduleRun {
+  schedule: string; fired_at: string; finished_at?: string
+  status: ApiRunStatus; error?: string; summary?: string
+}
+
+export interface ApiShell {
+  id: string; name: string; path: string
+}
+
+export interface ApiTerminalFailure {
+  id: string; session_id: string; command_text: string
+  working_directory: string; exit_code?: number; ended_at?: string
+}
+
+export interface ApiTerminalFailures {
+  enabled: boolean; failures: ApiTerminalFailure[]
+}
+
+export interface ApiErrorAssistSession {
+  id: string; name: string; prompt: string
 }
 
 export interface ApiAnalyticsSummary {
@@ -111,5 +255,36 @@ export interface ApiAnalyticsSummary {
   average_session_duration_secs?: number
   top_tools: [string, number][]
   deep_work_sessions: number
-  today?: { sessions: number; messages: number; active_time: string; tool_calls: number; tool_errors: number }
+  today?: {
+    sessions: number; messages: number; active_time: string
+    tool_calls: number; tool_errors: number; quota_denials: number
+    thumbs_up: number; thumbs_down: number
+  }
+}
+
+/** Cumulative token usage of a session, with the configured budget. */
+export interface ApiSessionUsage {
+  prompt_tokens: number; completion_tokens: number; total_tokens: number
+  cost_usd: number; estimated: boolean
+  budget_usd?: number | null; budget_tokens?: number | null
+}
+
+export interface ApiGoalStatus {
+  name: string; metric: string; period: 'day' | 'week' | 'month'
+  period_start: string; period_end: string
+  value: number; target: string
+  state: 'met' | 'behind' | 'missed'
+  progress: number
+}
+
+export interface ApiTimeseriesPoint {
+  date: string; sessions: number; messages: number; tokens: number
+  tool_calls: number; tool_errors: number; quota_denials: number; error_rate: number
+  thumbs_up: number; thumbs_down: number
+  /** Share of rated answers rated thumbs up; null on days without ratings. */
+  approval_rate?: number | null
+}
+
+export interface ApiAnalyticsTimeseries {
+  days: number; points: ApiTimeseriesPoint[]
 }
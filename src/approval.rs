@@ -0,0 +1,190 @@
+//! Asking the user before tool calls run in the REPL.
+//!
+//! [`ApprovalHook`] is a `pre_tool` hook on the REPL's agent loop. While
+//! approval mode is on it hands each call to the REPL as an
+//! [`ApprovalRequest`] and waits; the REPL shows the tool and its
+//! arguments, reads y/n/always and replies. Tools answered "always" run
+//! without asking for the rest of the REPL run.
+
+use agent_core::hooks::{Hook, HookAction};
+use agent_core::types::ToolCall;
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+
+/// The user's answer to an approval prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allow,
+    Deny,
+    /// Allow this and every later call of the same tool.
+    Always,
+}
+
+/// A tool call waiting for the user.
+pub struct ApprovalRequest {
+    pub call: ToolCall,
+    pub reply: oneshot::Sender<Decision>,
+}
+
+pub struct ApprovalHook {
+    enabled: AtomicBool,
+    always: Mutex<HashSet<String>>,
+    requests: mpsc::UnboundedSender<ApprovalRequest>,
+}
+
+impl ApprovalHook {
+    /// The hook and the receiving end the REPL answers requests from.
+    pub fn new(enabled: bool) -> (Arc<Self>, mpsc::UnboundedReceiver<ApprovalRequest>) {
+        let (requests, rx) = mpsc::unbounded_channel();
+        let hook = Arc::new(Self {
+            enabled: AtomicBool::new(enabled),
+            always: Mutex::new(HashSet::new()),
+            requests,
+        });
+        (hook, rx)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Tools answered "always", sorted.
+    pub fn always_allowed(&self) -> Vec<String> {
+        let mut tools: Vec<String> = self.always.lock().unwrap().iter().cloned().collect();
+        tools.sort();
+        tools
+    }
+
+    /// Ask before every tool again.
+    pub fn forget_always(&self) {
+        self.always.lock().unwrap().clear();
+    }
+}
+
+#[async_trait]
+impl Hook for ApprovalHook {
+    fn name(&self) -> &str {
+        "approval"
+    }
+
+    async fn pre_tool(&self, call: &mut ToolCall) -> HookAction {
+        if !self.is_enabled() || self.always.lock().unwrap().contains(&call.name) {
+            return HookAction::Continue;
+        }
+        let (reply, answer) = oneshot::channel();
+        let request = ApprovalRequest {
+            call: call.clone(),
+            reply,
+        };
+        if self.requests.send(request).is_err() {
+            return HookAction::Veto("no one is available to approve it".into());
+        }
+        match answer.await {
+            Ok(Decision::Allow) => HookAction::Continue,
+            Ok(Decision::Always) => {
+                self.always.lock().unwrap().insert(call.name.clone());
+                HookAction::Continue
+            }
+            Ok(Decision::Deny) | Err(_) => HookAction::Veto("the user declined this call".into()),
+        }
+    }
+}
+
+/// Tool arguments as indented JSON, or as sent if they are not JSON.
+pub fn pretty_arguments(arguments: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(arguments)
+        .ok()
+        .and_then(|v| serde_json::to_string_pretty(&v).ok())
+        .unwrap_or_else(|| arguments.to_string())
+}
+
+/// Print a tool call indented under the assistant's output.
+pub fn print_call(name: &str, arguments: &str) {
+    println!("\n  \x1b[0;35m⚡ {}\x1b[0m", name);
+    for line in pretty_arguments(arguments).lines() {
+        println!("    \x1b[2m{}\x1b[0m", line);
+    }
+}
+
+/// Show a tool call and read y/n/always from the terminal, asking again
+/// until the answer is one of those. End of input counts as no.
+pub fn prompt(call: &ToolCall) -> Decision {
+    print_call(&call.name, &call.arguments);
+    loop {
+        print!("  Run it? [y]es / [n]o / [a]lways for {}: ", call.name);
+        let _ = std::io::stdout().flush();
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+            return Decision::Deny;
+        }
+        match parse_answer(&line) {
+            Some(decision) => return decision,
+            None => println!("  Please answer y, n or a."),
+        }
+    }
+}
+
+fn parse_answer(line: &str) -> Option<Decision> {
+    match line.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => Some(Decision::Allow),
+        "n" | "no" => Some(Decision::Deny),
+        "a" | "always" => Some(Decision::Always),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str) -> ToolCall {
+        ToolCall {
+            id: "call_1".into(),
+            name: name.into(),
+            arguments: r#"{"path":"a.txt"}"#.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_approval_hook_asks_and_remembers_always() {
+        let (hook, mut rx) = ApprovalHook::new(false);
+        assert_eq!(hook.pre_tool(&mut call("file_read")).await, HookAction::Continue);
+
+        hook.set_enabled(true);
+        let answers = tokio::spawn(async move {
+            for decision in [Decision::Deny, Decision::Always] {
+                let request = rx.recv().await.unwrap();
+                assert_eq!(request.call.name, "file_read");
+                request.reply.send(decision).unwrap();
+            }
+            rx
+        });
+        assert!(matches!(
+            hook.pre_tool(&mut call("file_read")).await,
+            HookAction::Veto(_)
+        ));
+        assert_eq!(hook.pre_tool(&mut call("file_read")).await, HookAction::Continue);
+        let _rx = answers.await.unwrap();
+        // Remembered: no request is sent this time.
+        assert_eq!(hook.pre_tool(&mut call("file_read")).await, HookAction::Continue);
+        assert_eq!(hook.always_allowed(), vec!["file_read".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_answer_and_pretty_arguments() {
+        assert_eq!(parse_answer(" Y\n"), Some(Decision::Allow));
+        assert_eq!(parse_answer("always"), Some(Decision::Always));
+        assert_eq!(parse_answer("no"), Some(Decision::Deny));
+        assert_eq!(parse_answer("maybe"), None);
+        assert_eq!(pretty_arguments(r#"{"a":1}"#), "{\n  \"a\": 1\n}");
+        assert_eq!(pretty_arguments("not json"), "not json");
+    }
+}
@@ -0,0 +1,59 @@
+//! `backup` subcommands: take an encrypted backup of sessions, skills and
+//! config, list the backups on the `[backup]` remote, and restore one.
+
+use crate::BackupAction;
+use agent_backup::{Backups, Sources, Targets};
+use agent_core::config::AppConfig;
+use anyhow::Result;
+use std::path::PathBuf;
+
+pub async fn handle_backup(
+    action: BackupAction,
+    config: &AppConfig,
+    config_path: PathBuf,
+) -> Result<()> {
+    let backups = Backups::from_config(&config.backup)?;
+    match action {
+        BackupAction::Now { json } => {
+            let report = backups
+                .backup(&Sources::for_config(config)?, chrono::Utc::now())
+                .await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                return Ok(());
+            }
+            println!("Backed up to {} ({} KiB)", report.name, report.size.div_ceil(1024));
+            for name in &report.removed {
+                println!("Removed old backup {}", name);
+            }
+        }
+        BackupAction::List { json } => {
+            let list = backups.list().await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&list)?);
+            } else if list.is_empty() {
+                println!("No backups.");
+            } else {
+                for backup in list {
+                    println!(
+                        "{}  {}",
+                        backup.name,
+                        backup.created_at.format("%Y-%m-%d %H:%M UTC")
+                    );
+                }
+            }
+        }
+        BackupAction::Restore { name, overwrite } => {
+            let targets = Targets::for_config(config, config_path);
+            let report = backups.restore(name.as_deref(), &targets, overwrite).await?;
+            println!("Restored {}: {} file(s) written", report.name, report.restored);
+            if report.skipped > 0 {
+                println!(
+                    "Kept {} existing file(s); pass --overwrite to replace them",
+                    report.skipped
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,283 @@
+//! Tab-completion, inline hints and multiline validation for the REPL.
+//!
+//! Completes slash commands, session ID prefixes for `/switch`, tool names
+//! for `/tools`, skill names for `/skills`, and relative paths inside the
+//! workspace (for `/context` and any word that looks like a path). A line
+//! ending in `\` is treated as incomplete, so input continues on the next line.
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Helper};
+use std::borrow::Cow;
+use std::path::{Component, Path, PathBuf};
+
+/// Slash commands offered for completion (aliases included).
+pub const COMMANDS: &[&str] = &[
+    "/new",
+    "/agents",
+    "/sessions",
+    "/ls",
+    "/switch",
+    "/tools",
+    "/skills",
+    "/plugins",
+    "/workspace",
+    "/context",
+    "/analytics",
+    "/shells",
+    "/config",
+    "/clear",
+    "/checkpoint",
+    "/checkpoints",
+    "/undo",
+    "/changes",
+    "/diff",
+    "/revert",
+    "/accept",
+    "/feedback",
+    "/approval",
+    "/verbose",
+    "/edit",
+    "/help",
+    "/exit",
+    "/quit",
+];
+
+/// rustyline helper holding the completion sources.
+///
+/// Sessions, tools and skills change while the REPL runs, so the loop
+/// refreshes them via [`ReplHelper::set_sessions`] and friends.
+pub struct ReplHelper {
+    /// `(id, name)` of each known session.
+    sessions: Vec<(String, String)>,
+    tools: Vec<String>,
+    skills: Vec<String>,
+    workspace_root: PathBuf,
+}
+
+impl ReplHelper {
+    pub fn new(workspace_root: PathBuf) -> Self {
+        Self {
+            sessions: Vec::new(),
+            tools: Vec::new(),
+            skills: Vec::new(),
+            workspace_root,
+        }
+    }
+
+    pub fn set_sessions(&mut self, sessions: Vec<(String, String)>) {
+        self.sessions = sessions;
+    }
+
+    pub fn set_tools(&mut self, tools: Vec<String>) {
+        self.tools = tools;
+    }
+
+    pub fn set_skills(&mut self, skills: Vec<String>) {
+        self.skills = skills;
+    }
+
+    fn complete_line(&self, line: &str) -> (usize, Vec<Pair>) {
+        let word_start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[word_start..];
+
+        if line.starts_with('/') {
+            match line.split_once(' ') {
+                None => {
+                    let pairs = COMMANDS
+                        .iter()
+                        .filter(|c| c.starts_with(line))
+                        .map(|c| simple_pair(&format!("{c} ")))
+                        .collect();
+                    return (0, pairs);
+                }
+                Some((cmd, _)) if word_start > cmd.len() + 1 => {
+                    // Past the first argument; only paths make sense here.
+                    return (word_start, self.complete_path(word));
+                }
+                Some(("/switch", _)) => {
+                    let pairs = self
+                        .sessions
+                        .iter()
+                        .filter(|(id, _)| id.starts_with(word))
+                        .map(|(id, name)| Pair {
+                            display: format!("{} {}", &id[..id.len().min(8)], name),
+                            replacement: id[..id.len().min(8)].to_string(),
+                        })
+                        .collect();
+                    return (word_start, pairs);
+                }
+                Some(("/tools", _)) => return (word_start, prefix_pairs(&self.tools, word)),
+                Some(("/skills", _)) => return (word_start, prefix_pairs(&self.skills, word)),
+                Some(("/context", _)) => return (word_start, self.complete_path(word)),
+                Some(_) => return (word_start, Vec::new()),
+            }
+        }
+
+        if word.contains('/') || word.starts_with('.') {
+            return (word_start, self.complete_path(word));
+        }
+        (word_start, Vec::new())
+    }
+
+    /// Complete a relative path under the workspace root. Absolute paths and
+    /// `..` components are not completed, so suggestions stay in the workspace.
+    fn complete_path(&self, word: &str) -> Vec<Pair> {
+        let (dir, prefix) = match word.rfind('/') {
+            Some(i) => (&word[..=i], &word[i + 1..]),
+            None => ("", word),
+        };
+        let escapes = Path::new(dir)
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+        if escapes {
+            return Vec::new();
+        }
+
+        let Ok(entries) = std::fs::read_dir(self.workspace_root.join(dir)) else {
+            return Vec::new();
+        };
+        let mut pairs: Vec<Pair> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                    return None;
+                }
+                let suffix = if entry.file_type().is_ok_and(|t| t.is_dir()) { "/" } else { "" };
+                Some(Pair {
+                    display: format!("{name}{suffix}"),
+                    replacement: format!("{dir}{name}{suffix}"),
+                })
+            })
+            .collect();
+        pairs.sort_by(|a, b| a.display.cmp(&b.display));
+        pairs
+    }
+}
+
+fn simple_pair(s: &str) -> Pair {
+    Pair {
+        display: s.trim_end().to_string(),
+        replacement: s.to_string(),
+    }
+}
+
+fn prefix_pairs(items: &[String], prefix: &str) -> Vec<Pair> {
+    items
+        .iter()
+        .filter(|s| s.starts_with(prefix))
+        .map(|s| simple_pair(s))
+        .collect()
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        Ok(self.complete_line(&line[..pos]))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+
+    /// Hint the rest of a slash command once the prefix is unambiguous.
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<String> {
+        if pos < line.len() || !line.starts_with('/') || line.contains(' ') {
+            return None;
+        }
+        let mut matches = COMMANDS.iter().filter(|c| c.starts_with(line));
+        match (matches.next(), matches.next()) {
+            (Some(only), None) if only.len() > line.len() => Some(only[line.len()..].to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(format!("\x1b[2m{hint}\x1b[0m"))
+    }
+}
+
+impl Validator for ReplHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        if ctx.input().ends_with('\\') {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
+    }
+}
+
+/// Remove backslash line continuations, keeping the line breaks.
+pub fn join_continuations(input: &str) -> String {
+    input.replace("\\\n", "\n")
+}
+
+impl Helper for ReplHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn helper(root: &Path) -> ReplHelper {
+        let mut h = ReplHelper::new(root.to_path_buf());
+        h.set_sessions(vec![(
+            "1234abcd-0000-0000-0000-000000000000".into(),
+            "work".into(),
+        )]);
+        h.set_tools(vec!["file_read".into(), "file_write".into(), "shell".into()]);
+        h
+    }
+
+    fn replacements(pairs: &[Pair]) -> Vec<&str> {
+        pairs.iter().map(|p| p.replacement.as_str()).collect()
+    }
+
+    #[test]
+    fn test_completes_commands_and_arguments() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let h = helper(tmp.path());
+
+        let (start, pairs) = h.complete_line("/sw");
+        assert_eq!((start, replacements(&pairs)), (0, vec!["/switch "]));
+
+        let (start, pairs) = h.complete_line("/switch 12");
+        assert_eq!((start, replacements(&pairs)), (8, vec!["1234abcd"]));
+
+        let (_, pairs) = h.complete_line("/tools file_");
+        assert_eq!(replacements(&pairs), vec!["file_read", "file_write"]);
+    }
+
+    #[test]
+    fn test_completes_paths_inside_workspace() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(tmp.path().join("src")).unwrap();
+        std::fs::write(tmp.path().join("src/main.rs"), "").unwrap();
+        let h = helper(tmp.path());
+
+        let (start, pairs) = h.complete_line("explain ./sr");
+        assert_eq!((start, replacements(&pairs)), (8, vec!["./src/"]));
+
+        let (_, pairs) = h.complete_line("/context src/m");
+        assert_eq!(replacements(&pairs), vec!["src/main.rs"]);
+
+        assert!(h.complete_line("read ../").1.is_empty());
+        assert!(h.complete_line("read /etc/").1.is_empty());
+    }
+
+    #[test]
+    fn test_join_continuations() {
+        assert_eq!(join_continuations("fn main() {\\\n}"), "fn main() {\n}");
+        assert_eq!(join_continuations("a\nb"), "a\nb");
+    }
+}
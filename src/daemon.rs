@@ -0,0 +1,203 @@
+//! Background server management (`serve --daemon` and `stop`).
+//!
+//! `--daemon` re-launches the current executable detached from the terminal
+//! rather than forking, since forking a running Tokio runtime is unsound. The
+//! server process owns the PID file through [`PidGuard`]; `stop` sends it
+//! SIGTERM and waits for the graceful shutdown to finish.
+
+use agent_core::config::AppConfig;
+use anyhow::{bail, Context, Result};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long `stop` waits for the server to exit (drain timeout plus margin).
+const STOP_TIMEOUT: Duration = Duration::from_secs(35);
+
+/// Default PID file: `<data_dir>/agent-shell.pid`.
+pub fn pid_file() -> PathBuf {
+    AppConfig::data_dir().join("agent-shell.pid")
+}
+
+/// Log file for a daemonized server and for the TUI.
+pub fn log_file() -> PathBuf {
+    AppConfig::data_dir().join("agent-shell.log")
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 checks for existence without delivering anything.
+    let alive = unsafe { libc::kill(pid, 0) } == 0;
+    alive || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    // No cheap liveness check; assume the recorded process is alive.
+    true
+}
+
+/// PID file held for the lifetime of a running server; removed on drop.
+pub struct PidGuard {
+    path: PathBuf,
+}
+
+impl PidGuard {
+    /// Record the current process in `path`. Fails if another live server
+    /// already holds it; a stale file from a crashed server is replaced.
+    pub fn acquire(path: PathBuf) -> Result<Self> {
+        if let Some(pid) = read_pid(&path) {
+            if pid != std::process::id() && is_running(pid) {
+                bail!(
+                    "Server already running (pid {}). Stop it with `agent-shell stop`.",
+                    pid
+                );
+            }
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, format!("{}\n", std::process::id()))
+            .with_context(|| format!("Failed to write PID file {}", path.display()))?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for PidGuard {
+    fn drop(&mut self) {
+        if read_pid(&self.path) == Some(std::process::id()) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Re-run the current command line without `--daemon`, detached, with
+/// output appended to [`log_file`].
+pub fn spawn_background() -> Result<()> {
+    if let Some(pid) = read_pid(&pid_file()).filter(|pid| is_running(*pid)) {
+        bail!("Server already running (pid {}).", pid);
+    }
+
+    let log_path = log_file();
+    if let Some(parent) = log_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .with_context(|| format!("Failed to open log file {}", log_path.display()))?;
+
+    let args = std::env::args_os().skip(1).filter(|a| a != "--daemon");
+    let mut cmd = Command::new(std::env::current_exe()?);
+    cmd.args(args)
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    #[cfg(unix)]
+    {
+        // Own process group, so Ctrl+C in the launching terminal doesn't reach it.
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    let mut child = cmd.spawn().context("Failed to start background server")?;
+
+    // Catch immediate failures (bad config, port in use) before reporting success.
+    std::thread::sleep(Duration::from_millis(500));
+    if let Some(status) = child.try_wait()? {
+        bail!(
+            "Background server exited immediately ({}). See {}",
+            status,
+            log_path.display()
+        );
+    }
+
+    println!(
+        "Server started in background (pid {}). Logs: {}",
+        child.id(),
+        log_path.display()
+    );
+    Ok(())
+}
+
+/// Ask the server recorded in the PID file to shut down and wait for it.
+pub fn stop() -> Result<()> {
+    let path = pid_file();
+    let Some(pid) = read_pid(&path) else {
+        bail!("No running server (no PID file at {}).", path.display());
+    };
+    if !is_running(pid) {
+        let _ = std::fs::remove_file(&path);
+        bail!("No running server (removed stale PID file for pid {}).", pid);
+    }
+
+    terminate(pid)?;
+    println!("Sent shutdown signal to pid {}; waiting for it to exit…", pid);
+
+    let start = Instant::now();
+    while is_running(pid) && read_pid(&path) == Some(pid) {
+        if start.elapsed() > STOP_TIMEOUT {
+            bail!("Server (pid {}) did not exit within {:?}.", pid, STOP_TIMEOUT);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    println!("Server stopped.");
+    Ok(())
+}
+
+#[cfg(unix)]
+fn terminate(pid: u32) -> Result<()> {
+    let pid = libc::pid_t::try_from(pid)?;
+    if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to signal server");
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn terminate(pid: u32) -> Result<()> {
+    let status = Command::new("taskkill")
+        .args(["/PID", &pid.to_string()])
+        .status()?;
+    if !status.success() {
+        bail!("taskkill failed for pid {}", pid);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pid_guard_lifecycle() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("agent-shell.pid");
+
+        let guard = PidGuard::acquire(path.clone()).unwrap();
+        assert_eq!(read_pid(&path), Some(std::process::id()));
+        drop(guard);
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pid_guard_replaces_stale_file() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("agent-shell.pid");
+        // Above any real pid_max, so never a live process.
+        std::fs::write(&path, "2147483646\n").unwrap();
+
+        let _guard = PidGuard::acquire(path.clone()).unwrap();
+        assert_eq!(read_pid(&path), Some(std::process::id()));
+    }
+}
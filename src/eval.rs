@@ -0,0 +1,531 @@
+//! Benchmark harness (`agent-shell eval`).
+//!
+//! Every `*.toml` file in a directory is a task: a prompt, the tools the
+//! agent may use and assertions on its answer and tool calls. Each task is
+//! run once per provider, without failover, and scored pass/fail along
+//! with the tokens, cost and wall-clock time it took; the report compares
+//! the providers side by side.
+//!
+//! ```toml
+//! prompt = "What license is this project under? Check the LICENSE file."
+//! allowed_tools = ["file_read"]       # default: every enabled tool
+//! workspace = "fixtures/repo"         # relative to the task file
+//! timeout_secs = 120
+//!
+//! [[assert]]
+//! type = "contains"                   # also not_contains, regex, tool_called,
+//! value = "MIT"                       # tool_not_called, max_tool_calls
+//! ```
+
+use crate::ReportFormat;
+use agent_core::agent_loop::AgentLoop;
+use agent_core::config::AppConfig;
+use agent_core::tool_registry::ToolRegistry;
+use agent_core::types::{AgentEvent, Message};
+use agent_skills::SkillIndexer;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Exit status: every task passed on every provider.
+pub const EXIT_OK: i32 = 0;
+/// Exit status: at least one task failed.
+pub const EXIT_FAILED: i32 = 1;
+/// Exit status: no tasks could be loaded or a provider is unknown.
+pub const EXIT_USAGE: i32 = 2;
+
+fn default_timeout() -> u64 {
+    300
+}
+
+/// One task file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalTask {
+    /// Defaults to the file name without `.toml`.
+    #[serde(default)]
+    pub name: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub allowed_tools: Option<Vec<String>>,
+    #[serde(default)]
+    pub workspace: Option<PathBuf>,
+    #[serde(default = "default_timeout")]
+    pub timeout_secs: u64,
+    #[serde(default, rename = "assert")]
+    pub assertions: Vec<Assertion>,
+}
+
+/// A check on the outcome of a task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Assertion {
+    /// The answer contains `value`, ignoring case.
+    Contains { value: String },
+    NotContains { value: String },
+    /// The answer matches the regular expression.
+    Regex { pattern: String },
+    ToolCalled { tool: String },
+    ToolNotCalled { tool: String },
+    MaxToolCalls { count: usize },
+}
+
+impl Assertion {
+    /// Why an answer and the tools called fail this assertion, if they do.
+    fn failure(&self, answer: &str, tools: &[String]) -> Option<String> {
+        let answer_lower = answer.to_lowercase();
+        match self {
+            Self::Contains { value } => (!answer_lower.contains(&value.to_lowercase()))
+                .then(|| format!("answer does not contain {:?}", value)),
+            Self::NotContains { value } => answer_lower
+                .contains(&value.to_lowercase())
+                .then(|| format!("answer contains {:?}", value)),
+            Self::Regex { pattern } => match regex::Regex::new(pattern) {
+                Ok(re) => (!re.is_match(answer))
+                    .then(|| format!("answer does not match /{}/", pattern)),
+                Err(e) => Some(format!("invalid regex /{}/: {}", pattern, e)),
+            },
+            Self::ToolCalled { tool } => (!tools.contains(tool))
+                .then(|| format!("{} was not called", tool)),
+            Self::ToolNotCalled { tool } => tools
+                .contains(tool)
+                .then(|| format!("{} was called", tool)),
+            Self::MaxToolCalls { count } => (tools.len() > *count)
+                .then(|| format!("{} tool calls (at most {} expected)", tools.len(), count)),
+        }
+    }
+}
+
+/// How one task went on one provider.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskResult {
+    pub task: String,
+    pub provider: String,
+    pub passed: bool,
+    /// Failed assertions, or the error that stopped the run.
+    pub failures: Vec<String>,
+    pub answer: String,
+    pub tool_calls: Vec<String>,
+    pub latency_ms: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Totals for one provider.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderSummary {
+    pub provider: String,
+    pub model: String,
+    pub passed: usize,
+    pub total: usize,
+    pub tokens: u64,
+    pub mean_latency_ms: u64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    pub providers: Vec<ProviderSummary>,
+    pub results: Vec<TaskResult>,
+}
+
+/// Task files in `path` (or `path` itself), sorted by file name. Regular
+/// expressions are checked here so a typo fails before any model call.
+pub fn load_tasks(path: &Path) -> Result<Vec<(PathBuf, EvalTask)>> {
+    let files = if path.is_dir() {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+    files
+        .into_iter()
+        .map(|file| {
+            let text = std::fs::read_to_string(&file)
+                .with_context(|| format!("Cannot read {}", file.display()))?;
+            let mut task: EvalTask =
+                toml::from_str(&text).with_context(|| format!("Invalid task {}", file.display()))?;
+            if task.name.is_empty() {
+                task.name = file
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+            }
+            for assertion in &task.assertions {
+                if let Assertion::Regex { pattern } = assertion {
+                    regex::Regex::new(pattern)
+                        .with_context(|| format!("Invalid regex in {}", file.display()))?;
+                }
+            }
+            Ok((file, task))
+        })
+        .collect()
+}
+
+/// One config per provider to compare, each with only that provider so a
+/// failure is not hidden by failover. `names` picks providers from
+/// `[[providers]]`; empty means all of them.
+pub fn provider_configs(config: &AppConfig, names: &[String]) -> Result<Vec<(String, AppConfig)>> {
+    if config.providers.is_empty() {
+        if let Some(name) = names.iter().find(|n| n.as_str() != "default") {
+            bail!("Unknown provider '{}': only [provider] (\"default\") is configured", name);
+        }
+        let mut single = config.clone();
+        single.provider.failover.clear();
+        return Ok(vec![("default".to_string(), single)]);
+    }
+    if let Some(name) = names.iter().find(|n| !config.providers.iter().any(|p| &p.name == *n)) {
+        let known: Vec<&str> = config.providers.iter().map(|p| p.name.as_str()).collect();
+        bail!("Unknown provider '{}' (configured: {})", name, known.join(", "));
+    }
+    Ok(config
+        .providers
+        .iter()
+        .filter(|p| names.is_empty() || names.contains(&p.name))
+        .map(|p| {
+            let mut single = config.clone();
+            single.providers = vec![p.clone()];
+            (p.name.clone(), single)
+        })
+        .collect())
+}
+
+fn model_of(config: &AppConfig) -> String {
+    config
+        .providers
+        .first()
+        .map(|p| p.model.clone())
+        .unwrap_or_else(|| config.provider.model.clone())
+}
+
+/// Run `task` (read from `file`) with `config`'s provider.
+async fn run_task(
+    config: &AppConfig,
+    provider: &str,
+    skill_indexer: Arc<SkillIndexer>,
+    file: &Path,
+    task: &EvalTask,
+) -> TaskResult {
+    let mut result = TaskResult {
+        task: task.name.clone(),
+        provider: provider.to_string(),
+        passed: false,
+        failures: Vec::new(),
+        answer: String::new(),
+        tool_calls: Vec::new(),
+        latency_ms: 0,
+        prompt_tokens: 0,
+        completion_tokens: 0,
+        cost_usd: 0.0,
+    };
+
+    let mut config = config.clone();
+    if let Some(workspace) = &task.workspace {
+        let root = file.parent().unwrap_or(Path::new(".")).join(workspace);
+        if !root.is_dir() {
+            result.failures.push(format!("workspace {} is not a directory", root.display()));
+            return result;
+        }
+        config.sandbox.workspace_root = Some(root);
+    }
+    // File tools are bound to the workspace root when they are built.
+    let mut registry = ToolRegistry::new();
+    agent_tools::register_all(&mut registry, &config, Some(skill_indexer));
+    let agent = match AgentLoop::new(config, Arc::new(registry)) {
+        Ok(agent) => agent,
+        Err(e) => {
+            result.failures.push(e.to_string());
+            return result;
+        }
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<AgentEvent>();
+    let collect = tokio::spawn(async move {
+        let mut tools = Vec::new();
+        while let Some(event) = rx.recv().await {
+            if let AgentEvent::ToolCallStart { name, .. } = event {
+                tools.push(name);
+            }
+        }
+        tools
+    });
+    let messages = vec![Message::user(task.prompt.clone())];
+    let started = Instant::now();
+    let outcome = tokio::time::timeout(
+        Duration::from_secs(task.timeout_secs),
+        agent.run(&messages, task.allowed_tools.as_deref(), &[], &Default::default(), tx),
+    )
+    .await;
+    result.latency_ms = started.elapsed().as_millis() as u64;
+    result.tool_calls = collect.await.unwrap_or_default();
+
+    match outcome {
+        Ok(Ok(turn)) => {
+            result.answer = turn.final_message().content.clone();
+            result.prompt_tokens = turn.usage.prompt_tokens;
+            result.completion_tokens = turn.usage.completion_tokens;
+            result.cost_usd = turn.usage.cost_usd;
+            result.failures = task
+                .assertions
+                .iter()
+                .filter_map(|a| a.failure(&result.answer, &result.tool_calls))
+                .collect();
+            result.passed = result.failures.is_empty();
+        }
+        Ok(Err(e)) => result.failures.push(format!("error: {}", e)),
+        Err(_) => result
+            .failures
+            .push(format!("timed out after {}s", task.timeout_secs)),
+    }
+    result
+}
+
+fn summarize(provider: &str, model: String, results: &[TaskResult]) -> ProviderSummary {
+    let mine: Vec<&TaskResult> = results.iter().filter(|r| r.provider == provider).collect();
+    let total = mine.len();
+    ProviderSummary {
+        provider: provider.to_string(),
+        model,
+        passed: mine.iter().filter(|r| r.passed).count(),
+        total,
+        tokens: mine.iter().map(|r| r.prompt_tokens + r.completion_tokens).sum(),
+        mean_latency_ms: mine.iter().map(|r| r.latency_ms).sum::<u64>() / total.max(1) as u64,
+        cost_usd: mine.iter().map(|r| r.cost_usd).sum(),
+    }
+}
+
+fn seconds(ms: u64) -> String {
+    format!("{:.1}s", ms as f64 / 1000.0)
+}
+
+/// Tasks down the side, providers across; totals, then what failed.
+fn render_table(report: &EvalReport, markdown: bool) -> String {
+    let mut tasks: Vec<&str> = Vec::new();
+    for r in &report.results {
+        if !tasks.contains(&r.task.as_str()) {
+            tasks.push(&r.task);
+        }
+    }
+    let mut rows: Vec<Vec<String>> = vec![std::iter::once("Task".to_string())
+        .chain(report.providers.iter().map(|p| format!("{} ({})", p.provider, p.model)))
+        .collect()];
+    for task in &tasks {
+        let mut row = vec![task.to_string()];
+        for p in &report.providers {
+            let cell = report
+                .results
+                .iter()
+                .find(|r| r.task == *task && r.provider == p.provider)
+                .map(|r| {
+                    format!(
+                        "{} {} {} tok",
+                        if r.passed { "pass" } else { "FAIL" },
+                        seconds(r.latency_ms),
+                        r.prompt_tokens + r.completion_tokens
+                    )
+                })
+                .unwrap_or_default();
+            row.push(cell);
+        }
+        rows.push(row);
+    }
+    for label in ["Passed", "Tokens", "Mean latency", "Cost"] {
+        let mut row = vec![label.to_string()];
+        row.extend(report.providers.iter().map(|p| match label {
+            "Passed" => format!("{}/{}", p.passed, p.total),
+            "Tokens" => p.tokens.to_string(),
+            "Mean latency" => seconds(p.mean_latency_ms),
+            _ => format!("${:.4}", p.cost_usd),
+        }));
+        rows.push(row);
+    }
+
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|i| rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0))
+        .collect();
+    let mut out = String::new();
+    for (n, row) in rows.iter().enumerate() {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, w)| format!("{:<w$}", cell, w = *w))
+            .collect();
+        if markdown {
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+            if n == 0 {
+                let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+                out.push_str(&format!("| {} |\n", rule.join(" | ")));
+            }
+        } else {
+            out.push_str(cells.join("  ").trim_end());
+            out.push('\n');
+            if n == tasks.len() {
+                out.push('\n');
+            }
+        }
+    }
+
+    let failed: Vec<&TaskResult> = report.results.iter().filter(|r| !r.passed).collect();
+    if !failed.is_empty() {
+        out.push_str(if markdown { "\n**Failures**\n\n" } else { "\nFailures:\n" });
+        for r in failed {
+            out.push_str(&format!(
+                "{}{} on {}: {}\n",
+                if markdown { "- " } else { "  " },
+                r.task,
+                r.provider,
+                r.failures.join("; ")
+            ));
+        }
+    }
+    out
+}
+
+/// Run the tasks in `path` against `providers` and return the process exit
+/// code.
+pub async fn run(
+    config: AppConfig,
+    skill_indexer: Arc<SkillIndexer>,
+    path: PathBuf,
+    providers: Vec<String>,
+    format: ReportFormat,
+    out: Option<PathBuf>,
+) -> Result<i32> {
+    let (tasks, configs) = match load_tasks(&path).and_then(|tasks| {
+        if tasks.is_empty() {
+            bail!("No task files (*.toml) in {}", path.display());
+        }
+        Ok((tasks, provider_configs(&config, &providers)?))
+    }) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            return Ok(EXIT_USAGE);
+        }
+    };
+
+    let mut results = Vec::new();
+    for (provider, provider_config) in &configs {
+        for (file, task) in &tasks {
+            eprint!("{} on {} ... ", task.name, provider);
+            let result =
+                run_task(provider_config, provider, skill_indexer.clone(), file, task).await;
+            eprintln!(
+                "{} ({})",
+                if result.passed { "pass" } else { "FAIL" },
+                seconds(result.latency_ms)
+            );
+            results.push(result);
+        }
+    }
+    let report = EvalReport {
+        providers: configs
+            .iter()
+            .map(|(name, c)| summarize(name, model_of(c), &results))
+            .collect(),
+        results,
+    };
+
+    let rendered = match format {
+        ReportFormat::Text => render_table(&report, false),
+        ReportFormat::Markdown => render_table(&report, true),
+        ReportFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+    };
+    match out {
+        Some(path) => {
+            std::fs::write(&path, rendered)?;
+            eprintln!("Wrote {}", path.display());
+        }
+        None => print!("{}", rendered),
+    }
+
+    let all_passed = report.results.iter().all(|r| r.passed);
+    Ok(if all_passed { EXIT_OK } else { EXIT_FAILED })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::config::ProviderKind;
+
+    #[test]
+    fn test_assertions() {
+        let tools = vec!["file_read".to_string()];
+        let check = |a: Assertion| a.failure("Licensed under the MIT license.", &tools);
+        assert_eq!(check(Assertion::Contains { value: "mit".into() }), None);
+        assert!(check(Assertion::NotContains { value: "MIT".into() }).is_some());
+        assert_eq!(check(Assertion::Regex { pattern: r"\bMIT\b".into() }), None);
+        assert_eq!(check(Assertion::ToolCalled { tool: "file_read".into() }), None);
+        assert_eq!(
+            check(Assertion::ToolNotCalled { tool: "file_read".into() }).as_deref(),
+            Some("file_read was called")
+        );
+        assert!(check(Assertion::MaxToolCalls { count: 0 }).is_some());
+    }
+
+    #[test]
+    fn test_provider_configs() {
+        let config = AppConfig::default();
+        let single = provider_configs(&config, &[]).unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].0, "default");
+        assert!(provider_configs(&config, &["gateway".into()]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_eval_tasks_against_mock_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let scenario = dir.path().join("scenario.yaml");
+        std::fs::write(&scenario, "turns:\n  - responses:\n      - content: \"It is MIT.\"\n").unwrap();
+        let tasks = dir.path().join("tasks");
+        std::fs::create_dir(&tasks).unwrap();
+        std::fs::write(
+            tasks.join("license.toml"),
+            "prompt = \"Which license?\"\n[[assert]]\ntype = \"contains\"\nvalue = \"mit\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            tasks.join("tools.toml"),
+            "prompt = \"Read it\"\n[[assert]]\ntype = \"tool_called\"\ntool = \"file_read\"\n",
+        )
+        .unwrap();
+        std::fs::write(tasks.join("notes.txt"), "not a task").unwrap();
+
+        let mut config = AppConfig::default();
+        config.provider.kind = ProviderKind::Mock;
+        config.provider.scenario = Some(scenario);
+        config.session.history_dir = Some(dir.path().join("sessions"));
+        let skills = Arc::new(SkillIndexer::new(dir.path().join("skills")));
+
+        let loaded = load_tasks(&tasks).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].1.name, "license");
+
+        let (provider, provider_config) = &provider_configs(&config, &[]).unwrap()[0];
+        let mut results = Vec::new();
+        for (file, task) in &loaded {
+            results.push(run_task(provider_config, provider, skills.clone(), file, task).await);
+        }
+        assert!(results[0].passed, "{:?}", results[0].failures);
+        assert!(!results[1].passed);
+        assert_eq!(results[1].failures, vec!["file_read was not called".to_string()]);
+
+        let report = EvalReport {
+            providers: vec![summarize(provider, model_of(provider_config), &results)],
+            results,
+        };
+        assert_eq!(report.providers[0].passed, 1);
+        let table = render_table(&report, true);
+        assert!(table.contains("| Passed "));
+        assert!(table.contains("- tools on default: file_read was not called"));
+    }
+}
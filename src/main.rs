@@ -1,15 +1,31 @@
+mod approval;
+mod backup;
+mod completion;
+mod daemon;
+mod eval;
+mod oneshot;
+mod plugins;
+mod render;
 mod repl;
+mod repl_tools;
+mod review;
+mod schedule_runner;
+mod session_picker;
+mod subcommands;
+mod tui;
 
 use agent_core::config::AppConfig;
-use agent_core::scheduler::{ScheduledTask, Scheduler};
+use agent_core::context::DiffTarget;
+use agent_core::scheduler::{ScheduleHistory, Scheduler};
 use agent_core::tool_registry::ToolRegistry;
-use agent_plugins::PluginRegistry;
 use agent_skills::SkillIndexer;
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
 use tracing_subscriber::EnvFilter;
 
 #[derive(Parser)]
@@ -36,6 +52,14 @@ struct Cli {
     #[arg(short, long, global = true)]
     verbose: bool,
 
+    /// Print raw model output without markdown styling (implied when stdout is not a terminal)
+    #[arg(long, global = true)]
+    plain: bool,
+
+    /// Offer only tools that read: no file writes, code execution or git commits (`[tools] read_only`)
+    #[arg(long, global = true)]
+    read_only: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -49,6 +73,68 @@ enum Commands {
         session: Option<String>,
     },
 
+    /// Start the full-screen terminal UI (chat, tool output, sessions, analytics)
+    Tui {
+        /// Session name to create or resume
+        #[arg(short, long)]
+        session: Option<String>,
+    },
+
+    /// Run a single prompt non-interactively and exit
+    ///
+    /// Piped stdin is appended to the prompt, e.g. `cat error.log | agent-shell run "explain"`.
+    /// Exits 0 on success, 1 if the agent failed, 2 if no prompt was given.
+    Run {
+        /// Prompt text (optional when input is piped on stdin)
+        prompt: Option<String>,
+        /// Print a JSON report with the answer and tool calls
+        #[arg(long)]
+        json: bool,
+        /// Save the turn to a new session with this name
+        #[arg(short, long)]
+        session: Option<String>,
+    },
+
+    /// Review git changes and report findings (file, line, severity, suggestion)
+    ///
+    /// Reviews uncommitted changes by default. Uses the `reviewer` agent preset
+    /// when one is defined. Exits 0 on success, 1 if the agent failed, 2 if the
+    /// diff could not be read, 3 if a finding reached `--fail-on` (for git hooks).
+    Review {
+        /// Review only staged changes (e.g. from a pre-commit hook)
+        #[arg(long, conflicts_with = "range")]
+        staged: bool,
+        /// Review a revision range, e.g. `main..HEAD`
+        #[arg(long)]
+        range: Option<String>,
+        /// Agent preset to review with
+        #[arg(short, long)]
+        agent: Option<String>,
+        #[arg(short, long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        /// Exit 3 if any finding is at least this severe
+        #[arg(long, value_enum)]
+        fail_on: Option<review::Severity>,
+    },
+
+    /// Run evaluation tasks against providers and compare the results
+    ///
+    /// Each `*.toml` file in the directory is a task: a prompt, the tools it may
+    /// use and assertions on the answer and tool calls. Exits 0 if every task
+    /// passed, 1 if any failed, 2 if no tasks were found or a provider is unknown.
+    Eval {
+        /// Directory of task files, or a single task file
+        path: PathBuf,
+        /// `[[providers]]` entry to run against; repeat to compare (default: all)
+        #[arg(short, long = "provider")]
+        providers: Vec<String>,
+        #[arg(short, long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        /// Write the report to a file instead of stdout
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+
     /// Start the HTTP/WebSocket server
     Serve {
         /// Bind host
@@ -57,13 +143,88 @@ enum Commands {
         /// Bind port
         #[arg(long)]
         port: Option<u16>,
+        /// Listen on a Unix domain socket (Windows: named pipe) instead of TCP
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+        /// Run in the background, writing a PID file (stop with `agent-shell stop`)
+        #[arg(long)]
+        daemon: bool,
     },
 
+    /// Stop a server started with `serve` (e.g. `serve --daemon`)
+    Stop,
+
     /// Show or manage configuration
     Config {
         #[command(subcommand)]
         action: Option<ConfigAction>,
     },
+
+    /// List, show, export, delete or prune saved sessions
+    Sessions {
+        #[command(subcommand)]
+        action: SessionsAction,
+    },
+
+    /// List, search or validate skills
+    Skills {
+        #[command(subcommand)]
+        action: SkillsAction,
+    },
+
+    /// Search the plugin index, install plugins, or list, start and stop them
+    Plugin {
+        #[command(subcommand)]
+        action: PluginAction,
+    },
+
+    /// Print usage analytics from saved sessions
+    Analytics {
+        #[command(subcommand)]
+        action: AnalyticsAction,
+    },
+
+    /// Export sessions as fine-tuning datasets
+    Dataset {
+        #[command(subcommand)]
+        action: DatasetAction,
+    },
+
+    /// Import conversation history from ChatGPT, Claude or aider as sessions
+    ///
+    /// Takes a ChatGPT or Claude data export (the .zip or its conversations.json)
+    /// or an aider .aider.chat.history.md. Conversations imported before are skipped.
+    Import {
+        /// Export file
+        path: PathBuf,
+        /// `chatgpt`, `claude` or `aider`
+        #[arg(long)]
+        from: agent_core::import::ImportSource,
+        /// Only print what would be imported
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Back up sessions, skills and config to the [backup] remote, or restore them
+    Backup {
+        #[command(subcommand)]
+        action: BackupAction,
+    },
+
+    /// Print a shell completion script
+    ///
+    /// e.g. `agent-shell completions zsh > ~/.zfunc/_agent-shell`
+    Completions {
+        shell: clap_complete::Shell,
+    },
+
+    /// Write man pages for every command to a directory
+    #[command(hide = true)]
+    Mangen {
+        /// Output directory
+        #[arg(default_value = "man")]
+        out_dir: PathBuf,
+    },
 }
 
 #[derive(Subcommand)]
@@ -76,19 +237,269 @@ enum ConfigAction {
     Path,
 }
 
+#[derive(Subcommand)]
+pub(crate) enum SessionsAction {
+    /// List sessions, most recently updated first
+    #[command(alias = "ls")]
+    List {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a session's transcript
+    Show {
+        /// Session ID or unique prefix
+        id: String,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Export a session as Markdown or JSON
+    Export {
+        /// Session ID or unique prefix
+        id: String,
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Markdown)]
+        format: ExportFormat,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Delete a session
+    #[command(alias = "rm")]
+    Delete {
+        /// Session ID or unique prefix
+        id: String,
+    },
+    /// Archive or delete sessions over [session] retention_days / max_sessions
+    Prune {
+        /// Only list the sessions that would be pruned
+        #[arg(long)]
+        dry_run: bool,
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum DatasetAction {
+    /// Write one training example per session, with secrets and personal data redacted
+    Export {
+        /// Session IDs or unique prefixes (default: every session that passes the filters)
+        sessions: Vec<String>,
+        #[arg(short, long, value_enum, default_value_t = DatasetFormat::OpenaiJsonl)]
+        format: DatasetFormat,
+        /// Only sessions with this tag; repeat for any of several
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Only sessions updated on or after this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_parser = subcommands::parse_date)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        /// Only sessions updated before this date
+        #[arg(long, value_parser = subcommands::parse_date)]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        /// Only sessions that end with an answer and have no failed tool calls
+        #[arg(long)]
+        successful: bool,
+        /// Keep secrets and personal data as they are
+        #[arg(long)]
+        no_redact: bool,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub(crate) enum DatasetFormat {
+    /// `{"messages": [...]}` per line, for OpenAI-compatible fine-tuning
+    OpenaiJsonl,
+    /// `{"conversations": [{"from", "value"}]}` per line
+    Sharegpt,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub(crate) enum ExportFormat {
+    #[value(name = "md", alias = "markdown")]
+    Markdown,
+    Json,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub(crate) enum ReportFormat {
+    Text,
+    Json,
+    #[value(name = "md", alias = "markdown")]
+    Markdown,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum SkillsAction {
+    /// List loaded skills
+    List {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Search skill content
+    Search {
+        query: String,
+        /// Maximum number of results
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check skills for errors; exits 1 if any are found
+    Validate {
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum BackupAction {
+    /// Take an encrypted backup now
+    Now {
+        #[arg(long)]
+        json: bool,
+    },
+    /// List backups on the remote, newest first
+    #[command(alias = "ls")]
+    List {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Restore a backup (default: the newest), keeping existing files
+    Restore {
+        /// Backup name as shown by `backup list`
+        name: Option<String>,
+        /// Replace existing sessions, skills and config
+        #[arg(long)]
+        overwrite: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum PluginAction {
+    /// Search the index configured under `[plugins] index_url`
+    Search {
+        /// Matched against names and descriptions; omit to list everything
+        term: Option<String>,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Download a plugin, verify its SHA-256 checksum and register it
+    Install {
+        name: String,
+        /// Release to install (default: latest)
+        #[arg(long)]
+        version: Option<String>,
+        /// Grant the permissions the plugin requests without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// List installed plugins with their status (from the running server if any)
+    #[command(alias = "ls")]
+    List {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Start a plugin on the running server
+    Start {
+        /// `<category>:<name>`, or a name only one plugin has
+        key: String,
+    },
+    /// Stop a plugin on the running server
+    Stop {
+        /// `<category>:<name>`, or a name only one plugin has
+        key: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum AnalyticsAction {
+    /// Overall summary across all sessions
+    Summary,
+    /// Report for the current week or month
+    Report {
+        /// `week` or `month`
+        #[arg(short, long, default_value = "week")]
+        period: String,
+        /// `markdown`, `html` or `pdf` (default: from --out's extension, else markdown)
+        #[arg(short, long)]
+        format: Option<agent_analytics::ReportFormat>,
+        /// Write to a file instead of stdout
+        #[arg(short, long, alias = "output")]
+        out: Option<PathBuf>,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    // Commands that need no config, tools or logging.
+    match &cli.command {
+        Some(Commands::Completions { shell }) => {
+            let mut cmd = Cli::command();
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(*shell, &mut cmd, name, &mut std::io::stdout());
+            return Ok(());
+        }
+        Some(Commands::Serve { daemon: true, .. }) => {
+            daemon::spawn_background()?;
+            return Ok(());
+        }
+        Some(Commands::Stop) => {
+            daemon::stop()?;
+            return Ok(());
+        }
+        Some(Commands::Mangen { out_dir }) => {
+            std::fs::create_dir_all(out_dir)?;
+            clap_mangen::generate_to(Cli::command(), out_dir)?;
+            println!("Wrote man pages to {}", out_dir.display());
+            return Ok(());
+        }
+        _ => {}
+    }
+
     // Set up tracing.
     let filter = if cli.verbose {
         EnvFilter::new("debug")
     } else {
         EnvFilter::new(std::env::var("RUST_LOG").unwrap_or_else(|_| "agent_shell=info,warn".into()))
     };
-    tracing_subscriber::fmt()
-        .with_env_filter(filter)
-        .with_target(false)
+    // Logs go to stderr so `run` output can be piped; the TUI owns the whole
+    // screen, so there they go to the log file instead.
+    let tui_log = match &cli.command {
+        Some(Commands::Tui { .. }) => {
+            let path = daemon::log_file();
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            Some(
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)?,
+            )
+        }
+        _ => None,
+    };
+    let fmt = tracing_subscriber::fmt::layer().with_target(false);
+    let fmt = match tui_log {
+        Some(file) => fmt
+            .with_ansi(false)
+            .with_writer(std::sync::Mutex::new(file))
+            .boxed(),
+        None => fmt.with_writer(std::io::stderr).boxed(),
+    };
+    // The server also keeps recent events for the UI's Logs panel, from
+    // every crate at info (debug with -v) whatever RUST_LOG says.
+    let log_panel = matches!(cli.command, Some(Commands::Serve { .. })).then(|| {
+        let level = if cli.verbose { LevelFilter::DEBUG } else { LevelFilter::INFO };
+        agent_server::logs::LogLayer.with_filter(level)
+    });
+    tracing_subscriber::registry()
+        .with(fmt.with_filter(filter))
+        .with(log_panel)
         .init();
 
     // Load config.
@@ -104,6 +515,9 @@ async fn main() -> Result<()> {
     if let Some(api_base) = &cli.api_base {
         config.provider.api_base = api_base.clone();
     }
+    if cli.read_only {
+        config.tools.read_only = true;
+    }
 
     // Initialize skill indexer from the skills directory.
     let skills_dir = AppConfig::data_dir().join("skills");
@@ -120,11 +534,17 @@ async fn main() -> Result<()> {
 
     // Build tool registry with all built-in tools.
     let mut registry = ToolRegistry::new();
+    // The REPL also lets the agent run some of its commands.
+    let repl_requests = matches!(cli.command, None | Some(Commands::Chat { .. })).then(|| {
+        let (tool, requests) = repl_tools::ReplCommandTool::new();
+        registry.register(Arc::new(tool));
+        requests
+    });
     agent_tools::register_all(&mut registry, &config, Some(skill_indexer.clone()));
     let registry = Arc::new(registry);
 
-    // Build plugin registry (empty for now — plugins register at startup).
-    let plugin_registry = Arc::new(RwLock::new(PluginRegistry::new()));
+    // Build plugin registry with the plugins installed from the index.
+    let plugin_registry = Arc::new(RwLock::new(plugins::load_registry(&config)));
 
     tracing::info!(
         "Loaded {} tools, model: {}, endpoint: {}",
@@ -135,43 +555,28 @@ async fn main() -> Result<()> {
 
     // Spawn the scheduler as a background task if any schedules are configured.
     if !config.schedules.is_empty() {
-        let state_path = AppConfig::data_dir().join("scheduler_state.json");
-        let scheduler = Scheduler::new(config.schedules.clone(), state_path);
+        let state_path = Scheduler::state_path_for(&config);
+        let scheduler = Scheduler::new(config.schedules.clone(), state_path)
+            .with_quiet_hours(config.scheduler.quiet_hours.as_deref());
+        let history = ScheduleHistory::for_config(&config);
         let (sched_tx, mut sched_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (outcome_tx, outcome_rx) = tokio::sync::mpsc::unbounded_channel();
 
         tokio::spawn(async move {
-            scheduler.run(sched_tx).await;
+            scheduler.run(sched_tx, outcome_rx).await;
         });
 
+        let sched_config = config.clone();
+        let sched_skills = skill_indexer.clone();
         tokio::spawn(async move {
+            // Runs execute one at a time, so two firings never write to the
+            // same session or the history file concurrently.
             while let Some(task) = sched_rx.recv().await {
-                match &task {
-                    ScheduledTask::Prompt {
-                        schedule_name,
-                        prompt,
-                        ..
-                    } => {
-                        tracing::info!(
-                            "Scheduled task '{}' fired: prompt={}",
-                            schedule_name,
-                            &prompt[..prompt.len().min(80)]
-                        );
-                    }
-                    ScheduledTask::Heartbeat {
-                        schedule_name,
-                        skill,
-                        ..
-                    } => {
-                        tracing::info!(
-                            "Scheduled task '{}' fired: heartbeat (skill={})",
-                            schedule_name,
-                            skill
-                        );
-                    }
-                    ScheduledTask::Custom { schedule_name, .. } => {
-                        tracing::info!("Scheduled task '{}' fired: custom", schedule_name);
-                    }
+                let run = schedule_runner::execute(&sched_config, sched_skills.clone(), task).await;
+                if let Err(e) = history.record(&run) {
+                    tracing::warn!("Failed to record run of '{}': {}", run.schedule, e);
                 }
+                let _ = outcome_tx.send(run);
             }
         });
 
@@ -181,24 +586,126 @@ async fn main() -> Result<()> {
         );
     }
 
+    let plain = cli.plain || !std::io::IsTerminal::is_terminal(&std::io::stdout());
+
     match cli.command {
-        Some(Commands::Serve { host, port }) => {
+        Some(Commands::Serve {
+            host, port, socket, ..
+        }) => {
             if let Some(h) = host {
                 config.server.host = h;
             }
             if let Some(p) = port {
                 config.server.port = p;
             }
+            if socket.is_some() {
+                config.server.listen_socket = socket;
+            }
+            let _pid_guard = daemon::PidGuard::acquire(daemon::pid_file())?;
             agent_server::serve(config, registry, plugin_registry, skill_indexer).await?;
         }
         Some(Commands::Config { action }) => {
             handle_config_command(action, &config)?;
         }
+        Some(Commands::Sessions { action }) => {
+            subcommands::handle_sessions(action, &config)?;
+        }
+        Some(Commands::Skills { action }) => {
+            if !subcommands::handle_skills(action, skill_indexer)? {
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Analytics { action }) => {
+            subcommands::handle_analytics(action, &config)?;
+        }
+        Some(Commands::Plugin { action }) => {
+            plugins::handle_plugin(action, &config, &plugin_registry).await?;
+        }
+        Some(Commands::Dataset { action }) => {
+            subcommands::handle_dataset(action, &config)?;
+        }
+        Some(Commands::Import {
+            path,
+            from,
+            dry_run,
+        }) => {
+            subcommands::handle_import(&path, from, dry_run, &config)?;
+        }
+        Some(Commands::Backup { action }) => {
+            let config_path = cli.config.clone().unwrap_or_else(AppConfig::default_path);
+            backup::handle_backup(action, &config, config_path).await?;
+        }
+        Some(Commands::Completions { .. } | Commands::Mangen { .. } | Commands::Stop) => {
+            unreachable!()
+        }
+        Some(Commands::Run {
+            prompt,
+            json,
+            session,
+        }) => {
+            let code = oneshot::run(config, registry, prompt, json, plain, session).await?;
+            std::process::exit(code);
+        }
+        Some(Commands::Review {
+            staged,
+            range,
+            agent,
+            format,
+            fail_on,
+        }) => {
+            let target = match (staged, range) {
+                (_, Some(range)) => DiffTarget::Range(range),
+                (true, None) => DiffTarget::Staged,
+                (false, None) => DiffTarget::Uncommitted,
+            };
+            let code = review::run(
+                config,
+                registry,
+                skill_indexer,
+                target,
+                agent,
+                format,
+                fail_on,
+                plain,
+            )
+            .await?;
+            std::process::exit(code);
+        }
+        Some(Commands::Eval {
+            path,
+            providers,
+            format,
+            out,
+        }) => {
+            let code = eval::run(config, skill_indexer, path, providers, format, out).await?;
+            std::process::exit(code);
+        }
+        Some(Commands::Tui { session }) => {
+            tui::run(config, registry, skill_indexer, session).await?;
+        }
         Some(Commands::Chat { session }) => {
-            repl::run(config, registry, skill_indexer, session).await?;
+            repl::run(
+                config,
+                registry,
+                skill_indexer,
+                plugin_registry,
+                repl_requests,
+                session,
+                plain,
+            )
+            .await?;
         }
         None => {
-            repl::run(config, registry, skill_indexer, None).await?;
+            repl::run(
+                config,
+                registry,
+                skill_indexer,
+                plugin_registry,
+                repl_requests,
+                None,
+                plain,
+            )
+            .await?;
         }
     }
 
@@ -226,3 +733,20 @@ fn handle_config_command(action: Option<ConfigAction>, config: &AppConfig) -> Re
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_definition_and_completions() {
+        Cli::command().debug_assert();
+
+        let mut buf = Vec::new();
+        clap_complete::generate(clap_complete::Shell::Bash, &mut Cli::command(), "agent-shell", &mut buf);
+        let script = String::from_utf8(buf).unwrap();
+        for sub in ["sessions", "skills", "analytics", "completions"] {
+            assert!(script.contains(sub), "missing {sub}");
+        }
+    }
+}
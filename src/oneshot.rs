@@ -0,0 +1,173 @@
+//! Non-interactive single-turn mode (`agent-shell run`).
+//!
+//! Reads the prompt from the command line and/or stdin, runs one agent turn
+//! and prints the final answer (or a JSON report) to stdout. Tool progress
+//! goes to stderr so stdout stays clean for pipes.
+
+use crate::render::MarkdownRenderer;
+use agent_core::agent_loop::AgentLoop;
+use agent_core::config::AppConfig;
+use agent_core::session::SessionManager;
+use agent_core::tool_registry::ToolRegistry;
+use agent_core::types::{AgentEvent, Message};
+use anyhow::Result;
+use serde::Serialize;
+use std::io::{IsTerminal, Read};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Exit status: the turn completed.
+pub const EXIT_OK: i32 = 0;
+/// Exit status: the agent or provider failed.
+pub const EXIT_AGENT_ERROR: i32 = 1;
+/// Exit status: no prompt was given.
+pub const EXIT_USAGE: i32 = 2;
+
+#[derive(Debug, Default, Serialize)]
+struct ToolCallReport {
+    id: String,
+    name: String,
+    arguments: String,
+    output: Option<String>,
+    is_error: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct RunReport {
+    success: bool,
+    answer: String,
+    tool_calls: Vec<ToolCallReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Combine the CLI prompt with piped stdin, if any.
+pub fn build_prompt(prompt: Option<&str>, stdin: Option<&str>) -> Option<String> {
+    let prompt = prompt.map(str::trim).filter(|p| !p.is_empty());
+    let stdin = stdin.map(str::trim_end).filter(|s| !s.trim().is_empty());
+    match (prompt, stdin) {
+        (Some(p), Some(input)) => Some(format!("{p}\n\n```\n{input}\n```")),
+        (Some(p), None) => Some(p.to_string()),
+        (None, Some(input)) => Some(input.to_string()),
+        (None, None) => None,
+    }
+}
+
+/// Run a single agent turn and return the process exit code.
+pub async fn run(
+    config: AppConfig,
+    tool_registry: Arc<ToolRegistry>,
+    prompt: Option<String>,
+    json: bool,
+    plain: bool,
+    session_name: Option<String>,
+) -> Result<i32> {
+    let stdin = if std::io::stdin().is_terminal() {
+        None
+    } else {
+        let mut buf = String::new();
+        std::io::stdin().read_to_string(&mut buf)?;
+        Some(buf)
+    };
+    let Some(prompt) = build_prompt(prompt.as_deref(), stdin.as_deref()) else {
+        eprintln!("No prompt given. Usage: agent-shell run \"prompt\" (or pipe input on stdin)");
+        return Ok(EXIT_USAGE);
+    };
+
+    let agent = AgentLoop::new(config.clone(), tool_registry)?;
+    let messages = vec![Message::user(prompt)];
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<AgentEvent>();
+    let handle = {
+        let messages = messages.clone();
+        tokio::spawn(async move { agent.run(&messages, None, &[], &Default::default(), tx).await })
+    };
+
+    let progress = !json && std::io::stderr().is_terminal();
+    let mut tool_calls: Vec<ToolCallReport> = Vec::new();
+    let mut error = None;
+    while let Some(event) = rx.recv().await {
+        match event {
+            AgentEvent::ToolCallStart {
+                id,
+                name,
+                arguments,
+            } => {
+                if progress {
+                    eprintln!("\x1b[0;35m⚡ {}\x1b[0m", name);
+                }
+                tool_calls.push(ToolCallReport {
+                    id,
+                    name,
+                    arguments,
+                    ..Default::default()
+                });
+            }
+            AgentEvent::ToolResult(output) => {
+                if let Some(call) = tool_calls.iter_mut().find(|c| c.id == output.tool_call_id) {
+                    call.output = Some(output.content);
+                    call.is_error = output.is_error;
+                    call.duration_ms = output.duration_ms;
+                }
+            }
+            AgentEvent::Error(e) => error = Some(e),
+            _ => {}
+        }
+    }
+
+    let (answer, turn_messages) = match handle.await? {
+        Ok(result) => (result.final_message().content.clone(), result.messages),
+        Err(e) => {
+            error.get_or_insert_with(|| e.to_string());
+            (String::new(), Vec::new())
+        }
+    };
+
+    if let Some(name) = session_name {
+        let mut session_manager = SessionManager::new(&config)?;
+        session_manager.create_session(name)?;
+        for msg in messages.into_iter().chain(turn_messages) {
+            session_manager.push_message(msg)?;
+        }
+    }
+
+    let success = error.is_none();
+    if json {
+        let report = RunReport {
+            success,
+            answer,
+            tool_calls,
+            error: error.clone(),
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        if !answer.is_empty() {
+            let mut renderer = MarkdownRenderer::new(plain);
+            print!("{}", renderer.push(&answer));
+            println!("{}", renderer.finish());
+        }
+        if let Some(e) = &error {
+            eprintln!("Error: {}", e);
+        }
+    }
+
+    Ok(if success { EXIT_OK } else { EXIT_AGENT_ERROR })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_prompt() {
+        assert_eq!(build_prompt(Some("hi"), None).as_deref(), Some("hi"));
+        assert_eq!(build_prompt(None, Some("log\n")).as_deref(), Some("log"));
+        assert_eq!(
+            build_prompt(Some("explain"), Some("E0308\n")).as_deref(),
+            Some("explain\n\n```\nE0308\n```")
+        );
+        assert!(build_prompt(Some("  "), Some("\n")).is_none());
+    }
+}
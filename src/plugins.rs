@@ -0,0 +1,343 @@
+//! `plugin` subcommands: search the configured plugin index, install a
+//! release after verifying its checksum and getting the user's consent to
+//! the permissions it requests, and list, start or stop plugins.
+//!
+//! Listing and start/stop go to a running server over its IPC socket when
+//! one answers, and to this process's registry otherwise. The REPL's
+//! `/plugins` always works on the REPL's own registry.
+
+use crate::PluginAction;
+use agent_core::config::AppConfig;
+use agent_plugins::marketplace::{
+    self, IndexEntry, InstalledPlugin, InstalledPlugins, PluginIndex, PluginKind,
+};
+use agent_plugins::{PluginRegistry, PluginStatus, PluginSummary};
+use agent_server::ipc;
+use agent_server::ipc_handlers::{DaemonCommand, DaemonResponse};
+use anyhow::{anyhow, bail, Context, Result};
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Largest artifact `plugin install` downloads.
+const MAX_DOWNLOAD_BYTES: usize = 50 * 1024 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+pub async fn handle_plugin(
+    action: PluginAction,
+    config: &AppConfig,
+    registry: &RwLock<PluginRegistry>,
+) -> Result<()> {
+    match action {
+        PluginAction::Search { term, json } => {
+            let index = fetch_index(config).await?;
+            let found = index.search(term.as_deref().unwrap_or(""));
+            if json {
+                println!("{}", serde_json::to_string_pretty(&found)?);
+            } else if found.is_empty() {
+                println!("No plugins found.");
+            } else {
+                for entry in found {
+                    println!(
+                        "{}  {}  [{}]  {}",
+                        entry.name,
+                        entry.version,
+                        kind_label(entry.kind),
+                        entry.description
+                    );
+                }
+            }
+        }
+        PluginAction::Install { name, version, yes } => {
+            let index = fetch_index(config).await?;
+            let entry = index.find(&name, version.as_deref()).ok_or_else(|| match &version {
+                Some(v) => anyhow!("{} {} is not in the plugin index", name, v),
+                None => anyhow!("{} is not in the plugin index", name),
+            })?;
+            if !yes && !consent(config, entry)? {
+                bail!("Installation of {} cancelled", entry.name);
+            }
+            let installed = install(config, entry).await?;
+            println!(
+                "Installed {} {} to {}",
+                installed.name,
+                installed.version,
+                installed.path.display()
+            );
+        }
+        PluginAction::List { json } => {
+            let target = Target::detect(registry).await;
+            let plugins = target.list().await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&plugins)?);
+            } else if plugins.is_empty() {
+                println!("No plugins installed.");
+            } else {
+                print_summaries(&plugins);
+                if let Target::Local(_) = target {
+                    println!("(no server running; statuses are for this process)");
+                }
+            }
+        }
+        PluginAction::Start { key } => set_running_cli(registry, &key, true).await?,
+        PluginAction::Stop { key } => set_running_cli(registry, &key, false).await?,
+    }
+    Ok(())
+}
+
+async fn set_running_cli(registry: &RwLock<PluginRegistry>, key: &str, start: bool) -> Result<()> {
+    let target = Target::detect(registry).await;
+    let plugin = target.set_running(key, start).await?;
+    let verb = if start { "Started" } else { "Stopped" };
+    match target {
+        Target::Daemon => println!("{} {} on the running server", verb, plugin.key),
+        Target::Local(_) => println!(
+            "{} {} (no server running, so this only checked that it {}s)",
+            verb,
+            plugin.key,
+            if start { "start" } else { "stop" }
+        ),
+    }
+    Ok(())
+}
+
+/// Load the plugins installed from the index. A plugin whose artifact no
+/// longer matches its checksum is skipped: granted permissions belong to
+/// the verified artifact only.
+pub fn load_registry(config: &AppConfig) -> PluginRegistry {
+    let mut registry = PluginRegistry::new();
+    match InstalledPlugins::load(&config.plugins.dir()) {
+        Ok(installed) => {
+            for plugin in installed.plugins {
+                if let Err(e) = plugin.verify() {
+                    tracing::warn!("Not loading plugin {}", e);
+                    continue;
+                }
+                if let Err(e) = registry.register(Box::new(plugin)) {
+                    tracing::warn!("Skipping installed plugin: {}", e);
+                }
+            }
+        }
+        Err(e) => tracing::warn!("Failed to load installed plugins: {}", e),
+    }
+    registry
+}
+
+/// Where plugin listings and start/stop requests go.
+pub enum Target<'a> {
+    /// A server running on this machine, over its IPC socket.
+    Daemon,
+    /// The registry of this process.
+    Local(&'a RwLock<PluginRegistry>),
+}
+
+impl<'a> Target<'a> {
+    /// The running server if one answers, otherwise `local`.
+    pub async fn detect(local: &'a RwLock<PluginRegistry>) -> Target<'a> {
+        match ipc::send_command(&ipc::default_socket_path(), &DaemonCommand::Ping).await {
+            Ok(DaemonResponse::Ok { .. }) => Target::Daemon,
+            _ => Target::Local(local),
+        }
+    }
+
+    pub async fn list(&self) -> Result<Vec<PluginSummary>> {
+        match self {
+            Target::Daemon => {
+                let data = daemon_call(DaemonCommand::ListPlugins).await?;
+                Ok(serde_json::from_value(data["plugins"].clone())?)
+            }
+            Target::Local(registry) => Ok(registry.read().await.summaries()),
+        }
+    }
+
+    /// Start or stop the plugin `key` names (`<category>:<name>` or a name
+    /// only one plugin has), returning its new summary.
+    pub async fn set_running(&self, key: &str, start: bool) -> Result<PluginSummary> {
+        match self {
+            Target::Daemon => {
+                let key = key.to_string();
+                let cmd = if start {
+                    DaemonCommand::StartPlugin { key }
+                } else {
+                    DaemonCommand::StopPlugin { key }
+                };
+                let data = daemon_call(cmd).await?;
+                Ok(serde_json::from_value(data["plugin"].clone())?)
+            }
+            Target::Local(registry) => {
+                let mut registry = registry.write().await;
+                let resolved = registry
+                    .resolve_key(key)
+                    .ok_or_else(|| anyhow!("Plugin not found: {}", key))?;
+                if start {
+                    registry.start(&resolved).await
+                } else {
+                    registry.stop(&resolved).await
+                }
+                .map_err(|e| anyhow!(e))?;
+                let id = resolved.id();
+                registry
+                    .summaries()
+                    .into_iter()
+                    .find(|s| s.key == id)
+                    .ok_or_else(|| anyhow!("Plugin not found: {}", key))
+            }
+        }
+    }
+}
+
+async fn daemon_call(cmd: DaemonCommand) -> Result<serde_json::Value> {
+    let path = ipc::default_socket_path();
+    match ipc::send_command(&path, &cmd)
+        .await
+        .with_context(|| format!("No server answering on {}", path.display()))?
+    {
+        DaemonResponse::Ok { data } => Ok(data.unwrap_or_default()),
+        DaemonResponse::Error { message } => bail!(message),
+    }
+}
+
+/// Print one line per plugin: key, version, status and description.
+pub fn print_summaries(plugins: &[PluginSummary]) {
+    for p in plugins {
+        println!(
+            "  {:<28} {:<10} {:<11} {}",
+            p.key,
+            p.version,
+            status_label(&p.status),
+            p.description
+        );
+    }
+}
+
+fn status_label(status: &PluginStatus) -> &'static str {
+    match status {
+        PluginStatus::Available => "available",
+        PluginStatus::Unavailable => "unavailable",
+        PluginStatus::Running => "running",
+        PluginStatus::Error => "error",
+    }
+}
+
+/// Show what `entry` asks for and ask the user to grant it. Permissions
+/// already granted to an installed version are not asked for again.
+fn consent(config: &AppConfig, entry: &IndexEntry) -> Result<bool> {
+    let installed = InstalledPlugins::load(&config.plugins.dir()).map_err(|e| anyhow!(e))?;
+    let requested = match installed.get(&entry.name) {
+        Some(previous) => entry.permissions.beyond(&previous.permissions),
+        None => entry.permissions.clone(),
+    };
+    if requested.is_empty() {
+        return Ok(true);
+    }
+    println!("{} {} requests permission to:", entry.name, entry.version);
+    for line in requested.describe() {
+        println!("  - {}", line);
+    }
+    if !std::io::stdin().is_terminal() {
+        bail!("Cannot ask for consent without a terminal; re-run with --yes to grant these permissions");
+    }
+    print!("Grant these permissions and install? [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
+
+fn kind_label(kind: PluginKind) -> &'static str {
+    match kind {
+        PluginKind::Wasm => "wasm",
+        PluginKind::Skill => "skill",
+    }
+}
+
+fn require_https(url: &str) -> Result<()> {
+    if !url.starts_with("https://") {
+        bail!("Plugin URLs must use https: {}", url);
+    }
+    Ok(())
+}
+
+fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder().timeout(TIMEOUT).build()?)
+}
+
+async fn fetch_index(config: &AppConfig) -> Result<PluginIndex> {
+    let url = config
+        .plugins
+        .index_url
+        .as_deref()
+        .context("No plugin index configured; set index_url under [plugins] in config.toml")?;
+    require_https(url)?;
+    let index = client()?
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .with_context(|| format!("Failed to fetch plugin index {}", url))?
+        .json::<PluginIndex>()
+        .await
+        .with_context(|| format!("Invalid plugin index at {}", url))?;
+    Ok(index)
+}
+
+/// Download `entry`, verify its checksum, write it in place and record it
+/// in `installed.json`.
+async fn install(config: &AppConfig, entry: &IndexEntry) -> Result<InstalledPlugin> {
+    marketplace::validate_name(&entry.name).map_err(|e| anyhow!(e))?;
+    marketplace::validate_version(&entry.version).map_err(|e| anyhow!(e))?;
+    require_https(&entry.url)?;
+
+    let mut response = client()?
+        .get(&entry.url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .with_context(|| format!("Failed to download {}", entry.url))?;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > MAX_DOWNLOAD_BYTES {
+            bail!("{} is larger than {} MB", entry.url, MAX_DOWNLOAD_BYTES / (1024 * 1024));
+        }
+    }
+    marketplace::verify_checksum(&bytes, &entry.sha256)
+        .map_err(|e| anyhow!("Refusing to install {} {}: {}", entry.name, entry.version, e))?;
+
+    let dir = config.plugins.dir();
+    let path = artifact_path(&dir, entry);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, &bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let plugin = InstalledPlugin {
+        name: entry.name.clone(),
+        version: entry.version.clone(),
+        kind: entry.kind,
+        description: entry.description.clone(),
+        sha256: marketplace::sha256_hex(&bytes),
+        path,
+        installed_at: chrono::Utc::now(),
+        permissions: entry.permissions.clone(),
+    };
+    let mut installed = InstalledPlugins::load(&dir).map_err(|e| anyhow!(e))?;
+    installed.upsert(plugin.clone());
+    installed.save(&dir).map_err(|e| anyhow!(e))?;
+    Ok(plugin)
+}
+
+/// Skills go where the skill indexer looks; WASM modules are kept per
+/// version under the plugins directory.
+fn artifact_path(plugins_dir: &std::path::Path, entry: &IndexEntry) -> PathBuf {
+    match entry.kind {
+        PluginKind::Skill => AppConfig::data_dir()
+            .join("skills")
+            .join(&entry.name)
+            .join("SKILL.md"),
+        PluginKind::Wasm => plugins_dir
+            .join(&entry.name)
+            .join(format!("{}-{}.wasm", entry.name, entry.version)),
+    }
+}
@@ -0,0 +1,289 @@
+//! Terminal markdown rendering for streamed assistant output.
+//!
+//! Tokens arrive in arbitrary fragments, so output is buffered until a line
+//! is complete and then styled with ANSI escapes: headings, lists, quotes,
+//! inline `code` / **bold** / *italic*, and syntax-highlighted fenced blocks.
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const ITALIC: &str = "\x1b[3m";
+const DIM: &str = "\x1b[2m";
+const HEADING: &str = "\x1b[1;4;36m";
+const INLINE_CODE: &str = "\x1b[36m";
+const KEYWORD: &str = "\x1b[35m";
+const STRING: &str = "\x1b[32m";
+const COMMENT: &str = "\x1b[2;37m";
+const NUMBER: &str = "\x1b[33m";
+
+const KEYWORDS: &[&str] = &[
+    // Rust
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+    "type", "unsafe", "use", "where", "while",
+    // TypeScript / JavaScript
+    "class", "default", "export", "extends", "from", "function", "import", "interface", "new",
+    "null", "of", "this", "throw", "try", "catch", "typeof", "undefined", "var",
+    // Python
+    "def", "elif", "except", "finally", "is", "lambda", "None", "not", "and", "or", "pass",
+    "raise", "True", "False", "with", "yield",
+    // Shell
+    "do", "done", "esac", "fi", "then", "case", "echo", "local",
+];
+
+const HASH_COMMENT_LANGS: &[&str] = &[
+    "sh", "bash", "zsh", "shell", "fish", "python", "py", "toml", "yaml", "yml", "ruby", "rb",
+];
+
+/// Incremental markdown → ANSI renderer.
+///
+/// With `plain` set, text passes through untouched (for piping).
+#[derive(Debug, Default)]
+pub struct MarkdownRenderer {
+    plain: bool,
+    pending: String,
+    /// Language of the fenced block being rendered, if inside one.
+    fence: Option<String>,
+}
+
+impl MarkdownRenderer {
+    pub fn new(plain: bool) -> Self {
+        Self {
+            plain,
+            ..Self::default()
+        }
+    }
+
+    /// Feed a streamed fragment; returns text ready to print.
+    pub fn push(&mut self, chunk: &str) -> String {
+        if self.plain {
+            return chunk.to_string();
+        }
+        self.pending.push_str(chunk);
+        let Some(last_newline) = self.pending.rfind('\n') else {
+            return String::new();
+        };
+        let complete: String = self.pending.drain(..=last_newline).collect();
+        let mut out = String::new();
+        for line in complete.split_terminator('\n') {
+            out.push_str(&self.render_line(line));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Flush a trailing partial line (call when the stream ends or is
+    /// interrupted by other output such as a tool call).
+    pub fn finish(&mut self) -> String {
+        if self.plain || self.pending.is_empty() {
+            return String::new();
+        }
+        let line = std::mem::take(&mut self.pending);
+        self.render_line(&line)
+    }
+
+    /// Forget fence state between assistant messages.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.fence = None;
+    }
+
+    fn render_line(&mut self, line: &str) -> String {
+        let trimmed = line.trim_start();
+
+        if let Some(rest) = trimmed.strip_prefix("```") {
+            return if self.fence.take().is_some() {
+                format!("{DIM}└─{RESET}")
+            } else {
+                let lang = rest.trim().to_string();
+                let label = if lang.is_empty() { String::new() } else { format!(" {lang}") };
+                self.fence = Some(lang);
+                format!("{DIM}┌─{label}{RESET}")
+            };
+        }
+
+        if let Some(lang) = &self.fence {
+            return format!("{DIM}│{RESET} {}", highlight_code(line, lang));
+        }
+
+        let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            return format!("{HEADING}{}{RESET}", trimmed[hashes..].trim());
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("> ") {
+            return format!("{DIM}│ {}{RESET}", render_inline(rest));
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        if let Some(rest) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            return format!("{indent}• {}", render_inline(rest));
+        }
+
+        if trimmed.len() >= 3 && trimmed.chars().all(|c| c == '-' || c == '*' || c == '_') {
+            return format!("{DIM}{}{RESET}", "─".repeat(40));
+        }
+
+        format!("{indent}{}", render_inline(trimmed))
+    }
+}
+
+/// Style inline `code`, **bold** and *italic* / _italic_ spans.
+fn render_inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    // Underscore emphasis only opens at a word boundary, so snake_case survives.
+    let mut after_word = false;
+
+    while !rest.is_empty() {
+        let underscore_ok = !after_word || !rest.starts_with('_');
+        if let Some(after) = rest.strip_prefix('`') {
+            if let Some(end) = after.find('`') {
+                out.push_str(&format!("{INLINE_CODE}{}{RESET}", &after[..end]));
+                rest = &after[end + 1..];
+                continue;
+            }
+        }
+        if let Some(after) = rest
+            .strip_prefix("**")
+            .or_else(|| rest.strip_prefix("__").filter(|_| underscore_ok))
+        {
+            let marker = &rest[..2];
+            if let Some(end) = after.find(marker) {
+                if end > 0 {
+                    out.push_str(&format!("{BOLD}{}{RESET}", render_inline(&after[..end])));
+                    rest = &after[end + 2..];
+                    continue;
+                }
+            }
+        }
+        if let Some(after) = rest
+            .strip_prefix('*')
+            .or_else(|| rest.strip_prefix('_').filter(|_| underscore_ok))
+        {
+            let marker = &rest[..1];
+            if let Some(end) = after.find(marker) {
+                if end > 0 && !after.starts_with(' ') {
+                    out.push_str(&format!("{ITALIC}{}{RESET}", &after[..end]));
+                    rest = &after[end + 1..];
+                    continue;
+                }
+            }
+        }
+        let ch = rest.chars().next().unwrap();
+        after_word = ch.is_alphanumeric() || ch == '_';
+        out.push(ch);
+        rest = &rest[ch.len_utf8()..];
+    }
+    out
+}
+
+/// `'x'` or an escape like `'\n'` / `'\u{1F600}'` at the start of `text`.
+fn is_char_literal(text: &str) -> bool {
+    let body = &text[1..];
+    match body.strip_prefix('\\') {
+        Some(esc) => esc.find('\'').is_some_and(|end| end <= 10),
+        None => {
+            let mut chars = body.chars();
+            chars.next().is_some() && chars.next() == Some('\'')
+        }
+    }
+}
+
+/// Highlight one line of code: comments, strings, numbers and keywords.
+fn highlight_code(line: &str, lang: &str) -> String {
+    let hash_comments = HASH_COMMENT_LANGS.contains(&lang);
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let rest = &line[i..];
+        if rest.starts_with("//") || (hash_comments && c == '#') {
+            out.push_str(&format!("{COMMENT}{rest}{RESET}"));
+            break;
+        }
+        // In Rust a lone `'` starts a lifetime, not a string.
+        let lifetime = c == '\'' && matches!(lang, "rust" | "rs") && !is_char_literal(rest);
+        if (c == '"' || c == '\'' || c == '`') && !lifetime {
+            let mut end = line.len();
+            let mut escaped = false;
+            for (j, d) in line[i + 1..].char_indices() {
+                if escaped {
+                    escaped = false;
+                } else if d == '\\' {
+                    escaped = true;
+                } else if d == c {
+                    end = i + 1 + j + 1;
+                    break;
+                }
+            }
+            out.push_str(&format!("{STRING}{}{RESET}", &line[i..end]));
+            while chars.peek().is_some_and(|(k, _)| *k < end) {
+                chars.next();
+            }
+            continue;
+        }
+        if c.is_ascii_alphanumeric() || c == '_' {
+            let mut end = i + c.len_utf8();
+            while let Some((k, d)) = chars.peek() {
+                if d.is_ascii_alphanumeric() || *d == '_' || (c.is_ascii_digit() && *d == '.') {
+                    end = k + d.len_utf8();
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let word = &line[i..end];
+            if c.is_ascii_digit() {
+                out.push_str(&format!("{NUMBER}{word}{RESET}"));
+            } else if KEYWORDS.contains(&word) {
+                out.push_str(&format!("{KEYWORD}{word}{RESET}"));
+            } else {
+                out.push_str(word);
+            }
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_passes_through() {
+        let mut r = MarkdownRenderer::new(true);
+        assert_eq!(r.push("**hi**"), "**hi**");
+        assert_eq!(r.finish(), "");
+    }
+
+    #[test]
+    fn test_buffers_until_newline() {
+        let mut r = MarkdownRenderer::new(false);
+        assert_eq!(r.push("some **bo"), "");
+        let out = r.push("ld** text\nnext");
+        assert_eq!(out, format!("some {BOLD}bold{RESET} text\n"));
+        assert_eq!(r.finish(), "next");
+    }
+
+    #[test]
+    fn test_fenced_block_highlighted() {
+        let mut r = MarkdownRenderer::new(false);
+        let out = r.push("```rust\nlet x = \"s\"; // c\n```\n");
+        assert!(out.contains(&format!("{KEYWORD}let{RESET}")));
+        assert!(out.contains(&format!("{STRING}\"s\"{RESET}")));
+        assert!(out.contains(&format!("{COMMENT}// c{RESET}")));
+        assert!(!highlight_code("fn f<'a>(x: &'a str)", "rust").contains(STRING));
+        assert!(r.fence.is_none());
+    }
+
+    #[test]
+    fn test_inline_code_and_bullets() {
+        let mut r = MarkdownRenderer::new(false);
+        let out = r.push("- run `cargo test`\n");
+        assert_eq!(out, format!("• run {INLINE_CODE}cargo test{RESET}\n"));
+        assert_eq!(r.push("use snake_case_names\n"), "use snake_case_names\n");
+    }
+}
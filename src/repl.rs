@@ -1,14 +1,28 @@
+use crate::approval::{self, ApprovalHook};
+use crate::completion::{join_continuations, ReplHelper};
+use crate::render::MarkdownRenderer;
+use crate::repl_tools::{self, ReplRequest};
+use crate::session_picker::{self, Pick, SessionRow};
 use agent_core::agent_loop::AgentLoop;
+use agent_core::agents::{AgentPreset, AgentStore};
 use agent_core::config::{AppConfig, SandboxMode};
+use agent_core::error::AgentError;
 use agent_core::session::SessionManager;
 use agent_core::tool_registry::ToolRegistry;
-use agent_core::types::{AgentEvent, Message};
+use agent_core::types::{AgentEvent, Feedback, Message, Rating, Role};
+use agent_core::workspace;
+use agent_plugins::PluginRegistry;
 use agent_skills::SkillIndexer;
 use anyhow::Result;
 use rustyline::error::ReadlineError;
-use rustyline::{Config as RlConfig, DefaultEditor};
+use rustyline::history::DefaultHistory;
+use rustyline::{
+    Cmd, CompletionType, Config as RlConfig, Editor, EventHandler, KeyCode, KeyEvent, Modifiers,
+};
+use std::collections::HashSet;
+use std::io::IsTerminal;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, RwLock};
 
 const BANNER: &str = r#"
   ╔═══════════════════════════════════════════╗
@@ -17,17 +31,25 @@ const BANNER: &str = r#"
   ╚═══════════════════════════════════════════╝
 
   Type your message and press Enter to chat.
+  Press Tab to complete commands, session IDs, tools, skills and paths.
+  End a line with \ or press Alt+Enter for multiline input.
   Commands:
-    /new [name]    — Create a new session
+    /new [name] [--agent NAME] — Create a new session
+    /agents        — List agent presets
     /sessions      — List all sessions
     /switch <id>   — Switch to a session
-    /tools         — List available tools
-    /skills        — List loaded skills
+    /tools [name]  — List available tools (or show one)
+    /skills [name] — List loaded skills (or show one)
+    /plugins [start|stop <key>] — List plugins with status, or start/stop one
     /context [dir] — Detect project, git, and runtime environments
     /analytics     — Show session analytics summary
     /shells        — List detected shells
     /config        — Show current config
     /clear         — Clear current session history
+    /feedback up|down [comment] — Rate the last answer
+    /approval [on|off|reset] — Ask before each tool call (y/n/always)
+    /verbose [on|off] — Show full tool arguments and timings
+    /edit [text]   — Compose a prompt in $EDITOR
     /help          — Show this help
     /exit          — Quit
 "#;
@@ -37,7 +59,10 @@ pub async fn run(
     config: AppConfig,
     tool_registry: Arc<ToolRegistry>,
     skill_indexer: Arc<SkillIndexer>,
+    plugin_registry: Arc<RwLock<PluginRegistry>>,
+    repl_requests: Option<mpsc::UnboundedReceiver<ReplRequest>>,
     session_name: Option<String>,
+    plain: bool,
 ) -> Result<()> {
     println!("{}", BANNER);
     println!(
@@ -48,24 +73,77 @@ pub async fn run(
     // Warn if running in unsafe (unsandboxed) mode.
     if config.sandbox.mode == SandboxMode::Unsafe {
         println!("\x1b[1;33m  ⚠  WARNING: Sandbox mode is 'unsafe' — tools execute directly on your system!\x1b[0m");
-        println!("\x1b[1;33m     Set [sandbox] mode = \"docker\" in config for isolated execution.\x1b[0m");
+        println!("\x1b[1;33m     Set [sandbox] mode = \"docker\" (or \"native\" without Docker) in config for isolated execution.\x1b[0m");
+    }
+    if tool_registry.read_only() {
+        println!("  Read-only mode: tools that change files or the repository are off.");
     }
     println!();
 
     let mut session_manager = SessionManager::new(&config)?;
     if let Some(name) = session_name {
         session_manager.create_session(name)?;
+    } else if session_manager.list_sessions().len() > 1
+        && std::io::stdin().is_terminal()
+        && std::io::stdout().is_terminal()
+    {
+        pick_session(&mut session_manager)?;
     }
 
-    let agent_loop = Arc::new(AgentLoop::new(config.clone(), tool_registry.clone())?);
+    // The approval hook goes last so it sees arguments as other hooks left them.
+    let (approval, mut approval_rx) = ApprovalHook::new(config.tools.require_approval);
+    let mut agent_loop = AgentLoop::new(config.clone(), tool_registry.clone())?;
+    agent_loop.hooks_mut().register(approval.clone());
+    let agent_loop = Arc::new(agent_loop);
+    let mut verbose = false;
+    // `repl_command` calls; none arrive if the tool is not registered.
+    let mut repl_requests = repl_requests.unwrap_or_else(|| mpsc::unbounded_channel().1);
 
     // Set up rustyline.
-    let rl_config = RlConfig::builder().auto_add_history(true).build();
+    let rl_config = RlConfig::builder()
+        .auto_add_history(true)
+        .completion_type(CompletionType::List)
+        .build();
     let history_path = AppConfig::data_dir().join("repl_history.txt");
-    let mut rl = DefaultEditor::with_config(rl_config)?;
+    let mut rl: Editor<ReplHelper, DefaultHistory> = Editor::with_config(rl_config)?;
+    let workspace_root = match &config.sandbox.workspace_root {
+        Some(root) => root.clone(),
+        None => std::env::current_dir()?,
+    };
+    let mut helper = ReplHelper::new(workspace_root);
+    helper.set_tools(tool_registry.list_names().into_iter().map(String::from).collect());
+    rl.set_helper(Some(helper));
+    rl.bind_sequence(
+        KeyEvent(KeyCode::Enter, Modifiers::ALT),
+        EventHandler::Simple(Cmd::Newline),
+    );
     let _ = rl.load_history(&history_path);
 
     loop {
+        // Pick up sessions a running `serve` changed since the last prompt.
+        if let Err(e) = session_manager.reload_changed() {
+            eprintln!("\x1b[0;31mFailed to reload sessions: {}\x1b[0m", e);
+        }
+
+        // Refresh completion sources that change while the REPL runs.
+        if let Some(helper) = rl.helper_mut() {
+            helper.set_sessions(
+                session_manager
+                    .list_sessions()
+                    .into_iter()
+                    .map(|(id, name, _, _)| (id.to_string(), name.to_string()))
+                    .collect(),
+            );
+            helper.set_skills(
+                skill_indexer
+                    .get_skill_index()
+                    .skills
+                    .iter()
+                    .map(|s| s.name.clone())
+                    .collect(),
+            );
+        }
+
         let session_name = session_manager
             .active_session()
             .map(|s| s.name.as_str())
@@ -74,13 +152,49 @@ pub async fn run(
 
         match rl.readline(&prompt) {
             Ok(line) => {
-                let input = line.trim();
+                let line = join_continuations(&line);
+                let mut input = line.trim();
                 if input.is_empty() {
                     continue;
                 }
 
-                // Handle slash commands.
-                if input.starts_with('/') {
+                // `/edit` composes the next message in an external editor.
+                let composed;
+                if input == "/edit" || input.starts_with("/edit ") {
+                    let seed = input.strip_prefix("/edit").unwrap_or("").trim();
+                    composed = match compose_in_editor(seed) {
+                        Ok(Some(text)) => text,
+                        Ok(None) => {
+                            println!("  Empty message, nothing sent.");
+                            continue;
+                        }
+                        Err(e) => {
+                            eprintln!("\x1b[0;31mEditor error: {}\x1b[0m", e);
+                            continue;
+                        }
+                    };
+                    println!("{}", composed);
+                    input = composed.as_str();
+                } else if input == "/plugins" || input.starts_with("/plugins ") {
+                    let arg = input.strip_prefix("/plugins").unwrap_or("").trim();
+                    plugins_command(arg, &plugin_registry).await;
+                    continue;
+                } else if input == "/approval" || input.starts_with("/approval ") {
+                    let arg = input.strip_prefix("/approval").unwrap_or("").trim();
+                    approval_command(arg, &approval);
+                    continue;
+                } else if input == "/verbose" || input.starts_with("/verbose ") {
+                    let arg = input.strip_prefix("/verbose").unwrap_or("").trim();
+                    match toggle(arg, verbose) {
+                        Some(on) => {
+                            verbose = on;
+                            println!("  Verbose tool output {}.", if on { "on" } else { "off" });
+                        }
+                        None => println!("  Usage: /verbose [on|off]"),
+                    }
+                    continue;
+                } else if input.starts_with('/') {
+                    // Handle slash commands.
                     let handled = handle_command(
                         input,
                         &mut session_manager,
@@ -95,6 +209,7 @@ pub async fn run(
                 }
 
                 // Send user message to agent.
+                session_manager.auto_checkpoint()?;
                 let user_msg = Message::user(input);
                 session_manager.push_message(user_msg)?;
 
@@ -104,14 +219,27 @@ pub async fn run(
                     .cloned()
                     .collect();
 
-                // Get session tool filtering.
-                let (allowlist, denylist) = {
+                // Get session tool filtering, agent preset and workspaces.
+                let (allowlist, denylist, tool_calls, agent_name, roots) = {
                     let session = session_manager.active_session().unwrap();
                     (
                         session.tool_allowlist.clone(),
                         session.tool_denylist.clone(),
+                        session.tool_calls.clone(),
+                        session.agent.clone(),
+                        workspace::roots_for(&config.sandbox, session),
                     )
                 };
+                let preset = match agent_name {
+                    Some(name) => match resolve_agent(&config, &skill_indexer, &name) {
+                        Ok(preset) => Some(preset),
+                        Err(e) => {
+                            eprintln!("\x1b[1;31mError: {}\x1b[0m", e);
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
 
                 // Create event channel.
                 let (tx, mut rx) = mpsc::unbounded_channel::<AgentEvent>();
@@ -124,25 +252,88 @@ pub async fn run(
                     let allowlist = allowlist.clone();
                     let denylist = denylist.clone();
                     let tx = tx.clone();
-                    tokio::spawn(async move {
+                    tokio::spawn(workspace::scope(roots, async move {
                         agent
-                            .run(&messages, allowlist.as_deref(), &denylist, tx)
+                            .run_as(
+                                preset.as_ref(),
+                                &messages,
+                                allowlist.as_deref(),
+                                &denylist,
+                                &tool_calls,
+                                tx,
+                            )
                             .await
-                    })
+                    }))
                 };
                 drop(tx); // Drop our copy so the channel closes when agent is done.
 
                 // Print events as they arrive.
                 print!("\x1b[1;33massistant\x1b[0m: ");
                 let mut full_response = String::new();
-                while let Some(event) = rx.recv().await {
+                let mut renderer = MarkdownRenderer::new(plain);
+                // Calls whose arguments an approval prompt already showed.
+                let mut prompted: HashSet<String> = HashSet::new();
+                // Session a `repl_command` asked for, entered after saving.
+                let mut switch_to: Option<String> = None;
+                loop {
+                    // Events first, so output queued before a prompt is
+                    // printed before it.
+                    let event = tokio::select! {
+                        biased;
+                        event = rx.recv() => match event {
+                            Some(event) => event,
+                            None => break,
+                        },
+                        Some(request) = approval_rx.recv() => {
+                            print!("{}", renderer.finish());
+                            renderer.reset();
+                            let decision = approval::prompt(&request.call);
+                            prompted.insert(request.call.id.clone());
+                            let _ = request.reply.send(decision);
+                            continue;
+                        }
+                        Some(request) = repl_requests.recv() => {
+                            print!("{}", renderer.finish());
+                            renderer.reset();
+                            let handled = repl_tools::handle(
+                                request.action,
+                                &session_manager,
+                                &skill_indexer,
+                                &config,
+                            );
+                            let reply = match handled {
+                                Ok(handled) => {
+                                    println!(
+                                        "\n  \x1b[0;36m↪ {}\x1b[0m",
+                                        handled.confirmation.replace('\n', "\n    ")
+                                    );
+                                    if handled.switch_to.is_some() {
+                                        switch_to = handled.switch_to;
+                                    }
+                                    Ok(handled.result)
+                                }
+                                Err(e) => Err(e),
+                            };
+                            let _ = request.reply.send(reply);
+                            continue;
+                        }
+                    };
                     match event {
                         AgentEvent::ContentChunk(token) => {
-                            print!("{}", token);
+                            print!("{}", renderer.push(&token));
+                            let _ = std::io::Write::flush(&mut std::io::stdout());
                             full_response.push_str(&token);
                         }
-                        AgentEvent::ToolCallStart { name, .. } => {
-                            println!("\n  \x1b[0;35m⚡ Calling tool: {}\x1b[0m", name);
+                        AgentEvent::ToolCallStart { id, name, arguments } => {
+                            print!("{}", renderer.finish());
+                            renderer.reset();
+                            if prompted.remove(&id) {
+                                // Shown with the prompt.
+                            } else if verbose {
+                                approval::print_call(&name, &arguments);
+                            } else {
+                                println!("\n  \x1b[0;35m⚡ Calling tool: {}\x1b[0m", name);
+                            }
                         }
                         AgentEvent::ToolResult(output) => {
                             let status = if output.is_error {
@@ -155,24 +346,39 @@ pub async fn run(
                             } else {
                                 output.content.clone()
                             };
-                            println!("  {} {}", status, preview.replace('\n', "\n    "));
+                            let timing = match output.duration_ms {
+                                Some(ms) if verbose => format!("\x1b[2m({} ms)\x1b[0m ", ms),
+                                _ => String::new(),
+                            };
+                            println!("  {} {}{}", status, timing, preview.replace('\n', "\n    "));
                             print!("\x1b[1;33massistant\x1b[0m: ");
                         }
                         AgentEvent::Done(_msg) => {
                             // Final message already streamed via Token events.
                         }
                         AgentEvent::Error(e) => {
+                            print!("{}", renderer.finish());
                             println!("\n\x1b[0;31mError: {}\x1b[0m", e);
                         }
                         _ => {}
                     }
                 }
+                print!("{}", renderer.finish());
                 println!(); // Newline after response.
 
                 // Wait for agent to finish and save all messages (including
                 // intermediate tool calls and results) for complete history.
                 match agent_handle.await {
                     Ok(Ok(result)) => {
+                        if let Err(e) = session_manager.record_snapshots(result.snapshots) {
+                            eprintln!("\x1b[0;31mFailed to record file snapshots: {}\x1b[0m", e);
+                        }
+                        if let Err(e) = session_manager.record_tool_calls(&result.tool_calls) {
+                            eprintln!("\x1b[0;31mFailed to record tool calls: {}\x1b[0m", e);
+                        }
+                        if let Err(e) = session_manager.record_usage(result.usage) {
+                            eprintln!("\x1b[0;31mFailed to record token usage: {}\x1b[0m", e);
+                        }
                         for msg in result.messages {
                             session_manager.push_message(msg)?;
                         }
@@ -184,6 +390,14 @@ pub async fn run(
                         eprintln!("\x1b[0;31mTask error: {}\x1b[0m", e);
                     }
                 }
+                if let Some(id) = switch_to {
+                    session_manager.switch_session(&id)?;
+                    let name = session_manager
+                        .active_session()
+                        .map(|s| s.name.clone())
+                        .unwrap_or_default();
+                    println!("Switched to session: {} ({})", name, &id[..8]);
+                }
             }
             Err(ReadlineError::Interrupted) => {
                 println!("^C");
@@ -209,6 +423,198 @@ pub async fn run(
     Ok(())
 }
 
+/// Text analytics report over every saved session, or `None` if there
+/// are none.
+pub(crate) fn analytics_summary(session_manager: &SessionManager, config: &AppConfig) -> Option<String> {
+    let sessions_dir = config
+        .session
+        .history_dir
+        .clone()
+        .unwrap_or_else(|| AppConfig::data_dir().join("sessions"));
+
+    let mut all_sessions = Vec::new();
+    for (id, _, _, _) in session_manager.list_sessions() {
+        let path = sessions_dir.join(format!("{}.json", id));
+        if let Ok(session) = agent_core::session::Session::load_from(&path) {
+            all_sessions.push(session);
+        }
+    }
+    if all_sessions.is_empty() {
+        return None;
+    }
+    let mut analytics = agent_analytics::Analytics::default();
+    analytics.process_sessions(&all_sessions);
+    Some(agent_analytics::ReportGenerator::text_summary(&analytics))
+}
+
+/// Ask which session to continue, then switch to it or create it.
+fn pick_session(session_manager: &mut SessionManager) -> Result<()> {
+    let rows: Vec<SessionRow> = session_manager
+        .list_sessions()
+        .into_iter()
+        .map(|(id, name, updated, messages)| SessionRow {
+            id: id.to_string(),
+            name: name.to_string(),
+            updated,
+            messages,
+        })
+        .collect();
+    match session_picker::pick(&rows)? {
+        Some(Pick::Session(id)) => session_manager.switch_session(&id)?,
+        Some(Pick::New(name)) => {
+            session_manager.create_session(name)?;
+        }
+        None => {}
+    }
+    if let Some(session) = session_manager.active_session() {
+        println!("  Session: {} ({} msgs)\n", session.name, session.messages.len());
+    }
+    Ok(())
+}
+
+/// `on`, `off`, or nothing to flip `current`.
+fn toggle(arg: &str, current: bool) -> Option<bool> {
+    match arg {
+        "" => Some(!current),
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// `/approval [on|off|reset]`: switch approval prompts, or forget the
+/// tools answered "always".
+fn approval_command(arg: &str, approval: &ApprovalHook) {
+    if arg == "reset" {
+        approval.forget_always();
+        println!("  Every tool call will be asked about again.");
+        return;
+    }
+    match toggle(arg, approval.is_enabled()) {
+        Some(on) => {
+            approval.set_enabled(on);
+            println!("  Tool approval {}.", if on { "on" } else { "off" });
+            let always = approval.always_allowed();
+            if on && !always.is_empty() {
+                println!("  Always allowed: {}", always.join(", "));
+            }
+        }
+        None => println!("  Usage: /approval [on|off|reset]"),
+    }
+}
+
+/// Open `$VISUAL` / `$EDITOR` on a temporary file seeded with `seed` and
+/// return what was saved, or `None` if the result is empty.
+fn compose_in_editor(seed: &str) -> Result<Option<String>> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("$EDITOR is empty"))?;
+
+    let path = std::env::temp_dir().join(format!("agent-shell-prompt-{}.md", std::process::id()));
+    std::fs::write(&path, seed)?;
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status();
+    let text = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    let status = status.map_err(|e| anyhow::anyhow!("failed to run '{}': {}", editor, e))?;
+    if !status.success() {
+        anyhow::bail!("'{}' exited with {}", editor, status);
+    }
+    let text = text?.trim().to_string();
+    Ok((!text.is_empty()).then_some(text))
+}
+
+/// A path typed at the prompt, made absolute against the current directory
+/// to match the paths tools record.
+fn resolve_path(arg: &str) -> Result<std::path::PathBuf> {
+    let path = std::path::Path::new(arg);
+    Ok(match path.is_absolute() {
+        true => path.to_path_buf(),
+        false => std::env::current_dir()?.join(path),
+    })
+}
+
+/// Print a file's unified diff with added lines green and removed red.
+fn print_diff(diff: &agent_core::checkpoint::FileDiff) {
+    println!("\x1b[1m{:?} {}\x1b[0m", diff.status, diff.path.display());
+    for line in diff.diff.lines() {
+        match line.chars().next() {
+            _ if line.starts_with("+++") || line.starts_with("---") => println!("{}", line),
+            Some('+') => println!("\x1b[0;32m{}\x1b[0m", line),
+            Some('-') => println!("\x1b[0;31m{}\x1b[0m", line),
+            Some('@') => println!("\x1b[0;36m{}\x1b[0m", line),
+            _ => println!("{}", line),
+        }
+    }
+}
+
+/// Split `/new` arguments into an optional session name and `--agent` preset.
+pub(crate) fn parse_new_args(arg: &str) -> Result<(Option<String>, Option<String>), String> {
+    let mut name = Vec::new();
+    let mut agent = None;
+    let mut words = arg.split_whitespace();
+    while let Some(word) = words.next() {
+        if let Some(value) = word.strip_prefix("--agent=") {
+            agent = Some(value.to_string());
+        } else if word == "--agent" {
+            agent = Some(words.next().ok_or("Usage: /new [name] [--agent NAME]")?.to_string());
+        } else {
+            name.push(word);
+        }
+    }
+    let name = (!name.is_empty()).then(|| name.join(" "));
+    Ok((name, agent))
+}
+
+/// Look up an agent preset by name, with its skills appended to the prompt.
+pub(crate) fn resolve_agent(
+    config: &AppConfig,
+    skill_indexer: &SkillIndexer,
+    name: &str,
+) -> Result<AgentPreset, String> {
+    let store = AgentStore::load(AgentStore::path_for(config));
+    let (preset, _) = store
+        .resolve(config, name)
+        .ok_or_else(|| format!("Unknown agent: {} (see /agents)", name))?;
+    let docs = skill_indexer.skill_docs(&preset.skills);
+    Ok(preset.with_skill_docs(config.system_prompt.as_deref(), &docs))
+}
+
+/// `/plugins`: list this process's plugins, or start or stop one. Async,
+/// unlike the commands in [`handle_command`], since plugins may start
+/// asynchronously.
+async fn plugins_command(arg: &str, registry: &RwLock<PluginRegistry>) {
+    let target = crate::plugins::Target::Local(registry);
+    let mut words = arg.split_whitespace();
+    match (words.next(), words.next()) {
+        (None, _) => match target.list().await {
+            Ok(plugins) if plugins.is_empty() => {
+                println!("  No plugins installed. Find some with `agent-shell plugin search`.")
+            }
+            Ok(plugins) => crate::plugins::print_summaries(&plugins),
+            Err(e) => println!("  {}", e),
+        },
+        (Some(action @ ("start" | "stop")), Some(key)) => {
+            match target.set_running(key, action == "start").await {
+                Ok(plugin) => println!(
+                    "  {} {}",
+                    if action == "start" { "Started" } else { "Stopped" },
+                    plugin.key
+                ),
+                Err(e) => println!("  {}", e),
+            }
+        }
+        _ => println!("Usage: /plugins [start|stop <key>]"),
+    }
+}
+
 /// Handle a slash command. Returns `true` to continue the loop, `false` to exit.
 fn handle_command(
     input: &str,
@@ -226,14 +632,46 @@ fn handle_command(
             println!("Goodbye!");
             return Ok(false);
         }
-        "/new" => {
-            let name = if arg.is_empty() {
-                format!("session-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S"))
+        "/new" => match parse_new_args(arg) {
+            Ok((name, agent)) => {
+                if let Some(agent) = &agent {
+                    if let Err(e) = resolve_agent(config, skill_indexer, agent) {
+                        println!("{}", e);
+                        return Ok(true);
+                    }
+                }
+                let name = name.unwrap_or_else(|| {
+                    format!("session-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S"))
+                });
+                let session = session_manager.create_session_with_agent(&name, agent)?;
+                match &session.agent {
+                    Some(agent) => println!(
+                        "Created session: {} ({}) with agent {}",
+                        session.name,
+                        &session.id[..8],
+                        agent
+                    ),
+                    None => println!("Created session: {} ({})", session.name, &session.id[..8]),
+                }
+            }
+            Err(e) => println!("{}", e),
+        },
+        "/agents" => {
+            let store = AgentStore::load(AgentStore::path_for(config));
+            let agents = store.list(config);
+            if agents.is_empty() {
+                println!("  No agents. Define [agents.<name>] in config or POST /v1/agents.");
             } else {
-                arg.to_string()
-            };
-            let session = session_manager.create_session(&name)?;
-            println!("Created session: {} ({})", session.name, &session.id[..8]);
+                for (name, preset, _) in agents {
+                    let model = preset.model.as_deref().unwrap_or(&config.provider.model);
+                    println!(
+                        "  {:<14} {} — {}",
+                        name,
+                        model,
+                        preset.description.as_deref().unwrap_or("")
+                    );
+                }
+            }
         }
         "/sessions" | "/ls" => {
             let sessions = session_manager.list_sessions();
@@ -257,25 +695,31 @@ fn handle_command(
             if arg.is_empty() {
                 println!("Usage: /switch <session-id-prefix>");
             } else {
-                // Find a session ID that starts with the given prefix.
-                let sessions = session_manager.list_sessions();
-                let matches: Vec<_> = sessions
-                    .iter()
-                    .filter(|(id, _, _, _)| id.starts_with(arg))
-                    .collect();
-                match matches.len() {
-                    0 => println!("No session matching '{}'", arg),
-                    1 => {
-                        let id = matches[0].0.to_string();
-                        let name = matches[0].1.to_string();
-                        drop(sessions);
+                match session_manager.resolve_prefix(arg) {
+                    Ok(id) => {
                         session_manager.switch_session(&id)?;
+                        let name = session_manager
+                            .active_session()
+                            .map(|s| s.name.clone())
+                            .unwrap_or_default();
                         println!("Switched to session: {} ({})", name, &id[..8]);
                     }
-                    _ => println!("Ambiguous prefix '{}', {} matches", arg, matches.len()),
+                    Err(AgentError::Session(msg)) => println!("{}", msg),
+                    Err(e) => return Err(e.into()),
                 }
             }
         }
+        "/tools" if !arg.is_empty() => match tool_registry.get(arg) {
+            Some(tool) => {
+                println!("  {} — {}", tool.name(), tool.description());
+                if let Some(id) = tool_registry.id(arg) {
+                    println!("  ID: {}", id);
+                }
+                let schema = serde_json::to_string_pretty(&tool.parameters_schema())?;
+                println!("  Parameters:\n    {}", schema.replace('\n', "\n    "));
+            }
+            None => println!("  Unknown tool: {}", arg),
+        },
         "/tools" => {
             let names = tool_registry.list_names();
             if names.is_empty() {
@@ -287,8 +731,20 @@ fn handle_command(
                         println!("    • {} — {}", name, tool.description());
                     }
                 }
+                for (alias, name) in tool_registry.aliases() {
+                    println!("    • {} → {}", alias, name);
+                }
             }
         }
+        "/skills" if !arg.is_empty() => match skill_indexer.get_skill_meta(arg) {
+            Some(skill) => {
+                println!("  {} — {}", skill.name, skill.description);
+                if skill.has_sub_skills() {
+                    println!("  Sub-skills: {}", skill.sub_skill_names().join(", "));
+                }
+            }
+            None => println!("  Unknown skill: {}", arg),
+        },
         "/skills" => {
             let index = skill_indexer.get_skill_index();
             if index.is_empty() {
@@ -313,9 +769,63 @@ fn handle_command(
                 }
             }
         }
+        "/workspace" | "/workspaces" => {
+            let mut words = arg.split_whitespace();
+            let action = words.next();
+            let names: Vec<String> = words.map(String::from).collect();
+            let Some(session) = session_manager.active_session_mut() else {
+                println!("  No active session.");
+                return Ok(true);
+            };
+            match action {
+                None => {
+                    let configured = workspace::configured(&config.sandbox);
+                    if configured.is_empty() {
+                        println!("  No workspaces. Add [sandbox.workspaces] to config.");
+                    }
+                    let active = workspace::active(&config.sandbox, &session.workspaces);
+                    for ws in configured {
+                        let marker = match active.iter().position(|a| a.name == ws.name) {
+                            Some(0) => " ◀ primary",
+                            Some(_) => " ◀",
+                            None => "",
+                        };
+                        println!("  {:<14} {}{}", ws.name, ws.root.display(), marker);
+                    }
+                }
+                Some("use" | "add") if !names.is_empty() => {
+                    if let Err(e) = workspace::resolve(&config.sandbox, &names) {
+                        println!("  {}", e);
+                        return Ok(true);
+                    }
+                    if action == Some("use") {
+                        session.workspaces = names;
+                    } else {
+                        if session.workspaces.is_empty() {
+                            session.workspaces = workspace::active(&config.sandbox, &session.workspaces)
+                                .into_iter()
+                                .map(|w| w.name)
+                                .collect();
+                        }
+                        for name in names {
+                            if !session.workspaces.contains(&name) {
+                                session.workspaces.push(name);
+                            }
+                        }
+                    }
+                    println!("  Workspaces: {}", session.workspaces.join(", "));
+                    session_manager.save_active()?;
+                }
+                _ => println!("Usage: /workspace [use <name>... | add <name>...]"),
+            }
+        }
         "/context" => {
             let dir = if arg.is_empty() {
-                std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."))
+                session_manager
+                    .active_session()
+                    .and_then(|s| workspace::roots_for(&config.sandbox, s).into_iter().next())
+                    .or_else(|| std::env::current_dir().ok())
+                    .unwrap_or_else(|| std::path::PathBuf::from("."))
             } else {
                 std::path::PathBuf::from(arg)
             };
@@ -370,32 +880,10 @@ fn handle_command(
                 }
             }
         }
-        "/analytics" => {
-            // Load all sessions and compute analytics.
-            let sessions_dir = config
-                .session
-                .history_dir
-                .clone()
-                .unwrap_or_else(|| agent_core::config::AppConfig::data_dir().join("sessions"));
-
-            let mut all_sessions = Vec::new();
-            let session_list = session_manager.list_sessions();
-            for (id, _, _, _) in &session_list {
-                let path = sessions_dir.join(format!("{}.json", id));
-                if let Ok(session) = agent_core::session::Session::load_from(&path) {
-                    all_sessions.push(session);
-                }
-            }
-
-            if all_sessions.is_empty() {
-                println!("  No sessions to analyze.");
-            } else {
-                let mut analytics = agent_analytics::Analytics::default();
-                analytics.process_sessions(&all_sessions);
-                let summary = agent_analytics::ReportGenerator::text_summary(&analytics);
-                print!("{}", summary);
-            }
-        }
+        "/analytics" => match analytics_summary(session_manager, config) {
+            Some(summary) => print!("{}", summary),
+            None => println!("  No sessions to analyze."),
+        },
         "/shells" => {
             let shells = agent_pty::detect_available_shells();
             if shells.is_empty() {
@@ -424,22 +912,202 @@ fn handle_command(
             if let Some(session) = session_manager.active_session() {
                 let name = session.name.clone();
                 let old_id = session.id.clone();
+                let agent = session.agent.clone();
                 session_manager.delete_session(&old_id)?;
-                session_manager.create_session(&name)?;
+                session_manager.create_session_with_agent(&name, agent)?;
                 println!("Cleared session history.");
             }
         }
+        "/checkpoint" => match session_manager.active_session_mut() {
+            Some(session) => {
+                let label = (!arg.is_empty()).then(|| arg.to_string());
+                let id = session.checkpoint(label).id.clone();
+                session_manager.save_active()?;
+                println!("Checkpoint {} saved.", id);
+            }
+            None => println!("No active session."),
+        },
+        "/checkpoints" => match session_manager.active_session() {
+            Some(session) if !session.checkpoints.is_empty() => {
+                for c in &session.checkpoints {
+                    println!(
+                        "  {} {} ({} msgs{}){}",
+                        c.id,
+                        c.created_at.format("%Y-%m-%d %H:%M:%S"),
+                        c.message_count,
+                        match c.files.len() {
+                            0 => String::new(),
+                            n => format!(", {} file(s) changed since", n),
+                        },
+                        c.label.as_deref().map(|l| format!(" — {}", l)).unwrap_or_default()
+                    );
+                }
+            }
+            _ => println!("  No checkpoints."),
+        },
+        "/undo" => {
+            // `/undo [checkpoint-id] [--keep-files]`
+            let keep_files = arg.split_whitespace().any(|w| w == "--keep-files");
+            let target = arg.split_whitespace().find(|w| !w.starts_with("--"));
+            let Some(id) = session_manager.active_session_id().map(String::from) else {
+                println!("No active session.");
+                return Ok(true);
+            };
+            let store = session_manager.snapshot_store(&id);
+            let session = session_manager.active_session_mut().expect("active session");
+            let result = match target {
+                Some(checkpoint) => session.rollback(checkpoint, !keep_files, &store),
+                None => session.undo(!keep_files, &store),
+            };
+            match result {
+                Ok(rollback) => {
+                    session_manager.save_active()?;
+                    println!(
+                        "Rolled back to checkpoint {}: removed {} message(s), restored {} file(s).",
+                        rollback.checkpoint,
+                        rollback.messages_removed,
+                        rollback.files_restored.len()
+                    );
+                    for failure in rollback.failed {
+                        println!("  \x1b[0;31mCould not restore {}\x1b[0m", failure);
+                    }
+                }
+                Err(AgentError::Session(msg)) => println!("{}", msg),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        "/changes" => {
+            let Some(id) = session_manager.active_session_id().map(String::from) else {
+                println!("No active session.");
+                return Ok(true);
+            };
+            let store = session_manager.snapshot_store(&id);
+            let session = session_manager.active_session().expect("active session");
+            let changes = session.file_changes();
+            if changes.is_empty() {
+                println!("  No file changes.");
+            }
+            for change in changes {
+                let diff = change.diff(&store)?;
+                println!("  {:?} {}", diff.status, diff.path.display());
+            }
+        }
+        "/diff" => {
+            // `/diff [path]`
+            let Some(id) = session_manager.active_session_id().map(String::from) else {
+                println!("No active session.");
+                return Ok(true);
+            };
+            let store = session_manager.snapshot_store(&id);
+            let session = session_manager.active_session().expect("active session");
+            let filter = (!arg.is_empty()).then(|| resolve_path(arg)).transpose()?;
+            let changes: Vec<_> = session
+                .file_changes()
+                .into_iter()
+                .filter(|c| filter.as_ref().is_none_or(|p| *p == c.path))
+                .collect();
+            if changes.is_empty() {
+                println!("  No file changes.");
+            }
+            for change in changes {
+                print_diff(&change.diff(&store)?);
+            }
+        }
+        "/revert" | "/accept" => {
+            // `/revert <path>|--all`, `/accept <path>|--all`
+            let Some(id) = session_manager.active_session_id().map(String::from) else {
+                println!("No active session.");
+                return Ok(true);
+            };
+            if arg.is_empty() {
+                println!("Usage: {} <path>|--all", cmd);
+                return Ok(true);
+            }
+            let store = session_manager.snapshot_store(&id);
+            let session = session_manager.active_session_mut().expect("active session");
+            let paths: Vec<std::path::PathBuf> = if arg == "--all" {
+                session.file_changes().iter().map(|c| c.path.clone()).collect()
+            } else {
+                vec![resolve_path(arg)?]
+            };
+            for path in &paths {
+                let result = match cmd {
+                    "/revert" => session.revert_file(path, &store),
+                    _ => session.accept_file(path, &store),
+                };
+                match result {
+                    Ok(()) if cmd == "/revert" => println!("Reverted {}", path.display()),
+                    Ok(()) => println!("Accepted {}", path.display()),
+                    Err(AgentError::Session(msg)) => println!("{}", msg),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            session_manager.save_active()?;
+        }
+        "/feedback" => {
+            // `/feedback up|down [comment]` on the latest answer.
+            let (rating, comment) = arg.split_once(' ').unwrap_or((arg, ""));
+            let rating: Rating = match rating.parse() {
+                Ok(rating) => rating,
+                Err(_) => {
+                    println!("Usage: /feedback up|down [comment]");
+                    return Ok(true);
+                }
+            };
+            let Some(session) = session_manager.active_session_mut() else {
+                println!("No active session.");
+                return Ok(true);
+            };
+            let Some(id) = session
+                .messages
+                .iter()
+                .rev()
+                .find(|m| m.role == Role::Assistant && !m.content.trim().is_empty())
+                .map(|m| m.id.clone())
+            else {
+                println!("No answer to rate yet.");
+                return Ok(true);
+            };
+            let comment = comment.trim();
+            session.set_feedback(
+                &id,
+                Some(Feedback {
+                    rating,
+                    comment: (!comment.is_empty()).then(|| comment.to_string()),
+                    timestamp: chrono::Utc::now(),
+                }),
+            );
+            session_manager.save_active()?;
+            println!(
+                "Rated the last answer {}.",
+                if rating == Rating::Up { "👍" } else { "👎" }
+            );
+        }
         "/help" | "/?" => {
-            println!("  /new [name]    — Create a new session");
+            println!("  /new [name] [--agent NAME] — Create a new session");
+            println!("  /agents        — List agent presets");
             println!("  /sessions      — List all sessions");
             println!("  /switch <id>   — Switch to a session");
-            println!("  /tools         — List available tools");
-            println!("  /skills        — List loaded skills");
+            println!("  /tools [name]  — List available tools (or show one)");
+            println!("  /skills [name] — List loaded skills (or show one)");
+            println!("  /plugins [start|stop <key>] — List plugins with status, or start/stop one");
+            println!("  /workspace [use|add <name>...] — List workspaces, or pick this session's (first is primary)");
             println!("  /context [dir] — Detect project, git, and runtime environments");
             println!("  /analytics     — Show session analytics summary");
             println!("  /shells        — List detected shells");
             println!("  /config        — Show current config");
             println!("  /clear         — Clear current session history");
+            println!("  /checkpoint [label] — Save a checkpoint (one is also taken every turn)");
+            println!("  /checkpoints   — List checkpoints");
+            println!("  /undo [id] [--keep-files] — Roll back the last turn (or to a checkpoint), restoring files");
+            println!("  /changes       — List files changed this session");
+            println!("  /diff [path]   — Show diffs of changed files");
+            println!("  /revert <path>|--all — Restore changed files to before the session");
+            println!("  /accept <path>|--all — Keep changed files and drop their snapshots");
+            println!("  /feedback up|down [comment] — Rate the last answer");
+            println!("  /approval [on|off|reset] — Ask before each tool call (y/n/always)");
+            println!("  /verbose [on|off] — Show full tool arguments and timings");
+            println!("  /edit [text]   — Compose a prompt in $EDITOR");
             println!("  /help          — Show this help");
             println!("  /exit          — Quit");
         }
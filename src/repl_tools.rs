@@ -0,0 +1,335 @@
+//! REPL commands the agent can run for the user.
+//!
+//! [`ReplCommandTool`] (`repl_command`) is registered only when the REPL is
+//! what is running. Its calls are handed to the REPL loop as
+//! [`ReplRequest`]s, answered by [`handle`] against the REPL's session
+//! manager, and announced in the transcript. A session switch waits until
+//! the turn's messages are saved to the session they belong to.
+
+use agent_core::config::AppConfig;
+use agent_core::error::AgentError;
+use agent_core::session::SessionManager;
+use agent_core::tool_registry::Tool;
+use agent_core::types::Role;
+use agent_skills::SkillIndexer;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+
+/// Sessions `find_sessions` returns at most.
+const MAX_FOUND: usize = 10;
+/// Characters of a session's first user message shown as a summary.
+const SUMMARY_CHARS: usize = 80;
+
+/// What the agent asked the REPL to do.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ReplAction {
+    FindSessions { query: String },
+    SwitchSession { session: String },
+    LoadSkill { skill: String },
+    ShowAnalytics,
+}
+
+/// A `repl_command` call waiting for the REPL.
+pub struct ReplRequest {
+    pub action: ReplAction,
+    pub reply: oneshot::Sender<Result<String, String>>,
+}
+
+pub struct ReplCommandTool {
+    requests: mpsc::UnboundedSender<ReplRequest>,
+}
+
+impl ReplCommandTool {
+    /// The tool and the receiving end the REPL answers requests from.
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<ReplRequest>) {
+        let (requests, rx) = mpsc::unbounded_channel();
+        (Self { requests }, rx)
+    }
+
+    fn err(msg: impl Into<String>) -> AgentError {
+        AgentError::ToolExecution {
+            tool_name: "repl_command".into(),
+            message: msg.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ReplCommandTool {
+    fn name(&self) -> &str {
+        "repl_command"
+    }
+
+    fn description(&self) -> &str {
+        "Act on the user's REPL when they ask you to: find their past sessions by topic, switch \
+         to one (after this reply), load a skill, or show usage analytics. The user sees each \
+         action in the transcript."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["find_sessions", "switch_session", "load_skill", "show_analytics"],
+                    "description": "find_sessions: search session names and messages; switch_session: continue in another session once this reply ends; load_skill: read a skill's document; show_analytics: usage summary across sessions"
+                },
+                "query": {
+                    "type": "string",
+                    "description": "Words to look for (find_sessions)"
+                },
+                "session": {
+                    "type": "string",
+                    "description": "Session ID, ID prefix or exact name (switch_session); use find_sessions first when unsure"
+                },
+                "skill": {
+                    "type": "string",
+                    "description": "Skill name (load_skill)"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<String, AgentError> {
+        let action: ReplAction = serde_json::from_value(args)
+            .map_err(|e| Self::err(format!("Invalid arguments: {}", e)))?;
+        let (reply, answer) = oneshot::channel();
+        self.requests
+            .send(ReplRequest { action, reply })
+            .map_err(|_| Self::err("The REPL is not running"))?;
+        answer
+            .await
+            .map_err(|_| Self::err("The REPL did not answer"))?
+            .map_err(Self::err)
+    }
+}
+
+/// The REPL's answer to one request.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Handled {
+    /// Returned to the model.
+    pub result: String,
+    /// Printed in the transcript.
+    pub confirmation: String,
+    /// Session to switch to once the turn is saved.
+    pub switch_to: Option<String>,
+}
+
+/// Carry out `action` for the REPL.
+pub fn handle(
+    action: ReplAction,
+    session_manager: &SessionManager,
+    skill_indexer: &SkillIndexer,
+    config: &AppConfig,
+) -> Result<Handled, String> {
+    match action {
+        ReplAction::FindSessions { query } => {
+            let found = find_sessions(session_manager, &query);
+            let result = if found.is_empty() {
+                format!("No sessions match '{}'.", query)
+            } else {
+                found.join("\n")
+            };
+            Ok(Handled {
+                result,
+                confirmation: format!("Searched sessions for '{}': {} found", query, found.len()),
+                switch_to: None,
+            })
+        }
+        ReplAction::SwitchSession { session } => {
+            let id = resolve_session(session_manager, &session)?;
+            let name = session_manager
+                .session(&id)
+                .map(|s| s.name.clone())
+                .unwrap_or_default();
+            if session_manager.active_session_id() == Some(id.as_str()) {
+                return Ok(Handled {
+                    result: format!("Already in session '{}'.", name),
+                    confirmation: format!("Already in session: {}", name),
+                    switch_to: None,
+                });
+            }
+            Ok(Handled {
+                result: format!(
+                    "The REPL switches to session '{}' ({}) once this reply ends.",
+                    name,
+                    &id[..8]
+                ),
+                confirmation: format!("Switching to session: {} ({}) after this reply", name, &id[..8]),
+                switch_to: Some(id),
+            })
+        }
+        ReplAction::LoadSkill { skill } => {
+            let content = skill_indexer
+                .read_skill_content(&skill)
+                .map_err(|e| e.to_string())?;
+            Ok(Handled {
+                result: content.content,
+                confirmation: format!("Loaded skill: {}", skill),
+                switch_to: None,
+            })
+        }
+        ReplAction::ShowAnalytics => {
+            let summary = crate::repl::analytics_summary(session_manager, config)
+                .unwrap_or_else(|| "No sessions to analyze.".to_string());
+            Ok(Handled {
+                confirmation: format!("Analytics:\n{}", summary.trim_end()),
+                result: summary,
+                switch_to: None,
+            })
+        }
+    }
+}
+
+/// Session by exact (case-insensitive) name, most recently updated first,
+/// else by ID or unique ID prefix.
+fn resolve_session(session_manager: &SessionManager, session: &str) -> Result<String, String> {
+    let session = session.trim();
+    if session.is_empty() {
+        return Err("Name the session to switch to".into());
+    }
+    let by_name = session_manager
+        .list_sessions()
+        .into_iter()
+        .find(|(_, name, _, _)| name.eq_ignore_ascii_case(session))
+        .map(|(id, _, _, _)| id.to_string());
+    by_name
+        .or_else(|| session_manager.resolve_prefix(session).ok())
+        .ok_or_else(|| {
+            format!(
+                "No session with ID or name '{}'; use find_sessions to search by topic",
+                session
+            )
+        })
+}
+
+/// Sessions whose name or messages contain words of `query`, best match
+/// first, one line each: ID prefix, name, size, last update and how the
+/// conversation started.
+fn find_sessions(session_manager: &SessionManager, query: &str) -> Vec<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .filter(|w| w.len() > 2)
+        .collect();
+    let mut scored: Vec<(usize, String)> = session_manager
+        .list_sessions()
+        .into_iter()
+        .filter_map(|(id, _, _, _)| {
+            let session = session_manager.session(id)?;
+            let name = session.name.to_lowercase();
+            let text: String = session
+                .messages
+                .iter()
+                .filter(|m| matches!(m.role, Role::User | Role::Assistant))
+                .map(|m| m.content.to_lowercase())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let score: usize = words
+                .iter()
+                .map(|w| 3 * usize::from(name.contains(w.as_str())) + text.matches(w.as_str()).count().min(5))
+                .sum();
+            if score == 0 && !words.is_empty() {
+                return None;
+            }
+            let started: String = session
+                .messages
+                .iter()
+                .find(|m| m.role == Role::User)
+                .map(|m| m.content.split_whitespace().collect::<Vec<_>>().join(" "))
+                .unwrap_or_default()
+                .chars()
+                .take(SUMMARY_CHARS)
+                .collect();
+            Some((
+                score,
+                format!(
+                    "{} {} ({} msgs, updated {}): {}",
+                    &session.id[..8],
+                    session.name,
+                    session.messages.len(),
+                    session.updated_at.format("%Y-%m-%d %H:%M"),
+                    started
+                ),
+            ))
+        })
+        .collect();
+    // Stable, so equal scores stay most recent first.
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().take(MAX_FOUND).map(|(_, line)| line).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::types::Message;
+
+    fn manager(dir: &std::path::Path) -> SessionManager {
+        let mut config = AppConfig::default();
+        config.session.history_dir = Some(dir.to_path_buf());
+        SessionManager::new(&config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tool_forwards_actions() {
+        let (tool, mut rx) = ReplCommandTool::new();
+        let repl = tokio::spawn(async move {
+            let request = rx.recv().await.unwrap();
+            assert_eq!(
+                request.action,
+                ReplAction::SwitchSession { session: "parser".into() }
+            );
+            request.reply.send(Err("no such session".into())).unwrap();
+        });
+        let err = tool
+            .execute(json!({"action": "switch_session", "session": "parser"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no such session"));
+        repl.await.unwrap();
+        assert!(tool.execute(json!({"action": "explode"})).await.is_err());
+    }
+
+    #[test]
+    fn test_find_and_switch_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sessions = manager(dir.path());
+        let parser = sessions.create_session("debugging").unwrap().id.clone();
+        sessions
+            .push_message(Message::user("The parser panics on nested brackets"))
+            .unwrap();
+        sessions.create_session("release notes").unwrap();
+        let skills = SkillIndexer::new(dir.path().join("skills"));
+        let config = AppConfig::default();
+
+        let found = find_sessions(&sessions, "parser bug");
+        assert_eq!(found.len(), 1);
+        assert!(found[0].starts_with(&parser[..8]));
+        assert!(found[0].ends_with("The parser panics on nested brackets"));
+
+        let handled = handle(
+            ReplAction::SwitchSession { session: "Debugging".into() },
+            &sessions,
+            &skills,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(handled.switch_to.as_deref(), Some(parser.as_str()));
+        assert!(handle(
+            ReplAction::SwitchSession { session: "nothing like it".into() },
+            &sessions,
+            &skills,
+            &config,
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,313 @@
+//! Code review of git changes (`agent-shell review`).
+//!
+//! Gathers a diff through the git context, runs one turn with a
+//! review-focused agent preset and prints the structured findings as
+//! terminal output, JSON or a Markdown comment. With `--fail-on` it doubles
+//! as a pre-commit / pre-push hook.
+
+use crate::ReportFormat;
+use agent_core::agent_loop::AgentLoop;
+use agent_core::agents::AgentPreset;
+use agent_core::config::AppConfig;
+use agent_core::context::{ContextLinker, DiffTarget};
+use agent_core::tool_registry::ToolRegistry;
+use agent_core::types::{AgentEvent, Message};
+use agent_skills::SkillIndexer;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Exit status: review completed below the `--fail-on` threshold.
+pub const EXIT_OK: i32 = 0;
+/// Exit status: the agent failed or its reply could not be parsed.
+pub const EXIT_AGENT_ERROR: i32 = 1;
+/// Exit status: the diff could not be gathered (not a repo, bad range).
+pub const EXIT_USAGE: i32 = 2;
+/// Exit status: a finding reached the `--fail-on` severity.
+pub const EXIT_FINDINGS: i32 = 3;
+
+/// Diffs larger than this are truncated before being sent to the model.
+const MAX_DIFF_BYTES: usize = 200 * 1024;
+
+/// Preset used when `--agent` is not given and no `reviewer` agent exists.
+const REVIEW_SYSTEM_PROMPT: &str = "You are a meticulous senior code reviewer. \
+Look for bugs, security problems, race conditions, missing error handling, \
+unclear naming and missing tests. Only report issues you are confident about; \
+do not comment on formatting a linter would catch. You may read files to \
+understand the surrounding code.";
+
+/// Output format appended to the request regardless of the preset.
+const REVIEW_INSTRUCTIONS: &str = r#"Review the following diff. Reply with only a JSON object:
+{"summary": "<one or two sentences>", "findings": [{"file": "<path>", "line": <line in the new file or null>, "severity": "info" | "warning" | "error", "issue": "<what is wrong>", "suggestion": "<how to fix it>"}]}
+Use an empty findings list when the change looks good."#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[serde(alias = "low", alias = "nit", alias = "suggestion")]
+    Info,
+    #[serde(alias = "medium", alias = "warn")]
+    Warning,
+    #[serde(alias = "high", alias = "critical")]
+    Error,
+}
+
+impl Severity {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Self::Info => "\x1b[0;36m",
+            Self::Warning => "\x1b[1;33m",
+            Self::Error => "\x1b[1;31m",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub file: String,
+    #[serde(default)]
+    pub line: Option<u32>,
+    pub severity: Severity,
+    pub issue: String,
+    #[serde(default)]
+    pub suggestion: Option<String>,
+}
+
+impl Finding {
+    fn location(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}", self.file, line),
+            None => self.file.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Review {
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+}
+
+impl Review {
+    /// Most severe finding, if any.
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|f| f.severity).max()
+    }
+}
+
+/// The built-in review preset: read-only tools and a low temperature.
+fn default_preset() -> AgentPreset {
+    AgentPreset {
+        description: Some("Built-in code reviewer".into()),
+        system_prompt: Some(REVIEW_SYSTEM_PROMPT.into()),
+        temperature: Some(0.2),
+        tools: Some(vec!["file_read".into(), "file_list".into()]),
+        ..Default::default()
+    }
+}
+
+/// The user message: format instructions followed by the (possibly
+/// truncated) diff.
+pub fn build_request(diff: &str) -> String {
+    let mut end = diff.len().min(MAX_DIFF_BYTES);
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    let note = if end < diff.len() {
+        "\n[... diff truncated; review what is shown]"
+    } else {
+        ""
+    };
+    format!("{}\n\n```diff\n{}{}\n```", REVIEW_INSTRUCTIONS, &diff[..end], note)
+}
+
+/// Extract the JSON review, tolerating a surrounding code fence or prose.
+pub fn parse_review(text: &str) -> Result<Review, String> {
+    let json = match (text.find('{'), text.rfind('}')) {
+        (Some(s), Some(e)) if s < e => &text[s..=e],
+        _ => return Err("model reply did not contain a JSON object".into()),
+    };
+    serde_json::from_str(json).map_err(|e| format!("model reply was not valid JSON: {e}"))
+}
+
+/// Findings for a terminal, colored unless `plain`.
+pub fn render_text(review: &Review, plain: bool) -> String {
+    let mut out = String::new();
+    if !review.summary.is_empty() {
+        out.push_str(review.summary.trim());
+        out.push_str("\n\n");
+    }
+    if review.findings.is_empty() {
+        out.push_str("No issues found.\n");
+    }
+    for finding in &review.findings {
+        let severity = if plain {
+            format!("{:<7}", finding.severity.label())
+        } else {
+            format!(
+                "{}{:<7}\x1b[0m",
+                finding.severity.color(),
+                finding.severity.label()
+            )
+        };
+        out.push_str(&format!("{}  {}  {}\n", severity, finding.location(), finding.issue));
+        if let Some(suggestion) = finding.suggestion.as_deref().filter(|s| !s.is_empty()) {
+            out.push_str(&format!("         → {}\n", suggestion));
+        }
+    }
+    out
+}
+
+/// Findings as a Markdown comment ready to paste into a pull request.
+pub fn render_markdown(review: &Review) -> String {
+    let mut out = String::from("### Code review\n\n");
+    if !review.summary.is_empty() {
+        out.push_str(review.summary.trim());
+        out.push_str("\n\n");
+    }
+    if review.findings.is_empty() {
+        out.push_str("No issues found.\n");
+    }
+    for finding in &review.findings {
+        out.push_str(&format!(
+            "- **{}** `{}`: {}\n",
+            finding.severity.label(),
+            finding.location(),
+            finding.issue
+        ));
+        if let Some(suggestion) = finding.suggestion.as_deref().filter(|s| !s.is_empty()) {
+            out.push_str(&format!("  - Suggestion: {}\n", suggestion));
+        }
+    }
+    out
+}
+
+/// Review the changes in `target` and return the process exit code.
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    config: AppConfig,
+    tool_registry: Arc<ToolRegistry>,
+    skill_indexer: Arc<SkillIndexer>,
+    target: DiffTarget,
+    agent: Option<String>,
+    format: ReportFormat,
+    fail_on: Option<Severity>,
+    plain: bool,
+) -> Result<i32> {
+    let dir = std::env::current_dir()?;
+    let diff = match ContextLinker::get_git_diff(&dir, &target) {
+        Ok(diff) => diff,
+        Err(e) => {
+            eprintln!("Could not get diff: {}", e);
+            return Ok(EXIT_USAGE);
+        }
+    };
+    if diff.trim().is_empty() {
+        match format {
+            ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&Review::default())?),
+            _ => eprintln!("No changes to review."),
+        }
+        return Ok(EXIT_OK);
+    }
+
+    // An explicit --agent must exist; otherwise use a `reviewer` preset if
+    // one is defined, falling back to the built-in one.
+    let preset = match agent {
+        Some(name) => match crate::repl::resolve_agent(&config, &skill_indexer, &name) {
+            Ok(preset) => preset,
+            Err(e) => {
+                eprintln!("{}", e);
+                return Ok(EXIT_USAGE);
+            }
+        },
+        None => crate::repl::resolve_agent(&config, &skill_indexer, "reviewer")
+            .unwrap_or_else(|_| default_preset()),
+    };
+
+    let agent = AgentLoop::new(config, tool_registry)?;
+    let messages = vec![Message::user(build_request(&diff))];
+    let (tx, mut rx) = mpsc::unbounded_channel::<AgentEvent>();
+    let handle = tokio::spawn(async move {
+        agent.run_as(Some(&preset), &messages, None, &[], &Default::default(), tx).await
+    });
+
+    let progress = std::io::stderr().is_terminal();
+    while let Some(event) = rx.recv().await {
+        if let AgentEvent::ToolCallStart { name, .. } = event {
+            if progress {
+                eprintln!("\x1b[0;35m⚡ {}\x1b[0m", name);
+            }
+        }
+    }
+    let reply = match handle.await? {
+        Ok(result) => result.final_message().content.clone(),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return Ok(EXIT_AGENT_ERROR);
+        }
+    };
+    let review = match parse_review(&reply) {
+        Ok(review) => review,
+        Err(e) => {
+            eprintln!("Error: {}\n\n{}", e, reply);
+            return Ok(EXIT_AGENT_ERROR);
+        }
+    };
+
+    match format {
+        ReportFormat::Text => print!("{}", render_text(&review, plain)),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&review)?),
+        ReportFormat::Markdown => print!("{}", render_markdown(&review)),
+    }
+
+    let failed = matches!((fail_on, review.max_severity()), (Some(min), Some(max)) if max >= min);
+    Ok(if failed { EXIT_FINDINGS } else { EXIT_OK })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_render_review() {
+        let reply = r#"Here you go:
+```json
+{"summary": "Mostly fine.", "findings": [
+  {"file": "src/lib.rs", "line": 12, "severity": "critical", "issue": "unwrap on input", "suggestion": "return an error"},
+  {"file": "README.md", "severity": "nit", "issue": "typo"}
+]}
+```"#;
+        let review = parse_review(reply).unwrap();
+        assert_eq!(review.findings.len(), 2);
+        assert_eq!(review.findings[0].severity, Severity::Error);
+        assert_eq!(review.findings[1].line, None);
+        assert_eq!(review.max_severity(), Some(Severity::Error));
+
+        let text = render_text(&review, true);
+        assert!(text.contains("error    src/lib.rs:12  unwrap on input\n         → return an error"));
+        let md = render_markdown(&review);
+        assert!(md.contains("- **info** `README.md`: typo\n"));
+        assert!(parse_review("looks good").is_err());
+    }
+
+    #[test]
+    fn test_build_request_truncates_diff() {
+        let diff = "+é".repeat(MAX_DIFF_BYTES);
+        let request = build_request(&diff);
+        assert!(request.starts_with(REVIEW_INSTRUCTIONS));
+        assert!(request.contains("[... diff truncated"));
+        assert!(request.len() < MAX_DIFF_BYTES + REVIEW_INSTRUCTIONS.len() + 100);
+    }
+}
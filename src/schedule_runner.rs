@@ -0,0 +1,396 @@
+//! Executes tasks fired by the scheduler.
+//!
+//! Prompt tasks run one agent turn in the schedule's own session (resumed by
+//! name, created on the first run) against the schedule's workspace root, so
+//! a nightly job neither lands in the user's default session nor reads the
+//! wrong project. Heartbeats do the same with their skill's document added
+//! to the system prompt and a kickoff prompt built from a template. Custom
+//! tasks run a shell command through the sandbox and/or POST to a webhook,
+//! logging what happened in the schedule's session.
+
+use agent_core::agent_loop::AgentLoop;
+use agent_core::agents::AgentPreset;
+use agent_core::config::AppConfig;
+use agent_core::scheduler::{RunStatus, ScheduleRun, ScheduledTask};
+use agent_core::session::SessionManager;
+use agent_core::tool_registry::ToolRegistry;
+use agent_core::types::{AgentEvent, Message};
+use agent_core::workspace;
+use agent_skills::SkillIndexer;
+use agent_tools::sandbox::SandboxExecutor;
+use anyhow::{bail, Result};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Longest summary kept in the run history.
+const MAX_SUMMARY_CHARS: usize = 500;
+
+/// How long a custom task's webhook may take to answer.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Kickoff prompt for heartbeats without a `prompt` of their own.
+const HEARTBEAT_TEMPLATE: &str = "Heartbeat check-in for schedule '{schedule}' at {now}. \
+Work through the '{skill}' skill in your instructions: check what it covers, act on anything \
+that needs attention, and end with a short status report.";
+
+/// Run a fired task to completion and describe the outcome.
+pub async fn execute(
+    config: &AppConfig,
+    skill_indexer: Arc<SkillIndexer>,
+    task: ScheduledTask,
+) -> ScheduleRun {
+    let fired_at = chrono::Utc::now();
+    let (schedule, result) = match task {
+        ScheduledTask::Prompt {
+            schedule_name,
+            session,
+            workspace_root,
+            prompt,
+            ..
+        } => {
+            tracing::info!(
+                "Scheduled task '{}' fired: prompt={} (session '{}')",
+                schedule_name,
+                &prompt[..prompt.len().min(80)],
+                session
+            );
+            let result =
+                run_prompt(config, skill_indexer, &session, workspace_root, None, prompt).await;
+            (schedule_name, Some(result))
+        }
+        ScheduledTask::Heartbeat {
+            schedule_name,
+            session,
+            workspace_root,
+            skill,
+            prompt,
+            ..
+        } => {
+            tracing::info!(
+                "Scheduled task '{}' fired: heartbeat (skill={}, session '{}')",
+                schedule_name,
+                skill,
+                session
+            );
+            let result = run_heartbeat(
+                config,
+                skill_indexer,
+                &schedule_name,
+                &session,
+                workspace_root,
+                &skill,
+                prompt.as_deref(),
+            )
+            .await;
+            (schedule_name, Some(result))
+        }
+        ScheduledTask::Custom {
+            schedule_name,
+            session,
+            workspace_root,
+            command,
+            webhook,
+            ..
+        } => {
+            tracing::info!("Scheduled task '{}' fired: custom (session '{}')", schedule_name, session);
+            let result = run_custom(
+                config,
+                &schedule_name,
+                &session,
+                workspace_root,
+                command.as_deref(),
+                webhook.as_deref(),
+            )
+            .await;
+            (schedule_name, Some(result))
+        }
+    };
+
+    let mut run = ScheduleRun {
+        schedule,
+        fired_at,
+        finished_at: None,
+        status: RunStatus::Fired,
+        error: None,
+        summary: None,
+    };
+    match result {
+        Some(Ok(answer)) => {
+            run.finished_at = Some(chrono::Utc::now());
+            run.status = RunStatus::Succeeded;
+            run.summary = Some(answer.chars().take(MAX_SUMMARY_CHARS).collect());
+        }
+        Some(Err(e)) => {
+            tracing::warn!("Scheduled task '{}' failed: {:#}", run.schedule, e);
+            run.finished_at = Some(chrono::Utc::now());
+            run.status = RunStatus::Failed;
+            run.error = Some(format!("{:#}", e));
+        }
+        None => {}
+    }
+    run
+}
+
+/// `config` with a schedule's `workspace_root`, if it has one.
+fn with_workspace_root(config: &AppConfig, workspace_root: Option<PathBuf>) -> Result<AppConfig> {
+    let mut config = config.clone();
+    if let Some(root) = workspace_root {
+        if !root.is_dir() {
+            bail!("workspace_root {} is not a directory", root.display());
+        }
+        config.sandbox.workspace_root = Some(root);
+    }
+    Ok(config)
+}
+
+/// Run a custom task: `command` through the sandbox, then a POST to
+/// `webhook` with the command's result. Both are logged to the session;
+/// a non-zero exit or an error response fails the run.
+async fn run_custom(
+    config: &AppConfig,
+    schedule: &str,
+    session_name: &str,
+    workspace_root: Option<PathBuf>,
+    command: Option<&str>,
+    webhook: Option<&str>,
+) -> Result<String> {
+    if command.is_none() && webhook.is_none() {
+        bail!("custom schedule has neither a command nor a webhook");
+    }
+    let config = with_workspace_root(config, workspace_root)?;
+    let mut sessions = SessionManager::new(&config)?;
+    sessions.resume_or_create(session_name)?;
+
+    let mut summary = Vec::new();
+    let mut payload = serde_json::json!({
+        "schedule": schedule,
+        "fired_at": chrono::Utc::now(),
+    });
+    let mut failure = None;
+    if let Some(command) = command {
+        let result = SandboxExecutor::new(&config).exec_shell(command).await?;
+        sessions.push_message(Message::user(format!("Scheduled command: `{}`", command)))?;
+        sessions.push_message(Message::assistant(result.to_display_string()))?;
+        summary.push(format!("exit code {}", result.exit_code));
+        if result.exit_code != 0 {
+            failure = Some(format!(
+                "command exited with code {}:\n{}",
+                result.exit_code,
+                result.tail(5)
+            ));
+        }
+        payload["exit_code"] = result.exit_code.into();
+        payload["stdout"] = result.stdout.into();
+        payload["stderr"] = result.stderr.into();
+    }
+    if let Some(url) = webhook {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()?;
+        let status = client
+            .post(url)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map(|r| r.status());
+        let line = match &status {
+            Ok(status) => format!("POST {} → {}", url, status),
+            Err(e) => format!("POST {} failed: {}", url, e),
+        };
+        sessions.push_message(Message::assistant(format!("Scheduled webhook: {}", line)))?;
+        summary.push(line);
+        if let Err(e) = status {
+            failure.get_or_insert_with(|| format!("webhook failed: {}", e));
+        }
+    }
+    match failure {
+        Some(error) => bail!(error),
+        None => Ok(summary.join("; ")),
+    }
+}
+
+/// Fill `{schedule}`, `{skill}` and `{now}` into a kickoff template.
+fn kickoff_prompt(template: &str, schedule: &str, skill: &str, now: &str) -> String {
+    template
+        .replace("{schedule}", schedule)
+        .replace("{skill}", skill)
+        .replace("{now}", now)
+}
+
+/// Run a heartbeat: `skill`'s document joins the system prompt and the
+/// kickoff prompt starts the turn.
+async fn run_heartbeat(
+    config: &AppConfig,
+    skill_indexer: Arc<SkillIndexer>,
+    schedule: &str,
+    session_name: &str,
+    workspace_root: Option<PathBuf>,
+    skill: &str,
+    template: Option<&str>,
+) -> Result<String> {
+    if skill.is_empty() {
+        bail!("heartbeat schedule has no skill");
+    }
+    let content = skill_indexer.read_skill_content(skill)?;
+    let preset = AgentPreset::default().with_skill_docs(
+        config.system_prompt.as_deref(),
+        &[(content.name, content.content)],
+    );
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M %Z").to_string();
+    let prompt = kickoff_prompt(template.unwrap_or(HEARTBEAT_TEMPLATE), schedule, skill, &now);
+    run_prompt(
+        config,
+        skill_indexer,
+        session_name,
+        workspace_root,
+        Some(preset),
+        prompt,
+    )
+    .await
+}
+
+/// Run `prompt` in `session_name`, with `preset` applied if given, and
+/// return the final answer.
+async fn run_prompt(
+    config: &AppConfig,
+    skill_indexer: Arc<SkillIndexer>,
+    session_name: &str,
+    workspace_root: Option<PathBuf>,
+    preset: Option<AgentPreset>,
+    prompt: String,
+) -> Result<String> {
+    let pinned = workspace_root.is_some();
+    let config = with_workspace_root(config, workspace_root)?;
+
+    // File tools and project context are bound to the workspace root when
+    // they are built, so each run gets its own registry.
+    let mut registry = ToolRegistry::new();
+    agent_tools::register_all(&mut registry, &config, Some(skill_indexer));
+    let agent = AgentLoop::new(config.clone(), Arc::new(registry))?;
+
+    let mut sessions = SessionManager::new(&config)?;
+    sessions.resume_or_create(session_name)?;
+    if let Some(session) = sessions.active_session_mut() {
+        if session.working_directory.is_none() {
+            session.working_directory = config.sandbox.workspace_root.clone();
+        }
+    }
+    // A schedule's own workspace_root wins over the session's workspaces.
+    let (allowlist, denylist, tool_calls, roots) = sessions
+        .active_session()
+        .map(|s| {
+            let roots = if pinned {
+                Vec::new()
+            } else {
+                workspace::roots_for(&config.sandbox, s)
+            };
+            (s.tool_allowlist.clone(), s.tool_denylist.clone(), s.tool_calls.clone(), roots)
+        })
+        .unwrap_or_default();
+
+    let user = Message::user(prompt);
+    let mut messages: Vec<Message> = sessions.recent_messages().into_iter().cloned().collect();
+    messages.push(user.clone());
+
+    // Nobody watches a scheduled run; events are only drained.
+    let (tx, mut rx) = mpsc::unbounded_channel::<AgentEvent>();
+    let drain = tokio::spawn(async move { while rx.recv().await.is_some() {} });
+    let result = workspace::scope(
+        roots,
+        agent.run_as(
+            preset.as_ref(),
+            &messages,
+            allowlist.as_deref(),
+            &denylist,
+            &tool_calls,
+            tx,
+        ),
+    )
+    .await;
+    let _ = drain.await;
+    let result = result?;
+
+    let answer = result.final_message().content.clone();
+    sessions.auto_checkpoint()?;
+    sessions.record_snapshots(result.snapshots)?;
+    sessions.record_tool_calls(&result.tool_calls)?;
+    sessions.record_usage(result.usage)?;
+    sessions.push_message(user)?;
+    for msg in result.messages {
+        sessions.push_message(msg)?;
+    }
+    Ok(answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kickoff_prompt() {
+        let prompt = kickoff_prompt(HEARTBEAT_TEMPLATE, "inbox", "triage", "2026-03-02 09:00 UTC");
+        assert!(prompt.starts_with("Heartbeat check-in for schedule 'inbox' at 2026-03-02 09:00 UTC."));
+        assert!(prompt.contains("the 'triage' skill"));
+        assert_eq!(kickoff_prompt("{skill}: go", "s", "k", "now"), "k: go");
+    }
+
+    fn custom(command: Option<&str>) -> ScheduledTask {
+        ScheduledTask::Custom {
+            schedule_name: "cleanup".into(),
+            workspace: "default".into(),
+            session: "schedule:cleanup".into(),
+            workspace_root: None,
+            command: command.map(String::from),
+            webhook: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_task_runs_command_into_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.session.history_dir = Some(dir.path().join("sessions"));
+        config.sandbox.mode = agent_core::config::SandboxMode::Unsafe;
+        let skills = Arc::new(SkillIndexer::new(dir.path().join("skills")));
+
+        let run = execute(&config, skills.clone(), custom(Some("echo swept"))).await;
+        assert_eq!(run.status, RunStatus::Succeeded, "{:?}", run.error);
+        assert_eq!(run.summary.as_deref(), Some("exit code 0"));
+        let mut sessions = SessionManager::new(&config).unwrap();
+        sessions.resume_or_create("schedule:cleanup").unwrap();
+        let messages = &sessions.active_session().unwrap().messages;
+        assert_eq!(messages[0].content, "Scheduled command: `echo swept`");
+        assert!(messages[1].content.contains("swept"));
+
+        let run = execute(&config, skills.clone(), custom(Some("echo oops >&2; exit 3"))).await;
+        assert_eq!(run.status, RunStatus::Failed);
+        let error = run.error.unwrap();
+        assert!(error.contains("code 3") && error.contains("oops"), "{}", error);
+
+        let run = execute(&config, skills, custom(None)).await;
+        assert_eq!(run.status, RunStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_fails_without_skill() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.session.history_dir = Some(dir.path().join("sessions"));
+        let skills = Arc::new(SkillIndexer::new(dir.path().join("skills")));
+        let task = ScheduledTask::Heartbeat {
+            schedule_name: "hb".into(),
+            workspace: "default".into(),
+            session: "schedule:hb".into(),
+            workspace_root: None,
+            skill: "missing".into(),
+            prompt: None,
+        };
+        let run = execute(&config, skills, task).await;
+        assert_eq!(run.status, RunStatus::Failed);
+        assert!(run.finished_at.is_some());
+        assert!(run.error.is_some());
+    }
+}
@@ -0,0 +1,305 @@
+//! Session picker shown when the REPL starts with several sessions and
+//! none was named.
+//!
+//! Type to fuzzy-filter by name (or ID prefix), move with the arrow keys
+//! and press Enter to resume; Ctrl+N starts a new session named after the
+//! query. Esc keeps the most recently updated session, which is what the
+//! REPL resumed before there was a picker.
+
+use chrono::{DateTime, Utc};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+use ratatui::{Frame, TerminalOptions, Viewport};
+
+/// Terminal lines the picker takes below the prompt.
+const HEIGHT: u16 = 14;
+
+/// One session as listed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionRow {
+    pub id: String,
+    pub name: String,
+    pub updated: DateTime<Utc>,
+    pub messages: usize,
+}
+
+/// What the user chose.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pick {
+    Session(String),
+    /// Start a new session with this name.
+    New(String),
+}
+
+/// Score `candidate` against `query` as a case-insensitive subsequence, or
+/// `None` if it does not match. Consecutive matches and matches at word
+/// starts score higher; gaps cost a little. Each place the first query
+/// character occurs is tried as the start, keeping the best.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let chars: Vec<char> = candidate.to_lowercase().chars().collect();
+    (0..chars.len())
+        .filter(|&start| chars[start] == query[0])
+        .filter_map(|start| score_from(&query, &chars, start))
+        .max()
+}
+
+/// Greedy match of `query` in `chars` with its first character at `start`.
+fn score_from(query: &[char], chars: &[char], start: usize) -> Option<i64> {
+    let mut score = 0;
+    let mut q = 0;
+    let mut last: Option<usize> = None;
+    for (i, &c) in chars.iter().enumerate().skip(start) {
+        if q == query.len() {
+            break;
+        }
+        if c != query[q] {
+            continue;
+        }
+        score += 1;
+        if i == 0 || !chars[i - 1].is_alphanumeric() {
+            score += 8;
+        }
+        match last {
+            Some(prev) if prev + 1 == i => score += 5,
+            Some(prev) => score -= (i - prev - 1).min(5) as i64,
+            None => {}
+        }
+        last = Some(i);
+        q += 1;
+    }
+    (q == query.len()).then_some(score)
+}
+
+/// Indexes of the rows matching `query`, best first; rows are assumed to
+/// be sorted most recent first, which breaks ties.
+fn filter(rows: &[SessionRow], query: &str) -> Vec<usize> {
+    let mut scored: Vec<(i64, usize)> = rows
+        .iter()
+        .enumerate()
+        .filter_map(|(i, row)| {
+            let by_name = fuzzy_score(query, &row.name);
+            let by_id = row.id.starts_with(query.trim()).then_some(0);
+            by_name.max(by_id).map(|score| (score, i))
+        })
+        .collect();
+    scored.sort_by_key(|&(score, i)| (std::cmp::Reverse(score), i));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// "just now", "5m ago", "3h ago", "2d ago", then the date.
+fn ago(when: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let secs = (now - when).num_seconds().max(0);
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        86_400..=604_799 => format!("{}d ago", secs / 86_400),
+        _ => when.format("%Y-%m-%d").to_string(),
+    }
+}
+
+struct Picker<'a> {
+    rows: &'a [SessionRow],
+    query: String,
+    matches: Vec<usize>,
+    list: ListState,
+}
+
+impl<'a> Picker<'a> {
+    fn new(rows: &'a [SessionRow]) -> Self {
+        let mut picker = Self {
+            rows,
+            query: String::new(),
+            matches: Vec::new(),
+            list: ListState::default(),
+        };
+        picker.refilter();
+        picker
+    }
+
+    fn refilter(&mut self) {
+        self.matches = filter(self.rows, &self.query);
+        self.list
+            .select(if self.matches.is_empty() { None } else { Some(0) });
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if let Some(selected) = self.list.selected() {
+            let last = self.matches.len().saturating_sub(1) as isize;
+            self.list
+                .select(Some((selected as isize + delta).clamp(0, last) as usize));
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [input, list, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled("Resume session ❯ ", Style::new().fg(Color::Cyan)),
+                Span::raw(self.query.as_str()),
+                Span::styled(
+                    format!("  {}/{}", self.matches.len(), self.rows.len()),
+                    Style::new().fg(Color::DarkGray),
+                ),
+            ])),
+            input,
+        );
+
+        let now = Utc::now();
+        let width = self
+            .matches
+            .iter()
+            .map(|&i| self.rows[i].name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(40);
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .map(|&i| {
+                let row = &self.rows[i];
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{:<width$}", row.name)),
+                    Span::styled(
+                        format!(
+                            "  {:>5} msgs  {:<10}  {}",
+                            row.messages,
+                            ago(row.updated, now),
+                            &row.id[..8.min(row.id.len())]
+                        ),
+                        Style::new().fg(Color::DarkGray),
+                    ),
+                ]))
+            })
+            .collect();
+        let list_widget = List::new(items)
+            .highlight_symbol("▶ ")
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list_widget, list, &mut self.list);
+
+        frame.render_widget(
+            Paragraph::new("Enter resume · ↑/↓ move · Ctrl+N new session · Esc most recent")
+                .style(Style::new().fg(Color::DarkGray)),
+            help,
+        );
+    }
+}
+
+/// Let the user pick one of `rows` (most recent first). `None` means keep
+/// the most recent.
+pub fn pick(rows: &[SessionRow]) -> std::io::Result<Option<Pick>> {
+    let mut terminal = ratatui::init_with_options(TerminalOptions {
+        viewport: Viewport::Inline(HEIGHT),
+    });
+    let mut picker = Picker::new(rows);
+    let result = (|| loop {
+        terminal.draw(|frame| picker.draw(frame))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c') | KeyCode::Char('d') if ctrl => return Ok(None),
+            KeyCode::Char('n') if ctrl => {
+                let name = picker.query.trim();
+                let name = if name.is_empty() { "default" } else { name };
+                return Ok(Some(Pick::New(name.to_string())));
+            }
+            KeyCode::Char('u') if ctrl => {
+                picker.query.clear();
+                picker.refilter();
+            }
+            KeyCode::Enter => {
+                if let Some(selected) = picker.list.selected() {
+                    let row = &rows[picker.matches[selected]];
+                    return Ok(Some(Pick::Session(row.id.clone())));
+                }
+            }
+            KeyCode::Up => picker.move_by(-1),
+            KeyCode::Down => picker.move_by(1),
+            KeyCode::PageUp => picker.move_by(-10),
+            KeyCode::PageDown => picker.move_by(10),
+            KeyCode::Backspace => {
+                picker.query.pop();
+                picker.refilter();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                picker.query.push(c);
+                picker.refilter();
+            }
+            _ => {}
+        }
+    })();
+    let _ = terminal.clear();
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn row(id: &str, name: &str) -> SessionRow {
+        SessionRow {
+            id: id.into(),
+            name: name.into(),
+            updated: Utc::now(),
+            messages: 0,
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert!(fuzzy_score("abc", "a-b-c").is_some());
+        assert_eq!(fuzzy_score("acb", "abc"), None);
+        // Consecutive and word-start matches beat scattered ones.
+        assert!(fuzzy_score("rust", "rust-refactor") > fuzzy_score("rust", "a robust test"));
+        assert!(fuzzy_score("ref", "rust refactor") > fuzzy_score("ref", "prefix"));
+        assert!(fuzzy_score("API", "api-docs").is_some());
+    }
+
+    #[test]
+    fn test_filter_ranks_and_matches_id_prefix() {
+        let rows = vec![
+            row("aaaa1111", "deploy scripts"),
+            row("bbbb2222", "docs"),
+            row("cccc3333", "debugging session"),
+        ];
+        assert_eq!(filter(&rows, ""), vec![0, 1, 2]);
+        assert_eq!(filter(&rows, "docs"), vec![1, 0]);
+        // Ties go to the more recent session.
+        assert_eq!(filter(&rows, "ds"), vec![0, 2, 1]);
+        assert_eq!(filter(&rows, "cccc"), vec![2]);
+        assert!(filter(&rows, "zzz").is_empty());
+    }
+
+    #[test]
+    fn test_ago() {
+        let now = Utc::now();
+        assert_eq!(ago(now, now), "just now");
+        assert_eq!(ago(now - Duration::minutes(5), now), "5m ago");
+        assert_eq!(ago(now - Duration::hours(3), now), "3h ago");
+        assert_eq!(ago(now - Duration::days(2), now), "2d ago");
+        let old = now - Duration::days(30);
+        assert_eq!(ago(old, now), old.format("%Y-%m-%d").to_string());
+    }
+}
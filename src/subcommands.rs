@@ -0,0 +1,359 @@
+//! Scriptable `sessions`, `dataset`, `import`, `skills` and `analytics`
+//! subcommands.
+//!
+//! Each mirrors a REPL command or server endpoint and prints plain text by
+//! default or JSON with `--json`.
+
+use crate::{AnalyticsAction, DatasetAction, DatasetFormat, ExportFormat, SessionsAction, SkillsAction};
+use agent_core::config::{AppConfig, PruneAction};
+use agent_core::dataset::{self, DatasetFilter};
+use agent_core::import::ImportSource;
+use agent_core::scrubber::SecretScrubber;
+use agent_core::session::{PruneReason, SessionManager};
+use agent_skills::{SearchOptions, SearchService, SkillIndexer};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+pub fn handle_sessions(action: SessionsAction, config: &AppConfig) -> Result<()> {
+    let mut sm = SessionManager::new(config)?;
+
+    match action {
+        SessionsAction::List { json } => {
+            let mut sessions = sm.list_sessions();
+            sessions.sort_by_key(|(_, _, updated, _)| std::cmp::Reverse(*updated));
+            if json {
+                let list: Vec<_> = sessions
+                    .iter()
+                    .map(|(id, name, updated, count)| {
+                        serde_json::json!({
+                            "id": id,
+                            "name": name,
+                            "message_count": count,
+                            "updated_at": updated.to_rfc3339(),
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&list)?);
+            } else {
+                for (id, name, updated, count) in sessions {
+                    println!(
+                        "{}  {}  ({} msgs, updated {})",
+                        &id[..8],
+                        name,
+                        count,
+                        updated.format("%Y-%m-%d %H:%M")
+                    );
+                }
+            }
+        }
+        SessionsAction::Show { id, json } => {
+            let id = sm.resolve_prefix(&id)?;
+            let session = sm.session(&id).expect("resolved session exists");
+            if json {
+                println!("{}", serde_json::to_string_pretty(session)?);
+            } else {
+                print!("{}", session.to_markdown());
+            }
+        }
+        SessionsAction::Export { id, format, output } => {
+            let id = sm.resolve_prefix(&id)?;
+            let session = sm.session(&id).expect("resolved session exists");
+            let text = match format {
+                ExportFormat::Markdown => session.to_markdown(),
+                ExportFormat::Json => serde_json::to_string_pretty(session)?,
+            };
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, text)?;
+                    eprintln!("Exported {} to {}", session.name, path.display());
+                }
+                None => print!("{}", text),
+            }
+        }
+        SessionsAction::Delete { id } => {
+            let id = sm.resolve_prefix(&id)?;
+            let name = sm.session(&id).map(|s| s.name.clone()).unwrap_or_default();
+            sm.delete_session(&id)?;
+            println!("Deleted session: {} ({})", name, &id[..8]);
+        }
+        SessionsAction::Prune { dry_run, json } => {
+            let policy = &config.session;
+            if !policy.has_retention() {
+                anyhow::bail!(
+                    "No retention limit configured: set retention_days or max_sessions under [session]"
+                );
+            }
+            let pruned = sm.prune(policy, chrono::Utc::now(), dry_run)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&pruned)?);
+                return Ok(());
+            }
+            let verb = match (dry_run, policy.prune_action) {
+                (true, PruneAction::Archive) => "Would archive",
+                (true, PruneAction::Delete) => "Would delete",
+                (false, PruneAction::Archive) => "Archived",
+                (false, PruneAction::Delete) => "Deleted",
+            };
+            for session in &pruned {
+                let reason = match session.reason {
+                    PruneReason::Expired => "expired",
+                    PruneReason::OverLimit => "over max_sessions",
+                };
+                println!(
+                    "{} {}  {}  ({} msgs, updated {}, {})",
+                    verb,
+                    &session.id[..8],
+                    session.name,
+                    session.message_count,
+                    session.updated_at.format("%Y-%m-%d %H:%M"),
+                    reason
+                );
+            }
+            if pruned.is_empty() {
+                println!("No sessions to prune.");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Import conversations from another tool's export at `path`.
+/// `YYYY-MM-DD` (midnight UTC) or an RFC 3339 timestamp.
+pub fn parse_date(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc());
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| format!("expected YYYY-MM-DD or an RFC 3339 time, got {:?}", s))
+}
+
+pub fn handle_dataset(action: DatasetAction, config: &AppConfig) -> Result<()> {
+    let DatasetAction::Export {
+        sessions,
+        format,
+        tags,
+        since,
+        until,
+        successful,
+        no_redact,
+        output,
+    } = action;
+    let sm = SessionManager::new(config)?;
+    let filter = DatasetFilter {
+        tags,
+        since,
+        until,
+        successful,
+    };
+    let scrubber = (!no_redact).then(|| SecretScrubber::new().with_pii());
+
+    let ids: Vec<String> = if sessions.is_empty() {
+        let mut all = sm.list_sessions();
+        // Oldest first, so appending later exports keeps the file in order.
+        all.sort_by_key(|(_, _, updated, _)| *updated);
+        all.into_iter().map(|(id, _, _, _)| id.to_string()).collect()
+    } else {
+        sessions
+            .iter()
+            .map(|prefix| sm.resolve_prefix(prefix))
+            .collect::<Result<_, _>>()?
+    };
+    let mut out = String::new();
+    let mut exported = 0;
+    for id in &ids {
+        let Some(session) = sm.session(id).filter(|s| filter.matches(s)) else {
+            continue;
+        };
+        let example = match format {
+            DatasetFormat::OpenaiJsonl => dataset::openai_example(session, scrubber.as_ref()),
+            DatasetFormat::Sharegpt => dataset::sharegpt_example(session, scrubber.as_ref()),
+        };
+        if let Some(example) = example {
+            out.push_str(&example.to_string());
+            out.push('\n');
+            exported += 1;
+        }
+    }
+
+    match output {
+        Some(path) => std::fs::write(&path, out)?,
+        None => print!("{}", out),
+    }
+    eprintln!("Exported {} of {} sessions", exported, ids.len());
+    Ok(())
+}
+
+pub fn handle_import(
+    path: &Path,
+    source: ImportSource,
+    dry_run: bool,
+    config: &AppConfig,
+) -> Result<()> {
+    let data = read_export(path)?;
+    let sessions = source.parse(&data)?;
+    if dry_run {
+        for session in &sessions {
+            println!(
+                "{}  {}  ({} msgs)",
+                session.created_at.format("%Y-%m-%d %H:%M"),
+                session.name,
+                session.messages.len()
+            );
+        }
+        println!("{} conversation(s) found.", sessions.len());
+        return Ok(());
+    }
+    let found = sessions.len();
+    let added = SessionManager::new(config)?.import_sessions(sessions)?;
+    println!(
+        "Imported {} of {} conversation(s) from {} ({} already imported).",
+        added.len(),
+        found,
+        source.as_str(),
+        found - added.len()
+    );
+    Ok(())
+}
+
+/// Contents of an export file; for a `.zip`, its `conversations.json`.
+fn read_export(path: &Path) -> Result<String> {
+    let is_zip = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+    if !is_zip {
+        return std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()));
+    }
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut archive = zip::ZipArchive::new(file)?;
+    let name = archive
+        .file_names()
+        .filter(|name| name.rsplit('/').next() == Some("conversations.json"))
+        .min_by_key(|name| name.len())
+        .map(String::from)
+        .with_context(|| format!("No conversations.json in {}", path.display()))?;
+    let mut data = String::new();
+    archive.by_name(&name)?.read_to_string(&mut data)?;
+    Ok(data)
+}
+
+/// Returns `false` if `skills validate` found errors.
+pub fn handle_skills(action: SkillsAction, skill_indexer: Arc<SkillIndexer>) -> Result<bool> {
+    match action {
+        SkillsAction::List { json } => {
+            let index = skill_indexer.get_skill_index();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&index.skills)?);
+            } else if index.is_empty() {
+                println!("No skills loaded.");
+            } else {
+                for skill in &index.skills {
+                    println!("{} — {}", skill.name, skill.description);
+                }
+            }
+        }
+        SkillsAction::Search { query, limit, json } => {
+            let search = SearchService::new(skill_indexer);
+            let results = search.search_all(&query, &SearchOptions::with_limit(limit));
+            if json {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            } else if results.results.is_empty() {
+                println!("No matches for '{}'.", query);
+            } else {
+                for r in &results.results {
+                    let sub = r
+                        .sub_skill
+                        .as_deref()
+                        .map(|s| format!(" / {}", s))
+                        .unwrap_or_default();
+                    println!("{}{}  [{:?}]", r.domain, sub, r.match_type);
+                    if let Some(snippet) = &r.snippet {
+                        println!("    {}", snippet.replace('\n', " "));
+                    }
+                }
+                if results.truncated {
+                    println!(
+                        "Showing {} of {} matches.",
+                        results.results.len(),
+                        results.total_matches
+                    );
+                }
+            }
+        }
+        SkillsAction::Validate { json } => {
+            let result = agent_skills::validate_skills(&skill_indexer);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            } else {
+                println!("Checked {} skill(s).", result.skills_checked);
+                for err in &result.errors {
+                    println!("  error: {}", err);
+                }
+                for warning in &result.warnings {
+                    println!("  warning: {}", warning);
+                }
+                if result.valid {
+                    println!("All skills valid.");
+                }
+            }
+            return Ok(result.valid);
+        }
+    }
+    Ok(true)
+}
+
+pub fn handle_analytics(action: AnalyticsAction, config: &AppConfig) -> Result<()> {
+    let sm = SessionManager::new(config)?;
+    let mut analytics = agent_analytics::Analytics::default();
+    for (id, _, _, _) in sm.list_sessions() {
+        if let Some(session) = sm.session(id) {
+            analytics.process_session(session);
+        }
+    }
+    // Pruned sessions still count.
+    for session in sm.archived_sessions() {
+        analytics.process_session(&session);
+    }
+    analytics.finalize_all();
+
+    match action {
+        AnalyticsAction::Summary => {
+            print!(
+                "{}",
+                agent_analytics::ReportGenerator::text_summary(&analytics)
+            );
+        }
+        AnalyticsAction::Report {
+            period,
+            format,
+            out,
+        } => {
+            let today = chrono::Utc::now().date_naive();
+            let report =
+                agent_analytics::ReportGenerator::period_report(
+                    &analytics,
+                    &period,
+                    today,
+                    &config.analytics.goals,
+                )
+                .map_err(anyhow::Error::msg)?;
+            let format = format
+                .or_else(|| out.as_deref().map(agent_analytics::ReportFormat::from_path))
+                .unwrap_or_default();
+            let bytes = agent_analytics::ReportGenerator::render(&report, format);
+            match out {
+                Some(path) => {
+                    std::fs::write(&path, bytes)?;
+                    eprintln!("Wrote {} report to {}", period, path.display());
+                }
+                None => std::io::Write::write_all(&mut std::io::stdout(), &bytes)?,
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,926 @@
+//! Full-screen terminal UI (`agent-shell tui`).
+//!
+//! An alternative to the line-based REPL: chat, tool output, a session list
+//! and an analytics widget in separate panes, driving the same
+//! [`SessionManager`] and [`AgentLoop`] as the REPL. It sticks to the basic
+//! 16 colours and plain borders and leaves the mouse alone, so it behaves
+//! inside tmux and screen.
+
+use agent_analytics::Analytics;
+use agent_core::agent_loop::{AgentLoop, AgentTurnResult};
+use agent_core::config::AppConfig;
+use agent_core::error::AgentError;
+use agent_core::session::SessionManager;
+use agent_core::tool_registry::ToolRegistry;
+use agent_core::types::{AgentEvent, Message, Role};
+use agent_core::workspace;
+use agent_skills::SkillIndexer;
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Sparkline};
+use ratatui::{DefaultTerminal, Frame};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Tool output lines shown per call before truncating.
+const TOOL_PREVIEW_LINES: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Input,
+    Sessions,
+    Chat,
+    Tools,
+}
+
+impl Focus {
+    fn next(self) -> Self {
+        match self {
+            Focus::Input => Focus::Sessions,
+            Focus::Sessions => Focus::Chat,
+            Focus::Chat => Focus::Tools,
+            Focus::Tools => Focus::Input,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ToolEntry {
+    id: String,
+    name: String,
+    arguments: String,
+    output: Option<String>,
+    is_error: bool,
+}
+
+/// A turn that is still streaming.
+struct Turn {
+    rx: mpsc::UnboundedReceiver<AgentEvent>,
+    handle: JoinHandle<Result<AgentTurnResult, AgentError>>,
+    response: String,
+    tools: Vec<ToolEntry>,
+}
+
+/// Activity over the last 7 days, plus sessions/day for two weeks.
+#[derive(Debug, Default)]
+struct AnalyticsSnapshot {
+    sessions: u32,
+    messages: u32,
+    tool_calls: u32,
+    error_rate: f64,
+    tokens: u64,
+    daily_sessions: Vec<u64>,
+    top_tools: Vec<(String, u32)>,
+}
+
+impl AnalyticsSnapshot {
+    fn compute(session_manager: &SessionManager) -> Self {
+        let mut analytics = Analytics::default();
+        for (id, _, _, _) in session_manager.list_sessions() {
+            if let Some(session) = session_manager.session(id) {
+                analytics.process_session(session);
+            }
+        }
+        for session in session_manager.archived_sessions() {
+            analytics.process_session(&session);
+        }
+        analytics.finalize_all();
+
+        let today = chrono::Utc::now().date_naive();
+        let series = analytics.daily_series(today - chrono::Duration::days(13), today);
+        let week = &series[series.len().saturating_sub(7)..];
+        let tool_calls: u32 = week.iter().map(|d| d.tool_call_count).sum();
+        let tool_errors: u32 = week.iter().map(|d| d.tool_error_count).sum();
+        Self {
+            sessions: week.iter().map(|d| d.session_count).sum(),
+            messages: week.iter().map(|d| d.message_count).sum(),
+            tool_calls,
+            error_rate: if tool_calls == 0 {
+                0.0
+            } else {
+                tool_errors as f64 / tool_calls as f64
+            },
+            tokens: week.iter().map(|d| d.estimated_tokens).sum(),
+            daily_sessions: series.iter().map(|d| d.session_count as u64).collect(),
+            top_tools: analytics.top_tools(3),
+        }
+    }
+}
+
+struct App {
+    model: String,
+    config: AppConfig,
+    skill_indexer: Arc<SkillIndexer>,
+    session_manager: SessionManager,
+    agent_loop: Arc<AgentLoop>,
+    input: String,
+    focus: Focus,
+    session_list: ListState,
+    /// Lines scrolled up from the bottom of the chat / tool panes.
+    chat_scroll: usize,
+    tool_scroll: usize,
+    turn: Option<Turn>,
+    status: String,
+    analytics: AnalyticsSnapshot,
+}
+
+/// Run the TUI until the user quits.
+pub async fn run(
+    config: AppConfig,
+    tool_registry: Arc<ToolRegistry>,
+    skill_indexer: Arc<SkillIndexer>,
+    session_name: Option<String>,
+) -> Result<()> {
+    let mut session_manager = SessionManager::new(&config)?;
+    if let Some(name) = session_name {
+        session_manager.create_session(name)?;
+    }
+    let agent_loop = Arc::new(AgentLoop::new(config.clone(), tool_registry)?);
+    let mut app = App::new(config, skill_indexer, session_manager, agent_loop);
+
+    // crossterm's blocking reader lives on its own thread; the UI loop
+    // selects over its events and the agent's.
+    let (input_tx, mut input_rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while let Ok(ev) = event::read() {
+            if input_tx.send(ev).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut terminal = ratatui::init();
+    let result = app.event_loop(&mut terminal, &mut input_rx).await;
+    ratatui::restore();
+    result
+}
+
+impl App {
+    fn new(
+        config: AppConfig,
+        skill_indexer: Arc<SkillIndexer>,
+        session_manager: SessionManager,
+        agent_loop: Arc<AgentLoop>,
+    ) -> Self {
+        let analytics = AnalyticsSnapshot::compute(&session_manager);
+        let mut app = Self {
+            model: config.provider.model.clone(),
+            config,
+            skill_indexer,
+            session_manager,
+            agent_loop,
+            input: String::new(),
+            focus: Focus::Input,
+            session_list: ListState::default(),
+            chat_scroll: 0,
+            tool_scroll: 0,
+            turn: None,
+            status: "Enter send · Tab switch pane · Ctrl+N new session · Ctrl+C cancel/quit".into(),
+            analytics,
+        };
+        app.select_active_session();
+        app
+    }
+
+    async fn event_loop(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        input_rx: &mut mpsc::UnboundedReceiver<Event>,
+    ) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            tokio::select! {
+                ev = input_rx.recv() => match ev {
+                    Some(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                        if !self.on_key(key)? {
+                            break;
+                        }
+                    }
+                    Some(_) => {} // Resize and friends just redraw.
+                    None => break,
+                },
+                ev = next_agent_event(&mut self.turn) => match ev {
+                    Some(ev) => self.on_agent_event(ev),
+                    None => self.finish_turn().await?,
+                },
+            }
+        }
+        if let Some(turn) = self.turn.take() {
+            turn.handle.abort();
+        }
+        Ok(())
+    }
+
+    // ── Input ──────────────────────────────────────────────────────────
+
+    /// Handle a key press; returns `false` to quit.
+    fn on_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('c') if ctrl => {
+                if let Some(turn) = &self.turn {
+                    turn.handle.abort();
+                    self.status = "Cancelling…".into();
+                    return Ok(true);
+                }
+                return Ok(false);
+            }
+            KeyCode::Char('d') if ctrl => return Ok(false),
+            KeyCode::Char('n') if ctrl => {
+                self.new_session(None, None)?;
+                return Ok(true);
+            }
+            KeyCode::Tab => {
+                self.focus = self.focus.next();
+                return Ok(true);
+            }
+            KeyCode::Esc => {
+                self.focus = Focus::Input;
+                return Ok(true);
+            }
+            _ => {}
+        }
+
+        match self.focus {
+            Focus::Input => self.on_input_key(key, ctrl),
+            Focus::Sessions => {
+                self.on_sessions_key(key)?;
+                Ok(true)
+            }
+            Focus::Chat => {
+                scroll(&mut self.chat_scroll, key.code);
+                Ok(true)
+            }
+            Focus::Tools => {
+                scroll(&mut self.tool_scroll, key.code);
+                Ok(true)
+            }
+        }
+    }
+
+    fn on_input_key(&mut self, key: KeyEvent, ctrl: bool) -> Result<bool> {
+        match key.code {
+            KeyCode::Char('u') if ctrl => self.input.clear(),
+            KeyCode::Char(c) if !ctrl => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Enter => {
+                let text = std::mem::take(&mut self.input);
+                let text = text.trim();
+                if text.is_empty() {
+                    return Ok(true);
+                }
+                if text.starts_with('/') {
+                    return self.command(text);
+                }
+                if self.turn.is_some() {
+                    self.status = "Still answering — wait or press Ctrl+C to cancel".into();
+                    self.input = text.to_string();
+                } else {
+                    self.send(text.to_string())?;
+                }
+            }
+            KeyCode::PageUp | KeyCode::PageDown => scroll(&mut self.chat_scroll, key.code),
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn on_sessions_key(&mut self, key: KeyEvent) -> Result<()> {
+        let count = self.session_manager.list_sessions().len();
+        let selected = self.session_list.selected().unwrap_or(0);
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.session_list.select(Some(selected.saturating_sub(1)))
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.session_list
+                    .select(Some((selected + 1).min(count.saturating_sub(1))))
+            }
+            KeyCode::Enter => {
+                if let Some((id, _)) = self.sessions().get(selected) {
+                    let id = id.clone();
+                    self.switch_session(&id)?;
+                }
+            }
+            KeyCode::Char('n') => self.new_session(None, None)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The subset of REPL slash commands that make sense here.
+    fn command(&mut self, text: &str) -> Result<bool> {
+        let (cmd, arg) = text.split_once(' ').unwrap_or((text, ""));
+        let arg = arg.trim();
+        match cmd {
+            "/exit" | "/quit" | "/q" => return Ok(false),
+            "/new" => match crate::repl::parse_new_args(arg) {
+                Ok((name, agent)) => self.new_session(name, agent)?,
+                Err(e) => self.status = e,
+            },
+            "/switch" => match self.session_manager.resolve_prefix(arg) {
+                Ok(id) => self.switch_session(&id)?,
+                Err(AgentError::Session(msg)) => self.status = msg,
+                Err(e) => return Err(e.into()),
+            },
+            "/clear" => {
+                if let Some(session) = self.session_manager.active_session() {
+                    let (old_id, name) = (session.id.clone(), session.name.clone());
+                    let agent = session.agent.clone();
+                    self.session_manager.delete_session(&old_id)?;
+                    self.session_manager.create_session_with_agent(&name, agent)?;
+                    self.after_session_change("Session history cleared");
+                }
+            }
+            "/checkpoint" => {
+                if let Some(session) = self.session_manager.active_session_mut() {
+                    let label = (!arg.is_empty()).then(|| arg.to_string());
+                    let id = session.checkpoint(label).id.clone();
+                    self.session_manager.save_active()?;
+                    self.status = format!("Checkpoint {} saved", id);
+                }
+            }
+            "/undo" => {
+                let keep_files = arg.split_whitespace().any(|w| w == "--keep-files");
+                let target = arg.split_whitespace().find(|w| !w.starts_with("--"));
+                let store = self
+                    .session_manager
+                    .active_session_id()
+                    .map(|id| self.session_manager.snapshot_store(id));
+                if let (Some(store), Some(session)) = (store, self.session_manager.active_session_mut()) {
+                    let result = match target {
+                        Some(id) => session.rollback(id, !keep_files, &store),
+                        None => session.undo(!keep_files, &store),
+                    };
+                    match result {
+                        Ok(rollback) => {
+                            self.session_manager.save_active()?;
+                            self.after_session_change(&format!(
+                                "Rolled back to {}: {} message(s) removed, {} file(s) restored",
+                                rollback.checkpoint,
+                                rollback.messages_removed,
+                                rollback.files_restored.len()
+                            ));
+                        }
+                        Err(AgentError::Session(msg)) => self.status = msg,
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+            }
+            _ => self.status = format!("Unknown command: {} (try /new [--agent NAME], /switch, /clear, /undo, /quit)", cmd),
+        }
+        Ok(true)
+    }
+
+    // ── Sessions ───────────────────────────────────────────────────────
+
+    /// `(id, label)` for each session, most recently updated first.
+    fn sessions(&self) -> Vec<(String, String)> {
+        let mut sessions = self.session_manager.list_sessions();
+        sessions.sort_by_key(|(_, _, updated, _)| std::cmp::Reverse(*updated));
+        sessions
+            .into_iter()
+            .map(|(id, name, _, count)| (id.to_string(), format!("{} ({})", name, count)))
+            .collect()
+    }
+
+    fn select_active_session(&mut self) {
+        let active = self.session_manager.active_session_id().map(String::from);
+        let index = self
+            .sessions()
+            .iter()
+            .position(|(id, _)| Some(id) == active.as_ref());
+        self.session_list.select(index.or(Some(0)));
+    }
+
+    fn new_session(&mut self, name: Option<String>, agent: Option<String>) -> Result<()> {
+        if self.turn.is_some() {
+            self.status = "Can't change sessions while the agent is answering".into();
+            return Ok(());
+        }
+        if let Some(agent) = &agent {
+            if let Err(e) = crate::repl::resolve_agent(&self.config, &self.skill_indexer, agent) {
+                self.status = e;
+                return Ok(());
+            }
+        }
+        let name = name.unwrap_or_else(|| {
+            format!("session-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S"))
+        });
+        self.session_manager.create_session_with_agent(&name, agent)?;
+        self.after_session_change(&format!("Created session: {}", name));
+        Ok(())
+    }
+
+    fn switch_session(&mut self, id: &str) -> Result<()> {
+        if self.turn.is_some() {
+            self.status = "Can't change sessions while the agent is answering".into();
+            return Ok(());
+        }
+        self.session_manager.switch_session(id)?;
+        let name = self
+            .session_manager
+            .active_session()
+            .map(|s| s.name.clone())
+            .unwrap_or_default();
+        self.after_session_change(&format!("Switched to: {}", name));
+        Ok(())
+    }
+
+    fn after_session_change(&mut self, status: &str) {
+        self.chat_scroll = 0;
+        self.tool_scroll = 0;
+        self.status = status.to_string();
+        self.select_active_session();
+        self.analytics = AnalyticsSnapshot::compute(&self.session_manager);
+    }
+
+    // ── Agent turns ────────────────────────────────────────────────────
+
+    fn send(&mut self, text: String) -> Result<()> {
+        self.session_manager.auto_checkpoint()?;
+        self.session_manager.push_message(Message::user(text))?;
+        let messages: Vec<Message> = self
+            .session_manager
+            .recent_messages()
+            .into_iter()
+            .cloned()
+            .collect();
+        let (allowlist, denylist, tool_calls, agent_name, roots) = self
+            .session_manager
+            .active_session()
+            .map(|s| {
+                (
+                    s.tool_allowlist.clone(),
+                    s.tool_denylist.clone(),
+                    s.tool_calls.clone(),
+                    s.agent.clone(),
+                    workspace::roots_for(&self.config.sandbox, s),
+                )
+            })
+            .unwrap_or_default();
+        let preset = match agent_name {
+            Some(name) => {
+                match crate::repl::resolve_agent(&self.config, &self.skill_indexer, &name) {
+                    Ok(preset) => Some(preset),
+                    Err(e) => {
+                        self.status = e;
+                        return Ok(());
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let agent = self.agent_loop.clone();
+        let handle = tokio::spawn(workspace::scope(roots, async move {
+            agent
+                .run_as(
+                    preset.as_ref(),
+                    &messages,
+                    allowlist.as_deref(),
+                    &denylist,
+                    &tool_calls,
+                    tx,
+                )
+                .await
+        }));
+        self.turn = Some(Turn {
+            rx,
+            handle,
+            response: String::new(),
+            tools: Vec::new(),
+        });
+        self.chat_scroll = 0;
+        self.status = "Thinking…".into();
+        Ok(())
+    }
+
+    fn on_agent_event(&mut self, event: AgentEvent) {
+        let Some(turn) = &mut self.turn else { return };
+        match event {
+            AgentEvent::ContentChunk(token) => turn.response.push_str(&token),
+            AgentEvent::ToolCallStart {
+                id,
+                name,
+                arguments,
+            } => {
+                self.status = format!("Running {}…", name);
+                turn.tools.push(ToolEntry {
+                    id,
+                    name,
+                    arguments,
+                    output: None,
+                    is_error: false,
+                });
+                self.tool_scroll = 0;
+            }
+            AgentEvent::ToolResult(output) => {
+                if let Some(entry) = turn.tools.iter_mut().find(|t| t.id == output.tool_call_id) {
+                    entry.output = Some(output.content);
+                    entry.is_error = output.is_error;
+                }
+                self.status = "Thinking…".into();
+            }
+            AgentEvent::Error(e) => self.status = format!("Error: {}", e),
+            _ => {}
+        }
+    }
+
+    /// The turn's event channel closed: save its messages to the session.
+    async fn finish_turn(&mut self) -> Result<()> {
+        let Some(turn) = self.turn.take() else {
+            return Ok(());
+        };
+        match turn.handle.await {
+            Ok(Ok(result)) => {
+                let recorded = self
+                    .session_manager
+                    .record_snapshots(result.snapshots)
+                    .and_then(|()| self.session_manager.record_tool_calls(&result.tool_calls))
+                    .and_then(|()| self.session_manager.record_usage(result.usage));
+                for msg in result.messages {
+                    self.session_manager.push_message(msg)?;
+                }
+                self.status = match recorded {
+                    Ok(()) => "Ready".into(),
+                    Err(e) => format!("Failed to record the turn: {}", e),
+                };
+            }
+            Ok(Err(e)) => self.status = format!("Agent error: {}", e),
+            Err(e) if e.is_cancelled() => self.status = "Cancelled".into(),
+            Err(e) => self.status = format!("Task error: {}", e),
+        }
+        self.analytics = AnalyticsSnapshot::compute(&self.session_manager);
+        Ok(())
+    }
+
+    // ── Drawing ────────────────────────────────────────────────────────
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [side, body] =
+            Layout::horizontal([Constraint::Length(30), Constraint::Min(20)]).areas(main);
+        let [sessions, analytics] =
+            Layout::vertical([Constraint::Min(5), Constraint::Length(11)]).areas(side);
+        let [chat, tools, input] = Layout::vertical([
+            Constraint::Percentage(65),
+            Constraint::Percentage(35),
+            Constraint::Length(3),
+        ])
+        .areas(body);
+
+        self.draw_sessions(frame, sessions);
+        self.draw_analytics(frame, analytics);
+        self.draw_chat(frame, chat);
+        self.draw_tools(frame, tools);
+        self.draw_input(frame, input);
+
+        let session = self
+            .session_manager
+            .active_session()
+            .map(|s| s.name.as_str())
+            .unwrap_or("default");
+        let bar = Line::from(vec![
+            Span::styled(format!(" {} ", session), Style::new().fg(Color::Black).bg(Color::Cyan)),
+            Span::styled(format!(" {} ", self.model), Style::new().fg(Color::Black).bg(Color::White)),
+            Span::raw(format!(" {}", self.status)),
+        ]);
+        frame.render_widget(Paragraph::new(bar), status);
+    }
+
+    fn block(&self, title: &str, pane: Focus) -> Block<'static> {
+        let style = if self.focus == pane {
+            Style::new().fg(Color::Yellow)
+        } else {
+            Style::new().fg(Color::DarkGray)
+        };
+        Block::bordered()
+            .title(format!(" {} ", title))
+            .border_style(style)
+    }
+
+    fn draw_sessions(&mut self, frame: &mut Frame, area: Rect) {
+        let active = self.session_manager.active_session_id().unwrap_or("");
+        let items: Vec<ListItem> = self
+            .sessions()
+            .into_iter()
+            .map(|(id, label)| {
+                if id == active {
+                    ListItem::new(format!("● {}", label)).style(Style::new().fg(Color::Green))
+                } else {
+                    ListItem::new(format!("  {}", label))
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(self.block("Sessions", Focus::Sessions))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.session_list);
+    }
+
+    fn draw_analytics(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Last 7 days ")
+            .border_style(Style::new().fg(Color::DarkGray));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let [text, spark] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(inner);
+        let a = &self.analytics;
+        let mut lines = vec![
+            Line::from(format!("Sessions   {}", a.sessions)),
+            Line::from(format!("Messages   {}", a.messages)),
+            Line::from(format!("Tokens     ~{}", a.tokens)),
+            Line::from(format!(
+                "Tool calls {} ({:.0}% err)",
+                a.tool_calls,
+                a.error_rate * 100.0
+            )),
+        ];
+        if !a.top_tools.is_empty() {
+            let top: Vec<&str> = a.top_tools.iter().map(|(name, _)| name.as_str()).collect();
+            lines.push(Line::styled(top.join(", "), Style::new().fg(Color::DarkGray)));
+        }
+        frame.render_widget(Paragraph::new(lines), text);
+        frame.render_widget(
+            Sparkline::default()
+                .data(&a.daily_sessions)
+                .style(Style::new().fg(Color::Cyan)),
+            spark,
+        );
+    }
+
+    fn chat_lines(&self) -> Vec<(String, Style)> {
+        let mut lines = Vec::new();
+        let header = |lines: &mut Vec<(String, Style)>, who: &str, color: Color| {
+            if !lines.is_empty() {
+                lines.push((String::new(), Style::new()));
+            }
+            lines.push((who.to_string(), Style::new().fg(color).add_modifier(Modifier::BOLD)));
+        };
+        if let Some(session) = self.session_manager.active_session() {
+            for msg in &session.messages {
+                match msg.role {
+                    Role::User => {
+                        header(&mut lines, "you", Color::Cyan);
+                        lines.extend(msg.content.lines().map(|l| (l.to_string(), Style::new())));
+                    }
+                    Role::Assistant => {
+                        if !msg.content.is_empty() {
+                            header(&mut lines, "assistant", Color::Yellow);
+                            lines.extend(msg.content.lines().map(|l| (l.to_string(), Style::new())));
+                        }
+                        for call in msg.tool_calls.iter().flatten() {
+                            lines.push((format!("⚡ {}", call.name), Style::new().fg(Color::Magenta)));
+                        }
+                    }
+                    Role::System | Role::Tool => {}
+                }
+            }
+        }
+        if let Some(turn) = &self.turn {
+            header(&mut lines, "assistant", Color::Yellow);
+            lines.extend(turn.response.lines().map(|l| (l.to_string(), Style::new())));
+            lines.push(("▌".into(), Style::new().fg(Color::DarkGray)));
+        }
+        lines
+    }
+
+    fn draw_chat(&mut self, frame: &mut Frame, area: Rect) {
+        let block = self.block("Chat", Focus::Chat);
+        let inner = block.inner(area);
+        let wrapped = wrap_lines(&self.chat_lines(), inner.width as usize);
+        let visible = bottom_window(wrapped, inner.height as usize, &mut self.chat_scroll);
+        frame.render_widget(Paragraph::new(visible).block(block), area);
+    }
+
+    fn draw_tools(&mut self, frame: &mut Frame, area: Rect) {
+        let mut entries = self
+            .session_manager
+            .active_session()
+            .map(|s| tool_entries(&s.messages))
+            .unwrap_or_default();
+        if let Some(turn) = &self.turn {
+            entries.extend(turn.tools.iter().cloned());
+        }
+
+        let mut lines = Vec::new();
+        for entry in &entries {
+            lines.push((
+                format!("⚡ {} {}", entry.name, entry.arguments),
+                Style::new().fg(Color::Magenta),
+            ));
+            match &entry.output {
+                None => lines.push(("  running…".into(), Style::new().fg(Color::DarkGray))),
+                Some(output) => {
+                    let style = if entry.is_error {
+                        Style::new().fg(Color::Red)
+                    } else {
+                        Style::new().fg(Color::Gray)
+                    };
+                    let total = output.lines().count();
+                    lines.extend(
+                        output
+                            .lines()
+                            .take(TOOL_PREVIEW_LINES)
+                            .map(|l| (format!("  {}", l), style)),
+                    );
+                    if total > TOOL_PREVIEW_LINES {
+                        lines.push((
+                            format!("  … {} more lines", total - TOOL_PREVIEW_LINES),
+                            Style::new().fg(Color::DarkGray),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let block = self.block("Tool output", Focus::Tools);
+        let inner = block.inner(area);
+        let wrapped = wrap_lines(&lines, inner.width as usize);
+        let visible = bottom_window(wrapped, inner.height as usize, &mut self.tool_scroll);
+        frame.render_widget(Paragraph::new(visible).block(block), area);
+    }
+
+    fn draw_input(&self, frame: &mut Frame, area: Rect) {
+        let block = self.block("Message", Focus::Input);
+        let inner = block.inner(area);
+        // Keep the end of long input (and the cursor) in view.
+        let width = inner.width.saturating_sub(1) as usize;
+        let chars: Vec<char> = self.input.chars().collect();
+        let shown: String = chars[chars.len().saturating_sub(width)..].iter().collect();
+        let cursor_x = inner.x + shown.chars().count() as u16;
+        frame.render_widget(Paragraph::new(shown).block(block), area);
+        if self.focus == Focus::Input {
+            frame.set_cursor_position((cursor_x, inner.y));
+        }
+    }
+}
+
+async fn next_agent_event(turn: &mut Option<Turn>) -> Option<AgentEvent> {
+    match turn {
+        Some(turn) => turn.rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn scroll(offset: &mut usize, key: KeyCode) {
+    match key {
+        KeyCode::Up | KeyCode::Char('k') => *offset += 1,
+        KeyCode::Down | KeyCode::Char('j') => *offset = offset.saturating_sub(1),
+        KeyCode::PageUp => *offset += 10,
+        KeyCode::PageDown => *offset = offset.saturating_sub(10),
+        KeyCode::Home => *offset = usize::MAX,
+        KeyCode::End => *offset = 0,
+        _ => {}
+    }
+}
+
+/// Tool calls and their results, paired up from a session's history.
+fn tool_entries(messages: &[Message]) -> Vec<ToolEntry> {
+    let mut entries: Vec<ToolEntry> = Vec::new();
+    for msg in messages {
+        for call in msg.tool_calls.iter().flatten() {
+            entries.push(ToolEntry {
+                id: call.id.clone(),
+                name: call.name.clone(),
+                arguments: call.arguments.clone(),
+                output: None,
+                is_error: false,
+            });
+        }
+        if let (Role::Tool, Some(id)) = (&msg.role, &msg.tool_call_id) {
+            if let Some(entry) = entries.iter_mut().find(|e| &e.id == id) {
+                entry.output = Some(msg.content.clone());
+            }
+        }
+    }
+    entries
+}
+
+/// Hard-wrap styled lines to `width` columns (by character count).
+fn wrap_lines(lines: &[(String, Style)], width: usize) -> Vec<Line<'static>> {
+    let width = width.max(1);
+    let mut out = Vec::new();
+    for (text, style) in lines {
+        let chars: Vec<char> = text.chars().collect();
+        if chars.is_empty() {
+            out.push(Line::default());
+            continue;
+        }
+        for chunk in chars.chunks(width) {
+            out.push(Line::styled(chunk.iter().collect::<String>(), *style));
+        }
+    }
+    out
+}
+
+/// The `height` lines ending `offset` lines above the bottom. Clamps
+/// `offset` so scrolling past the top stops there.
+fn bottom_window(lines: Vec<Line<'static>>, height: usize, offset: &mut usize) -> Vec<Line<'static>> {
+    let max_offset = lines.len().saturating_sub(height);
+    *offset = (*offset).min(max_offset);
+    let end = lines.len() - *offset;
+    let start = end.saturating_sub(height);
+    lines[start..end].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::types::ToolCall;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn test_wrap_and_bottom_window() {
+        let lines = vec![("abcdefgh".to_string(), Style::new()), (String::new(), Style::new())];
+        let wrapped = wrap_lines(&lines, 3);
+        assert_eq!(wrapped.len(), 4); // "abc", "def", "gh", ""
+
+        let mut offset = 100;
+        let visible = bottom_window(wrapped, 2, &mut offset);
+        assert_eq!(offset, 2);
+        assert_eq!(visible[0], Line::from("abc"));
+    }
+
+    #[test]
+    fn test_tool_entries_pairs_results() {
+        let call = ToolCall {
+            id: "call-1".into(),
+            name: "shell".into(),
+            arguments: r#"{"command":"ls"}"#.into(),
+        };
+        let messages = vec![
+            Message::user("list files"),
+            Message::assistant_with_tool_calls("", vec![call]),
+            Message::tool_result("call-1", "Cargo.toml"),
+        ];
+        let entries = tool_entries(&messages);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "shell");
+        assert_eq!(entries[0].output.as_deref(), Some("Cargo.toml"));
+    }
+
+    #[tokio::test]
+    async fn test_draws_all_panes() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut config = AppConfig::default();
+        config.session.history_dir = Some(tmp.path().to_path_buf());
+        let mut session_manager = SessionManager::new(&config).unwrap();
+        session_manager.create_session("work").unwrap();
+        session_manager.push_message(Message::user("hello there")).unwrap();
+        let agent_loop =
+            Arc::new(AgentLoop::new(config.clone(), Arc::new(ToolRegistry::new())).unwrap());
+        config.provider.model = "test-model".into();
+        let skill_indexer = Arc::new(SkillIndexer::new(tmp.path().join("skills")));
+        let mut app = App::new(config, skill_indexer, session_manager, agent_loop);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        for expected in ["Sessions", "Last 7 days", "Chat", "Tool output", "hello there", "test-model"] {
+            assert!(screen.contains(expected), "missing {expected}");
+        }
+    }
+    #[tokio::test]
+    async fn test_new_session_with_agent() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut config: AppConfig = toml::from_str("[agents.coder]\nmodel = \"qwen\"\n").unwrap();
+        config.session.history_dir = Some(tmp.path().to_path_buf());
+        config.session.agents_path = Some(tmp.path().join("agents.json"));
+        let session_manager = SessionManager::new(&config).unwrap();
+        let agent_loop =
+            Arc::new(AgentLoop::new(config.clone(), Arc::new(ToolRegistry::new())).unwrap());
+        let skill_indexer = Arc::new(SkillIndexer::new(tmp.path().join("skills")));
+        let mut app = App::new(config, skill_indexer, session_manager, agent_loop);
+        let before = app.sessions().len();
+
+        app.command("/new work --agent missing").unwrap();
+        assert!(app.status.starts_with("Unknown agent"));
+        assert_eq!(app.sessions().len(), before);
+
+        app.command("/new work --agent coder").unwrap();
+        let session = app.session_manager.active_session().unwrap();
+        assert_eq!((session.name.as_str(), session.agent.as_deref()), ("work", Some("coder")));
+        app.command("/clear").unwrap();
+        let session = app.session_manager.active_session().unwrap();
+        assert_eq!(session.agent.as_deref(), Some("coder"));
+    }
+}